@@ -0,0 +1,34 @@
+#![feature(test)]
+
+extern crate test;
+use pyo3::prelude::*;
+use pyo3::types::IntoPyDict;
+use pyo3::wrap_pyfunction;
+use test::Bencher;
+
+#[pyfunction]
+fn add(a: u64, b: u64) -> u64 {
+    a + b
+}
+
+#[bench]
+fn call_pyfunction(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let locals = [("add", wrap_pyfunction!(add)(py))].into_py_dict(py);
+    b.iter(|| {
+        py.run("for i in range(1000): add(i, 1)", None, Some(locals))
+            .unwrap();
+    });
+}
+
+#[bench]
+fn call_pyfunction_with_keywords(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let locals = [("add", wrap_pyfunction!(add)(py))].into_py_dict(py);
+    b.iter(|| {
+        py.run("for i in range(1000): add(i, b=1)", None, Some(locals))
+            .unwrap();
+    });
+}
@@ -100,7 +100,7 @@ const CFG_KEY: &'static str = "py_sys_config";
 /// (hrm, this is sort of re-implementing what distutils does, except
 /// by passing command line args instead of referring to a python.h)
 #[cfg(not(target_os = "windows"))]
-static SYSCONFIG_FLAGS: [&'static str; 7] = [
+static SYSCONFIG_FLAGS: [&'static str; 8] = [
     "Py_USING_UNICODE",
     "Py_UNICODE_WIDE",
     "WITH_THREAD",
@@ -108,6 +108,7 @@ static SYSCONFIG_FLAGS: [&'static str; 7] = [
     "Py_REF_DEBUG",
     "Py_TRACE_REFS",
     "COUNT_ALLOCS",
+    "Py_GIL_DISABLED",
 ];
 
 static SYSCONFIG_VALUES: [&'static str; 1] = [
@@ -199,7 +200,7 @@ fn load_cross_compile_info() -> Result<(InterpreterConfig, HashMap<String, Strin
         version: python_version,
         libdir: Some(env::var("PYO3_CROSS_LIB_DIR").expect("PYO3_CROSS_LIB_DIR is not set")),
         shared,
-        ld_version: "".to_string(),
+        ld_version: format!("{}.{}", major, minor),
         base_prefix: "".to_string(),
         executable: "".to_string(),
     };
@@ -497,7 +498,25 @@ fn configure(interpreter_config: &InterpreterConfig) -> Result<String, String> {
     }
 
     let is_extension_module = env::var_os("CARGO_FEATURE_EXTENSION_MODULE").is_some();
-    if !is_extension_module || cfg!(target_os = "windows") {
+    if env::var_os("CARGO_FEATURE_DYNAMIC_PYTHON").is_some() {
+        if env::var("CARGO_CFG_TARGET_FAMILY").map_or(true, |family| family != "unix") {
+            return Err(
+                "The `dynamic-python` feature is only supported on unix, use `delay-load` on \
+                 Windows"
+                    .to_string(),
+            );
+        }
+        // libpython is loaded at runtime with `pyo3::dynamic::load_libpython`
+    } else if target_is_emscripten() {
+        // Extension modules are emscripten side modules, whose CPython symbols are resolved
+        // against the main module (the interpreter) when they are loaded
+        if !is_extension_module {
+            println!("{}", get_rustc_link_lib(&interpreter_config).unwrap());
+            if let Some(libdir) = &interpreter_config.libdir {
+                println!("cargo:rustc-link-search=native={}", libdir);
+            }
+        }
+    } else if !is_extension_module || cfg!(target_os = "windows") {
         println!("{}", get_rustc_link_lib(&interpreter_config).unwrap());
         if let Some(libdir) = &interpreter_config.libdir {
             println!("cargo:rustc-link-search=native={}", libdir);
@@ -509,11 +528,24 @@ fn configure(interpreter_config: &InterpreterConfig) -> Result<String, String> {
         }
     }
 
+    if env::var_os("CARGO_FEATURE_DELAY_LOAD").is_some() {
+        if env::var("CARGO_CFG_TARGET_ENV").map_or(true, |env| env != "msvc") {
+            return Err("The `delay-load` feature is only supported for MSVC targets".to_string());
+        }
+        // The application passes `/DELAYLOAD:<dll>` to the linker, which then needs the helper
+        // that loads the DLL on the first call
+        println!("cargo:rustc-link-lib=delayimp");
+        println!(
+            "cargo:rustc-env=PYO3_PYTHON_DLL={}.dll",
+            get_library_link_name(&interpreter_config.version, &interpreter_config.ld_version)
+        );
+    }
+
     let mut flags = String::new();
 
     if interpreter_config.version.implementation == PythonInterpreterKind::PyPy {
         println!("cargo:rustc-cfg=PyPy");
-        flags += format!("CFG_PyPy").as_ref();
+        flags += format!("CFG_PyPy,").as_ref();
     };
 
     if interpreter_config.version.major == 2 {
@@ -530,12 +562,32 @@ fn configure(interpreter_config: &InterpreterConfig) -> Result<String, String> {
             println!("cargo:rustc-cfg=Py_3_{}", i);
             flags += format!("CFG_Py_3_{},", i).as_ref();
         }
+        // Read by `pyo3_build_config` in the build scripts of dependents as DEP_PYTHON_VERSION
+        // and DEP_PYTHON_IMPLEMENTATION
+        println!("cargo:version=3.{}", minor);
+        println!(
+            "cargo:implementation={:?}",
+            interpreter_config.version.implementation
+        );
+        // For the `__build__` attribute of modules
+        println!(
+            "cargo:rustc-env=PYO3_PYTHON_VERSION={:?} 3.{}",
+            interpreter_config.version.implementation, minor
+        );
     }
     println!("cargo:rustc-cfg=Py_3");
 
     return Ok(flags);
 }
 
+/// Checks whether we are compiling for emscripten, which is the target used by Pyodide.
+///
+/// `cfg!(target_os)` describes the host running the build script, so the target has to be read
+/// from the environment cargo sets for build scripts.
+fn target_is_emscripten() -> bool {
+    env::var("CARGO_CFG_TARGET_OS").map_or(false, |os| os == "emscripten")
+}
+
 fn check_rustc_version() {
     let channel = Channel::read().expect("Failed to determine rustc channel");
     if !channel.supports_features() {
@@ -559,8 +611,22 @@ fn check_rustc_version() {
     }
 }
 
+/// Passes the version of the compiler to pyo3 as `PYO3_RUSTC_VERSION`, for the `__build__`
+/// attribute of modules. It's the compiler of the crates defining the modules too.
+fn export_rustc_version() {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=PYO3_RUSTC_VERSION={}", version.trim());
+}
+
 fn main() -> Result<(), String> {
     check_rustc_version();
+    export_rustc_version();
     // 1. Setup cfg variables so we can do conditional compilation in this library based on the
     // python interpeter's compilation flags. This is necessary for e.g. matching the right unicode
     // and threading interfaces.  First check if we're cross compiling, if so, we cannot run the
@@ -573,6 +639,14 @@ fn main() -> Result<(), String> {
     // match the pkg-config package name, which is going to have a . in it).
     let cross_compiling =
         env::var("PYO3_CROSS_INCLUDE_DIR").is_ok() && env::var("PYO3_CROSS_LIB_DIR").is_ok();
+    if target_is_emscripten() && !cross_compiling {
+        // The host interpreter's configuration doesn't describe the WebAssembly build of CPython
+        return Err(
+            "Building for emscripten (e.g. Pyodide) requires PYO3_CROSS_INCLUDE_DIR and \
+             PYO3_CROSS_LIB_DIR to point at the headers and libraries of the target CPython build"
+                .to_string(),
+        );
+    }
     let (interpreter_config, mut config_map) = if cross_compiling {
         load_cross_compile_info()?
     } else {
@@ -602,6 +676,16 @@ fn main() -> Result<(), String> {
         config_map.insert("WITH_THREAD".to_owned(), "1".to_owned());
     }
 
+    // Free-threaded interpreters (PEP 703) use a different object header and atomic reference
+    // counting, which `ffi::PyObject`, `Py_INCREF` and `Py_DECREF` don't implement.
+    if config_map.get("Py_GIL_DISABLED").map(|v| v.as_str()) == Some("1") {
+        eprintln!(
+            "The Python interpreter was built without the GIL (free-threaded), which pyo3 doesn't \
+             support yet. Use an interpreter built with the GIL."
+        );
+        exit(1);
+    }
+
     for (key, val) in &config_map {
         match cfg_line_for_var(key, val) {
             Some(line) => println!("{}", line),
@@ -611,7 +695,7 @@ fn main() -> Result<(), String> {
 
     // 2. Export python interpreter compilation flags as cargo variables that
     // will be visible to dependents. All flags will be available to dependent
-    // build scripts in the environment variable DEP_PYTHON27_PYTHON_FLAGS as
+    // build scripts in the environment variable DEP_PYTHON_PYTHON_FLAGS as
     // comma separated list; each item in the list looks like
     //
     // {VAL,FLAG}_{flag_name}=val;
@@ -10,7 +10,7 @@ function Invoke-Call
     }
 }
 
-Invoke-Call { cargo test --verbose --features="num-bigint num-complex" }
+Invoke-Call { cargo test --verbose --features="num-bigint num-complex chrono serde" }
 
 foreach ($example in Get-ChildItem -dir "examples")
 {
@@ -3,8 +3,13 @@ set -ex
 
 # run `cargo test` only if testing against cpython.
 if ! [[ $FEATURES == *"pypy"* ]]; then
-  cargo test --features "$FEATURES num-bigint num-complex"
+  cargo test --features "$FEATURES num-bigint num-complex chrono serde"
   ( cd pyo3-derive-backend; cargo test )
+  # The internal sanity checks, which only run in debug builds by default
+  cargo test --release --lib --features runtime-checks assume_gil_acquired
+  cargo test --lib --features unchecked
+  # serde_yaml breaks type inference in the other tests, see Cargo.toml
+  cargo test --features serde_yaml --test test_yaml_conversion
 else
   # check that pypy at least builds
   PYTHON_SYS_EXECUTABLE="/opt/anaconda/envs/pypy3/bin/pypy3" cargo build;
@@ -0,0 +1,4 @@
+fn main() {
+    // Enables e.g. `#[cfg(py_3_6)]` for the Python version pyo3 is built for
+    pyo3_build_config::use_pyo3_cfgs();
+}
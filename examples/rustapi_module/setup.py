@@ -1,6 +1,4 @@
 import os
-import sys
-import platform
 
 from setuptools import setup
 from setuptools.command.test import test as TestCommand
@@ -46,34 +44,21 @@ class CargoModifiedSdist(SdistCommand):
         with open(cargo_loc, "r") as f:
             cargo_toml = toml.load(f)
 
-        rel_pyo3_path = cargo_toml["dependencies"]["pyo3"]["path"]
         base_path = os.path.dirname(__file__)
-        abs_pyo3_path = os.path.abspath(os.path.join(base_path, rel_pyo3_path))
-
-        cargo_toml["dependencies"]["pyo3"]["path"] = abs_pyo3_path
+        for dependencies, name in [
+            ("dependencies", "pyo3"),
+            ("build-dependencies", "pyo3-build-config"),
+        ]:
+            rel_path = cargo_toml[dependencies][name]["path"]
+            abs_path = os.path.abspath(os.path.join(base_path, rel_path))
+            cargo_toml[dependencies][name]["path"] = abs_path
 
         with open(cargo_loc, "w") as f:
             toml.dump(cargo_toml, f)
 
 
-def get_py_version_cfgs():
-    # For now each Cfg Py_3_X flag is interpreted as "at least 3.X"
-    version = sys.version_info[0:2]
-    py3_min = 5
-    out_cfg = []
-    for minor in range(py3_min, version[1] + 1):
-        out_cfg.append("--cfg=Py_3_%d" % minor)
-
-    if platform.python_implementation() == "PyPy":
-        out_cfg.append("--cfg=PyPy")
-
-    return out_cfg
-
-
 def make_rust_extension(module_name):
-    return RustExtension(
-        module_name, "Cargo.toml", rustc_flags=get_py_version_cfgs(), debug=True
-    )
+    return RustExtension(module_name, "Cargo.toml", debug=True)
 
 
 install_requires = []
@@ -42,7 +42,7 @@ fn make_time<'p>(
     )
 }
 
-#[cfg(Py_3_6)]
+#[cfg(py_3_6)]
 #[pyfunction]
 fn time_with_fold<'p>(
     py: Python<'p>,
@@ -77,7 +77,7 @@ fn get_time_tuple<'p>(py: Python<'p>, dt: &PyTime) -> &'p PyTuple {
     )
 }
 
-#[cfg(Py_3_6)]
+#[cfg(py_3_6)]
 #[pyfunction]
 fn get_time_tuple_fold<'p>(py: Python<'p>, dt: &PyTime) -> &'p PyTuple {
     PyTuple::new(
@@ -155,7 +155,7 @@ fn get_datetime_tuple<'p>(py: Python<'p>, dt: &PyDateTime) -> &'p PyTuple {
     )
 }
 
-#[cfg(Py_3_6)]
+#[cfg(py_3_6)]
 #[pyfunction]
 fn get_datetime_tuple_fold<'p>(py: Python<'p>, dt: &PyDateTime) -> &'p PyTuple {
     PyTuple::new(
@@ -226,7 +226,7 @@ fn datetime(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(datetime_from_timestamp))?;
 
     // Python 3.6+ functions
-    #[cfg(Py_3_6)]
+    #[cfg(py_3_6)]
     {
         m.add_wrapped(wrap_pyfunction!(time_with_fold))?;
         m.add_wrapped(wrap_pyfunction!(get_time_tuple_fold))?;
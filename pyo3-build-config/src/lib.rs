@@ -0,0 +1,134 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Helpers for the build scripts of crates using PyO3.
+//!
+//! PyO3 is compiled against the Python interpreter found when building (see
+//! `PYTHON_SYS_EXECUTABLE`), and uses the C API of that interpreter's version. Its build script
+//! exports the version to the build scripts of the crates depending on `pyo3` directly, so they
+//! can make the same distinction: [use_pyo3_cfgs] enables a cfg for every Python version the
+//! interpreter is at least compatible with. This lets a single crate be compiled for several
+//! Python versions, using newer C APIs where they are available:
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     pyo3_build_config::use_pyo3_cfgs();
+//! }
+//!
+//! // src/lib.rs
+//! #[cfg(py_3_8)]
+//! fn vectorcall_available() -> bool {
+//!     true
+//! }
+//!
+//! #[cfg(not(py_3_8))]
+//! fn vectorcall_available() -> bool {
+//!     false
+//! }
+//! ```
+
+use std::env;
+use std::fmt;
+
+/// The oldest Python 3 version supported by PyO3.
+const MINIMUM_MINOR: u8 = 5;
+
+/// The Python implementation PyO3 was compiled against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PythonImplementation {
+    CPython,
+    PyPy,
+}
+
+/// The version of the Python interpreter PyO3 was compiled against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PythonVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub implementation: PythonImplementation,
+}
+
+impl fmt::Display for PythonVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let implementation = match self.implementation {
+            PythonImplementation::CPython => "CPython",
+            PythonImplementation::PyPy => "PyPy",
+        };
+        write!(f, "{} {}.{}", implementation, self.major, self.minor)
+    }
+}
+
+impl PythonVersion {
+    /// The cfgs describing this version: `py_3_5` up to `py_3_<minor>`, and `pypy` for PyPy.
+    pub fn cfgs(&self) -> Vec<String> {
+        let mut cfgs: Vec<String> = (MINIMUM_MINOR..=self.minor)
+            .map(|minor| format!("py_{}_{}", self.major, minor))
+            .collect();
+        if self.implementation == PythonImplementation::PyPy {
+            cfgs.push("pypy".to_string());
+        }
+        cfgs
+    }
+
+    fn parse(version: &str, implementation: &str) -> Option<PythonVersion> {
+        let mut parts = version.splitn(2, '.').map(|part| part.parse::<u8>());
+        let (major, minor) = match (parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => (major, minor),
+            _ => return None,
+        };
+        let implementation = match implementation {
+            "CPython" => PythonImplementation::CPython,
+            "PyPy" => PythonImplementation::PyPy,
+            _ => return None,
+        };
+        Some(PythonVersion {
+            major,
+            minor,
+            implementation,
+        })
+    }
+}
+
+/// Returns the version of the Python interpreter PyO3 was compiled against.
+///
+/// This only works in the build script of a crate depending on `pyo3` directly, which is when
+/// cargo passes PyO3's build information on. Returns `None` otherwise.
+pub fn python_version() -> Option<PythonVersion> {
+    let version = env::var("DEP_PYTHON_VERSION").ok()?;
+    let implementation = env::var("DEP_PYTHON_IMPLEMENTATION").ok()?;
+    PythonVersion::parse(&version, &implementation)
+}
+
+/// Enables the cfgs of the Python version PyO3 was compiled against for the crate being built,
+/// e.g. `py_3_5` to `py_3_8` for Python 3.8.
+///
+/// # Panics
+/// Panics if it isn't called by the build script of a crate depending on `pyo3` directly.
+pub fn use_pyo3_cfgs() {
+    let version = python_version()
+        .expect("use_pyo3_cfgs() must be called by the build script of a crate depending on pyo3");
+    for cfg in version.cfgs() {
+        println!("cargo:rustc-cfg={}", cfg);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cfgs() {
+        let version = PythonVersion::parse("3.7", "CPython").unwrap();
+        assert_eq!(version.to_string(), "CPython 3.7");
+        assert_eq!(version.cfgs(), ["py_3_5", "py_3_6", "py_3_7"]);
+
+        let version = PythonVersion::parse("3.6", "PyPy").unwrap();
+        assert_eq!(version.cfgs(), ["py_3_5", "py_3_6", "pypy"]);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(PythonVersion::parse("3", "CPython"), None);
+        assert_eq!(PythonVersion::parse("3.x", "CPython"), None);
+        assert_eq!(PythonVersion::parse("3.8", "Jython"), None);
+    }
+}
@@ -235,7 +235,10 @@ pub const DESCR: Proto = Proto {
 
 pub const ITER: Proto = Proto {
     name: "Iter",
-    py_methods: &[],
+    py_methods: &[PyMethod {
+        name: "__length_hint__",
+        proto: "pyo3::class::iter::LengthHintProtocolImpl",
+    }],
     methods: &[
         MethodProto::Unary {
             name: "__iter__",
@@ -247,6 +250,11 @@ pub const ITER: Proto = Proto {
             pyres: true,
             proto: "pyo3::class::iter::PyIterNextProtocol",
         },
+        MethodProto::Unary {
+            name: "__length_hint__",
+            pyres: false,
+            proto: "pyo3::class::iter::PyIterLengthHintProtocol",
+        },
     ],
 };
 
@@ -0,0 +1,181 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Options from the `#[pyo3(...)]` attribute on a field.
+#[derive(Default)]
+struct FieldOptions {
+    name: Option<syn::LitStr>,
+    default: bool,
+}
+
+/// Options from the `#[pyo3(...)]` attribute on the struct.
+#[derive(Default)]
+struct ContainerOptions {
+    dataclass: Option<syn::LitStr>,
+    json_schema: bool,
+}
+
+fn pyo3_attr_metas(attrs: &[syn::Attribute]) -> syn::Result<Vec<syn::NestedMeta>> {
+    let mut metas = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("pyo3") {
+            continue;
+        }
+        match attr.parse_meta()? {
+            syn::Meta::List(list) => metas.extend(list.nested.into_iter()),
+            meta => return Err(syn::Error::new_spanned(meta, "Expected #[pyo3(...)]")),
+        }
+    }
+    Ok(metas)
+}
+
+fn parse_field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for meta in pyo3_attr_metas(&field.attrs)? {
+        match meta {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Str(ref lit),
+                ..
+            })) if path.is_ident("name") => options.name = Some(lit.clone()),
+            syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.is_ident("default") => {
+                options.default = true
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "Only name = \"...\" and default are supported on fields",
+                ))
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn parse_container_options(input: &syn::DeriveInput) -> syn::Result<ContainerOptions> {
+    let mut options = ContainerOptions::default();
+    for meta in pyo3_attr_metas(&input.attrs)? {
+        match meta {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Str(ref lit),
+                ..
+            })) if path.is_ident("dataclass") => options.dataclass = Some(lit.clone()),
+            syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.is_ident("json_schema") => {
+                options.json_schema = true
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "Only dataclass = \"...\" and json_schema are supported here",
+                ))
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Implements `FromPyObject` for a struct with named fields, reading each field from the
+/// dataclass, attrs class, dict or plain object it is extracted from.
+///
+/// With `#[pyo3(dataclass = "module.Class")]`, `FromPy<T> for PyObject` is implemented as well by
+/// calling the given class with the fields as keyword arguments. With `#[pyo3(json_schema)]`,
+/// `pyo3::pydantic::JsonSchema` is implemented, with all fields not marked as `default` required.
+pub fn build_derive_from_pyobject(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "#[derive(FromPyObject)] is only supported for structs with named fields",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "#[derive(FromPyObject)] does not support generic structs",
+        ));
+    }
+    let options = parse_container_options(input)?;
+
+    let cls = &input.ident;
+    let cls_name = cls.to_string();
+
+    let mut extract_fields = Vec::new();
+    let mut set_kwargs = Vec::new();
+    let mut properties = Vec::new();
+    for field in fields {
+        let field_options = parse_field_options(field)?;
+        let ident = field.ident.as_ref().unwrap();
+        let py_name = match field_options.name {
+            Some(name) => name.value(),
+            None => ident.to_string(),
+        };
+        let extract = if field_options.default {
+            quote! { __source.extract_or_default(#py_name)? }
+        } else {
+            quote! { __source.extract(#cls_name, #py_name)? }
+        };
+        extract_fields.push(quote! { #ident: #extract });
+        let ty = &field.ty;
+        let required = !field_options.default;
+        properties.push(quote! {
+            (#py_name, <#ty as pyo3::pydantic::JsonSchema>::json_schema(py), #required)
+        });
+        set_kwargs.push(quote! {
+            __kwargs
+                .set_item(#py_name, pyo3::IntoPy::<pyo3::PyObject>::into_py(other.#ident, py))
+                .expect("Failed to set keyword argument");
+        });
+    }
+
+    let into_py = match options.dataclass {
+        Some(dataclass) => quote! {
+            impl pyo3::FromPy<#cls> for pyo3::PyObject {
+                fn from_py(other: #cls, py: pyo3::Python) -> Self {
+                    let __kwargs = pyo3::types::PyDict::new(py);
+                    #(#set_kwargs)*
+                    pyo3::derive_utils::construct_dataclass(py, #dataclass, __kwargs)
+                        .unwrap_or_else(|e| {
+                            e.print(py);
+                            panic!("Failed to construct {}", #dataclass)
+                        })
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    let json_schema = if options.json_schema {
+        quote! {
+            impl pyo3::pydantic::JsonSchema for #cls {
+                fn json_schema(py: pyo3::Python) -> pyo3::PyObject {
+                    pyo3::pydantic::object_schema(py, #cls_name, &[#(#properties),*])
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl<'source> pyo3::FromPyObject<'source> for #cls {
+            fn extract(ob: &'source pyo3::types::PyAny) -> pyo3::PyResult<Self> {
+                let __source = pyo3::derive_utils::FieldSource::new(ob)?;
+                Ok(#cls {
+                    #(#extract_fields),*
+                })
+            }
+        }
+
+        #into_py
+
+        #json_schema
+    })
+}
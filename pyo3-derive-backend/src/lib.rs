@@ -4,19 +4,27 @@
 #![recursion_limit = "1024"]
 
 mod defs;
+mod from_pyobject;
 mod func;
 mod method;
 mod module;
+mod ops;
 mod pyclass;
+mod pyenum;
 mod pyfunction;
 mod pyimpl;
 mod pymethod;
 mod pyproto;
+mod rest;
+mod signature;
+mod stubs;
 mod utils;
 
+pub use from_pyobject::build_derive_from_pyobject;
 pub use module::{add_fn_to_module, process_functions_in_module, py_init};
 pub use pyclass::{build_py_class, PyClassArgs};
+pub use pyenum::build_py_enum;
 pub use pyfunction::{build_py_function, PyFunctionAttr};
 pub use pyimpl::{build_py_methods, impl_methods};
 pub use pyproto::build_py_proto;
-pub use utils::get_doc;
+pub use utils::{get_doc, parse_doc_index_attr, parse_module_state_attr, parse_multi_phase_attr};
@@ -3,7 +3,7 @@
 use crate::pyfunction::Argument;
 use crate::pyfunction::{parse_name_attribute, PyFunctionAttr};
 use crate::utils;
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use quote::ToTokens;
 use syn::ext::IdentExt;
@@ -29,12 +29,22 @@ pub enum FnType {
     FnCall,
     FnClass,
     FnStatic,
+    ClassAttribute,
     PySelf(syn::TypePath),
 }
 
+/// How a method borrows the instance it's called on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SelfBorrow {
+    Shared,
+    Mutable,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct FnSpec<'a> {
     pub tp: FnType,
+    // How `self` is borrowed, or `None` if the function has no `self`
+    pub self_borrow: Option<SelfBorrow>,
     // Rust function name
     pub name: &'a syn::Ident,
     // Wrapped python name. This should not have any leading r#.
@@ -44,6 +54,8 @@ pub struct FnSpec<'a> {
     pub args: Vec<FnArg<'a>>,
     pub output: syn::Type,
     pub doc: syn::LitStr,
+    // Whether the function is an `async fn`, which returns a coroutine
+    pub is_async: bool,
 }
 
 pub fn get_return_info(output: &syn::ReturnType) -> syn::Type {
@@ -68,11 +80,18 @@ impl<'a> FnSpec<'a> {
         } = parse_method_attributes(meth_attrs, allow_custom_name)?;
 
         let mut has_self = false;
+        let mut self_borrow = None;
+        let mut cls_ty = None;
         let mut arguments = Vec::new();
         for input in sig.inputs.iter() {
             match input {
-                syn::FnArg::Receiver(_) => {
+                syn::FnArg::Receiver(receiver) => {
                     has_self = true;
+                    self_borrow = Some(if receiver.mutability.is_some() {
+                        SelfBorrow::Mutable
+                    } else {
+                        SelfBorrow::Shared
+                    });
                 }
                 syn::FnArg::Typed(syn::PatType {
                     ref pat, ref ty, ..
@@ -80,6 +99,7 @@ impl<'a> FnSpec<'a> {
                     // skip first argument (cls)
                     if (fn_type == FnType::FnClass || fn_type == FnType::FnNew) && !has_self {
                         has_self = true;
+                        cls_ty = Some(ty);
                         continue;
                     }
 
@@ -122,6 +142,16 @@ impl<'a> FnSpec<'a> {
                 syn::Type::Path(p) => replace_self(p),
                 _ => panic!("Invalid type as self"),
             };
+            let is_mut = tp
+                .path
+                .segments
+                .last()
+                .map_or(false, |segment| segment.ident == "PyRefMut");
+            self_borrow = Some(if is_mut {
+                SelfBorrow::Mutable
+            } else {
+                SelfBorrow::Shared
+            });
             fn_type = FnType::PySelf(tp);
         }
 
@@ -156,28 +186,105 @@ impl<'a> FnSpec<'a> {
 
         let text_signature = match &fn_type {
             FnType::Fn | FnType::PySelf(_) | FnType::FnClass | FnType::FnStatic => {
-                utils::parse_text_signature_attrs(&mut *meth_attrs, name)?
+                utils::parse_text_signature_attrs(&mut *meth_attrs, &python_name)?
             }
             FnType::FnNew => parse_erroneous_text_signature(
                 "text_signature not allowed on __new__; if you want to add a signature on \
                  __new__, put it on the struct definition instead",
             )?,
-            FnType::FnCall | FnType::Getter | FnType::Setter => {
+            FnType::FnCall | FnType::Getter | FnType::Setter | FnType::ClassAttribute => {
                 parse_erroneous_text_signature("text_signature not allowed with this attribute")?
             }
         };
 
-        let doc = utils::get_doc(&meth_attrs, text_signature, true)?;
+        if sig.asyncness.is_some() {
+            match fn_type {
+                FnType::FnStatic => check_async_args(&arguments)?,
+                FnType::Fn if self_borrow == Some(SelfBorrow::Shared) => {
+                    check_async_args(&arguments)?
+                }
+                FnType::FnClass => {
+                    if let Some(cls_ty) = cls_ty.filter(|ty| borrows(ty)) {
+                        return Err(syn::Error::new_spanned(
+                            cls_ty,
+                            "The class of async class methods must be taken as `Py<PyType>`, as \
+                             the coroutine outlives the call",
+                        ));
+                    }
+                    check_async_args(&arguments)?
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        sig.asyncness,
+                        "Only methods taking `&self`, class methods and static methods can be \
+                         async",
+                    ))
+                }
+            }
+        }
 
-        Ok(FnSpec {
+        let mut spec = FnSpec {
             tp: fn_type,
+            self_borrow,
             name,
             python_name,
             attrs: fn_attrs,
             args: arguments,
             output: ty,
-            doc,
-        })
+            doc: syn::LitStr::new("", Span::call_site()),
+            is_async: sig.asyncness.is_some(),
+        };
+        let text_signature = match (text_signature, &spec.tp) {
+            (Some(text_signature), _) => Some(text_signature),
+            _ if utils::has_doc_text_signature(meth_attrs)? => None,
+            (None, FnType::Fn) | (None, FnType::PySelf(_)) => {
+                Some(spec.auto_text_signature(Some("$self")))
+            }
+            (None, FnType::FnClass) => Some(spec.auto_text_signature(Some("$cls"))),
+            // The signature of `#[new]` becomes the one of the class, see `initialize_type`
+            (None, FnType::FnStatic) | (None, FnType::FnNew) => {
+                Some(spec.auto_text_signature(None))
+            }
+            (None, FnType::FnCall)
+            | (None, FnType::Getter)
+            | (None, FnType::Setter)
+            | (None, FnType::ClassAttribute) => None,
+        };
+        spec.doc = utils::get_doc(&meth_attrs, text_signature, true)?;
+
+        Ok(spec)
+    }
+
+    /// Generates the text signature of the function from its arguments, e.g.
+    /// `method($self, a, b=None, *args, c=..., **kwargs)`, for functions without
+    /// `#[text_signature]`. `receiver` is the parameter `inspect` binds to `__self__`.
+    ///
+    /// Defaults which aren't Python literals are shown as `...`.
+    pub fn auto_text_signature(&self, receiver: Option<&str>) -> syn::LitStr {
+        let mut params: Vec<String> = receiver.into_iter().map(str::to_string).collect();
+        let mut kw_only_marker = !self.accept_args();
+        for arg in self.args.iter().filter(|arg| !arg.py) {
+            let name = arg.name.unraw().to_string();
+            if self.is_args(arg.name) {
+                params.push(format!("*{}", name));
+            } else if self.is_kwargs(arg.name) {
+                params.push(format!("**{}", name));
+            } else {
+                if self.is_kw_only(arg.name) && kw_only_marker {
+                    params.push("*".to_string());
+                    kw_only_marker = false;
+                }
+                match self.python_default(arg) {
+                    Some(default) => params.push(format!("{}={}", name, default)),
+                    None => params.push(name),
+                }
+                if self.ends_pos_only(arg.name) {
+                    params.push("/".to_string());
+                }
+            }
+        }
+        let signature = format!("{}({})", self.python_name.unraw(), params.join(", "));
+        syn::LitStr::new(&signature, self.python_name.span())
     }
 
     pub fn is_args(&self, name: &syn::Ident) -> bool {
@@ -189,15 +296,12 @@ impl<'a> FnSpec<'a> {
         false
     }
 
+    /// Whether the function takes `*args`, as opposed to only having a `"*"` separator.
     pub fn accept_args(&self) -> bool {
-        for s in self.attrs.iter() {
-            match *s {
-                Argument::VarArgs(_) => return true,
-                Argument::VarArgsSeparator => return true,
-                _ => (),
-            }
-        }
-        false
+        self.attrs.iter().any(|arg| match arg {
+            Argument::VarArgs(_) => true,
+            _ => false,
+        })
     }
 
     pub fn is_kwargs(&self, name: &syn::Ident) -> bool {
@@ -229,7 +333,7 @@ impl<'a> FnSpec<'a> {
                         }
                     }
                 }
-                Argument::Kwarg(ref path, ref opt) => {
+                Argument::Kwarg(ref path, Some(ref opt)) => {
                     if path.is_ident(name) {
                         let i: syn::Expr = syn::parse_str(&opt).unwrap();
                         return Some(quote!(#i));
@@ -241,6 +345,46 @@ impl<'a> FnSpec<'a> {
         None
     }
 
+    /// Whether the argument comes before a `"/"`, so it can't be passed by keyword.
+    pub fn is_pos_only(&self, name: &syn::Ident) -> bool {
+        for s in self.attrs.iter() {
+            match s {
+                Argument::PosOnlySeparator => return false,
+                Argument::Arg(ref path, _) if path.is_ident(name) => {
+                    return self.attrs.contains(&Argument::PosOnlySeparator)
+                }
+                _ => (),
+            }
+        }
+        false
+    }
+
+    /// Whether `name` is the last positional-only argument, which is followed by `/` in
+    /// signatures.
+    pub fn ends_pos_only(&self, name: &syn::Ident) -> bool {
+        let separator = self
+            .attrs
+            .iter()
+            .position(|arg| *arg == Argument::PosOnlySeparator);
+        match separator {
+            Some(i) if i > 0 => match &self.attrs[i - 1] {
+                Argument::Arg(path, _) => path.is_ident(name),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The default value of `arg` as a Python expression, with `...` for defaults that aren't
+    /// literals Python understands.
+    pub fn python_default(&self, arg: &FnArg) -> Option<String> {
+        match self.default_value(arg.name) {
+            Some(default) => Some(python_literal(default)),
+            None if arg.optional.is_some() => Some("None".to_string()),
+            None => None,
+        }
+    }
+
     pub fn is_kw_only(&self, name: &syn::Ident) -> bool {
         for s in self.attrs.iter() {
             if let Argument::Kwarg(ref path, _) = s {
@@ -253,6 +397,73 @@ impl<'a> FnSpec<'a> {
     }
 }
 
+/// Renders the default value of an argument for a text signature, if it's a literal Python also
+/// understands.
+fn python_literal(default: TokenStream) -> String {
+    match syn::parse2::<syn::Expr>(default) {
+        Ok(syn::Expr::Lit(syn::ExprLit { lit, .. })) => match lit {
+            syn::Lit::Int(int) => int.base10_digits().to_string(),
+            syn::Lit::Float(float) => float.base10_digits().to_string(),
+            syn::Lit::Bool(boolean) if boolean.value => "True".to_string(),
+            syn::Lit::Bool(_) => "False".to_string(),
+            syn::Lit::Str(string) => format!("{:?}", string.value()),
+            _ => "...".to_string(),
+        },
+        Ok(syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        })) => match python_literal(expr.into_token_stream()) {
+            ref literal if literal == "..." => literal.clone(),
+            literal => format!("-{}", literal),
+        },
+        Ok(syn::Expr::Path(ref path)) if path.path.is_ident("None") => "None".to_string(),
+        _ => "...".to_string(),
+    }
+}
+
+/// Checks that the arguments of an `async fn` can be moved into the returned coroutine, which
+/// outlives the call.
+pub fn check_async_args(args: &[FnArg]) -> syn::Result<()> {
+    for arg in args {
+        if arg.py || borrows(arg.ty) {
+            return Err(syn::Error::new_spanned(
+                arg.ty,
+                "Arguments of async functions must be owned values, as the coroutine outlives \
+                 the call",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `ty` is a reference or has a lifetime parameter.
+fn borrows(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(_) => true,
+        syn::Type::Path(syn::TypePath { path, .. }) => {
+            path.segments
+                .iter()
+                .any(|segment| match &segment.arguments {
+                    syn::PathArguments::AngleBracketed(params) => {
+                        params.args.iter().any(|arg| match arg {
+                            syn::GenericArgument::Lifetime(_) => true,
+                            syn::GenericArgument::Type(ty) => borrows(ty),
+                            _ => false,
+                        })
+                    }
+                    _ => false,
+                })
+        }
+        syn::Type::Tuple(tuple) => tuple.elems.iter().any(borrows),
+        syn::Type::Slice(slice) => borrows(&slice.elem),
+        syn::Type::Array(array) => borrows(&array.elem),
+        syn::Type::Paren(paren) => borrows(&paren.elem),
+        syn::Type::Group(group) => borrows(&group.elem),
+        _ => false,
+    }
+}
+
 pub fn is_ref(name: &syn::Ident, ty: &syn::Type) -> bool {
     match ty {
         syn::Type::Reference(_) => return true,
@@ -371,6 +582,8 @@ fn parse_method_attributes(
                     res = Some(FnType::FnClass)
                 } else if name.is_ident("staticmethod") {
                     res = Some(FnType::FnStatic)
+                } else if name.is_ident("classattr") {
+                    res = Some(FnType::ClassAttribute)
                 } else if name.is_ident("setter") || name.is_ident("getter") {
                     if let syn::AttrStyle::Inner(_) = attr.style {
                         return Err(syn::Error::new_spanned(
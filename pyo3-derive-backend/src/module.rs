@@ -6,6 +6,8 @@ use crate::pyfunction;
 use crate::pyfunction::PyFunctionAttr;
 use crate::pymethod;
 use crate::pymethod::get_arg_names;
+use crate::signature;
+use crate::stubs;
 use crate::utils;
 use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
@@ -13,8 +15,53 @@ use syn::Ident;
 
 /// Generates the function that is called by the python interpreter to initialize the native
 /// module
-pub fn py_init(fnname: &Ident, name: &Ident, doc: syn::LitStr) -> TokenStream {
+pub fn py_init(
+    fnname: &Ident,
+    name: &Ident,
+    doc: syn::LitStr,
+    multi_phase: bool,
+    state: Option<syn::Type>,
+    doc_index: bool,
+) -> TokenStream {
     let cb_name = Ident::new(&format!("PyInit_{}", name), Span::call_site());
+    // Expanded in the crate defining the module, so the environment is the one of that crate
+    let build = quote! {
+        pyo3::derive_utils::BuildInfo {
+            crate_name: env!("CARGO_PKG_NAME"),
+            crate_version: env!("CARGO_PKG_VERSION"),
+            git_hash: option_env!("GIT_HASH"),
+            debug: cfg!(debug_assertions),
+        }
+    };
+
+    if multi_phase {
+        let (init_state, init) = match state {
+            Some(state) => (
+                quote! { pyo3::derive_utils::init_module_state::<#state>(module); },
+                quote! { init_with_state::<#state> },
+            ),
+            None => (quote! {}, quote! { init }),
+        };
+        return quote! {
+            #[no_mangle]
+            #[allow(non_snake_case)]
+            /// This autogenerated function is called by the python interpreter when importing
+            /// the module.
+            pub unsafe extern "C" fn #cb_name() -> *mut pyo3::ffi::PyObject {
+                static MODULE_DEF: pyo3::derive_utils::ModuleDef =
+                    pyo3::derive_utils::ModuleDef::new();
+
+                unsafe extern "C" fn exec(
+                    module: *mut pyo3::ffi::PyObject,
+                ) -> std::os::raw::c_int {
+                    #init_state
+                    pyo3::derive_utils::exec_module(module, #doc, #doc_index, &#build, #fnname)
+                }
+
+                MODULE_DEF.#init(concat!(stringify!(#name), "\0"), exec)
+            }
+        };
+    }
 
     quote! {
         #[no_mangle]
@@ -22,7 +69,13 @@ pub fn py_init(fnname: &Ident, name: &Ident, doc: syn::LitStr) -> TokenStream {
         /// This autogenerated function is called by the python interpreter when importing
         /// the module.
         pub unsafe extern "C" fn #cb_name() -> *mut pyo3::ffi::PyObject {
-            pyo3::derive_utils::make_module(concat!(stringify!(#name), "\0"), #doc, #fnname)
+            pyo3::derive_utils::make_module(
+                concat!(stringify!(#name), "\0"),
+                #doc,
+                #doc_index,
+                &#build,
+                #fnname,
+            )
         }
     }
 }
@@ -38,10 +91,15 @@ pub fn process_functions_in_module(func: &mut syn::ItemFn) {
             {
                 let function_to_python = add_fn_to_module(func, python_name, pyfn_attrs);
                 let function_wrapper_ident = function_wrapper_ident(&func.sig.ident);
+                // The registration must be compiled out together with a `#[cfg]`'d function
+                let cfg_attrs = func.attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
                 let item: syn::ItemFn = syn::parse_quote! {
                     fn block_wrapper() {
-                        #function_to_python
-                        #module_name.add_wrapped(&#function_wrapper_ident)?;
+                        #(#cfg_attrs)*
+                        {
+                            #function_to_python
+                            #module_name.add_wrapped(&#function_wrapper_ident)?;
+                        }
                     }
                 };
                 stmts.extend(item.block.stmts.into_iter());
@@ -145,27 +203,54 @@ pub fn add_fn_to_module(
         }
     }
 
+    if func.sig.asyncness.is_some() {
+        if let Err(err) = method::check_async_args(&arguments) {
+            return err.to_compile_error();
+        }
+    }
+
     let ty = method::get_return_info(&func.sig.output);
 
-    let text_signature = match utils::parse_text_signature_attrs(&mut func.attrs, &python_name) {
-        Ok(text_signature) => text_signature,
+    let min_python = match utils::parse_min_python_attr(&mut func.attrs) {
+        Ok(min_python) => min_python,
         Err(err) => return err.to_compile_error(),
     };
-    let doc = match utils::get_doc(&func.attrs, text_signature, true) {
-        Ok(doc) => doc,
+    // Too old interpreters get `None`, which `PyModule::add_wrapped` skips
+    let version_check = min_python.map(|(major, minor)| {
+        quote! {
+            let (major, minor, _) = py.version_info();
+            if (major, minor) < (#major, #minor) {
+                return py.None();
+            }
+        }
+    });
+
+    let text_signature = match utils::parse_text_signature_attrs(&mut func.attrs, &python_name) {
+        Ok(text_signature) => text_signature,
         Err(err) => return err.to_compile_error(),
     };
-
     let function_wrapper_ident = function_wrapper_ident(&func.sig.ident);
 
-    let spec = method::FnSpec {
+    let mut spec = method::FnSpec {
         tp: method::FnType::Fn,
+        self_borrow: None,
         name: &function_wrapper_ident,
         python_name,
         attrs: pyfn_attrs,
         args: arguments,
         output: ty,
-        doc,
+        doc: syn::LitStr::new("", Span::call_site()),
+        is_async: func.sig.asyncness.is_some(),
+    };
+    // Functions are created without `__self__`, so there's no `$module` parameter
+    let text_signature = match utils::has_doc_text_signature(&func.attrs) {
+        Ok(false) => text_signature.or_else(|| Some(spec.auto_text_signature(None))),
+        Ok(true) => text_signature,
+        Err(err) => return err.to_compile_error(),
+    };
+    spec.doc = match utils::get_doc(&func.attrs, text_signature, true) {
+        Ok(doc) => doc,
+        Err(err) => return err.to_compile_error(),
     };
 
     let doc = &spec.doc;
@@ -173,14 +258,34 @@ pub fn add_fn_to_module(
     let python_name = &spec.python_name;
 
     let wrapper = function_c_wrapper(&func.sig.ident, &spec);
+    let wrapper_ident = utils::wrapper_ident(None, python_name);
+    let fastcall_ident = pymethod::fastcall_wrapper_ident(&wrapper_ident);
+    let meth = quote! {
+        pyo3::class::PyMethodType::PyCFunctionFastWithKeywords(#fastcall_ident, #wrapper_ident)
+    };
+    let signature = signature::register(&meth, &spec);
+    let stub = if cfg!(feature = "stubs") {
+        let stub = stubs::fn_stub(None, &spec);
+        Some(quote! {
+            pyo3::inventory::submit! {
+                #![crate = pyo3]
+                pyo3::stubs::FunctionStub { meth: #meth, stub: #stub }
+            }
+        })
+    } else {
+        None
+    };
 
     let tokens = quote! {
         fn #function_wrapper_ident(py: pyo3::Python) -> pyo3::PyObject {
+            #version_check
             #wrapper
+            #stub
+            #signature
 
             let _def = pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_meth: #meth,
                 ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
                 ml_doc: #doc,
             };
@@ -209,15 +314,19 @@ fn function_c_wrapper(name: &Ident, spec: &method::FnSpec<'_>) -> TokenStream {
         #name(#(#names),*)
     };
 
+    let wrapper = utils::wrapper_ident(None, &spec.python_name);
+    let location = quote! { concat!(stringify!(#name), "()") };
+    let fastcall = pymethod::impl_wrap_fastcall(&wrapper, &location, &quote! {}, spec, cb.clone());
     let body = pymethod::impl_arg_params(spec, cb);
 
     quote! {
-        unsafe extern "C" fn __wrap(
+        #[allow(non_snake_case)]
+        unsafe extern "C" fn #wrapper(
             _slf: *mut pyo3::ffi::PyObject,
             _args: *mut pyo3::ffi::PyObject,
             _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
         {
-            const _LOCATION: &'static str = concat!(stringify!(#name), "()");
+            const _LOCATION: &'static str = #location;
 
             let _py = pyo3::Python::assume_gil_acquired();
             let _pool = pyo3::GILPool::new(_py);
@@ -229,5 +338,7 @@ fn function_c_wrapper(name: &Ident, spec: &method::FnSpec<'_>) -> TokenStream {
             pyo3::callback::cb_convert(
                 pyo3::callback::PyObjectCallbackConverter, _py, _result)
         }
+
+        #fastcall
     }
 }
@@ -0,0 +1,306 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! `#[pyclass(ops(...))]`, mapping Python operators onto `std::ops` implementations
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+
+/// Whether a `std::ops` trait is a binary or unary operator or an augmented assignment
+#[derive(Clone, Copy, PartialEq)]
+enum OpKind {
+    Binary,
+    Unary,
+    Assign,
+}
+
+/// The `std::ops` traits with their kind, method and `pyo3::class::operators::Operator`
+const OPS: &[(&str, OpKind, &str, &str)] = &[
+    ("Add", OpKind::Binary, "add", "Add"),
+    ("Sub", OpKind::Binary, "sub", "Sub"),
+    ("Mul", OpKind::Binary, "mul", "Mul"),
+    ("Div", OpKind::Binary, "div", "TrueDiv"),
+    ("Rem", OpKind::Binary, "rem", "Mod"),
+    ("Shl", OpKind::Binary, "shl", "LShift"),
+    ("Shr", OpKind::Binary, "shr", "RShift"),
+    ("BitAnd", OpKind::Binary, "bitand", "And"),
+    ("BitOr", OpKind::Binary, "bitor", "Or"),
+    ("BitXor", OpKind::Binary, "bitxor", "Xor"),
+    ("Neg", OpKind::Unary, "neg", "Neg"),
+    ("Not", OpKind::Unary, "not", "Invert"),
+    ("AddAssign", OpKind::Assign, "add_assign", "Add"),
+    ("SubAssign", OpKind::Assign, "sub_assign", "Sub"),
+    ("MulAssign", OpKind::Assign, "mul_assign", "Mul"),
+    ("DivAssign", OpKind::Assign, "div_assign", "TrueDiv"),
+    ("RemAssign", OpKind::Assign, "rem_assign", "Mod"),
+    ("ShlAssign", OpKind::Assign, "shl_assign", "LShift"),
+    ("ShrAssign", OpKind::Assign, "shr_assign", "RShift"),
+    ("BitAndAssign", OpKind::Assign, "bitand_assign", "And"),
+    ("BitOrAssign", OpKind::Assign, "bitor_assign", "Or"),
+    ("BitXorAssign", OpKind::Assign, "bitxor_assign", "Xor"),
+];
+
+/// An entry of `#[pyclass(ops(...))]`, e.g. `Add`, `&Add` or `Add::<f64>`
+pub struct OpsEntry {
+    /// Whether the trait is implemented for references to the class, written `&Add`
+    by_ref: bool,
+    /// The `std::ops` trait
+    trait_: syn::Ident,
+    /// The right operand, or `None` for the class itself
+    rhs: Option<syn::Type>,
+    kind: OpKind,
+    method: syn::Ident,
+    operator: syn::Ident,
+}
+
+impl OpsEntry {
+    /// Parses the arguments of `ops(...)`
+    pub fn parse_list(call: &syn::ExprCall) -> syn::Result<Vec<OpsEntry>> {
+        call.args.iter().map(OpsEntry::parse).collect()
+    }
+
+    fn parse(expr: &syn::Expr) -> syn::Result<OpsEntry> {
+        let (by_ref, path) = match expr {
+            syn::Expr::Reference(syn::ExprReference {
+                mutability: None,
+                expr,
+                ..
+            }) => match &**expr {
+                syn::Expr::Path(path) => (true, path),
+                _ => return Err(syn::Error::new_spanned(expr, "Expected a std::ops trait")),
+            },
+            syn::Expr::Path(path) => (false, path),
+            _ => return Err(syn::Error::new_spanned(expr, "Expected a std::ops trait")),
+        };
+        if path.path.segments.len() != 1 {
+            return Err(syn::Error::new_spanned(path, "Expected a std::ops trait"));
+        }
+        let segment = &path.path.segments[0];
+        let name = segment.ident.to_string();
+        let (kind, method, operator) = match OPS.iter().find(|op| op.0 == name) {
+            Some((_, kind, method, operator)) => (*kind, *method, *operator),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &segment.ident,
+                    "Unsupported operator, expected a std::ops trait like Add or AddAssign",
+                ))
+            }
+        };
+        let rhs = match &segment.arguments {
+            syn::PathArguments::None => None,
+            syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+                match &args.args[0] {
+                    syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                    arg => return Err(syn::Error::new_spanned(arg, "Expected a type")),
+                }
+            }
+            args => {
+                return Err(syn::Error::new_spanned(
+                    args,
+                    "Expected a single right operand type, e.g. Mul::<f64>",
+                ))
+            }
+        };
+        if kind == OpKind::Unary && rhs.is_some() {
+            return Err(syn::Error::new_spanned(
+                &segment.arguments,
+                "Unary operators don't have a right operand",
+            ));
+        }
+        if kind == OpKind::Assign && by_ref {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "Augmented assignments take the class by mutable reference, remove the `&`",
+            ));
+        }
+        Ok(OpsEntry {
+            by_ref,
+            trait_: segment.ident.clone(),
+            rhs,
+            kind,
+            method: syn::Ident::new(method, segment.ident.span()),
+            operator: syn::Ident::new(operator, segment.ident.span()),
+        })
+    }
+
+    /// The code applying the operator if `other` can be extracted, returning from `binary_op`,
+    /// `unary_op` or `inplace_op`
+    fn attempt(&self, cls: &syn::Ident, reflected: bool) -> TokenStream {
+        let trait_ = &self.trait_;
+        let method = &self.method;
+        let span = self.trait_.span();
+        let slf = if self.by_ref {
+            quote! { self }
+        } else {
+            quote! { std::clone::Clone::clone(self) }
+        };
+        let slf_ty = if self.by_ref {
+            quote! { &#cls }
+        } else {
+            quote! { #cls }
+        };
+        match (self.kind, &self.rhs) {
+            (OpKind::Unary, _) => quote_spanned! {span=>
+                return Ok(pyo3::IntoPy::<pyo3::PyObject>::into_py(
+                    std::ops::#trait_::#method(#slf),
+                    py,
+                ));
+            },
+            (OpKind::Binary, None) => {
+                let other = if self.by_ref {
+                    quote! { other }
+                } else {
+                    quote! { std::clone::Clone::clone(other) }
+                };
+                quote_spanned! {span=>
+                    if let Ok(other) = pyo3::ObjectProtocol::extract::<&#cls>(other) {
+                        return Ok(pyo3::IntoPy::<pyo3::PyObject>::into_py(
+                            std::ops::#trait_::#method(#slf, #other),
+                            py,
+                        ));
+                    }
+                }
+            }
+            (OpKind::Binary, Some(rhs)) if reflected => {
+                let reflected_trait = syn::Ident::new(&format!("Reflected{}", trait_), span);
+                quote_spanned! {span=>
+                    if let Ok(other) = pyo3::ObjectProtocol::extract::<#rhs>(other) {
+                        if let Some(result) =
+                            <#rhs as pyo3::class::ops::#reflected_trait<#slf_ty>>::reflected(
+                                other, #slf, py,
+                            )
+                        {
+                            return Ok(result);
+                        }
+                    }
+                }
+            }
+            (OpKind::Binary, Some(rhs)) => quote_spanned! {span=>
+                if let Ok(other) = pyo3::ObjectProtocol::extract::<#rhs>(other) {
+                    return Ok(pyo3::IntoPy::<pyo3::PyObject>::into_py(
+                        <#slf_ty as std::ops::#trait_<#rhs>>::#method(#slf, other),
+                        py,
+                    ));
+                }
+            },
+            (OpKind::Assign, None) => quote_spanned! {span=>
+                if let Ok(other) = pyo3::ObjectProtocol::extract::<&#cls>(other) {
+                    // `other` may be `self`, so it's cloned before `self` is modified
+                    let other = std::clone::Clone::clone(other);
+                    std::ops::#trait_::#method(self, other);
+                    return Ok(true);
+                }
+            },
+            (OpKind::Assign, Some(rhs)) => quote_spanned! {span=>
+                if let Ok(other) = pyo3::ObjectProtocol::extract::<#rhs>(other) {
+                    <#cls as std::ops::#trait_<#rhs>>::#method(self, other);
+                    return Ok(true);
+                }
+            },
+        }
+    }
+}
+
+/// Implements `PyClassOps` for `#[pyclass(ops(...))]`.
+pub fn impl_ops(cls: &syn::Ident, entries: &[OpsEntry]) -> TokenStream {
+    // The operators in the order of their first entry, so that the attempts of each are
+    // grouped while keeping their order
+    let operators = |kind: OpKind| {
+        let mut operators: Vec<&syn::Ident> = Vec::new();
+        for entry in entries.iter().filter(|entry| entry.kind == kind) {
+            if !operators.contains(&&entry.operator) {
+                operators.push(&entry.operator);
+            }
+        }
+        operators
+    };
+    let attempts = |kind: OpKind, operator: &syn::Ident, reflected: bool| {
+        let attempts = entries
+            .iter()
+            .filter(|entry| entry.kind == kind && &entry.operator == operator)
+            .filter(|entry| !reflected || entry.rhs.is_some())
+            .map(|entry| entry.attempt(cls, reflected));
+        quote! { #(#attempts)* }
+    };
+
+    let binary = operators(OpKind::Binary);
+    let forward = binary
+        .iter()
+        .map(|operator| attempts(OpKind::Binary, operator, false));
+    let reflected = binary
+        .iter()
+        .map(|operator| attempts(OpKind::Binary, operator, true));
+    let unary = operators(OpKind::Unary);
+    let unary_attempts = unary
+        .iter()
+        .map(|operator| attempts(OpKind::Unary, operator, false));
+    let assign = operators(OpKind::Assign);
+    let assign_attempts = assign
+        .iter()
+        .map(|operator| attempts(OpKind::Assign, operator, false));
+    let span = cls.span();
+    let binary_ops = binary.iter();
+    let binary_ops2 = binary.iter();
+    let unary_ops = unary.iter();
+    let assign_ops = assign.iter();
+    let assign_ops2 = assign.iter();
+
+    quote_spanned! {span=>
+        impl pyo3::class::ops::PyClassOps for #cls {
+            const BINARY: &'static [pyo3::class::operators::Operator] =
+                &[#(pyo3::class::operators::Operator::#binary_ops),*];
+            const UNARY: &'static [pyo3::class::operators::Operator] =
+                &[#(pyo3::class::operators::Operator::#unary_ops),*];
+            const INPLACE: &'static [pyo3::class::operators::Operator] =
+                &[#(pyo3::class::operators::Operator::#assign_ops),*];
+
+            #[allow(unused_variables)]
+            fn binary_op(
+                &self,
+                py: pyo3::Python,
+                op: pyo3::class::operators::Operator,
+                other: &pyo3::types::PyAny,
+                reflected: bool,
+            ) -> pyo3::PyResult<pyo3::PyObject> {
+                #(
+                    if op == pyo3::class::operators::Operator::#binary_ops2 {
+                        if reflected {
+                            #reflected
+                        } else {
+                            #forward
+                        }
+                    }
+                )*
+                Ok(py.NotImplemented())
+            }
+
+            #[allow(unused_variables)]
+            fn unary_op(
+                &self,
+                py: pyo3::Python,
+                op: pyo3::class::operators::Operator,
+            ) -> pyo3::PyResult<pyo3::PyObject> {
+                #(
+                    if op == pyo3::class::operators::Operator::#unary {
+                        #unary_attempts
+                    }
+                )*
+                Ok(py.NotImplemented())
+            }
+
+            #[allow(unused_variables)]
+            fn inplace_op(
+                &mut self,
+                py: pyo3::Python,
+                op: pyo3::class::operators::Operator,
+                other: &pyo3::types::PyAny,
+            ) -> pyo3::PyResult<bool> {
+                #(
+                    if op == pyo3::class::operators::Operator::#assign_ops2 {
+                        #assign_attempts
+                    }
+                )*
+                Ok(false)
+            }
+        }
+    }
+}
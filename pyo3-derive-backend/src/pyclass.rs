@@ -1,13 +1,14 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 use crate::method::{FnArg, FnSpec, FnType};
+use crate::ops::{impl_ops, OpsEntry};
 use crate::pymethod::{impl_py_getter_def, impl_py_setter_def, impl_wrap_getter, impl_wrap_setter};
+use crate::stubs;
 use crate::utils;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
-use syn::punctuated::Punctuated;
 use syn::{parse_quote, Expr, Token};
 
 /// The parsed arguments of the pyclass macro
@@ -17,15 +18,116 @@ pub struct PyClassArgs {
     pub flags: Vec<syn::Expr>,
     pub base: syn::TypePath,
     pub module: Option<syn::LitStr>,
+    pub abcs: Vec<syn::LitStr>,
+    pub protocols: Vec<syn::LitStr>,
+    pub metaclass: Option<syn::Expr>,
+    /// The qualified name of a Python class to extend, given as `extends = "module.Name"`
+    pub python_base: Option<syn::LitStr>,
+    /// Whether to generate the `PyGCProtocol` implementation from the fields, set by `gc` but
+    /// not by `gc = "manual"`
+    pub derive_gc: bool,
+    /// How instances are pickled, set by `pickle` or `pickle = "serde"`
+    pub pickle: Option<Pickle>,
+    /// Whether to derive `==` and `!=` from `PartialEq`, set by `eq`
+    pub eq: bool,
+    /// Whether to derive the comparisons from `PartialOrd`, set by `ord`
+    pub ord: bool,
+    /// Whether to derive `__hash__` from `Hash`, set by `hash`
+    pub hash: Option<syn::ExprPath>,
+    /// How `repr()` formats instances, set by `repr` or `repr = "fields"`
+    pub repr: Option<Repr>,
+    /// Whether to derive `__str__` from `Display`, set by `str`
+    pub str: bool,
+    /// The `std::ops` implementations to map operators onto, set by `ops(...)`
+    pub ops: Vec<OpsEntry>,
+}
+
+/// The state `#[pyclass(pickle)]` saves for instances
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pickle {
+    /// A dict of the fields
+    Fields,
+    /// The struct converted with `pyo3::pythonize`
+    Serde,
+}
+
+/// The `repr()` derived by `#[pyclass(repr)]`
+#[derive(Clone, Copy, PartialEq)]
+pub enum Repr {
+    /// `Debug`
+    Debug,
+    /// The class name followed by the fields, like dataclasses
+    Fields,
 }
 
 impl Parse for PyClassArgs {
     fn parse(input: ParseStream) -> syn::parse::Result<Self> {
         let mut slf = PyClassArgs::default();
+        let mut is_abstract = false;
+        let mut final_token = None;
+        let mut is_subclass = false;
+        let mut singleton = None;
 
-        let vars = Punctuated::<Expr, Token![,]>::parse_terminated(input)?;
-        for expr in vars {
-            slf.add_expr(&expr)?;
+        while !input.is_empty() {
+            // `abstract` and `final` are reserved keywords, so they can't be parsed as expressions
+            if input.peek(Token![abstract]) {
+                input.parse::<Token![abstract]>()?;
+                is_abstract = true;
+            } else if input.peek(Token![final]) {
+                final_token = Some(input.parse::<Token![final]>()?);
+            } else {
+                let expr: Expr = input.parse()?;
+                if let syn::Expr::Path(ref exp) = expr {
+                    is_subclass |= exp.path.is_ident("subclass");
+                    if exp.path.is_ident("singleton") {
+                        singleton = Some(exp.clone());
+                    }
+                }
+                slf.add_expr(&expr)?;
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        if let Some(singleton) = singleton {
+            if is_abstract || is_subclass {
+                return Err(syn::Error::new_spanned(
+                    singleton,
+                    "A singleton class can't be abstract or allow subclassing",
+                ));
+            }
+            if slf.pickle.is_some() {
+                return Err(syn::Error::new_spanned(
+                    singleton,
+                    "A singleton class is already pickled as its instance",
+                ));
+            }
+        }
+        if let Some(hash) = &slf.hash {
+            if !slf.eq && !slf.ord {
+                return Err(syn::Error::new_spanned(
+                    hash,
+                    "hash requires eq or ord, so that equal objects have equal hashes",
+                ));
+            }
+        }
+        if let Some(final_token) = final_token {
+            if is_abstract || is_subclass {
+                return Err(syn::Error::new(
+                    final_token.span,
+                    "A final class can't be abstract or allow subclassing",
+                ));
+            }
+            slf.flags
+                .push(parse_quote! {pyo3::type_object::PY_TYPE_FLAG_FINAL});
+        }
+        if is_abstract {
+            slf.flags
+                .push(parse_quote! {pyo3::type_object::PY_TYPE_FLAG_ABSTRACT});
+            if slf.metaclass.is_none() {
+                slf.metaclass = Some(parse_quote! {"abc.ABCMeta"});
+            }
         }
         Ok(slf)
     }
@@ -37,6 +139,18 @@ impl Default for PyClassArgs {
             freelist: None,
             name: None,
             module: None,
+            abcs: Vec::new(),
+            protocols: Vec::new(),
+            metaclass: None,
+            python_base: None,
+            derive_gc: false,
+            pickle: None,
+            eq: false,
+            ord: false,
+            hash: None,
+            repr: None,
+            str: false,
+            ops: Vec::new(),
             // We need the 0 as value for the constant we're later building using quote for when there
             // are no other flags
             flags: vec![parse_quote! {0}],
@@ -52,10 +166,25 @@ impl PyClassArgs {
         match expr {
             syn::Expr::Path(ref exp) if exp.path.segments.len() == 1 => self.add_path(exp),
             syn::Expr::Assign(ref assign) => self.add_assign(assign),
+            syn::Expr::Call(ref call) => self.add_call(call),
             _ => Err(syn::Error::new_spanned(expr, "Could not parse arguments")),
         }
     }
 
+    /// Match a list of arguments, like `ops(Add, Sub)`
+    fn add_call(&mut self, call: &syn::ExprCall) -> syn::Result<()> {
+        match *call.func {
+            syn::Expr::Path(ref exp) if exp.path.is_ident("ops") => {
+                self.ops.extend(OpsEntry::parse_list(call)?);
+                Ok(())
+            }
+            _ => Err(syn::Error::new_spanned(
+                call.func.clone(),
+                "Unsupported parameter",
+            )),
+        }
+    }
+
     /// Match a single flag
     fn add_assign(&mut self, assign: &syn::ExprAssign) -> syn::Result<()> {
         let key = match *assign.left {
@@ -90,6 +219,13 @@ impl PyClassArgs {
                         qself: None,
                     };
                 }
+                // A Python class, which is imported when the class is initialized
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) => {
+                    self.python_base = Some(lit.clone());
+                }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         *assign.right.clone(),
@@ -111,6 +247,93 @@ impl PyClassArgs {
                     ));
                 }
             },
+            "abc" => match *assign.right {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) => {
+                    self.abcs.push(lit.clone());
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        *assign.right.clone(),
+                        "Wrong format for abc",
+                    ));
+                }
+            },
+            "implements" => match *assign.right {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) => {
+                    self.protocols.push(lit.clone());
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        *assign.right.clone(),
+                        "Wrong format for implements",
+                    ));
+                }
+            },
+            "gc" => match *assign.right {
+                // The class implements `PyGCProtocol` itself
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) if lit.value() == "manual" => {
+                    self.flags
+                        .push(parse_quote! {pyo3::type_object::PY_TYPE_FLAG_GC});
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        *assign.right.clone(),
+                        "Expected gc = \"manual\"",
+                    ));
+                }
+            },
+            "pickle" => match *assign.right {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) if lit.value() == "serde" => {
+                    self.pickle = Some(Pickle::Serde);
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        *assign.right.clone(),
+                        "Expected pickle = \"serde\"",
+                    ));
+                }
+            },
+            "repr" => match *assign.right {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) if lit.value() == "fields" => {
+                    self.repr = Some(Repr::Fields);
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        *assign.right.clone(),
+                        "Expected repr = \"fields\"",
+                    ));
+                }
+            },
+            "metaclass" => match *assign.right {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(_),
+                    ..
+                })
+                | syn::Expr::Path(_) => {
+                    self.metaclass = Some(*assign.right.clone());
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        *assign.right.clone(),
+                        "Wrong format for metaclass",
+                    ));
+                }
+            },
             _ => {
                 return Err(syn::Error::new_spanned(
                     *assign.left.clone(),
@@ -127,6 +350,7 @@ impl PyClassArgs {
         let flag = exp.path.segments.first().unwrap().ident.to_string();
         let path = match flag.as_str() {
             "gc" => {
+                self.derive_gc = true;
                 parse_quote! {pyo3::type_object::PY_TYPE_FLAG_GC}
             }
             "weakref" => {
@@ -144,6 +368,34 @@ impl PyClassArgs {
             "dict" => {
                 parse_quote! {pyo3::type_object::PY_TYPE_FLAG_DICT}
             }
+            "singleton" => {
+                parse_quote! {pyo3::type_object::PY_TYPE_FLAG_SINGLETON}
+            }
+            "pickle" => {
+                // Only generates methods, without setting a flag
+                self.pickle = Some(Pickle::Fields);
+                return Ok(());
+            }
+            "eq" => {
+                self.eq = true;
+                return Ok(());
+            }
+            "ord" => {
+                self.ord = true;
+                return Ok(());
+            }
+            "hash" => {
+                self.hash = Some(exp.clone());
+                return Ok(());
+            }
+            "repr" => {
+                self.repr = Some(Repr::Debug);
+                return Ok(());
+            }
+            "str" => {
+                self.str = true;
+                return Ok(());
+            }
             _ => {
                 return Err(syn::Error::new_spanned(
                     exp.path.clone(),
@@ -164,11 +416,20 @@ pub fn build_py_class(class: &mut syn::ItemStruct, attr: &PyClassArgs) -> syn::R
     )?;
     let doc = utils::get_doc(&class.attrs, text_signature, true)?;
     let mut descriptors = Vec::new();
+    let mut field_names = Vec::new();
+    let mut gc_field_names = Vec::new();
 
     check_generics(class)?;
     if let syn::Fields::Named(ref mut fields) = class.fields {
         for field in fields.named.iter_mut() {
-            let field_descs = parse_descriptors(field)?;
+            field_names.extend(field.ident.clone());
+            let (field_descs, skip_gc) = parse_descriptors(field)?;
+            if !skip_gc {
+                if attr.derive_gc {
+                    check_clearable(&field.ty)?;
+                }
+                gc_field_names.extend(field.ident.clone());
+            }
             if !field_descs.is_empty() {
                 descriptors.push((field.clone(), field_descs));
             }
@@ -180,27 +441,47 @@ pub fn build_py_class(class: &mut syn::ItemStruct, attr: &PyClassArgs) -> syn::R
         ));
     }
 
-    impl_class(&class.ident, &attr, doc, descriptors)
+    impl_class(
+        &class.ident,
+        &attr,
+        doc,
+        descriptors,
+        &field_names,
+        &gc_field_names,
+    )
 }
 
-/// Parses `#[pyo3(get, set)]`
-fn parse_descriptors(item: &mut syn::Field) -> syn::Result<Vec<FnType>> {
+/// Parses `#[pyo3(get, set)]` and `#[pyo3(gc = "skip")]`, returning the descriptors and whether
+/// the garbage collector should skip the field
+fn parse_descriptors(item: &mut syn::Field) -> syn::Result<(Vec<FnType>, bool)> {
     let mut descs = Vec::new();
+    let mut skip_gc = false;
     let mut new_attrs = Vec::new();
     for attr in item.attrs.iter() {
         if let Ok(syn::Meta::List(ref list)) = attr.parse_meta() {
             if list.path.is_ident("pyo3") {
                 for meta in list.nested.iter() {
                     if let syn::NestedMeta::Meta(ref metaitem) = meta {
-                        if metaitem.path().is_ident("get") {
-                            descs.push(FnType::Getter);
-                        } else if metaitem.path().is_ident("set") {
-                            descs.push(FnType::Setter);
-                        } else {
-                            return Err(syn::Error::new_spanned(
-                                metaitem,
-                                "Only get and set are supported",
-                            ));
+                        match metaitem {
+                            syn::Meta::Path(path) if path.is_ident("get") => {
+                                descs.push(FnType::Getter);
+                            }
+                            syn::Meta::Path(path) if path.is_ident("set") => {
+                                descs.push(FnType::Setter);
+                            }
+                            syn::Meta::NameValue(syn::MetaNameValue {
+                                path,
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            }) if path.is_ident("gc") && lit.value() == "skip" => {
+                                skip_gc = true;
+                            }
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    metaitem,
+                                    "Only get, set and gc = \"skip\" are supported",
+                                ));
+                            }
                         }
                     }
                 }
@@ -213,7 +494,7 @@ fn parse_descriptors(item: &mut syn::Field) -> syn::Result<Vec<FnType>> {
     }
     item.attrs.clear();
     item.attrs.extend(new_attrs);
-    Ok(descs)
+    Ok((descs, skip_gc))
 }
 
 /// The orphan rule disallows using a generic inventory struct, so we create the whole boilerplate
@@ -250,6 +531,44 @@ fn impl_inventory(cls: &syn::Ident) -> TokenStream {
     }
 }
 
+/// Implements `PySingleton` and adds the `__new__` and `__reduce__` methods returning the instance
+fn impl_singleton(cls: &syn::Ident) -> TokenStream {
+    quote! {
+        impl pyo3::singleton::PySingleton for #cls {
+            fn instance_slot() -> &'static pyo3::cache::PyStatic {
+                static INSTANCE: pyo3::cache::PyStatic = pyo3::cache::PyStatic::new();
+                &INSTANCE
+            }
+        }
+
+        impl pyo3::singleton::SingletonClassesCannotDefineNew for #cls {}
+
+        pyo3::inventory::submit! {
+            #![crate = pyo3] {
+                type TyInventory = <#cls as pyo3::class::methods::PyMethodsInventoryDispatch>::InventoryType;
+                <TyInventory as pyo3::class::methods::PyMethodsInventory>::new(&[
+                    pyo3::class::PyMethodDefType::New(pyo3::class::PyMethodDef {
+                        ml_name: "__new__",
+                        ml_meth: pyo3::class::PyMethodType::PyNewFunc(
+                            pyo3::singleton::singleton_new::<#cls>
+                        ),
+                        ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
+                        ml_doc: "\0",
+                    }),
+                    pyo3::class::PyMethodDefType::Method(pyo3::class::PyMethodDef {
+                        ml_name: "__reduce__",
+                        ml_meth: pyo3::class::PyMethodType::PyCFunction(
+                            pyo3::singleton::singleton_reduce
+                        ),
+                        ml_flags: pyo3::ffi::METH_NOARGS,
+                        ml_doc: "\0",
+                    }),
+                ])
+            }
+        }
+    }
+}
+
 fn get_class_python_name(cls: &syn::Ident, attr: &PyClassArgs) -> TokenStream {
     match &attr.name {
         Some(name) => quote! { #name },
@@ -262,9 +581,18 @@ fn impl_class(
     attr: &PyClassArgs,
     doc: syn::LitStr,
     descriptors: Vec<(syn::Field, Vec<FnType>)>,
+    field_names: &[syn::Ident],
+    gc_field_names: &[syn::Ident],
 ) -> syn::Result<TokenStream> {
     let cls_name = get_class_python_name(cls, attr).to_string();
 
+    if let (Some(freelist), Some(_)) = (&attr.freelist, &attr.python_base) {
+        return Err(syn::Error::new_spanned(
+            freelist,
+            "A class extending a Python class can't have a freelist",
+        ));
+    }
+
     let extra = {
         if let Some(freelist) = &attr.freelist {
             quote! {
@@ -307,6 +635,7 @@ fn impl_class(
     let mut has_weakref = false;
     let mut has_dict = false;
     let mut has_gc = false;
+    let mut has_singleton = false;
     for f in attr.flags.iter() {
         if let syn::Expr::Path(ref epath) = f {
             if epath.path == parse_quote! {pyo3::type_object::PY_TYPE_FLAG_WEAKREF} {
@@ -315,9 +644,78 @@ fn impl_class(
                 has_dict = true;
             } else if epath.path == parse_quote! {pyo3::type_object::PY_TYPE_FLAG_GC} {
                 has_gc = true;
+            } else if epath.path == parse_quote! {pyo3::type_object::PY_TYPE_FLAG_SINGLETON} {
+                has_singleton = true;
             }
         }
     }
+    let extra = if let Some(pickle) = attr.pickle {
+        let pickle_impl = impl_pickle(cls, pickle, field_names);
+        quote! {
+            #pickle_impl
+            #extra
+        }
+    } else {
+        extra
+    };
+    let extra = {
+        let eq = if attr.eq || attr.ord {
+            quote! { impl pyo3::class::compare::PyClassEq for #cls {} }
+        } else {
+            quote! {}
+        };
+        let ord = if attr.ord {
+            quote! { impl pyo3::class::compare::PyClassOrd for #cls {} }
+        } else {
+            quote! {}
+        };
+        let hash = if attr.hash.is_some() {
+            quote! { impl pyo3::class::compare::PyClassHash for #cls {} }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #eq
+            #ord
+            #hash
+            #extra
+        }
+    };
+    let extra = {
+        let repr = match attr.repr {
+            Some(Repr::Debug) => quote! { impl pyo3::class::repr::PyClassDebugRepr for #cls {} },
+            Some(Repr::Fields) => impl_fields_repr(cls, field_names),
+            None => quote! {},
+        };
+        let str = if attr.str {
+            quote! { impl pyo3::class::repr::PyClassDisplayStr for #cls {} }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #repr
+            #str
+            #extra
+        }
+    };
+    let extra = if !attr.ops.is_empty() {
+        let ops_impl = impl_ops(cls, &attr.ops);
+        quote! {
+            #ops_impl
+            #extra
+        }
+    } else {
+        extra
+    };
+    let extra = if has_singleton {
+        let singleton_impl = impl_singleton(cls);
+        quote! {
+            #singleton_impl
+            #extra
+        }
+    } else {
+        extra
+    };
     let weakref = if has_weakref {
         quote! {std::mem::size_of::<*const pyo3::ffi::PyObject>()}
     } else {
@@ -334,8 +732,10 @@ fn impl_class(
         quote! { None }
     };
 
-    // Enforce at compile time that PyGCProtocol is implemented
-    let gc_impl = if has_gc {
+    let gc_impl = if attr.derive_gc {
+        impl_traverse(cls, gc_field_names)
+    } else if has_gc {
+        // Enforce at compile time that PyGCProtocol is implemented
         let closure_name = format!("__assertion_closure_{}", cls.to_string());
         let closure_token = syn::Ident::new(&closure_name, Span::call_site());
         quote! {
@@ -350,10 +750,56 @@ fn impl_class(
         quote! {}
     };
 
+    // A Python metaclass is given by its qualified name, a Rust one as a type
+    let metaclass = match &attr.metaclass {
+        Some(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(name),
+            ..
+        })) => import_python_type(name),
+        Some(metaclass) => quote! {
+            Some({
+                fn metaclass(py: pyo3::Python) -> pyo3::PyResult<&pyo3::types::PyType> {
+                    Ok(py.get_type::<#metaclass>())
+                }
+                metaclass
+            })
+        },
+        None => quote! { None },
+    };
+    let python_base = match &attr.python_base {
+        Some(name) => import_python_type(name),
+        None => quote! { None },
+    };
+    // The Rust type is the first base of a Python class created at runtime, see
+    // `PyTypeInfo::python_class`
+    let python_class = if attr.python_base.is_some() {
+        quote! {
+            unsafe fn python_class() -> Option<&'static mut *mut pyo3::ffi::PyTypeObject> {
+                static mut PYTHON_CLASS: *mut pyo3::ffi::PyTypeObject = 0 as *mut _;
+                Some(&mut PYTHON_CLASS)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let inventory_impl = impl_inventory(&cls);
+    let stub_impl = if cfg!(feature = "stubs") {
+        Some(quote! {
+            impl pyo3::stubs::PyStubType for #cls {
+                fn type_hint() -> String {
+                    #cls_name.to_string()
+                }
+            }
+        })
+    } else {
+        None
+    };
 
     let base = &attr.base;
     let flags = &attr.flags;
+    let abcs = &attr.abcs;
+    let protocols = &attr.protocols;
 
     Ok(quote! {
         impl pyo3::type_object::PyTypeInfo for #cls {
@@ -364,6 +810,10 @@ fn impl_class(
             const MODULE: Option<&'static str> = #module;
             const DESCRIPTION: &'static str = #doc;
             const FLAGS: usize = #(#flags)|*;
+            const ABCS: &'static [&'static str] = &[#(#abcs),*];
+            const PROTOCOLS: &'static [&'static str] = &[#(#protocols),*];
+            const METACLASS: Option<for<'p> fn(pyo3::Python<'p>) -> pyo3::PyResult<&'p pyo3::types::PyType>> = #metaclass;
+            const PYTHON_BASE: Option<for<'p> fn(pyo3::Python<'p>) -> pyo3::PyResult<&'p pyo3::types::PyType>> = #python_base;
 
             const SIZE: usize = {
                 Self::OFFSET as usize +
@@ -383,6 +833,8 @@ fn impl_class(
                 static mut TYPE_OBJECT: pyo3::ffi::PyTypeObject = pyo3::ffi::PyTypeObject_INIT;
                 &mut TYPE_OBJECT
             }
+
+            #python_class
         }
 
         impl pyo3::IntoPy<PyObject> for #cls {
@@ -393,6 +845,8 @@ fn impl_class(
 
         #inventory_impl
 
+        #stub_impl
+
         #extra
 
         #gc_impl
@@ -400,6 +854,156 @@ fn impl_class(
     })
 }
 
+/// Generates a function importing the Python class with the qualified name `name`, where names
+/// without a module refer to `builtins`.
+fn import_python_type(name: &syn::LitStr) -> TokenStream {
+    quote! {
+        Some({
+            fn import_type(py: pyo3::Python) -> pyo3::PyResult<&pyo3::types::PyType> {
+                let name: &str = #name;
+                let (module, attr) = match name.rfind('.') {
+                    Some(pos) => (&name[..pos], &name[pos + 1..]),
+                    None => ("builtins", name),
+                };
+                Ok(py.import(module)?.get(attr)?.downcast_ref()?)
+            }
+            import_type
+        })
+    }
+}
+
+/// Implements `PyClassFieldsRepr` for `#[pyclass(repr = "fields")]`.
+fn impl_fields_repr(cls: &syn::Ident, field_names: &[syn::Ident]) -> TokenStream {
+    let keys: Vec<_> = field_names
+        .iter()
+        .map(|name| name.unraw().to_string())
+        .collect();
+    quote! {
+        impl pyo3::class::repr::PyClassFieldsRepr for #cls {
+            fn repr_fields(&self, py: pyo3::Python) -> Vec<(&'static str, pyo3::PyObject)> {
+                vec![#((#keys, pyo3::ToPyObject::to_object(&self.#field_names, py)),)*]
+            }
+        }
+    }
+}
+
+/// Implements `PyPickle` for `#[pyclass(pickle)]` and adds the methods pickle uses.
+fn impl_pickle(cls: &syn::Ident, pickle: Pickle, field_names: &[syn::Ident]) -> TokenStream {
+    let pickle_impl = match pickle {
+        Pickle::Fields => {
+            let keys: Vec<_> = field_names
+                .iter()
+                .map(|name| name.unraw().to_string())
+                .collect();
+            quote! {
+                impl pyo3::pickle::PyPickle for #cls {
+                    fn get_state(&self, py: pyo3::Python) -> pyo3::PyResult<pyo3::PyObject> {
+                        let state = pyo3::types::PyDict::new(py);
+                        #(state.set_item(#keys, &self.#field_names)?;)*
+                        Ok(state.into())
+                    }
+
+                    fn set_state(&mut self, state: &pyo3::types::PyAny) -> pyo3::PyResult<()> {
+                        *self = Self::from_state(state)?;
+                        Ok(())
+                    }
+
+                    fn from_state(state: &pyo3::types::PyAny) -> pyo3::PyResult<Self> {
+                        Ok(#cls {
+                            #(#field_names: pyo3::pickle::state_field(state, #keys)?,)*
+                        })
+                    }
+                }
+            }
+        }
+        Pickle::Serde => quote! {
+            impl pyo3::pickle::PyPickle for #cls {
+                fn get_state(&self, py: pyo3::Python) -> pyo3::PyResult<pyo3::PyObject> {
+                    pyo3::pythonize::pythonize(py, self)
+                }
+
+                fn set_state(&mut self, state: &pyo3::types::PyAny) -> pyo3::PyResult<()> {
+                    *self = pyo3::pythonize::depythonize(state)?;
+                    Ok(())
+                }
+
+                fn from_state(state: &pyo3::types::PyAny) -> pyo3::PyResult<Self> {
+                    pyo3::pythonize::depythonize(state)
+                }
+            }
+        },
+    };
+
+    quote! {
+        #pickle_impl
+
+        pyo3::inventory::submit! {
+            #![crate = pyo3] {
+                type TyInventory = <#cls as pyo3::class::methods::PyMethodsInventoryDispatch>::InventoryType;
+                <TyInventory as pyo3::class::methods::PyMethodsInventory>::new(&[
+                    pyo3::class::PyMethodDefType::Method(pyo3::class::PyMethodDef {
+                        ml_name: "__getstate__",
+                        ml_meth: pyo3::class::PyMethodType::PyCFunction(
+                            pyo3::pickle::pickle_getstate::<#cls>
+                        ),
+                        ml_flags: pyo3::ffi::METH_NOARGS,
+                        ml_doc: "\0",
+                    }),
+                    pyo3::class::PyMethodDefType::Method(pyo3::class::PyMethodDef {
+                        ml_name: "__setstate__",
+                        ml_meth: pyo3::class::PyMethodType::PyCFunction(
+                            pyo3::pickle::pickle_setstate::<#cls>
+                        ),
+                        ml_flags: pyo3::ffi::METH_O,
+                        ml_doc: "\0",
+                    }),
+                    pyo3::class::PyMethodDefType::Method(pyo3::class::PyMethodDef {
+                        ml_name: "__reduce__",
+                        ml_meth: pyo3::class::PyMethodType::PyCFunction(
+                            pyo3::pickle::pickle_reduce::<#cls>
+                        ),
+                        ml_flags: pyo3::ffi::METH_NOARGS,
+                        ml_doc: "\0",
+                    }),
+                    pyo3::class::PyMethodDefType::Class(pyo3::class::PyMethodDef {
+                        ml_name: "_pyo3_from_state",
+                        ml_meth: pyo3::class::PyMethodType::PyCFunction(
+                            pyo3::pickle::pickle_from_state::<#cls>
+                        ),
+                        ml_flags: pyo3::ffi::METH_O | pyo3::ffi::METH_CLASS,
+                        ml_doc: "\0",
+                    }),
+                ])
+            }
+        }
+    }
+}
+
+/// Implements `PyGCProtocol` for `#[pyclass(gc)]`, visiting and clearing every field not marked
+/// with `#[pyo3(gc = "skip")]` through `PyTraverse`.
+fn impl_traverse(cls: &syn::Ident, field_names: &[syn::Ident]) -> TokenStream {
+    quote! {
+        impl<'p> pyo3::class::gc::PyGCProtocol<'p> for #cls {
+            fn __traverse__(
+                &'p self,
+                visit: pyo3::class::gc::PyVisit,
+            ) -> Result<(), pyo3::class::gc::PyTraverseError> {
+                #(pyo3::class::gc::PyTraverse::traverse(&self.#field_names, &visit)?;)*
+                Ok(())
+            }
+
+            fn __clear__(&'p mut self) {
+                // Only called by the garbage collector, which holds the GIL
+                let _py = unsafe { pyo3::Python::assume_gil_acquired() };
+                #(pyo3::class::gc::PyTraverse::clear(&mut self.#field_names, _py);)*
+            }
+        }
+
+        impl<'p> pyo3::class::gc::PyGCTraverseProtocol<'p> for #cls {}
+        impl<'p> pyo3::class::gc::PyGCClearProtocol<'p> for #cls {}
+    }
+}
+
 fn impl_descriptors(
     cls: &syn::Type,
     descriptors: Vec<(syn::Field, Vec<FnType>)>,
@@ -440,6 +1044,7 @@ fn impl_descriptors(
         })
         .collect();
 
+    let mut member_stubs = Vec::new();
     let py_methods: Vec<TokenStream> = descriptors
         .iter()
         .flat_map(|&(ref field, ref fns)| {
@@ -455,20 +1060,30 @@ fn impl_descriptors(
                         FnType::Getter => {
                             let spec = FnSpec {
                                 tp: FnType::Getter,
+                                self_borrow: None,
                                 name: &name,
                                 python_name: name.unraw(),
                                 attrs: Vec::new(),
                                 args: Vec::new(),
                                 output: parse_quote!(PyResult<#field_ty>),
                                 doc,
+                                is_async: false,
                             };
-                            Ok(impl_py_getter_def(&spec, &impl_wrap_getter(&cls, &spec)?))
+                            if cfg!(feature = "stubs") {
+                                member_stubs.push(stubs::member_stub(cls, &spec));
+                            }
+                            Ok(impl_py_getter_def(
+                                &cls,
+                                &spec,
+                                &impl_wrap_getter(&cls, &spec)?,
+                            ))
                         }
                         FnType::Setter => {
                             let setter_name =
                                 syn::Ident::new(&format!("set_{}", name), Span::call_site());
                             let spec = FnSpec {
                                 tp: FnType::Setter,
+                                self_borrow: None,
                                 name: &setter_name,
                                 python_name: name.unraw(),
                                 attrs: Vec::new(),
@@ -483,8 +1098,16 @@ fn impl_descriptors(
                                 }],
                                 output: parse_quote!(PyResult<()>),
                                 doc,
+                                is_async: false,
                             };
-                            Ok(impl_py_setter_def(&spec, &impl_wrap_setter(&cls, &spec)?))
+                            if cfg!(feature = "stubs") {
+                                member_stubs.push(stubs::member_stub(cls, &spec));
+                            }
+                            Ok(impl_py_setter_def(
+                                &cls,
+                                &spec,
+                                &impl_wrap_setter(&cls, &spec)?,
+                            ))
                         }
                         _ => unreachable!(),
                     }
@@ -492,10 +1115,17 @@ fn impl_descriptors(
                 .collect::<Vec<syn::Result<TokenStream>>>()
         })
         .collect::<syn::Result<_>>()?;
+    let stubs = if cfg!(feature = "stubs") && !member_stubs.is_empty() {
+        Some(stubs::class_stub(cls, &member_stubs))
+    } else {
+        None
+    };
 
     Ok(quote! {
         #(#methods)*
 
+        #stubs
+
         pyo3::inventory::submit! {
             #![crate = pyo3] {
                 type ClsInventory = <#cls as pyo3::class::methods::PyMethodsInventoryDispatch>::InventoryType;
@@ -505,6 +1135,45 @@ fn impl_descriptors(
     })
 }
 
+/// Rejects fields of `#[pyclass(gc)]` holding a `Py<T>` which `__clear__` can't release, as the
+/// garbage collector would find reference cycles going through it but never break them.
+///
+/// `Option`, `Vec`, `VecDeque`, `HashMap` and `BTreeMap` release their contents when cleared, so
+/// a `Py<T>` inside them is fine.
+fn check_clearable(ty: &syn::Type) -> syn::Result<()> {
+    match ty {
+        syn::Type::Path(syn::TypePath { path, .. }) => {
+            let segment = match path.segments.last() {
+                Some(segment) => segment,
+                None => return Ok(()),
+            };
+            match segment.ident.to_string().as_str() {
+                "Py" => Err(syn::Error::new_spanned(
+                    ty,
+                    "Py<T> fields can't be cleared to break reference cycles, use Option<Py<T>> \
+                     or skip the field with #[pyo3(gc = \"skip\")]",
+                )),
+                "Option" | "Vec" | "VecDeque" | "HashMap" | "BTreeMap" => Ok(()),
+                _ => match &segment.arguments {
+                    syn::PathArguments::AngleBracketed(args) => {
+                        for arg in &args.args {
+                            if let syn::GenericArgument::Type(ty) = arg {
+                                check_clearable(ty)?;
+                            }
+                        }
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                },
+            }
+        }
+        syn::Type::Tuple(tuple) => tuple.elems.iter().try_for_each(check_clearable),
+        syn::Type::Paren(paren) => check_clearable(&paren.elem),
+        syn::Type::Group(group) => check_clearable(&group.elem),
+        _ => Ok(()),
+    }
+}
+
 fn check_generics(class: &mut syn::ItemStruct) -> syn::Result<()> {
     if class.generics.params.is_empty() {
         Ok(())
@@ -0,0 +1,122 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::pyclass::PyClassArgs;
+use crate::utils;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+
+/// Implements `PyEnum` and the conversions for a fieldless enum marked with `#[pyclass]`
+pub fn build_py_enum(enum_: &mut syn::ItemEnum, attr: &PyClassArgs) -> syn::Result<TokenStream> {
+    if attr.freelist.is_some()
+        || attr.flags.len() > 1
+        || attr.base != syn::parse_quote! {pyo3::types::PyAny}
+        || !attr.abcs.is_empty()
+        || !attr.protocols.is_empty()
+        || attr.metaclass.is_some()
+    {
+        return Err(syn::Error::new_spanned(
+            &enum_.ident,
+            "Only the `name` and `module` parameters of #[pyclass] are supported for enums",
+        ));
+    }
+    if !enum_.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &enum_.generics,
+            "#[pyclass] cannot have generic parameters",
+        ));
+    }
+    if enum_.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &enum_.ident,
+            "#[pyclass] enums must have at least one variant",
+        ));
+    }
+    for variant in &enum_.variants {
+        if variant.fields != syn::Fields::Unit {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "#[pyclass] can only be used with fieldless enums",
+            ));
+        }
+    }
+
+    let cls = &enum_.ident;
+    let name = match &attr.name {
+        Some(name) => quote! { #name },
+        None => {
+            let name = cls.unraw();
+            quote! { #name }
+        }
+    };
+    let module = match &attr.module {
+        Some(module) => quote! { Some(#module) },
+        None => quote! { None },
+    };
+    let doc = utils::get_doc(&enum_.attrs, None, false)?;
+    let variants: Vec<_> = enum_.variants.iter().map(|v| &v.ident).collect();
+    let variant_names: Vec<_> = variants.iter().map(|v| v.unraw()).collect();
+
+    let stub_impl = if cfg!(feature = "stubs") {
+        Some(quote! {
+            impl pyo3::stubs::PyStubType for #cls {
+                fn type_hint() -> String {
+                    stringify!(#name).to_string()
+                }
+            }
+        })
+    } else {
+        None
+    };
+
+    Ok(quote! {
+        #stub_impl
+
+        impl pyo3::enums::PyEnum for #cls {
+            const NAME: &'static str = stringify!(#name);
+            const MODULE: Option<&'static str> = #module;
+            const DESCRIPTION: &'static str = #doc;
+            const VARIANTS: &'static [(&'static str, isize)] = &[
+                #((stringify!(#variant_names), #cls::#variants as isize)),*
+            ];
+
+            fn discriminant(&self) -> isize {
+                match self {
+                    #(#cls::#variants => #cls::#variants as isize),*
+                }
+            }
+
+            fn from_discriminant(value: isize) -> Option<Self> {
+                #(
+                    if value == #cls::#variants as isize {
+                        return Some(#cls::#variants);
+                    }
+                )*
+                None
+            }
+
+            fn type_cache() -> &'static pyo3::cache::PyStatic {
+                static TYPE: pyo3::cache::PyStatic = pyo3::cache::PyStatic::new();
+                &TYPE
+            }
+        }
+
+        impl pyo3::ToPyObject for #cls {
+            fn to_object(&self, py: pyo3::Python) -> pyo3::PyObject {
+                pyo3::enums::enum_to_object(self, py)
+            }
+        }
+
+        impl pyo3::IntoPy<pyo3::PyObject> for #cls {
+            fn into_py(self, py: pyo3::Python) -> pyo3::PyObject {
+                pyo3::enums::enum_to_object(&self, py)
+            }
+        }
+
+        impl<'source> pyo3::FromPyObject<'source> for #cls {
+            fn extract(ob: &'source pyo3::types::PyAny) -> pyo3::PyResult<Self> {
+                pyo3::enums::extract_enum(ob)
+            }
+        }
+    })
+}
@@ -10,11 +10,15 @@ use syn::{NestedMeta, Path};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Argument {
+    /// `"/"`, which ends the positional-only arguments
+    PosOnlySeparator,
+    /// `"*"`, which starts the keyword-only arguments without taking `*args`
     VarArgsSeparator,
     VarArgs(syn::Path),
     KeywordArgs(syn::Path),
     Arg(syn::Path, Option<String>),
-    Kwarg(syn::Path, String),
+    /// A keyword-only argument, which is required if it has no default
+    Kwarg(syn::Path, Option<String>),
 }
 
 /// The attributes of the pyfunction macro
@@ -22,6 +26,7 @@ pub enum Argument {
 pub struct PyFunctionAttr {
     pub arguments: Vec<Argument>,
     has_kw: bool,
+    has_pos_only: bool,
     has_varargs: bool,
     has_kwargs: bool,
 }
@@ -44,6 +49,12 @@ impl PyFunctionAttr {
     }
 
     pub fn add_item(&mut self, item: &NestedMeta) -> syn::Result<()> {
+        if self.has_kwargs {
+            return Err(syn::Error::new_spanned(
+                item,
+                "no arguments are allowed after **kwargs",
+            ));
+        }
         match item {
             NestedMeta::Meta(syn::Meta::Path(ref ident)) => self.add_work(item, ident)?,
             NestedMeta::Meta(syn::Meta::NameValue(ref nv)) => {
@@ -63,22 +74,26 @@ impl PyFunctionAttr {
     fn add_literal(&mut self, item: &NestedMeta, lit: &syn::Lit) -> syn::Result<()> {
         match lit {
             syn::Lit::Str(ref lits) => {
-                // "*"
                 if lits.value() == "*" {
-                    if self.has_kwargs {
+                    self.add_varargs(item, Argument::VarArgsSeparator)?;
+                } else if lits.value() == "/" {
+                    if self.has_pos_only {
+                        return Err(syn::Error::new_spanned(item, "/ is already defined"));
+                    }
+                    if self.has_varargs {
                         return Err(syn::Error::new_spanned(
                             item,
-                            "syntax error, keyword self.arguments is defined",
+                            "/ must come before * and *args",
                         ));
                     }
-                    if self.has_varargs {
+                    if self.arguments.is_empty() {
                         return Err(syn::Error::new_spanned(
                             item,
-                            "self.arguments already define * (var args)",
+                            "/ must follow at least one argument",
                         ));
                     }
-                    self.has_varargs = true;
-                    self.arguments.push(Argument::VarArgsSeparator);
+                    self.has_pos_only = true;
+                    self.arguments.push(Argument::PosOnlySeparator);
                 } else {
                     return Err(syn::Error::new_spanned(lits, "Unknown string literal"));
                 }
@@ -93,18 +108,29 @@ impl PyFunctionAttr {
         Ok(())
     }
 
-    fn add_work(&mut self, item: &NestedMeta, path: &Path) -> syn::Result<()> {
-        // self.arguments in form somename
-        if self.has_kwargs {
+    fn add_varargs(&mut self, item: &NestedMeta, argument: Argument) -> syn::Result<()> {
+        if self.has_varargs {
             return Err(syn::Error::new_spanned(
                 item,
-                "syntax error, keyword self.arguments is defined",
+                "* or *args is already defined",
             ));
         }
+        self.has_varargs = true;
+        self.arguments.push(argument);
+        Ok(())
+    }
+
+    fn add_work(&mut self, item: &NestedMeta, path: &Path) -> syn::Result<()> {
+        // self.arguments in form somename
+        if self.has_varargs {
+            // Keyword-only arguments may be required in any order
+            self.arguments.push(Argument::Kwarg(path.clone(), None));
+            return Ok(());
+        }
         if self.has_kw {
             return Err(syn::Error::new_spanned(
                 item,
-                "syntax error, argument is not allowed after keyword argument",
+                "non-default argument follows default argument",
             ));
         }
         self.arguments.push(Argument::Arg(path.clone(), None));
@@ -112,80 +138,21 @@ impl PyFunctionAttr {
     }
 
     fn add_name_value(&mut self, item: &NestedMeta, nv: &syn::MetaNameValue) -> syn::Result<()> {
-        match nv.lit {
+        let default = match nv.lit {
             syn::Lit::Str(ref litstr) => {
                 if litstr.value() == "*" {
                     // args="*"
-                    if self.has_kwargs {
-                        return Err(syn::Error::new_spanned(
-                            item,
-                            "* - syntax error, keyword self.arguments is defined",
-                        ));
-                    }
-                    if self.has_varargs {
-                        return Err(syn::Error::new_spanned(item, "*(var args) is defined"));
-                    }
-                    self.has_varargs = true;
-                    self.arguments.push(Argument::VarArgs(nv.path.clone()));
+                    return self.add_varargs(item, Argument::VarArgs(nv.path.clone()));
                 } else if litstr.value() == "**" {
                     // kwargs="**"
-                    if self.has_kwargs {
-                        return Err(syn::Error::new_spanned(
-                            item,
-                            "self.arguments already define ** (kw args)",
-                        ));
-                    }
                     self.has_kwargs = true;
                     self.arguments.push(Argument::KeywordArgs(nv.path.clone()));
-                } else if self.has_varargs {
-                    self.arguments
-                        .push(Argument::Kwarg(nv.path.clone(), litstr.value()))
-                } else {
-                    if self.has_kwargs {
-                        return Err(syn::Error::new_spanned(
-                            item,
-                            "syntax error, keyword self.arguments is defined",
-                        ));
-                    }
-                    self.has_kw = true;
-                    self.arguments
-                        .push(Argument::Arg(nv.path.clone(), Some(litstr.value())))
-                }
-            }
-            syn::Lit::Int(ref litint) => {
-                if self.has_varargs {
-                    self.arguments
-                        .push(Argument::Kwarg(nv.path.clone(), format!("{}", litint)));
-                } else {
-                    if self.has_kwargs {
-                        return Err(syn::Error::new_spanned(
-                            item,
-                            "syntax error, keyword self.arguments is defined",
-                        ));
-                    }
-                    self.has_kw = true;
-                    self.arguments
-                        .push(Argument::Arg(nv.path.clone(), Some(format!("{}", litint))));
-                }
-            }
-            syn::Lit::Bool(ref litb) => {
-                if self.has_varargs {
-                    self.arguments
-                        .push(Argument::Kwarg(nv.path.clone(), format!("{}", litb.value)));
-                } else {
-                    if self.has_kwargs {
-                        return Err(syn::Error::new_spanned(
-                            item,
-                            "syntax error, keyword self.arguments is defined",
-                        ));
-                    }
-                    self.has_kw = true;
-                    self.arguments.push(Argument::Arg(
-                        nv.path.clone(),
-                        Some(format!("{}", litb.value)),
-                    ));
+                    return Ok(());
                 }
+                litstr.value()
             }
+            syn::Lit::Int(ref litint) => format!("{}", litint),
+            syn::Lit::Bool(ref litb) => format!("{}", litb.value),
             _ => {
                 return Err(syn::Error::new_spanned(
                     nv.lit.clone(),
@@ -193,6 +160,14 @@ impl PyFunctionAttr {
                 ));
             }
         };
+        if self.has_varargs {
+            self.arguments
+                .push(Argument::Kwarg(nv.path.clone(), Some(default)));
+        } else {
+            self.has_kw = true;
+            self.arguments
+                .push(Argument::Arg(nv.path.clone(), Some(default)));
+        }
         Ok(())
     }
 }
@@ -255,6 +230,9 @@ mod test {
         assert!(items(quote! {test, "*", args="*"}).is_err());
         assert!(items(quote! {test, kwargs="**", args="*"}).is_err());
         assert!(items(quote! {test, kwargs="**", args}).is_err());
+        assert!(items(quote! {"/", test}).is_err());
+        assert!(items(quote! {test, "/", test2, "/"}).is_err());
+        assert!(items(quote! {test, "*", test2, "/"}).is_err());
     }
 
     #[test]
@@ -277,7 +255,22 @@ mod test {
                 Argument::Arg(parse_quote! {test1}, None),
                 Argument::Arg(parse_quote! {test2}, Some("None".to_owned())),
                 Argument::VarArgsSeparator,
-                Argument::Kwarg(parse_quote! {test3}, "None".to_owned()),
+                Argument::Kwarg(parse_quote! {test3}, Some("None".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pos_only_and_required_kw_only() {
+        let args = items(quote! {test1, "/", test2="1", "*", test3, test4=true}).unwrap();
+        assert!(
+            args == vec![
+                Argument::Arg(parse_quote! {test1}, None),
+                Argument::PosOnlySeparator,
+                Argument::Arg(parse_quote! {test2}, Some("1".to_owned())),
+                Argument::VarArgsSeparator,
+                Argument::Kwarg(parse_quote! {test3}, None),
+                Argument::Kwarg(parse_quote! {test4}, Some("true".to_owned())),
             ]
         );
     }
@@ -291,7 +284,7 @@ mod test {
                 Argument::Arg(parse_quote! {test1}, None),
                 Argument::Arg(parse_quote! {test2}, Some("None".to_owned())),
                 Argument::VarArgs(parse_quote! {args}),
-                Argument::Kwarg(parse_quote! {test3}, "None".to_owned()),
+                Argument::Kwarg(parse_quote! {test3}, Some("None".to_owned())),
                 Argument::KeywordArgs(parse_quote! {kwargs}),
             ]
         );
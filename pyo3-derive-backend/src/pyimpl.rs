@@ -1,6 +1,7 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 use crate::pymethod;
+use crate::stubs;
 use proc_macro2::TokenStream;
 use quote::quote;
 
@@ -23,13 +24,36 @@ pub fn build_py_methods(ast: &mut syn::ItemImpl) -> syn::Result<TokenStream> {
 pub fn impl_methods(ty: &syn::Type, impls: &mut Vec<syn::ImplItem>) -> syn::Result<TokenStream> {
     // get method names in impl block
     let mut methods = Vec::new();
+    let mut member_stubs = Vec::new();
     for iimpl in impls.iter_mut() {
-        if let syn::ImplItem::Method(ref mut meth) = iimpl {
-            methods.push(pymethod::gen_py_method(ty, &mut meth.sig, &mut meth.attrs)?);
+        match iimpl {
+            syn::ImplItem::Method(ref mut meth) => {
+                let (method, stub) = pymethod::gen_py_method(ty, &mut meth.sig, &mut meth.attrs)?;
+                methods.push(method);
+                member_stubs.extend(stub);
+            }
+            syn::ImplItem::Const(ref mut konst) => {
+                let len = konst.attrs.len();
+                konst.attrs.retain(|attr| !attr.path.is_ident("classattr"));
+                if konst.attrs.len() != len {
+                    methods.push(pymethod::impl_py_const_class_attribute(ty, &konst.ident));
+                    if cfg!(feature = "stubs") {
+                        member_stubs.push(stubs::class_attribute_stub(ty, &konst.ident, &konst.ty));
+                    }
+                }
+            }
+            _ => {}
         }
     }
+    let stubs = if cfg!(feature = "stubs") {
+        Some(stubs::class_stub(ty, &member_stubs))
+    } else {
+        None
+    };
 
     Ok(quote! {
+       #stubs
+
        pyo3::inventory::submit! {
             #![crate = pyo3] {
                 type TyInventory = <#ty as pyo3::class::methods::PyMethodsInventoryDispatch>::InventoryType;
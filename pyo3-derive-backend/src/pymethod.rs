@@ -1,30 +1,107 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
-use crate::method::{FnArg, FnSpec, FnType};
+use crate::method::{FnArg, FnSpec, FnType, SelfBorrow};
+use crate::signature;
+use crate::stubs;
 use crate::utils;
 use proc_macro2::{Span, TokenStream};
-use quote::quote;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
 
+/// Generates the method definition, and the `pyo3::stubs::MemberStub` of the method if the
+/// `stubs` feature is enabled.
 pub fn gen_py_method(
     cls: &syn::Type,
     sig: &mut syn::Signature,
     meth_attrs: &mut Vec<syn::Attribute>,
-) -> syn::Result<TokenStream> {
+) -> syn::Result<(TokenStream, Option<TokenStream>)> {
     check_generic(sig)?;
 
+    let is_final = utils::parse_final_attr(meth_attrs);
+    let min_python = utils::parse_min_python_attr(meth_attrs)?;
+    let is_abstract = parse_abstractmethod_attr(meth_attrs);
     let spec = FnSpec::parse(sig, &mut *meth_attrs, true)?;
 
-    Ok(match spec.tp {
-        FnType::Fn => impl_py_method_def(&spec, &impl_wrap(cls, &spec, true)),
+    match spec.tp {
+        FnType::Fn | FnType::PySelf(_) => {}
+        _ if is_abstract => {
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                "#[abstractmethod] is only supported on instance methods",
+            ));
+        }
+        FnType::FnClass | FnType::FnStatic => {}
+        _ if is_final => {
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                "#[pyo3(final)] is only supported on methods, class methods and static methods",
+            ));
+        }
+        _ => {}
+    }
+    if is_abstract && is_final {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "An abstract method can't be final",
+        ));
+    }
+
+    let def = match spec.tp {
+        FnType::Fn => impl_py_method_def(cls, &spec, &impl_wrap(cls, &spec, true)),
         FnType::PySelf(ref self_ty) => {
-            impl_py_method_def(&spec, &impl_wrap_pyslf(cls, &spec, self_ty, true))
+            impl_py_method_def(cls, &spec, &impl_wrap_pyslf(cls, &spec, self_ty, true))
         }
-        FnType::FnNew => impl_py_method_def_new(&spec, &impl_wrap_new(cls, &spec)),
-        FnType::FnCall => impl_py_method_def_call(&spec, &impl_wrap(cls, &spec, false)),
-        FnType::FnClass => impl_py_method_def_class(&spec, &impl_wrap_class(cls, &spec)),
-        FnType::FnStatic => impl_py_method_def_static(&spec, &impl_wrap_static(cls, &spec)),
-        FnType::Getter => impl_py_getter_def(&spec, &impl_wrap_getter(cls, &spec)?),
-        FnType::Setter => impl_py_setter_def(&spec, &impl_wrap_setter(cls, &spec)?),
-    })
+        FnType::FnNew => impl_py_method_def_new(cls, &spec, &impl_wrap_new(cls, &spec)),
+        FnType::FnCall => impl_py_method_def_call(cls, &spec, &impl_wrap(cls, &spec, false)),
+        FnType::FnClass => impl_py_method_def_class(cls, &spec, &impl_wrap_class(cls, &spec)),
+        FnType::FnStatic => impl_py_method_def_static(cls, &spec, &impl_wrap_static(cls, &spec)),
+        FnType::Getter => impl_py_getter_def(cls, &spec, &impl_wrap_getter(cls, &spec)?),
+        FnType::Setter => impl_py_setter_def(cls, &spec, &impl_wrap_setter(cls, &spec)?),
+        FnType::ClassAttribute => impl_py_class_attribute(cls, &spec)?,
+    };
+
+    let def = if is_abstract {
+        quote! {{
+            fn __abstract() -> &'static pyo3::class::PyMethodDefType {
+                static DEF: pyo3::class::PyMethodDefType = #def;
+                &DEF
+            }
+            pyo3::class::PyMethodDefType::Abstract(__abstract)
+        }}
+    } else if is_final {
+        quote! {{
+            fn __final() -> &'static pyo3::class::PyMethodDefType {
+                static DEF: pyo3::class::PyMethodDefType = #def;
+                &DEF
+            }
+            pyo3::class::PyMethodDefType::Final(__final)
+        }}
+    } else {
+        def
+    };
+
+    let def = match min_python {
+        Some((major, minor)) => quote! {{
+            fn __def() -> &'static pyo3::class::PyMethodDefType {
+                static DEF: pyo3::class::PyMethodDefType = #def;
+                &DEF
+            }
+            pyo3::class::PyMethodDefType::MinPython((#major, #minor), __def)
+        }},
+        None => def,
+    };
+    let stub = if cfg!(feature = "stubs") {
+        Some(stubs::member_stub(cls, &spec))
+    } else {
+        None
+    };
+    Ok((def, stub))
+}
+
+/// Parses and removes `#[abstractmethod]`.
+fn parse_abstractmethod_attr(attrs: &mut Vec<syn::Attribute>) -> bool {
+    let len = attrs.len();
+    attrs.retain(|attr| !attr.path.is_ident("abstractmethod"));
+    attrs.len() != len
 }
 
 fn check_generic(sig: &syn::Signature) -> syn::Result<()> {
@@ -45,11 +122,34 @@ fn check_generic(sig: &syn::Signature) -> syn::Result<()> {
 
 /// Generate function wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_wrap(cls: &syn::Type, spec: &FnSpec<'_>, noargs: bool) -> TokenStream {
+    if spec.is_async {
+        return impl_wrap_async(cls, spec);
+    }
     let body = impl_call(cls, &spec);
     let slf = impl_self(&quote! { &mut #cls });
     impl_wrap_common(cls, spec, noargs, slf, body)
 }
 
+/// Generates the wrapper of an async method taking `&self`. The coroutine outlives the call, so
+/// it owns a reference to the instance, which it borrows while it is polled with the GIL held.
+fn impl_wrap_async(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
+    let fname = &spec.name;
+    let names = get_arg_names(spec);
+    let body = quote! {
+        async move {
+            let _slf: &#cls = &*(&*pyo3::AsPyRef::as_ref(
+                &_slf_py,
+                pyo3::Python::assume_gil_acquired(),
+            ) as *const #cls);
+            _slf.#fname(#(#names),*).await
+        }
+    };
+    let slf = quote! {
+        let _slf_py: pyo3::Py<#cls> = pyo3::Py::from_borrowed_ptr(_slf);
+    };
+    impl_wrap_common(cls, spec, true, slf, body)
+}
+
 pub fn impl_wrap_pyslf(
     cls: &syn::Type,
     spec: &FnSpec<'_>,
@@ -72,10 +172,12 @@ fn impl_wrap_common(
     slf: TokenStream,
     body: TokenStream,
 ) -> TokenStream {
+    let wrapper = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &spec.python_name;
     if spec.args.is_empty() && noargs {
         quote! {
-            unsafe extern "C" fn __wrap(
+            #[allow(non_snake_case)]
+            unsafe extern "C" fn #wrapper(
                 _slf: *mut pyo3::ffi::PyObject
             ) -> *mut pyo3::ffi::PyObject
             {
@@ -93,16 +195,29 @@ fn impl_wrap_common(
             }
         }
     } else {
+        let location = quote! { concat!(stringify!(#cls), ".", stringify!(#python_name), "()") };
+        // `__call__` (without `noargs`) is a slot, which can't use `METH_FASTCALL`
+        let fastcall = if noargs {
+            Some(impl_wrap_fastcall(
+                &wrapper,
+                &location,
+                &slf,
+                spec,
+                body.clone(),
+            ))
+        } else {
+            None
+        };
         let body = impl_arg_params(&spec, body);
 
         quote! {
-            unsafe extern "C" fn __wrap(
+            #[allow(non_snake_case)]
+            unsafe extern "C" fn #wrapper(
                 _slf: *mut pyo3::ffi::PyObject,
                 _args: *mut pyo3::ffi::PyObject,
                 _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
             {
-                const _LOCATION: &'static str = concat!(
-                    stringify!(#cls), ".", stringify!(#python_name), "()");
+                const _LOCATION: &'static str = #location;
                 let _py = pyo3::Python::assume_gil_acquired();
                 let _pool = pyo3::GILPool::new(_py);
                 #slf
@@ -114,19 +229,63 @@ fn impl_wrap_common(
                 pyo3::callback::cb_convert(
                     pyo3::callback::PyObjectCallbackConverter, _py, _result)
             }
+
+            #fastcall
         }
     }
 }
 
+/// Generates the `METH_FASTCALL | METH_KEYWORDS` entry point of a function next to the
+/// `METH_VARARGS | METH_KEYWORDS` one named `wrapper`, see
+/// `pyo3::class::PyMethodType::PyCFunctionFastWithKeywords`.
+///
+/// `slf` converts the `_slf` argument and `cb` calls the function, as for the other entry point.
+pub fn impl_wrap_fastcall(
+    wrapper: &syn::Ident,
+    location: &TokenStream,
+    slf: &TokenStream,
+    spec: &FnSpec<'_>,
+    cb: TokenStream,
+) -> TokenStream {
+    let fastcall_wrapper = fastcall_wrapper_ident(wrapper);
+    let body = impl_arg_params_fastcall(spec, cb);
+
+    quote! {
+        #[allow(unused_mut, non_snake_case)]
+        unsafe extern "C" fn #fastcall_wrapper(
+            _slf: *mut pyo3::ffi::PyObject,
+            _args: *const *mut pyo3::ffi::PyObject,
+            _nargs: pyo3::ffi::Py_ssize_t,
+            _kwnames: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+        {
+            const _LOCATION: &'static str = #location;
+            let _py = pyo3::Python::assume_gil_acquired();
+            let _pool = pyo3::GILPool::new(_py);
+            #slf
+
+            #body
+
+            pyo3::callback::cb_convert(
+                pyo3::callback::PyObjectCallbackConverter, _py, _result)
+        }
+    }
+}
+
+/// The name of the `METH_FASTCALL` entry point generated next to `wrapper`.
+pub fn fastcall_wrapper_ident(wrapper: &syn::Ident) -> syn::Ident {
+    format_ident!("{}_fastcall", wrapper)
+}
+
 /// Generate function wrapper for protocol method (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_proto_wrap(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
+    let wrapper = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &spec.python_name;
     let cb = impl_call(cls, &spec);
     let body = impl_arg_params(&spec, cb);
 
     quote! {
-        #[allow(unused_mut)]
-        unsafe extern "C" fn __wrap(
+        #[allow(unused_mut, non_snake_case)]
+        unsafe extern "C" fn #wrapper(
             _slf: *mut pyo3::ffi::PyObject,
             _args: *mut pyo3::ffi::PyObject,
             _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
@@ -148,6 +307,7 @@ pub fn impl_proto_wrap(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
 
 /// Generate class method wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_wrap_new(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
+    let wrapper = utils::wrapper_ident(Some(cls), &spec.python_name);
     let name = &spec.name;
     let python_name = &spec.python_name;
     let names: Vec<syn::Ident> = get_arg_names(&spec);
@@ -156,8 +316,8 @@ pub fn impl_wrap_new(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     let body = impl_arg_params(spec, cb);
 
     quote! {
-        #[allow(unused_mut)]
-        unsafe extern "C" fn __wrap(
+        #[allow(unused_mut, non_snake_case)]
+        unsafe extern "C" fn #wrapper(
             _cls: *mut pyo3::ffi::PyTypeObject,
             _args: *mut pyo3::ffi::PyObject,
             _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
@@ -193,16 +353,32 @@ pub fn impl_wrap_new(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
 
 /// Generate class method wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_wrap_class(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
+    let wrapper = utils::wrapper_ident(Some(cls), &spec.python_name);
     let name = &spec.name;
     let python_name = &spec.python_name;
     let names: Vec<syn::Ident> = get_arg_names(&spec);
-    let cb = quote! { #cls::#name(&_cls, #(#names),*) };
+    let cb = if spec.is_async {
+        // The coroutine outlives the call, so it gets its own reference to the class
+        quote! {
+            #cls::#name(
+                pyo3::Py::from_borrowed_ptr(pyo3::AsPyPointer::as_ptr(_cls)),
+                #(#names),*
+            )
+        }
+    } else {
+        quote! { #cls::#name(&_cls, #(#names),*) }
+    };
 
+    let location = quote! { concat!(stringify!(#cls), ".", stringify!(#python_name), "()") };
+    let cls_from_slf = quote! {
+        let _cls = pyo3::types::PyType::from_type_ptr(_py, _slf as *mut pyo3::ffi::PyTypeObject);
+    };
+    let fastcall = impl_wrap_fastcall(&wrapper, &location, &cls_from_slf, spec, cb.clone());
     let body = impl_arg_params(spec, cb);
 
     quote! {
-        #[allow(unused_mut)]
-        unsafe extern "C" fn __wrap(
+        #[allow(unused_mut, non_snake_case)]
+        unsafe extern "C" fn #wrapper(
             _cls: *mut pyo3::ffi::PyObject,
             _args: *mut pyo3::ffi::PyObject,
             _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
@@ -219,21 +395,26 @@ pub fn impl_wrap_class(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
             pyo3::callback::cb_convert(
                 pyo3::callback::PyObjectCallbackConverter, _py, _result)
         }
+
+        #fastcall
     }
 }
 
 /// Generate static method wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_wrap_static(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
+    let wrapper = utils::wrapper_ident(Some(cls), &spec.python_name);
     let name = &spec.name;
     let python_name = &spec.python_name;
     let names: Vec<syn::Ident> = get_arg_names(&spec);
     let cb = quote! { #cls::#name(#(#names),*) };
 
+    let location = quote! { concat!(stringify!(#cls), ".", stringify!(#python_name), "()") };
+    let fastcall = impl_wrap_fastcall(&wrapper, &location, &quote! {}, spec, cb.clone());
     let body = impl_arg_params(spec, cb);
 
     quote! {
-        #[allow(unused_mut)]
-        unsafe extern "C" fn __wrap(
+        #[allow(unused_mut, non_snake_case)]
+        unsafe extern "C" fn #wrapper(
             _slf: *mut pyo3::ffi::PyObject,
             _args: *mut pyo3::ffi::PyObject,
             _kwargs: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
@@ -249,11 +430,14 @@ pub fn impl_wrap_static(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
             pyo3::callback::cb_convert(
                 pyo3::callback::PyObjectCallbackConverter, _py, _result)
         }
+
+        #fastcall
     }
 }
 
 /// Generate functiona wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub(crate) fn impl_wrap_getter(cls: &syn::Type, spec: &FnSpec) -> syn::Result<TokenStream> {
+    let wrapper = utils::wrapper_ident(Some(cls), &spec.python_name);
     let takes_py = match &*spec.args {
         [] => false,
         [arg] if utils::if_type_is_python(arg.ty) => true,
@@ -275,7 +459,8 @@ pub(crate) fn impl_wrap_getter(cls: &syn::Type, spec: &FnSpec) -> syn::Result<To
     };
 
     Ok(quote! {
-        unsafe extern "C" fn __wrap(
+        #[allow(non_snake_case)]
+        unsafe extern "C" fn #wrapper(
             _slf: *mut pyo3::ffi::PyObject, _: *mut ::std::os::raw::c_void) -> *mut pyo3::ffi::PyObject
         {
             const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#python_name),"()");
@@ -299,8 +484,63 @@ pub(crate) fn impl_wrap_getter(cls: &syn::Type, spec: &FnSpec) -> syn::Result<To
     })
 }
 
+/// Generates the definition of a `#[classattr]` function, which is called once when the type
+/// object is initialized.
+fn impl_py_class_attribute(cls: &syn::Type, spec: &FnSpec) -> syn::Result<TokenStream> {
+    if spec.self_borrow.is_some() {
+        return Err(syn::Error::new_spanned(
+            spec.name,
+            "#[classattr] can't take self, as it's computed when the class is created",
+        ));
+    }
+    let name = &spec.name;
+    let python_name = &spec.python_name;
+    let fncall = match &*spec.args {
+        [] => quote! { #cls::#name() },
+        [arg] if arg.py => quote! { #cls::#name(py) },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                spec.args[0].ty,
+                "#[classattr] can only have one argument of type pyo3::Python",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        pyo3::class::PyMethodDefType::ClassAttribute({
+            fn __wrap(py: pyo3::Python) -> pyo3::PyResult<pyo3::PyObject> {
+                pyo3::derive_utils::IntoPyResult::into_py_result(#fncall)
+                    .map(|val| pyo3::IntoPy::<pyo3::PyObject>::into_py(val, py))
+            }
+
+            pyo3::class::PyClassAttributeDef {
+                name: stringify!(#python_name),
+                meth: __wrap,
+            }
+        })
+    })
+}
+
+/// Generates the definition of a `#[classattr]` associated constant.
+pub(crate) fn impl_py_const_class_attribute(cls: &syn::Type, name: &syn::Ident) -> TokenStream {
+    let python_name = name.unraw();
+    quote! {
+        pyo3::class::PyMethodDefType::ClassAttribute({
+            fn __wrap(py: pyo3::Python) -> pyo3::PyResult<pyo3::PyObject> {
+                Ok(pyo3::IntoPy::<pyo3::PyObject>::into_py(#cls::#name, py))
+            }
+
+            pyo3::class::PyClassAttributeDef {
+                name: stringify!(#python_name),
+                meth: __wrap,
+            }
+        })
+    }
+}
+
 /// Generate functiona wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub(crate) fn impl_wrap_setter(cls: &syn::Type, spec: &FnSpec<'_>) -> syn::Result<TokenStream> {
+    let wrapper = utils::wrapper_ident(Some(cls), &spec.python_name);
     let name = &spec.name;
     let python_name = &spec.python_name;
 
@@ -321,8 +561,8 @@ pub(crate) fn impl_wrap_setter(cls: &syn::Type, spec: &FnSpec<'_>) -> syn::Resul
     };
 
     Ok(quote! {
-        #[allow(unused_mut)]
-        unsafe extern "C" fn __wrap(
+        #[allow(unused_mut, non_snake_case)]
+        unsafe extern "C" fn #wrapper(
             _slf: *mut pyo3::ffi::PyObject,
             _value: *mut pyo3::ffi::PyObject, _: *mut ::std::os::raw::c_void) -> pyo3::libc::c_int
         {
@@ -378,6 +618,20 @@ fn bool_to_ident(condition: bool) -> syn::Ident {
 }
 
 pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
+    impl_arg_params_with(spec, body, false)
+}
+
+/// Like `impl_arg_params`, for the `_args`, `_nargs` and `_kwnames` of `METH_FASTCALL`.
+pub fn impl_arg_params_fastcall(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
+    impl_arg_params_with(spec, body, true)
+}
+
+fn impl_arg_params_with(spec: &FnSpec<'_>, body: TokenStream, fastcall: bool) -> TokenStream {
+    let body = if spec.is_async {
+        quote! { pyo3::asyncio::into_coroutine(_py, #body) }
+    } else {
+        body
+    };
     if spec.args.is_empty() {
         return quote! {
             let _result = {
@@ -392,15 +646,17 @@ pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
         if arg.py || spec.is_args(&arg.name) || spec.is_kwargs(&arg.name) {
             continue;
         }
-        let name = arg.name;
+        let name = arg.name.unraw().to_string();
         let kwonly = bool_to_ident(spec.is_kw_only(&arg.name));
+        let posonly = bool_to_ident(spec.is_pos_only(&arg.name));
         let opt = bool_to_ident(arg.optional.is_some() || spec.default_value(&arg.name).is_some());
 
         params.push(quote! {
             pyo3::derive_utils::ParamDescription {
-                name: stringify!(#name),
+                name: #name,
                 is_optional: #opt,
-                kw_only: #kwonly
+                kw_only: #kwonly,
+                pos_only: #posonly,
             }
         });
     }
@@ -411,9 +667,38 @@ pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
         param_conversion.push(impl_arg_param(&arg, &spec, idx, &mut option_pos));
     }
 
+    let borrow_check = impl_borrow_check(spec);
+
     let accept_args = bool_to_ident(spec.accept_args());
     let accept_kwargs = bool_to_ident(spec.accept_kwargs());
     let num_normal_params = params.len();
+    let parse = if fastcall {
+        quote! {
+            pyo3::derive_utils::parse_fn_args_fastcall(
+                _py,
+                Some(_LOCATION),
+                PARAMS,
+                _args,
+                _nargs,
+                _kwnames,
+                #accept_args,
+                #accept_kwargs,
+                &mut output
+            )
+        }
+    } else {
+        quote! {
+            pyo3::derive_utils::parse_fn_args(
+                Some(_LOCATION),
+                PARAMS,
+                _args,
+                _kwargs,
+                #accept_args,
+                #accept_kwargs,
+                &mut output
+            )
+        }
+    };
     // create array of arguments, and then parse
     quote! {
         use pyo3::ObjectProtocol;
@@ -422,28 +707,80 @@ pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
         ];
 
         let mut output = [None; #num_normal_params];
-        let mut _args = _args;
-        let mut _kwargs = _kwargs;
 
         // Workaround to use the question mark operator without rewriting everything
         let _result = (|| {
-            let (_args, _kwargs) = pyo3::derive_utils::parse_fn_args(
-                Some(_LOCATION),
-                PARAMS,
-                _args,
-                _kwargs,
-                #accept_args,
-                #accept_kwargs,
-                &mut output
-            )?;
+            let (_args, _kwargs) = #parse?;
 
             #(#param_conversion)*
+            #borrow_check
 
             pyo3::derive_utils::IntoPyResult::into_py_result(#body)
         })();
     }
 }
 
+/// Returns whether `ty` is a reference or an optional reference, and whether it's mutable.
+fn reference_mutability(ty: &syn::Type) -> Option<bool> {
+    match ty {
+        syn::Type::Reference(reference) => Some(reference.mutability.is_some()),
+        syn::Type::Path(syn::TypePath { path, .. }) => {
+            let segment = path.segments.last()?;
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(params) if segment.ident == "Option" => {
+                    match params.args.first()? {
+                        syn::GenericArgument::Type(ty) => reference_mutability(ty),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Checks that arguments extracted as `&mut T` don't refer to the same object as `self` or
+/// another argument, e.g. in `a.merge(a)`, which would alias the mutable reference.
+///
+/// Nothing is generated for functions without a mutable borrow among their arguments.
+fn impl_borrow_check(spec: &FnSpec<'_>) -> TokenStream {
+    let mut borrows = Vec::new();
+    let mut any_mut = false;
+    for (idx, arg) in spec.args.iter().enumerate() {
+        if arg.py || spec.is_args(&arg.name) || spec.is_kwargs(&arg.name) {
+            continue;
+        }
+        if let Some(mutable) = reference_mutability(arg.ty) {
+            let name = arg.name.unraw().to_string();
+            let arg_name = syn::Ident::new(&format!("arg{}", idx), Span::call_site());
+            borrows.push(quote! {
+                pyo3::derive_utils::ArgBorrow::new(#name, &#arg_name, #mutable)
+            });
+            any_mut |= mutable;
+        }
+    }
+    if borrows.is_empty() {
+        return TokenStream::new();
+    }
+    if let Some(self_borrow) = spec.self_borrow {
+        let mutable = self_borrow == SelfBorrow::Mutable;
+        borrows.insert(
+            0,
+            quote! {
+                pyo3::derive_utils::ArgBorrow::new("self", &&*_slf, #mutable)
+            },
+        );
+        any_mut |= mutable;
+    }
+    if !any_mut || borrows.len() < 2 {
+        return TokenStream::new();
+    }
+    quote! {
+        pyo3::derive_utils::check_borrows(&[#(#borrows),*])?;
+    }
+}
+
 /// Re option_pos: The option slice doesn't contain the py: Python argument, so the argument
 /// index and the index in option diverge when using py: Python
 fn impl_arg_param(
@@ -462,10 +799,14 @@ fn impl_arg_param(
 
     let ty = arg.ty;
     let name = arg.name;
+    let py_name = name.unraw().to_string();
+    let map_err = quote! {
+        .map_err(|e| pyo3::derive_utils::argument_extraction_error(_py, #py_name, e))?
+    };
 
     if spec.is_args(&name) {
         return quote! {
-            let #arg_name = <#ty as pyo3::FromPyObject>::extract(_args.as_ref())?;
+            let #arg_name = <#ty as pyo3::FromPyObject>::extract(_args.as_ref())#map_err;
         };
     } else if spec.is_kwargs(&name) {
         return quote! {
@@ -490,7 +831,7 @@ fn impl_arg_param(
                     if _obj.is_none() {
                         #default
                     } else {
-                        Some(_obj.extract()?)
+                        Some(_obj.extract()#map_err)
                     }
                 },
                 None => #default
@@ -503,7 +844,7 @@ fn impl_arg_param(
                     if _obj.is_none() {
                         #default
                     } else {
-                        _obj.extract()?
+                        _obj.extract()#map_err
                     }
                 },
                 None => #default
@@ -511,35 +852,48 @@ fn impl_arg_param(
         }
     } else {
         quote! {
-            let #arg_name = #arg_value.unwrap().extract()?;
+            let #arg_name = #arg_value.unwrap().extract()#map_err;
         }
     }
 }
 
-pub fn impl_py_method_def(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+pub fn impl_py_method_def(cls: &syn::Type, spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+    let wrapper_ident = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &spec.python_name;
     let doc = &spec.doc;
     if spec.args.is_empty() {
+        let meth = quote! { pyo3::class::PyMethodType::PyNoArgsFunction(#wrapper_ident) };
+        let signature = signature::register(&meth, spec);
         quote! {
             pyo3::class::PyMethodDefType::Method({
                 #wrapper
+                #signature
 
                 pyo3::class::PyMethodDef {
                     ml_name: stringify!(#python_name),
-                    ml_meth: pyo3::class::PyMethodType::PyNoArgsFunction(__wrap),
+                    ml_meth: #meth,
                     ml_flags: pyo3::ffi::METH_NOARGS,
                     ml_doc: #doc,
                 }
             })
         }
     } else {
+        let fastcall_ident = fastcall_wrapper_ident(&wrapper_ident);
+        let meth = quote! {
+            pyo3::class::PyMethodType::PyCFunctionFastWithKeywords(
+                #fastcall_ident,
+                #wrapper_ident,
+            )
+        };
+        let signature = signature::register(&meth, spec);
         quote! {
             pyo3::class::PyMethodDefType::Method({
                 #wrapper
+                #signature
 
                 pyo3::class::PyMethodDef {
                     ml_name: stringify!(#python_name),
-                    ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                    ml_meth: #meth,
                     ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
                     ml_doc: #doc,
                 }
@@ -548,16 +902,25 @@ pub fn impl_py_method_def(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
     }
 }
 
-pub fn impl_py_method_def_new(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+pub fn impl_py_method_def_new(
+    cls: &syn::Type,
+    spec: &FnSpec,
+    wrapper: &TokenStream,
+) -> TokenStream {
+    let wrapper_ident = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &spec.python_name;
     let doc = &spec.doc;
+    let meth = quote! { pyo3::class::PyMethodType::PyNewFunc(#wrapper_ident) };
+    let signature = signature::register(&meth, spec);
     quote! {
         pyo3::class::PyMethodDefType::New({
             #wrapper
+            #signature
+            impl pyo3::singleton::SingletonClassesCannotDefineNew for #cls {}
 
             pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyNewFunc(__wrap),
+                ml_meth: #meth,
                 ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
                 ml_doc: #doc,
             }
@@ -565,16 +928,27 @@ pub fn impl_py_method_def_new(spec: &FnSpec, wrapper: &TokenStream) -> TokenStre
     }
 }
 
-pub fn impl_py_method_def_class(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+pub fn impl_py_method_def_class(
+    cls: &syn::Type,
+    spec: &FnSpec,
+    wrapper: &TokenStream,
+) -> TokenStream {
+    let wrapper_ident = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &spec.python_name;
     let doc = &spec.doc;
+    let fastcall_ident = fastcall_wrapper_ident(&wrapper_ident);
+    let meth = quote! {
+        pyo3::class::PyMethodType::PyCFunctionFastWithKeywords(#fastcall_ident, #wrapper_ident)
+    };
+    let signature = signature::register(&meth, spec);
     quote! {
         pyo3::class::PyMethodDefType::Class({
             #wrapper
+            #signature
 
             pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_meth: #meth,
                 ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS |
                 pyo3::ffi::METH_CLASS,
                 ml_doc: #doc,
@@ -583,16 +957,27 @@ pub fn impl_py_method_def_class(spec: &FnSpec, wrapper: &TokenStream) -> TokenSt
     }
 }
 
-pub fn impl_py_method_def_static(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+pub fn impl_py_method_def_static(
+    cls: &syn::Type,
+    spec: &FnSpec,
+    wrapper: &TokenStream,
+) -> TokenStream {
+    let wrapper_ident = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &spec.python_name;
     let doc = &spec.doc;
+    let fastcall_ident = fastcall_wrapper_ident(&wrapper_ident);
+    let meth = quote! {
+        pyo3::class::PyMethodType::PyCFunctionFastWithKeywords(#fastcall_ident, #wrapper_ident)
+    };
+    let signature = signature::register(&meth, spec);
     quote! {
         pyo3::class::PyMethodDefType::Static({
             #wrapper
+            #signature
 
             pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_meth: #meth,
                 ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS | pyo3::ffi::METH_STATIC,
                 ml_doc: #doc,
             }
@@ -600,16 +985,24 @@ pub fn impl_py_method_def_static(spec: &FnSpec, wrapper: &TokenStream) -> TokenS
     }
 }
 
-pub fn impl_py_method_def_call(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+pub fn impl_py_method_def_call(
+    cls: &syn::Type,
+    spec: &FnSpec,
+    wrapper: &TokenStream,
+) -> TokenStream {
+    let wrapper_ident = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &spec.python_name;
     let doc = &spec.doc;
+    let meth = quote! { pyo3::class::PyMethodType::PyCFunctionWithKeywords(#wrapper_ident) };
+    let signature = signature::register(&meth, spec);
     quote! {
         pyo3::class::PyMethodDefType::Call({
             #wrapper
+            #signature
 
             pyo3::class::PyMethodDef {
                 ml_name: stringify!(#python_name),
-                ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_meth: #meth,
                 ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
                 ml_doc: #doc,
             }
@@ -617,7 +1010,12 @@ pub fn impl_py_method_def_call(spec: &FnSpec, wrapper: &TokenStream) -> TokenStr
     }
 }
 
-pub(crate) fn impl_py_setter_def(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+pub(crate) fn impl_py_setter_def(
+    cls: &syn::Type,
+    spec: &FnSpec,
+    wrapper: &TokenStream,
+) -> TokenStream {
+    let wrapper_ident = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &&spec.python_name;
     let doc = &spec.doc;
 
@@ -627,14 +1025,19 @@ pub(crate) fn impl_py_setter_def(spec: &FnSpec, wrapper: &TokenStream) -> TokenS
 
             pyo3::class::PySetterDef {
                 name: stringify!(#python_name),
-                meth: __wrap,
+                meth: #wrapper_ident,
                 doc: #doc,
             }
         })
     }
 }
 
-pub(crate) fn impl_py_getter_def(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+pub(crate) fn impl_py_getter_def(
+    cls: &syn::Type,
+    spec: &FnSpec,
+    wrapper: &TokenStream,
+) -> TokenStream {
+    let wrapper_ident = utils::wrapper_ident(Some(cls), &spec.python_name);
     let python_name = &&spec.python_name;
     let doc = &spec.doc;
 
@@ -644,7 +1047,7 @@ pub(crate) fn impl_py_getter_def(spec: &FnSpec, wrapper: &TokenStream) -> TokenS
 
             pyo3::class::PyGetterDef {
                 name: stringify!(#python_name),
-                meth: __wrap,
+                meth: #wrapper_ident,
                 doc: #doc,
             }
         })
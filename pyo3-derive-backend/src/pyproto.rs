@@ -4,6 +4,7 @@ use crate::defs;
 use crate::func::impl_method_proto;
 use crate::method::FnSpec;
 use crate::pymethod;
+use crate::utils;
 use proc_macro2::TokenStream;
 use quote::quote;
 use quote::ToTokens;
@@ -78,6 +79,7 @@ fn impl_proto_impl(
                         Err(err) => return err.to_compile_error(),
                     };
                     let meth = pymethod::impl_proto_wrap(ty, &fn_spec);
+                    let wrapper = utils::wrapper_ident(Some(ty), &fn_spec.python_name);
 
                     py_methods.push(quote! {
                         impl #proto for #ty
@@ -88,7 +90,7 @@ fn impl_proto_impl(
 
                                 Some(pyo3::class::PyMethodDef {
                                     ml_name: stringify!(#name),
-                                    ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                                    ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(#wrapper),
                                     ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
                                     ml_doc: ""
                                 })
@@ -0,0 +1,293 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Conversion of the markdown of Rust doc comments to the reStructuredText Python tools expect.
+
+use std::collections::HashMap;
+
+/// The underline characters of reST section titles, for markdown heading levels 1 to 6.
+const HEADING_UNDERLINES: [char; 6] = ['=', '-', '~', '^', '"', '\''];
+
+/// Converts the markdown of a doc comment to reST.
+///
+/// Covers what doc comments commonly use: code fences become `code-block` directives (without
+/// the lines rustdoc hides), inline code becomes an inline literal, links to URLs become reST
+/// links and intra-doc links their text, headings become section titles, and lists are
+/// separated from the preceding paragraph. Everything else is left as it is.
+pub fn markdown_to_rest(markdown: &str) -> String {
+    let mut links = HashMap::new();
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        in_code ^= trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        match link_definition(line) {
+            Some((label, url)) if !in_code => {
+                links.insert(label.to_lowercase(), url.to_string());
+            }
+            _ => lines.push(line),
+        }
+    }
+
+    let mut rest: Vec<String> = Vec::new();
+    // The closing fence and whether the code is Rust
+    let mut fence: Option<(&str, bool)> = None;
+    let mut in_list = false;
+    for line in lines {
+        let trimmed = line.trim_start();
+        if let Some((marker, is_rust)) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+                rest.push(String::new());
+            } else if let Some(line) = code_line(line, is_rust) {
+                rest.push(if line.is_empty() {
+                    line
+                } else {
+                    format!("    {}", line)
+                });
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let marker = &trimmed[..3];
+            let language = code_language(trimmed.trim_start_matches(&marker[..1]));
+            fence = Some((marker, language == "rust"));
+            push_blank(&mut rest);
+            rest.push(format!(".. code-block:: {}", language));
+            rest.push(String::new());
+            in_list = false;
+        } else if let Some((level, title)) = heading(line) {
+            let title = convert_inline(title, &links);
+            let underline = HEADING_UNDERLINES[level - 1]
+                .to_string()
+                .repeat(title.chars().count());
+            push_blank(&mut rest);
+            rest.push(title);
+            rest.push(underline);
+            rest.push(String::new());
+            in_list = false;
+        } else if is_list_item(trimmed) {
+            if !in_list {
+                push_blank(&mut rest);
+                in_list = true;
+            }
+            let indent = &line[..line.len() - trimmed.len()];
+            let item = if trimmed.starts_with("+ ") {
+                // reST doesn't know `+` bullets
+                format!("- {}", &trimmed[2..])
+            } else {
+                trimmed.to_string()
+            };
+            rest.push(format!("{}{}", indent, convert_inline(&item, &links)));
+        } else if line.trim().is_empty() {
+            // Avoid doubled blank lines after the ones added around blocks
+            push_blank(&mut rest);
+            in_list = false;
+        } else {
+            // Continuation lines of a list item are indented, anything else ends the list
+            in_list &= line.starts_with(' ');
+            rest.push(convert_inline(line, &links));
+        }
+    }
+
+    while rest.last().map_or(false, |line| line.is_empty()) {
+        rest.pop();
+    }
+    rest.join("\n")
+}
+
+/// Adds a blank line, unless there is one already.
+fn push_blank(rest: &mut Vec<String>) {
+    if rest.last().map_or(false, |line| !line.is_empty()) {
+        rest.push(String::new());
+    }
+}
+
+/// Parses a reference-style link definition, e.g. `[label]: https://example.com`.
+fn link_definition(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if !line.starts_with('[') {
+        return None;
+    }
+    let end = line.find("]:")?;
+    let url = line[end + 2..].trim();
+    if url.is_empty() || url.contains(' ') {
+        return None;
+    }
+    Some((&line[1..end], url))
+}
+
+/// Maps the info string of a code fence to the language of a `code-block`.
+fn code_language(info: &str) -> &str {
+    // Rustdoc treats unlabeled code and attributes like `ignore` or `no_run` as Rust
+    match info.split(',').next().unwrap_or("").trim() {
+        "" | "rust" | "ignore" | "no_run" | "should_panic" | "compile_fail" | "edition2015"
+        | "edition2018" => "rust",
+        "py" => "python",
+        language => language,
+    }
+}
+
+/// Returns a line of a code block as it is shown, or `None` for lines rustdoc hides.
+fn code_line(line: &str, is_rust: bool) -> Option<String> {
+    let trimmed = line.trim_start();
+    if !is_rust {
+        Some(line.to_string())
+    } else if trimmed == "#" || trimmed.starts_with("# ") {
+        None
+    } else if trimmed.starts_with("##") {
+        // `##` escapes a line starting with `#`
+        let indent = &line[..line.len() - trimmed.len()];
+        Some(format!("{}{}", indent, &trimmed[1..]))
+    } else {
+        Some(line.to_string())
+    }
+}
+
+/// Parses an ATX heading, e.g. `## Examples`, returning its level and title.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > HEADING_UNDERLINES.len() || !line[level..].starts_with(' ') {
+        return None;
+    }
+    let title = line[level..].trim().trim_end_matches('#').trim_end();
+    Some((level, title))
+}
+
+/// Whether a line, without its indentation, starts a bulleted or numbered list item.
+fn is_list_item(trimmed: &str) -> bool {
+    if trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ") {
+        return true;
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && trimmed[digits..].starts_with(". ")
+}
+
+/// Converts the inline code and links of a line.
+fn convert_inline(line: &str, links: &HashMap<String, String>) -> String {
+    let mut rest = String::new();
+    let mut remaining = line;
+    while let Some(start) = remaining.find(|c| c == '`' || c == '[') {
+        rest.push_str(&remaining[..start]);
+        remaining = &remaining[start..];
+        let consumed = if remaining.starts_with('`') {
+            code_span(remaining).map(|(len, code)| {
+                rest.push_str(&code);
+                len
+            })
+        } else {
+            link(remaining, links).map(|(len, link)| {
+                rest.push_str(&link);
+                len
+            })
+        };
+        let len = consumed.unwrap_or_else(|| {
+            // Not code or a link, so copy the run of backticks or the bracket as it is
+            let len = remaining.chars().take_while(|&c| c == '`').count().max(1);
+            rest.push_str(&remaining[..len]);
+            len
+        });
+        remaining = &remaining[len..];
+    }
+    rest.push_str(remaining);
+    rest
+}
+
+/// Parses a code span at the start of `text`, returning its length and its reST rendering.
+fn code_span(text: &str) -> Option<(usize, String)> {
+    let ticks = text.chars().take_while(|&c| c == '`').count();
+    let fence = &text[..ticks];
+    let mut search = ticks;
+    loop {
+        let end = search + text[search..].find(fence)?;
+        let closing = text[end..].chars().take_while(|&c| c == '`').count();
+        if closing == ticks {
+            let code = text[ticks..end].trim();
+            return Some((end + ticks, format!("``{}``", code)));
+        }
+        search = end + closing;
+    }
+}
+
+/// Parses a link at the start of `text`, returning its length and its reST rendering.
+///
+/// Links to URLs become reST links. Intra-doc links like ``[`Foo`]`` can't be resolved outside
+/// rustdoc, so only their text is kept. Brackets which aren't a link return `None`.
+fn link(text: &str, links: &HashMap<String, String>) -> Option<(usize, String)> {
+    let label_end = text.find(']')?;
+    let label = &text[1..label_end];
+    let after = &text[label_end + 1..];
+    let (len, target) = if after.starts_with('(') {
+        let url_end = after.find(')')?;
+        (
+            label_end + 1 + url_end + 1,
+            Some(after[1..url_end].to_string()),
+        )
+    } else if after.starts_with('[') {
+        let ref_end = after.find(']')?;
+        let reference = match &after[1..ref_end] {
+            "" => label,
+            reference => reference,
+        };
+        let target = links.get(&reference.to_lowercase()).cloned();
+        (label_end + 1 + ref_end + 1, target)
+    } else {
+        let target = links.get(&label.to_lowercase()).cloned();
+        if target.is_none() && !label.starts_with('`') {
+            // Plain brackets, e.g. `[1, 2]`
+            return None;
+        }
+        (label_end + 1, target)
+    };
+
+    let rendered = match target {
+        Some(ref url) if url.contains("://") || url.starts_with("mailto:") => {
+            format!("`{} <{}>`__", label.replace('`', ""), url)
+        }
+        _ => convert_inline(label, links),
+    };
+    Some((len, rendered))
+}
+
+#[cfg(test)]
+mod test {
+    use super::markdown_to_rest;
+
+    #[test]
+    fn test_code_blocks() {
+        let markdown = "Adds two numbers.\n```\n# use foo::add;\nassert_eq!(add(1, 2), 3);\n\n## not hidden\n```\n```python\n# a comment\nadd(1, 2)\n```";
+        assert_eq!(
+            markdown_to_rest(markdown),
+            "Adds two numbers.\n\n.. code-block:: rust\n\n    assert_eq!(add(1, 2), 3);\n\n    # not hidden\n\n.. code-block:: python\n\n    # a comment\n    add(1, 2)"
+        );
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            markdown_to_rest("Returns `None`, or ``a `b` c`` if [`Foo::bar`] is set"),
+            "Returns ``None``, or ``a `b` c`` if ``Foo::bar`` is set"
+        );
+        assert_eq!(
+            markdown_to_rest("Unmatched ` and [1, 2] stay"),
+            "Unmatched ` and [1, 2] stay"
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let markdown = "See [the docs](https://example.com/docs), [PEP 8][pep8], [numpy] and [Foo](crate::Foo).\n\n[pep8]: https://www.python.org/dev/peps/pep-0008/\n[Numpy]: https://numpy.org";
+        assert_eq!(
+            markdown_to_rest(markdown),
+            "See `the docs <https://example.com/docs>`__, `PEP 8 <https://www.python.org/dev/peps/pep-0008/>`__, `numpy <https://numpy.org>`__ and Foo."
+        );
+    }
+
+    #[test]
+    fn test_headings_and_lists() {
+        let markdown = "Summary.\n# Arguments\nThe arguments:\n- `a`: the first\n  one\n+ `b`\n\nDone.\n## Panics ##\n1. never";
+        assert_eq!(
+            markdown_to_rest(markdown),
+            "Summary.\n\nArguments\n=========\n\nThe arguments:\n\n- ``a``: the first\n  one\n- ``b``\n\nDone.\n\nPanics\n------\n\n1. never"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Generates the records of `pyo3::signature`.
+
+use crate::method::FnSpec;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+
+/// Generates the registration of the signature of the function implemented by `meth`, a
+/// `pyo3::class::PyMethodType`.
+pub fn register(meth: &TokenStream, spec: &FnSpec) -> TokenStream {
+    let name = spec.python_name.unraw().to_string();
+    let mut params = Vec::new();
+    for arg in spec.args.iter().filter(|arg| !arg.py) {
+        let arg_name = arg.name.unraw().to_string();
+        let is_variadic = spec.is_args(arg.name) || spec.is_kwargs(arg.name);
+        let kind = if spec.is_args(arg.name) {
+            quote! { VarPositional }
+        } else if spec.is_kwargs(arg.name) {
+            quote! { VarKeyword }
+        } else if spec.is_kw_only(arg.name) {
+            quote! { KeywordOnly }
+        } else if spec.is_pos_only(arg.name) {
+            quote! { PositionalOnly }
+        } else {
+            quote! { PositionalOrKeyword }
+        };
+        let default = match spec.python_default(arg) {
+            Some(ref default) if !is_variadic => quote! { Some(#default) },
+            _ => quote! { None },
+        };
+        params.push(quote! {
+            pyo3::signature::Parameter {
+                name: #arg_name,
+                kind: pyo3::signature::ParameterKind::#kind,
+                default: #default,
+            }
+        });
+    }
+    quote! {
+        pyo3::inventory::submit! {
+            #![crate = pyo3]
+            pyo3::signature::FunctionSignature {
+                meth: #meth,
+                name: #name,
+                params: &[#(#params),*],
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Code generation for the `.pyi` stubs recorded with the `stubs` feature
+
+use crate::method::{FnSpec, FnType};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+
+/// Generates an expression evaluating to a `fn() -> String` which renders the stub of a
+/// function or method, with the type hints of its arguments and return type.
+pub fn fn_stub(cls: Option<&syn::Type>, spec: &FnSpec) -> TokenStream {
+    let name = spec.python_name.to_string();
+    let hint = |ty: &syn::Type| {
+        let ty = nameable_type(ty, cls);
+        quote! { <#ty as pyo3::stubs::PyStubType>::type_hint() }
+    };
+
+    let mut params = Vec::new();
+    match (&spec.tp, cls) {
+        (_, None) | (FnType::FnStatic, _) => {}
+        (FnType::FnClass, _) | (FnType::FnNew, _) => params.push(quote! { "cls".to_string() }),
+        _ => params.push(quote! { "self".to_string() }),
+    }
+
+    let (decorator, def_name, ret) = match spec.tp {
+        FnType::Getter => ("@property\n".to_string(), name.clone(), hint(&spec.output)),
+        FnType::Setter => {
+            if let Some(value) = spec.args.last() {
+                let value = hint(value.ty);
+                params.push(quote! { format!("value: {}", #value) });
+            }
+            (
+                format!("@{}.setter\n", name),
+                name.clone(),
+                quote! { "None".to_string() },
+            )
+        }
+        _ => {
+            let mut kw_only_marker = !spec.accept_args();
+            for arg in spec.args.iter().filter(|arg| !arg.py) {
+                let arg_name = arg.name.unraw().to_string();
+                if spec.is_args(arg.name) {
+                    params.push(quote! { format!("*{}", #arg_name) });
+                } else if spec.is_kwargs(arg.name) {
+                    params.push(quote! { format!("**{}", #arg_name) });
+                } else {
+                    if spec.is_kw_only(arg.name) && kw_only_marker {
+                        params.push(quote! { "*".to_string() });
+                        kw_only_marker = false;
+                    }
+                    let default =
+                        if arg.optional.is_some() || spec.default_value(arg.name).is_some() {
+                            " = ..."
+                        } else {
+                            ""
+                        };
+                    let arg_hint = hint(arg.ty);
+                    params.push(quote! { format!("{}: {}{}", #arg_name, #arg_hint, #default) });
+                    if spec.ends_pos_only(arg.name) {
+                        params.push(quote! { "/".to_string() });
+                    }
+                }
+            }
+
+            let decorator = match spec.tp {
+                FnType::FnClass => "@classmethod\n",
+                FnType::FnStatic => "@staticmethod\n",
+                _ => "",
+            };
+            let def_name = match spec.tp {
+                FnType::FnNew => "__new__".to_string(),
+                FnType::FnCall => "__call__".to_string(),
+                _ => name.clone(),
+            };
+            let ret = match (&spec.tp, &spec.output, cls) {
+                // `#[new]` may initialize a `PyRawObject` instead of returning the instance
+                (FnType::FnNew, _, Some(cls)) => hint(cls),
+                (_, syn::Type::Infer(_), _) => quote! { "None".to_string() },
+                (_, output, _) => hint(output),
+            };
+            (decorator.to_string(), def_name, ret)
+        }
+    };
+
+    let def = if spec.is_async { "async def" } else { "def" };
+    let template = format!("{}{} {}({{}}) -> {{}}: ...", decorator, def, def_name);
+
+    quote! {{
+        fn __stub() -> String {
+            let params: Vec<String> = vec![#(#params),*];
+            format!(#template, params.join(", "), #ret)
+        }
+        __stub
+    }}
+}
+
+/// Generates a `pyo3::stubs::MemberStub` for a method or property of `cls`.
+pub fn member_stub(cls: &syn::Type, spec: &FnSpec) -> TokenStream {
+    if spec.tp == FnType::ClassAttribute {
+        return class_attribute_stub(cls, &spec.python_name, &spec.output);
+    }
+    let name = spec.python_name.to_string();
+    let kind = match spec.tp {
+        FnType::Getter => quote! { pyo3::stubs::MemberKind::Getter },
+        FnType::Setter => quote! { pyo3::stubs::MemberKind::Setter },
+        _ => quote! { pyo3::stubs::MemberKind::Method },
+    };
+    let stub = fn_stub(Some(cls), spec);
+    quote! {
+        pyo3::stubs::MemberStub {
+            name: #name,
+            kind: #kind,
+            stub: #stub,
+        }
+    }
+}
+
+/// Generates a `pyo3::stubs::MemberStub` for a `#[classattr]` of `cls` of type `ty`.
+pub fn class_attribute_stub(cls: &syn::Type, name: &syn::Ident, ty: &syn::Type) -> TokenStream {
+    let name = name.unraw().to_string();
+    let ty = nameable_type(ty, Some(cls));
+    let template = format!("{}: typing.ClassVar[{{}}]", name);
+    quote! {
+        pyo3::stubs::MemberStub {
+            name: #name,
+            kind: pyo3::stubs::MemberKind::ClassAttribute,
+            stub: {
+                fn __stub() -> String {
+                    format!(#template, <#ty as pyo3::stubs::PyStubType>::type_hint())
+                }
+                __stub
+            },
+        }
+    }
+}
+
+/// Generates the registration of the members of `cls`.
+pub fn class_stub(cls: &syn::Type, members: &[TokenStream]) -> TokenStream {
+    quote! {
+        pyo3::inventory::submit! {
+            #![crate = pyo3]
+            pyo3::stubs::ClassStub {
+                type_object: pyo3::stubs::type_object_ptr::<#cls>,
+                members: &[#(#members),*],
+            }
+        }
+    }
+}
+
+/// Rewrites `ty` so it can be named outside of the function it's used in: lifetimes become
+/// `'static` and `Self` becomes `cls`.
+fn nameable_type(ty: &syn::Type, cls: Option<&syn::Type>) -> syn::Type {
+    let mut ty = ty.clone();
+    rewrite_type(&mut ty, cls);
+    ty
+}
+
+fn rewrite_type(ty: &mut syn::Type, cls: Option<&syn::Type>) {
+    match ty {
+        syn::Type::Reference(reference) => {
+            reference.lifetime = None;
+            rewrite_type(&mut reference.elem, cls);
+        }
+        syn::Type::Path(path) => {
+            if let Some(cls) = cls {
+                if path.qself.is_none() && path.path.is_ident("Self") {
+                    *ty = cls.clone();
+                    return;
+                }
+            }
+            for segment in path.path.segments.iter_mut() {
+                if let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments {
+                    for arg in args.args.iter_mut() {
+                        match arg {
+                            syn::GenericArgument::Lifetime(lifetime) => {
+                                *lifetime = syn::Lifetime::new("'static", lifetime.span());
+                            }
+                            syn::GenericArgument::Type(ty) => rewrite_type(ty, cls),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        syn::Type::Tuple(tuple) => {
+            for elem in tuple.elems.iter_mut() {
+                rewrite_type(elem, cls);
+            }
+        }
+        syn::Type::Slice(slice) => rewrite_type(&mut slice.elem, cls),
+        syn::Type::Array(array) => rewrite_type(&mut array.elem, cls),
+        syn::Type::Paren(paren) => rewrite_type(&mut paren.elem, cls),
+        syn::Type::Group(group) => rewrite_type(&mut group.elem, cls),
+        syn::Type::ImplTrait(_) => *ty = syn::parse_quote! { pyo3::PyObject },
+        _ => {}
+    }
+}
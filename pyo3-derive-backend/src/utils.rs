@@ -1,13 +1,33 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
+use crate::rest;
 use proc_macro2::Span;
 use proc_macro2::TokenStream;
+use quote::quote;
 use std::fmt::Display;
+use syn::ext::IdentExt;
 
 pub fn print_err(msg: String, t: TokenStream) {
     println!("Error: {} in '{}'", msg, t.to_string());
 }
 
+/// Returns the name of the generated wrapper of a function, e.g. `__pyo3_wrap_MyClass_method`.
+///
+/// Wrappers are the entry points called by Python, so their names show up in native profilers
+/// and debuggers.
+pub fn wrapper_ident(cls: Option<&syn::Type>, name: &syn::Ident) -> syn::Ident {
+    let name = name.unraw();
+    let cls = match cls {
+        Some(syn::Type::Path(path)) => path.path.segments.last().map(|segment| &segment.ident),
+        _ => None,
+    };
+    let ident = match cls {
+        Some(cls) => format!("__pyo3_wrap_{}_{}", cls.unraw(), name),
+        None => format!("__pyo3_wrap_{}", name),
+    };
+    syn::Ident::new(&ident, Span::call_site())
+}
+
 /// Check if the given type `ty` is `pyo3::Python`.
 pub fn if_type_is_python(ty: &syn::Type) -> bool {
     match ty {
@@ -93,6 +113,17 @@ pub fn parse_text_signature_attrs<T: Display + quote::ToTokens + ?Sized>(
     Ok(text_signature)
 }
 
+/// Whether the doc comments start with a text signature in CPython's format, i.e. a line like
+/// `name(a, b)` followed by a `--` line.
+pub fn has_doc_text_signature(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+    let doc = get_doc(attrs, None, false)?.value();
+    let mut lines = doc.lines().map(str::trim);
+    Ok(match (lines.next(), lines.next()) {
+        (Some(signature), Some("--")) => signature.contains('(') && signature.ends_with(')'),
+        _ => false,
+    })
+}
+
 // FIXME(althonos): not sure the docstring formatting is on par here.
 pub fn get_doc(
     attrs: &[syn::Attribute],
@@ -109,6 +140,7 @@ pub fn get_doc(
         doc.push_str("\n--\n\n");
     }
 
+    let mut body = String::new();
     let mut separator = "";
     let mut first = true;
 
@@ -121,11 +153,11 @@ pub fn get_doc(
                         span = litstr.span();
                     }
                     let d = litstr.value();
-                    doc.push_str(separator);
+                    body.push_str(separator);
                     if d.starts_with(' ') {
-                        doc.push_str(&d[1..d.len()]);
+                        body.push_str(&d[1..d.len()]);
                     } else {
-                        doc.push_str(&d);
+                        body.push_str(&d);
                     };
                     separator = "\n";
                 } else {
@@ -135,9 +167,142 @@ pub fn get_doc(
         }
     }
 
+    if cfg!(feature = "rest-docstrings") {
+        doc.push_str(&rest::markdown_to_rest(&body));
+    } else {
+        doc.push_str(&body);
+    }
+
     if null_terminated {
         doc.push('\0');
     }
 
     Ok(syn::LitStr::new(&doc, span))
 }
+
+/// Parses and removes `final` from `#[pyo3(...)]`, which marks a method that Python subclasses
+/// must not override.
+pub fn parse_final_attr(attrs: &mut Vec<syn::Attribute>) -> bool {
+    parse_flag_attr(attrs, "final")
+}
+
+/// Parses and removes `multi_phase` from `#[pyo3(...)]`, which makes a `#[pymodule]` use
+/// multi-phase initialization.
+pub fn parse_multi_phase_attr(attrs: &mut Vec<syn::Attribute>) -> bool {
+    parse_flag_attr(attrs, "multi_phase")
+}
+
+/// Parses and removes `#[pyo3(state = "Type")]`, the type of the per-module state of a
+/// multi-phase `#[pymodule]`.
+pub fn parse_module_state_attr(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Option<syn::Type>> {
+    match parse_value_attr(attrs, "state")? {
+        Some(syn::Lit::Str(lit)) => Ok(Some(lit.parse()?)),
+        Some(lit) => Err(syn::Error::new_spanned(
+            lit,
+            "Expected a type like \"State\"",
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Parses and removes `doc_index` from `#[pyo3(...)]`, which makes a `#[pymodule]` append an
+/// index of its classes and functions to its docstring.
+pub fn parse_doc_index_attr(attrs: &mut Vec<syn::Attribute>) -> bool {
+    parse_flag_attr(attrs, "doc_index")
+}
+
+/// Parses and removes the flag `name` from `#[pyo3(...)]`, returning whether it was present.
+fn parse_flag_attr(attrs: &mut Vec<syn::Attribute>, name: &str) -> bool {
+    let mut is_set = false;
+    let mut new_attrs = Vec::new();
+
+    for mut attr in attrs.drain(..) {
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) if list.path.is_ident("pyo3") => list,
+            _ => {
+                new_attrs.push(attr);
+                continue;
+            }
+        };
+        let len = list.nested.len();
+        let nested: Vec<_> = list
+            .nested
+            .into_iter()
+            .filter(|meta| match meta {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) => !path.is_ident(name),
+                _ => true,
+            })
+            .collect();
+        is_set |= nested.len() != len;
+        if !nested.is_empty() {
+            attr.tokens = quote! { (#(#nested),*) };
+            new_attrs.push(attr);
+        }
+    }
+
+    *attrs = new_attrs;
+    is_set
+}
+
+/// Parses and removes `#[pyo3(min_python = "3.8")]`, returning the `(major, minor)` version.
+pub fn parse_min_python_attr(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Option<(u8, u8)>> {
+    match parse_value_attr(attrs, "min_python")? {
+        Some(lit) => Ok(Some(parse_python_version(&lit)?)),
+        None => Ok(None),
+    }
+}
+
+/// Parses and removes the key `name` from `#[pyo3(...)]`, returning its value. Other keys are
+/// kept for the other parsers.
+fn parse_value_attr(attrs: &mut Vec<syn::Attribute>, name: &str) -> syn::Result<Option<syn::Lit>> {
+    let mut value = None;
+    let mut new_attrs = Vec::new();
+
+    for mut attr in attrs.drain(..) {
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) if list.path.is_ident("pyo3") => list,
+            _ => {
+                new_attrs.push(attr);
+                continue;
+            }
+        };
+        let mut nested = Vec::new();
+        for meta in list.nested.into_iter() {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) if nv.path.is_ident(name) => {
+                    if value.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            nv,
+                            format!("{} can not be specified multiple times", name),
+                        ));
+                    }
+                    value = Some(nv.lit.clone());
+                }
+                meta => nested.push(meta),
+            }
+        }
+        if !nested.is_empty() {
+            attr.tokens = quote! { (#(#nested),*) };
+            new_attrs.push(attr);
+        }
+    }
+
+    *attrs = new_attrs;
+    Ok(value)
+}
+
+fn parse_python_version(lit: &syn::Lit) -> syn::Result<(u8, u8)> {
+    let err = || syn::Error::new_spanned(lit, "Expected a version like \"3.8\"");
+    let value = match lit {
+        syn::Lit::Str(s) => s.value(),
+        _ => return Err(err()),
+    };
+    let mut parts = value.split('.');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(major), Some(minor), None) => Ok((
+            major.parse().map_err(|_| err())?,
+            minor.parse().map_err(|_| err())?,
+        )),
+        _ => Err(err()),
+    }
+}
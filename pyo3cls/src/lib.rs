@@ -1,11 +1,12 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
-//! This crate declares only the proc macro attributes, as a crate defining proc macro attributes
+//! This crate declares only the proc macro attributes and derives, as a crate defining proc macro attributes
 //! must not contain any other public items.
 
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use pyo3_derive_backend::{
-    build_py_class, build_py_function, build_py_methods, build_py_proto, get_doc,
+    build_derive_from_pyobject, build_py_class, build_py_enum, build_py_function, build_py_methods,
+    build_py_proto, get_doc, parse_doc_index_attr, parse_module_state_attr, parse_multi_phase_attr,
     process_functions_in_module, py_init, PyClassArgs, PyFunctionAttr,
 };
 use quote::quote;
@@ -24,13 +25,27 @@ pub fn pymodule(attr: TokenStream, input: TokenStream) -> TokenStream {
     };
 
     process_functions_in_module(&mut ast);
+    let multi_phase = parse_multi_phase_attr(&mut ast.attrs);
+    let doc_index = parse_doc_index_attr(&mut ast.attrs);
+    let state = match parse_module_state_attr(&mut ast.attrs) {
+        Ok(Some(_)) if !multi_phase => {
+            return syn::Error::new_spanned(
+                &ast.sig.ident,
+                "#[pyo3(state = \"...\")] requires #[pyo3(multi_phase)]",
+            )
+            .to_compile_error()
+            .into()
+        }
+        Ok(state) => state,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     let doc = match get_doc(&ast.attrs, None, false) {
         Ok(doc) => doc,
         Err(err) => return err.to_compile_error().into(),
     };
 
-    let expanded = py_init(&ast.sig.ident, &modname, doc);
+    let expanded = py_init(&ast.sig.ident, &modname, doc, multi_phase, state, doc_index);
 
     quote!(
         #ast
@@ -53,9 +68,17 @@ pub fn pyproto(_: TokenStream, input: TokenStream) -> TokenStream {
 
 #[proc_macro_attribute]
 pub fn pyclass(attr: TokenStream, input: TokenStream) -> TokenStream {
-    let mut ast = parse_macro_input!(input as syn::ItemStruct);
+    let mut ast = parse_macro_input!(input as syn::Item);
     let args = parse_macro_input!(attr as PyClassArgs);
-    let expanded = build_py_class(&mut ast, &args).unwrap_or_else(|e| e.to_compile_error());
+    let expanded = match ast {
+        syn::Item::Struct(ref mut class) => build_py_class(class, &args),
+        syn::Item::Enum(ref mut enum_) => build_py_enum(enum_, &args),
+        _ => Err(syn::Error::new_spanned(
+            &ast,
+            "#[pyclass] can only be used with structs and fieldless enums",
+        )),
+    }
+    .unwrap_or_else(|e| e.to_compile_error());
 
     quote!(
         #ast
@@ -89,3 +112,11 @@ pub fn pyfunction(attr: TokenStream, input: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+#[proc_macro_derive(FromPyObject, attributes(pyo3))]
+pub fn derive_from_pyobject(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    build_derive_from_pyobject(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
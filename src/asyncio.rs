@@ -0,0 +1,563 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Integration of Rust futures and file descriptors with asyncio event loops.
+//!
+//! [into_coroutine] turns a Rust `Future` into a Python coroutine, which is how `async fn`s
+//! marked with `#[pyfunction]`, or in `#[pymethods]` taking `&self`, `cls: Py<PyType>` or nothing,
+//! are exposed to Python.
+//! The future is polled by the event loop that awaits the coroutine, and is dropped when the
+//! awaiting task is cancelled.
+//!
+//! Applications that run their own event loop, such as GUI toolkits, can call [drive_python]
+//! periodically (e.g. from an idle handler or a timer) to keep asyncio callbacks, Python timers
+//! and signal handlers running without handing the main thread over to Python.
+//!
+//! Instead of running a second reactor next to the Python event loop, Rust networking code can
+//! register its sockets with the loop through [add_reader] and [add_writer], which call a Rust
+//! closure (holding the GIL) whenever the file descriptor becomes ready. [wait_readable] and
+//! [wait_writable] return futures that Python code, or a coroutine driving Rust code, can
+//! `await`.
+//!
+//! This relies on `loop.add_reader()` and `loop.add_writer()`, which are not available on the
+//! default `ProactorEventLoop` on Windows; use a `SelectorEventLoop` there.
+//!
+//! # Examples
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//! use pyo3::wrap_pyfunction;
+//!
+//! #[pyfunction]
+//! async fn add(a: i32, b: i32) -> i32 {
+//!     a + b
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let add = wrap_pyfunction!(add)(py);
+//! pyo3::py_run!(py, add, "import asyncio; assert asyncio.run(add(1, 2)) == 3");
+//! ```
+//!
+//! ```
+//! # #[cfg(unix)] {
+//! use pyo3::asyncio::wait_readable;
+//! use pyo3::prelude::*;
+//! use std::io::Write;
+//! use std::os::unix::io::AsRawFd;
+//! use std::os::unix::net::UnixStream;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let event_loop = py.import("asyncio").unwrap().call0("new_event_loop").unwrap();
+//! let (rust_end, mut other_end) = UnixStream::pair().unwrap();
+//!
+//! let readable = wait_readable(event_loop, rust_end.as_raw_fd()).unwrap();
+//! other_end.write_all(b"ping").unwrap();
+//! event_loop.call_method1("run_until_complete", (readable,)).unwrap();
+//! event_loop.call_method0("close").unwrap();
+//! # }
+//! ```
+
+use crate::cache::PyStatic;
+use crate::callback::{cb_convert, PyObjectCallbackConverter};
+use crate::derive_utils::IntoPyResult;
+use crate::err::PyResult;
+use crate::exceptions::asyncio::CancelledError;
+use crate::exceptions::RuntimeError;
+use crate::ffi;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::internal_tricks::{bound_function, capsule_value, into_capsule};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyModule, PyTuple};
+use crate::{IntoPy, PyObject, Python, ToPyObject};
+use std::cell::RefCell;
+use std::future::Future;
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+#[cfg(windows)]
+use std::os::windows::io::RawSocket as RawFd;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+use std::time::Duration;
+
+/// Returns the event loop running in the current thread, raising `RuntimeError` if there is
+/// none.
+pub fn get_running_loop(py: Python) -> PyResult<&PyAny> {
+    py.import("asyncio")?.call0("get_running_loop")
+}
+
+/// Calls `callback` whenever `fd` is ready for reading, until the returned watcher is dropped.
+///
+/// Errors returned by `callback` are passed to the event loop's exception handler. The
+/// callback must be `Send`, as the event loop may drop it on any thread.
+pub fn add_reader<F>(event_loop: &PyAny, fd: RawFd, callback: F) -> PyResult<FdWatcher>
+where
+    F: FnMut(Python) -> PyResult<()> + Send + 'static,
+{
+    FdWatcher::new(event_loop, fd, Interest::Read, callback)
+}
+
+/// Calls `callback` whenever `fd` is ready for writing, until the returned watcher is dropped.
+///
+/// Errors returned by `callback` are passed to the event loop's exception handler. The
+/// callback must be `Send`, as the event loop may drop it on any thread.
+pub fn add_writer<F>(event_loop: &PyAny, fd: RawFd, callback: F) -> PyResult<FdWatcher>
+where
+    F: FnMut(Python) -> PyResult<()> + Send + 'static,
+{
+    FdWatcher::new(event_loop, fd, Interest::Write, callback)
+}
+
+/// Returns an `asyncio.Future` of `event_loop` which completes once `fd` is ready for reading.
+///
+/// Cancelling the future stops watching `fd`. Like with `loop.add_reader()`, only one callback
+/// can wait for a file descriptor at a time.
+pub fn wait_readable(event_loop: &PyAny, fd: RawFd) -> PyResult<&PyAny> {
+    wait_ready(event_loop, fd, Interest::Read)
+}
+
+/// Returns an `asyncio.Future` of `event_loop` which completes once `fd` is ready for writing.
+///
+/// Cancelling the future stops watching `fd`. Like with `loop.add_writer()`, only one callback
+/// can wait for a file descriptor at a time.
+pub fn wait_writable(event_loop: &PyAny, fd: RawFd) -> PyResult<&PyAny> {
+    wait_ready(event_loop, fd, Interest::Write)
+}
+
+/// Runs the work Python has queued for the current thread, for about `budget`.
+///
+/// This runs pending calls and signal handlers, then runs the current thread's asyncio event
+/// loop for `budget` with [drive_event_loop]; threads without an event loop only run the pending
+/// calls. A zero budget runs the callbacks which are ready without waiting. A callback that runs
+/// longer than `budget` can't be interrupted.
+pub fn drive_python(py: Python, budget: Duration) -> PyResult<()> {
+    crate::err::error_on_minusone(py, unsafe { ffi::Py_MakePendingCalls() })?;
+    let policy = py.import("asyncio")?.call0("get_event_loop_policy")?;
+    let event_loop = match policy.call_method0("get_event_loop") {
+        Ok(event_loop) => event_loop,
+        // Raised by threads other than the main thread that have no event loop
+        Err(ref err) if err.is_instance::<RuntimeError>(py) => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    if event_loop.call_method0("is_closed")?.is_true()? {
+        return Ok(());
+    }
+    drive_event_loop(event_loop, budget)
+}
+
+/// Runs `event_loop`, which must not be running, for about `budget`.
+///
+/// The loop runs its callbacks as they become ready, and waits for I/O and timers in between
+/// instead of polling. A zero budget runs a single iteration of the loop, which runs the
+/// callbacks that are ready and whose timers have expired without waiting.
+pub fn drive_event_loop(event_loop: &PyAny, budget: Duration) -> PyResult<()> {
+    // `stop()` makes `run_forever()` return after the iteration it is called in
+    let stop = event_loop.getattr("stop")?;
+    let timer = event_loop.call_method1("call_later", (budget.as_secs_f64(), stop))?;
+    let result = event_loop.call_method0("run_forever");
+    // Not called yet if the loop stopped because a callback raised
+    timer.call_method0("cancel")?;
+    result.map(drop)
+}
+
+/// Wraps `future` in a Python coroutine, which returns the output of the future when awaited.
+///
+/// The future is polled with the GIL held by the event loop running the coroutine, so it must not
+/// block; send blocking work to another thread and await its result instead. The future is dropped
+/// as soon as the awaiting task is cancelled, and a future returning `asyncio.CancelledError`
+/// cancels the task. Like other coroutines, the returned coroutine can only be awaited once.
+pub fn into_coroutine<F, T, R>(py: Python, future: F) -> PyResult<PyObject>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: IntoPyResult<R>,
+    R: IntoPy<PyObject>,
+{
+    let future: BoxFuture = Box::pin(async move {
+        let result = future.await.into_py_result();
+        // Futures are only polled with the GIL held
+        let py = unsafe { Python::assume_gil_acquired() };
+        result.map(|result| result.into_py(py))
+    });
+    let mut future = Some(future);
+    let start = closure_into_py(py, move |py, _| match future.take() {
+        Some(future) => Task::start(py, future),
+        None => Err(RuntimeError::py_err("The coroutine was already awaited")),
+    })?;
+
+    static COROUTINE: PyStatic = PyStatic::new();
+    let coroutine = COROUTINE.get_or_init(py, || {
+        PyModule::from_code(py, COROUTINE_CODE, "pyo3_asyncio.py", "pyo3_asyncio")
+            .and_then(|module| module.get("coroutine"))
+            .expect("failed to define the coroutine helper")
+            .into()
+    });
+    coroutine.call1((start,)).map(Into::into)
+}
+
+const COROUTINE_CODE: &str = "async def coroutine(start):\n    return await start()\n";
+
+type BoxFuture = Pin<Box<dyn Future<Output = PyResult<PyObject>> + Send>>;
+
+/// A Rust future driven by an asyncio event loop, completing an `asyncio.Future` of that loop.
+struct Task {
+    future: Mutex<Option<BoxFuture>>,
+    event_loop: PyObject,
+    result: PyObject,
+    scheduled: AtomicBool,
+}
+
+impl Task {
+    fn start(py: Python, future: BoxFuture) -> PyResult<PyObject> {
+        let event_loop = get_running_loop(py)?;
+        let result = event_loop.call_method0("create_future")?;
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(future)),
+            event_loop: event_loop.into(),
+            result: result.into(),
+            scheduled: AtomicBool::new(false),
+        });
+
+        // Drop the Rust future when the awaiting task is cancelled
+        let cancelled = task.clone();
+        let on_done = closure_into_py(py, move |py, _| {
+            cancelled.future.lock().unwrap().take();
+            Ok(py.None())
+        })?;
+        result.call_method1("add_done_callback", (on_done,))?;
+
+        task.schedule(py)?;
+        Ok(result.into())
+    }
+
+    /// Schedules a call to [Task::poll] with the event loop, unless one is pending already.
+    fn schedule(self: &Arc<Self>, py: Python) -> PyResult<()> {
+        if self.scheduled.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let task = self.clone();
+        let poll = closure_into_py(py, move |py, _| {
+            task.poll(py)?;
+            Ok(py.None())
+        })?;
+        self.event_loop
+            .call_method1(py, "call_soon_threadsafe", (poll,))?;
+        Ok(())
+    }
+
+    fn poll(self: &Arc<Self>, py: Python) -> PyResult<()> {
+        self.scheduled.store(false, Ordering::SeqCst);
+        let result = {
+            let mut future = self.future.lock().unwrap();
+            let waker = Waker::from(self.clone());
+            let poll = match future.as_mut() {
+                Some(future) => future.as_mut().poll(&mut Context::from_waker(&waker)),
+                None => return Ok(()),
+            };
+            match poll {
+                Poll::Ready(result) => {
+                    *future = None;
+                    result
+                }
+                Poll::Pending => return Ok(()),
+            }
+        };
+
+        let future = self.result.as_ref(py);
+        if future.call_method0("done")?.is_true()? {
+            return Ok(());
+        }
+        match result {
+            Ok(value) => future.call_method1("set_result", (value,))?,
+            Err(ref err) if err.is_instance::<CancelledError>(py) => {
+                future.call_method0("cancel")?
+            }
+            Err(err) => future.call_method1("set_exception", (err.to_object(py),))?,
+        };
+        Ok(())
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        if unsafe { ffi::PyGILState_Check() } == 1 {
+            let py = unsafe { Python::assume_gil_acquired() };
+            // Fails if the event loop was closed, in which case the task can't complete anyway
+            let _ = self.schedule(py);
+            return;
+        }
+        // Without threads (e.g. in Pyodide) nothing else can hold the locks, and there is no
+        // waker thread to hand the task to
+        #[cfg(target_os = "emscripten")]
+        {
+            let gil = Python::acquire_gil();
+            let _ = self.schedule(gil.python());
+        }
+        #[cfg(not(target_os = "emscripten"))]
+        wake_from_thread(self);
+    }
+}
+
+/// Hands a task woken by a thread that doesn't hold the GIL to the waker thread.
+#[cfg(not(target_os = "emscripten"))]
+fn wake_from_thread(task: Arc<Task>) {
+    // Wakers are often called with locks held that the polling thread may need, so the GIL
+    // must not be acquired here
+    let mut sender = WAKE_SENDER.lock();
+    let sender = sender.get_or_insert_with(|| {
+        let (sender, receiver) = mpsc::channel::<Arc<Task>>();
+        thread::Builder::new()
+            .name("pyo3-asyncio-waker".to_string())
+            .spawn(move || {
+                for task in receiver {
+                    let gil = Python::acquire_gil();
+                    let _ = task.schedule(gil.python());
+                }
+            })
+            .expect("failed to spawn the asyncio waker thread");
+        sender
+    });
+    let _ = sender.send(task);
+}
+
+/// Schedules tasks woken by threads that don't hold the GIL.
+#[cfg(not(target_os = "emscripten"))]
+static WAKE_SENDER: spin::Mutex<Option<mpsc::Sender<Arc<Task>>>> = spin::Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Interest {
+    Read,
+    Write,
+}
+
+impl Interest {
+    fn add_method(self) -> &'static str {
+        match self {
+            Interest::Read => "add_reader",
+            Interest::Write => "add_writer",
+        }
+    }
+
+    fn remove_method(self) -> &'static str {
+        match self {
+            Interest::Read => "remove_reader",
+            Interest::Write => "remove_writer",
+        }
+    }
+}
+
+/// A Rust callback registered with an event loop by [add_reader] or [add_writer].
+///
+/// Dropping the watcher unregisters the callback, acquiring the GIL if necessary.
+#[must_use]
+pub struct FdWatcher {
+    event_loop: PyObject,
+    fd: RawFd,
+    interest: Interest,
+}
+
+impl FdWatcher {
+    fn new<F>(event_loop: &PyAny, fd: RawFd, interest: Interest, mut callback: F) -> PyResult<Self>
+    where
+        F: FnMut(Python) -> PyResult<()> + Send + 'static,
+    {
+        let py = event_loop.py();
+        let callback = closure_into_py(py, move |py, _| callback(py).map(|()| py.None()))?;
+        event_loop.call_method1(interest.add_method(), (fd, callback))?;
+        Ok(FdWatcher {
+            event_loop: event_loop.into(),
+            fd,
+            interest,
+        })
+    }
+
+    /// Returns the watched file descriptor.
+    pub fn fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for FdWatcher {
+    fn drop(&mut self) {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // Fails if the loop was closed already, which also removed the callback
+        let _ = self
+            .event_loop
+            .call_method1(py, self.interest.remove_method(), (self.fd,));
+    }
+}
+
+fn wait_ready(event_loop: &PyAny, fd: RawFd, interest: Interest) -> PyResult<&PyAny> {
+    let py = event_loop.py();
+    let future = event_loop.call_method0("create_future")?;
+
+    let (loop_obj, future_obj): (PyObject, PyObject) = (event_loop.into(), future.into());
+    let on_ready = closure_into_py(py, move |py, _| {
+        loop_obj.call_method1(py, interest.remove_method(), (fd,))?;
+        let future = future_obj.as_ref(py);
+        if !future.call_method0("done")?.is_true()? {
+            future.call_method1("set_result", (py.None(),))?;
+        }
+        Ok(py.None())
+    })?;
+    event_loop.call_method1(interest.add_method(), (fd, on_ready))?;
+
+    // Stop watching when the future is cancelled
+    let loop_obj: PyObject = event_loop.into();
+    let on_done = closure_into_py(py, move |py, _| {
+        loop_obj.call_method1(py, interest.remove_method(), (fd,))?;
+        Ok(py.None())
+    })?;
+    future.call_method1("add_done_callback", (on_done,))?;
+    Ok(future)
+}
+
+const CAPSULE_NAME: &[u8] = b"pyo3.asyncio\0";
+
+type Closure = RefCell<Box<dyn FnMut(Python, &PyTuple) -> PyResult<PyObject> + Send>>;
+
+/// Wraps `f` in a Python function taking any number of positional arguments.
+///
+/// `f` must be `Send`, as the function can be released by whichever thread holds the GIL.
+fn closure_into_py<F>(py: Python, f: F) -> PyResult<&PyAny>
+where
+    F: FnMut(Python, &PyTuple) -> PyResult<PyObject> + Send + 'static,
+{
+    let closure: Closure = RefCell::new(Box::new(f));
+    let capsule = into_capsule(py, CAPSULE_NAME, closure)?;
+    let def = static_method_def!(
+        "rust_callback",
+        PyCFunction(call_closure),
+        ffi::METH_VARARGS,
+        ""
+    );
+    bound_function(py, def, capsule)
+}
+
+unsafe extern "C" fn call_closure(
+    capsule: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let closure: &Closure = capsule_value(capsule, CAPSULE_NAME);
+    let result = match closure.try_borrow_mut() {
+        Ok(mut f) => f(py, py.from_borrowed_ptr(args)),
+        Err(_) => Err(RuntimeError::py_err("Rust callback called recursively")),
+    };
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::{add_reader, closure_into_py, drive_python, wait_readable};
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::{Python, ToPyObject};
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_drive_python() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let asyncio = py.import("asyncio").unwrap();
+        let policy = asyncio.call0("get_event_loop_policy").unwrap();
+        let previous = match policy.call_method0("get_event_loop") {
+            Ok(previous) => previous.to_object(py),
+            Err(_) => py.None(),
+        };
+        let event_loop = asyncio.call0("new_event_loop").unwrap();
+        asyncio.call1("set_event_loop", (event_loop,)).unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let record = {
+            let calls = calls.clone();
+            closure_into_py(py, move |py, _| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(py.None())
+            })
+            .unwrap()
+        };
+        event_loop.call_method1("call_soon", (record,)).unwrap();
+        event_loop
+            .call_method1("call_later", (0.05, record))
+            .unwrap();
+
+        // A zero budget only runs the ready callbacks
+        drive_python(py, Duration::from_millis(0)).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // The loop waits for the timer within the budget
+        let start = Instant::now();
+        drive_python(py, Duration::from_millis(200)).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(150));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Closed and missing loops are skipped
+        event_loop.call_method0("close").unwrap();
+        drive_python(py, Duration::from_millis(0)).unwrap();
+        asyncio.call1("set_event_loop", (py.None(),)).unwrap();
+        drive_python(py, Duration::from_millis(0)).unwrap();
+        asyncio.call1("set_event_loop", (previous,)).unwrap();
+    }
+
+    #[test]
+    fn test_fd_readiness() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let asyncio = py.import("asyncio").unwrap();
+        let event_loop = asyncio.call0("new_event_loop").unwrap();
+        let (mut rust_end, mut other_end) = UnixStream::pair().unwrap();
+        let fd = rust_end.as_raw_fd();
+
+        // A cancelled future stops watching the fd
+        let readable = wait_readable(event_loop, fd).unwrap();
+        readable.call_method0("cancel").unwrap();
+        let sleep = asyncio.call1("sleep", (0,)).unwrap();
+        event_loop
+            .call_method1("run_until_complete", (sleep,))
+            .unwrap();
+        assert!(!event_loop
+            .call_method1("remove_reader", (fd,))
+            .unwrap()
+            .is_true()
+            .unwrap());
+        let readable = wait_readable(event_loop, fd).unwrap();
+        other_end.write_all(b"ping").unwrap();
+        event_loop
+            .call_method1("run_until_complete", (readable,))
+            .unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let watcher = {
+            let received = received.clone();
+            let loop_obj: crate::PyObject = event_loop.into();
+            add_reader(event_loop, fd, move |py| {
+                let mut buf = [0; 16];
+                let n = rust_end.read(&mut buf)?;
+                received.lock().unwrap().extend_from_slice(&buf[..n]);
+                loop_obj.call_method0(py, "stop")?;
+                Ok(())
+            })
+            .unwrap()
+        };
+        event_loop.call_method0("run_forever").unwrap();
+        assert_eq!(&received.lock().unwrap()[..], b"ping");
+
+        drop(watcher);
+        assert!(!event_loop
+            .call_method1("remove_reader", (fd,))
+            .unwrap()
+            .is_true()
+            .unwrap());
+        event_loop.call_method0("close").unwrap();
+    }
+}
@@ -20,6 +20,8 @@
 use crate::err::{self, PyResult};
 use crate::exceptions;
 use crate::ffi;
+use crate::instance::PyRef;
+use crate::type_object::PyTypeInfo;
 use crate::types::PyAny;
 use crate::AsPyPointer;
 use crate::Python;
@@ -27,7 +29,7 @@ use libc;
 use std::ffi::CStr;
 use std::os::raw;
 use std::pin::Pin;
-use std::{cell, mem, slice};
+use std::{cell, mem, ptr, slice};
 
 /// Allows access to the underlying buffer used by a python object such as `bytes`, `bytearray` or `array.array`.
 // use Pin<Box> because Python expects that the Py_buffer struct has a stable memory address
@@ -157,6 +159,12 @@ pub unsafe trait Element {
     fn is_compatible_format(format: &CStr) -> bool;
 }
 
+/// Trait implemented for element types which can be exported with [BufferExport].
+pub unsafe trait ElementFormat: Element {
+    /// The format string of the element, in the syntax of the `struct` module.
+    fn format() -> &'static CStr;
+}
+
 fn validate(b: &ffi::Py_buffer) {
     // shape and stride information must be provided when we use PyBUF_FULL_RO
     assert!(!b.shape.is_null());
@@ -604,6 +612,240 @@ impl Drop for PyBuffer {
     }
 }
 
+/// Fills the `Py_buffer` passed to
+/// [PyBufferProtocol::bf_getbuffer](crate::class::buffer::PyBufferProtocol::bf_getbuffer),
+/// exporting a slice of Rust data.
+///
+/// The slice is exported as a one-dimensional buffer, unless [with_shape](#method.with_shape)
+/// describes its layout. The flags given by the consumer are checked, so that e.g. requesting
+/// a writable buffer of read-only data raises `BufferError`.
+///
+/// Python can access the data until the consumer releases the buffer, e.g. until a
+/// `memoryview` is released or collected, which is why [fill](#method.fill) is unsafe. The
+/// exporting object is kept alive, and
+/// [bf_releasebuffer](crate::class::buffer::PyBufferProtocol::bf_releasebuffer) can be
+/// implemented to count the exports, like `bytearray` does to refuse resizing.
+///
+/// # Example
+/// ```
+/// use pyo3::buffer::BufferExport;
+/// use pyo3::class::PyBufferProtocol;
+/// use pyo3::ffi;
+/// use pyo3::prelude::*;
+/// use std::os::raw::c_int;
+///
+/// /// A 2x3 matrix
+/// #[pyclass]
+/// struct Matrix {
+///     values: [f64; 6],
+///     shape: [isize; 2],
+///     strides: [isize; 2],
+/// }
+///
+/// #[pyproto]
+/// impl PyBufferProtocol for Matrix {
+///     fn bf_getbuffer(&self, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+///         // The values are never moved or modified, so they can be exported
+///         unsafe {
+///             BufferExport::new(&self.values)
+///                 .with_shape(&self.shape, &self.strides)
+///                 .fill(self, view, flags)
+///         }
+///     }
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let matrix = Py::new(py, Matrix {
+///     values: [1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+///     shape: [2, 3],
+///     strides: [24, 8],
+/// }).unwrap();
+/// pyo3::py_run!(py, matrix, r#"
+///     view = memoryview(matrix)
+///     assert view.tolist() == [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]
+///     assert view.readonly
+/// "#);
+/// ```
+pub struct BufferExport<'a> {
+    buf: *mut raw::c_void,
+    len_bytes: usize,
+    item_size: usize,
+    format: &'static CStr,
+    readonly: bool,
+    /// The shape and the strides in bytes, if the data isn't one-dimensional
+    layout: Option<(&'a [isize], &'a [isize])>,
+}
+
+impl<'a> BufferExport<'a> {
+    /// Exports `data` as a read-only buffer.
+    pub fn new<T: ElementFormat>(data: &'a [T]) -> BufferExport<'a> {
+        BufferExport {
+            buf: data.as_ptr() as *mut raw::c_void,
+            len_bytes: mem::size_of_val(data),
+            item_size: mem::size_of::<T>(),
+            format: T::format(),
+            readonly: true,
+            layout: None,
+        }
+    }
+
+    /// Exports `data` as a writable buffer, which Python can modify through the cells.
+    pub fn new_writable<T: ElementFormat>(data: &'a [cell::Cell<T>]) -> BufferExport<'a> {
+        BufferExport {
+            readonly: false,
+            ..BufferExport::new(unsafe { &*(data as *const [cell::Cell<T>] as *const [T]) })
+        }
+    }
+
+    /// Exports the data as a multi-dimensional array with the given shape and strides, where
+    /// the strides are the distances between the items of each dimension in bytes.
+    ///
+    /// [fill](#method.fill) raises `BufferError` if the two don't have the same length or if
+    /// they describe items outside of the data.
+    pub fn with_shape(self, shape: &'a [isize], strides: &'a [isize]) -> BufferExport<'a> {
+        BufferExport {
+            layout: Some((shape, strides)),
+            ..self
+        }
+    }
+
+    /// Fills `view`, the buffer passed to `bf_getbuffer` with `flags`, for the data of
+    /// `exporter`, which is usually `self`.
+    ///
+    /// # Safety
+    ///
+    /// The exported data, as well as the shape and strides, must stay at the same address and
+    /// must only be modified through the cells of [new_writable](#method.new_writable) until the
+    /// consumer releases the buffer. E.g. a `Vec` must not be pushed to or dropped, which
+    /// `bf_releasebuffer` can help to ensure by counting the exports.
+    pub unsafe fn fill<T: PyTypeInfo>(
+        self,
+        exporter: &T,
+        view: *mut ffi::Py_buffer,
+        flags: raw::c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(exceptions::BufferError::py_err("View is null"));
+        }
+        if (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE && self.readonly {
+            return Err(exceptions::BufferError::py_err("Object is not writable"));
+        }
+        let len = match self.layout {
+            Some((shape, strides)) => self.check_layout(shape, strides, flags)?,
+            None => self.len_bytes,
+        };
+
+        let obj = PyRef::from_ref(exporter).as_ptr();
+        ffi::Py_INCREF(obj);
+        (*view).obj = obj;
+        (*view).buf = self.buf;
+        (*view).len = len as isize;
+        (*view).readonly = self.readonly as raw::c_int;
+        (*view).itemsize = self.item_size as isize;
+
+        (*view).format = ptr::null_mut();
+        if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+            (*view).format = self.format.as_ptr() as *mut _;
+        }
+
+        (*view).ndim = 1;
+        (*view).shape = ptr::null_mut();
+        (*view).strides = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        if let Some((shape, strides)) = self.layout {
+            (*view).ndim = shape.len() as raw::c_int;
+            if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+                (*view).shape = shape.as_ptr() as *mut _;
+            }
+            if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+                (*view).strides = strides.as_ptr() as *mut _;
+            }
+        } else {
+            // Without a layout, the shape and strides point into the view, like in
+            // `PyBuffer_FillInfo`. The number of items is stored in `internal`, which is
+            // reserved for the exporter and has the size of a `Py_ssize_t`.
+            if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+                (*view).internal = (len / self.item_size) as *mut raw::c_void;
+                (*view).shape = &mut (*view).internal as *mut *mut raw::c_void as *mut isize;
+            }
+            if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+                (*view).strides = &mut (*view).itemsize;
+            }
+        }
+
+        (*view).suboffsets = ptr::null_mut();
+        Ok(())
+    }
+
+    /// Checks that the items described by the layout are within the data and that the
+    /// consumer can handle the layout, and returns the length of the buffer in bytes.
+    fn check_layout(
+        &self,
+        shape: &[isize],
+        strides: &[isize],
+        flags: raw::c_int,
+    ) -> PyResult<usize> {
+        if shape.len() != strides.len() || shape.len() > ffi::PyBUF_MAX_NDIM as usize {
+            return Err(exceptions::BufferError::py_err("Invalid shape or strides"));
+        }
+        if shape.iter().any(|&n| n < 0) {
+            return Err(exceptions::BufferError::py_err("Invalid shape or strides"));
+        }
+        let item_size = self.item_size as isize;
+        let count: isize = shape.iter().product();
+        if count > 0 {
+            // The offsets of the first and last bytes of the items furthest from the start
+            let (mut first, mut last) = (0, item_size - 1);
+            for (&n, &stride) in shape.iter().zip(strides) {
+                if stride < 0 {
+                    first += (n - 1) * stride;
+                } else {
+                    last += (n - 1) * stride;
+                }
+            }
+            if first < 0 || last >= self.len_bytes as isize {
+                return Err(exceptions::BufferError::py_err(
+                    "Shape and strides describe items outside of the data",
+                ));
+            }
+        }
+
+        // Arrays without items have any layout
+        let c_contiguous =
+            count == 0 || is_contiguous(item_size, shape.iter().rev().zip(strides.iter().rev()));
+        let f_contiguous = count == 0 || is_contiguous(item_size, shape.iter().zip(strides));
+        let requested = |flag| (flags & flag) == flag;
+        if (!requested(ffi::PyBUF_STRIDES) && !c_contiguous)
+            || (requested(ffi::PyBUF_C_CONTIGUOUS) && !c_contiguous)
+            || (requested(ffi::PyBUF_F_CONTIGUOUS) && !f_contiguous)
+            || (requested(ffi::PyBUF_ANY_CONTIGUOUS) && !c_contiguous && !f_contiguous)
+        {
+            return Err(exceptions::BufferError::py_err(
+                "Buffer does not have the requested layout",
+            ));
+        }
+        Ok((count * item_size) as usize)
+    }
+}
+
+/// Checks whether the items are contiguous when visiting the dimensions in the given order,
+/// from the fastest to the slowest changing index.
+fn is_contiguous<'a>(
+    item_size: isize,
+    dimensions: impl Iterator<Item = (&'a isize, &'a isize)>,
+) -> bool {
+    let mut expected = item_size;
+    for (&n, &stride) in dimensions {
+        // The stride of a dimension of length 1 is never used
+        if n != 1 && stride != expected {
+            return false;
+        }
+        expected *= n;
+    }
+    true
+}
+
 /// Like `std::mem::cell`, but only provides read-only access to the data.
 ///
 /// `&ReadOnlyCell<T>` is basically a safe version of `*const T`:
@@ -625,7 +867,13 @@ impl<T: Copy> ReadOnlyCell<T> {
 }
 
 macro_rules! impl_element(
-    ($t:ty, $f:ident) => {
+    ($t:ty, $f:ident, $format:expr) => {
+        unsafe impl ElementFormat for $t {
+            fn format() -> &'static CStr {
+                CStr::from_bytes_with_nul(concat!($format, "\0").as_bytes()).unwrap()
+            }
+        }
+
         unsafe impl Element for $t {
             fn is_compatible_format(format: &CStr) -> bool {
                 let slice = format.to_bytes();
@@ -638,18 +886,18 @@ macro_rules! impl_element(
     }
 );
 
-impl_element!(u8, UnsignedInteger);
-impl_element!(u16, UnsignedInteger);
-impl_element!(u32, UnsignedInteger);
-impl_element!(u64, UnsignedInteger);
-impl_element!(usize, UnsignedInteger);
-impl_element!(i8, SignedInteger);
-impl_element!(i16, SignedInteger);
-impl_element!(i32, SignedInteger);
-impl_element!(i64, SignedInteger);
-impl_element!(isize, SignedInteger);
-impl_element!(f32, Float);
-impl_element!(f64, Float);
+impl_element!(u8, UnsignedInteger, "B");
+impl_element!(u16, UnsignedInteger, "H");
+impl_element!(u32, UnsignedInteger, "I");
+impl_element!(u64, UnsignedInteger, "Q");
+impl_element!(usize, UnsignedInteger, "N");
+impl_element!(i8, SignedInteger, "b");
+impl_element!(i16, SignedInteger, "h");
+impl_element!(i32, SignedInteger, "i");
+impl_element!(i64, SignedInteger, "q");
+impl_element!(isize, SignedInteger, "n");
+impl_element!(f32, Float, "f");
+impl_element!(f64, Float, "d");
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,199 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Caching of constant Python objects.
+//!
+//! Functions that return the same constant on every call, such as a tuple of field names or a
+//! default configuration dict, would otherwise convert it to a new Python object each time.
+//! [static_py!](crate::static_py) converts the value once per interpreter and returns the cached
+//! object on later calls.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::static_py;
+//! use pyo3::types::PyAny;
+//! use pyo3::AsPyPointer;
+//!
+//! fn field_names(py: Python) -> &PyAny {
+//!     static_py!(py, ("id", "name", "email"))
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! assert_eq!(field_names(py).as_ptr(), field_names(py).as_ptr());
+//! ```
+
+use crate::ffi;
+use crate::types::PyAny;
+use crate::{IntoPyPointer, PyObject, Python};
+
+/// Converts a value to a Python object on first use and returns the same object afterwards.
+///
+/// `static_py!(py, value)` evaluates to a `&PyAny`. `value` can be any expression whose type
+/// implements [ToPyObject](crate::ToPyObject); it is only evaluated when the current interpreter
+/// has no cached object yet, so building a `HashMap` for a small dict is cheap. The cache belongs
+/// to the call site, so two `static_py!` invocations never share an object.
+///
+/// The cached objects are never released, and they must not be mutated, as every caller sees the
+/// same object: prefer tuples and strings, and copy cached dicts and lists before handing them
+/// to Python code that may modify them.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::static_py;
+/// use std::collections::HashMap;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let defaults = static_py!(py, {
+///     let mut defaults = HashMap::new();
+///     defaults.insert("timeout", 30);
+///     defaults.insert("retries", 3);
+///     defaults
+/// });
+/// assert_eq!(defaults.get_item("timeout").unwrap().extract::<i32>().unwrap(), 30);
+/// ```
+#[macro_export]
+macro_rules! static_py {
+    ($py:expr, $value:expr) => {{
+        static CACHE: $crate::cache::PyStatic = $crate::cache::PyStatic::new();
+        let py: $crate::Python = $py;
+        CACHE.get_or_init(py, || $crate::ToPyObject::to_object(&$value, py))
+    }};
+}
+
+/// The storage of [static_py!](crate::static_py), holding one object per interpreter.
+///
+/// The interpreters are identified by their ID, which is never reused, so an object is never
+/// returned to an interpreter other than the one that created it.
+pub struct PyStatic {
+    // Pairs of interpreter IDs and owned object pointers
+    objects: spin::Mutex<Vec<(i64, usize)>>,
+}
+
+impl PyStatic {
+    /// Creates an empty cache.
+    pub const fn new() -> PyStatic {
+        PyStatic {
+            objects: spin::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the object of the current interpreter, creating it with `init` if there is none.
+    pub fn get_or_init<'p, F>(&'static self, py: Python<'p>, init: F) -> &'p PyAny
+    where
+        F: FnOnce() -> PyObject,
+    {
+        match self.get_or_try_init(py, || Ok::<_, ()>(init())) {
+            Ok(object) => object,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    /// Like [get_or_init](#method.get_or_init), but returns the error of `init` if it fails,
+    /// in which case nothing is stored.
+    pub fn get_or_try_init<'p, F, E>(&'static self, py: Python<'p>, init: F) -> Result<&'p PyAny, E>
+    where
+        F: FnOnce() -> Result<PyObject, E>,
+    {
+        let interpreter = interpreter_id(py);
+        if let Some(object) = self.find(interpreter) {
+            return Ok(unsafe { py.from_borrowed_ptr(object as *mut ffi::PyObject) });
+        }
+        // The lock is not held while converting, as the conversion may run Python code which
+        // releases the GIL. The first object stored wins if two threads race.
+        let created = init()?.into_ptr();
+        let object = {
+            let mut objects = self.objects.lock();
+            match objects.iter().find(|(id, _)| *id == interpreter) {
+                Some(&(_, object)) => {
+                    unsafe { ffi::Py_DECREF(created) };
+                    object
+                }
+                None => {
+                    objects.push((interpreter, created as usize));
+                    created as usize
+                }
+            }
+        };
+        Ok(unsafe { py.from_borrowed_ptr(object as *mut ffi::PyObject) })
+    }
+
+    fn find(&self, interpreter: i64) -> Option<usize> {
+        self.objects
+            .lock()
+            .iter()
+            .find(|(id, _)| *id == interpreter)
+            .map(|&(_, object)| object)
+    }
+}
+
+/// Returns the ID of the current interpreter, 0 for the main interpreter.
+#[cfg(all(Py_3_7, not(PyPy)))]
+pub(crate) fn interpreter_id(_py: Python) -> i64 {
+    unsafe { ffi::PyInterpreterState_GetID((*ffi::PyThreadState_Get()).interp) }
+}
+
+// Subinterpreters are not supported by older versions and PyPy
+#[cfg(not(all(Py_3_7, not(PyPy))))]
+pub(crate) fn interpreter_id(_py: Python) -> i64 {
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use crate::interpreters::PyInterpreterPool;
+    use crate::types::PyAny;
+    use crate::{AsPyPointer, ObjectProtocol, Python};
+    use std::cell::Cell;
+
+    fn constant(py: Python) -> &PyAny {
+        static_py!(py, ("a", 1, (2.5, "b")))
+    }
+
+    #[test]
+    fn test_cached() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let first = constant(py);
+        assert_eq!(
+            first.extract::<(String, i32, (f64, String))>().unwrap().1,
+            1
+        );
+        assert_eq!(first.as_ptr(), constant(py).as_ptr());
+    }
+
+    #[test]
+    fn test_evaluated_once() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let calls = Cell::new(0);
+        for _ in 0..3 {
+            let value = static_py!(py, {
+                calls.set(calls.get() + 1);
+                "value"
+            });
+            assert_eq!(value.extract::<&str>().unwrap(), "value");
+        }
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_per_interpreter() {
+        let main = {
+            let gil = Python::acquire_gil();
+            constant(gil.python()).as_ptr() as usize
+        };
+        let pool = PyInterpreterPool::new(1, &[]).unwrap();
+        let (sub, again) = pool
+            .submit(|py| {
+                let sub = constant(py).as_ptr() as usize;
+                (sub, constant(py).as_ptr() as usize)
+            })
+            .join()
+            .unwrap();
+        assert_ne!(sub, main);
+        assert_eq!(sub, again);
+    }
+}
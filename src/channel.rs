@@ -0,0 +1,230 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Message passing between Rust and Python threads.
+//!
+//! [channel] creates a `PyChannel` Python object together with a Rust `Sender` and `Receiver`.
+//! Values sent with the `Sender` are converted to Python objects when Python code calls
+//! `get()` on the channel, and objects Python code passes to `put()` are extracted and delivered
+//! to the `Receiver`. This makes producer/consumer setups across the language boundary simple:
+//!
+//!  * `get(timeout=None)` releases the GIL while it waits, so Rust threads that need the GIL
+//!    can keep running. It raises `TimeoutError` if `timeout` seconds passed without a value,
+//!    and `EOFError` once all `Sender`s were dropped and no values are left.
+//!  * `put(item)` raises the extraction error if `item` can't be converted, and
+//!    `BrokenPipeError` if the `Receiver` was dropped.
+//!
+//! The Rust ends don't need the GIL. A Rust thread waiting on the `Receiver` must not hold the
+//! GIL though, or the Python producer can't run; use [Python::allow_threads] in that case.
+//!
+//! [Python::allow_threads]: ../struct.Python.html#method.allow_threads
+//!
+//! # Example
+//! ```
+//! use pyo3::channel::channel;
+//! use pyo3::prelude::*;
+//! use pyo3::types::PyDict;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let (sender, receiver, chan) = channel::<i32, String>(py).unwrap();
+//!
+//! sender.send(21).unwrap();
+//! let locals = PyDict::new(py);
+//! locals.set_item("chan", chan).unwrap();
+//! py.run("chan.put(str(chan.get() * 2))", None, Some(locals)).unwrap();
+//! assert_eq!(receiver.recv().unwrap(), "42");
+//! ```
+
+use crate::callback::{cb_convert, PyObjectCallbackConverter};
+use crate::err::PyResult;
+use crate::exceptions::{BrokenPipeError, EOFError, TimeoutError, TypeError, ValueError};
+use crate::ffi;
+use crate::internal_tricks::{bound_function, capsule_value, into_capsule};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict, PyTuple};
+use crate::{FromPyObject, IntoPy, PyObject, Python};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const CAPSULE_NAME: &[u8] = b"pyo3.channel\0";
+
+/// The Rust side of a `PyChannel` object, owned by a capsule that both methods are bound to.
+struct ChannelState {
+    put: Box<dyn Fn(&PyAny) -> PyResult<()>>,
+    get: Box<dyn Fn(Python, Option<Duration>) -> PyResult<PyObject>>,
+}
+
+/// Creates a channel between Rust and Python. See the [module documentation](index.html).
+///
+/// Returns the `Sender` for values going to Python, the `Receiver` for values coming from
+/// Python, and the `PyChannel` object to hand to Python code.
+pub fn channel<S, R>(py: Python) -> PyResult<(Sender<S>, Receiver<R>, &PyAny)>
+where
+    S: IntoPy<PyObject> + Send + 'static,
+    R: for<'a> FromPyObject<'a> + Send + 'static,
+{
+    let (to_python, from_rust) = mpsc::channel::<S>();
+    let (to_rust, from_python) = mpsc::channel::<R>();
+    let from_rust = Mutex::new(from_rust);
+
+    let state = ChannelState {
+        put: Box::new(move |item| {
+            to_rust
+                .send(item.extract()?)
+                .map_err(|_| BrokenPipeError::py_err("The channel's receiver was dropped"))
+        }),
+        get: Box::new(move |py, timeout| {
+            let item = py.allow_threads(|| {
+                let from_rust = from_rust.lock().unwrap();
+                match timeout {
+                    Some(timeout) => from_rust.recv_timeout(timeout),
+                    None => from_rust.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                }
+            });
+            match item {
+                Ok(item) => Ok(item.into_py(py)),
+                Err(RecvTimeoutError::Timeout) => Err(TimeoutError::py_err(
+                    "Timed out waiting for a value from the channel",
+                )),
+                Err(RecvTimeoutError::Disconnected) => {
+                    Err(EOFError::py_err("The channel's senders were dropped"))
+                }
+            }
+        }),
+    };
+    let capsule = into_capsule(py, CAPSULE_NAME, state)?;
+
+    let put = static_method_def!(
+        "put",
+        PyCFunction(put),
+        ffi::METH_O,
+        "put(item)\n--\n\nSends item to the Rust receiver."
+    );
+    let get = static_method_def!(
+        "get",
+        PyCFunctionWithKeywords(get),
+        ffi::METH_VARARGS | ffi::METH_KEYWORDS,
+        "get(timeout=None)\n--\n\nWaits for a value from the Rust sender."
+    );
+    let builtins = py.import("builtins")?;
+    let namespace = PyDict::new(py);
+    namespace.set_item("__slots__", PyTuple::empty(py))?;
+    for &(name, def) in &[("put", put), ("get", get)] {
+        let function = bound_function(py, def, capsule)?;
+        namespace.set_item(name, builtins.call1("staticmethod", (function,))?)?;
+    }
+    let class = builtins.call1("type", ("PyChannel", PyTuple::empty(py), namespace))?;
+    Ok((to_python, from_python, class.call0()?))
+}
+
+unsafe fn state<'a>(capsule: *mut ffi::PyObject) -> &'a ChannelState {
+    capsule_value(capsule, CAPSULE_NAME)
+}
+
+unsafe extern "C" fn put(
+    capsule: *mut ffi::PyObject,
+    item: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let result = (state(capsule).put)(py.from_borrowed_ptr(item));
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+unsafe extern "C" fn get(
+    capsule: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+    kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let args: &PyTuple = py.from_borrowed_ptr(args);
+    let kwargs: Option<&PyDict> = py.from_borrowed_ptr_or_opt(kwargs);
+    let timeout = match (
+        args.len(),
+        kwargs.and_then(|kwargs| kwargs.get_item("timeout")),
+    ) {
+        (0, timeout) => timeout,
+        (1, None) => Some(args.get_item(0)),
+        _ => {
+            let err = TypeError::py_err("get() takes a single optional argument 'timeout'");
+            return cb_convert(PyObjectCallbackConverter, py, Err::<PyObject, _>(err));
+        }
+    };
+    let result = timeout
+        .filter(|timeout| !timeout.is_none())
+        .map(|timeout| {
+            Duration::try_from_secs_f64(timeout.extract::<f64>()?)
+                .map_err(|_| ValueError::py_err("'timeout' must be a non-negative, finite number"))
+        })
+        .transpose()
+        .and_then(|timeout| (state(capsule).get)(py, timeout));
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::channel;
+    use crate::exceptions::{BrokenPipeError, EOFError, TimeoutError, ValueError};
+    use crate::types::PyDict;
+    use crate::Python;
+    use std::thread;
+
+    #[test]
+    fn test_rust_to_python() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (sender, receiver, chan) = channel::<Vec<i32>, i32>(py).unwrap();
+        let locals = PyDict::new(py);
+        locals.set_item("chan", chan).unwrap();
+
+        let producer = thread::spawn(move || {
+            for i in 0..3 {
+                sender.send(vec![i; 2]).unwrap();
+            }
+        });
+        py.run(
+            r#"
+total = 0
+while True:
+    try:
+        total += sum(chan.get(timeout=5))
+    except EOFError:
+        break
+chan.put(total)
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        producer.join().unwrap();
+        assert_eq!(receiver.recv().unwrap(), 6);
+
+        let err = py.run("chan.get()", None, Some(locals)).unwrap_err();
+        assert!(err.is_instance::<EOFError>(py));
+        let err = py.run("chan.put('a')", None, Some(locals)).unwrap_err();
+        assert!(!err.is_instance::<BrokenPipeError>(py));
+        drop(receiver);
+        let err = py.run("chan.put(1)", None, Some(locals)).unwrap_err();
+        assert!(err.is_instance::<BrokenPipeError>(py));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (_sender, _receiver, chan) = channel::<i32, i32>(py).unwrap();
+        let locals = PyDict::new(py);
+        locals.set_item("chan", chan).unwrap();
+        let err = py.run("chan.get(0.01)", None, Some(locals)).unwrap_err();
+        assert!(err.is_instance::<TimeoutError>(py));
+        assert!(py.run("chan.get(1, 2)", None, Some(locals)).is_err());
+        for timeout in &["-1", "float('inf')", "float('nan')"] {
+            let err = py
+                .run(&format!("chan.get({})", timeout), None, Some(locals))
+                .unwrap_err();
+            assert!(err.is_instance::<ValueError>(py));
+        }
+    }
+}
@@ -230,12 +230,10 @@ where
             // Behave like python's __getattr__ (as opposed to __getattribute__) and check
             // for existing fields and methods first
             let existing = ffi::PyObject_GenericGetAttr(slf, arg);
-            if existing.is_null() {
-                // PyObject_HasAttr also tries to get an object and clears the error if it fails
-                ffi::PyErr_Clear();
-            } else {
+            if !existing.is_null() || ffi::PyErr_ExceptionMatches(ffi::PyExc_AttributeError) == 0 {
                 return existing;
             }
+            ffi::PyErr_Clear();
 
             let slf = py.mut_from_borrowed_ptr::<T>(slf);
             let arg = py.from_borrowed_ptr::<crate::types::PyAny>(arg);
@@ -250,6 +248,88 @@ where
     }
 }
 
+/// Sets `tp_getattro` and `tp_setattro` for `__getattr__`, `__setattr__` and `__delattr__`
+/// defined in `#[pymethods]`, whose names are given. Static types don't get these slots from the
+/// methods in their dict, unlike Python classes.
+pub(crate) fn tp_as_attribute_methods<T: PyTypeInfo>(
+    type_object: &mut ffi::PyTypeObject,
+    names: &[&str],
+) {
+    if names.contains(&"__getattr__") {
+        type_object.tp_getattro = Some(getattr_method::<T>);
+    }
+    if names.contains(&"__setattr__") || names.contains(&"__delattr__") {
+        type_object.tp_setattro = Some(setattr_method::<T>);
+    }
+}
+
+/// Returns the method `name` defined for `T`, if any.
+unsafe fn attribute_method<'p, T: PyTypeInfo>(py: Python<'p>, name: &str) -> Option<&'p PyAny> {
+    let dict: &crate::types::PyDict = py.from_borrowed_ptr(T::type_object().tp_dict);
+    dict.get_item(name)
+}
+
+/// Calls `__getattr__` for attributes the generic lookup doesn't find.
+unsafe extern "C" fn getattr_method<T: PyTypeInfo>(
+    slf: *mut ffi::PyObject,
+    name: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let existing = ffi::PyObject_GenericGetAttr(slf, name);
+    if !existing.is_null() || ffi::PyErr_ExceptionMatches(ffi::PyExc_AttributeError) == 0 {
+        return existing;
+    }
+    ffi::PyErr_Clear();
+
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let slf = py.from_borrowed_ptr::<PyAny>(slf);
+    let name = py.from_borrowed_ptr::<PyAny>(name);
+    let result: PyResult<PyObject> = match attribute_method::<T>(py, "__getattr__") {
+        Some(method) => method.call1((slf, name)).map(|value| value.into_py(py)),
+        None => Err(PyErr::new::<exceptions::AttributeError, _>(
+            name.str()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        )),
+    };
+    crate::callback::cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+/// Calls `__setattr__`, or `__delattr__` when `value` is null, falling back to the generic
+/// implementation when the class doesn't define the method.
+unsafe extern "C" fn setattr_method<T: PyTypeInfo>(
+    slf: *mut ffi::PyObject,
+    name: *mut ffi::PyObject,
+    value: *mut ffi::PyObject,
+) -> c_int {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let result = if value.is_null() {
+        attribute_method::<T>(py, "__delattr__").map(|method| {
+            method.call1((
+                py.from_borrowed_ptr::<PyAny>(slf),
+                py.from_borrowed_ptr::<PyAny>(name),
+            ))
+        })
+    } else {
+        attribute_method::<T>(py, "__setattr__").map(|method| {
+            method.call1((
+                py.from_borrowed_ptr::<PyAny>(slf),
+                py.from_borrowed_ptr::<PyAny>(name),
+                py.from_borrowed_ptr::<PyAny>(value),
+            ))
+        })
+    };
+    match result {
+        None => ffi::PyObject_GenericSetAttr(slf, name, value),
+        Some(Ok(_)) => 0,
+        Some(Err(err)) => {
+            err.restore(py);
+            -1
+        }
+    }
+}
+
 /// An object may support setting attributes (by implementing PyObjectSetAttrProtocol)
 /// and may support deleting attributes (by implementing PyObjectDelAttrProtocol)
 /// and we need to generate a single extern c function that supports only setting, only deleting
@@ -535,7 +615,7 @@ where
     }
 }
 
-fn extract_op(op: c_int) -> PyResult<CompareOp> {
+pub(crate) fn extract_op(op: c_int) -> PyResult<CompareOp> {
     match op {
         ffi::Py_LT => Ok(CompareOp::Lt),
         ffi::Py_LE => Ok(CompareOp::Le),
@@ -59,7 +59,7 @@ where
     fn tp_as_buffer() -> Option<ffi::PyBufferProcs> {
         Some(ffi::PyBufferProcs {
             bf_getbuffer: Self::cb_bf_getbuffer(),
-            bf_releasebuffer: None,
+            bf_releasebuffer: Self::cb_bf_releasebuffer(),
             ..ffi::PyBufferProcs_INIT
         })
     }
@@ -102,3 +102,40 @@ where
         Some(wrap::<T>)
     }
 }
+
+trait PyBufferReleaseBufferProtocolImpl {
+    fn cb_bf_releasebuffer() -> Option<ffi::releasebufferproc>;
+}
+
+impl<'p, T> PyBufferReleaseBufferProtocolImpl for T
+where
+    T: PyBufferProtocol<'p>,
+{
+    default fn cb_bf_releasebuffer() -> Option<ffi::releasebufferproc> {
+        None
+    }
+}
+
+impl<T> PyBufferReleaseBufferProtocolImpl for T
+where
+    T: for<'p> PyBufferReleaseBufferProtocol<'p>,
+{
+    #[inline]
+    fn cb_bf_releasebuffer() -> Option<ffi::releasebufferproc> {
+        unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject, arg1: *mut ffi::Py_buffer)
+        where
+            T: for<'p> PyBufferReleaseBufferProtocol<'p>,
+        {
+            let py = crate::Python::assume_gil_acquired();
+            let _pool = crate::GILPool::new(py);
+            let exporter = py.mut_from_borrowed_ptr::<T>(slf);
+
+            // Releasing a buffer can't fail, so errors are reported like in `__del__`
+            if let Err(e) = exporter.bf_releasebuffer(arg1).into() {
+                e.restore(py);
+                ffi::PyErr_WriteUnraisable(slf);
+            }
+        }
+        Some(wrap::<T>)
+    }
+}
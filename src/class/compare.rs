@@ -0,0 +1,165 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Comparison and hashing derived from the Rust traits of a class.
+//!
+//! `#[pyclass(eq)]` implements `==` and `!=` with `PartialEq`, `#[pyclass(ord)]` implements all
+//! six comparisons with `PartialOrd`, and `#[pyclass(hash)]` implements `hash()` with `Hash`,
+//! which must be combined with `eq` or `ord` so that equal objects have equal hashes. Comparing
+//! with an object of another type returns `NotImplemented`, so Python falls back to the other
+//! operand or to identity. Ordering comparisons of values `partial_cmp` can't order are false.
+//!
+//! These slots take precedence over `__richcmp__` and `__hash__` implemented with
+//! `#[pyproto]`. Like Python classes defining `__eq__`, classes with `eq` or `ord` but without
+//! `hash` are unhashable. Instances of a class with `hash` must not be changed while they're
+//! in a set or used as dict keys.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//!
+//! #[pyclass(eq, ord, hash)]
+//! #[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+//! struct Version {
+//!     major: u32,
+//!     minor: u32,
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let old = Py::new(py, Version { major: 1, minor: 2 }).unwrap();
+//! let new = Py::new(py, Version { major: 1, minor: 10 }).unwrap();
+//! let same = Py::new(py, Version { major: 1, minor: 10 }).unwrap();
+//! pyo3::py_run!(py, old new same, r#"
+//! assert old < new and new == same and new is not same
+//! assert len({old, new, same}) == 2
+//! "#);
+//! ```
+
+use crate::class::basic::{extract_op, CompareOp};
+use crate::ffi;
+use crate::type_object::PyTypeInfo;
+use crate::types::PyAny;
+use crate::{AsPyPointer, IntoPyPointer, Python, ToPyObject};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_int;
+use std::ptr;
+
+/// A class whose `==` and `!=` use `PartialEq`, implemented by `#[pyclass(eq)]`.
+pub trait PyClassEq: PyTypeInfo + PartialEq {}
+
+/// A class whose comparisons use `PartialOrd`, implemented by `#[pyclass(ord)]`.
+pub trait PyClassOrd: PyClassEq + PartialOrd {}
+
+/// A class whose hash uses `Hash`, implemented by `#[pyclass(hash)]`.
+pub trait PyClassHash: PyTypeInfo + Hash {}
+
+#[doc(hidden)]
+pub trait PyCompareImpl {
+    fn tp_as_compare(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T> PyCompareImpl for T {
+    default fn tp_as_compare(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T: PyClassEq> PyCompareImpl for T {
+    default fn tp_as_compare(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_richcompare = Some(richcompare_eq::<T>);
+    }
+}
+
+impl<T: PyClassOrd> PyCompareImpl for T {
+    fn tp_as_compare(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_richcompare = Some(richcompare_ord::<T>);
+    }
+}
+
+#[doc(hidden)]
+pub trait PyHashImpl {
+    fn tp_as_hash(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T> PyHashImpl for T {
+    default fn tp_as_hash(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T: PyClassHash> PyHashImpl for T {
+    fn tp_as_hash(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_hash = Some(hash::<T>);
+    }
+}
+
+/// Calls `compare` with both operands if `other` is a `T`, and returns `NotImplemented`
+/// otherwise.
+unsafe fn compare<T: PyTypeInfo>(
+    slf: *mut ffi::PyObject,
+    other: *mut ffi::PyObject,
+    op: c_int,
+    compare: impl FnOnce(&T, &T, CompareOp) -> Option<bool>,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let op = match extract_op(op) {
+        Ok(op) => op,
+        Err(err) => {
+            err.restore(py);
+            return ptr::null_mut();
+        }
+    };
+    let other = py.from_borrowed_ptr::<PyAny>(other);
+    if !T::is_instance(other) {
+        return py.NotImplemented().into_ptr();
+    }
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    let other = py.from_borrowed_ptr::<T>(other.as_ptr());
+    match compare(slf, other, op) {
+        Some(result) => result.to_object(py).into_ptr(),
+        None => py.NotImplemented().into_ptr(),
+    }
+}
+
+unsafe extern "C" fn richcompare_eq<T: PyClassEq>(
+    slf: *mut ffi::PyObject,
+    other: *mut ffi::PyObject,
+    op: c_int,
+) -> *mut ffi::PyObject {
+    compare::<T>(slf, other, op, |slf, other, op| match op {
+        CompareOp::Eq => Some(slf == other),
+        CompareOp::Ne => Some(slf != other),
+        _ => None,
+    })
+}
+
+unsafe extern "C" fn richcompare_ord<T: PyClassOrd>(
+    slf: *mut ffi::PyObject,
+    other: *mut ffi::PyObject,
+    op: c_int,
+) -> *mut ffi::PyObject {
+    compare::<T>(slf, other, op, |slf, other, op| {
+        let ordering = slf.partial_cmp(other);
+        Some(match op {
+            CompareOp::Eq => slf == other,
+            CompareOp::Ne => slf != other,
+            CompareOp::Lt => ordering == Some(Ordering::Less),
+            CompareOp::Le => ordering.map_or(false, |ordering| ordering != Ordering::Greater),
+            CompareOp::Gt => ordering == Some(Ordering::Greater),
+            CompareOp::Ge => ordering.map_or(false, |ordering| ordering != Ordering::Less),
+        })
+    })
+}
+
+unsafe extern "C" fn hash<T: PyClassHash>(slf: *mut ffi::PyObject) -> ffi::Py_hash_t {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    let mut hasher = DefaultHasher::new();
+    slf.hash(&mut hasher);
+    match hasher.finish() as ffi::Py_hash_t {
+        // -1 signals an error
+        -1 => -2,
+        hash => hash,
+    }
+}
@@ -4,10 +4,15 @@
 //!
 
 use crate::ffi;
+use crate::instance::Py;
+use crate::object::PyObject;
 use crate::type_object::PyTypeInfo;
 use crate::AsPyPointer;
 use crate::Python;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::os::raw::{c_int, c_void};
+use std::sync::Mutex;
 
 #[repr(transparent)]
 pub struct PyTraverseError(c_int);
@@ -51,11 +56,17 @@ pub struct PyVisit<'p> {
 }
 
 impl<'p> PyVisit<'p> {
+    /// Reports a reference to `obj` to the garbage collector. `None`, or any other null
+    /// pointer, is skipped.
     pub fn call<T>(&self, obj: &T) -> Result<(), PyTraverseError>
     where
         T: AsPyPointer,
     {
-        let r = unsafe { (self.visit)(obj.as_ptr(), self.arg) };
+        let ptr = obj.as_ptr();
+        if ptr.is_null() {
+            return Ok(());
+        }
+        let r = unsafe { (self.visit)(ptr, self.arg) };
         if r == 0 {
             Ok(())
         } else {
@@ -64,6 +75,192 @@ impl<'p> PyVisit<'p> {
     }
 }
 
+/// Values which can hold references to Python objects, visited and cleared by the
+/// `PyGCProtocol` implementation `#[pyclass(gc)]` generates.
+///
+/// `PyObject` and `Py<T>` report their object, and `Option`, `Box`, `RefCell`, `Mutex`, tuples,
+/// `Vec`, `VecDeque`, `HashMap` and `BTreeMap` forward to their contents. Numbers, `bool`,
+/// `char`, `String` and `()` hold no objects and visit nothing.
+///
+/// Other types don't implement the trait, so that a field holding Python objects can't be
+/// skipped by accident. There's deliberately no implementation for `Rc` and `Arc`: the
+/// collector may only be told about references the object owns, not about shared ones. Mark
+/// such fields with `#[pyo3(gc = "skip")]`, or implement `PyGCProtocol` by hand with
+/// `#[pyclass(gc = "manual")]`.
+///
+/// Clearing breaks reference cycles: a `PyObject` is replaced by `None`, an `Option<PyObject>`
+/// or `Option<Py<T>>` becomes `None` and the containers clear their contents. A plain
+/// `Py<T>` can't be cleared, so `#[pyclass(gc)]` rejects fields holding one outside of `Option`
+/// or a collection.
+pub trait PyTraverse {
+    fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError>;
+    fn clear(&mut self, py: Python);
+}
+
+macro_rules! traverse_nothing {
+    ($($ty:ty),*) => {
+        $(
+            impl PyTraverse for $ty {
+                fn traverse(&self, _visit: &PyVisit) -> Result<(), PyTraverseError> {
+                    Ok(())
+                }
+
+                fn clear(&mut self, _py: Python) {}
+            }
+        )*
+    };
+}
+
+traverse_nothing!(
+    (),
+    bool,
+    char,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    f32,
+    f64,
+    String
+);
+
+impl PyTraverse for PyObject {
+    fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+        visit.call(self)
+    }
+
+    fn clear(&mut self, py: Python) {
+        *self = py.None();
+    }
+}
+
+impl<T> PyTraverse for Py<T> {
+    fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+        visit.call(self)
+    }
+
+    fn clear(&mut self, _py: Python) {}
+}
+
+impl<T: PyTraverse> PyTraverse for Option<T> {
+    default fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+        match self {
+            Some(value) => T::traverse(value, visit),
+            None => Ok(()),
+        }
+    }
+
+    default fn clear(&mut self, py: Python) {
+        if let Some(value) = self {
+            T::clear(value, py);
+        }
+    }
+}
+
+impl PyTraverse for Option<PyObject> {
+    fn clear(&mut self, _py: Python) {
+        *self = None;
+    }
+}
+
+impl<T> PyTraverse for Option<Py<T>> {
+    fn clear(&mut self, _py: Python) {
+        *self = None;
+    }
+}
+
+impl<T: PyTraverse> PyTraverse for Box<T> {
+    fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+        T::traverse(self, visit)
+    }
+
+    fn clear(&mut self, py: Python) {
+        T::clear(self, py)
+    }
+}
+
+impl<T: PyTraverse> PyTraverse for RefCell<T> {
+    fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+        // A value borrowed mutably is being modified, its references are reported next time
+        match self.try_borrow() {
+            Ok(value) => T::traverse(&value, visit),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn clear(&mut self, py: Python) {
+        T::clear(self.get_mut(), py)
+    }
+}
+
+impl<T: PyTraverse> PyTraverse for Mutex<T> {
+    fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+        // Like a borrowed `RefCell`, a locked value is reported next time
+        match self.try_lock() {
+            Ok(value) => T::traverse(&value, visit),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn clear(&mut self, py: Python) {
+        if let Ok(value) = self.get_mut() {
+            T::clear(value, py)
+        }
+    }
+}
+
+macro_rules! traverse_tuple {
+    ($($param:ident $index:tt),*) => {
+        impl<$($param: PyTraverse),*> PyTraverse for ($($param,)*) {
+            fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+                $(self.$index.traverse(visit)?;)*
+                Ok(())
+            }
+
+            fn clear(&mut self, py: Python) {
+                $(self.$index.clear(py);)*
+            }
+        }
+    };
+}
+
+traverse_tuple!(A 0);
+traverse_tuple!(A 0, B 1);
+traverse_tuple!(A 0, B 1, C 2);
+traverse_tuple!(A 0, B 1, C 2, D 3);
+
+macro_rules! traverse_values {
+    ($ty:ty, $values:ident, $values_mut:ident, $value:ident, $($param:ident),*) => {
+        impl<$value: PyTraverse, $($param),*> PyTraverse for $ty {
+            fn traverse(&self, visit: &PyVisit) -> Result<(), PyTraverseError> {
+                for value in self.$values() {
+                    PyTraverse::traverse(value, visit)?;
+                }
+                Ok(())
+            }
+
+            fn clear(&mut self, py: Python) {
+                for value in self.$values_mut() {
+                    PyTraverse::clear(value, py);
+                }
+            }
+        }
+    };
+}
+
+traverse_values!(Vec<T>, iter, iter_mut, T,);
+traverse_values!(VecDeque<T>, iter, iter_mut, T,);
+traverse_values!(HashMap<K, V, S>, values, values_mut, V, K, S);
+traverse_values!(BTreeMap<K, V>, values, values_mut, V, K);
+
 trait PyGCTraverseProtocolImpl {
     fn tp_traverse() -> Option<ffi::traverseproc>;
 }
@@ -93,7 +290,8 @@ where
             T: for<'p> PyGCTraverseProtocol<'p>,
         {
             let py = Python::assume_gil_acquired();
-            let _pool = crate::GILPool::new(py);
+            // Releasing pointers could deallocate objects while the collector traverses them
+            let _pool = crate::GILPool::new_no_pointers(py);
             let slf = py.mut_from_borrowed_ptr::<T>(slf);
 
             let visit = PyVisit {
@@ -3,12 +3,14 @@
 //! Trait and support implementation for implementing iterators
 
 use crate::callback::{CallbackConverter, PyObjectCallbackConverter};
+use crate::class::methods::PyMethodDef;
 use crate::err::PyResult;
-use crate::instance::PyRefMut;
+use crate::instance::{Py, PyRefMut};
 use crate::type_object::PyTypeInfo;
 use crate::IntoPyPointer;
 use crate::Python;
 use crate::{ffi, IntoPy, PyObject};
+use pyo3cls::{pyclass, pyproto};
 use std::ptr;
 
 /// Python Iterator Interface.
@@ -30,6 +32,18 @@ pub trait PyIterProtocol<'p>: PyTypeInfo + Sized {
     {
         unimplemented!()
     }
+
+    /// Returns an estimate of the number of remaining items, used by Python to preallocate
+    /// when consuming the iterator (e.g. in `list(it)`). See
+    /// [PEP 424](https://www.python.org/dev/peps/pep-0424/).
+    ///
+    /// An iterator wrapping a Rust `Iterator` can simply forward `self.iter.size_hint().0`.
+    fn __length_hint__(&'p self) -> Self::Result
+    where
+        Self: PyIterLengthHintProtocol<'p>,
+    {
+        unimplemented!()
+    }
 }
 
 pub trait PyIterIterProtocol<'p>: PyIterProtocol<'p> {
@@ -42,13 +56,21 @@ pub trait PyIterNextProtocol<'p>: PyIterProtocol<'p> {
     type Result: Into<PyResult<Option<Self::Success>>>;
 }
 
+pub trait PyIterLengthHintProtocol<'p>: PyIterProtocol<'p> {
+    type Result: Into<PyResult<usize>>;
+}
+
 #[doc(hidden)]
 pub trait PyIterProtocolImpl {
     fn tp_as_iter(_typeob: &mut ffi::PyTypeObject);
+    fn methods() -> Vec<PyMethodDef>;
 }
 
 impl<T> PyIterProtocolImpl for T {
     default fn tp_as_iter(_typeob: &mut ffi::PyTypeObject) {}
+    default fn methods() -> Vec<PyMethodDef> {
+        Vec::new()
+    }
 }
 
 impl<'p, T> PyIterProtocolImpl for T
@@ -60,6 +82,15 @@ where
         typeob.tp_iter = Self::tp_iter();
         typeob.tp_iternext = Self::tp_iternext();
     }
+
+    fn methods() -> Vec<PyMethodDef> {
+        let mut methods = Vec::new();
+
+        if let Some(def) = <Self as LengthHintProtocolImpl>::__length_hint__() {
+            methods.push(def)
+        }
+        methods
+    }
 }
 
 trait PyIterIterProtocolImpl {
@@ -118,6 +149,20 @@ where
     }
 }
 
+#[doc(hidden)]
+pub trait LengthHintProtocolImpl {
+    fn __length_hint__() -> Option<PyMethodDef>;
+}
+
+impl<'p, T> LengthHintProtocolImpl for T
+where
+    T: PyIterProtocol<'p>,
+{
+    default fn __length_hint__() -> Option<PyMethodDef> {
+        None
+    }
+}
+
 struct IterNextConverter;
 
 impl<T> CallbackConverter<Option<T>> for IterNextConverter
@@ -141,3 +186,91 @@ where
         ptr::null_mut()
     }
 }
+
+/// A Python iterator over the items of a Rust iterator, e.g. to return a lazily computed sequence
+/// from a `#[pyfunction]`.
+///
+/// Items are converted when Python asks for them, and `__length_hint__` reports the lower bound
+/// of [Iterator::size_hint], so that e.g. `list(it)` preallocates.
+///
+/// ```
+/// use pyo3::class::iter::PyRustIterator;
+/// use pyo3::prelude::*;
+///
+/// #[pyfunction]
+/// fn squares(py: Python, n: u64) -> PyResult<Py<PyRustIterator>> {
+///     PyRustIterator::new(py, (0..n).map(|i| i * i))
+/// }
+/// ```
+#[pyclass(name = RustIterator, module = "pyo3")]
+pub struct PyRustIterator {
+    iter: Box<dyn Iterator<Item = PyObject> + Send>,
+}
+
+impl PyRustIterator {
+    /// Wraps `iter`, converting its items with [IntoPy].
+    pub fn new<I>(py: Python, iter: I) -> PyResult<Py<PyRustIterator>>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: IntoPy<PyObject>,
+    {
+        let iter = iter.into_iter().map(|item| {
+            // Only advanced by `__next__`, which holds the GIL
+            let py = unsafe { Python::assume_gil_acquired() };
+            item.into_py(py)
+        });
+        Py::new(
+            py,
+            PyRustIterator {
+                iter: Box::new(iter),
+            },
+        )
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for PyRustIterator {
+    fn __iter__(slf: PyRefMut<Self>) -> PyResult<Py<PyRustIterator>> {
+        Ok(slf.into())
+    }
+
+    fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        Ok(slf.iter.next())
+    }
+
+    fn __length_hint__(&self) -> PyResult<usize> {
+        Ok(self.iter.size_hint().0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyRustIterator;
+    use crate::{py_run, Python};
+
+    #[test]
+    fn test_rust_iterator() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let it = PyRustIterator::new(py, vec![1, 2, 3]).unwrap();
+        py_run!(
+            py,
+            it,
+            r#"
+from operator import length_hint
+assert type(it).__name__ == "RustIterator"
+assert iter(it) is it
+assert length_hint(it) == 3
+assert next(it) == 1
+assert length_hint(it) == 2
+assert list(it) == [2, 3]
+assert length_hint(it) == 0
+"#
+        );
+
+        let it = PyRustIterator::new(py, (0..).filter(|i| i % 2 == 0).take(2)).unwrap();
+        py_run!(py, it, "assert __import__('operator').length_hint(it) == 0");
+        py_run!(py, it, "assert list(it) == [0, 2]");
+    }
+}
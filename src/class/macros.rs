@@ -117,12 +117,15 @@ macro_rules! py_binary_num_func {
             let lhs = py.from_borrowed_ptr::<$crate::types::PyAny>(lhs);
             let rhs = py.from_borrowed_ptr::<$crate::types::PyAny>(rhs);
 
-            let result = match lhs.extract() {
-                Ok(lhs) => match rhs.extract() {
-                    Ok(rhs) => $class::$f(lhs, rhs).into(),
-                    Err(e) => Err(e.into()),
-                },
-                Err(e) => Err(e.into()),
+            // Operands of unsupported types let Python try the reflected operation, while other
+            // extraction errors, e.g. an `OverflowError`, are raised
+            let operands = lhs.extract().and_then(|lhs| Ok((lhs, rhs.extract()?)));
+            let result = match operands {
+                Ok((lhs, rhs)) => $class::$f(lhs, rhs).into(),
+                Err(e) if e.is_instance::<$crate::exceptions::TypeError>(py) => {
+                    return $crate::IntoPyPointer::into_ptr(py.NotImplemented())
+                }
+                Err(e) => Err(e),
             };
             $crate::callback::cb_convert($conv, py, result)
         }
@@ -1,6 +1,7 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 use crate::ffi;
+use crate::{PyObject, PyResult, Python};
 use libc::c_int;
 use std::ffi::CString;
 
@@ -22,12 +23,29 @@ pub enum PyMethodDefType {
     Getter(PyGetterDef),
     /// Represents setter descriptor, used by `#[setter]`
     Setter(PySetterDef),
+    /// Represents a class attribute, used by `#[classattr]`
+    ClassAttribute(PyClassAttributeDef),
+    /// Wraps a definition that is only used when the running interpreter is at least
+    /// the given `(major, minor)` version, used by `#[pyo3(min_python = "...")]`
+    MinPython((u8, u8), fn() -> &'static PyMethodDefType),
+    /// Wraps the definition of an abstract method, used by `#[abstractmethod]`
+    Abstract(fn() -> &'static PyMethodDefType),
+    /// Wraps the definition of a method Python subclasses must not override, used by
+    /// `#[pyo3(final)]`
+    Final(fn() -> &'static PyMethodDefType),
 }
 
 #[derive(Copy, Clone, Debug)]
 pub enum PyMethodType {
     PyCFunction(ffi::PyCFunction),
     PyCFunctionWithKeywords(ffi::PyCFunctionWithKeywords),
+    /// A function using the `METH_FASTCALL | METH_KEYWORDS` calling convention, which avoids
+    /// building a tuple and a dict for every call, and the same function using
+    /// `METH_VARARGS | METH_KEYWORDS` for the Python versions where PyO3 doesn't use the former
+    PyCFunctionFastWithKeywords(
+        ffi::_PyCFunctionFastWithKeywords,
+        ffi::PyCFunctionWithKeywords,
+    ),
     PyNoArgsFunction(ffi::PyNoArgsFunction),
     PyNewFunc(ffi::newfunc),
     PyInitFunc(ffi::initproc),
@@ -55,6 +73,13 @@ pub struct PySetterDef {
     pub doc: &'static str,
 }
 
+/// A class attribute, whose value is computed once when the type object is initialized.
+#[derive(Copy, Clone, Debug)]
+pub struct PyClassAttributeDef {
+    pub name: &'static str,
+    pub meth: for<'p> fn(Python<'p>) -> PyResult<PyObject>,
+}
+
 unsafe impl Sync for PyMethodDef {}
 
 unsafe impl Sync for ffi::PyMethodDef {}
@@ -65,23 +90,53 @@ unsafe impl Sync for PySetterDef {}
 
 unsafe impl Sync for ffi::PyGetSetDef {}
 
+impl PyMethodDefType {
+    /// Returns the Python name of methods, class methods and static methods
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match *self {
+            PyMethodDefType::Method(ref def)
+            | PyMethodDefType::Class(ref def)
+            | PyMethodDefType::Static(ref def) => Some(def.ml_name),
+            _ => None,
+        }
+    }
+}
+
+impl PyMethodType {
+    /// Returns the C function Python calls, and `flags` adjusted to its calling convention.
+    pub(crate) fn c_function(self, flags: c_int) -> (ffi::PyCFunction, c_int) {
+        match self {
+            PyMethodType::PyCFunction(meth) => (meth, flags),
+            PyMethodType::PyCFunctionWithKeywords(meth) => {
+                (unsafe { std::mem::transmute(meth) }, flags)
+            }
+            #[cfg(all(Py_3_8, not(Py_LIMITED_API), not(PyPy)))]
+            PyMethodType::PyCFunctionFastWithKeywords(meth, _) => (
+                unsafe { std::mem::transmute(meth) },
+                flags & !ffi::METH_VARARGS | ffi::METH_FASTCALL,
+            ),
+            #[cfg(not(all(Py_3_8, not(Py_LIMITED_API), not(PyPy))))]
+            PyMethodType::PyCFunctionFastWithKeywords(_, meth) => {
+                (unsafe { std::mem::transmute(meth) }, flags)
+            }
+            PyMethodType::PyNoArgsFunction(meth) => (unsafe { std::mem::transmute(meth) }, flags),
+            PyMethodType::PyNewFunc(meth) => (unsafe { std::mem::transmute(meth) }, flags),
+            PyMethodType::PyInitFunc(meth) => (unsafe { std::mem::transmute(meth) }, flags),
+        }
+    }
+}
+
 impl PyMethodDef {
     /// Convert `PyMethodDef` to Python method definition struct `ffi::PyMethodDef`
     pub fn as_method_def(&self) -> ffi::PyMethodDef {
-        let meth = match self.ml_meth {
-            PyMethodType::PyCFunction(meth) => meth,
-            PyMethodType::PyCFunctionWithKeywords(meth) => unsafe { std::mem::transmute(meth) },
-            PyMethodType::PyNoArgsFunction(meth) => unsafe { std::mem::transmute(meth) },
-            PyMethodType::PyNewFunc(meth) => unsafe { std::mem::transmute(meth) },
-            PyMethodType::PyInitFunc(meth) => unsafe { std::mem::transmute(meth) },
-        };
+        let (meth, flags) = self.ml_meth.c_function(self.ml_flags);
 
         ffi::PyMethodDef {
             ml_name: CString::new(self.ml_name)
                 .expect("Method name must not contain NULL byte")
                 .into_raw(),
             ml_meth: Some(meth),
-            ml_flags: self.ml_flags,
+            ml_flags: flags,
             ml_doc: self.ml_doc.as_ptr() as *const _,
         }
     }
@@ -145,9 +200,15 @@ where
     T: PyMethodsInventoryDispatch,
 {
     fn py_methods() -> Vec<&'static PyMethodDefType> {
+        let (major, minor, _) = crate::python::runtime_version();
         inventory::iter::<T::InventoryType>
             .into_iter()
             .flat_map(PyMethodsInventory::get_methods)
+            .filter_map(|def| match *def {
+                PyMethodDefType::MinPython(min, def) if (major, minor) >= min => Some(def()),
+                PyMethodDefType::MinPython(..) => None,
+                _ => Some(def),
+            })
             .collect()
     }
 }
@@ -7,6 +7,7 @@ mod macros;
 
 pub mod basic;
 pub mod buffer;
+pub mod compare;
 pub mod context;
 pub mod descr;
 pub mod gc;
@@ -14,17 +15,26 @@ pub mod iter;
 pub mod mapping;
 pub mod methods;
 pub mod number;
+pub mod operators;
+pub mod ops;
 pub mod pyasync;
+pub mod repr;
 pub mod sequence;
 
 pub use self::basic::PyObjectProtocol;
 pub use self::buffer::PyBufferProtocol;
+pub use self::compare::{PyClassEq, PyClassHash, PyClassOrd};
 pub use self::context::PyContextProtocol;
 pub use self::descr::PyDescrProtocol;
-pub use self::gc::{PyGCProtocol, PyTraverseError, PyVisit};
-pub use self::iter::PyIterProtocol;
+pub use self::gc::{PyGCProtocol, PyTraverse, PyTraverseError, PyVisit};
+pub use self::iter::{PyIterProtocol, PyRustIterator};
 pub use self::mapping::PyMappingProtocol;
-pub use self::methods::{PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType, PySetterDef};
-pub use self::number::PyNumberProtocol;
+pub use self::methods::{
+    PyClassAttributeDef, PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType, PySetterDef,
+};
+pub use self::number::{NotImplementedOr, PyNumberProtocol};
+pub use self::operators::{Operator, PyOperators};
+pub use self::ops::PyClassOps;
 pub use self::pyasync::PyAsyncProtocol;
+pub use self::repr::{PyClassDebugRepr, PyClassDisplayStr, PyClassFieldsRepr};
 pub use self::sequence::PySequenceProtocol;
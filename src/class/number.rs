@@ -9,7 +9,50 @@ use crate::class::methods::PyMethodDef;
 use crate::err::PyResult;
 use crate::type_object::PyTypeInfo;
 use crate::FromPyObject;
-use crate::{ffi, IntoPy, PyObject};
+use crate::{ffi, IntoPy, PyObject, Python};
+
+/// Return type for operator methods that only support some operands.
+///
+/// `NotImplemented` is returned to Python as `NotImplemented`, which makes Python try the
+/// reflected operation of the other operand (e.g. its `__radd__`) instead of failing.
+/// Note that `Option<T>` can't be used for this, as `None` is converted to Python's `None`.
+///
+/// ```rust,ignore
+/// fn __add__(lhs: &PyAny, rhs: &PyAny) -> PyResult<NotImplementedOr<f64>> {
+///     match (lhs.extract::<f64>(), rhs.extract::<f64>()) {
+///         (Ok(a), Ok(b)) => Ok(NotImplementedOr::Value(a + b)),
+///         _ => Ok(NotImplementedOr::NotImplemented),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotImplementedOr<T> {
+    /// The result of the operation
+    Value(T),
+    /// The operation is not supported for these operands
+    NotImplemented,
+}
+
+impl<T> From<Option<T>> for NotImplementedOr<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => NotImplementedOr::Value(value),
+            None => NotImplementedOr::NotImplemented,
+        }
+    }
+}
+
+impl<T> IntoPy<PyObject> for NotImplementedOr<T>
+where
+    T: IntoPy<PyObject>,
+{
+    fn into_py(self, py: Python) -> PyObject {
+        match self {
+            NotImplementedOr::Value(value) => value.into_py(py),
+            NotImplementedOr::NotImplemented => py.NotImplemented(),
+        }
+    }
+}
 
 /// Number interface
 #[allow(unused_variables)]
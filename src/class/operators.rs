@@ -0,0 +1,332 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Operator overloading through a single method, for expression builders.
+//!
+//! Query builders and symbolic math libraries overload every operator in the same way: they
+//! record the operator and its operands in a new expression object. Instead of implementing
+//! thirty methods of [PyNumberProtocol](crate::class::number::PyNumberProtocol) and
+//! [PyObjectProtocol](crate::class::basic::PyObjectProtocol), such a class implements
+//! [PyOperators], and all arithmetic, bitwise, comparison and unary operators call
+//! [PyOperators::apply] with the matching [Operator].
+//!
+//! The slots set by [PyOperators] take precedence over the ones of `#[pyproto]` implementations.
+//! As with Python classes that define `__eq__`, instances are not hashable.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//! use pyo3::class::operators::{Operator, PyOperators};
+//! use pyo3::types::PyAny;
+//!
+//! #[pyclass]
+//! struct Symbol {
+//!     expr: String,
+//! }
+//!
+//! impl PyOperators for Symbol {
+//!     fn apply(&self, py: Python, op: Operator, other: Option<&PyAny>, reflected: bool) -> PyResult<PyObject> {
+//!         let other = match other {
+//!             Some(other) => other.str()?.to_string()?.into_owned(),
+//!             None => return Ok(format!("({}{})", op.symbol(), self.expr).into_py(py)),
+//!         };
+//!         let expr = if reflected {
+//!             format!("({} {} {})", other, op.symbol(), self.expr)
+//!         } else {
+//!             format!("({} {} {})", self.expr, op.symbol(), other)
+//!         };
+//!         Ok(expr.into_py(py))
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let x = Py::new(py, Symbol { expr: "x".to_string() }).unwrap();
+//! pyo3::py_run!(py, x, r#"
+//! assert x + 1 == "(x + 1)"
+//! assert 2 ** x == "(2 ** x)"
+//! assert -x == "(-x)"
+//! assert (x <= 3) == "(x <= 3)"
+//! "#);
+//! ```
+
+use crate::err::PyResult;
+use crate::ffi;
+use crate::type_object::PyTypeInfo;
+use crate::types::PyAny;
+use crate::{IntoPyPointer, PyObject, Python};
+use std::os::raw::c_int;
+use std::ptr;
+
+/// An operator passed to [PyOperators::apply].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operator {
+    /// `+`, `__add__`
+    Add,
+    /// `-`, `__sub__`
+    Sub,
+    /// `*`, `__mul__`
+    Mul,
+    /// `@`, `__matmul__`
+    MatMul,
+    /// `/`, `__truediv__`
+    TrueDiv,
+    /// `//`, `__floordiv__`
+    FloorDiv,
+    /// `%`, `__mod__`
+    Mod,
+    /// `**`, `__pow__`
+    Pow,
+    /// `<<`, `__lshift__`
+    LShift,
+    /// `>>`, `__rshift__`
+    RShift,
+    /// `&`, `__and__`
+    And,
+    /// `|`, `__or__`
+    Or,
+    /// `^`, `__xor__`
+    Xor,
+    /// `<`, `__lt__`
+    Lt,
+    /// `<=`, `__le__`
+    Le,
+    /// `==`, `__eq__`
+    Eq,
+    /// `!=`, `__ne__`
+    Ne,
+    /// `>`, `__gt__`
+    Gt,
+    /// `>=`, `__ge__`
+    Ge,
+    /// Unary `-`, `__neg__`
+    Neg,
+    /// Unary `+`, `__pos__`
+    Pos,
+    /// `abs()`, `__abs__`
+    Abs,
+    /// `~`, `__invert__`
+    Invert,
+}
+
+impl Operator {
+    /// Returns the Python syntax of the operator, `"abs"` for [Operator::Abs].
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::MatMul => "@",
+            Operator::TrueDiv => "/",
+            Operator::FloorDiv => "//",
+            Operator::Mod => "%",
+            Operator::Pow => "**",
+            Operator::LShift => "<<",
+            Operator::RShift => ">>",
+            Operator::And => "&",
+            Operator::Or => "|",
+            Operator::Xor => "^",
+            Operator::Lt => "<",
+            Operator::Le => "<=",
+            Operator::Eq => "==",
+            Operator::Ne => "!=",
+            Operator::Gt => ">",
+            Operator::Ge => ">=",
+            Operator::Neg => "-",
+            Operator::Pos => "+",
+            Operator::Abs => "abs",
+            Operator::Invert => "~",
+        }
+    }
+
+    /// Whether the operator takes a single operand.
+    pub fn is_unary(self) -> bool {
+        match self {
+            Operator::Neg | Operator::Pos | Operator::Abs | Operator::Invert => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the operator is a rich comparison.
+    pub fn is_comparison(self) -> bool {
+        match self {
+            Operator::Lt
+            | Operator::Le
+            | Operator::Eq
+            | Operator::Ne
+            | Operator::Gt
+            | Operator::Ge => true,
+            _ => false,
+        }
+    }
+
+    fn from_compare_op(op: c_int) -> Option<Operator> {
+        match op {
+            ffi::Py_LT => Some(Operator::Lt),
+            ffi::Py_LE => Some(Operator::Le),
+            ffi::Py_EQ => Some(Operator::Eq),
+            ffi::Py_NE => Some(Operator::Ne),
+            ffi::Py_GT => Some(Operator::Gt),
+            ffi::Py_GE => Some(Operator::Ge),
+            _ => None,
+        }
+    }
+}
+
+/// Implements all operators of a class with a single method.
+pub trait PyOperators: PyTypeInfo + Sized {
+    /// Applies `op` to `self` and `other`, which is `None` for unary operators.
+    ///
+    /// `reflected` is true when `self` is the right operand, as in `__radd__`. Comparisons are
+    /// never reflected: Python swaps the operator instead, so `1 < x` calls `apply` with
+    /// [Operator::Gt]. Return `py.NotImplemented()` to let Python try the other operand.
+    fn apply(
+        &self,
+        py: Python,
+        op: Operator,
+        other: Option<&PyAny>,
+        reflected: bool,
+    ) -> PyResult<PyObject>;
+}
+
+#[doc(hidden)]
+pub trait PyOperatorsImpl {
+    fn tp_as_operators(_typeob: &mut ffi::PyTypeObject);
+}
+
+impl<T> PyOperatorsImpl for T {
+    default fn tp_as_operators(_typeob: &mut ffi::PyTypeObject) {}
+}
+
+impl<T> PyOperatorsImpl for T
+where
+    T: PyOperators,
+{
+    fn tp_as_operators(typeob: &mut ffi::PyTypeObject) {
+        if typeob.tp_as_number.is_null() {
+            typeob.tp_as_number = Box::into_raw(Box::new(ffi::PyNumberMethods_INIT));
+        }
+        let number = unsafe { &mut *typeob.tp_as_number };
+        number.nb_add = Some(add::<T>);
+        number.nb_subtract = Some(sub::<T>);
+        number.nb_multiply = Some(mul::<T>);
+        number.nb_matrix_multiply = Some(matmul::<T>);
+        number.nb_true_divide = Some(truediv::<T>);
+        number.nb_floor_divide = Some(floordiv::<T>);
+        number.nb_remainder = Some(mod_::<T>);
+        number.nb_power = Some(pow::<T>);
+        number.nb_lshift = Some(lshift::<T>);
+        number.nb_rshift = Some(rshift::<T>);
+        number.nb_and = Some(and::<T>);
+        number.nb_or = Some(or::<T>);
+        number.nb_xor = Some(xor::<T>);
+        number.nb_negative = Some(neg::<T>);
+        number.nb_positive = Some(pos::<T>);
+        number.nb_absolute = Some(abs::<T>);
+        number.nb_invert = Some(invert::<T>);
+        typeob.tp_richcompare = Some(richcompare::<T>);
+    }
+}
+
+unsafe fn apply<T: PyOperators>(
+    py: Python,
+    slf: *mut ffi::PyObject,
+    op: Operator,
+    other: *mut ffi::PyObject,
+    reflected: bool,
+) -> *mut ffi::PyObject {
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    let other = if other.is_null() {
+        None
+    } else {
+        Some(py.from_borrowed_ptr::<PyAny>(other))
+    };
+    match slf.apply(py, op, other, reflected) {
+        Ok(result) => result.into_ptr(),
+        Err(err) => {
+            err.restore(py);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn binary<T: PyOperators>(
+    lhs: *mut ffi::PyObject,
+    rhs: *mut ffi::PyObject,
+    op: Operator,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    if T::is_instance(py.from_borrowed_ptr(lhs)) {
+        apply::<T>(py, lhs, op, rhs, false)
+    } else if T::is_instance(py.from_borrowed_ptr(rhs)) {
+        apply::<T>(py, rhs, op, lhs, true)
+    } else {
+        py.NotImplemented().into_ptr()
+    }
+}
+
+macro_rules! binary_slot {
+    ($name:ident, $op:ident) => {
+        unsafe extern "C" fn $name<T: PyOperators>(
+            lhs: *mut ffi::PyObject,
+            rhs: *mut ffi::PyObject,
+        ) -> *mut ffi::PyObject {
+            binary::<T>(lhs, rhs, Operator::$op)
+        }
+    };
+}
+
+binary_slot!(add, Add);
+binary_slot!(sub, Sub);
+binary_slot!(mul, Mul);
+binary_slot!(matmul, MatMul);
+binary_slot!(truediv, TrueDiv);
+binary_slot!(floordiv, FloorDiv);
+binary_slot!(mod_, Mod);
+binary_slot!(lshift, LShift);
+binary_slot!(rshift, RShift);
+binary_slot!(and, And);
+binary_slot!(or, Or);
+binary_slot!(xor, Xor);
+
+unsafe extern "C" fn pow<T: PyOperators>(
+    lhs: *mut ffi::PyObject,
+    rhs: *mut ffi::PyObject,
+    modulo: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    // The three-argument form of `pow()` is not an operator
+    if modulo != ffi::Py_None() {
+        let py = Python::assume_gil_acquired();
+        return py.NotImplemented().into_ptr();
+    }
+    binary::<T>(lhs, rhs, Operator::Pow)
+}
+
+macro_rules! unary_slot {
+    ($name:ident, $op:ident) => {
+        unsafe extern "C" fn $name<T: PyOperators>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject {
+            let py = Python::assume_gil_acquired();
+            let _pool = crate::GILPool::new(py);
+            apply::<T>(py, slf, Operator::$op, ptr::null_mut(), false)
+        }
+    };
+}
+
+unary_slot!(neg, Neg);
+unary_slot!(pos, Pos);
+unary_slot!(abs, Abs);
+unary_slot!(invert, Invert);
+
+unsafe extern "C" fn richcompare<T: PyOperators>(
+    slf: *mut ffi::PyObject,
+    other: *mut ffi::PyObject,
+    op: c_int,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    match Operator::from_compare_op(op) {
+        Some(op) => apply::<T>(py, slf, op, other, false),
+        None => py.NotImplemented().into_ptr(),
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Python operators mapped onto the `std::ops` implementations of a class.
+//!
+//! `#[pyclass(ops(...))]` lists the `std::ops` traits a class implements, and the macro
+//! implements [PyClassOps], which sets the matching number slots. Each entry selects one
+//! implementation:
+//!
+//! * `Add` uses `impl Add for T`, calling it on clones of both operands, so `T` must be `Clone`.
+//! * `&Add` uses `impl Add<&T> for &T`, calling it on references to both operands.
+//! * `Add::<R>` uses `impl Add<R> for T` on a clone of `self`, extracting the right operand as
+//!   `R`. `&Add::<R>` uses `impl Add<R> for &T`. When the class instance is the right operand,
+//!   as in `__radd__`, they call `impl Add<T> for R` or `impl Add<&T> for R` instead, if it
+//!   exists.
+//!
+//! The binary operators are `Add`, `Sub`, `Mul`, `Div` (`/`, `__truediv__`), `Rem`, `Shl`,
+//! `Shr`, `BitAnd`, `BitOr` and `BitXor`, and the unary ones `Neg` and `Not` (`~`,
+//! `__invert__`). Their results are converted with `IntoPy<PyObject>`. The `*Assign` traits
+//! implement the augmented assignments like `__iadd__`, taking the right operand by value as
+//! above. Python falls back to the binary operator for augmented assignments without an
+//! `*Assign` entry, like for its own classes.
+//!
+//! Several entries for the same operator are tried in order, and when the right operand can't
+//! be extracted for any of them, the operator returns `NotImplemented`. The slots set here take
+//! precedence over the ones of `#[pyproto]` implementations; operators that aren't listed are
+//! left alone.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//! use std::ops::{Add, AddAssign, Mul, Neg};
+//!
+//! #[pyclass(ops(&Add, Mul::<f64>, Neg, AddAssign))]
+//! #[derive(Clone, Copy)]
+//! struct Vector {
+//!     #[pyo3(get)]
+//!     x: f64,
+//!     #[pyo3(get)]
+//!     y: f64,
+//! }
+//!
+//! impl Add for &Vector {
+//!     type Output = Vector;
+//!     fn add(self, other: &Vector) -> Vector {
+//!         Vector { x: self.x + other.x, y: self.y + other.y }
+//!     }
+//! }
+//!
+//! impl Mul<f64> for Vector {
+//!     type Output = Vector;
+//!     fn mul(self, factor: f64) -> Vector {
+//!         Vector { x: self.x * factor, y: self.y * factor }
+//!     }
+//! }
+//!
+//! // Makes `2.0 * vector` work as well
+//! impl Mul<Vector> for f64 {
+//!     type Output = Vector;
+//!     fn mul(self, vector: Vector) -> Vector {
+//!         vector * self
+//!     }
+//! }
+//!
+//! impl Neg for Vector {
+//!     type Output = Vector;
+//!     fn neg(self) -> Vector {
+//!         self * -1.0
+//!     }
+//! }
+//!
+//! impl AddAssign for Vector {
+//!     fn add_assign(&mut self, other: Vector) {
+//!         *self = &*self + &other;
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let v = Py::new(py, Vector { x: 1.0, y: 2.0 }).unwrap();
+//! pyo3::py_run!(py, v, r#"
+//! w = -(v + v * 2) + 2.0 * v
+//! assert (w.x, w.y) == (-1.0, -2.0)
+//! w += v
+//! assert (w.x, w.y) == (0.0, 0.0)
+//! "#);
+//! ```
+
+use crate::callback::{cb_convert, PyObjectCallbackConverter};
+use crate::class::operators::Operator;
+use crate::err::PyResult;
+use crate::ffi;
+use crate::type_object::PyTypeInfo;
+use crate::types::PyAny;
+use crate::{IntoPy, IntoPyPointer, PyObject, Python};
+use std::ops;
+
+/// A class whose operators use its `std::ops` implementations, implemented by
+/// `#[pyclass(ops(...))]`.
+pub trait PyClassOps: PyTypeInfo + Sized {
+    /// The binary operators the class implements.
+    const BINARY: &'static [Operator];
+    /// The unary operators the class implements.
+    const UNARY: &'static [Operator];
+    /// The binary operators the class implements augmented assignments of.
+    const INPLACE: &'static [Operator];
+
+    /// Applies the binary operator `op` to `self` and `other`, which is the left operand when
+    /// `reflected` is true. Returns `NotImplemented` when `other` has an unsupported type.
+    fn binary_op(
+        &self,
+        py: Python,
+        op: Operator,
+        other: &PyAny,
+        reflected: bool,
+    ) -> PyResult<PyObject>;
+
+    /// Applies the unary operator `op` to `self`.
+    fn unary_op(&self, py: Python, op: Operator) -> PyResult<PyObject>;
+
+    /// Applies the augmented assignment of `op` to `self`, returning whether `other` has a
+    /// supported type.
+    fn inplace_op(&mut self, py: Python, op: Operator, other: &PyAny) -> PyResult<bool>;
+}
+
+#[doc(hidden)]
+pub trait PyClassOpsImpl {
+    fn tp_as_ops(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T> PyClassOpsImpl for T {
+    default fn tp_as_ops(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T: PyClassOps> PyClassOpsImpl for T {
+    fn tp_as_ops(type_object: &mut ffi::PyTypeObject) {
+        if type_object.tp_as_number.is_null() {
+            type_object.tp_as_number = Box::into_raw(Box::new(ffi::PyNumberMethods_INIT));
+        }
+        let number = unsafe { &mut *type_object.tp_as_number };
+        for op in T::BINARY {
+            match op {
+                Operator::Add => number.nb_add = Some(add::<T>),
+                Operator::Sub => number.nb_subtract = Some(sub::<T>),
+                Operator::Mul => number.nb_multiply = Some(mul::<T>),
+                Operator::TrueDiv => number.nb_true_divide = Some(div::<T>),
+                Operator::Mod => number.nb_remainder = Some(rem::<T>),
+                Operator::LShift => number.nb_lshift = Some(shl::<T>),
+                Operator::RShift => number.nb_rshift = Some(shr::<T>),
+                Operator::And => number.nb_and = Some(bitand::<T>),
+                Operator::Or => number.nb_or = Some(bitor::<T>),
+                Operator::Xor => number.nb_xor = Some(bitxor::<T>),
+                _ => (),
+            }
+        }
+        for op in T::UNARY {
+            match op {
+                Operator::Neg => number.nb_negative = Some(neg::<T>),
+                Operator::Invert => number.nb_invert = Some(not::<T>),
+                _ => (),
+            }
+        }
+        for op in T::INPLACE {
+            match op {
+                Operator::Add => number.nb_inplace_add = Some(add_assign::<T>),
+                Operator::Sub => number.nb_inplace_subtract = Some(sub_assign::<T>),
+                Operator::Mul => number.nb_inplace_multiply = Some(mul_assign::<T>),
+                Operator::TrueDiv => number.nb_inplace_true_divide = Some(div_assign::<T>),
+                Operator::Mod => number.nb_inplace_remainder = Some(rem_assign::<T>),
+                Operator::LShift => number.nb_inplace_lshift = Some(shl_assign::<T>),
+                Operator::RShift => number.nb_inplace_rshift = Some(shr_assign::<T>),
+                Operator::And => number.nb_inplace_and = Some(bitand_assign::<T>),
+                Operator::Or => number.nb_inplace_or = Some(bitor_assign::<T>),
+                Operator::Xor => number.nb_inplace_xor = Some(bitxor_assign::<T>),
+                _ => (),
+            }
+        }
+    }
+}
+
+unsafe fn binary<T: PyClassOps>(
+    lhs: *mut ffi::PyObject,
+    rhs: *mut ffi::PyObject,
+    op: Operator,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let (slf, other, reflected) = if T::is_instance(py.from_borrowed_ptr(lhs)) {
+        (lhs, rhs, false)
+    } else if T::is_instance(py.from_borrowed_ptr(rhs)) {
+        (rhs, lhs, true)
+    } else {
+        return py.NotImplemented().into_ptr();
+    };
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    let other = py.from_borrowed_ptr::<PyAny>(other);
+    cb_convert(
+        PyObjectCallbackConverter,
+        py,
+        slf.binary_op(py, op, other, reflected),
+    )
+}
+
+unsafe fn inplace<T: PyClassOps>(
+    slf: *mut ffi::PyObject,
+    other: *mut ffi::PyObject,
+    op: Operator,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let other = py.from_borrowed_ptr::<PyAny>(other);
+    let result = py
+        .mut_from_borrowed_ptr::<T>(slf)
+        .inplace_op(py, op, other)
+        .map(|supported| {
+            if supported {
+                PyObject::from_borrowed_ptr(py, slf)
+            } else {
+                py.NotImplemented()
+            }
+        });
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+macro_rules! binary_slots {
+    ($($name:ident, $name_assign:ident, $op:ident;)*) => {
+        $(
+            unsafe extern "C" fn $name<T: PyClassOps>(
+                lhs: *mut ffi::PyObject,
+                rhs: *mut ffi::PyObject,
+            ) -> *mut ffi::PyObject {
+                binary::<T>(lhs, rhs, Operator::$op)
+            }
+
+            unsafe extern "C" fn $name_assign<T: PyClassOps>(
+                slf: *mut ffi::PyObject,
+                other: *mut ffi::PyObject,
+            ) -> *mut ffi::PyObject {
+                inplace::<T>(slf, other, Operator::$op)
+            }
+        )*
+    };
+}
+
+binary_slots! {
+    add, add_assign, Add;
+    sub, sub_assign, Sub;
+    mul, mul_assign, Mul;
+    div, div_assign, TrueDiv;
+    rem, rem_assign, Mod;
+    shl, shl_assign, LShift;
+    shr, shr_assign, RShift;
+    bitand, bitand_assign, And;
+    bitor, bitor_assign, Or;
+    bitxor, bitxor_assign, Xor;
+}
+
+macro_rules! unary_slot {
+    ($name:ident, $op:ident) => {
+        unsafe extern "C" fn $name<T: PyClassOps>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject {
+            let py = Python::assume_gil_acquired();
+            let _pool = crate::GILPool::new(py);
+            let slf = py.from_borrowed_ptr::<T>(slf);
+            cb_convert(
+                PyObjectCallbackConverter,
+                py,
+                slf.unary_op(py, Operator::$op),
+            )
+        }
+    };
+}
+
+unary_slot!(neg, Neg);
+unary_slot!(not, Invert);
+
+macro_rules! reflected_ops {
+    ($($name:ident, $trait_:ident, $method:ident;)*) => {
+        $(
+            /// Applies the operator if the left operand implements it, used for the reflected
+            /// operators of `#[pyclass(ops(...))]`.
+            #[doc(hidden)]
+            pub trait $name<Rhs> {
+                fn reflected(self, rhs: Rhs, py: Python) -> Option<PyObject>;
+            }
+
+            impl<L, Rhs> $name<Rhs> for L {
+                default fn reflected(self, _rhs: Rhs, _py: Python) -> Option<PyObject> {
+                    None
+                }
+            }
+
+            impl<L, Rhs> $name<Rhs> for L
+            where
+                L: ops::$trait_<Rhs>,
+                L::Output: IntoPy<PyObject>,
+            {
+                fn reflected(self, rhs: Rhs, py: Python) -> Option<PyObject> {
+                    Some(ops::$trait_::$method(self, rhs).into_py(py))
+                }
+            }
+        )*
+    };
+}
+
+reflected_ops! {
+    ReflectedAdd, Add, add;
+    ReflectedSub, Sub, sub;
+    ReflectedMul, Mul, mul;
+    ReflectedDiv, Div, div;
+    ReflectedRem, Rem, rem;
+    ReflectedShl, Shl, shl;
+    ReflectedShr, Shr, shr;
+    ReflectedBitAnd, BitAnd, bitand;
+    ReflectedBitOr, BitOr, bitor;
+    ReflectedBitXor, BitXor, bitxor;
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! `repr()` and `str()` derived from the Rust traits or the fields of a class.
+//!
+//! `#[pyclass(repr)]` implements `repr()` with `Debug`, and `#[pyclass(str)]` implements `str()`
+//! with `Display`. `#[pyclass(repr = "fields")]` formats instances like dataclasses do, as the
+//! name of their class followed by the `repr()` of every field converted with `ToPyObject`, e.g.
+//! `Point(x=1, y=2, label='origin')`. A field containing the instance itself is shown as `...`.
+//! Without `str`, `str()` falls back to `repr()` like for any Python class.
+//!
+//! These slots take precedence over `__repr__` and `__str__` implemented with `#[pyproto]`.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//! use std::fmt;
+//!
+//! #[pyclass(repr = "fields", str)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//!     label: Option<String>,
+//! }
+//!
+//! impl fmt::Display for Point {
+//!     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//!         write!(f, "({}, {})", self.x, self.y)
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let point = Py::new(py, Point { x: 1, y: 2, label: None }).unwrap();
+//! pyo3::py_run!(py, point, r#"
+//! assert repr(point) == "Point(x=1, y=2, label=None)"
+//! assert str(point) == "(1, 2)"
+//! "#);
+//! ```
+
+use crate::err::PyResult;
+use crate::ffi;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::type_object::PyTypeInfo;
+use crate::types::{PyAny, PyType};
+use crate::{IntoPyPointer, Python, ToPyObject};
+use std::fmt::{Debug, Display};
+use std::ptr;
+
+/// A class whose `repr()` uses `Debug`, implemented by `#[pyclass(repr)]`.
+pub trait PyClassDebugRepr: PyTypeInfo + Debug {}
+
+/// A class whose `repr()` lists its fields, implemented by `#[pyclass(repr = "fields")]`.
+pub trait PyClassFieldsRepr: PyTypeInfo {
+    /// Returns the names of the fields with their values.
+    fn repr_fields(&self, py: Python) -> Vec<(&'static str, PyObject)>;
+}
+
+/// A class whose `str()` uses `Display`, implemented by `#[pyclass(str)]`.
+pub trait PyClassDisplayStr: PyTypeInfo + Display {}
+
+#[doc(hidden)]
+pub trait PyReprImpl {
+    fn tp_as_repr(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T> PyReprImpl for T {
+    default fn tp_as_repr(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T: PyClassDebugRepr> PyReprImpl for T {
+    fn tp_as_repr(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_repr = Some(debug_repr::<T>);
+    }
+}
+
+#[doc(hidden)]
+pub trait PyFieldsReprImpl {
+    fn tp_as_fields_repr(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T> PyFieldsReprImpl for T {
+    default fn tp_as_fields_repr(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T: PyClassFieldsRepr> PyFieldsReprImpl for T {
+    fn tp_as_fields_repr(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_repr = Some(fields_repr::<T>);
+    }
+}
+
+#[doc(hidden)]
+pub trait PyStrImpl {
+    fn tp_as_str(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T> PyStrImpl for T {
+    default fn tp_as_str(_type_object: &mut ffi::PyTypeObject) {}
+}
+
+impl<T: PyClassDisplayStr> PyStrImpl for T {
+    fn tp_as_str(type_object: &mut ffi::PyTypeObject) {
+        type_object.tp_str = Some(display_str::<T>);
+    }
+}
+
+/// Converts the formatted instance into a `str`.
+unsafe fn format<T: PyTypeInfo>(
+    slf: *mut ffi::PyObject,
+    format: impl FnOnce(Python, &T) -> PyResult<String>,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let slf_ref = py.from_borrowed_ptr::<T>(slf);
+    match format(py, slf_ref) {
+        Ok(s) => s.to_object(py).into_ptr(),
+        Err(err) => {
+            err.restore(py);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe extern "C" fn debug_repr<T: PyClassDebugRepr>(
+    slf: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    format::<T>(slf, |_py, slf| Ok(format!("{:?}", slf)))
+}
+
+unsafe extern "C" fn display_str<T: PyClassDisplayStr>(
+    slf: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    format::<T>(slf, |_py, slf| Ok(slf.to_string()))
+}
+
+unsafe extern "C" fn fields_repr<T: PyClassFieldsRepr>(
+    slf: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    // Like `reprlib.recursive_repr`, which dataclasses use
+    match ffi::Py_ReprEnter(slf) {
+        0 => (),
+        1 => return "...".to_object(Python::assume_gil_acquired()).into_ptr(),
+        _ => return ptr::null_mut(),
+    }
+    let result = format::<T>(slf, |py, slf_ref| {
+        // The name of a Python subclass for its instances
+        let class = PyType::from_type_ptr(py, ffi::Py_TYPE(slf));
+        let mut fields = Vec::new();
+        for (name, value) in slf_ref.repr_fields(py) {
+            let value: &PyAny = value.cast_as(py)?;
+            fields.push(format!("{}={}", name, value.repr()?.to_string()?));
+        }
+        Ok(format!("{}({})", class.name(), fields.join(", ")))
+    });
+    ffi::Py_ReprLeave(slf);
+    result
+}
@@ -4,15 +4,23 @@
 
 //! Functionality for the code generated by the derive backend
 
-use crate::err::PyResult;
-use crate::exceptions::TypeError;
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{RuntimeError, TypeError};
 use crate::init_once;
 use crate::instance::PyNativeType;
-use crate::types::{PyAny, PyDict, PyModule, PyTuple};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::module::ModuleState;
+use crate::types::typeobject::import_qualified;
+use crate::types::{PyAny, PyDict, PyModule, PyTuple, PyType};
 use crate::GILPool;
 use crate::Python;
-use crate::{ffi, IntoPy, PyObject};
+use crate::{ffi, AsPyPointer, FromPyObject, IntoPy, IntoPyPointer, PyObject, ToPyObject};
+use std::cell::UnsafeCell;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_int, c_void};
 use std::ptr;
+use std::slice;
 
 /// Description of a python parameter; used for `parse_args()`.
 #[derive(Debug)]
@@ -21,18 +29,22 @@ pub struct ParamDescription {
     pub name: &'static str,
     /// Whether the parameter is optional.
     pub is_optional: bool,
-    /// Whether the parameter is optional.
+    /// Whether the parameter can only be passed by keyword.
     pub kw_only: bool,
+    /// Whether the parameter can only be passed by position.
+    pub pos_only: bool,
 }
 
 /// Parse argument list
 ///
 /// * fname:  Name of the current function
-/// * params: Declared parameters of the function
+/// * params: Declared parameters of the function, the positional ones first
 /// * args:   Positional arguments
 /// * kwargs: Keyword arguments
 /// * output: Output array that receives the arguments.
 ///           Must have same length as `params` and must be initialized to `None`.
+///
+/// The errors are `TypeError`s worded like the ones of Python functions.
 pub fn parse_fn_args<'p>(
     fname: Option<&str>,
     params: &[ParamDescription],
@@ -42,85 +54,362 @@ pub fn parse_fn_args<'p>(
     accept_kwargs: bool,
     output: &mut [Option<&'p PyAny>],
 ) -> PyResult<(&'p PyTuple, Option<&'p PyDict>)> {
+    let py = args.py();
+    // Copy kwargs not to modify it
+    let kwargs = match kwargs {
+        Some(k) => Some(k.copy()?),
+        None => None,
+    };
+    let (used_args, kwargs) = parse_args(
+        py,
+        fname,
+        params,
+        args.as_slice(),
+        kwargs,
+        accept_args,
+        accept_kwargs,
+        output,
+    )?;
+    // Adjust the remaining args
+    let args = if accept_args {
+        let slice = args
+            .slice(used_args as isize, args.len() as isize)
+            .into_py(py);
+        py.checked_cast_as(slice).unwrap()
+    } else {
+        args
+    };
+    Ok((args, kwargs))
+}
+
+/// Parse argument list passed with the `METH_FASTCALL | METH_KEYWORDS` calling convention
+///
+/// `args` points to the `nargs` positional arguments, followed by the values of the keyword
+/// arguments whose names are in the tuple `kwnames`, which is null without keyword arguments.
+/// The other parameters are those of [parse_fn_args]. The tuple of the remaining positional
+/// arguments is empty unless `accept_args` is set.
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn parse_fn_args_fastcall<'p>(
+    py: Python<'p>,
+    fname: Option<&str>,
+    params: &[ParamDescription],
+    args: *const *mut ffi::PyObject,
+    nargs: ffi::Py_ssize_t,
+    kwnames: *mut ffi::PyObject,
+    accept_args: bool,
+    accept_kwargs: bool,
+    output: &mut [Option<&'p PyAny>],
+) -> PyResult<(&'p PyTuple, Option<&'p PyDict>)> {
+    let kwnames: Option<&PyTuple> = py.from_borrowed_ptr_or_opt(kwnames);
+    let nkwargs = kwnames.map_or(0, |kwnames| kwnames.len());
+    let nargs = nargs as usize;
+    let args: &[PyObject] = if args.is_null() {
+        &[]
+    } else {
+        // `PyObject` has the same memory layout as `*mut ffi::PyObject`
+        slice::from_raw_parts(args as *const PyObject, nargs + nkwargs)
+    };
+    let (args, values) = args.split_at(nargs);
+    // Keyword arguments still go through a dict, positional ones are what makes calls fast
+    let kwargs = match kwnames {
+        Some(kwnames) if nkwargs > 0 => {
+            let kwargs = PyDict::new(py);
+            for (name, value) in kwnames.as_slice().iter().zip(values) {
+                kwargs.set_item(name, value)?;
+            }
+            Some(kwargs)
+        }
+        _ => None,
+    };
+    let (used_args, kwargs) = parse_args(
+        py,
+        fname,
+        params,
+        args,
+        kwargs,
+        accept_args,
+        accept_kwargs,
+        output,
+    )?;
+    let args = if accept_args {
+        PyTuple::new(py, &args[used_args..])
+    } else {
+        PyTuple::empty(py)
+    };
+    Ok((args, kwargs))
+}
+
+/// The parsing shared by [parse_fn_args] and [parse_fn_args_fastcall], which removes the
+/// arguments it assigns from `kwargs`. Returns the number of positional arguments assigned to
+/// parameters, and the remaining keyword arguments.
+#[allow(clippy::too_many_arguments)]
+fn parse_args<'p>(
+    py: Python<'p>,
+    fname: Option<&str>,
+    params: &[ParamDescription],
+    args: &'p [PyObject],
+    kwargs: Option<&'p PyDict>,
+    accept_args: bool,
+    accept_kwargs: bool,
+    output: &mut [Option<&'p PyAny>],
+) -> PyResult<(usize, Option<&'p PyDict>)> {
     let nargs = args.len();
-    let mut used_args = 0;
     macro_rules! raise_error {
         ($s: expr $(,$arg:expr)*) => (return Err(TypeError::py_err(format!(
             concat!("{} ", $s), fname.unwrap_or("function") $(,$arg)*
         ))))
     }
-    // Copy kwargs not to modify it
-    let kwargs = match kwargs {
-        Some(k) => Some(k.copy()?),
-        None => None,
-    };
+    let positional = params.iter().filter(|p| !p.kw_only).count();
+    // Raise an error when we get too many positional args
+    if !accept_args && nargs > positional {
+        let required = params
+            .iter()
+            .filter(|p| !p.kw_only && !p.is_optional)
+            .count();
+        raise_error!(
+            "takes {}{} positional argument{} but {} {} given",
+            if required < positional {
+                format!("from {} to ", required)
+            } else {
+                String::new()
+            },
+            positional,
+            if positional == 1 { "" } else { "s" },
+            nargs,
+            if nargs == 1 { "was" } else { "were" }
+        )
+    }
+    let mut missing_positional = Vec::new();
+    let mut missing_kw_only = Vec::new();
+    let mut pos_only_passed_by_keyword = Vec::new();
     // Iterate through the parameters and assign values to output:
     for (i, (p, out)) in params.iter().zip(output).enumerate() {
-        *out = match kwargs.and_then(|d| d.get_item(p.name)) {
-            Some(kwarg) => {
-                if i < nargs {
-                    raise_error!("got multiple values for argument: {}", p.name)
-                }
-                kwargs.as_ref().unwrap().del_item(p.name).unwrap();
-                Some(kwarg)
+        let kwarg = kwargs.and_then(|d| d.get_item(p.name));
+        if p.pos_only {
+            // With **kwargs, a keyword argument of the same name goes into kwargs
+            if kwarg.is_some() && !accept_kwargs {
+                pos_only_passed_by_keyword.push(p.name);
             }
-            None => {
+        } else if let Some(kwarg) = kwarg {
+            if !p.kw_only && i < nargs {
+                raise_error!("got multiple values for argument '{}'", p.name)
+            }
+            kwargs.as_ref().unwrap().del_item(p.name)?;
+            *out = Some(kwarg);
+            continue;
+        }
+        *out = if !p.kw_only && i < nargs {
+            Some(unsafe { py.from_borrowed_ptr(args[i].as_ptr()) })
+        } else {
+            if !p.is_optional {
                 if p.kw_only {
-                    if !p.is_optional {
-                        raise_error!("missing required keyword-only argument: {}", p.name)
-                    }
-                    None
-                } else if i < nargs {
-                    used_args += 1;
-                    Some(args.get_item(i))
+                    missing_kw_only.push(p.name);
                 } else {
-                    if !p.is_optional {
-                        raise_error!("missing required positional argument: {}", p.name)
-                    }
-                    None
+                    missing_positional.push(p.name);
                 }
             }
-        }
+            None
+        };
+    }
+    if !pos_only_passed_by_keyword.is_empty() {
+        raise_error!(
+            "got some positional-only arguments passed as keyword arguments: {}",
+            quoted_names(&pos_only_passed_by_keyword)
+        )
     }
     let is_kwargs_empty = kwargs.as_ref().map_or(true, |dict| dict.is_empty());
     // Raise an error when we get an unknown key
     if !accept_kwargs && !is_kwargs_empty {
         let (key, _) = kwargs.unwrap().iter().next().unwrap();
-        raise_error!("got an unexpected keyword argument: {}", key)
+        raise_error!("got an unexpected keyword argument '{}'", key)
     }
-    // Raise an error when we get too many positional args
-    if !accept_args && used_args < nargs {
-        raise_error!(
-            "takes at most {} positional argument{} ({} given)",
-            used_args,
-            if used_args == 1 { "" } else { "s" },
-            nargs
-        )
+    for (missing, kind) in &[
+        (missing_positional, "positional"),
+        (missing_kw_only, "keyword-only"),
+    ] {
+        if !missing.is_empty() {
+            raise_error!(
+                "missing {} required {} argument{}: {}",
+                missing.len(),
+                kind,
+                if missing.len() == 1 { "" } else { "s" },
+                quoted_names(missing)
+            )
+        }
     }
-    // Adjust the remaining args
-    let args = if accept_args {
-        let py = args.py();
-        let slice = args.slice(used_args as isize, nargs as isize).into_py(py);
-        py.checked_cast_as(slice).unwrap()
-    } else {
-        args
-    };
     let kwargs = if accept_kwargs && is_kwargs_empty {
         None
     } else {
         kwargs
     };
-    Ok((args, kwargs))
+    Ok((nargs.min(positional), kwargs))
+}
+
+/// Formats names like Python's argument errors, e.g. `'a' and 'b'` or `'a', 'b', and 'c'`.
+fn quoted_names(names: &[&str]) -> String {
+    let quoted: Vec<String> = names.iter().map(|name| format!("'{}'", name)).collect();
+    match quoted.split_last() {
+        Some((last, [first])) => format!("{} and {}", first, last),
+        Some((last, rest)) if !rest.is_empty() => format!("{}, and {}", rest.join(", "), last),
+        _ => quoted.join(""),
+    }
+}
+
+/// Adds the name of the argument to a `TypeError` raised when extracting it, e.g.
+/// `argument 'a': 'str' object cannot be interpreted as an integer`.
+pub fn argument_extraction_error(py: Python, name: &str, error: PyErr) -> PyErr {
+    if error.is_instance::<TypeError>(py) {
+        let message = error.to_object(py);
+        let message: &PyAny = unsafe { py.from_owned_ptr(message.into_ptr()) };
+        TypeError::py_err(format!("argument '{}': {}", name, message))
+    } else {
+        error
+    }
+}
+
+/// Data borrowed by an argument extracted as a reference, see [check_borrows].
+pub trait BorrowedData {
+    /// Returns the address and the size of the borrowed data, or `None` if there is none.
+    fn borrowed_data(&self) -> Option<(usize, usize)>;
+}
+
+impl<T: ?Sized> BorrowedData for &T {
+    fn borrowed_data(&self) -> Option<(usize, usize)> {
+        Some((
+            *self as *const T as *const u8 as usize,
+            std::mem::size_of_val(*self),
+        ))
+    }
+}
+
+impl<T: ?Sized> BorrowedData for &mut T {
+    fn borrowed_data(&self) -> Option<(usize, usize)> {
+        Some((
+            &**self as *const T as *const u8 as usize,
+            std::mem::size_of_val(&**self),
+        ))
+    }
+}
+
+impl<T: BorrowedData> BorrowedData for Option<T> {
+    fn borrowed_data(&self) -> Option<(usize, usize)> {
+        self.as_ref().and_then(BorrowedData::borrowed_data)
+    }
+}
+
+/// An argument extracted as a reference, or the `self` of a method.
+pub struct ArgBorrow {
+    name: &'static str,
+    data: Option<(usize, usize)>,
+    mutable: bool,
+}
+
+impl ArgBorrow {
+    pub fn new<A: BorrowedData>(name: &'static str, arg: &A, mutable: bool) -> ArgBorrow {
+        ArgBorrow {
+            name,
+            data: arg.borrowed_data(),
+            mutable,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.name {
+            "self" => "self".to_string(),
+            name => format!("argument '{}'", name),
+        }
+    }
+}
+
+/// Raises `RuntimeError` if an argument borrowed mutably refers to the same data as another
+/// argument, e.g. when a method taking `&mut self` and `other: &Self` is called as `a.f(a)`.
+///
+/// Pyclass instances can be extracted as `&T` and `&mut T` because of this check, which makes
+/// calls that would alias a mutable reference fail instead of being undefined behavior. Only the
+/// arguments of one call are compared, so borrows held by an outer call on the same object, e.g.
+/// when a method calls back into Python, aren't detected.
+pub fn check_borrows(borrows: &[ArgBorrow]) -> PyResult<()> {
+    for (i, first) in borrows.iter().enumerate() {
+        for second in &borrows[i + 1..] {
+            if !first.mutable && !second.mutable {
+                continue;
+            }
+            let overlap = match (first.data, second.data) {
+                (Some((a, a_len)), Some((b, b_len))) => {
+                    a_len != 0 && b_len != 0 && a < b + b_len && b < a + a_len
+                }
+                _ => false,
+            };
+            if overlap {
+                let (borrowed, other) = if first.mutable {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                return Err(RuntimeError::py_err(format!(
+                    "{} is the same object as {}, which is borrowed mutably",
+                    other.describe(),
+                    borrowed.describe()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What `#[pymodule]` records about the build of the crate defining the module, for its
+/// `__build__` attribute.
+pub struct BuildInfo {
+    pub crate_name: &'static str,
+    pub crate_version: &'static str,
+    /// The `GIT_HASH` environment variable when the crate was compiled
+    pub git_hash: Option<&'static str>,
+    pub debug: bool,
+}
+
+/// The features of pyo3 which were enabled, for `__build__`.
+const PYO3_FEATURES: &[(&str, bool)] = &[
+    ("extension-module", cfg!(feature = "extension-module")),
+    ("delay-load", cfg!(feature = "delay-load")),
+    ("dynamic-python", cfg!(feature = "dynamic-python")),
+    ("runtime-checks", cfg!(feature = "runtime-checks")),
+    ("unchecked", cfg!(feature = "unchecked")),
+    ("pydantic", cfg!(feature = "pydantic")),
+    ("stubs", cfg!(feature = "stubs")),
+    ("rest-docstrings", cfg!(feature = "rest-docstrings")),
+    ("unsound-subclass", cfg!(feature = "unsound-subclass")),
+];
+
+/// Sets the `__build__` attribute of `module`, a dict describing the binary: the crate's name,
+/// version and git hash, whether it's a debug build, and the rustc, pyo3 and Python versions
+/// and pyo3 features it was compiled with.
+fn add_build_info(module: &PyModule, build: &BuildInfo) -> PyResult<()> {
+    let py = module.py();
+    let info = PyDict::new(py);
+    info.set_item("crate", build.crate_name)?;
+    info.set_item("version", build.crate_version)?;
+    info.set_item("git_hash", build.git_hash)?;
+    info.set_item("profile", if build.debug { "debug" } else { "release" })?;
+    info.set_item("rustc", env!("PYO3_RUSTC_VERSION"))?;
+    info.set_item("pyo3", env!("CARGO_PKG_VERSION"))?;
+    info.set_item("python", env!("PYO3_PYTHON_VERSION"))?;
+    let features: Vec<&str> = PYO3_FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(feature, _)| *feature)
+        .collect();
+    info.set_item("pyo3_features", features)?;
+    module.setattr("__build__", info)
 }
 
 /// Builds a module (or null) from a user given initializer. Used for `#[pymodule]`.
 pub unsafe fn make_module(
     name: &str,
     doc: &str,
+    doc_index: bool,
+    build: &BuildInfo,
     initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
 ) -> *mut ffi::PyObject {
-    use crate::IntoPyPointer;
-
     init_once();
 
     #[cfg(py_sys_config = "WITH_THREAD")]
@@ -150,17 +439,239 @@ pub unsafe fn make_module(
     };
 
     module
-        .add("__doc__", doc)
+        .setattr("__doc__", doc)
         .expect("Failed to add doc for module");
-    match initializer(py, module) {
+    let result = add_build_info(module, build)
+        .and_then(|_| initializer(py, module))
+        .and_then(|_| {
+            if doc_index {
+                add_doc_index(module, doc)
+            } else {
+                Ok(())
+            }
+        });
+    match result {
         Ok(_) => module.into_ptr(),
         Err(e) => {
-            e.restore(py);
+            let name = name.trim_end_matches('\0');
+            crate::types::module::init_failure_error(py, name, e).restore(py);
             ptr::null_mut()
         }
     }
 }
 
+/// The definition of a `#[pymodule]` using multi-phase initialization (PEP 489).
+///
+/// Python creates the module object itself and calls the exec slot on it, once per import, so
+/// the module can be loaded into several interpreters and reloaded.
+pub struct ModuleDef {
+    def: UnsafeCell<ffi::PyModuleDef>,
+    slots: UnsafeCell<[ffi::PyModuleDef_Slot; 2]>,
+}
+
+// The definition is only modified while holding the GIL
+unsafe impl Sync for ModuleDef {}
+
+impl ModuleDef {
+    pub const fn new() -> ModuleDef {
+        ModuleDef {
+            def: UnsafeCell::new(ffi::PyModuleDef_INIT),
+            slots: UnsafeCell::new([
+                ffi::PyModuleDef_Slot {
+                    slot: 0,
+                    value: ptr::null_mut(),
+                },
+                ffi::PyModuleDef_Slot {
+                    slot: 0,
+                    value: ptr::null_mut(),
+                },
+            ]),
+        }
+    }
+
+    /// Returns the initialized definition, which is what `PyInit_*` returns for multi-phase
+    /// initialization.
+    pub unsafe fn init(
+        &'static self,
+        name: &'static str,
+        exec: unsafe extern "C" fn(*mut ffi::PyObject) -> c_int,
+    ) -> *mut ffi::PyObject {
+        self.init_def(name, exec, 0, None)
+    }
+
+    /// Like [init](ModuleDef::init), for a module with a state of type `T`, used for
+    /// `#[pyo3(multi_phase, state = "T")]`. `exec` must call [init_module_state] first.
+    pub unsafe fn init_with_state<T: Default + Send + 'static>(
+        &'static self,
+        name: &'static str,
+        exec: unsafe extern "C" fn(*mut ffi::PyObject) -> c_int,
+    ) -> *mut ffi::PyObject {
+        // Python allocates the state with `PyMem_Malloc`
+        assert!(
+            mem::align_of::<ModuleState<T>>() <= 2 * mem::size_of::<usize>(),
+            "The alignment of the module state is too large"
+        );
+        let size = mem::size_of::<ModuleState<T>>() as ffi::Py_ssize_t;
+        self.init_def(name, exec, size, Some(ModuleState::<T>::free))
+    }
+
+    unsafe fn init_def(
+        &'static self,
+        name: &'static str,
+        exec: unsafe extern "C" fn(*mut ffi::PyObject) -> c_int,
+        size: ffi::Py_ssize_t,
+        free: Option<ffi::freefunc>,
+    ) -> *mut ffi::PyObject {
+        init_once();
+
+        let slots = &mut *self.slots.get();
+        slots[0] = ffi::PyModuleDef_Slot {
+            slot: ffi::Py_mod_exec,
+            value: exec as *mut c_void,
+        };
+        let def = &mut *self.def.get();
+        def.m_name = name.as_ptr() as *const _;
+        def.m_size = size;
+        def.m_free = free;
+        def.m_slots = slots.as_mut_ptr();
+        ffi::PyModuleDef_Init(def)
+    }
+}
+
+/// Creates the state of a module declared with `#[pyo3(multi_phase, state = "T")]`, before the
+/// initializer runs.
+pub unsafe fn init_module_state<T: Default + Send + 'static>(module: *mut ffi::PyObject) {
+    ModuleState::init(module, T::default());
+}
+
+/// Runs a user given initializer on a module created by Python. Used for the exec slot of a
+/// `#[pymodule]` using multi-phase initialization.
+pub unsafe fn exec_module(
+    module: *mut ffi::PyObject,
+    doc: &str,
+    doc_index: bool,
+    build: &BuildInfo,
+    initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
+) -> c_int {
+    let py = Python::assume_gil_acquired();
+    let _pool = GILPool::new(py);
+    let module = py.from_borrowed_ptr::<PyModule>(module);
+
+    let result = module
+        .setattr("__doc__", doc)
+        .and_then(|_| add_build_info(module, build))
+        .and_then(|_| initializer(py, module))
+        .and_then(|_| {
+            if doc_index {
+                add_doc_index(module, doc)
+            } else {
+                Ok(())
+            }
+        });
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            let name = module.name().unwrap_or("<unknown>").to_string();
+            crate::types::module::init_failure_error(py, &name, e).restore(py);
+            -1
+        }
+    }
+}
+
+/// Sets the docstring of `module` to `doc` followed by an index of the module's classes and
+/// functions, with their signatures and the first line of their documentation. Used for
+/// `#[pyo3(doc_index)]`.
+fn add_doc_index(module: &PyModule, doc: &str) -> PyResult<()> {
+    let mut classes = Vec::new();
+    let mut functions = Vec::new();
+    for (name, value) in module.dict().iter() {
+        let name: &str = name.extract()?;
+        if name.starts_with('_') {
+            continue;
+        }
+        if value.downcast_ref::<PyType>().is_ok() {
+            classes.push(doc_index_entry(name, value, "")?);
+        } else if unsafe { ffi::PyCFunction_Check(value.as_ptr()) } != 0 {
+            functions.push(doc_index_entry(name, value, "(...)")?);
+        }
+    }
+
+    let mut index = doc.trim_end().to_string();
+    for (title, entries) in &[("Classes", classes), ("Functions", functions)] {
+        if entries.is_empty() {
+            continue;
+        }
+        if !index.is_empty() {
+            index.push_str("\n\n");
+        }
+        index.push_str(title);
+        index.push('\n');
+        index.push_str(&"-".repeat(title.len()));
+        for entry in entries {
+            index.push('\n');
+            index.push_str(entry);
+        }
+    }
+    module.setattr("__doc__", index)
+}
+
+/// Renders `name(signature)` and the summary of `value`'s documentation, indented below it.
+fn doc_index_entry(name: &str, value: &PyAny, unknown_signature: &str) -> PyResult<String> {
+    let signature = value
+        .getattr("__text_signature__")
+        .ok()
+        .and_then(|signature| signature.extract::<String>().ok());
+    let mut entry = name.to_string();
+    match signature {
+        Some(ref signature) if signature.starts_with("($") => {
+            // The receiver, e.g. `$module` or `$self`, isn't passed by the caller
+            let mut params = signature
+                .find(", ")
+                .map_or(")", |end| &signature[end + 2..]);
+            if params.starts_with("/, ") {
+                params = &params[3..];
+            } else if params == "/)" {
+                params = ")";
+            }
+            entry.push('(');
+            entry.push_str(params);
+        }
+        Some(signature) => entry.push_str(&signature),
+        None => entry.push_str(unknown_signature),
+    }
+    let doc = value.getattr("__doc__")?.extract::<Option<&str>>()?;
+    if let Some(summary) = doc.and_then(|doc| doc.lines().map(str::trim).find(|l| !l.is_empty())) {
+        entry.push_str("\n    ");
+        entry.push_str(summary);
+    }
+    Ok(entry)
+}
+
+/// Turns the result of a `PyInit_*` function into a module. Used by `wrap_pymodule!`.
+///
+/// For multi-phase initialization, `PyInit_*` returns the module definition, from which the
+/// module is created and executed the way the import system does it.
+pub unsafe fn module_from_init(py: Python, init: *mut ffi::PyObject) -> PyObject {
+    if init.is_null() || ffi::PyObject_TypeCheck(init, &mut ffi::PyModuleDef_Type) == 0 {
+        return PyObject::from_owned_ptr_or_panic(py, init);
+    }
+    let def = init as *mut ffi::PyModuleDef;
+    let create = || -> PyResult<PyObject> {
+        let name = CStr::from_ptr((*def).m_name).to_string_lossy();
+        let spec = py
+            .import("importlib.machinery")?
+            .call1("ModuleSpec", (name.as_ref(), py.None()))?;
+        let module =
+            PyObject::from_owned_ptr_or_err(py, ffi::PyModule_FromDefAndSpec(def, spec.as_ptr()))?;
+        crate::err::error_on_minusone(py, ffi::PyModule_ExecDef(module.as_ptr(), def))?;
+        Ok(module)
+    };
+    create().unwrap_or_else(|e| {
+        e.restore(py);
+        crate::err::panic_after_error()
+    })
+}
+
 /// This trait wraps a T: IntoPy<PyObject> into PyResult<T> while PyResult<T> remains PyResult<T>.
 ///
 /// This is necessary because proc macros run before typechecking and can't decide
@@ -181,3 +692,94 @@ impl<T: IntoPy<PyObject>> IntoPyResult<T> for PyResult<T> {
         self
     }
 }
+
+/// Where `#[derive(FromPyObject)]` reads the fields of a struct from.
+enum FieldKind<'p> {
+    /// A dataclass or attrs instance, with the names of its declared fields
+    Declared(Vec<String>),
+    Dict(&'p PyDict),
+    Attributes,
+}
+
+/// The object a struct deriving `FromPyObject` is extracted from.
+pub struct FieldSource<'p> {
+    obj: &'p PyAny,
+    kind: FieldKind<'p>,
+}
+
+impl<'p> FieldSource<'p> {
+    pub fn new(obj: &'p PyAny) -> PyResult<FieldSource<'p>> {
+        let py = obj.py();
+        // Classes carry the same metadata as their instances, but aren't instances themselves
+        let is_instance = !py.is_instance::<PyType, _>(obj)?;
+        let kind = if let Ok(dict) = obj.downcast_ref::<PyDict>() {
+            FieldKind::Dict(dict)
+        } else if is_instance && obj.hasattr("__dataclass_fields__")? {
+            let fields = py.import("dataclasses")?.call1("fields", (obj,))?;
+            FieldKind::Declared(field_names(fields)?)
+        } else if is_instance && obj.hasattr("__attrs_attrs__")? {
+            FieldKind::Declared(field_names(obj.getattr("__attrs_attrs__")?)?)
+        } else {
+            FieldKind::Attributes
+        };
+        Ok(FieldSource { obj, kind })
+    }
+
+    fn get(&self, name: &str) -> PyResult<Option<&'p PyAny>> {
+        match self.kind {
+            FieldKind::Declared(ref names) => {
+                if names.iter().any(|n| n == name) {
+                    self.obj.getattr(name).map(Some)
+                } else {
+                    Ok(None)
+                }
+            }
+            FieldKind::Dict(dict) => Ok(dict.get_item(name)),
+            FieldKind::Attributes => {
+                if self.obj.hasattr(name)? {
+                    self.obj.getattr(name).map(Some)
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Extracts the field `name` of the struct `struct_name`.
+    pub fn extract<T>(&self, struct_name: &str, name: &str) -> PyResult<T>
+    where
+        T: FromPyObject<'p>,
+    {
+        match self.get(name)? {
+            Some(value) => value.extract(),
+            None => Err(TypeError::py_err(format!(
+                "Failed to extract {}: missing field '{}'",
+                struct_name, name
+            ))),
+        }
+    }
+
+    /// Extracts the field `name`, or returns the default value if it is missing.
+    pub fn extract_or_default<T>(&self, name: &str) -> PyResult<T>
+    where
+        T: FromPyObject<'p> + Default,
+    {
+        match self.get(name)? {
+            Some(value) => value.extract(),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+fn field_names(fields: &PyAny) -> PyResult<Vec<String>> {
+    fields
+        .iter()?
+        .map(|field| field?.getattr("name")?.extract())
+        .collect()
+}
+
+/// Calls the class named `qualname` with `kwargs`. Used for `#[pyo3(dataclass = "...")]`.
+pub fn construct_dataclass(py: Python, qualname: &str, kwargs: &PyDict) -> PyResult<PyObject> {
+    let cls = import_qualified(py, qualname, "builtins")?;
+    Ok(cls.call((), Some(kwargs))?.into())
+}
@@ -0,0 +1,80 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Loading libpython at runtime from a path chosen by the application.
+//!
+//! With the `dynamic-python` feature, PyO3 doesn't link libpython, the same as with
+//! `extension-module`. The Python symbols are instead resolved by the dynamic loader from the
+//! libpython that [load_libpython] loaded into the process. This lets an application discover
+//! the Python installation to use when it runs ("bring your own Python"), e.g. from a
+//! configuration file or by probing the usual locations.
+//!
+//! The code using PyO3 has to be in a plugin (a `cdylib`) that the application loads after
+//! calling [load_libpython], because the symbols of the application itself are resolved when
+//! it starts. The plugin is still compiled for one Python version, so an application that
+//! supports several versions builds one plugin per version, and picks the plugin matching
+//! [libpython_version] of the library it loaded. On Windows, see the `delay-load` feature
+//! instead.
+//!
+//! # Example
+//! ```no_run
+//! use pyo3::dynamic;
+//!
+//! dynamic::load_libpython("/opt/python3.8/lib/libpython3.8.so.1.0")
+//!     .expect("failed to load libpython");
+//! let (major, minor) = dynamic::libpython_version().unwrap();
+//! let plugin = format!("libplugin_py{}{}.so", major, minor);
+//! // ... load the plugin, e.g. with `libc::dlopen`, and call into it
+//! ```
+
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Loads the libpython at `path` into the process, making its symbols available to everything
+/// loaded afterwards.
+///
+/// Fails with `AlreadyExists` if a libpython is already loaded (or linked into the process),
+/// and with `Other` carrying the loader's message if the library can't be loaded.
+pub fn load_libpython(path: impl AsRef<Path>) -> io::Result<()> {
+    if libpython_version().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "libpython is already loaded",
+        ));
+    }
+    let path = CString::new(path.as_ref().as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_GLOBAL) };
+    if handle.is_null() {
+        let message = unsafe { CStr::from_ptr(libc::dlerror()) };
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            message.to_string_lossy().into_owned(),
+        ));
+    }
+    // The library stays loaded for the lifetime of the process, so the handle isn't needed
+    Ok(())
+}
+
+/// Returns the `(major, minor)` version of the libpython loaded into the process, if any.
+///
+/// This doesn't require the interpreter to be initialized.
+pub fn libpython_version() -> Option<(u8, u8)> {
+    let name = "Py_GetVersion\0".as_ptr() as *const libc::c_char;
+    let symbol = unsafe { libc::dlsym(libc::RTLD_DEFAULT, name) };
+    if symbol.is_null() {
+        return None;
+    }
+    let get_version: extern "C" fn() -> *const libc::c_char =
+        unsafe { std::mem::transmute(symbol) };
+    // The version starts with e.g. "3.8.1 (default, ...)"
+    let version = unsafe { CStr::from_ptr(get_version()) }.to_string_lossy();
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|part| part.parse::<u8>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => Some((major, minor)),
+        _ => None,
+    }
+}
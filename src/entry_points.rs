@@ -0,0 +1,330 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Discovering Python plugins through entry points.
+//!
+//! Python packages advertise plugins as [entry points](https://packaging.python.org/specifications/entry-points/)
+//! in their metadata, e.g. `greet = my_plugin:greet` in the `my_app.plugins` group. [entry_points]
+//! lists the ones of a group that are installed, read with `importlib.metadata`, or the
+//! `importlib_metadata` backport before Python 3.8, and [EntryPoint::load] imports the object an
+//! entry point refers to.
+//!
+//! Failures are reported as [EntryPointError]s, whose [kind](EntryPointError::kind) tells a
+//! missing plugin from a broken one, so an application can skip plugins that fail to load.
+//!
+//! # Example
+//! ```
+//! use pyo3::entry_points::{entry_points, EntryPointErrorKind};
+//! use pyo3::prelude::*;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! for entry_point in entry_points(py, "my_app.plugins").unwrap() {
+//!     match entry_point.load(py) {
+//!         Ok(plugin) => {
+//!             plugin.call0(py).unwrap();
+//!         }
+//!         Err(err) => assert_eq!(err.kind(), EntryPointErrorKind::Load),
+//!     }
+//! }
+//! ```
+
+use crate::err::PyErr;
+use crate::exceptions::{AttributeError, ImportError, LookupError};
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{IntoPyDict, PyAny};
+use crate::{AsPyRef, IntoPy, Python, ToPyObject};
+use std::fmt;
+
+/// An installed entry point, found by [entry_points] or [entry_point].
+pub struct EntryPoint {
+    /// The name of the entry point, e.g. `greet`.
+    pub name: String,
+    /// The group of the entry point, e.g. `my_app.plugins`.
+    pub group: String,
+    /// The object the entry point refers to, e.g. `my_plugin:greet`.
+    pub value: String,
+    /// The name of the distribution declaring the entry point, if known.
+    pub dist: Option<String>,
+    entry_point: PyObject,
+}
+
+impl EntryPoint {
+    fn new(py: Python, entry_point: &PyAny) -> Result<EntryPoint, PyErr> {
+        // `dist` was added in Python 3.10 and importlib_metadata 3.6
+        let dist = match entry_point.getattr("dist") {
+            Ok(dist) if !dist.is_none() => {
+                Some(dist.getattr("metadata")?.get_item("Name")?.extract()?)
+            }
+            Ok(_) => None,
+            Err(err) if err.is_instance::<AttributeError>(py) => None,
+            Err(err) => return Err(err),
+        };
+        Ok(EntryPoint {
+            name: entry_point.getattr("name")?.extract()?,
+            group: entry_point.getattr("group")?.extract()?,
+            value: entry_point.getattr("value")?.extract()?,
+            dist,
+            entry_point: entry_point.into_py(py),
+        })
+    }
+
+    /// The module part of [value](#structfield.value), e.g. `my_plugin`.
+    pub fn module(&self) -> &str {
+        self.value.split(':').next().unwrap().trim()
+    }
+
+    /// The attribute part of [value](#structfield.value), e.g. `greet`, or `None` if the entry
+    /// point refers to a module.
+    pub fn attr(&self) -> Option<&str> {
+        let attr = &self.value[self.value.find(':')? + 1..];
+        // Without the extras, e.g. `[fast]`
+        Some(attr.split('[').next().unwrap().trim())
+    }
+
+    /// Imports the module of the entry point and returns the object it refers to.
+    pub fn load(&self, py: Python) -> Result<PyObject, EntryPointError> {
+        self.entry_point
+            .as_ref(py)
+            .call_method0("load")
+            .map(|obj| obj.into_py(py))
+            .map_err(|err| {
+                let message = format!(
+                    "Failed to load entry point {} = {} of group {}",
+                    self.name, self.value, self.group
+                );
+                EntryPointError::from_py_err(py, EntryPointErrorKind::Load, message, err)
+            })
+    }
+}
+
+impl fmt::Debug for EntryPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EntryPoint")
+            .field("name", &self.name)
+            .field("group", &self.group)
+            .field("value", &self.value)
+            .field("dist", &self.dist)
+            .finish()
+    }
+}
+
+/// Returns the entry points of `group` of the installed distributions.
+pub fn entry_points(py: Python, group: &str) -> Result<Vec<EntryPoint>, EntryPointError> {
+    let metadata = py
+        .import("importlib.metadata")
+        .or_else(|_| py.import("importlib_metadata"))
+        .map_err(|_| {
+            EntryPointError::new(
+                EntryPointErrorKind::Unavailable,
+                "importlib.metadata is unavailable, install the importlib_metadata backport"
+                    .to_string(),
+                None,
+            )
+        })?;
+    let read = || -> Result<Vec<EntryPoint>, PyErr> {
+        let all = metadata.call0("entry_points")?;
+        // Python 3.10 added `select`; before, the entry points are grouped in a dict
+        let entry_points = if all.hasattr("select")? {
+            all.call_method("select", (), Some([("group", group)].into_py_dict(py)))?
+        } else {
+            all.call_method1("get", (group, ()))?
+        };
+        let mut found = Vec::new();
+        for entry_point in entry_points.iter()? {
+            found.push(EntryPoint::new(py, entry_point?)?);
+        }
+        Ok(found)
+    };
+    read().map_err(|err| {
+        let message = format!("Failed to read the entry points of group {}", group);
+        EntryPointError::from_py_err(py, EntryPointErrorKind::Metadata, message, err)
+    })
+}
+
+/// Returns the entry point `name` of `group`, the first one found if several distributions
+/// declare it.
+pub fn entry_point(py: Python, group: &str, name: &str) -> Result<EntryPoint, EntryPointError> {
+    entry_points(py, group)?
+        .into_iter()
+        .find(|entry_point| entry_point.name == name)
+        .ok_or_else(|| {
+            EntryPointError::new(
+                EntryPointErrorKind::NotFound,
+                format!("No entry point {} in group {}", name, group),
+                None,
+            )
+        })
+}
+
+/// The reason an [EntryPointError] occurred.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryPointErrorKind {
+    /// Neither `importlib.metadata` nor the `importlib_metadata` backport can be imported.
+    Unavailable,
+    /// Reading the metadata of the installed distributions raised an exception.
+    Metadata,
+    /// The requested entry point isn't installed.
+    NotFound,
+    /// Importing the object an entry point refers to raised an exception.
+    Load,
+}
+
+/// An error discovering or loading entry points.
+///
+/// Errors caused by a Python exception convert back into that exception; otherwise
+/// [Unavailable](EntryPointErrorKind::Unavailable) converts into an `ImportError` and
+/// [NotFound](EntryPointErrorKind::NotFound) into a `LookupError`.
+#[derive(Debug)]
+pub struct EntryPointError {
+    kind: EntryPointErrorKind,
+    message: String,
+    err: Option<PyErr>,
+}
+
+impl EntryPointError {
+    fn new(kind: EntryPointErrorKind, message: String, err: Option<PyErr>) -> EntryPointError {
+        EntryPointError { kind, message, err }
+    }
+
+    /// Appends the type and message of `err` to `message`.
+    fn from_py_err(
+        py: Python,
+        kind: EntryPointErrorKind,
+        message: String,
+        err: PyErr,
+    ) -> EntryPointError {
+        let instance = err.to_object(py);
+        let instance = instance.as_ref(py);
+        let message = match instance.str() {
+            Ok(text) => format!(
+                "{}: {}: {}",
+                message,
+                instance.get_type().name(),
+                text.to_string_lossy()
+            ),
+            Err(_) => format!("{}: {}", message, instance.get_type().name()),
+        };
+        EntryPointError::new(kind, message, Some(err))
+    }
+
+    /// Returns the reason of the error.
+    pub fn kind(&self) -> EntryPointErrorKind {
+        self.kind
+    }
+
+    /// Returns the Python exception which caused the error, if any.
+    pub fn py_err(&self) -> Option<&PyErr> {
+        self.err.as_ref()
+    }
+}
+
+impl fmt::Display for EntryPointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for EntryPointError {}
+
+impl std::convert::From<EntryPointError> for PyErr {
+    fn from(err: EntryPointError) -> PyErr {
+        match (err.err, err.kind) {
+            (Some(err), _) => err,
+            (None, EntryPointErrorKind::NotFound) => LookupError::py_err(err.message),
+            (None, _) => ImportError::py_err(err.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{entry_point, entry_points, EntryPointErrorKind};
+    use crate::err::PyErr;
+    use crate::exceptions::{LookupError, ModuleNotFoundError};
+    use crate::Python;
+    use std::sync::Once;
+
+    /// Installs a distribution declaring entry points in the group `pyo3_test.plugins`.
+    fn install_plugins(py: Python) {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            py.run(
+                r#"
+import importlib, os, sys, tempfile
+path = tempfile.mkdtemp()
+info = os.path.join(path, "pyo3_test_plugins-1.0.dist-info")
+os.mkdir(info)
+with open(os.path.join(info, "METADATA"), "w") as f:
+    f.write("Metadata-Version: 2.1\nName: pyo3-test-plugins\nVersion: 1.0\n")
+with open(os.path.join(info, "entry_points.txt"), "w") as f:
+    f.write("[pyo3_test.plugins]\ngreet = pyo3_test_plugin:greet\n"
+            "broken = pyo3_test_plugin_missing:run\n")
+with open(os.path.join(path, "pyo3_test_plugin.py"), "w") as f:
+    f.write("def greet(name):\n    return 'Hello ' + name\n")
+sys.path.append(path)
+importlib.invalidate_caches()
+"#,
+                None,
+                None,
+            )
+            .unwrap();
+        });
+    }
+
+    #[test]
+    fn test_load_entry_points() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        install_plugins(py);
+
+        let mut found = entry_points(py, "pyo3_test.plugins").unwrap();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = found.iter().map(|ep| ep.name.as_str()).collect();
+        assert_eq!(names, ["broken", "greet"]);
+
+        let greet = &found[1];
+        assert_eq!(greet.group, "pyo3_test.plugins");
+        assert_eq!(greet.module(), "pyo3_test_plugin");
+        assert_eq!(greet.attr(), Some("greet"));
+        assert_eq!(greet.dist.as_ref().unwrap(), "pyo3-test-plugins");
+        let greeting: String = greet
+            .load(py)
+            .unwrap()
+            .call1(py, ("PyO3",))
+            .unwrap()
+            .extract(py)
+            .unwrap();
+        assert_eq!(greeting, "Hello PyO3");
+
+        let err = found[0].load(py).unwrap_err();
+        assert_eq!(err.kind(), EntryPointErrorKind::Load);
+        assert_eq!(
+            err.to_string(),
+            "Failed to load entry point broken = pyo3_test_plugin_missing:run of group \
+             pyo3_test.plugins: ModuleNotFoundError: No module named 'pyo3_test_plugin_missing'"
+        );
+        assert!(PyErr::from(err).is_instance::<ModuleNotFoundError>(py));
+
+        assert!(entry_points(py, "pyo3_test.no_plugins").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_entry_point_by_name() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        install_plugins(py);
+
+        let greet = entry_point(py, "pyo3_test.plugins", "greet").unwrap();
+        assert_eq!(greet.value, "pyo3_test_plugin:greet");
+
+        let err = entry_point(py, "pyo3_test.plugins", "missing").unwrap_err();
+        assert_eq!(err.kind(), EntryPointErrorKind::NotFound);
+        assert!(err.py_err().is_none());
+        assert_eq!(
+            err.to_string(),
+            "No entry point missing in group pyo3_test.plugins"
+        );
+        assert!(PyErr::from(err).is_instance::<LookupError>(py));
+    }
+}
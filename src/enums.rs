@@ -0,0 +1,132 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Fieldless Rust enums exposed as Python `enum.IntEnum` classes.
+//!
+//! `#[pyclass]` on an enum without fields implements [PyEnum] for it, along with `ToPyObject`,
+//! `IntoPy<PyObject>` and `FromPyObject`. The Python class is a real subclass of `enum.IntEnum`
+//! named after the enum, with the variants as members whose values are the discriminants, so
+//! members compare and hash like integers and support `int()`, iteration and lookup by name or
+//! value. Rust values convert to the matching members, and only members of the class can be
+//! extracted back to the Rust enum.
+//!
+//! The classes are created once per interpreter when they are first used, and can be added to a
+//! module with [PyModule::add_enum](crate::types::PyModule::add_enum). Only the `name` and
+//! `module` parameters of `#[pyclass]` are supported for enums, and they can't have
+//! `#[pymethods]`.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//!
+//! /// The color of a traffic light
+//! #[pyclass]
+//! #[derive(Debug, PartialEq)]
+//! enum Light {
+//!     Red = 1,
+//!     Amber,
+//!     Green,
+//! }
+//!
+//! #[pyfunction]
+//! fn next_light(light: Light) -> Light {
+//!     match light {
+//!         Light::Red => Light::Green,
+//!         Light::Green => Light::Amber,
+//!         Light::Amber => Light::Red,
+//!     }
+//! }
+//!
+//! #[pymodule]
+//! fn traffic(_py: Python, m: &PyModule) -> PyResult<()> {
+//!     m.add_enum::<Light>()?;
+//!     m.add_wrapped(pyo3::wrap_pyfunction!(next_light))
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let traffic = pyo3::wrap_pymodule!(traffic)(py);
+//! pyo3::py_run!(py, traffic, r#"
+//! assert traffic.next_light(traffic.Light.Red) is traffic.Light.Green
+//! assert traffic.Light.Amber == 2 and traffic.Light(3).name == "Green"
+//! "#);
+//! ```
+
+use crate::cache::PyStatic;
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::TypeError;
+use crate::instance::PyNativeType;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict, PyType};
+use crate::{AsPyPointer, PyObject, Python};
+
+/// A fieldless enum exposed as a Python `enum.IntEnum`, implemented by `#[pyclass]`.
+pub trait PyEnum: Sized {
+    /// The name of the Python class.
+    const NAME: &'static str;
+
+    /// The module of the Python class, if any.
+    const MODULE: Option<&'static str>;
+
+    /// The docstring of the Python class.
+    const DESCRIPTION: &'static str;
+
+    /// The names and discriminants of the variants, in declaration order.
+    const VARIANTS: &'static [(&'static str, isize)];
+
+    /// Returns the discriminant of `self`.
+    fn discriminant(&self) -> isize;
+
+    /// Returns the variant with the discriminant `value`.
+    fn from_discriminant(value: isize) -> Option<Self>;
+
+    #[doc(hidden)]
+    fn type_cache() -> &'static PyStatic;
+
+    /// Returns the Python class of the enum in the current interpreter.
+    fn type_object(py: Python) -> &PyType {
+        let cls = Self::type_cache().get_or_init(py, || {
+            create_type::<Self>(py).expect("failed to create the enum class")
+        });
+        unsafe { py.from_borrowed_ptr(cls.as_ptr()) }
+    }
+}
+
+fn create_type<T: PyEnum>(py: Python) -> PyResult<PyObject> {
+    let kwargs = PyDict::new(py);
+    if let Some(module) = T::MODULE {
+        kwargs.set_item("module", module)?;
+    }
+    let cls = py
+        .import("enum")?
+        .call("IntEnum", (T::NAME, T::VARIANTS.to_vec()), Some(kwargs))?;
+    if !T::DESCRIPTION.is_empty() {
+        cls.setattr("__doc__", T::DESCRIPTION)?;
+    }
+    Ok(cls.into())
+}
+
+#[doc(hidden)]
+pub fn enum_to_object<T: PyEnum>(value: &T, py: Python) -> PyObject {
+    T::type_object(py)
+        .call1((value.discriminant(),))
+        .expect("every variant is a member of the enum class")
+        .into()
+}
+
+#[doc(hidden)]
+pub fn extract_enum<T: PyEnum>(ob: &PyAny) -> PyResult<T> {
+    let cls = T::type_object(ob.py());
+    if !cls.is_instance(ob)? {
+        return Err(PyErr::new::<TypeError, _>(format!(
+            "expected {}, got '{}'",
+            T::NAME,
+            ob.get_type().name()
+        )));
+    }
+    let value: isize = ob.extract()?;
+    // Enums with members can't be subclassed, so every instance is a variant
+    T::from_discriminant(value).ok_or_else(|| {
+        PyErr::new::<TypeError, _>(format!("{} is not a variant of {}", value, T::NAME))
+    })
+}
@@ -21,7 +21,7 @@ use std::os::raw::c_char;
 ///
 /// When you construct an instance of `PyErrValue`, we highly recommend to use `from_err_args`
 /// method.  If you want to to construct `PyErrValue::ToArgs` directly, please do not forget to
-/// call `Python::acquire_gil`.
+/// acquire the GIL, e.g. with `Python::with_gil`.
 pub enum PyErrValue {
     None,
     Value(PyObject),
@@ -31,8 +31,7 @@ pub enum PyErrValue {
 
 impl PyErrValue {
     pub fn from_err_args<T: 'static + PyErrArguments>(value: T) -> Self {
-        let _ = Python::acquire_gil();
-        PyErrValue::ToArgs(Box::new(value))
+        Python::with_gil(|_| PyErrValue::ToArgs(Box::new(value)))
     }
 }
 
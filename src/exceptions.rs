@@ -36,6 +36,24 @@ macro_rules! impl_exception_boilerplate {
             pub fn into<R, T: $crate::ToPyObject + 'static>(args: T) -> $crate::PyResult<R> {
                 $crate::PyErr::new::<Self, T>(args).into()
             }
+
+            /// Creates the exception from `args` and sets `attrs` on the instance, e.g. the line
+            /// a parser failed at. The error raised while creating the instance is returned
+            /// instead if there is one.
+            pub fn py_err_with_attrs(
+                py: $crate::Python,
+                args: impl $crate::IntoPy<$crate::Py<$crate::types::PyTuple>>,
+                attrs: &[(&str, $crate::PyObject)],
+            ) -> $crate::PyErr {
+                let create = || -> $crate::PyResult<$crate::PyErr> {
+                    let instance = $crate::ObjectProtocol::call1(py.get_type::<Self>(), args)?;
+                    for (name, value) in attrs {
+                        $crate::ObjectProtocol::setattr(instance, *name, value)?;
+                    }
+                    Ok($crate::PyErr::from_instance(instance))
+                };
+                create().unwrap_or_else(|err| err)
+            }
         }
     };
 }
@@ -46,9 +64,13 @@ macro_rules! impl_exception_boilerplate {
 ///
 /// `import_exception!(module, MyError)`
 ///
-/// * `module` is the name of the containing module.
+/// * `module` is the name of the containing module, which may be dotted.
 /// * `MyError` is the name of the new exception type.
 ///
+/// Several exceptions of a module, e.g. a hierarchy defined with [create_exception!] by another
+/// extension module, can be imported at once with `import_exception!(module, MyError,
+/// MyTimeoutError)`.
+///
 /// # Example
 /// ```
 /// use pyo3::import_exception;
@@ -81,6 +103,11 @@ macro_rules! import_exception {
 
         $crate::import_exception_type_object!($module, $name);
     };
+    ($module: expr, $($name: ident),+ $(,)?) => {
+        $(
+            $crate::import_exception!($module, $name);
+        )+
+    };
 }
 
 /// `impl $crate::type_object::PyTypeObject for $name` where `$name` is an exception defined in python
@@ -95,10 +122,7 @@ macro_rules! import_exception_type_object {
                 static mut TYPE_OBJECT: *mut $crate::ffi::PyTypeObject = ::std::ptr::null_mut();
 
                 TYPE_OBJECT_ONCE.call_once(|| {
-                    let gil = $crate::Python::acquire_gil();
-                    let py = gil.python();
-
-                    unsafe {
+                    $crate::Python::with_gil(|py| unsafe {
                         let imp = py
                             .import(stringify!($module))
                             .expect(concat!("Can not import module: ", stringify!($module)));
@@ -110,7 +134,7 @@ macro_rules! import_exception_type_object {
                         ));
                         TYPE_OBJECT =
                             $crate::IntoPyPointer::into_ptr(cls) as *mut $crate::ffi::PyTypeObject;
-                    }
+                    })
                 });
 
                 unsafe { std::ptr::NonNull::new_unchecked(TYPE_OBJECT) }
@@ -122,11 +146,19 @@ macro_rules! import_exception_type_object {
 /// Defines a new exception type.
 ///
 /// # Syntax
-/// `create_exception!(module, MyError, BaseException)`
+/// `create_exception!(module, MyError, BaseException)` or
+/// `create_exception!(module, MyError, BaseException, "Docstring")`
 ///
 /// * `module` is the name of the containing module.
 /// * `MyError` is the name of the new exception type.
-/// * `BaseException` is the superclass of MyError, usually `pyo3::exceptions::Exception`
+/// * `BaseException` is the superclass of MyError, usually `pyo3::exceptions::Exception`, or
+///   another exception created with this macro to build a hierarchy.
+/// * `"Docstring"` is the optional `__doc__` of the exception, also used as the documentation
+///   of the Rust type.
+///
+/// Exceptions carrying more information than their arguments can be raised with
+/// `MyError::py_err_with_attrs`, and [PyModule::add_exceptions](crate::types::PyModule::add_exceptions)
+/// adds a hierarchy to a module.
 ///
 /// # Example
 /// ```
@@ -166,13 +198,22 @@ macro_rules! create_exception {
 
         $crate::create_exception_type_object!($module, $name, $base);
     };
+    ($module: ident, $name: ident, $base: ty, $doc: expr) => {
+        #[doc = $doc]
+        #[allow(non_camel_case_types)] // E.g. `socket.herror`
+        pub struct $name;
+
+        $crate::impl_exception_boilerplate!($name);
+
+        $crate::create_exception_type_object!($module, $name, $base, $doc);
+    };
 }
 
 /// `impl $crate::type_object::PyTypeObject for $name` where `$name` is an exception newly defined in
 /// rust code.
 #[macro_export]
 macro_rules! create_exception_type_object {
-    ($module: ident, $name: ident, $base: ty) => {
+    ($module: ident, $name: ident, $base: ty $(, $doc: expr)?) => {
         unsafe impl $crate::type_object::PyTypeObject for $name {
             fn init_type() -> std::ptr::NonNull<$crate::ffi::PyTypeObject> {
                 // We can't use lazy_static here because raw pointers aren't Send
@@ -180,17 +221,20 @@ macro_rules! create_exception_type_object {
                 static mut TYPE_OBJECT: *mut $crate::ffi::PyTypeObject = ::std::ptr::null_mut();
 
                 TYPE_OBJECT_ONCE.call_once(|| {
-                    let gil = $crate::Python::acquire_gil();
-                    let py = gil.python();
-
-                    unsafe {
+                    $crate::Python::with_gil(|py| unsafe {
+                        #[allow(unused_mut)]
+                        let mut dict: Option<$crate::PyObject> = None;
+                        $(
+                            let doc = [("__doc__", $doc)];
+                            dict = Some($crate::types::IntoPyDict::into_py_dict(&doc, py).into());
+                        )?
                         TYPE_OBJECT = $crate::PyErr::new_type(
                             py,
                             concat!(stringify!($module), ".", stringify!($name)),
                             Some(py.get_type::<$base>()),
-                            None,
+                            dict,
                         );
-                    }
+                    })
                 });
 
                 unsafe { std::ptr::NonNull::new_unchecked(TYPE_OBJECT) }
@@ -362,8 +406,8 @@ pub mod socket {
 mod test {
     use crate::exceptions::Exception;
     use crate::objectprotocol::ObjectProtocol;
-    use crate::types::{IntoPyDict, PyDict};
-    use crate::{PyErr, Python};
+    use crate::types::{IntoPyDict, PyDict, PyModule};
+    use crate::{PyErr, Python, ToPyObject};
 
     import_exception!(socket, gaierror);
     import_exception!(email.errors, MessageError);
@@ -420,6 +464,67 @@ mod test {
         .expect("assertion failed");
     }
 
+    create_exception!(hierarchy, BaseError, Exception, "The base of all errors.");
+    create_exception!(hierarchy, TimeoutError, BaseError);
+    create_exception!(hierarchy, ParseError, BaseError, "Invalid input.");
+
+    #[test]
+    fn custom_exception_hierarchy() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::new(py, "hierarchy").unwrap();
+        module
+            .add_exceptions(&[
+                py.get_type::<BaseError>(),
+                py.get_type::<TimeoutError>(),
+                py.get_type::<ParseError>(),
+            ])
+            .unwrap();
+        let err = ParseError::py_err_with_attrs(py, ("bad token",), &[("line", 3.to_object(py))]);
+        let ctx = [("m", module.to_object(py)), ("err", err.to_object(py))].into_py_dict(py);
+        py.run(
+            r#"
+assert issubclass(m.TimeoutError, m.BaseError) and issubclass(m.ParseError, m.BaseError)
+assert issubclass(m.BaseError, Exception)
+assert m.BaseError.__doc__ == "The base of all errors."
+assert m.TimeoutError.__doc__ is None
+assert m.ParseError.__doc__ == "Invalid input."
+assert m.ParseError.__module__ == "hierarchy"
+assert isinstance(err, m.ParseError)
+assert err.args == ("bad token",) and err.line == 3
+"#,
+            None,
+            Some(ctx),
+        )
+        .unwrap();
+
+        // Errors creating the instance are returned instead
+        let err = ParseError::py_err_with_attrs(py, (), &[("args", 1.to_object(py))]);
+        assert!(err.is_instance::<crate::exceptions::TypeError>(py));
+    }
+
+    #[test]
+    fn import_exception_hierarchy() {
+        import_exception!(email.errors, MessageError, HeaderParseError, BoundaryError);
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let ctx = [
+            ("MessageError", py.get_type::<MessageError>()),
+            ("HeaderParseError", py.get_type::<HeaderParseError>()),
+            ("BoundaryError", py.get_type::<BoundaryError>()),
+        ]
+        .into_py_dict(py);
+        py.run(
+            "import email.errors as e\n\
+             assert (MessageError, HeaderParseError, BoundaryError) == \
+             (e.MessageError, e.HeaderParseError, e.BoundaryError)",
+            None,
+            Some(ctx),
+        )
+        .unwrap();
+    }
+
     #[test]
     fn custom_exception() {
         create_exception!(mymodule, CustomError, Exception);
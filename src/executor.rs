@@ -0,0 +1,287 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! A thread pool for running many small closures that need the GIL.
+//!
+//! Calling into Python from an arbitrary Rust thread means creating a Python thread state,
+//! acquiring the GIL, and tearing the thread state down again. When many Rust threads each
+//! need to run a small Python callback, that overhead easily dominates. A [PyExecutor] instead
+//! owns a fixed number of threads that are registered with the interpreter once, and runs
+//! submitted closures on them. A worker that has acquired the GIL keeps running queued jobs
+//! before releasing it again, so bursts of callbacks are batched under a single acquisition.
+//!
+//! # Example
+//! ```
+//! use pyo3::executor::PyExecutor;
+//! use pyo3::prelude::*;
+//!
+//! let executor = PyExecutor::new(2);
+//! let handles: Vec<_> = (0..4)
+//!     .map(|i| {
+//!         executor.submit(move |py| {
+//!             let value = py.eval(&format!("{} * 2", i), None, None).unwrap();
+//!             value.extract::<i32>().unwrap()
+//!         })
+//!     })
+//!     .collect();
+//! let results: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+//! assert_eq!(results, vec![0, 2, 4, 6]);
+//! ```
+//!
+//! # Deadlocks
+//!
+//! Jobs run while their worker holds the GIL, so a job that blocks with the GIL held stops all
+//! other workers, and a thread that holds the GIL while waiting for a job stops the job. To wait
+//! for another job or a lock from inside a job, release the GIL with
+//! [Python::allow_threads]. To wait for a job while holding the GIL, use
+//! [PyJobHandle::join_allow_threads] instead of [PyJobHandle::join]. Dropping the executor
+//! releases the GIL while it waits for the queued jobs.
+
+use crate::ffi;
+use crate::gil::GILPool;
+use crate::python::{prepare_freethreaded_python, Python};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn for<'py> FnOnce(Python<'py>) + Send>;
+
+/// A pool of threads registered with the Python interpreter. See the [module docs](self).
+///
+/// Dropping the executor waits for all submitted jobs to finish, releasing the GIL meanwhile if
+/// the current thread holds it.
+pub struct PyExecutor {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+/// A handle to the result of a closure passed to [PyExecutor::submit].
+pub struct PyJobHandle<R> {
+    pub(crate) receiver: mpsc::Receiver<thread::Result<R>>,
+}
+
+impl<R> PyJobHandle<R> {
+    /// Waits for the job to finish and returns its result.
+    ///
+    /// Returns `Err` with the panic payload if the job panicked.
+    ///
+    /// This deadlocks if the current thread holds the GIL, as the job can't acquire it; use
+    /// [join_allow_threads](PyJobHandle::join_allow_threads) then.
+    pub fn join(self) -> thread::Result<R> {
+        self.receiver
+            .recv()
+            .expect("PyExecutor worker disappeared without running the job")
+    }
+
+    /// Like [join](PyJobHandle::join), but releases the GIL held by the current thread while
+    /// waiting, so that the job can run.
+    pub fn join_allow_threads(self, py: Python) -> thread::Result<R>
+    where
+        R: Send,
+    {
+        py.allow_threads(|| self.join())
+    }
+}
+
+impl PyExecutor {
+    /// Creates an executor with `threads` worker threads.
+    ///
+    /// # Panics
+    /// Panics if `threads` is zero.
+    pub fn new(threads: usize) -> PyExecutor {
+        assert!(threads > 0, "PyExecutor needs at least one thread");
+        prepare_freethreaded_python();
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads)
+            .map(|i| {
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name(format!("pyo3-executor-{}", i))
+                    .spawn(move || worker_loop(&receiver))
+                    .expect("Failed to spawn PyExecutor worker thread")
+            })
+            .collect();
+
+        PyExecutor {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Returns the number of worker threads.
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Runs `f` on one of the worker threads, without waiting for it to finish.
+    ///
+    /// A panic in `f` is caught and discarded, so that the worker keeps running.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: for<'py> FnOnce(Python<'py>) + Send + 'static,
+    {
+        self.sender
+            .as_ref()
+            .unwrap()
+            .send(Box::new(f))
+            .expect("PyExecutor worker threads have stopped");
+    }
+
+    /// Runs `f` on one of the worker threads, returning a handle to wait for its result.
+    pub fn submit<F, R>(&self, f: F) -> PyJobHandle<R>
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.execute(move |py| {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(py)));
+            let _ = sender.send(result);
+        });
+        PyJobHandle { receiver }
+    }
+}
+
+impl Drop for PyExecutor {
+    fn drop(&mut self) {
+        // Closing the channel makes the workers exit once the queue is empty
+        drop(self.sender.take());
+        let workers = std::mem::replace(&mut self.workers, Vec::new());
+        let join = move || {
+            for worker in workers {
+                let _ = worker.join();
+            }
+        };
+        // The queued jobs need the GIL to finish
+        #[cfg(not(PyPy))]
+        {
+            if crate::gil::gil_is_acquired() {
+                unsafe {
+                    let tstate = ffi::PyEval_SaveThread();
+                    join();
+                    ffi::PyEval_RestoreThread(tstate);
+                }
+                return;
+            }
+        }
+        join();
+    }
+}
+
+fn run_job(py: Python, job: Job) {
+    let _pool = GILPool::new(py);
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| job(py)));
+}
+
+fn worker_loop(receiver: &Mutex<mpsc::Receiver<Job>>) {
+    // Register this thread with the interpreter once, and keep its thread state around
+    // while waiting for jobs.
+    let gstate = unsafe { ffi::PyGILState_Ensure() };
+    let mut tstate = unsafe { ffi::PyEval_SaveThread() };
+
+    loop {
+        // Wait for a job without holding the GIL
+        let job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => break,
+        };
+
+        unsafe { ffi::PyEval_RestoreThread(tstate) };
+        let py = unsafe { Python::assume_gil_acquired() };
+        run_job(py, job);
+        // Drain whatever else is queued while we hold the GIL anyway
+        loop {
+            let next = receiver.lock().unwrap().try_recv();
+            match next {
+                Ok(job) => run_job(py, job),
+                Err(_) => break,
+            }
+        }
+        tstate = unsafe { ffi::PyEval_SaveThread() };
+    }
+
+    unsafe {
+        ffi::PyEval_RestoreThread(tstate);
+        ffi::PyGILState_Release(gstate);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyExecutor;
+    use crate::types::PyList;
+    use crate::{ObjectProtocol, PyObject, Python};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_executor_runs_jobs() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        {
+            let executor = PyExecutor::new(3);
+            assert_eq!(executor.threads(), 3);
+            for _ in 0..20 {
+                let counter = counter.clone();
+                executor.execute(move |py| {
+                    py.eval("1 + 1", None, None).unwrap();
+                    counter.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 20);
+    }
+
+    #[test]
+    fn test_executor_submit_and_panic() {
+        let executor = PyExecutor::new(1);
+        let list: PyObject = {
+            let gil = Python::acquire_gil();
+            PyList::empty(gil.python()).into()
+        };
+
+        assert!(executor.submit(|_| panic!("boom")).join().is_err());
+        let len = executor
+            .submit(move |py| {
+                let list: &PyList = list.cast_as(py).unwrap();
+                list.append(1).unwrap();
+                list.len()
+            })
+            .join()
+            .unwrap();
+        assert_eq!(len, 1);
+    }
+
+    #[test]
+    fn test_join_holding_the_gil() {
+        let executor = PyExecutor::new(1);
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let handle = executor.submit(|py| {
+            py.eval("6 * 7", None, None)
+                .unwrap()
+                .extract::<i32>()
+                .unwrap()
+        });
+        assert_eq!(handle.join_allow_threads(py).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_drop_holding_the_gil() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let executor = PyExecutor::new(2);
+        for _ in 0..20 {
+            let counter = counter.clone();
+            executor.execute(move |py| {
+                py.eval("1 + 1", None, None).unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        drop(executor);
+        assert_eq!(counter.load(Ordering::SeqCst), 20);
+        // The GIL is held again
+        py.eval("1 + 1", None, None).unwrap();
+    }
+}
@@ -77,6 +77,30 @@ extern "C" {
     pub fn PyEval_RestoreThread(arg1: *mut PyThreadState) -> ();
 }
 
+pub type Py_tracefunc = extern "C" fn(
+    obj: *mut PyObject,
+    frame: *mut crate::ffi::PyFrameObject,
+    what: c_int,
+    arg: *mut PyObject,
+) -> c_int;
+
+pub const PyTrace_CALL: c_int = 0;
+pub const PyTrace_EXCEPTION: c_int = 1;
+pub const PyTrace_LINE: c_int = 2;
+pub const PyTrace_RETURN: c_int = 3;
+pub const PyTrace_C_CALL: c_int = 4;
+pub const PyTrace_C_EXCEPTION: c_int = 5;
+pub const PyTrace_C_RETURN: c_int = 6;
+#[cfg(Py_3_7)]
+pub const PyTrace_OPCODE: c_int = 7;
+
+#[cfg(not(PyPy))]
+#[cfg_attr(windows, link(name = "pythonXY"))]
+extern "C" {
+    pub fn PyEval_SetProfile(func: Option<Py_tracefunc>, obj: *mut PyObject) -> ();
+    pub fn PyEval_SetTrace(func: Option<Py_tracefunc>, obj: *mut PyObject) -> ();
+}
+
 #[cfg(py_sys_config = "WITH_THREAD")]
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
@@ -0,0 +1,21 @@
+//! Per-object locks of the free-threaded (`--disable-gil`) builds, see PEP 703.
+use crate::ffi::object::PyObject;
+
+#[repr(C)]
+pub struct PyCriticalSection {
+    _cs_prev: usize,
+    _cs_mutex: *mut std::os::raw::c_void,
+}
+
+#[repr(C)]
+pub struct PyCriticalSection2 {
+    _cs_base: PyCriticalSection,
+    _cs_mutex2: *mut std::os::raw::c_void,
+}
+
+extern "C" {
+    pub fn PyCriticalSection_Begin(c: *mut PyCriticalSection, op: *mut PyObject);
+    pub fn PyCriticalSection_End(c: *mut PyCriticalSection);
+    pub fn PyCriticalSection2_Begin(c: *mut PyCriticalSection2, a: *mut PyObject, b: *mut PyObject);
+    pub fn PyCriticalSection2_End(c: *mut PyCriticalSection2);
+}
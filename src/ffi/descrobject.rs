@@ -70,3 +70,25 @@ extern "C" {
     #[cfg_attr(PyPy, link_name = "PyPyProperty_Type")]
     pub static mut PyProperty_Type: PyTypeObject;
 }
+
+/// The common header of descriptors, `PyDescr_COMMON` in CPython.
+#[cfg(not(any(Py_LIMITED_API, PyPy)))]
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyDescrObject {
+    pub ob_base: PyObject,
+    pub d_type: *mut PyTypeObject,
+    pub d_name: *mut PyObject,
+    pub d_qualname: *mut PyObject,
+}
+
+/// The layout of `method_descriptor` and `classmethod_descriptor` objects.
+#[cfg(not(any(Py_LIMITED_API, PyPy)))]
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyMethodDescrObject {
+    pub d_common: PyDescrObject,
+    pub d_method: *mut PyMethodDef,
+    #[cfg(Py_3_8)]
+    pub vectorcall: Option<crate::ffi::object::vectorcallfunc>,
+}
@@ -15,31 +15,23 @@ pub unsafe fn PyCFunction_Check(op: *mut PyObject) -> c_int {
 pub type PyCFunction =
     unsafe extern "C" fn(slf: *mut PyObject, args: *mut PyObject) -> *mut PyObject;
 
-#[cfg(all(Py_3_8, not(Py_LIMITED_API)))]
-#[cfg_attr(Py_3_8, link_name = "_PyObject_Vectorcall")]
-pub type PyObject_Vectorcall = unsafe extern "C" fn(
-    slf: *mut PyObject,
-    // positional and keyword arguments
-    args: *const *mut PyObject,
-    // number of position arguments in args, after which values are kwargs
-    nargs: crate::ffi::pyport::Py_ssize_t,
-    // tuple of kwargs, if given, or null
-    kwnames: *mut PyObject,
-) -> *mut PyObject;
-
-#[cfg(all(Py_3_8, not(Py_LIMITED_API)))]
-#[cfg_attr(Py_3_8, link_name = "PyVectorcall_Call")]
-pub type PyVectorcall_Call = unsafe extern "C" fn(
-    obj: *mut PyObject,
-    tuple: *mut PyObject,
-    dict: *mut PyObject,
-) -> *mut PyObject;
+#[cfg(all(Py_3_8, not(PyPy), not(Py_LIMITED_API)))]
+#[cfg_attr(windows, link(name = "pythonXY"))]
+extern "C" {
+    pub fn PyVectorcall_Call(
+        callable: *mut PyObject,
+        tuple: *mut PyObject,
+        dict: *mut PyObject,
+    ) -> *mut PyObject;
+}
 
-#[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
-const PY_VECTORCALL_ARGUMENTS_OFFSET: crate::ffi::pyport::Py_ssize_t =
+/// Set in the `nargsf` argument of a vectorcall if the callee may temporarily overwrite
+/// `args[-1]`, which saves an allocation when calling bound methods
+#[cfg(not(Py_LIMITED_API))]
+pub const PY_VECTORCALL_ARGUMENTS_OFFSET: crate::ffi::pyport::Py_ssize_t =
     1 << (8 * std::mem::size_of::<usize>() - 1);
 
-#[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
+#[cfg(not(Py_LIMITED_API))]
 #[inline(always)]
 pub unsafe fn PyVectorcall_NARGS(
     n: crate::ffi::pyport::Py_ssize_t,
@@ -50,7 +42,14 @@ pub unsafe fn PyVectorcall_NARGS(
 #[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
 pub type _PyCFunctionFast = unsafe extern "C" fn(
     slf: *mut PyObject,
-    args: *mut *mut PyObject,
+    args: *const *mut PyObject,
+    nargs: crate::ffi::pyport::Py_ssize_t,
+) -> *mut PyObject;
+
+// Declared on all versions, so that code generated for any version can name it
+pub type _PyCFunctionFastWithKeywords = unsafe extern "C" fn(
+    slf: *mut PyObject,
+    args: *const *mut PyObject,
     nargs: crate::ffi::pyport::Py_ssize_t,
     kwnames: *mut PyObject,
 ) -> *mut PyObject;
@@ -11,6 +11,8 @@ pub use self::code::*;
 pub use self::codecs::*;
 pub use self::compile::*;
 pub use self::complexobject::*;
+#[cfg(py_sys_config = "Py_GIL_DISABLED")]
+pub use self::critical_section::*;
 pub use self::datetime::*;
 pub use self::descrobject::*;
 pub use self::dictobject::*;
@@ -111,6 +113,9 @@ mod pyerrors; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and
 
 mod pystate; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
 
+#[cfg(py_sys_config = "Py_GIL_DISABLED")]
+mod critical_section;
+
 #[cfg(Py_LIMITED_API)]
 mod pyarena {}
 mod modsupport; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
@@ -107,6 +107,14 @@ pub type ternaryfunc = unsafe extern "C" fn(
     arg3: *mut PyObject,
 ) -> *mut PyObject;
 
+#[cfg(Py_3_8)]
+pub type vectorcallfunc = unsafe extern "C" fn(
+    callable: *mut PyObject,
+    args: *const *mut PyObject,
+    nargsf: ::libc::size_t,
+    kwnames: *mut PyObject,
+) -> *mut PyObject;
+
 pub type inquiry = unsafe extern "C" fn(arg1: *mut PyObject) -> c_int;
 
 pub type lenfunc = unsafe extern "C" fn(arg1: *mut PyObject) -> Py_ssize_t;
@@ -509,6 +517,10 @@ mod typeobject {
         pub tp_del: Option<ffi::object::destructor>,
         pub tp_version_tag: c_uint,
         pub tp_finalize: Option<ffi::object::destructor>,
+        #[cfg(Py_3_8)]
+        pub tp_vectorcall: Option<ffi::object::vectorcallfunc>,
+        #[cfg(all(Py_3_8, not(Py_3_9)))]
+        pub tp_print: Option<object::printfunc>,
         #[cfg(PyPy)]
         pub tp_pypy_flags: ::std::os::raw::c_long,
         #[cfg(py_sys_config = "COUNT_ALLOCS")]
@@ -577,6 +589,10 @@ mod typeobject {
                     tp_weaklist: ptr::null_mut(),
                     tp_del: None,
                     tp_version_tag: 0,
+                    #[cfg(Py_3_8)]
+                    tp_vectorcall: None,
+                    #[cfg(all(Py_3_8, not(Py_3_9)))]
+                    tp_print: None,
                     $($tail)*
                 }
             }
@@ -1,5 +1,11 @@
 use crate::ffi::object::*;
 use crate::ffi::pyport::Py_ssize_t;
+#[cfg(not(Py_LIMITED_API))]
+use crate::ffi::{
+    PyDict_New, PyDict_SetItem, PyTuple_GET_ITEM, PyTuple_GET_SIZE, PyTuple_New, PyTuple_SET_ITEM,
+    PyVectorcall_NARGS, PY_VECTORCALL_ARGUMENTS_OFFSET,
+};
+use libc::size_t;
 use std::os::raw::{c_char, c_int, c_void};
 use std::ptr;
 
@@ -369,3 +375,146 @@ extern "C" {
     #[cfg_attr(PyPy, link_name = "PyPyObject_IsSubclass")]
     pub fn PyObject_IsSubclass(object: *mut PyObject, typeorclass: *mut PyObject) -> c_int;
 }
+
+// The calling API added in Python 3.8 and 3.9 (PEP 590). Where the interpreter doesn't provide a
+// function, it's implemented with the older API, so these are available on all versions.
+
+#[cfg(all(Py_3_9, not(PyPy)))]
+#[cfg_attr(windows, link(name = "pythonXY"))]
+extern "C" {
+    pub fn PyObject_CallNoArgs(func: *mut PyObject) -> *mut PyObject;
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn PyObject_VectorcallMethod(
+        name: *mut PyObject,
+        args: *const *mut PyObject,
+        nargsf: size_t,
+        kwnames: *mut PyObject,
+    ) -> *mut PyObject;
+}
+
+#[cfg(any(not(Py_3_9), PyPy))]
+#[inline]
+pub unsafe fn PyObject_CallNoArgs(func: *mut PyObject) -> *mut PyObject {
+    PyObject_CallObject(func, ptr::null_mut())
+}
+
+/// Returns the vectorcall function of `callable`, if its type supports vectorcall.
+#[cfg(all(Py_3_8, not(PyPy), not(Py_LIMITED_API)))]
+#[inline]
+pub unsafe fn PyVectorcall_Function(callable: *mut PyObject) -> Option<vectorcallfunc> {
+    let tp = Py_TYPE(callable);
+    if PyType_HasFeature(tp, _Py_TPFLAGS_HAVE_VECTORCALL) == 0 {
+        return None;
+    }
+    let offset = (*tp).tp_vectorcall_offset;
+    *((callable as *const u8).offset(offset) as *const Option<vectorcallfunc>)
+}
+
+/// Calls `callable` with the positional arguments `args[..nargs]`, and the keyword arguments
+/// named by the tuple `kwnames` (which may be null) with the values following them in `args`.
+#[cfg(not(Py_LIMITED_API))]
+#[inline]
+pub unsafe fn PyObject_Vectorcall(
+    callable: *mut PyObject,
+    args: *const *mut PyObject,
+    nargsf: size_t,
+    kwnames: *mut PyObject,
+) -> *mut PyObject {
+    #[cfg(all(Py_3_8, not(PyPy)))]
+    {
+        if let Some(func) = PyVectorcall_Function(callable) {
+            return func(callable, args, nargsf, kwnames);
+        }
+    }
+    vectorcall_with_tuple(callable, args, nargsf, kwnames)
+}
+
+/// Makes a vectorcall through `tp_call`, for older interpreters and types without vectorcall.
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn vectorcall_with_tuple(
+    callable: *mut PyObject,
+    args: *const *mut PyObject,
+    nargsf: size_t,
+    kwnames: *mut PyObject,
+) -> *mut PyObject {
+    let nargs = PyVectorcall_NARGS(nargsf as Py_ssize_t);
+    let tuple = PyTuple_New(nargs);
+    if tuple.is_null() {
+        return ptr::null_mut();
+    }
+    for i in 0..nargs {
+        let arg = *args.offset(i);
+        Py_INCREF(arg);
+        PyTuple_SET_ITEM(tuple, i, arg);
+    }
+    let mut kwargs = ptr::null_mut();
+    if !kwnames.is_null() {
+        kwargs = PyDict_New();
+        if kwargs.is_null() {
+            Py_DECREF(tuple);
+            return ptr::null_mut();
+        }
+        for i in 0..PyTuple_GET_SIZE(kwnames) {
+            let value = *args.offset(nargs + i);
+            if PyDict_SetItem(kwargs, PyTuple_GET_ITEM(kwnames, i), value) == -1 {
+                Py_DECREF(tuple);
+                Py_DECREF(kwargs);
+                return ptr::null_mut();
+            }
+        }
+    }
+    let result = PyObject_Call(callable, tuple, kwargs);
+    Py_DECREF(tuple);
+    Py_XDECREF(kwargs);
+    result
+}
+
+#[cfg(not(Py_LIMITED_API))]
+#[inline]
+pub unsafe fn PyObject_CallOneArg(func: *mut PyObject, arg: *mut PyObject) -> *mut PyObject {
+    // The first slot is the scratch space PY_VECTORCALL_ARGUMENTS_OFFSET allows to overwrite
+    let args = [ptr::null_mut(), arg];
+    let nargsf = 1 | PY_VECTORCALL_ARGUMENTS_OFFSET as size_t;
+    PyObject_Vectorcall(func, args.as_ptr().offset(1), nargsf, ptr::null_mut())
+}
+
+#[cfg(Py_LIMITED_API)]
+#[inline]
+pub unsafe fn PyObject_CallOneArg(func: *mut PyObject, arg: *mut PyObject) -> *mut PyObject {
+    PyObject_CallFunctionObjArgs(func, arg, ptr::null_mut::<PyObject>())
+}
+
+#[cfg(all(Py_3_9, not(PyPy), not(Py_LIMITED_API)))]
+#[inline]
+pub unsafe fn PyObject_CallMethodNoArgs(slf: *mut PyObject, name: *mut PyObject) -> *mut PyObject {
+    let nargsf = 1 | PY_VECTORCALL_ARGUMENTS_OFFSET as size_t;
+    PyObject_VectorcallMethod(name, &slf, nargsf, ptr::null_mut())
+}
+
+#[cfg(any(not(Py_3_9), PyPy, Py_LIMITED_API))]
+#[inline]
+pub unsafe fn PyObject_CallMethodNoArgs(slf: *mut PyObject, name: *mut PyObject) -> *mut PyObject {
+    PyObject_CallMethodObjArgs(slf, name, ptr::null_mut::<PyObject>())
+}
+
+#[cfg(all(Py_3_9, not(PyPy), not(Py_LIMITED_API)))]
+#[inline]
+pub unsafe fn PyObject_CallMethodOneArg(
+    slf: *mut PyObject,
+    name: *mut PyObject,
+    arg: *mut PyObject,
+) -> *mut PyObject {
+    let args = [slf, arg];
+    let nargsf = 2 | PY_VECTORCALL_ARGUMENTS_OFFSET as size_t;
+    PyObject_VectorcallMethod(name, args.as_ptr(), nargsf, ptr::null_mut())
+}
+
+#[cfg(any(not(Py_3_9), PyPy, Py_LIMITED_API))]
+#[inline]
+pub unsafe fn PyObject_CallMethodOneArg(
+    slf: *mut PyObject,
+    name: *mut PyObject,
+    arg: *mut PyObject,
+) -> *mut PyObject {
+    PyObject_CallMethodObjArgs(slf, name, arg, ptr::null_mut::<PyObject>())
+}
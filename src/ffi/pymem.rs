@@ -1,5 +1,5 @@
 use libc::size_t;
-use std::os::raw::c_void;
+use std::os::raw::{c_int, c_uint, c_void};
 
 #[cfg(not(Py_LIMITED_API))]
 #[cfg_attr(windows, link(name = "pythonXY"))]
@@ -57,3 +57,10 @@ extern "C" {
         -> ();
     pub fn PyMem_SetupDebugHooks() -> ();
 }
+
+#[cfg(all(Py_3_7, not(any(PyPy, Py_LIMITED_API))))]
+#[cfg_attr(windows, link(name = "pythonXY"))]
+extern "C" {
+    pub fn PyTraceMalloc_Track(domain: c_uint, ptr: libc::uintptr_t, size: size_t) -> c_int;
+    pub fn PyTraceMalloc_Untrack(domain: c_uint, ptr: libc::uintptr_t) -> c_int;
+}
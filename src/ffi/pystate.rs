@@ -27,6 +27,8 @@ extern "C" {
     pub fn PyInterpreterState_New() -> *mut PyInterpreterState;
     pub fn PyInterpreterState_Clear(arg1: *mut PyInterpreterState) -> ();
     pub fn PyInterpreterState_Delete(arg1: *mut PyInterpreterState) -> ();
+    #[cfg(all(Py_3_7, not(PyPy)))]
+    pub fn PyInterpreterState_GetID(arg1: *mut PyInterpreterState) -> i64;
     //fn _PyState_AddModule(arg1: *mut PyObject,
     //                      arg2: *mut PyModuleDef) -> c_int;
     pub fn PyState_FindModule(arg1: *mut PyModuleDef) -> *mut PyObject;
@@ -65,6 +67,10 @@ extern "C" {
     #[cfg_attr(PyPy, link_name = "PyPyGILState_Release")]
     pub fn PyGILState_Release(arg1: PyGILState_STATE) -> ();
     pub fn PyGILState_GetThisThreadState() -> *mut PyThreadState;
+    #[cfg(not(PyPy))]
+    pub fn PyGILState_Check() -> c_int;
+    #[cfg(not(PyPy))]
+    pub fn _PyThreadState_UncheckedGet() -> *mut PyThreadState;
 }
 
 #[inline]
@@ -0,0 +1,283 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Forwarding the arguments of a call to another callable.
+//!
+//! A function taking `*args` and `**kwargs` receives them as a `(&PyTuple, Option<&PyDict>)`
+//! pair. [CallArgs] re-packs that pair so arguments can be added, overridden or removed before
+//! the call is passed on, which is what decorator-style wrappers written in Rust need. The
+//! caller's `kwargs` dict is copied before the first change, so it is never modified.
+//! [update_wrapper] then makes the wrapper look like the function it wraps, like
+//! `functools.wraps` does for Python decorators. [partial] binds arguments given in Rust to a
+//! callable, e.g. to register it as a callback.
+//!
+//! # Example
+//! ```
+//! use pyo3::forward::CallArgs;
+//! use pyo3::prelude::*;
+//! use pyo3::types::{PyAny, PyDict, PyTuple};
+//!
+//! /// Calls `func` with the given arguments, using a timeout of 10 unless one is given.
+//! #[pyfunction(func, args = "*", kwargs = "**")]
+//! fn with_timeout(
+//!     func: &PyAny,
+//!     args: &PyTuple,
+//!     kwargs: Option<&PyDict>,
+//! ) -> PyResult<PyObject> {
+//!     let mut call = CallArgs::new(args, kwargs);
+//!     call.set_default_kwarg("timeout", 10)?;
+//!     call.call(func).map(PyObject::from)
+//! }
+//! ```
+
+use crate::err::PyResult;
+use crate::instance::PyNativeType;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict, PyTuple};
+use crate::{ffi, AsPyPointer, IntoPy, IntoPyPointer, Py, Python, ToPyObject};
+
+/// The positional and keyword arguments of a call, which can be modified and passed on to
+/// another callable.
+pub struct CallArgs<'p> {
+    py: Python<'p>,
+    args: Vec<&'p PyAny>,
+    kwargs: Option<&'p PyDict>,
+    /// Whether `kwargs` is our own copy, which can be modified
+    kwargs_owned: bool,
+}
+
+impl<'p> CallArgs<'p> {
+    /// Wraps the arguments received by a function with `*args` and `**kwargs`.
+    pub fn new(args: &'p PyTuple, kwargs: Option<&'p PyDict>) -> CallArgs<'p> {
+        CallArgs {
+            py: args.py(),
+            args: args.iter().collect(),
+            kwargs,
+            kwargs_owned: false,
+        }
+    }
+
+    /// Creates empty arguments.
+    pub fn empty(py: Python<'p>) -> CallArgs<'p> {
+        CallArgs {
+            py,
+            args: Vec::new(),
+            kwargs: None,
+            kwargs_owned: false,
+        }
+    }
+
+    /// Returns the positional arguments.
+    pub fn args(&self) -> &[&'p PyAny] {
+        &self.args
+    }
+
+    /// Returns the keyword arguments, if there are any.
+    pub fn kwargs(&self) -> Option<&'p PyDict> {
+        self.kwargs
+    }
+
+    /// Returns the keyword argument `name`.
+    pub fn get_kwarg(&self, name: &str) -> Option<&'p PyAny> {
+        self.kwargs.and_then(|kwargs| kwargs.get_item(name))
+    }
+
+    /// Appends a positional argument.
+    pub fn push_arg(&mut self, value: impl ToPyObject) {
+        let value = self.to_any(value);
+        self.args.push(value);
+    }
+
+    /// Inserts a positional argument at `index`, e.g. 0 to pass an extra first argument.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of positional arguments.
+    pub fn insert_arg(&mut self, index: usize, value: impl ToPyObject) {
+        let value = self.to_any(value);
+        self.args.insert(index, value);
+    }
+
+    /// Removes and returns the positional argument at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove_arg(&mut self, index: usize) -> &'p PyAny {
+        self.args.remove(index)
+    }
+
+    /// Sets the keyword argument `name`, overriding the value given by the caller.
+    pub fn set_kwarg(&mut self, name: &str, value: impl ToPyObject) -> PyResult<()> {
+        self.kwargs_mut()?.set_item(name, value)
+    }
+
+    /// Sets the keyword argument `name` unless the caller gave it.
+    pub fn set_default_kwarg(&mut self, name: &str, value: impl ToPyObject) -> PyResult<()> {
+        if self.get_kwarg(name).is_none() {
+            self.set_kwarg(name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the keyword argument `name`, e.g. to consume an argument the
+    /// wrapper handles itself.
+    pub fn remove_kwarg(&mut self, name: &str) -> PyResult<Option<&'p PyAny>> {
+        let value = match self.get_kwarg(name) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        self.kwargs_mut()?.del_item(name)?;
+        Ok(Some(value))
+    }
+
+    /// Calls `callable` with the arguments, like `callable(*args, **kwargs)` in Python.
+    ///
+    /// `callable` can be any Python object, e.g. a `&PyAny` or the `PyObject` returned by
+    /// `wrap_pyfunction!`.
+    pub fn call(&self, callable: &impl AsPyPointer) -> PyResult<&'p PyAny> {
+        let args = self.to_tuple().into_ptr();
+        let kwargs = self.kwargs.into_ptr();
+        let result = unsafe {
+            let result = ffi::PyObject_Call(callable.as_ptr(), args, kwargs);
+            self.py.from_owned_ptr_or_err(result)
+        };
+        unsafe {
+            ffi::Py_DECREF(args);
+            ffi::Py_XDECREF(kwargs);
+        }
+        result
+    }
+
+    /// Converts the arguments back to the `(args, kwargs)` pair.
+    pub fn into_parts(self) -> (&'p PyTuple, Option<&'p PyDict>) {
+        (self.to_tuple(), self.kwargs)
+    }
+
+    fn to_tuple(&self) -> &'p PyTuple {
+        PyTuple::new(self.py, &self.args)
+    }
+
+    fn to_any(&self, value: impl ToPyObject) -> &'p PyAny {
+        unsafe { self.py.from_owned_ptr(value.to_object(self.py).into_ptr()) }
+    }
+
+    /// Returns the keyword arguments for modification, copying the caller's dict first.
+    fn kwargs_mut(&mut self) -> PyResult<&'p PyDict> {
+        let kwargs = match self.kwargs {
+            Some(kwargs) if self.kwargs_owned => kwargs,
+            Some(kwargs) => kwargs.copy()?,
+            None => PyDict::new(self.py),
+        };
+        self.kwargs = Some(kwargs);
+        self.kwargs_owned = true;
+        Ok(kwargs)
+    }
+}
+
+/// The attributes [update_wrapper] copies, like `functools.WRAPPER_ASSIGNMENTS`.
+const WRAPPER_ASSIGNMENTS: [&str; 4] = ["__module__", "__name__", "__qualname__", "__doc__"];
+
+/// Copies `__module__`, `__name__`, `__qualname__` and `__doc__` from `wrapped` to `wrapper`,
+/// merges the `__dict__` of `wrapped` into that of `wrapper` and sets `wrapper.__wrapped__`
+/// to `wrapped`, like `functools.update_wrapper`. Returns `wrapper`.
+///
+/// This keeps `help()`, `inspect.signature` and other introspection working through
+/// decorators implemented in Rust. Attributes `wrapped` doesn't have are skipped. `wrapper`
+/// has to accept new attributes, e.g. an instance of a `#[pyclass(dict)]` with a `__call__`
+/// method. Functions created with `wrap_pyfunction!` can't be modified.
+///
+/// # Example
+/// ```
+/// use pyo3::forward::{update_wrapper, CallArgs};
+/// use pyo3::prelude::*;
+/// use pyo3::types::{PyAny, PyDict, PyTuple};
+/// use pyo3::wrap_pyfunction;
+///
+/// /// Calls a function and counts the calls.
+/// #[pyclass(dict)]
+/// struct Counted {
+///     func: PyObject,
+///     #[pyo3(get)]
+///     calls: u64,
+/// }
+///
+/// #[pymethods]
+/// impl Counted {
+///     #[call]
+///     #[args(args = "*", kwargs = "**")]
+///     fn __call__(&mut self, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<PyObject> {
+///         self.calls += 1;
+///         CallArgs::new(args, kwargs).call(&self.func).map(PyObject::from)
+///     }
+/// }
+///
+/// /// The decorator
+/// #[pyfunction]
+/// fn counted(py: Python, func: &PyAny) -> PyResult<PyObject> {
+///     let wrapper: PyObject = Py::new(py, Counted { func: func.into(), calls: 0 })?.into();
+///     update_wrapper(wrapper.cast_as(py)?, func)?;
+///     Ok(wrapper)
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let counted = wrap_pyfunction!(counted)(py);
+/// pyo3::py_run!(py, counted, r#"
+///     @counted
+///     def greet(name):
+///         "Says hello"
+///         return "Hello " + name
+///
+///     assert greet("world") == "Hello world"
+///     assert (greet.__name__, greet.__doc__, greet.calls) == ("greet", "Says hello", 1)
+/// "#);
+/// ```
+pub fn update_wrapper<'p>(wrapper: &'p PyAny, wrapped: &PyAny) -> PyResult<&'p PyAny> {
+    for name in &WRAPPER_ASSIGNMENTS {
+        if let Ok(value) = wrapped.getattr(*name) {
+            wrapper.setattr(*name, value)?;
+        }
+    }
+    if let Ok(dict) = wrapped.getattr("__dict__") {
+        wrapper
+            .getattr("__dict__")?
+            .call_method1("update", (dict,))?;
+    }
+    // Set last, so it isn't overridden with the `__wrapped__` of `wrapped`
+    wrapper.setattr("__wrapped__", wrapped)?;
+    Ok(wrapper)
+}
+
+/// Returns a callable calling `func` with `args` followed by the positional arguments it gets,
+/// and with `kwargs` updated with the keyword arguments it gets, like
+/// `functools.partial(func, *args, **kwargs)`.
+///
+/// This binds values from Rust to callbacks registered with Python frameworks, which usually
+/// call them with fixed arguments. The result is a `functools.partial` object, so it can be
+/// pickled if `func` and the arguments can, and `inspect.signature` shows the remaining
+/// parameters.
+///
+/// # Example
+/// ```
+/// use pyo3::forward::partial;
+/// use pyo3::prelude::*;
+/// use pyo3::types::IntoPyDict;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let log = py.eval("lambda prefix, message, sep: prefix + sep + message", None, None)?;
+/// let callback = partial(log, ("worker-1",), Some([("sep", ": ")].into_py_dict(py)))?;
+/// let line: String = callback.call1(("started",))?.extract()?;
+/// assert_eq!(line, "worker-1: started");
+/// # Ok::<(), PyErr>(())
+/// ```
+pub fn partial<'p>(
+    func: &'p PyAny,
+    args: impl IntoPy<Py<PyTuple>>,
+    kwargs: Option<&PyDict>,
+) -> PyResult<&'p PyAny> {
+    let py = func.py();
+    let args: &PyTuple = unsafe { py.from_owned_ptr(args.into_py(py).into_ptr()) };
+    let mut partial_args = vec![func];
+    partial_args.extend(args.iter());
+    py.import("functools")?
+        .call("partial", PyTuple::new(py, partial_args), kwargs)
+}
@@ -72,11 +72,16 @@ where
     T: PyObjectWithFreeList,
 {
     unsafe fn alloc(_py: Python) -> *mut ffi::PyObject {
+        let tp_ptr = <Self as PyTypeInfo>::type_object();
         if let Some(obj) = <Self as PyObjectWithFreeList>::get_free_list().pop() {
-            ffi::PyObject_Init(obj, <Self as PyTypeInfo>::type_object());
+            ffi::PyObject_Init(obj, tp_ptr);
+            // Objects are untracked while they are in the free list, see `dealloc`
+            if ffi::PyType_IS_GC(tp_ptr) != 0 {
+                ffi::PyObject_GC_Track(obj as *mut c_void);
+            }
             obj
         } else {
-            ffi::PyType_GenericAlloc(<Self as PyTypeInfo>::type_object(), 0)
+            ffi::PyType_GenericAlloc(tp_ptr, 0)
         }
     }
 
@@ -87,23 +92,37 @@ where
             return;
         }
 
+        let ty = ffi::Py_TYPE(obj);
+        // Instances of Python subclasses have a different size, so they can't be reused
+        if ty != <Self as PyTypeInfo>::type_object() {
+            free::<Self>(obj);
+            return;
+        }
+        // The garbage collector must not traverse the dropped value
+        if ffi::PyType_IS_GC(ty) != 0 {
+            ffi::PyObject_GC_UnTrack(obj as *mut c_void);
+        }
         if let Some(obj) = <Self as PyObjectWithFreeList>::get_free_list().insert(obj) {
-            match Self::type_object().tp_free {
-                Some(free) => free(obj as *mut c_void),
-                None => {
-                    let ty = ffi::Py_TYPE(obj);
-                    if ffi::PyType_IS_GC(ty) != 0 {
-                        ffi::PyObject_GC_Del(obj as *mut c_void);
-                    } else {
-                        ffi::PyObject_Free(obj as *mut c_void);
-                    }
+            free::<Self>(obj);
+        }
+    }
+}
 
-                    // For heap types, PyType_GenericAlloc calls INCREF on the type objects,
-                    // so we need to call DECREF here:
-                    if ffi::PyType_HasFeature(ty, ffi::Py_TPFLAGS_HEAPTYPE) != 0 {
-                        ffi::Py_DECREF(ty as *mut ffi::PyObject);
-                    }
-                }
+unsafe fn free<T: PyTypeInfo>(obj: *mut ffi::PyObject) {
+    match T::type_object().tp_free {
+        Some(free) => free(obj as *mut c_void),
+        None => {
+            let ty = ffi::Py_TYPE(obj);
+            if ffi::PyType_IS_GC(ty) != 0 {
+                ffi::PyObject_GC_Del(obj as *mut c_void);
+            } else {
+                ffi::PyObject_Free(obj as *mut c_void);
+            }
+
+            // For heap types, PyType_GenericAlloc calls INCREF on the type objects,
+            // so we need to call DECREF here:
+            if ffi::PyType_HasFeature(ty, ffi::Py_TPFLAGS_HEAPTYPE) != 0 {
+                ffi::Py_DECREF(ty as *mut ffi::PyObject);
             }
         }
     }
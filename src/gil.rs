@@ -4,11 +4,14 @@
 
 use crate::ffi;
 use crate::internal_tricks::Unsendable;
-use crate::types::PyAny;
-use crate::Python;
+use crate::types::{IntoPyDict, PyAny};
+use crate::{IntoPy, PyObject, Python};
 use spin;
 use std::ptr::NonNull;
-use std::{any, sync};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use std::{any, panic, sync};
 
 static START: sync::Once = sync::Once::new();
 static START_PYO3: sync::Once = sync::Once::new();
@@ -17,7 +20,8 @@ static START_PYO3: sync::Once = sync::Once::new();
 ///
 /// If the Python interpreter is not already initialized, this function
 /// will initialize it with disabled signal handling
-/// (Python will not raise the `KeyboardInterrupt` exception).
+/// (Python will not raise the `KeyboardInterrupt` exception),
+/// unless enabled with [signal::install_python_handlers](signal/fn.install_python_handlers.html).
 /// Python signal handling depends on the notion of a 'main thread', which must be
 /// the thread that initializes the Python interpreter.
 ///
@@ -51,14 +55,15 @@ pub fn prepare_freethreaded_python() {
             #[cfg(not(Py_3_7))]
             assert_eq!(ffi::PyEval_ThreadsInitialized(), 0);
             // Initialize Python.
-            // We use Py_InitializeEx() with initsigs=0 to disable Python signal handling.
+            // We use Py_InitializeEx() with initsigs=0 to disable Python signal handling,
+            // unless the application opted in with `signal::install_python_handlers`.
             // Signal handling depends on the notion of a 'main thread', which doesn't exist in this case.
             // Note that the 'main thread' notion in Python isn't documented properly;
             // and running Python without one is not officially supported.
 
             // PyPy does not support the embedding API
             #[cfg(not(PyPy))]
-            ffi::Py_InitializeEx(0);
+            ffi::Py_InitializeEx(crate::signal::python_handlers_enabled() as std::os::raw::c_int);
 
             // > Changed in version 3.7: This function is now called by Py_Initialize(), so you don’t have
             // > to call it yourself anymore.
@@ -88,6 +93,8 @@ pub fn init_once() {
 
 /// RAII type that represents the Global Interpreter Lock acquisition.
 ///
+/// [Python::with_gil] is usually the better choice, as it releases the GIL on its own.
+///
 /// # Example
 /// ```
 /// use pyo3::Python;
@@ -102,6 +109,7 @@ pub struct GILGuard {
     owned: usize,
     borrowed: usize,
     gstate: ffi::PyGILState_STATE,
+    acquired_at: Option<Instant>,
     // Stable solution for impl !Send
     no_send: Unsendable,
 }
@@ -111,10 +119,138 @@ impl Drop for GILGuard {
     fn drop(&mut self) {
         unsafe {
             let pool: &'static mut ReleasePool = &mut *POOL;
+            pool.check_drain_order(self.owned, self.borrowed);
             pool.drain(self.python(), self.owned, self.borrowed, true);
 
             ffi::PyGILState_Release(self.gstate);
         }
+        if let Some(acquired_at) = self.acquired_at {
+            metrics::record_hold(acquired_at.elapsed());
+        }
+    }
+}
+
+/// Counters for diagnosing contention on the GIL.
+///
+/// Collecting metrics is opt-in: nothing is recorded until [GILMetrics::enable] is called.
+/// Once enabled, every [GILGuard] records how long it waited for the GIL and how long it
+/// was held, and [Python::allow_threads] records how long it waited to get the GIL back.
+///
+/// Note that the hold time of a `GILGuard` includes the periods during which the GIL was
+/// temporarily released (e.g. by `allow_threads` or by the interpreter itself).
+///
+/// # Example
+/// ```
+/// use pyo3::{GILMetrics, Python};
+///
+/// GILMetrics::enable();
+/// {
+///     let _gil = Python::acquire_gil();
+/// }
+/// let metrics = GILMetrics::snapshot();
+/// assert!(metrics.acquisitions >= 1);
+/// ```
+///
+/// A snapshot can be converted into a Python `dict`, so it is easy to expose from a module:
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::GILMetrics;
+///
+/// #[pyfunction]
+/// fn gil_metrics() -> GILMetrics {
+///     GILMetrics::snapshot()
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GILMetrics {
+    /// Number of recorded GIL acquisitions.
+    pub acquisitions: u64,
+    /// Total time spent waiting for the GIL to become available.
+    pub wait_time: Duration,
+    /// Total time the GIL was held by `GILGuard`s.
+    pub hold_time: Duration,
+}
+
+impl GILMetrics {
+    /// Starts recording GIL metrics.
+    pub fn enable() {
+        metrics::ENABLED.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops recording GIL metrics. Already recorded values are kept.
+    pub fn disable() {
+        metrics::ENABLED.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns whether GIL metrics are currently recorded.
+    #[inline]
+    pub fn is_enabled() -> bool {
+        metrics::ENABLED.load(Ordering::Relaxed)
+    }
+
+    /// Returns the values recorded so far.
+    pub fn snapshot() -> GILMetrics {
+        GILMetrics {
+            acquisitions: metrics::ACQUISITIONS.load(Ordering::SeqCst),
+            wait_time: Duration::from_nanos(metrics::WAIT_NANOS.load(Ordering::SeqCst)),
+            hold_time: Duration::from_nanos(metrics::HOLD_NANOS.load(Ordering::SeqCst)),
+        }
+    }
+
+    /// Resets all counters to zero.
+    pub fn reset() {
+        metrics::ACQUISITIONS.store(0, Ordering::SeqCst);
+        metrics::WAIT_NANOS.store(0, Ordering::SeqCst);
+        metrics::HOLD_NANOS.store(0, Ordering::SeqCst);
+    }
+}
+
+/// Converts the metrics into a `dict` with the keys `acquisitions`, `wait_time` and
+/// `hold_time`, where the times are given as float seconds.
+impl IntoPy<PyObject> for GILMetrics {
+    fn into_py(self, py: Python) -> PyObject {
+        let dict = [
+            ("wait_time", self.wait_time.as_secs_f64()),
+            ("hold_time", self.hold_time.as_secs_f64()),
+        ]
+        .into_py_dict(py);
+        dict.set_item("acquisitions", self.acquisitions)
+            .expect("Failed to set acquisitions");
+        dict.into()
+    }
+}
+
+pub(crate) mod metrics {
+    use super::*;
+
+    pub(super) static ENABLED: AtomicBool = AtomicBool::new(false);
+    pub(super) static ACQUISITIONS: AtomicU64 = AtomicU64::new(0);
+    pub(super) static WAIT_NANOS: AtomicU64 = AtomicU64::new(0);
+    pub(super) static HOLD_NANOS: AtomicU64 = AtomicU64::new(0);
+
+    /// Runs `acquire`, recording its duration as time spent waiting for the GIL.
+    /// Returns the point in time the GIL was acquired, if metrics are enabled.
+    #[inline]
+    pub(crate) fn record_acquire<T>(acquire: impl FnOnce() -> T) -> (T, Option<Instant>) {
+        if !GILMetrics::is_enabled() {
+            return (acquire(), None);
+        }
+        let start = Instant::now();
+        let result = acquire();
+        let acquired_at = Instant::now();
+        ACQUISITIONS.fetch_add(1, Ordering::Relaxed);
+        WAIT_NANOS.fetch_add(as_nanos(acquired_at - start), Ordering::Relaxed);
+        (result, Some(acquired_at))
+    }
+
+    #[inline]
+    pub(super) fn record_hold(held: Duration) {
+        HOLD_NANOS.fetch_add(as_nanos(held), Ordering::Relaxed);
+    }
+
+    fn as_nanos(duration: Duration) -> u64 {
+        duration.as_nanos() as u64
     }
 }
 
@@ -156,6 +292,14 @@ impl ReleasePool {
         vec.set_len(0);
     }
 
+    /// Pools must be dropped in the reverse order of their creation.
+    fn check_drain_order(&self, owned: usize, borrowed: usize) {
+        pyo3_assert!(
+            owned <= self.owned.len() && borrowed <= self.borrowed.len(),
+            "GILGuard or GILPool dropped out of order"
+        );
+    }
+
     pub unsafe fn drain(&mut self, _py: Python, owned: usize, borrowed: usize, pointers: bool) {
         // Release owned objects(call decref)
         while owned < self.owned.len() {
@@ -213,6 +357,7 @@ impl<'p> Drop for GILPool<'p> {
     fn drop(&mut self) {
         unsafe {
             let pool: &'static mut ReleasePool = &mut *POOL;
+            pool.check_drain_order(self.owned, self.borrowed);
             pool.drain(self.py, self.owned, self.borrowed, self.pointers);
         }
     }
@@ -254,12 +399,14 @@ impl GILGuard {
         prepare_freethreaded_python();
 
         unsafe {
-            let gstate = ffi::PyGILState_Ensure(); // acquire GIL
+            // acquire GIL
+            let (gstate, acquired_at) = metrics::record_acquire(|| ffi::PyGILState_Ensure());
             let pool: &'static mut ReleasePool = &mut *POOL;
             GILGuard {
                 owned: pool.owned.len(),
                 borrowed: pool.borrowed.len(),
                 gstate,
+                acquired_at,
                 no_send: Unsendable::default(),
             }
         }
@@ -272,6 +419,60 @@ impl GILGuard {
     }
 }
 
+/// How long [Python::try_with_gil] waits for the GIL.
+pub(crate) const TRY_GIL_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// Held by a caller of `with_gil_timeout` from the moment it sees the GIL free until it holds it,
+/// so that two such callers never both go for the GIL and one ends up blocked behind the other.
+#[cfg(not(any(PyPy, Py_3_12)))]
+static GIL_TRY_LOCK: AtomicBool = AtomicBool::new(false);
+
+/// Returns whether the current thread holds the GIL.
+///
+/// Unlike `PyGILState_Check`, this keeps working once subinterpreters were created, which
+/// disables `PyGILState_Check`.
+#[cfg(not(PyPy))]
+pub(crate) fn gil_is_acquired() -> bool {
+    unsafe {
+        let current = ffi::_PyThreadState_UncheckedGet();
+        !current.is_null() && current == ffi::PyGILState_GetThisThreadState()
+    }
+}
+
+#[cfg(not(any(PyPy, Py_3_12)))]
+pub(crate) fn with_gil_timeout<F, R>(timeout: Duration, f: F) -> Option<R>
+where
+    F: for<'py> FnOnce(Python<'py>) -> R,
+{
+    prepare_freethreaded_python();
+    if gil_is_acquired() {
+        return Some(Python::with_gil(f));
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut backoff = Duration::from_micros(1);
+    loop {
+        // Before 3.12, this is the thread state of whichever thread holds the GIL
+        let gil_is_free = unsafe { ffi::_PyThreadState_UncheckedGet().is_null() };
+        if gil_is_free
+            && GIL_TRY_LOCK
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        {
+            return Some(Python::with_gil(|py| {
+                GIL_TRY_LOCK.store(false, Ordering::SeqCst);
+                f(py)
+            }));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        std::thread::sleep(std::cmp::min(backoff, deadline - now));
+        backoff = std::cmp::min(backoff * 2, TRY_GIL_TIMEOUT);
+    }
+}
+
 use self::array_list::ArrayList;
 
 mod array_list {
@@ -330,12 +531,13 @@ mod array_list {
 
 #[cfg(test)]
 mod test {
-    use super::{GILPool, NonNull, ReleasePool, POOL};
+    use super::{GILMetrics, GILPool, NonNull, ReleasePool, POOL};
     use crate::object::PyObject;
+    use crate::types::PyDict;
     use crate::AsPyPointer;
     use crate::Python;
-    use crate::ToPyObject;
     use crate::{ffi, gil};
+    use crate::{IntoPy, ObjectProtocol, ToPyObject};
 
     fn get_object() -> PyObject {
         // Convenience function for getting a single unique object
@@ -476,6 +678,28 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_gil_metrics() {
+        GILMetrics::enable();
+        let before = GILMetrics::snapshot();
+        {
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            py.allow_threads(|| ());
+        }
+        let after = GILMetrics::snapshot();
+        assert!(after.acquisitions >= before.acquisitions + 2);
+        assert!(after.wait_time >= before.wait_time);
+        assert!(after.hold_time >= before.hold_time);
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict: PyObject = after.into_py(py);
+        let dict: &PyDict = dict.extract(py).unwrap();
+        let acquisitions: u64 = dict.get_item("acquisitions").unwrap().extract().unwrap();
+        assert_eq!(acquisitions, after.acquisitions);
+    }
+
     #[test]
     fn test_pyobject_drop() {
         gil::init_once();
@@ -0,0 +1,568 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Converting object graphs whose nodes are shared through `Rc` or `Arc`.
+//!
+//! Converting a value with `ToPyObject` converts every node it reaches, so a node shared by two
+//! parents ends up as two separate Python objects. [ToPyGraph] converts through a
+//! [GraphConverter] instead, which remembers the Python object each `Rc` or `Arc` was converted
+//! to and returns that object when it meets the same node again. Shared nodes therefore stay
+//! shared in Python, so `a.child is b.child` holds and changes made through one parent are seen
+//! through the other.
+//!
+//! [ToPyGraph] is implemented for `Rc`, `Arc`, `RefCell`, `Box`, `Option`, `Vec`, slices, maps
+//! and the types that convert to Python numbers and strings. Other types implement it by
+//! converting their fields with [GraphConverter::convert]. Nodes that aren't behind an `Rc` or
+//! `Arc`, such as the nodes of an arena, can be memoized with [GraphConverter::get_or_convert].
+//!
+//! # Cycles
+//!
+//! A node that can be reached from its own fields, e.g. a doubly linked list or a graph using
+//! `Rc<RefCell<_>>`, needs its Python object before its fields are converted. Such nodes also
+//! implement [ToPyGraph::create_object_graph], which creates an empty object such as a dict,
+//! and [ToPyGraph::fill_object_graph], which converts the fields into it. The converter
+//! remembers the object in between, so a field leading back to the node gets the same object
+//! and the Python objects form the same cycle. [GraphConverter::get_or_create] does the same
+//! for arena nodes. Converting a cycle through nodes without these methods raises `ValueError`
+//! instead of recursing forever.
+//!
+//! Python frees cyclic objects with its garbage collector, which handles dicts, lists and
+//! `#[pyclass(gc)]` classes.
+//!
+//! # Example
+//! ```
+//! use pyo3::graph::{to_object_graph, GraphConverter, ToPyGraph};
+//! use pyo3::prelude::*;
+//! use pyo3::types::PyDict;
+//! use std::rc::Rc;
+//!
+//! struct Node {
+//!     name: String,
+//!     children: Vec<Rc<Node>>,
+//! }
+//!
+//! impl ToPyGraph for Node {
+//!     fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+//!         let dict = PyDict::new(converter.py());
+//!         dict.set_item("name", &self.name)?;
+//!         dict.set_item("children", converter.convert(&self.children)?)?;
+//!         Ok(dict.into())
+//!     }
+//! }
+//!
+//! let leaf = Rc::new(Node { name: "leaf".to_string(), children: vec![] });
+//! let root = Node {
+//!     name: "root".to_string(),
+//!     children: vec![leaf.clone(), leaf],
+//! };
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let root = to_object_graph(py, &root)?;
+//! pyo3::py_run!(py, root, "assert root['children'][0] is root['children'][1]");
+//! # Ok::<(), PyErr>(())
+//! ```
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::ValueError;
+use crate::object::PyObject;
+use crate::types::{PyAny, PyDict, PyList};
+use crate::{Python, ToPyObject};
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Converts `value` to a Python object, converting every shared node only once.
+pub fn to_object_graph<T>(py: Python, value: &T) -> PyResult<PyObject>
+where
+    T: ToPyGraph + ?Sized,
+{
+    GraphConverter::new(py).convert(value)
+}
+
+/// Conversion to Python objects that preserves the sharing of nodes, see the
+/// [module documentation](self).
+pub trait ToPyGraph {
+    /// Converts `self`, converting nested values with [GraphConverter::convert].
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject>;
+
+    /// Creates the object of `self` without converting the values it refers to, which
+    /// [ToPyGraph::fill_object_graph] then adds.
+    ///
+    /// Nodes that can be reached from their own fields implement both methods, so the object
+    /// of a node shared through `Rc` or `Arc` exists before its fields are converted. Returns
+    /// `None` by default, and converting a cycle through such nodes raises `ValueError`.
+    fn create_object_graph(&self, _py: Python) -> PyResult<Option<PyObject>> {
+        Ok(None)
+    }
+
+    /// Converts the values `self` refers to into `object`, the object returned by
+    /// [ToPyGraph::create_object_graph].
+    fn fill_object_graph(&self, _object: &PyAny, _converter: &mut GraphConverter) -> PyResult<()> {
+        Ok(())
+    }
+}
+
+/// Identifies a node by its address and type, since a struct and its first field have the same
+/// address.
+type NodeKey = (usize, TypeId);
+
+fn node_key<T: 'static>(node: &T) -> NodeKey {
+    (node as *const T as usize, TypeId::of::<T>())
+}
+
+/// The conversion of one object graph, which remembers the Python object of every node.
+pub struct GraphConverter<'p> {
+    py: Python<'p>,
+    /// The objects of the converted nodes, and `None` for the nodes being converted
+    objects: HashMap<NodeKey, Option<PyObject>>,
+}
+
+impl<'p> GraphConverter<'p> {
+    /// Creates a converter that hasn't converted any nodes yet.
+    pub fn new(py: Python<'p>) -> GraphConverter<'p> {
+        GraphConverter {
+            py,
+            objects: HashMap::new(),
+        }
+    }
+
+    /// Returns the Python token.
+    pub fn py(&self) -> Python<'p> {
+        self.py
+    }
+
+    /// Converts `value`, returning the objects of the nodes converted before when they're
+    /// reached again.
+    ///
+    /// Keep using the same converter for all values whose nodes should be shared.
+    pub fn convert<T>(&mut self, value: &T) -> PyResult<PyObject>
+    where
+        T: ToPyGraph + ?Sized,
+    {
+        value.to_object_graph(self)
+    }
+
+    /// Returns the object `node` was converted to, or converts it with `convert` and remembers
+    /// the result.
+    ///
+    /// This memoizes nodes that aren't behind an `Rc` or `Arc`, e.g. the nodes of an arena.
+    /// Nodes are identified by their address, so `node` has to stay at the same place while the
+    /// converter is used.
+    ///
+    /// Raises `ValueError` when `node` is reached again while it's being converted, i.e. when
+    /// the graph has a cycle. Use [GraphConverter::get_or_create] for such nodes.
+    pub fn get_or_convert<T, F>(&mut self, node: &T, convert: F) -> PyResult<PyObject>
+    where
+        T: 'static,
+        F: FnOnce(&mut Self) -> PyResult<PyObject>,
+    {
+        let key = node_key(node);
+        if let Some(result) = self.lookup::<T>(key) {
+            return result;
+        }
+        self.objects.insert(key, None);
+        let result = convert(self);
+        match &result {
+            Ok(object) => {
+                self.objects.insert(key, Some(object.clone_ref(self.py)));
+            }
+            Err(_) => {
+                self.objects.remove(&key);
+            }
+        }
+        result
+    }
+
+    /// Returns the object `node` was converted to, or creates it with `create` and converts the
+    /// values `node` refers to with `fill`.
+    ///
+    /// The object is remembered before `fill` is called, so the values can refer back to
+    /// `node`, and the Python objects get the same cycles as the Rust values.
+    pub fn get_or_create<T, C, F>(&mut self, node: &T, create: C, fill: F) -> PyResult<PyObject>
+    where
+        T: 'static,
+        C: FnOnce(Python<'p>) -> PyResult<PyObject>,
+        F: FnOnce(&PyAny, &mut Self) -> PyResult<()>,
+    {
+        let key = node_key(node);
+        if let Some(result) = self.lookup::<T>(key) {
+            return result;
+        }
+        let object = create(self.py)?;
+        self.fill(key, object, fill)
+    }
+
+    /// Converts a node shared through `Rc` or `Arc`, creating its object first if it supports
+    /// that.
+    fn convert_node<T: ToPyGraph + 'static>(&mut self, node: &T) -> PyResult<PyObject> {
+        let key = node_key(node);
+        if let Some(result) = self.lookup::<T>(key) {
+            return result;
+        }
+        match node.create_object_graph(self.py)? {
+            Some(object) => self.fill(key, object, |object, converter| {
+                node.fill_object_graph(object, converter)
+            }),
+            None => self.get_or_convert(node, |converter| converter.convert(node)),
+        }
+    }
+
+    /// Returns the object of a converted node, or raises `ValueError` if the node is still
+    /// being converted.
+    fn lookup<T>(&self, key: NodeKey) -> Option<PyResult<PyObject>> {
+        match self.objects.get(&key)? {
+            Some(object) => Some(Ok(object.clone_ref(self.py))),
+            None => Some(Err(PyErr::new::<ValueError, _>(format!(
+                "cannot convert a cyclic reference to {}, which isn't created before its fields \
+                 are converted",
+                std::any::type_name::<T>()
+            )))),
+        }
+    }
+
+    /// Remembers `object` as the object of the node and converts the node's values into it.
+    fn fill<F>(&mut self, key: NodeKey, object: PyObject, fill: F) -> PyResult<PyObject>
+    where
+        F: FnOnce(&PyAny, &mut Self) -> PyResult<()>,
+    {
+        self.objects.insert(key, Some(object.clone_ref(self.py)));
+        let result = fill(object.cast_as(self.py)?, self);
+        if result.is_err() {
+            self.objects.remove(&key);
+        }
+        result.map(|()| object)
+    }
+
+    /// Returns the number of nodes converted so far.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Checks if no nodes were converted yet.
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+impl<T: ToPyGraph + 'static> ToPyGraph for Rc<T> {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        converter.convert_node::<T>(self)
+    }
+}
+
+impl<T: ToPyGraph + 'static> ToPyGraph for Arc<T> {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        converter.convert_node::<T>(self)
+    }
+}
+
+impl<T: ToPyGraph + ?Sized> ToPyGraph for RefCell<T> {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        self.borrow().to_object_graph(converter)
+    }
+
+    fn create_object_graph(&self, py: Python) -> PyResult<Option<PyObject>> {
+        self.borrow().create_object_graph(py)
+    }
+
+    fn fill_object_graph(&self, object: &PyAny, converter: &mut GraphConverter) -> PyResult<()> {
+        self.borrow().fill_object_graph(object, converter)
+    }
+}
+
+impl<T: ToPyGraph + ?Sized> ToPyGraph for Box<T> {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        (**self).to_object_graph(converter)
+    }
+
+    fn create_object_graph(&self, py: Python) -> PyResult<Option<PyObject>> {
+        (**self).create_object_graph(py)
+    }
+
+    fn fill_object_graph(&self, object: &PyAny, converter: &mut GraphConverter) -> PyResult<()> {
+        (**self).fill_object_graph(object, converter)
+    }
+}
+
+impl<'a, T: ToPyGraph + ?Sized> ToPyGraph for &'a T {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        (**self).to_object_graph(converter)
+    }
+
+    fn create_object_graph(&self, py: Python) -> PyResult<Option<PyObject>> {
+        (**self).create_object_graph(py)
+    }
+
+    fn fill_object_graph(&self, object: &PyAny, converter: &mut GraphConverter) -> PyResult<()> {
+        (**self).fill_object_graph(object, converter)
+    }
+}
+
+impl<T: ToPyGraph> ToPyGraph for Option<T> {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        match self {
+            Some(value) => converter.convert(value),
+            None => Ok(converter.py().None()),
+        }
+    }
+}
+
+impl<T: ToPyGraph> ToPyGraph for [T] {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        let items = self
+            .iter()
+            .map(|item| converter.convert(item))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PyList::new(converter.py(), items).into())
+    }
+}
+
+impl<T: ToPyGraph> ToPyGraph for Vec<T> {
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        converter.convert(self.as_slice())
+    }
+}
+
+impl<K, V, H> ToPyGraph for HashMap<K, V, H>
+where
+    K: Hash + Eq + ToPyObject,
+    V: ToPyGraph,
+    H: BuildHasher,
+{
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        let dict = PyDict::new(converter.py());
+        for (key, value) in self {
+            dict.set_item(key, converter.convert(value)?)?;
+        }
+        Ok(dict.into())
+    }
+}
+
+impl<K, V> ToPyGraph for BTreeMap<K, V>
+where
+    K: Eq + ToPyObject,
+    V: ToPyGraph,
+{
+    fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+        let dict = PyDict::new(converter.py());
+        for (key, value) in self {
+            dict.set_item(key, converter.convert(value)?)?;
+        }
+        Ok(dict.into())
+    }
+}
+
+/// Implements `ToPyGraph` for types without nested nodes through `ToPyObject`.
+macro_rules! to_py_graph_leaf {
+    ($($ty: ty),*) => {
+        $(
+            impl ToPyGraph for $ty {
+                fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+                    Ok(self.to_object(converter.py()))
+                }
+            }
+        )*
+    };
+}
+
+to_py_graph_leaf!(
+    (),
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    f32,
+    f64,
+    str,
+    String,
+    PyObject
+);
+
+#[cfg(test)]
+mod test {
+    use super::{to_object_graph, GraphConverter, ToPyGraph};
+    use crate::err::PyResult;
+    use crate::exceptions;
+    use crate::object::PyObject;
+    use crate::types::{IntoPyDict, PyAny, PyDict, PyList};
+    use crate::Python;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    struct Node {
+        value: i32,
+        next: Option<Rc<RefCell<Node>>>,
+    }
+
+    impl ToPyGraph for Node {
+        fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+            let dict = PyDict::new(converter.py());
+            dict.set_item("value", self.value)?;
+            dict.set_item("next", converter.convert(&self.next)?)?;
+            Ok(dict.into())
+        }
+    }
+
+    fn node(value: i32, next: Option<Rc<RefCell<Node>>>) -> Rc<RefCell<Node>> {
+        Rc::new(RefCell::new(Node { value, next }))
+    }
+
+    #[test]
+    fn test_shared_nodes() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let tail = node(3, None);
+        let a = node(1, Some(tail.clone()));
+        let b = node(2, Some(tail.clone()));
+
+        let mut converter = GraphConverter::new(py);
+        let lists = converter.convert(&vec![a.clone(), b, a]).unwrap();
+        assert_eq!(converter.len(), 3);
+        let locals = [("lists", lists)].into_py_dict(py);
+        py.run(
+            "assert lists[0] is lists[2]\n\
+             assert lists[0]['next'] is lists[1]['next']\n\
+             assert lists[1] == {'value': 2, 'next': {'value': 3, 'next': None}}",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        // Nodes converted before keep their objects
+        let again = converter.convert(&tail).unwrap();
+        locals.set_item("again", again).unwrap();
+        py.run("assert again is lists[0]['next']", None, Some(locals))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_arc_map() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let shared = Arc::new(vec![1, 2]);
+        let mut map = BTreeMap::new();
+        map.insert("a", shared.clone());
+        map.insert("b", shared);
+        let dict = to_object_graph(py, &map).unwrap();
+        let locals = [("d", dict)].into_py_dict(py);
+        py.run("assert d['a'] is d['b'] == [1, 2]", None, Some(locals))
+            .unwrap();
+    }
+
+    /// A node of a cyclic graph
+    struct Person {
+        name: &'static str,
+        friends: Vec<Rc<RefCell<Person>>>,
+    }
+
+    impl ToPyGraph for Person {
+        fn to_object_graph(&self, converter: &mut GraphConverter) -> PyResult<PyObject> {
+            let dict = PyDict::new(converter.py());
+            self.fill_object_graph(dict.as_ref(), converter)?;
+            Ok(dict.into())
+        }
+
+        fn create_object_graph(&self, py: Python) -> PyResult<Option<PyObject>> {
+            Ok(Some(PyDict::new(py).into()))
+        }
+
+        fn fill_object_graph(
+            &self,
+            object: &PyAny,
+            converter: &mut GraphConverter,
+        ) -> PyResult<()> {
+            let dict: &PyDict = object.downcast_ref()?;
+            dict.set_item("name", self.name)?;
+            dict.set_item("friends", converter.convert(&self.friends)?)
+        }
+    }
+
+    #[test]
+    fn test_cyclic_nodes() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let person = |name| {
+            Rc::new(RefCell::new(Person {
+                name,
+                friends: Vec::new(),
+            }))
+        };
+        let (alice, bob) = (person("alice"), person("bob"));
+        alice.borrow_mut().friends = vec![bob.clone(), alice.clone()];
+        bob.borrow_mut().friends = vec![alice.clone()];
+
+        let alice_obj = to_object_graph(py, &alice).unwrap();
+        let locals = [("alice", alice_obj)].into_py_dict(py);
+        py.run(
+            "bob = alice['friends'][0]\n\
+             assert bob['name'] == 'bob' and bob['friends'][0] is alice\n\
+             assert alice['friends'][1] is alice",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        // Break the Rust cycles so the nodes are freed
+        alice.borrow_mut().friends.clear();
+        bob.borrow_mut().friends.clear();
+    }
+
+    #[test]
+    fn test_arena_cycle() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // Each node refers to the next by its index, and the last one to the first
+        let arena: Vec<usize> = vec![1, 2, 0];
+
+        fn convert(
+            arena: &[usize],
+            index: usize,
+            converter: &mut GraphConverter,
+        ) -> PyResult<PyObject> {
+            converter.get_or_create(
+                &arena[index],
+                |py| Ok(PyList::empty(py).into()),
+                |object, converter| {
+                    let list: &PyList = object.downcast_ref()?;
+                    list.append(index)?;
+                    list.append(convert(arena, arena[index], converter)?)
+                },
+            )
+        }
+
+        let mut converter = GraphConverter::new(py);
+        let first = convert(&arena, 0, &mut converter).unwrap();
+        assert_eq!(converter.len(), 3);
+        let locals = [("first", first)].into_py_dict(py);
+        py.run(
+            "assert first[1][1][0] == 2 and first[1][1][1] is first",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_cycle() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let a = node(1, None);
+        let b = node(2, Some(a.clone()));
+        a.borrow_mut().next = Some(b);
+        let err = to_object_graph(py, &a).unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        a.borrow_mut().next = None;
+    }
+}
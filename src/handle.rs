@@ -0,0 +1,353 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Handles to the entries of an arena owned by Rust.
+//!
+//! Graphs, ECS worlds and document trees are usually stored in an arena, such as a `Vec`, a
+//! `HashMap` or a slot map, with the entries referring to each other by key. Instead of wrapping
+//! every entry in a Python object, a class owning the arena can give Python code [PyHandle]s:
+//! lightweight `pyo3.Handle` objects holding just the key. Its methods take handles as
+//! arguments and look the entries up with [PyHandle::get], which raises `ReferenceError` when
+//! the entry was removed in the meantime, instead of returning a dangling reference.
+//!
+//! Handles compare equal and hash alike when their keys do, so they work as dict keys and in
+//! sets. Extracting a `PyHandle<K>` from a handle with another key type raises `TypeError`, so
+//! a node handle can't be passed where an edge handle is expected. With arenas that reuse
+//! keys, e.g. `Vec<Option<T>>`, a handle to a removed entry finds a new entry stored under the
+//! same key; generational keys like those of slot maps avoid that.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::handle::PyHandle;
+//! use pyo3::prelude::*;
+//! use std::collections::HashMap;
+//!
+//! #[pyclass]
+//! struct Document {
+//!     nodes: HashMap<u32, String>,
+//!     next_key: u32,
+//! }
+//!
+//! #[pymethods]
+//! impl Document {
+//!     fn add(&mut self, text: String) -> PyHandle<u32> {
+//!         self.next_key += 1;
+//!         self.nodes.insert(self.next_key, text);
+//!         PyHandle::new(self.next_key)
+//!     }
+//!
+//!     fn text(&self, node: PyHandle<u32>) -> PyResult<String> {
+//!         Ok(node.get(&self.nodes)?.clone())
+//!     }
+//!
+//!     fn remove(&mut self, node: PyHandle<u32>) {
+//!         self.nodes.remove(&node.key());
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let doc = Py::new(py, Document { nodes: HashMap::new(), next_key: 0 }).unwrap();
+//! pyo3::py_run!(py, doc, r#"
+//! node = doc.add("hello")
+//! assert doc.text(node) == "hello"
+//! doc.remove(node)
+//! try:
+//!     doc.text(node)
+//! except ReferenceError:
+//!     pass
+//! "#);
+//! ```
+
+use crate::class::basic::PyObjectProtocol;
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{ReferenceError, TypeError};
+use crate::instance::Py;
+use crate::object::PyObject;
+use crate::types::PyAny;
+use crate::{FromPyObject, IntoPy, PyTryFrom, Python};
+use pyo3cls::{pyclass, pyproto};
+use std::any::{Any, TypeId};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+/// A key Python code holds through a `pyo3.Handle` object, see the
+/// [module documentation](self).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PyHandle<K> {
+    key: K,
+}
+
+impl<K> PyHandle<K>
+where
+    K: Copy + Eq + Hash + fmt::Debug + 'static,
+{
+    /// Creates a handle to the entry stored under `key`.
+    pub fn new(key: K) -> PyHandle<K> {
+        PyHandle { key }
+    }
+
+    /// Returns the key.
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    /// Returns the entry of `arena` this handle refers to, or raises `ReferenceError` if it was
+    /// removed.
+    pub fn get<'a, A>(&self, arena: &'a A) -> PyResult<&'a A::Value>
+    where
+        A: HandleArena<K> + ?Sized,
+    {
+        arena.get_entry(self.key).ok_or_else(|| self.removed())
+    }
+
+    /// Returns the entry of `arena` this handle refers to for modification, or raises
+    /// `ReferenceError` if it was removed.
+    pub fn get_mut<'a, A>(&self, arena: &'a mut A) -> PyResult<&'a mut A::Value>
+    where
+        A: HandleArena<K> + ?Sized,
+    {
+        let err = self.removed();
+        arena.get_entry_mut(self.key).ok_or(err)
+    }
+
+    /// Looks the entry up with `lookup`, raising `ReferenceError` if it returns `None`.
+    ///
+    /// This supports arenas that don't implement [HandleArena], e.g. `|key| slot_map.get(key)`.
+    pub fn resolve<T, F>(&self, lookup: F) -> PyResult<T>
+    where
+        F: FnOnce(K) -> Option<T>,
+    {
+        lookup(self.key).ok_or_else(|| self.removed())
+    }
+
+    fn removed(&self) -> PyErr {
+        PyErr::new::<ReferenceError, _>(format!("handle {:?} refers to a removed entry", self.key))
+    }
+}
+
+impl<K> IntoPy<PyObject> for PyHandle<K>
+where
+    K: Copy + Eq + Hash + fmt::Debug + 'static,
+{
+    fn into_py(self, py: Python) -> PyObject {
+        Py::new(
+            py,
+            Handle {
+                key: Box::new(self.key),
+            },
+        )
+        .expect("failed to create a handle")
+        .into()
+    }
+}
+
+impl<'source, K> FromPyObject<'source> for PyHandle<K>
+where
+    K: Copy + Eq + Hash + fmt::Debug + 'static,
+{
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let handle = <Handle as PyTryFrom>::try_from(ob)?;
+        match handle.key.as_any().downcast_ref::<K>() {
+            Some(key) => Ok(PyHandle::new(*key)),
+            None => Err(PyErr::new::<TypeError, _>(format!(
+                "expected a handle with a key of type {}, got {}",
+                std::any::type_name::<K>(),
+                handle.key.type_name()
+            ))),
+        }
+    }
+}
+
+/// An arena whose entries can be looked up through a [PyHandle].
+pub trait HandleArena<K> {
+    /// The type of the entries
+    type Value;
+
+    /// Returns the entry stored under `key`.
+    fn get_entry(&self, key: K) -> Option<&Self::Value>;
+
+    /// Returns the entry stored under `key` for modification.
+    fn get_entry_mut(&mut self, key: K) -> Option<&mut Self::Value>;
+}
+
+impl<K, V, H> HandleArena<K> for HashMap<K, V, H>
+where
+    K: Eq + Hash,
+    H: BuildHasher,
+{
+    type Value = V;
+
+    fn get_entry(&self, key: K) -> Option<&V> {
+        self.get(&key)
+    }
+
+    fn get_entry_mut(&mut self, key: K) -> Option<&mut V> {
+        self.get_mut(&key)
+    }
+}
+
+impl<K: Ord, V> HandleArena<K> for BTreeMap<K, V> {
+    type Value = V;
+
+    fn get_entry(&self, key: K) -> Option<&V> {
+        self.get(&key)
+    }
+
+    fn get_entry_mut(&mut self, key: K) -> Option<&mut V> {
+        self.get_mut(&key)
+    }
+}
+
+/// Entries are removed by setting them to `None`, so the indices of the others stay valid.
+impl<V> HandleArena<usize> for [Option<V>] {
+    type Value = V;
+
+    fn get_entry(&self, key: usize) -> Option<&V> {
+        self.get(key)?.as_ref()
+    }
+
+    fn get_entry_mut(&mut self, key: usize) -> Option<&mut V> {
+        self.get_mut(key)?.as_mut()
+    }
+}
+
+impl<V> HandleArena<usize> for Vec<Option<V>> {
+    type Value = V;
+
+    fn get_entry(&self, key: usize) -> Option<&V> {
+        self.as_slice().get_entry(key)
+    }
+
+    fn get_entry_mut(&mut self, key: usize) -> Option<&mut V> {
+        self.as_mut_slice().get_entry_mut(key)
+    }
+}
+
+/// The operations `pyo3.Handle` needs on a key whose type was erased.
+trait HandleKey {
+    fn as_any(&self) -> &dyn Any;
+    fn type_name(&self) -> &'static str;
+    fn eq_key(&self, other: &dyn HandleKey) -> bool;
+    fn hash_key(&self) -> u64;
+    fn fmt_key(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+impl<K> HandleKey for K
+where
+    K: Eq + Hash + fmt::Debug + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<K>()
+    }
+
+    fn eq_key(&self, other: &dyn HandleKey) -> bool {
+        other.as_any().downcast_ref::<K>() == Some(self)
+    }
+
+    fn hash_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        TypeId::of::<K>().hash(&mut hasher);
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn fmt_key(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The Python object of a [PyHandle].
+#[pyclass(module = "pyo3", eq, hash)]
+struct Handle {
+    key: Box<dyn HandleKey>,
+}
+
+impl PartialEq for Handle {
+    fn eq(&self, other: &Handle) -> bool {
+        self.key.eq_key(&*other.key)
+    }
+}
+
+impl Hash for Handle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key.hash_key())
+    }
+}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.key.fmt_key(f)
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol<'_> for Handle {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("Handle({:?})", self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyHandle;
+    use crate::exceptions;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::IntoPyDict;
+    use crate::{IntoPy, PyObject, Python};
+
+    #[test]
+    fn test_handle_lookup() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut arena = vec![Some("a".to_string()), Some("b".to_string())];
+        let obj: PyObject = PyHandle::new(1usize).into_py(py);
+        let handle: PyHandle<usize> = obj.extract(py).unwrap();
+        assert_eq!(handle.get(&arena).unwrap(), "b");
+        handle.get_mut(&mut arena).unwrap().push('!');
+        assert_eq!(arena[1].as_ref().unwrap(), "b!");
+
+        arena[1] = None;
+        let err = handle.get(&arena).unwrap_err();
+        assert!(err.is_instance::<exceptions::ReferenceError>(py));
+        let err = PyHandle::new(5).resolve(|key| arena.get(key)).unwrap_err();
+        assert!(err.is_instance::<exceptions::ReferenceError>(py));
+    }
+
+    #[test]
+    fn test_handle_object() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let a: PyObject = PyHandle::new(1u32).into_py(py);
+        let a_again: PyObject = PyHandle::new(1u32).into_py(py);
+        let b: PyObject = PyHandle::new(2u32).into_py(py);
+        let other_type: PyObject = PyHandle::new(1u64).into_py(py);
+        let locals = [
+            ("a", a),
+            ("a_again", a_again),
+            ("b", b),
+            ("other", other_type),
+        ]
+        .into_py_dict(py);
+        py.run(
+            "assert a == a_again and hash(a) == hash(a_again) and a != b\n\
+             assert a != other and len({a, a_again, b, other}) == 3\n\
+             assert repr(a) == 'Handle(1)' and type(a).__module__ == 'pyo3'",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let other = locals.get_item("other").unwrap();
+        let err = other.extract::<PyHandle<u32>>().unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+        let err = py.eval("1", None, None).unwrap().extract::<PyHandle<u32>>();
+        assert!(err.unwrap_err().is_instance::<exceptions::TypeError>(py));
+    }
+}
@@ -0,0 +1,274 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Python-compatible hashing from Rust.
+//!
+//! [PyHash::py_hash] returns the same value as Python's `hash()` for Rust integers, strings and
+//! tuples of them, without creating Python objects. This allows Rust data structures to bucket
+//! values consistently with Python's `dict` and `set`. String hashes depend on
+//! `PYTHONHASHSEED`, so they use the hash function and secret of the running interpreter, and
+//! are only stable within one process.
+//!
+//! # Example
+//! ```
+//! use pyo3::hash::PyHash;
+//! use pyo3::prelude::*;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let key = ("answer", 42);
+//! let hash: isize = py.eval("hash(('answer', 42))", None, None).unwrap().extract().unwrap();
+//! assert_eq!(key.py_hash(py), hash);
+//! ```
+
+use crate::ffi;
+use crate::Python;
+use std::ffi::CStr;
+use std::os::raw::c_void;
+
+/// The number of bits of the modulus of integer hashes.
+#[cfg(target_pointer_width = "64")]
+pub const HASH_BITS: u32 = 61;
+/// The number of bits of the modulus of integer hashes.
+#[cfg(not(target_pointer_width = "64"))]
+pub const HASH_BITS: u32 = 31;
+
+/// The prime modulus of integer hashes, `sys.hash_info.modulus`.
+pub const HASH_MODULUS: u64 = (1 << HASH_BITS) - 1;
+
+/// Values with the same hash as in Python.
+pub trait PyHash {
+    /// Returns the value of Python's `hash()` for the Python equivalent of `self`.
+    fn py_hash(&self, py: Python) -> isize;
+}
+
+/// Returns the hash of a Python `int`.
+pub fn hash_int(value: i128) -> isize {
+    let hash = (value.wrapping_abs() as u128 % u128::from(HASH_MODULUS)) as isize;
+    let hash = if value < 0 { -hash } else { hash };
+    // -1 is the error value of the C API
+    if hash == -1 {
+        -2
+    } else {
+        hash
+    }
+}
+
+/// Returns the hash of a Python `bytes` object, which depends on `PYTHONHASHSEED`.
+pub fn hash_bytes(_py: Python, value: &[u8]) -> isize {
+    if value.is_empty() {
+        return 0;
+    }
+    let hash = unsafe { (*ffi::PyHash_GetFuncDef()).hash }
+        .expect("the interpreter has no string hash function");
+    let hash = hash(
+        value.as_ptr() as *const c_void,
+        value.len() as ffi::Py_ssize_t,
+    );
+    if hash == -1 {
+        -2
+    } else {
+        hash
+    }
+}
+
+/// Returns the hash of a Python `str`, which depends on `PYTHONHASHSEED`.
+pub fn hash_str(py: Python, value: &str) -> isize {
+    // Python hashes the code units of the narrowest of the latin-1, UCS-2 and UCS-4 encodings
+    let max_char = value.chars().max().map_or(0, u32::from);
+    if max_char < 0x100 {
+        let units: Vec<u8> = value.chars().map(|c| c as u8).collect();
+        hash_bytes(py, &units)
+    } else if max_char < 0x10000 {
+        let units: Vec<u8> = value
+            .chars()
+            .flat_map(|c| (c as u16).to_ne_bytes().to_vec())
+            .collect();
+        hash_bytes(py, &units)
+    } else {
+        let units: Vec<u8> = value
+            .chars()
+            .flat_map(|c| (c as u32).to_ne_bytes().to_vec())
+            .collect();
+        hash_bytes(py, &units)
+    }
+}
+
+/// Returns the hash of a Python `tuple` from the hashes of its items.
+#[cfg(Py_3_8)]
+pub fn hash_tuple(hashes: &[isize]) -> isize {
+    #[cfg(target_pointer_width = "64")]
+    const PRIMES: (usize, usize, usize) = (
+        11_400_714_785_074_694_791,
+        14_029_467_366_897_019_727,
+        2_870_177_450_012_600_261,
+    );
+    #[cfg(target_pointer_width = "64")]
+    const ROTATE: u32 = 31;
+    #[cfg(not(target_pointer_width = "64"))]
+    const PRIMES: (usize, usize, usize) = (2_654_435_761, 2_246_822_519, 374_761_393);
+    #[cfg(not(target_pointer_width = "64"))]
+    const ROTATE: u32 = 13;
+
+    // The xxHash based algorithm of CPython 3.8
+    let mut acc = PRIMES.2;
+    for &hash in hashes {
+        acc = acc.wrapping_add((hash as usize).wrapping_mul(PRIMES.1));
+        acc = acc.rotate_left(ROTATE);
+        acc = acc.wrapping_mul(PRIMES.0);
+    }
+    acc = acc.wrapping_add(hashes.len() ^ (PRIMES.2 ^ 3_527_539));
+    if acc == usize::max_value() {
+        1_546_275_796
+    } else {
+        acc as isize
+    }
+}
+
+/// Returns the hash of a Python `tuple` from the hashes of its items.
+#[cfg(not(Py_3_8))]
+pub fn hash_tuple(hashes: &[isize]) -> isize {
+    let mut acc: usize = 0x0034_5678;
+    let mut mult: usize = 1_000_003;
+    for (i, &hash) in hashes.iter().enumerate() {
+        let remaining = hashes.len() - i - 1;
+        acc = (acc ^ hash as usize).wrapping_mul(mult);
+        mult = mult.wrapping_add(82520 + remaining + remaining);
+    }
+    acc = acc.wrapping_add(97531);
+    if acc == usize::max_value() {
+        -2
+    } else {
+        acc as isize
+    }
+}
+
+/// The string hash algorithm of the interpreter, as in `sys.hash_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashInfo {
+    /// The name of the algorithm, `siphash13` or `siphash24` by default.
+    pub algorithm: String,
+    /// The size of the output of the algorithm in bits.
+    pub hash_bits: u32,
+    /// The size of the seed of the algorithm in bits.
+    pub seed_bits: u32,
+}
+
+/// Returns the string hash algorithm of the interpreter.
+pub fn hash_info(_py: Python) -> HashInfo {
+    let def = unsafe { &*ffi::PyHash_GetFuncDef() };
+    HashInfo {
+        algorithm: unsafe { CStr::from_ptr(def.name) }
+            .to_string_lossy()
+            .into_owned(),
+        hash_bits: def.hash_bits as u32,
+        seed_bits: def.seed_bits as u32,
+    }
+}
+
+macro_rules! int_hash {
+    ($($t:ty),*) => {
+        $(
+            impl PyHash for $t {
+                fn py_hash(&self, _py: Python) -> isize {
+                    hash_int(*self as i128)
+                }
+            }
+        )*
+    };
+}
+
+int_hash!(bool, i8, u8, i16, u16, i32, u32, i64, u64, isize, usize, i128);
+
+impl PyHash for str {
+    fn py_hash(&self, py: Python) -> isize {
+        hash_str(py, self)
+    }
+}
+
+impl PyHash for String {
+    fn py_hash(&self, py: Python) -> isize {
+        hash_str(py, self)
+    }
+}
+
+impl<'a, T: PyHash + ?Sized> PyHash for &'a T {
+    fn py_hash(&self, py: Python) -> isize {
+        (**self).py_hash(py)
+    }
+}
+
+macro_rules! tuple_hash {
+    ($($name:ident: $idx:tt),+) => {
+        impl<$($name: PyHash),+> PyHash for ($($name,)+) {
+            fn py_hash(&self, py: Python) -> isize {
+                hash_tuple(&[$(self.$idx.py_hash(py)),+])
+            }
+        }
+    };
+}
+
+tuple_hash!(A: 0);
+tuple_hash!(A: 0, B: 1);
+tuple_hash!(A: 0, B: 1, C: 2);
+tuple_hash!(A: 0, B: 1, C: 2, D: 3);
+tuple_hash!(A: 0, B: 1, C: 2, D: 3, E: 4);
+tuple_hash!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+tuple_hash!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+tuple_hash!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+tuple_hash!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ToPyObject;
+    use crate::{AsPyRef, ObjectProtocol};
+
+    fn python_hash(py: Python, value: impl ToPyObject) -> isize {
+        value.to_object(py).as_ref(py).hash().unwrap()
+    }
+
+    #[test]
+    fn test_int_hash() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for &value in &[0, 1, -1, -2, 42, i64::min_value(), i64::max_value()] {
+            assert_eq!(value.py_hash(py), python_hash(py, value));
+        }
+        assert_eq!(
+            u64::max_value().py_hash(py),
+            python_hash(py, u64::max_value())
+        );
+        assert_eq!(true.py_hash(py), 1);
+        assert_eq!(hash_int(HASH_MODULUS as i128), 0);
+    }
+
+    #[test]
+    fn test_str_hash() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for value in &["", "a", "hash", "caf\u{e9}", "\u{3b1}\u{3b2}", "\u{1f600}!"] {
+            assert_eq!(value.py_hash(py), python_hash(py, *value));
+        }
+    }
+
+    #[test]
+    fn test_hash_info() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let sys_info = py.import("sys").unwrap().get("hash_info").unwrap();
+        let info = hash_info(py);
+        let algorithm: String = sys_info.getattr("algorithm").unwrap().extract().unwrap();
+        let modulus: u64 = sys_info.getattr("modulus").unwrap().extract().unwrap();
+        assert_eq!(info.algorithm, algorithm);
+        assert_eq!(modulus, HASH_MODULUS);
+    }
+
+    #[test]
+    fn test_tuple_hash() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert_eq!((1,).py_hash(py), python_hash(py, (1,)));
+        assert_eq!(("a", -1, "b").py_hash(py), python_hash(py, ("a", -1, "b")));
+        assert_eq!(((1, 2), "c").py_hash(py), python_hash(py, ((1, 2), "c")));
+    }
+}
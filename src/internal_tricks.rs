@@ -1,6 +1,93 @@
+use crate::err::PyResult;
+use crate::ffi;
+use crate::types::PyAny;
+use crate::{AsPyPointer, Python};
 use std::marker::PhantomData;
+use std::os::raw::c_void;
 use std::rc::Rc;
 
+/// Whether pyo3's internal sanity checks (GIL held, pointer validity, pool consistency) run.
+///
+/// They are enabled in debug builds and with the `runtime-checks` feature, and are always
+/// disabled with the `unchecked` feature.
+pub(crate) const RUNTIME_CHECKS: bool = cfg!(all(
+    any(debug_assertions, feature = "runtime-checks"),
+    not(feature = "unchecked")
+));
+
+/// Like `debug_assert!`, but controlled by [RUNTIME_CHECKS].
+macro_rules! pyo3_assert {
+    ($($arg:tt)*) => {
+        if $crate::internal_tricks::RUNTIME_CHECKS {
+            assert!($($arg)*);
+        }
+    };
+}
+
 /// A marker type that makes the type !Send.
 /// Temporal hack until https://github.com/rust-lang/rust/issues/13231 is resolved.
 pub(crate) type Unsendable = PhantomData<Rc<()>>;
+
+/// Evaluates to a `&'static ffi::PyMethodDef` for `$meth`, a function of the type
+/// `ffi::$kind`, e.g. to create functions with [bound_function].
+///
+/// `$name` and `$doc` must be string literals.
+macro_rules! static_method_def {
+    ($name:expr, $kind:ident($meth:expr), $flags:expr, $doc:expr) => {{
+        static DEF: $crate::ffi::PyMethodDef = $crate::ffi::PyMethodDef {
+            ml_name: concat!($name, "\0").as_ptr() as *const _,
+            ml_meth: Some(unsafe {
+                std::mem::transmute::<$crate::ffi::$kind, $crate::ffi::PyCFunction>($meth)
+            }),
+            ml_flags: $flags,
+            ml_doc: concat!($doc, "\0").as_ptr() as *const _,
+        };
+        &DEF
+    }};
+}
+
+/// Creates a Python function which calls `def` with `data` as its `self` argument, the
+/// equivalent of a closure.
+pub(crate) fn bound_function<'p>(
+    py: Python<'p>,
+    def: &'static ffi::PyMethodDef,
+    data: &PyAny,
+) -> PyResult<&'p PyAny> {
+    unsafe {
+        py.from_owned_ptr_or_err(ffi::PyCFunction_New(
+            def as *const _ as *mut _,
+            data.as_ptr(),
+        ))
+    }
+}
+
+/// Moves `value` into a capsule named `name`, which drops it once Python releases the capsule.
+/// `name` must end with a NUL byte.
+pub(crate) fn into_capsule<'p, T: 'static>(
+    py: Python<'p>,
+    name: &'static [u8],
+    value: T,
+) -> PyResult<&'p PyAny> {
+    unsafe {
+        let value = Box::into_raw(Box::new(value));
+        let capsule = ffi::PyCapsule_New(
+            value as *mut c_void,
+            name.as_ptr() as *const _,
+            Some(destroy_capsule::<T>),
+        );
+        if capsule.is_null() {
+            drop(Box::from_raw(value));
+        }
+        py.from_owned_ptr_or_err(capsule)
+    }
+}
+
+unsafe extern "C" fn destroy_capsule<T>(capsule: *mut ffi::PyObject) {
+    let value = ffi::PyCapsule_GetPointer(capsule, ffi::PyCapsule_GetName(capsule));
+    drop(Box::from_raw(value as *mut T));
+}
+
+/// Returns the value of a capsule created by [into_capsule] with the same `name`.
+pub(crate) unsafe fn capsule_value<'a, T>(capsule: *mut ffi::PyObject, name: &[u8]) -> &'a T {
+    &*(ffi::PyCapsule_GetPointer(capsule, name.as_ptr() as *const _) as *const T)
+}
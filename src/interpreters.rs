@@ -0,0 +1,320 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! A pool of subinterpreters for running Python code in isolation.
+//!
+//! Each worker of a [PyInterpreterPool] owns its own subinterpreter, created with
+//! `Py_NewInterpreter`, with separate modules, globals and builtins. This is useful for services
+//! that run independent Python tasks and must not let them interfere with each other. Modules
+//! the tasks need can be imported once per interpreter when the pool is created.
+//!
+//! Python objects can not be shared between interpreters, so arguments and results are passed as
+//! Rust values and converted inside the interpreter that runs the task. Python exceptions are
+//! passed back as [InterpreterError]s carrying the original exception type and message.
+//!
+//! Note that the interpreters still share the GIL, so Python code in different interpreters
+//! does not run in parallel; Rust code that releases the GIL with `allow_threads` does. Within
+//! a task, only use the `Python` token passed to it: `Python::acquire_gil` would return to the
+//! main interpreter. `#[pyclass]` types can not be used in subinterpreters, and extension
+//! modules that do not support subinterpreters may fail to import.
+//!
+//! # Example
+//! ```
+//! use pyo3::interpreters::PyInterpreterPool;
+//! use pyo3::prelude::*;
+//!
+//! let pool = PyInterpreterPool::new(2, &["json"]).unwrap();
+//! let dumped = pool.call::<_, String>("json.dumps", (vec![1, 2],));
+//! assert_eq!(dumped.join().unwrap().unwrap(), "[1, 2]");
+//!
+//! let sum = pool.eval::<i32>("sum(range(5))");
+//! assert_eq!(sum.join().unwrap().unwrap(), 10);
+//! ```
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::RuntimeError;
+use crate::executor::PyJobHandle;
+use crate::ffi;
+use crate::gil::GILPool;
+use crate::instance::{AsPyRef, Py};
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::python::{prepare_freethreaded_python, Python};
+use crate::types::PyTuple;
+use crate::{FromPyObject, IntoPy};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn for<'py> FnOnce(Python<'py>) + Send>;
+
+/// A pool of threads that each run their own subinterpreter. See the [module docs](self).
+///
+/// Dropping the pool waits for all submitted jobs to finish and destroys the interpreters.
+pub struct PyInterpreterPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl PyInterpreterPool {
+    /// Creates a pool of `interpreters` subinterpreters, each of which imports the modules in
+    /// `preload` before running any jobs.
+    ///
+    /// Returns an error if a subinterpreter can't be created or one of the modules can't be
+    /// imported.
+    ///
+    /// # Panics
+    /// Panics if `interpreters` is zero.
+    pub fn new(
+        interpreters: usize,
+        preload: &[&str],
+    ) -> Result<PyInterpreterPool, InterpreterError> {
+        assert!(
+            interpreters > 0,
+            "PyInterpreterPool needs at least one interpreter"
+        );
+        prepare_freethreaded_python();
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (ready_sender, ready) = mpsc::channel();
+        let workers = (0..interpreters)
+            .map(|i| {
+                let receiver = receiver.clone();
+                let ready = ready_sender.clone();
+                let preload: Vec<String> = preload.iter().map(|m| m.to_string()).collect();
+                thread::Builder::new()
+                    .name(format!("pyo3-interpreter-{}", i))
+                    .spawn(move || worker_loop(&receiver, &preload, &ready))
+                    .expect("Failed to spawn PyInterpreterPool worker thread")
+            })
+            .collect();
+
+        let pool = PyInterpreterPool {
+            sender: Some(sender),
+            workers,
+        };
+        for _ in 0..interpreters {
+            ready
+                .recv()
+                .expect("PyInterpreterPool worker disappeared during startup")?;
+        }
+        Ok(pool)
+    }
+
+    /// Returns the number of interpreters.
+    pub fn interpreters(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Runs `f` in one of the interpreters, returning a handle to wait for its result.
+    pub fn submit<F, R>(&self, f: F) -> PyJobHandle<R>
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let job: Job = Box::new(move |py| {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(py)));
+            let _ = sender.send(result);
+        });
+        self.sender
+            .as_ref()
+            .unwrap()
+            .send(job)
+            .expect("PyInterpreterPool worker threads have stopped");
+        PyJobHandle { receiver }
+    }
+
+    /// Calls `function`, given as `"module.name"`, with `args` in one of the interpreters and
+    /// extracts the result as `R`.
+    pub fn call<A, R>(&self, function: &str, args: A) -> PyJobHandle<Result<R, InterpreterError>>
+    where
+        A: IntoPy<Py<PyTuple>> + Send + 'static,
+        R: for<'a> FromPyObject<'a> + Send + 'static,
+    {
+        let function = function.to_string();
+        self.submit(move |py| {
+            let (module, name) = match function.rfind('.') {
+                Some(dot) => (&function[..dot], &function[dot + 1..]),
+                None => ("builtins", &function[..]),
+            };
+            py.import(module)
+                .and_then(|module| module.call1(name, args))
+                .and_then(|result| result.extract())
+                .map_err(|err| InterpreterError::new(py, err))
+        })
+    }
+
+    /// Evaluates the Python expression `code` in one of the interpreters and extracts the
+    /// result as `R`.
+    pub fn eval<R>(&self, code: &str) -> PyJobHandle<Result<R, InterpreterError>>
+    where
+        R: for<'a> FromPyObject<'a> + Send + 'static,
+    {
+        let code = code.to_string();
+        self.submit(move |py| {
+            py.eval(&code, None, None)
+                .and_then(|result| result.extract())
+                .map_err(|err| InterpreterError::new(py, err))
+        })
+    }
+}
+
+impl Drop for PyInterpreterPool {
+    fn drop(&mut self) {
+        // Closing the channel makes the workers exit once the queue is empty
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// An exception raised in a subinterpreter, detached from the objects of that interpreter.
+///
+/// Converts into a `RuntimeError` with the message `"{type_name}: {message}"`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterpreterError {
+    /// The name of the exception type, e.g. `"ValueError"`.
+    pub type_name: String,
+    /// The string representation of the exception.
+    pub message: String,
+}
+
+impl InterpreterError {
+    fn new(py: Python, err: PyErr) -> InterpreterError {
+        let instance: PyObject = err.into_py(py);
+        let instance = instance.as_ref(py);
+        InterpreterError {
+            type_name: instance.get_type().name().into_owned(),
+            message: match instance.str() {
+                Ok(message) => message.to_string_lossy().into_owned(),
+                Err(_) => String::new(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.type_name, self.message)
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+impl std::convert::From<InterpreterError> for PyErr {
+    fn from(err: InterpreterError) -> PyErr {
+        RuntimeError::py_err(err.to_string())
+    }
+}
+
+fn worker_loop(
+    receiver: &Mutex<mpsc::Receiver<Job>>,
+    preload: &[String],
+    ready: &mpsc::Sender<Result<(), InterpreterError>>,
+) {
+    // Create the subinterpreter from a thread state of the main interpreter, which is restored
+    // to tear everything down again
+    let gstate = unsafe { ffi::PyGILState_Ensure() };
+    let main_tstate = unsafe { ffi::PyThreadState_Get() };
+    let tstate = unsafe { ffi::Py_NewInterpreter() };
+
+    let loaded = if tstate.is_null() {
+        Err(InterpreterError {
+            type_name: "RuntimeError".to_string(),
+            message: "Failed to create a subinterpreter".to_string(),
+        })
+    } else {
+        let py = unsafe { Python::assume_gil_acquired() };
+        let _pool = GILPool::new(py);
+        preload
+            .iter()
+            .map(|module| py.import(module).map(|_| ()))
+            .collect::<PyResult<()>>()
+            .map_err(|err| InterpreterError::new(py, err))
+    };
+    let failed = loaded.is_err();
+    let _ = ready.send(loaded);
+
+    if !failed {
+        unsafe { ffi::PyEval_SaveThread() };
+        loop {
+            // Wait for a job without holding the GIL
+            let job = match receiver.lock().unwrap().recv() {
+                Ok(job) => job,
+                Err(_) => break,
+            };
+
+            unsafe { ffi::PyEval_RestoreThread(tstate) };
+            let py = unsafe { Python::assume_gil_acquired() };
+            {
+                let _pool = GILPool::new(py);
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| job(py)));
+            }
+            unsafe { ffi::PyEval_SaveThread() };
+        }
+        unsafe { ffi::PyEval_RestoreThread(tstate) };
+    }
+
+    unsafe {
+        if !tstate.is_null() {
+            ffi::Py_EndInterpreter(tstate);
+        }
+        ffi::PyThreadState_Swap(main_tstate);
+        ffi::PyGILState_Release(gstate);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyInterpreterPool;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::PyDict;
+    use crate::Python;
+
+    #[test]
+    fn test_isolation() {
+        let pool = PyInterpreterPool::new(2, &["colorsys"]).unwrap();
+        assert_eq!(pool.interpreters(), 2);
+
+        let handle = pool.submit(|py| {
+            py.run("import builtins\nbuiltins.pool_marker = 1", None, None)
+                .unwrap();
+            let modules: &PyDict = py
+                .import("sys")
+                .unwrap()
+                .get("modules")
+                .unwrap()
+                .downcast_ref()
+                .unwrap();
+            modules.get_item("colorsys").is_some()
+        });
+        assert!(handle.join().unwrap());
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert!(!py
+            .import("builtins")
+            .unwrap()
+            .hasattr("pool_marker")
+            .unwrap());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = PyInterpreterPool::new(1, &["no_such_module"])
+            .err()
+            .unwrap();
+        assert_eq!(err.type_name, "ModuleNotFoundError");
+
+        let pool = PyInterpreterPool::new(1, &[]).unwrap();
+        let err = pool.eval::<i32>("1 / 0").join().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "ZeroDivisionError: division by zero");
+        assert_eq!(
+            pool.call::<_, usize>("len", ("abc",)).join().unwrap(),
+            Ok(3)
+        );
+    }
+}
@@ -0,0 +1,552 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Bridges between Rust IO traits and Python file objects.
+//!
+//! [PyFileLike] implements `std::io::Read`, `Write` and `Seek` on top of a Python file-like
+//! object, so that Rust code can read from and write to whatever stream a Python caller passes
+//! in. Every operation acquires the GIL and calls the corresponding Python method, so small
+//! reads and writes should be buffered with `std::io::BufReader` and `BufWriter`.
+//!
+//! In the other direction, [reader_into_py] and [writer_into_py] wrap a Rust `Read` or `Write`
+//! in a Python object derived from `io.RawIOBase`. Wrap it in `io.BufferedReader` or
+//! `io.TextIOWrapper` on the Python side for buffering and text decoding.
+//!
+//! Functions that take an IO argument can accept a [FileOrPath], which is either a path to open
+//! in Rust or a file object wrapped in a [PyFileLike].
+//!
+//! # Example
+//! ```
+//! use pyo3::io::{reader_into_py, PyFileLike};
+//! use pyo3::prelude::*;
+//! use std::io::{BufRead, BufReader, Write};
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//!
+//! // Rust writing to a Python file object
+//! let buffer = py.import("io").unwrap().call0("BytesIO").unwrap();
+//! let mut file = PyFileLike::new(buffer.into());
+//! file.write_all(b"first\nsecond\n").unwrap();
+//! let value: Vec<u8> = buffer.call_method0("getvalue").unwrap().extract().unwrap();
+//! assert_eq!(value, b"first\nsecond\n");
+//!
+//! // Python reading from a Rust reader
+//! let reader = reader_into_py(py, &b"from rust\n"[..]).unwrap();
+//! let line: Vec<u8> = reader.call_method0("readline").unwrap().extract().unwrap();
+//! assert_eq!(line, b"from rust\n");
+//!
+//! // Rust reading lines from a Python text file
+//! let text = py.import("io").unwrap().call1("StringIO", ("a\nb\n",)).unwrap();
+//! let lines: Vec<String> = BufReader::new(PyFileLike::new(text.into()))
+//!     .lines()
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! assert_eq!(lines, vec!["a", "b"]);
+//! ```
+
+use crate::callback::{cb_convert, PyObjectCallbackConverter};
+use crate::err::PyResult;
+use crate::exceptions::OSError;
+use crate::ffi;
+use crate::instance::AsPyRef;
+use crate::internal_tricks::{bound_function, capsule_value, into_capsule};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyBytes, PyDict, PySlice, PyString, PyTuple};
+use crate::{AsPyPointer, FromPyObject, PyObject, Python, ToPyObject};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A Python file-like object used through Rust's IO traits.
+///
+/// Binary files are read with `readinto` when available, or `read` otherwise. Text files
+/// (`io.TextIOBase` instances) are read and written as UTF-8. Seeking flushes the file first.
+pub struct PyFileLike {
+    inner: PyObject,
+    is_text: bool,
+    // Encoded text read from a text file that did not fit into the caller's buffer
+    read_pending: Vec<u8>,
+    // Trailing bytes of an incomplete UTF-8 sequence written to a text file
+    write_pending: Vec<u8>,
+}
+
+impl PyFileLike {
+    /// Wraps the Python file-like object `file`.
+    pub fn new(file: PyObject) -> PyFileLike {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let is_text = py
+            .import("io")
+            .and_then(|io| io.get("TextIOBase"))
+            .map(|text_io| unsafe {
+                ffi::PyObject_IsInstance(file.as_ptr(), text_io.as_ptr()) == 1
+            })
+            .unwrap_or(false);
+        PyFileLike {
+            inner: file,
+            is_text,
+            read_pending: Vec::new(),
+            write_pending: Vec::new(),
+        }
+    }
+
+    /// Returns the wrapped Python object.
+    pub fn into_inner(self) -> PyObject {
+        self.inner
+    }
+
+    fn read_text(&mut self, py: Python, buf: &mut [u8]) -> PyResult<usize> {
+        if self.read_pending.is_empty() {
+            let text = self.inner.call_method1(py, "read", (buf.len(),))?;
+            let text: &PyString = text.cast_as(py)?;
+            self.read_pending
+                .extend_from_slice(text.to_string()?.as_bytes());
+        }
+        let n = buf.len().min(self.read_pending.len());
+        buf[..n].copy_from_slice(&self.read_pending[..n]);
+        self.read_pending.drain(..n);
+        Ok(n)
+    }
+
+    fn read_binary(&mut self, py: Python, buf: &mut [u8]) -> PyResult<usize> {
+        let file = self.inner.as_ref(py);
+        if file.hasattr("readinto")? {
+            let array = py.import("builtins")?.call1("bytearray", (buf.len(),))?;
+            let n = file.call_method1("readinto", (array,))?;
+            // `None` means that no data is available on a non-blocking stream
+            let n: usize = if n.is_none() { 0 } else { n.extract()? };
+            let data: Vec<u8> = array.extract()?;
+            check_read_len("readinto", n, buf.len())?;
+            buf[..n].copy_from_slice(&data[..n]);
+            Ok(n)
+        } else {
+            let data = file.call_method1("read", (buf.len(),))?;
+            let data: &PyBytes = data.downcast_ref()?;
+            let data = data.as_bytes();
+            check_read_len("read", data.len(), buf.len())?;
+            buf[..data.len()].copy_from_slice(data);
+            Ok(data.len())
+        }
+    }
+
+    fn write_text(&mut self, py: Python, buf: &[u8]) -> io::Result<usize> {
+        self.write_pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.write_pending) {
+            Ok(text) => text.len(),
+            // An incomplete sequence at the end is kept until the next write
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => {
+                self.write_pending
+                    .truncate(self.write_pending.len() - buf.len());
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+        };
+        let text = std::str::from_utf8(&self.write_pending[..valid]).unwrap();
+        self.inner.call_method1(py, "write", (text,))?;
+        self.write_pending.drain(..valid);
+        Ok(buf.len())
+    }
+}
+
+/// Raises `OSError` if a file returned more data than requested, like `io.BufferedReader`.
+fn check_read_len(method: &str, len: usize, requested: usize) -> PyResult<()> {
+    if len > requested {
+        return Err(OSError::py_err(format!(
+            "{}() returned invalid length {} (should have been between 0 and {})",
+            method, len, requested
+        )));
+    }
+    Ok(())
+}
+
+impl Read for PyFileLike {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let n = if self.is_text {
+            self.read_text(py, buf)?
+        } else {
+            self.read_binary(py, buf)?
+        };
+        Ok(n)
+    }
+}
+
+impl Write for PyFileLike {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if self.is_text {
+            return self.write_text(py, buf);
+        }
+        let n = self
+            .inner
+            .call_method1(py, "write", (PyBytes::new(py, buf),))?;
+        // Raw streams return `None` if the write would block
+        Ok(if n.is_none() { 0 } else { n.extract(py)? })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.write_pending.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Incomplete UTF-8 sequence written to a text file",
+            ));
+        }
+        let gil = Python::acquire_gil();
+        self.inner.call_method0(gil.python(), "flush")?;
+        Ok(())
+    }
+}
+
+impl Seek for PyFileLike {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        // Like Python files, write everything before moving, which fails for incomplete UTF-8
+        self.flush()?;
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+        self.read_pending.clear();
+        let pos = self.inner.call_method1(py, "seek", (offset, whence))?;
+        Ok(pos.extract(py)?)
+    }
+}
+
+/// An IO argument that is either a path or a file object, following the convention of Python
+/// functions like `json.dump` or `PIL.Image.open`.
+///
+/// Strings, bytes and `os.PathLike` objects extract as a [FileOrPath::Path], which is opened in
+/// Rust; any object with a `read` or `write` method extracts as a [FileOrPath::File].
+pub enum FileOrPath {
+    /// A filesystem path.
+    Path(PathBuf),
+    /// A Python file object.
+    File(PyFileLike),
+}
+
+impl FileOrPath {
+    /// Returns the path, if the argument was a path.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            FileOrPath::Path(path) => Some(path),
+            FileOrPath::File(_) => None,
+        }
+    }
+
+    /// Opens the path for reading or returns the file object.
+    pub fn reader(self) -> io::Result<Box<dyn Read>> {
+        Ok(match self {
+            FileOrPath::Path(path) => Box::new(File::open(path)?),
+            FileOrPath::File(file) => Box::new(file),
+        })
+    }
+
+    /// Creates or truncates the file at the path for writing, or returns the file object.
+    pub fn writer(self) -> io::Result<Box<dyn Write>> {
+        Ok(match self {
+            FileOrPath::Path(path) => Box::new(File::create(path)?),
+            FileOrPath::File(file) => Box::new(file),
+        })
+    }
+}
+
+impl<'source> FromPyObject<'source> for FileOrPath {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if ob.hasattr("read")? || ob.hasattr("write")? {
+            return Ok(FileOrPath::File(PyFileLike::new(ob.into())));
+        }
+        Ok(FileOrPath::Path(ob.extract()?))
+    }
+}
+
+const CAPSULE_NAME: &[u8] = b"pyo3.io\0";
+
+/// The Rust stream behind a Python object created by `reader_into_py` or `writer_into_py`.
+enum RustStream {
+    Reader(Mutex<Box<dyn Read + Send>>),
+    Writer(Mutex<Box<dyn Write + Send>>),
+}
+
+/// Wraps `reader` in an `io.RawIOBase` Python object, which reads from it with the GIL
+/// released.
+pub fn reader_into_py<R>(py: Python, reader: R) -> PyResult<&PyAny>
+where
+    R: Read + Send + 'static,
+{
+    let readinto = static_method_def!("readinto", PyCFunction(readinto), ffi::METH_O, "");
+    let readable = static_method_def!(
+        "readable",
+        PyNoArgsFunction(returns_true),
+        ffi::METH_NOARGS,
+        ""
+    );
+    stream_into_py(
+        py,
+        "RustReader",
+        RustStream::Reader(Mutex::new(Box::new(reader))),
+        &[("readinto", readinto), ("readable", readable)],
+    )
+}
+
+/// Wraps `writer` in an `io.RawIOBase` Python object, which writes to it with the GIL
+/// released. The writer is dropped when the Python object is garbage collected.
+pub fn writer_into_py<W>(py: Python, writer: W) -> PyResult<&PyAny>
+where
+    W: Write + Send + 'static,
+{
+    let write = static_method_def!("write", PyCFunction(write), ffi::METH_O, "");
+    let flush = static_method_def!("flush", PyNoArgsFunction(flush), ffi::METH_NOARGS, "");
+    let writable = static_method_def!(
+        "writable",
+        PyNoArgsFunction(returns_true),
+        ffi::METH_NOARGS,
+        ""
+    );
+    stream_into_py(
+        py,
+        "RustWriter",
+        RustStream::Writer(Mutex::new(Box::new(writer))),
+        &[("write", write), ("flush", flush), ("writable", writable)],
+    )
+}
+
+/// `readable()` or `writable()`.
+unsafe extern "C" fn returns_true(_: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    ffi::Py_INCREF(ffi::Py_True());
+    ffi::Py_True()
+}
+
+/// Creates an instance of a new `io.RawIOBase` subclass with the given methods, which are bound
+/// to a capsule owning `stream`.
+fn stream_into_py<'p>(
+    py: Python<'p>,
+    class_name: &str,
+    stream: RustStream,
+    methods: &[(&str, &'static ffi::PyMethodDef)],
+) -> PyResult<&'p PyAny> {
+    let capsule = into_capsule(py, CAPSULE_NAME, stream)?;
+    let builtins = py.import("builtins")?;
+    let namespace = PyDict::new(py);
+    for &(name, def) in methods {
+        let function = bound_function(py, def, capsule)?;
+        namespace.set_item(name, builtins.call1("staticmethod", (function,))?)?;
+    }
+    let bases = PyTuple::new(py, &[py.import("io")?.get("RawIOBase")?]);
+    let class = builtins.call1("type", (class_name, bases, namespace))?;
+    class.call0()
+}
+
+unsafe fn stream<'a>(capsule: *mut ffi::PyObject) -> &'a RustStream {
+    capsule_value(capsule, CAPSULE_NAME)
+}
+
+unsafe extern "C" fn readinto(
+    capsule: *mut ffi::PyObject,
+    buffer: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let result = (|| -> PyResult<PyObject> {
+        let reader = match stream(capsule) {
+            RustStream::Reader(reader) => reader,
+            RustStream::Writer(_) => unreachable!(),
+        };
+        let view = py
+            .import("builtins")?
+            .call1("memoryview", (py.from_borrowed_ptr::<PyAny>(buffer),))?
+            .call_method1("cast", ("B",))?;
+        let mut data = vec![0; view.len()?];
+        let n = py.allow_threads(|| reader.lock().unwrap().read(&mut data))?;
+        view.set_item(
+            PySlice::new(py, 0, n as isize, 1),
+            PyBytes::new(py, &data[..n]),
+        )?;
+        Ok(n.to_object(py))
+    })();
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+unsafe extern "C" fn write(
+    capsule: *mut ffi::PyObject,
+    buffer: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let result = (|| -> PyResult<PyObject> {
+        let writer = match stream(capsule) {
+            RustStream::Writer(writer) => writer,
+            RustStream::Reader(_) => unreachable!(),
+        };
+        let data = py
+            .import("builtins")?
+            .call1("bytes", (py.from_borrowed_ptr::<PyAny>(buffer),))?;
+        let data: &[u8] = data.downcast_ref::<PyBytes>()?.as_bytes();
+        let n = py.allow_threads(|| writer.lock().unwrap().write(data))?;
+        Ok(n.to_object(py))
+    })();
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+unsafe extern "C" fn flush(capsule: *mut ffi::PyObject) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let result = match stream(capsule) {
+        RustStream::Writer(writer) => py
+            .allow_threads(|| writer.lock().unwrap().flush())
+            .map(|()| py.None())
+            .map_err(Into::into),
+        RustStream::Reader(_) => Ok(py.None()),
+    };
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{reader_into_py, writer_into_py, FileOrPath, PyFileLike};
+    use crate::exceptions::TypeError;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::PyDict;
+    use crate::{Python, ToPyObject};
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_python_file_from_rust() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let buffer = py.import("io").unwrap().call0("BytesIO").unwrap();
+        let mut file = PyFileLike::new(buffer.into());
+        file.write_all(b"hello world").unwrap();
+        file.flush().unwrap();
+        assert_eq!(file.seek(SeekFrom::Start(6)).unwrap(), 6);
+        let mut rest = String::new();
+        file.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "world");
+
+        // Text files are encoded as UTF-8, even if a write splits a character
+        let text = py.import("io").unwrap().call0("StringIO").unwrap();
+        let mut file = PyFileLike::new(text.into());
+        let bytes = "größe".as_bytes();
+        file.write_all(&bytes[..3]).unwrap();
+        file.write_all(&bytes[3..]).unwrap();
+        file.flush().unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = [0; 2];
+        let mut read = Vec::new();
+        loop {
+            match file.read(&mut buf).unwrap() {
+                0 => break,
+                n => read.extend_from_slice(&buf[..n]),
+            }
+        }
+        assert_eq!(read, bytes);
+    }
+
+    #[test]
+    fn test_python_file_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+class ReadintoTooMuch:
+    def readinto(self, b):
+        return len(b) + 1
+
+class ReadTooMuch:
+    def read(self, n):
+        return b" " * (n + 1)
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        for class in &["ReadintoTooMuch", "ReadTooMuch"] {
+            let file = locals.get_item(class).unwrap().call0().unwrap();
+            let mut buf = [0; 4];
+            assert!(PyFileLike::new(file.into()).read(&mut buf).is_err());
+        }
+
+        // Seeking fails instead of dropping an incomplete UTF-8 sequence
+        let text = py.import("io").unwrap().call0("StringIO").unwrap();
+        let mut file = PyFileLike::new(text.into());
+        file.write_all(&"ö".as_bytes()[..1]).unwrap();
+        assert!(file.seek(SeekFrom::Start(0)).is_err());
+    }
+
+    #[test]
+    fn test_rust_stream_from_python() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        let reader = reader_into_py(py, &b"line 1\nline 2\n"[..]).unwrap();
+        locals.set_item("reader", reader).unwrap();
+
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let output = Shared::default();
+        let writer = writer_into_py(py, output.clone()).unwrap();
+        locals.set_item("writer", writer).unwrap();
+
+        py.run(
+            r#"
+import io
+assert reader.readable() and not reader.writable()
+lines = list(io.TextIOWrapper(io.BufferedReader(reader)))
+assert lines == ["line 1\n", "line 2\n"], lines
+with io.TextIOWrapper(io.BufferedWriter(writer)) as w:
+    w.write("".join(lines).upper())
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        assert_eq!(&output.0.lock().unwrap()[..], b"LINE 1\nLINE 2\n");
+    }
+
+    #[test]
+    fn test_file_or_path() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let path = std::env::temp_dir().join("pyo3_test_file_or_path.txt");
+        let path_str = path.to_str().unwrap();
+        let pathlib = py.import("pathlib").unwrap();
+
+        let arg: FileOrPath = path_str.to_object(py).extract(py).unwrap();
+        assert_eq!(arg.path(), Some(path.as_path()));
+        arg.writer().unwrap().write_all(b"contents").unwrap();
+
+        let arg: FileOrPath = pathlib
+            .call1("Path", (path_str,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        let mut contents = String::new();
+        arg.reader().unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "contents");
+        std::fs::remove_file(&path).unwrap();
+
+        let buffer = py.import("io").unwrap().call0("BytesIO").unwrap();
+        let arg: FileOrPath = buffer.extract().unwrap();
+        assert!(arg.path().is_none());
+        arg.writer().unwrap().write_all(b"data").unwrap();
+        let value: Vec<u8> = buffer.call_method0("getvalue").unwrap().extract().unwrap();
+        assert_eq!(value, b"data");
+
+        let err = 1.to_object(py).extract::<FileOrPath>(py).err().unwrap();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+}
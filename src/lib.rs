@@ -103,17 +103,17 @@
 //! use pyo3::types::IntoPyDict;
 //!
 //! fn main() -> PyResult<()> {
-//!     let gil = Python::acquire_gil();
-//!     let py = gil.python();
-//!     let sys = py.import("sys")?;
-//!     let version: String = sys.get("version")?.extract()?;
+//!     Python::with_gil(|py| {
+//!         let sys = py.import("sys")?;
+//!         let version: String = sys.get("version")?.extract()?;
 //!
-//!     let locals = [("os", py.import("os")?)].into_py_dict(py);
-//!     let code = "os.getenv('USER') or os.getenv('USERNAME') or 'Unknown'";
-//!     let user: String = py.eval(code, None, Some(&locals))?.extract()?;
+//!         let locals = [("os", py.import("os")?)].into_py_dict(py);
+//!         let code = "os.getenv('USER') or os.getenv('USERNAME') or 'Unknown'";
+//!         let user: String = py.eval(code, None, Some(&locals))?.extract()?;
 //!
-//!     println!("Hello {}, I'm Python {}", user, version);
-//!     Ok(())
+//!         println!("Hello {}, I'm Python {}", user, version);
+//!         Ok(())
+//!     })
 //! }
 //! ```
 
@@ -123,7 +123,7 @@ pub use crate::conversion::{
     ToBorrowedObject, ToPyObject,
 };
 pub use crate::err::{PyDowncastError, PyErr, PyErrArguments, PyErrValue, PyResult};
-pub use crate::gil::{init_once, GILGuard, GILPool};
+pub use crate::gil::{init_once, GILGuard, GILMetrics, GILPool};
 pub use crate::instance::{AsPyRef, ManagedPyRef, Py, PyNativeType, PyRef, PyRefMut};
 pub use crate::object::PyObject;
 pub use crate::objectprotocol::ObjectProtocol;
@@ -146,35 +146,80 @@ pub use libc;
 #[doc(hidden)]
 pub use unindent;
 
+#[macro_use]
+mod internal_tricks;
+
+// Lets the code generated by `#[pyclass]` and the other macros refer to `pyo3` inside this crate
+extern crate self as pyo3;
+
 /// Raw ffi declarations for the c interface of python
 #[allow(clippy::unknown_clippy_lints)]
 #[allow(clippy::missing_safety_doc)]
 pub mod ffi;
 
+pub mod asyncio;
 pub mod buffer;
+pub mod cache;
 #[doc(hidden)]
 pub mod callback;
+pub mod channel;
 pub mod class;
 mod conversion;
 #[doc(hidden)]
 pub mod derive_utils;
+#[cfg(all(unix, feature = "dynamic-python"))]
+pub mod dynamic;
+pub mod entry_points;
+pub mod enums;
 mod err;
 pub mod exceptions;
+pub mod executor;
+pub mod forward;
 pub mod freelist;
 mod gil;
+pub mod graph;
+pub mod handle;
+pub mod hash;
 mod instance;
-mod internal_tricks;
+#[cfg(not(any(PyPy, Py_LIMITED_API)))]
+pub mod interpreters;
+pub mod io;
 pub mod marshal;
 mod object;
 mod objectprotocol;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod pickle;
+#[cfg(feature = "bytemuck")]
+pub mod pod;
 pub mod prelude;
+#[cfg(feature = "pydantic")]
+pub mod pydantic;
 mod python;
+#[cfg(feature = "serde")]
+pub mod pythonize;
+pub mod sandbox;
+pub mod signal;
+pub mod signature;
+pub mod singleton;
+#[cfg(any(unix, windows))]
+pub mod socket;
+pub mod struct_format;
+#[cfg(feature = "stubs")]
+pub mod stubs;
+pub mod sync;
+pub mod thread_local;
+pub mod tracemalloc;
 pub mod type_object;
 pub mod types;
+#[cfg(all(windows, feature = "delay-load"))]
+pub mod windows;
 
 /// The proc macros, which are also part of the prelude
 pub mod proc_macro {
     pub use pyo3cls::pymodule;
+    /// The derive macros
+    pub use pyo3cls::FromPyObject;
     /// The proc macro attributes
     pub use pyo3cls::{pyclass, pyfunction, pymethods, pyproto};
 }
@@ -196,7 +241,9 @@ macro_rules! wrap_pyfunction {
 macro_rules! wrap_pymodule {
     ($module_name:ident) => {{
         pyo3::paste::expr! {
-            &|py| unsafe { pyo3::PyObject::from_owned_ptr(py, [<PyInit_ $module_name>]()) }
+            &|py| unsafe {
+                pyo3::derive_utils::module_from_init(py, [<PyInit_ $module_name>]())
+            }
         }
     }};
 }
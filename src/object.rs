@@ -42,9 +42,11 @@ impl PyObject {
     /// Undefined behavior if the pointer is NULL or invalid.
     #[inline]
     pub unsafe fn from_owned_ptr(_py: Python, ptr: *mut ffi::PyObject) -> PyObject {
-        debug_assert!(
+        pyo3_assert!(
             !ptr.is_null() && ffi::Py_REFCNT(ptr) > 0,
-            format!("REFCNT: {:?} - {:?}", ptr, ffi::Py_REFCNT(ptr))
+            "REFCNT: {:?} - {:?}",
+            ptr,
+            ffi::Py_REFCNT(ptr)
         );
         PyObject(NonNull::new_unchecked(ptr))
     }
@@ -87,9 +89,11 @@ impl PyObject {
     /// Undefined behavior if the pointer is NULL or invalid.
     #[inline]
     pub unsafe fn from_borrowed_ptr(_py: Python, ptr: *mut ffi::PyObject) -> PyObject {
-        debug_assert!(
+        pyo3_assert!(
             !ptr.is_null() && ffi::Py_REFCNT(ptr) > 0,
-            format!("REFCNT: {:?} - {:?}", ptr, ffi::Py_REFCNT(ptr))
+            "REFCNT: {:?} - {:?}",
+            ptr,
+            ffi::Py_REFCNT(ptr)
         );
         ffi::Py_INCREF(ptr);
         PyObject(NonNull::new_unchecked(ptr))
@@ -201,7 +205,7 @@ impl PyObject {
     /// Calls the object without arguments.
     /// This is equivalent to the Python expression: 'self()'
     pub fn call0(&self, py: Python) -> PyResult<PyObject> {
-        self.call(py, (), None)
+        unsafe { PyObject::from_owned_ptr_or_err(py, ffi::PyObject_CallNoArgs(self.as_ptr())) }
     }
 
     /// Calls the object.
@@ -237,7 +241,9 @@ impl PyObject {
     /// Calls a method on the object.
     /// This is equivalent to the Python expression: 'self.name()'
     pub fn call_method0(&self, py: Python, name: &str) -> PyResult<PyObject> {
-        self.call_method(py, name, (), None)
+        name.with_borrowed_ptr(py, |name| unsafe {
+            PyObject::from_owned_ptr_or_err(py, ffi::PyObject_CallMethodNoArgs(self.as_ptr(), name))
+        })
     }
 
     /// Calls a method on the object.
@@ -7,7 +7,7 @@ use crate::ffi;
 use crate::instance::PyNativeType;
 use crate::object::PyObject;
 use crate::type_object::PyTypeInfo;
-use crate::types::{PyAny, PyDict, PyIterator, PyString, PyTuple, PyType};
+use crate::types::{PyAny, PyDict, PyExtractIterator, PyIterator, PyString, PyTuple, PyType};
 use crate::AsPyPointer;
 use crate::IntoPyPointer;
 use crate::Py;
@@ -84,6 +84,13 @@ pub trait ObjectProtocol {
     /// Determines whether this object is callable.
     fn is_callable(&self) -> bool;
 
+    /// Returns whether `iter(self)` can succeed, because the object implements `__iter__` or
+    /// the sequence protocol.
+    ///
+    /// Like `callable(self)`, this only checks the type of the object, so `__iter__` can still
+    /// raise an exception.
+    fn is_iterable(&self) -> bool;
+
     /// Calls the object.
     /// This is equivalent to the Python expression: `self(*args, **kwargs)`.
     fn call(&self, args: impl IntoPy<Py<PyTuple>>, kwargs: Option<&PyDict>) -> PyResult<&PyAny>;
@@ -169,6 +176,14 @@ pub trait ObjectProtocol {
     /// is an iterator, this returns itself.
     fn iter(&self) -> PyResult<PyIterator>;
 
+    /// Takes an iterable and returns an iterator extracting its items as `T`.
+    ///
+    /// Items are pulled from the Python iterator only as the returned iterator is consumed,
+    /// so this works for generators too large to materialize.
+    fn iter_extract<V>(&self) -> PyResult<PyExtractIterator<V>>
+    where
+        V: for<'a> FromPyObject<'a>;
+
     /// Gets the Python type object for this object's type.
     fn get_type(&self) -> &PyType;
 
@@ -323,6 +338,13 @@ where
         unsafe { ffi::PyCallable_Check(self.as_ptr()) != 0 }
     }
 
+    fn is_iterable(&self) -> bool {
+        unsafe {
+            (*ffi::Py_TYPE(self.as_ptr())).tp_iter.is_some()
+                || ffi::PySequence_Check(self.as_ptr()) != 0
+        }
+    }
+
     fn call(&self, args: impl IntoPy<Py<PyTuple>>, kwargs: Option<&PyDict>) -> PyResult<&PyAny> {
         let args = args.into_py(self.py()).into_ptr();
         let kwargs = kwargs.into_ptr();
@@ -338,7 +360,10 @@ where
     }
 
     fn call0(&self) -> PyResult<&PyAny> {
-        self.call((), None)
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyObject_CallNoArgs(self.as_ptr()))
+        }
     }
 
     fn call1(&self, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyAny> {
@@ -369,7 +394,10 @@ where
     }
 
     fn call_method0(&self, name: &str) -> PyResult<&PyAny> {
-        self.call_method(name, (), None)
+        name.with_borrowed_ptr(self.py(), |name| unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyObject_CallMethodNoArgs(self.as_ptr(), name))
+        })
     }
 
     fn call_method1(&self, name: &str, args: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyAny> {
@@ -446,6 +474,13 @@ where
         Ok(PyIterator::from_object(self.py(), self)?)
     }
 
+    fn iter_extract<V>(&self) -> PyResult<PyExtractIterator<V>>
+    where
+        V: for<'a> FromPyObject<'a>,
+    {
+        Ok(PyExtractIterator::from_object(self.py(), self)?)
+    }
+
     fn get_type(&self) -> &PyType {
         unsafe { PyType::from_type_ptr(self.py(), (*self.as_ptr()).ob_type) }
     }
@@ -499,7 +534,7 @@ where
 mod test {
     use super::*;
     use crate::instance::AsPyRef;
-    use crate::types::{IntoPyDict, PyString};
+    use crate::types::{IntoPyDict, PyLong, PyString, PyTuple};
     use crate::Python;
     use crate::{PyTryFrom, ToPyObject};
 
@@ -532,6 +567,65 @@ mod test {
         assert!(a.call_method1("nonexistent_method", (1,)).is_err());
     }
 
+    #[test]
+    fn test_call_helpers() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = vec![1, 2].to_object(py);
+        let append = PyString::intern(py, "append");
+        let func = py
+            .eval("lambda a, b=0, *, c=0: (a, b, c)", None, None)
+            .unwrap();
+        unsafe {
+            let len = py.from_owned_ptr::<PyAny>(ffi::PyObject_CallMethodNoArgs(
+                list.as_ptr(),
+                PyString::intern(py, "__len__").as_ptr(),
+            ));
+            assert_eq!(len.extract::<usize>().unwrap(), 2);
+
+            let three = 3.to_object(py);
+            ffi::Py_DECREF(ffi::PyObject_CallMethodOneArg(
+                list.as_ptr(),
+                append.as_ptr(),
+                three.as_ptr(),
+            ));
+            assert_eq!(list.extract::<Vec<i32>>(py).unwrap(), [1, 2, 3]);
+
+            let result =
+                py.from_owned_ptr::<PyAny>(ffi::PyObject_CallOneArg(func.as_ptr(), three.as_ptr()));
+            assert_eq!(result.extract::<(i32, i32, i32)>().unwrap(), (3, 0, 0));
+
+            // Python functions support vectorcall on 3.8+, `int` goes through `tp_call`
+            let args = [1.to_object(py), 2.to_object(py), 4.to_object(py)];
+            let args: Vec<_> = args.iter().map(|arg| arg.as_ptr()).collect();
+            let kwnames = PyTuple::new(py, &["c"]);
+            let result = py.from_owned_ptr::<PyAny>(ffi::PyObject_Vectorcall(
+                func.as_ptr(),
+                args.as_ptr(),
+                2,
+                kwnames.as_ptr(),
+            ));
+            assert_eq!(result.extract::<(i32, i32, i32)>().unwrap(), (1, 2, 4));
+
+            let int_type = py.get_type::<PyLong>();
+            let zero = py.from_owned_ptr::<PyAny>(ffi::PyObject_CallNoArgs(int_type.as_ptr()));
+            assert_eq!(zero.extract::<i32>().unwrap(), 0);
+            let base = [
+                PyString::new(py, "ff").as_ptr(),
+                16.to_object(py).into_ptr(),
+            ];
+            let kwnames = PyTuple::new(py, &["base"]);
+            let result = py.from_owned_ptr::<PyAny>(ffi::PyObject_Vectorcall(
+                int_type.as_ptr(),
+                base.as_ptr(),
+                1,
+                kwnames.as_ptr(),
+            ));
+            ffi::Py_DECREF(base[1]);
+            assert_eq!(result.extract::<i32>().unwrap(), 255);
+        }
+    }
+
     #[test]
     fn test_call_with_kwargs() {
         let gil = Python::acquire_gil();
@@ -549,4 +643,25 @@ mod test {
         let obj = py.eval("42", None, None).unwrap();
         assert_eq!(unsafe { obj.get_type().as_type_ptr() }, obj.get_type_ptr())
     }
+
+    #[test]
+    fn test_comparison_helpers() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let one = 1.to_object(py);
+        let one = one.as_ref(py);
+        assert!(one.lt(2).unwrap());
+        assert!(one.le(1).unwrap());
+        assert!(!one.gt(1).unwrap());
+        assert!(one.ge(0).unwrap());
+        assert!(one.rich_eq(1).unwrap());
+        assert!(one.rich_ne("1").unwrap());
+        assert!(one.lt("1").is_err());
+
+        let list = crate::types::PyList::new(py, &[3, 1, 2]);
+        let mut items: Vec<&PyAny> = list.iter().collect();
+        items.sort_by(|a, b| a.compare(*b).unwrap());
+        let sorted: Vec<i32> = items.iter().map(|i| i.extract().unwrap()).collect();
+        assert_eq!(sorted, vec![1, 2, 3]);
+    }
 }
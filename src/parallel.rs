@@ -0,0 +1,135 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Running [rayon](https://docs.rs/rayon) parallel iterators from Python extensions.
+//!
+//! Rayon worker threads that touch Python objects need the GIL. If the thread that started the
+//! parallel computation still holds the GIL, every worker that tries to acquire it blocks forever,
+//! while the calling thread waits for the workers to finish: a deadlock.
+//!
+//! This module provides the two halves to do it right:
+//!
+//!  * [allow_threads] releases the GIL and runs the computation inside the rayon thread pool.
+//!  * [with_gil] re-acquires the GIL inside a worker closure. It panics with a helpful message
+//!    instead of deadlocking when called from a rayon worker while no [allow_threads] call
+//!    is active.
+//!
+//! This module is only available with the `rayon` feature.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::parallel;
+//! use rayon::prelude::*;
+//!
+//! #[pyfunction]
+//! fn total_len(py: Python, objs: Vec<PyObject>) -> usize {
+//!     parallel::allow_threads(py, || {
+//!         objs.par_iter()
+//!             .map(|obj| parallel::with_gil(|py| obj.as_ref(py).len().unwrap_or(0)))
+//!             .sum()
+//!     })
+//! }
+//! # let gil = Python::acquire_gil();
+//! # let py = gil.python();
+//! # let objs = vec!["ab".to_object(py), vec![1, 2, 3].to_object(py)];
+//! # assert_eq!(total_len(py, objs), 5);
+//! ```
+
+use crate::Python;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of currently running `allow_threads` calls.
+static ACTIVE_BRIDGES: AtomicUsize = AtomicUsize::new(0);
+
+struct BridgeGuard;
+
+impl BridgeGuard {
+    fn new() -> BridgeGuard {
+        ACTIVE_BRIDGES.fetch_add(1, Ordering::SeqCst);
+        BridgeGuard
+    }
+}
+
+impl Drop for BridgeGuard {
+    fn drop(&mut self) {
+        ACTIVE_BRIDGES.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Releases the GIL and runs `op` inside the global rayon thread pool.
+///
+/// Inside `op`, use [with_gil] to access Python objects from the worker threads.
+pub fn allow_threads<T, F>(py: Python, op: F) -> T
+where
+    F: Send + FnOnce() -> T,
+    T: Send,
+{
+    py.allow_threads(|| {
+        let _guard = BridgeGuard::new();
+        rayon::scope(|_| op())
+    })
+}
+
+/// Like [allow_threads], but runs `op` inside the given thread pool.
+pub fn allow_threads_in<T, F>(py: Python, pool: &rayon::ThreadPool, op: F) -> T
+where
+    F: Send + FnOnce() -> T,
+    T: Send,
+{
+    py.allow_threads(|| {
+        let _guard = BridgeGuard::new();
+        pool.install(op)
+    })
+}
+
+/// Acquires the GIL from within a rayon worker and runs `f`.
+///
+/// The GIL is held only for the duration of `f`. Avoid starting nested parallel work inside
+/// `f`: the worker would wait for other workers while holding the GIL, and those workers
+/// cannot acquire it.
+///
+/// # Panics
+/// Panics when called from a rayon worker thread while no [allow_threads] call is active,
+/// as the thread that started the parallel work most likely still holds the GIL.
+pub fn with_gil<F, R>(f: F) -> R
+where
+    F: for<'py> FnOnce(Python<'py>) -> R,
+{
+    if rayon::current_thread_index().is_some() && ACTIVE_BRIDGES.load(Ordering::SeqCst) == 0 {
+        panic!(
+            "pyo3::parallel::with_gil called from a rayon worker without \
+             pyo3::parallel::allow_threads; this would deadlock if the GIL is still held"
+        );
+    }
+    Python::with_gil(f)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ToPyObject;
+    use rayon::prelude::*;
+
+    #[test]
+    fn test_with_gil_in_workers() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let objs: Vec<_> = (0..100).map(|i| i.to_object(py)).collect();
+        let sum: i64 = allow_threads(py, || {
+            objs.par_iter()
+                .map(|obj| with_gil(|py| obj.extract::<i64>(py).unwrap()))
+                .sum()
+        });
+        assert_eq!(sum, (0..100).sum());
+    }
+
+    #[test]
+    fn test_with_gil_without_bridge_panics() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let result = pool.install(|| std::panic::catch_unwind(|| with_gil(|_| ())));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Pickling instances of `#[pyclass]` types.
+//!
+//! `#[pyclass(pickle)]` implements [PyPickle] from the fields of the struct and adds
+//! `__getstate__`, `__setstate__` and `__reduce__` methods to the class, so its instances work
+//! with `pickle`, `copy.copy`, `copy.deepcopy` and `multiprocessing`. The state is a dict mapping
+//! the field names to the fields converted with `ToPyObject`, and unpickling extracts every field
+//! from it again. `#[pyclass(pickle = "serde")]` converts the whole struct with
+//! [pythonize](crate::pythonize) instead, which needs the `serde` feature and a struct
+//! implementing `Serialize` and `Deserialize`.
+//!
+//! Unpickling creates the instance directly from the state, without calling the `#[new]` method,
+//! so classes whose constructor takes different arguments than the fields are supported. Like any
+//! pickled class, the class has to be importable from the module named by
+//! `#[pyclass(module = "...")]`.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//! use pyo3::wrap_pymodule;
+//!
+//! #[pyclass(pickle, module = "shapes")]
+//! struct Circle {
+//!     radius: f64,
+//!     label: Option<String>,
+//! }
+//!
+//! #[pymethods]
+//! impl Circle {
+//!     #[new]
+//!     fn new(obj: &PyRawObject, diameter: f64) {
+//!         obj.init(Circle { radius: diameter / 2.0, label: None });
+//!     }
+//!
+//!     #[getter]
+//!     fn radius(&self) -> f64 {
+//!         self.radius
+//!     }
+//! }
+//!
+//! #[pymodule]
+//! fn shapes(_py: Python, m: &PyModule) -> PyResult<()> {
+//!     m.add_class::<Circle>()
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let shapes = wrap_pymodule!(shapes)(py);
+//! pyo3::py_run!(py, shapes, r#"
+//! import pickle, sys
+//! sys.modules["shapes"] = shapes
+//! circle = pickle.loads(pickle.dumps(shapes.Circle(3.0)))
+//! assert circle.radius == 1.5
+//! "#);
+//! ```
+
+use crate::callback::{cb_convert, PyObjectCallbackConverter};
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::ValueError;
+use crate::ffi;
+use crate::instance::Py;
+use crate::objectprotocol::ObjectProtocol;
+use crate::type_object::{PyRawObject, PyTypeCreate, PyTypeInfo};
+use crate::types::{PyAny, PyDict, PyType};
+use crate::{FromPyObject, IntoPyPointer, PyObject, Python, ToPyObject};
+
+/// The name of the class method [PyPickle] classes are unpickled with.
+const FROM_STATE: &str = "_pyo3_from_state";
+
+/// A class whose instances can be pickled, implemented by `#[pyclass(pickle)]`.
+pub trait PyPickle: PyTypeCreate + Sized + 'static {
+    /// Returns the state pickle saves, which is passed to [PyPickle::from_state] when unpickling.
+    fn get_state(&self, py: Python) -> PyResult<PyObject>;
+
+    /// Replaces the value of `self` with the one saved in `state`.
+    fn set_state(&mut self, state: &PyAny) -> PyResult<()>;
+
+    /// Creates a value from a state returned by [PyPickle::get_state].
+    fn from_state(state: &PyAny) -> PyResult<Self>;
+}
+
+/// Extracts the field `name` from the state dict of a class using `#[pyclass(pickle)]`.
+#[doc(hidden)]
+pub fn state_field<'p, T>(state: &'p PyAny, name: &str) -> PyResult<T>
+where
+    T: FromPyObject<'p>,
+{
+    let state: &PyDict = state.downcast_ref()?;
+    match state.get_item(name) {
+        Some(value) => value.extract(),
+        None => Err(PyErr::new::<ValueError, _>(format!(
+            "pickle state is missing the field '{}'",
+            name
+        ))),
+    }
+}
+
+/// The `__getstate__` of `#[pyclass(pickle)]` classes.
+#[doc(hidden)]
+pub unsafe extern "C" fn pickle_getstate<T: PyPickle>(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    cb_convert(PyObjectCallbackConverter, py, slf.get_state(py))
+}
+
+/// The `__setstate__` of `#[pyclass(pickle)]` classes.
+#[doc(hidden)]
+pub unsafe extern "C" fn pickle_setstate<T: PyPickle>(
+    slf: *mut ffi::PyObject,
+    state: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let slf = py.mut_from_borrowed_ptr::<T>(slf);
+    let state = py.from_borrowed_ptr::<PyAny>(state);
+    cb_convert(PyObjectCallbackConverter, py, slf.set_state(state))
+}
+
+/// The `__reduce__` of `#[pyclass(pickle)]` classes, which unpickles instances by passing the
+/// state to the `_pyo3_from_state` class method of their class.
+#[doc(hidden)]
+pub unsafe extern "C" fn pickle_reduce<T: PyPickle>(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let class = PyType::from_type_ptr(py, ffi::Py_TYPE(slf));
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    let result = slf.get_state(py).and_then(|state| {
+        let from_state = class.getattr(FROM_STATE)?;
+        Ok((from_state, (state,)).to_object(py))
+    });
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+/// The `_pyo3_from_state` class method of `#[pyclass(pickle)]` classes, which creates an
+/// instance of `cls` without calling `__new__`.
+#[doc(hidden)]
+pub unsafe extern "C" fn pickle_from_state<T: PyPickle>(
+    cls: *mut ffi::PyObject,
+    state: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let state = py.from_borrowed_ptr::<PyAny>(state);
+    let result = T::from_state(state).and_then(|value| {
+        // `cls` is a Python subclass when unpickling one of its instances
+        let tp_ptr = <T as PyTypeInfo>::type_object();
+        let ob = PyRawObject::new(py, tp_ptr, cls as *mut ffi::PyTypeObject)?;
+        ob.init(value);
+        Ok(Py::<T>::from_owned_ptr(ob.into_ptr()))
+    });
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
@@ -0,0 +1,296 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Zero-copy memoryviews of plain-old-data Rust values.
+//!
+//! This module requires the `bytemuck` feature. Any `#[repr(C)]` type implementing
+//! [bytemuck::Pod] can be exposed to Python as a `memoryview` without copying, once it
+//! describes its layout in the syntax of Python's `struct` module through [StructFormat].
+//! Python code can then read the records with `struct.iter_unpack(view.format, view)`, or
+//! pass the view to numpy and similar libraries.
+//!
+//! # Example
+//! ```
+//! use pyo3::pod::{memoryview, StructFormat};
+//! use pyo3::prelude::*;
+//!
+//! #[repr(C)]
+//! #[derive(Clone, Copy)]
+//! struct Point {
+//!     x: f64,
+//!     y: f64,
+//!     id: u32,
+//!     _padding: u32,
+//! }
+//!
+//! unsafe impl bytemuck::Zeroable for Point {}
+//! unsafe impl bytemuck::Pod for Point {}
+//!
+//! impl StructFormat for Point {
+//!     const FORMAT: &'static str = "ddI4x";
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let points = vec![Point { x: 1.0, y: 2.0, id: 7, _padding: 0 }];
+//! let view = memoryview(py, points).unwrap();
+//! let struct_module = py.import("struct").unwrap();
+//! let unpacked: (f64, f64, u32) = struct_module
+//!     .call1("unpack", (view.getattr("format").unwrap(), view))
+//!     .unwrap()
+//!     .extract()
+//!     .unwrap();
+//! assert_eq!(unpacked, (1.0, 2.0, 7));
+//! ```
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::ValueError;
+use crate::ffi;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyAny;
+use crate::Python;
+use std::any::Any;
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+/// A `bytemuck::Pod` type with a description of its layout in the syntax of Python's `struct`
+/// module.
+///
+/// The format must use native alignment (no `<`, `>`, `=` or `!` prefix), so that it matches
+/// the padding of `#[repr(C)]` types. [memoryview] checks that `struct.calcsize(FORMAT)` equals
+/// the size of the type.
+pub trait StructFormat: bytemuck::Pod {
+    /// The `struct` format string of a single value, e.g. `"ii"` for `[i32; 2]`.
+    const FORMAT: &'static str;
+}
+
+macro_rules! struct_format {
+    ($($ty: ty => $format: expr),*) => {
+        $(
+            impl StructFormat for $ty {
+                const FORMAT: &'static str = $format;
+            }
+        )*
+    };
+}
+
+struct_format!(
+    u8 => "B", i8 => "b", u16 => "H", i16 => "h", u32 => "I", i32 => "i", u64 => "Q",
+    i64 => "q", usize => "N", isize => "n", f32 => "f", f64 => "d"
+);
+
+/// Creates a writable one-dimensional `memoryview` over `data`, which Python then owns.
+pub fn memoryview<T: StructFormat>(py: Python, data: Vec<T>) -> PyResult<&PyAny> {
+    let buf = data.as_ptr() as *mut c_void;
+    let len = data.len();
+    export::<T>(py, buf, len, false, Box::new(data))
+}
+
+/// Creates a read-only one-dimensional `memoryview` over the static `data`.
+pub fn memoryview_static<'p, T: StructFormat>(
+    py: Python<'p>,
+    data: &'static [T],
+) -> PyResult<&'p PyAny> {
+    export::<T>(
+        py,
+        data.as_ptr() as *mut c_void,
+        data.len(),
+        true,
+        Box::new(()),
+    )
+}
+
+fn export<T: StructFormat>(
+    py: Python,
+    buf: *mut c_void,
+    len: usize,
+    readonly: bool,
+    owner: Box<dyn Any>,
+) -> PyResult<&PyAny> {
+    let size: usize = py
+        .import("struct")?
+        .call1("calcsize", (T::FORMAT,))?
+        .extract()?;
+    if size != mem::size_of::<T>() {
+        return Err(ValueError::py_err(format!(
+            "Format '{}' describes {} bytes, but the type has {} bytes",
+            T::FORMAT,
+            size,
+            mem::size_of::<T>()
+        )));
+    }
+    let format = CString::new(T::FORMAT)
+        .map_err(|_| ValueError::py_err("Format must not contain a NUL byte"))?;
+
+    unsafe {
+        let exporter = ffi::PyType_GenericAlloc(exporter_type(py)?, 0);
+        if exporter.is_null() {
+            return Err(PyErr::fetch(py));
+        }
+        let exporter_ref = exporter as *mut PodExporter;
+        ptr::write(
+            exporter_ref,
+            PodExporter {
+                ob_base: ptr::read(exporter),
+                buf,
+                itemsize: mem::size_of::<T>() as ffi::Py_ssize_t,
+                shape: len as ffi::Py_ssize_t,
+                readonly,
+                format,
+                owner,
+            },
+        );
+
+        let view = ffi::PyMemoryView_FromObject(exporter);
+        ffi::Py_DECREF(exporter);
+        py.from_owned_ptr_or_err(view)
+    }
+}
+
+/// The object behind the memoryviews, which owns the data and exports it with the buffer
+/// protocol.
+#[repr(C)]
+struct PodExporter {
+    ob_base: ffi::PyObject,
+    buf: *mut c_void,
+    itemsize: ffi::Py_ssize_t,
+    shape: ffi::Py_ssize_t,
+    readonly: bool,
+    format: CString,
+    owner: Box<dyn Any>,
+}
+
+static mut EXPORTER_TYPE: ffi::PyTypeObject = ffi::PyTypeObject_INIT;
+static mut EXPORTER_BUFFER_PROCS: ffi::PyBufferProcs = ffi::PyBufferProcs_INIT;
+
+/// Returns the exporter type, readying it on first use. The GIL makes this race-free.
+unsafe fn exporter_type(py: Python) -> PyResult<*mut ffi::PyTypeObject> {
+    let type_object = &mut EXPORTER_TYPE;
+    if type_object.tp_flags & ffi::Py_TPFLAGS_READY == 0 {
+        EXPORTER_BUFFER_PROCS.bf_getbuffer = Some(get_buffer);
+        type_object.tp_name = "pyo3.PodBuffer\0".as_ptr() as *const _;
+        type_object.tp_basicsize = mem::size_of::<PodExporter>() as ffi::Py_ssize_t;
+        type_object.tp_flags = ffi::Py_TPFLAGS_DEFAULT;
+        type_object.tp_dealloc = Some(dealloc);
+        type_object.tp_as_buffer = &mut EXPORTER_BUFFER_PROCS;
+        if ffi::PyType_Ready(type_object) != 0 {
+            return Err(PyErr::fetch(py));
+        }
+    }
+    Ok(type_object)
+}
+
+unsafe extern "C" fn get_buffer(
+    obj: *mut ffi::PyObject,
+    view: *mut ffi::Py_buffer,
+    flags: c_int,
+) -> c_int {
+    let exporter = &*(obj as *const PodExporter);
+    if flags & ffi::PyBUF_WRITABLE != 0 && exporter.readonly {
+        ffi::PyErr_SetString(
+            ffi::PyExc_BufferError,
+            "Object is not writable\0".as_ptr() as *const _,
+        );
+        return -1;
+    }
+    ffi::Py_INCREF(obj);
+    (*view).obj = obj;
+    (*view).buf = exporter.buf;
+    (*view).len = exporter.shape * exporter.itemsize;
+    (*view).itemsize = exporter.itemsize;
+    (*view).readonly = exporter.readonly as c_int;
+    (*view).ndim = 1;
+    (*view).format = if flags & ffi::PyBUF_FORMAT != 0 {
+        exporter.format.as_ptr() as *mut _
+    } else {
+        ptr::null_mut()
+    };
+    (*view).shape = if flags & ffi::PyBUF_ND != 0 {
+        &exporter.shape as *const _ as *mut _
+    } else {
+        ptr::null_mut()
+    };
+    // The data is C-contiguous, which consumers assume if `strides` is NULL
+    (*view).strides = ptr::null_mut();
+    (*view).suboffsets = ptr::null_mut();
+    (*view).internal = ptr::null_mut();
+    0
+}
+
+unsafe extern "C" fn dealloc(obj: *mut ffi::PyObject) {
+    let exporter = obj as *mut PodExporter;
+    ptr::drop_in_place(&mut (*exporter).format);
+    ptr::drop_in_place(&mut (*exporter).owner);
+    ffi::PyObject_Free(obj as *mut c_void);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{memoryview, memoryview_static, StructFormat};
+    use crate::exceptions::ValueError;
+    use crate::types::PyDict;
+    use crate::Python;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Record {
+        id: u16,
+        value: f64,
+    }
+
+    unsafe impl bytemuck::Zeroable for Record {}
+    unsafe impl bytemuck::Pod for Record {}
+
+    impl StructFormat for Record {
+        const FORMAT: &'static str = "Hd";
+    }
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    struct Wrong(u32);
+
+    unsafe impl bytemuck::Zeroable for Wrong {}
+    unsafe impl bytemuck::Pod for Wrong {}
+
+    impl StructFormat for Wrong {
+        const FORMAT: &'static str = "Q";
+    }
+
+    #[test]
+    fn test_memoryview() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let records = vec![Record { id: 1, value: 0.5 }, Record { id: 2, value: 1.5 }];
+        let locals = PyDict::new(py);
+        locals
+            .set_item("view", memoryview(py, records).unwrap())
+            .unwrap();
+        locals
+            .set_item("numbers", memoryview_static(py, &[1i32, 2, 3]).unwrap())
+            .unwrap();
+        py.run(
+            r#"
+import struct
+assert view.format == "Hd" and view.itemsize == 16 and len(view) == 2
+assert list(struct.iter_unpack(view.format, view)) == [(1, 0.5), (2, 1.5)]
+struct.pack_into("H", view, 16, 3)
+assert struct.unpack_from("Hd", view, 16) == (3, 1.5)
+
+assert numbers.readonly and numbers.tolist() == [1, 2, 3]
+try:
+    numbers[0] = 0
+except TypeError:
+    pass
+else:
+    assert False
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let err = memoryview(py, vec![Wrong(0)]).err().unwrap();
+        assert!(err.is_instance::<ValueError>(py));
+    }
+}
@@ -22,4 +22,5 @@ pub use crate::types::PyModule;
 // This is required for the constructor
 pub use crate::PyRawObject;
 pub use pyo3cls::pymodule;
+pub use pyo3cls::FromPyObject;
 pub use pyo3cls::{pyclass, pyfunction, pymethods, pyproto};
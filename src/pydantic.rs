@@ -0,0 +1,218 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Interoperability with [pydantic](https://docs.pydantic.dev) models.
+//!
+//! Web backends often validate their input with pydantic and then hand it to Rust.
+//! [validate] runs arbitrary Python input through a model and extracts the validated data into
+//! a Rust type, going through the model's dict export. Usually the target is a struct with
+//! `#[derive(FromPyObject)]`, which reads the exported fields by key.
+//!
+//! In the other direction, [JsonSchema] describes Rust types as JSON Schema, e.g. to publish the
+//! shape of the data a Rust function expects, or to build a model with
+//! `pydantic.create_model`. `#[derive(FromPyObject)]` implements it with `#[pyo3(json_schema)]`.
+//!
+//! Both pydantic 1 and 2 are supported. This module is only available with the `pydantic`
+//! feature.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::pydantic;
+//!
+//! #[derive(FromPyObject)]
+//! #[pyo3(json_schema)]
+//! struct User {
+//!     name: String,
+//!     age: u32,
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let locals = pyo3::types::PyDict::new(py);
+//! py.run(
+//!     "import pydantic\nclass User(pydantic.BaseModel):\n    name: str\n    age: int\n",
+//!     None,
+//!     Some(locals),
+//! )
+//! .unwrap();
+//! let model = locals.get_item("User").unwrap();
+//!
+//! // pydantic coerces the string "42" to an int
+//! let input = py.eval("{'name': 'Ada', 'age': '42'}", None, None).unwrap();
+//! let user: User = pydantic::validate(model, input).unwrap();
+//! assert_eq!(user.age, 42);
+//!
+//! let schema = pydantic::json_schema_string::<User>(py).unwrap();
+//! assert!(schema.contains("\"required\": [\"name\", \"age\"]"));
+//! ```
+
+use crate::err::PyResult;
+use crate::instance::PyNativeType;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict, PyList};
+use crate::{FromPyObject, PyObject, Python, ToPyObject};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+/// Validates `input` with the pydantic `model` class and extracts the validated fields as `T`.
+///
+/// This is equivalent to `model.model_validate(input).model_dump()` (or
+/// `model.parse_obj(input).dict()` with pydantic 1), followed by an extraction. Validation
+/// errors are raised as pydantic's `ValidationError`.
+pub fn validate<'p, T>(model: &'p PyAny, input: &PyAny) -> PyResult<T>
+where
+    T: FromPyObject<'p>,
+{
+    let instance = if model.hasattr("model_validate")? {
+        model.call_method1("model_validate", (input,))?
+    } else {
+        model.call_method1("parse_obj", (input,))?
+    };
+    model_to_dict(instance)?.extract()
+}
+
+/// Exports a pydantic model instance as a dict, using `model_dump()` or, with pydantic 1,
+/// `dict()`.
+pub fn model_to_dict(instance: &PyAny) -> PyResult<&PyAny> {
+    if instance.hasattr("model_dump")? {
+        instance.call_method0("model_dump")
+    } else {
+        instance.call_method0("dict")
+    }
+}
+
+/// Types that can describe themselves as JSON Schema.
+///
+/// The schema is built as a Python dict, so that it can be passed to Python code directly.
+pub trait JsonSchema {
+    /// Returns the JSON Schema of `Self` as a dict.
+    fn json_schema(py: Python) -> PyObject;
+}
+
+/// Returns the JSON Schema of `T` serialized with `json.dumps`.
+pub fn json_schema_string<T: JsonSchema>(py: Python) -> PyResult<String> {
+    let schema = T::json_schema(py);
+    py.import("json")?.call1("dumps", (schema,))?.extract()
+}
+
+/// Builds the JSON Schema of an object with the given `(name, schema, required)` properties.
+/// Used by `#[derive(FromPyObject)]` with `#[pyo3(json_schema)]`.
+pub fn object_schema(py: Python, title: &str, properties: &[(&str, PyObject, bool)]) -> PyObject {
+    let schema = PyDict::new(py);
+    let props = PyDict::new(py);
+    let required = PyList::empty(py);
+    for (name, property, is_required) in properties {
+        props.set_item(name, property).unwrap();
+        if *is_required {
+            required.append(name).unwrap();
+        }
+    }
+    schema.set_item("title", title).unwrap();
+    schema.set_item("type", "object").unwrap();
+    schema.set_item("properties", props).unwrap();
+    schema.set_item("required", required).unwrap();
+    schema.into()
+}
+
+fn type_schema(py: Python, ty: &str) -> PyObject {
+    let schema = PyDict::new(py);
+    schema.set_item("type", ty).unwrap();
+    schema.into()
+}
+
+macro_rules! json_schema_type {
+    ($ty: expr, $($rust_ty: ty),*) => {
+        $(
+            impl JsonSchema for $rust_ty {
+                fn json_schema(py: Python) -> PyObject {
+                    type_schema(py, $ty)
+                }
+            }
+        )*
+    };
+}
+
+json_schema_type!("boolean", bool);
+json_schema_type!("integer", i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+json_schema_type!("number", f32, f64);
+json_schema_type!("string", char, String, &str);
+
+impl<T: JsonSchema> JsonSchema for Option<T> {
+    fn json_schema(py: Python) -> PyObject {
+        let schema = PyDict::new(py);
+        let options = [T::json_schema(py), type_schema(py, "null")];
+        schema.set_item("anyOf", options.to_object(py)).unwrap();
+        schema.into()
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Vec<T> {
+    fn json_schema(py: Python) -> PyObject {
+        let schema = PyDict::new(py);
+        schema.set_item("type", "array").unwrap();
+        schema.set_item("items", T::json_schema(py)).unwrap();
+        schema.into()
+    }
+}
+
+fn map_schema(py: Python, values: PyObject) -> PyObject {
+    let schema = PyDict::new(py);
+    schema.set_item("type", "object").unwrap();
+    schema.set_item("additionalProperties", values).unwrap();
+    schema.into()
+}
+
+impl<V: JsonSchema, S: BuildHasher> JsonSchema for HashMap<String, V, S> {
+    fn json_schema(py: Python) -> PyObject {
+        map_schema(py, V::json_schema(py))
+    }
+}
+
+impl<V: JsonSchema> JsonSchema for BTreeMap<String, V> {
+    fn json_schema(py: Python) -> PyObject {
+        map_schema(py, V::json_schema(py))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{json_schema_string, validate, JsonSchema};
+    use crate::types::PyDict;
+    use crate::Python;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_validate() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            "import pydantic\nclass Point(pydantic.BaseModel):\n    x: int\n    y: int = 0\n",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let model = locals.get_item("Point").unwrap();
+
+        let input = py.eval("{'x': '3'}", None, None).unwrap();
+        let point: HashMap<String, i32> = validate(model, input).unwrap();
+        assert_eq!(point["x"], 3);
+        assert_eq!(point["y"], 0);
+
+        let input = py.eval("{'x': 'three'}", None, None).unwrap();
+        assert!(validate::<HashMap<String, i32>>(model, input).is_err());
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert_eq!(
+            json_schema_string::<Vec<Option<u8>>>(py).unwrap(),
+            r#"{"type": "array", "items": {"anyOf": [{"type": "integer"}, {"type": "null"}]}}"#
+        );
+        let schema = <HashMap<String, f64>>::json_schema(py);
+        let schema: &PyDict = schema.cast_as(py).unwrap();
+        assert!(schema.get_item("additionalProperties").is_some());
+    }
+}
@@ -3,6 +3,7 @@
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
 use crate::err::{PyDowncastError, PyErr, PyResult};
+use crate::exceptions::{RuntimeError, TimeoutError};
 use crate::ffi;
 use crate::gil::{self, GILGuard};
 use crate::instance::AsPyRef;
@@ -13,8 +14,13 @@ use crate::AsPyPointer;
 use crate::{FromPyPointer, IntoPyPointer, PyTryFrom};
 use std::ffi::CString;
 use std::marker::PhantomData;
-use std::os::raw::c_int;
+use std::mem;
+use std::os::raw::{c_int, c_long, c_void};
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr::NonNull;
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
 
 pub use gil::prepare_freethreaded_python;
 
@@ -57,13 +63,26 @@ impl<'p> Python<'p> {
     /// Because the output lifetime `'p` is not connected to any input parameter,
     /// care must be taken that the compiler infers an appropriate lifetime for `'p`
     /// when calling this function.
+    ///
+    /// If runtime checks are enabled (in debug builds or with the `runtime-checks` feature),
+    /// this panics when the current thread does not hold the GIL.
     #[inline]
     pub unsafe fn assume_gil_acquired() -> Python<'p> {
+        #[cfg(not(PyPy))]
+        pyo3_assert!(
+            ffi::PyGILState_Check() != 0,
+            "Python::assume_gil_acquired() called without holding the GIL"
+        );
         Python(PhantomData)
     }
 
     /// Acquires the global interpreter lock, which allows access to the Python runtime.
     ///
+    /// The GIL is held until the returned guard is dropped. Prefer [Python::with_gil], which
+    /// can't hold the GIL for longer than intended: a guard stored in a struct or kept alive
+    /// across an `.await` keeps other Python threads waiting, and guards have to be dropped in
+    /// the reverse order of their acquisition.
+    ///
     /// If the Python runtime is not already initialized, this function will initialize it.
     /// See [prepare_freethreaded_python()](fn.prepare_freethreaded_python.html) for details.
     #[inline]
@@ -71,6 +90,84 @@ impl<'p> Python<'p> {
         GILGuard::acquire()
     }
 
+    /// Acquires the global interpreter lock and runs `f` with it.
+    ///
+    /// This is the recommended way to access the Python runtime. The GIL is released, and all
+    /// objects registered in the release pool during `f` are dropped, when `f` returns.
+    /// Because `f` has to work for any lifetime `'py`, no reference bound to the GIL can
+    /// escape the closure; return owned data or a `PyObject` instead.
+    ///
+    /// If the Python runtime is not already initialized, this function will initialize it.
+    /// See [prepare_freethreaded_python()](fn.prepare_freethreaded_python.html) for details.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// let sum: i32 = Python::with_gil(|py| py.eval("1 + 2", None, None)?.extract()).unwrap();
+    /// assert_eq!(sum, 3);
+    /// ```
+    ///
+    /// Borrowed objects can't outlive the closure:
+    /// ```compile_fail
+    /// use pyo3::prelude::*;
+    ///
+    /// let obj = Python::with_gil(|py| py.eval("1 + 2", None, None).unwrap());
+    /// ```
+    #[inline]
+    pub fn with_gil<F, R>(f: F) -> R
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R,
+    {
+        let guard = GILGuard::acquire();
+        f(guard.python())
+    }
+
+    /// Like [Python::with_gil], but gives up if the GIL could not be acquired within `timeout`.
+    ///
+    /// Returns `None` if the timeout elapsed, in which case `f` is never run. Panics in `f` are
+    /// resumed in the current thread.
+    ///
+    /// CPython has no way to try to take the GIL without blocking, so the current thread polls
+    /// until it sees the GIL released, and only then takes it. Other callers of this function
+    /// never make it wait, but a Python thread that grabs the GIL first can. As the interpreter
+    /// hands the GIL over to waiting threads every `sys.getswitchinterval()` (5 ms by default),
+    /// this usually doesn't take longer than that, unless the holder is running code which doesn't
+    /// return to the interpreter loop.
+    ///
+    /// Not available on PyPy or Python 3.12 and later, which give no way to tell whether another
+    /// thread holds the GIL.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let answer = Python::with_gil_timeout(Duration::from_secs(1), |py| {
+    ///     py.eval("6 * 7", None, None).and_then(|v| v.extract::<i32>()).is_ok()
+    /// });
+    /// assert_eq!(answer, Some(true));
+    /// ```
+    #[cfg(not(any(PyPy, Py_3_12)))]
+    pub fn with_gil_timeout<F, R>(timeout: Duration, f: F) -> Option<R>
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R,
+    {
+        gil::with_gil_timeout(timeout, f)
+    }
+
+    /// Like [Python::with_gil_timeout], but only waits for a very short time (1 ms).
+    ///
+    /// Useful for latency-sensitive threads (audio, UI) that would rather skip some Python
+    /// work than block while the interpreter is busy.
+    #[cfg(not(any(PyPy, Py_3_12)))]
+    pub fn try_with_gil<F, R>(f: F) -> Option<R>
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R,
+    {
+        gil::with_gil_timeout(gil::TRY_GIL_TIMEOUT, f)
+    }
+
     /// Temporarily releases the `GIL`, thus allowing other Python threads to run.
     ///
     /// # Example
@@ -128,7 +225,7 @@ impl<'p> Python<'p> {
         unsafe {
             let save = ffi::PyEval_SaveThread();
             let result = f();
-            ffi::PyEval_RestoreThread(save);
+            gil::metrics::record_acquire(|| ffi::PyEval_RestoreThread(save));
             result
         }
     }
@@ -192,6 +289,219 @@ impl<'p> Python<'p> {
         })
     }
 
+    /// Like [Python::run], but raises `TimeoutError` in the running code once `timeout` has
+    /// elapsed.
+    ///
+    /// This is meant to bound the runtime of scripts such as plugins, and is best-effort only:
+    /// the exception is raised asynchronously by a watchdog thread, the next time the interpreter
+    /// checks for asynchronous exceptions. Code blocked in a C function (e.g. `time.sleep` or a
+    /// blocking read) is only interrupted once that function returns, and the script can catch
+    /// the exception like any other. To stop the script for good, run it in a separate process.
+    ///
+    /// # Example:
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let err = py
+    ///     .run_with_timeout("while True: pass", None, None, Duration::from_millis(100))
+    ///     .unwrap_err();
+    /// assert!(err.is_instance::<pyo3::exceptions::TimeoutError>(py));
+    /// ```
+    pub fn run_with_timeout(
+        self,
+        code: &str,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+        timeout: Duration,
+    ) -> PyResult<()> {
+        self.with_timeout(timeout, || self.run(code, globals, locals))
+    }
+
+    /// Like [Python::eval], but raises `TimeoutError` in the evaluated code once `timeout` has
+    /// elapsed. See [Python::run_with_timeout] for the limitations.
+    pub fn eval_with_timeout(
+        self,
+        code: &str,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+        timeout: Duration,
+    ) -> PyResult<&'p PyAny> {
+        self.with_timeout(timeout, || self.eval(code, globals, locals))
+    }
+
+    /// Runs `f`, raising `TimeoutError` in the current thread from a watchdog thread once
+    /// `timeout` has elapsed.
+    fn with_timeout<R, F>(self, timeout: Duration, f: F) -> PyResult<R>
+    where
+        F: FnOnce() -> PyResult<R>,
+    {
+        #[derive(PartialEq)]
+        enum State {
+            Running,
+            TimedOut,
+            Finished,
+        }
+
+        /// Stops the watchdog when dropped, also if `f` panics, so that it can't raise the
+        /// exception in whatever the thread runs next.
+        struct Watchdog<'p> {
+            py: Python<'p>,
+            state: Arc<(Mutex<State>, Condvar)>,
+            thread: Option<thread::JoinHandle<()>>,
+        }
+
+        impl<'p> Watchdog<'p> {
+            /// Stops the watchdog thread and returns whether it raised the exception.
+            fn finish(&mut self) -> bool {
+                let (lock, finished) = &*self.state;
+                let timed_out = {
+                    let mut state = lock.lock().unwrap();
+                    let timed_out = *state == State::TimedOut;
+                    *state = State::Finished;
+                    finished.notify_all();
+                    timed_out
+                };
+                if let Some(thread) = self.thread.take() {
+                    // The watchdog may be waiting for the GIL
+                    self.py.allow_threads(move || thread.join().unwrap());
+                }
+                timed_out
+            }
+
+            /// Discards the exception if the code finished before it noticed it, by letting it
+            /// interrupt a no-op. Clearing it with `PyThreadState_SetAsyncExc` instead would leave
+            /// the interpreter signalled, which stops it from calling trace functions.
+            fn clear(&self) {
+                let _ = self.py.eval("None", None, None);
+            }
+        }
+
+        impl<'p> Drop for Watchdog<'p> {
+            fn drop(&mut self) {
+                if self.thread.is_some() && self.finish() {
+                    self.clear();
+                }
+            }
+        }
+
+        let thread_id: u64 =
+            crate::ObjectProtocol::extract(self.import("threading")?.call0("get_ident")?)?;
+        let thread_id = thread_id as c_long;
+        let state = Arc::new((Mutex::new(State::Running), Condvar::new()));
+
+        let thread = {
+            let state = state.clone();
+            thread::spawn(move || {
+                let (lock, finished) = &*state;
+                let guard = lock.lock().unwrap();
+                let (guard, wait) = finished
+                    .wait_timeout_while(guard, timeout, |state| *state == State::Running)
+                    .unwrap();
+                if !wait.timed_out() {
+                    return;
+                }
+                drop(guard);
+                // The state is only updated with the GIL held, so it can't change while we hold
+                // the GIL as well
+                let _gil = Python::acquire_gil();
+                let mut state = lock.lock().unwrap();
+                if *state == State::Running {
+                    unsafe {
+                        ffi::PyThreadState_SetAsyncExc(thread_id, ffi::PyExc_TimeoutError);
+                    }
+                    *state = State::TimedOut;
+                }
+            })
+        };
+        let mut watchdog = Watchdog {
+            py: self,
+            state,
+            thread: Some(thread),
+        };
+
+        let result = f();
+        let timed_out = watchdog.finish();
+
+        if !timed_out {
+            return result;
+        }
+        match result {
+            Err(ref err) if err.is_instance::<TimeoutError>(self) => Err(TimeoutError::py_err(
+                format!("Execution timed out after {:?}", timeout),
+            )),
+            result => {
+                // The code finished before it noticed the exception, so it must not leak
+                watchdog.clear();
+                result
+            }
+        }
+    }
+
+    /// Runs `f` on the main thread, the thread that initialized Python, and returns its result.
+    ///
+    /// Some libraries, such as `tkinter` and `signal`, only work in the main thread. If the
+    /// current thread is the main thread, `f` runs immediately. Otherwise it is scheduled with
+    /// `Py_AddPendingCall` and runs the next time the main thread executes Python code, while
+    /// the current thread waits with the GIL released. Panics in `f` are resumed in the current
+    /// thread.
+    ///
+    /// This blocks forever if the main thread doesn't run Python code, e.g. because it waits for
+    /// the current thread or exited.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let name: String = py
+    ///     .run_on_main_thread(|py| {
+    ///         let thread = py.import("threading")?.call0("current_thread")?;
+    ///         thread.getattr("name")?.extract()
+    ///     })
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(name, "MainThread");
+    /// ```
+    pub fn run_on_main_thread<F, R>(self, f: F) -> PyResult<R>
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R + Send,
+        R: Send,
+    {
+        let threading = self.import("threading")?;
+        let main_thread = threading.call0("main_thread")?;
+        let main_thread: u64 =
+            crate::ObjectProtocol::extract(crate::ObjectProtocol::getattr(main_thread, "ident")?)?;
+        let current_thread: u64 = crate::ObjectProtocol::extract(threading.call0("get_ident")?)?;
+        if main_thread == current_thread {
+            return Ok(f(self));
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let call: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+            let py = unsafe { Python::assume_gil_acquired() };
+            let _pool = crate::GILPool::new(py);
+            let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(|| f(py))));
+        });
+        // The call borrows from the current stack frame, which is safe because we wait for it
+        let call: Box<dyn FnOnce() + Send + 'static> = unsafe { mem::transmute(call) };
+        let arg = Box::into_raw(Box::new(call));
+        if unsafe { ffi::Py_AddPendingCall(Some(run_pending_call), arg as *mut c_void) } == -1 {
+            drop(unsafe { Box::from_raw(arg) });
+            return Err(RuntimeError::py_err(
+                "The queue of calls to run on the main thread is full",
+            ));
+        }
+        match self.allow_threads(move || receiver.recv()) {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(payload)) => panic::resume_unwind(payload),
+            Err(_) => unreachable!("the pending call was dropped without running"),
+        }
+    }
+
     /// Runs code in the given context.
     /// `start` indicates the type of input expected:
     /// one of `Py_single_input`, `Py_file_input`, or `Py_eval_input`.
@@ -242,6 +552,12 @@ impl<'p> Python<'p> {
         PyModule::import(self, name)
     }
 
+    /// Returns a module which is only imported when one of its attributes is first accessed,
+    /// see [PyModule::import_lazy].
+    pub fn import_lazy(self, name: &str) -> PyResult<&'p PyModule> {
+        PyModule::import_lazy(self, name)
+    }
+
     /// Check whether `obj` is an instance of type `T` like Python `isinstance` function
     pub fn is_instance<T: PyTypeObject, V: AsPyPointer>(self, obj: &V) -> PyResult<bool> {
         T::type_object().as_ref(self).is_instance(obj)
@@ -269,6 +585,67 @@ impl<'p> Python<'p> {
     pub fn NotImplemented(self) -> PyObject {
         unsafe { PyObject::from_borrowed_ptr(self, ffi::Py_NotImplemented()) }
     }
+
+    /// Gets the Python builtin value `Ellipsis`, or `...`.
+    #[allow(non_snake_case)] // the Python keyword starts with uppercase
+    #[inline]
+    pub fn Ellipsis(self) -> PyObject {
+        unsafe { PyObject::from_borrowed_ptr(self, ffi::Py_Ellipsis()) }
+    }
+
+    /// Gets the `(major, minor, patch)` version of the running interpreter.
+    ///
+    /// This is the version of the interpreter the extension was loaded into, which can be
+    /// newer than the one it was compiled against.
+    pub fn version_info(self) -> (u8, u8, u8) {
+        runtime_version()
+    }
+
+    /// Runs the Python signal handlers of signals received since the last check, returning
+    /// the exception raised by one of them, e.g. `KeyboardInterrupt` after Ctrl-C.
+    ///
+    /// The interpreter only does this between bytecode instructions, so a long computation in
+    /// Rust should call this regularly to stay interruptible. Signal handlers only run on the
+    /// main thread; on other threads this always returns `Ok(())`.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// #[pyfunction]
+    /// fn count(py: Python, n: u64) -> PyResult<u64> {
+    ///     let mut total = 0;
+    ///     for i in 0..n {
+    ///         if i % 1_000_000 == 0 {
+    ///             py.check_signals()?;
+    ///         }
+    ///         total += i;
+    ///     }
+    ///     Ok(total)
+    /// }
+    /// ```
+    pub fn check_signals(self) -> PyResult<()> {
+        if unsafe { ffi::PyErr_CheckSignals() } == -1 {
+            Err(PyErr::fetch(self))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Parses the version of the running interpreter out of `Py_GetVersion`,
+/// which has the form `"3.8.1 (default, ...)"`.
+pub(crate) fn runtime_version() -> (u8, u8, u8) {
+    let version = unsafe { std::ffi::CStr::from_ptr(ffi::Py_GetVersion()) };
+    let version = version.to_str().unwrap_or("");
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|part| part.parse::<u8>().unwrap_or(0));
+    (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    )
 }
 
 impl<'p> Python<'p> {
@@ -430,6 +807,12 @@ impl<'p> Python<'p> {
     }
 }
 
+extern "C" fn run_pending_call(arg: *mut c_void) -> c_int {
+    let call = unsafe { Box::from_raw(arg as *mut Box<dyn FnOnce() + Send>) };
+    call();
+    0
+}
+
 #[cfg(test)]
 mod test {
     use crate::objectprotocol::ObjectProtocol;
@@ -477,6 +860,119 @@ mod test {
         assert_eq!(v, 2);
     }
 
+    #[test]
+    fn test_check_signals() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // Nothing was received
+        assert!(py.check_signals().is_ok());
+    }
+
+    #[test]
+    fn test_with_gil() {
+        let value: i32 = Python::with_gil(|py| {
+            let list = PyList::new(py, &[1, 2, 3]);
+            list.get_item(2).extract().unwrap()
+        });
+        assert_eq!(value, 3);
+    }
+
+    #[test]
+    #[cfg(not(any(PyPy, Py_3_12)))]
+    fn test_with_gil_timeout() {
+        use std::sync::mpsc;
+        use std::thread;
+        use std::time::Duration;
+
+        // Runs in place if the GIL is already held
+        let gil = Python::acquire_gil();
+        assert_eq!(
+            Python::with_gil_timeout(Duration::from_millis(0), |_| 1),
+            Some(1)
+        );
+
+        // Times out while another thread holds the GIL, without running `f`
+        let (tx, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let res = Python::with_gil_timeout(Duration::from_millis(50), |_| ());
+            tx.send(()).unwrap();
+            res
+        });
+        rx.recv().unwrap();
+        assert_eq!(handle.join().unwrap(), None);
+        assert_eq!(
+            thread::spawn(|| Python::try_with_gil(|_| ()))
+                .join()
+                .unwrap(),
+            None
+        );
+        drop(gil);
+
+        // `f` runs on the calling thread, and may borrow from it
+        let caller = thread::current().id();
+        let ran_on = Python::with_gil_timeout(Duration::from_secs(10), |_| thread::current().id());
+        assert_eq!(ran_on, Some(caller));
+        let handle = thread::spawn(|| {
+            let local = vec![1, 2, 3];
+            Python::with_gil_timeout(Duration::from_secs(10), |py| PyList::new(py, &local).len())
+        });
+        assert_eq!(handle.join().unwrap(), Some(3));
+
+        // Waits for the GIL to be released while the timeout allows it
+        let gil = Python::acquire_gil();
+        let (tx, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            tx.send(()).unwrap();
+            Python::with_gil_timeout(Duration::from_secs(10), |_| 2)
+        });
+        rx.recv().unwrap();
+        thread::sleep(Duration::from_millis(20));
+        gil.python()
+            .allow_threads(|| assert_eq!(handle.join().unwrap(), Some(2)));
+        drop(gil);
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        use crate::exceptions::TimeoutError;
+        use std::time::Duration;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err = py
+            .run_with_timeout("while True: pass", None, None, Duration::from_millis(50))
+            .unwrap_err();
+        assert!(err.is_instance::<TimeoutError>(py));
+
+        let v: i32 = py
+            .eval_with_timeout("1 + 1", None, None, Duration::from_secs(10))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(v, 2);
+        py.run("for _ in range(100000): pass", None, None).unwrap();
+    }
+
+    #[test]
+    fn test_with_timeout_panic() {
+        use crate::PyResult;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::thread;
+        use std::time::Duration;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            py.with_timeout(Duration::from_millis(10), || -> PyResult<()> {
+                py.allow_threads(|| thread::sleep(Duration::from_millis(200)));
+                panic!("job failed")
+            })
+        }));
+        assert!(result.is_err());
+        // The watchdog must not raise the exception in unrelated code
+        py.run("for _ in range(100000): pass", None, None).unwrap();
+    }
+
     #[test]
     fn test_is_instance() {
         let gil = Python::acquire_gil();
@@ -496,4 +992,20 @@ mod test {
         assert!(py.is_subclass::<PyBool, PyInt>().unwrap());
         assert!(!py.is_subclass::<PyBool, PyList>().unwrap());
     }
+
+    #[test]
+    #[cfg(all(feature = "runtime-checks", not(feature = "unchecked"), not(PyPy)))]
+    #[should_panic(expected = "Python::assume_gil_acquired() called without holding the GIL")]
+    fn test_assume_gil_acquired_checked() {
+        crate::prepare_freethreaded_python();
+        // Test threads don't hold the GIL
+        let _py = unsafe { Python::assume_gil_acquired() };
+    }
+
+    #[test]
+    #[cfg(feature = "unchecked")]
+    fn test_assume_gil_acquired_unchecked() {
+        crate::prepare_freethreaded_python();
+        let _py = unsafe { Python::assume_gil_acquired() };
+    }
 }
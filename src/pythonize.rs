@@ -0,0 +1,893 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Converting any `serde` value to and from Python objects.
+//!
+//! This module requires the `serde` feature. [pythonize] turns a value implementing
+//! [serde::Serialize] into the Python objects the `json` module would produce for it, and
+//! [depythonize] deserializes such objects into a type implementing [serde::Deserialize]. Config
+//! and JSON-like data can then cross the boundary without conversion code for every type, and
+//! without going through a JSON string.
+//!
+//! | Rust                                  | Python                       |
+//! |---------------------------------------|------------------------------|
+//! | `bool`, integers, floats              | `bool`, `int`, `float`       |
+//! | `char`, strings                       | `str`                        |
+//! | bytes, e.g. with `serde_bytes`        | `bytes`                      |
+//! | `None`, `()` and unit structs         | `None`                       |
+//! | sequences                             | `list`                       |
+//! | tuples and tuple structs              | `tuple`                      |
+//! | maps and structs                      | `dict`                       |
+//! | unit enum variants                    | `str` of the variant name    |
+//! | other enum variants                   | `{variant: value}`           |
+//!
+//! [depythonize] also accepts tuples and sets for sequences and `bytearray` for bytes. Objects of
+//! other types raise `TypeError`, and values that don't fit the Rust type, e.g. a missing field,
+//! raise `ValueError`.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::pythonize::{depythonize, pythonize};
+//! use pyo3::types::IntoPyDict;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Config {
+//!     name: String,
+//!     retries: u32,
+//!     servers: Vec<String>,
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let config = Config {
+//!     name: "worker".to_string(),
+//!     retries: 3,
+//!     servers: vec!["a".to_string(), "b".to_string()],
+//! };
+//! let obj = pythonize(py, &config).unwrap();
+//! let locals = [("config", &obj)].into_py_dict(py);
+//! py.run("config['retries'] += 1", None, Some(locals)).unwrap();
+//! let updated: Config = depythonize(&obj.as_ref(py)).unwrap();
+//! assert_eq!(updated.retries, 4);
+//! ```
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{TypeError, ValueError};
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{
+    PyAny, PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyFrozenSet, PyList, PyLong, PySet,
+    PyString, PyTuple,
+};
+use crate::{IntoPy, Python, ToPyObject};
+use serde::de::{self, IntoDeserializer};
+use serde::ser::{self, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Converts `value` to Python objects, see the [module documentation](self).
+pub fn pythonize<T>(py: Python, value: &T) -> PyResult<PyObject>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Pythonizer { py }).map_err(|err| err.0)
+}
+
+/// Deserializes `obj` into a `T`, see the [module documentation](self).
+///
+/// Strings are borrowed from `obj` where `T` allows it.
+pub fn depythonize<'de, T>(obj: &'de PyAny) -> PyResult<T>
+where
+    T: de::Deserialize<'de>,
+{
+    T::deserialize(Depythonizer { input: obj }).map_err(|err| err.0)
+}
+
+/// The Python exception behind a failed conversion, which `serde` requires to be an
+/// `std::error::Error`.
+struct Error(PyErr);
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<PyErr> for Error {
+    fn from(err: PyErr) -> Error {
+        Error(err)
+    }
+}
+
+impl From<crate::PyDowncastError> for Error {
+    fn from(err: crate::PyDowncastError) -> Error {
+        Error(err.into())
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(ValueError::py_err(msg.to_string()))
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(ValueError::py_err(msg.to_string()))
+    }
+
+    fn invalid_type(unexp: de::Unexpected, exp: &dyn de::Expected) -> Error {
+        Error(TypeError::py_err(format!(
+            "invalid type: {}, expected {}",
+            unexp, exp
+        )))
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+struct Pythonizer<'p> {
+    py: Python<'p>,
+}
+
+impl<'p> Pythonizer<'p> {
+    fn object<T: IntoPy<PyObject>>(&self, value: T) -> Result<PyObject> {
+        Ok(value.into_py(self.py))
+    }
+
+    /// Wraps the value of an enum variant other than a unit variant in a dict with the
+    /// variant name as its key, like `serde_json`.
+    fn variant(py: Python, variant: &'static str, value: PyObject) -> Result<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item(variant, value)?;
+        Ok(dict.to_object(py))
+    }
+}
+
+impl<'p> ser::Serializer for Pythonizer<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+    type SerializeSeq = SeqBuilder<'p>;
+    type SerializeTuple = SeqBuilder<'p>;
+    type SerializeTupleStruct = SeqBuilder<'p>;
+    type SerializeTupleVariant = SeqBuilder<'p>;
+    type SerializeMap = MapBuilder<'p>;
+    type SerializeStruct = MapBuilder<'p>;
+    type SerializeStructVariant = MapBuilder<'p>;
+
+    fn serialize_bool(self, v: bool) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    #[cfg(not(Py_LIMITED_API))]
+    fn serialize_i128(self, v: i128) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    #[cfg(not(Py_LIMITED_API))]
+    fn serialize_u128(self, v: u128) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<PyObject> {
+        self.object(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<PyObject> {
+        Ok(PyBytes::new(self.py, v).to_object(self.py))
+    }
+
+    fn serialize_none(self) -> Result<PyObject> {
+        Ok(self.py.None())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<PyObject> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<PyObject> {
+        Ok(self.py.None())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<PyObject> {
+        Ok(self.py.None())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<PyObject> {
+        self.object(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<PyObject> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<PyObject> {
+        let py = self.py;
+        Pythonizer::variant(py, variant, value.serialize(self)?)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder<'p>> {
+        Ok(SeqBuilder::new(self.py, len, false, None))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqBuilder<'p>> {
+        Ok(SeqBuilder::new(self.py, Some(len), true, None))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqBuilder<'p>> {
+        Ok(SeqBuilder::new(self.py, Some(len), true, None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqBuilder<'p>> {
+        Ok(SeqBuilder::new(self.py, Some(len), true, Some(variant)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapBuilder<'p>> {
+        Ok(MapBuilder::new(self.py, None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<MapBuilder<'p>> {
+        Ok(MapBuilder::new(self.py, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapBuilder<'p>> {
+        Ok(MapBuilder::new(self.py, Some(variant)))
+    }
+}
+
+/// Collects the items of a sequence, tuple or tuple variant.
+struct SeqBuilder<'p> {
+    py: Python<'p>,
+    items: Vec<PyObject>,
+    tuple: bool,
+    variant: Option<&'static str>,
+}
+
+impl<'p> SeqBuilder<'p> {
+    fn new(
+        py: Python<'p>,
+        len: Option<usize>,
+        tuple: bool,
+        variant: Option<&'static str>,
+    ) -> SeqBuilder<'p> {
+        SeqBuilder {
+            py,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            tuple,
+            variant,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.items
+            .push(value.serialize(Pythonizer { py: self.py })?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<PyObject> {
+        let py = self.py;
+        let obj = if self.tuple {
+            PyTuple::new(py, self.items).to_object(py)
+        } else {
+            PyList::new(py, self.items).to_object(py)
+        };
+        match self.variant {
+            Some(variant) => Pythonizer::variant(py, variant, obj),
+            None => Ok(obj),
+        }
+    }
+}
+
+impl<'p> ser::SerializeSeq for SeqBuilder<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl<'p> ser::SerializeTuple for SeqBuilder<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl<'p> ser::SerializeTupleStruct for SeqBuilder<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl<'p> ser::SerializeTupleVariant for SeqBuilder<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+/// Fills the dict of a map, struct or struct variant.
+struct MapBuilder<'p> {
+    dict: &'p PyDict,
+    key: Option<PyObject>,
+    variant: Option<&'static str>,
+}
+
+impl<'p> MapBuilder<'p> {
+    fn new(py: Python<'p>, variant: Option<&'static str>) -> MapBuilder<'p> {
+        MapBuilder {
+            dict: PyDict::new(py),
+            key: None,
+            variant,
+        }
+    }
+
+    fn finish(self) -> Result<PyObject> {
+        let py = self.dict.py();
+        let obj = self.dict.to_object(py);
+        match self.variant {
+            Some(variant) => Pythonizer::variant(py, variant, obj),
+            None => Ok(obj),
+        }
+    }
+}
+
+impl<'p> ser::SerializeMap for MapBuilder<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(Pythonizer { py: self.dict.py() })?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        let value = value.serialize(Pythonizer { py: self.dict.py() })?;
+        self.dict.set_item(key, value)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl<'p> ser::SerializeStruct for MapBuilder<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let value = value.serialize(Pythonizer { py: self.dict.py() })?;
+        self.dict.set_item(key, value)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl<'p> ser::SerializeStructVariant for MapBuilder<'p> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+struct Depythonizer<'de> {
+    input: &'de PyAny,
+}
+
+impl<'de> Depythonizer<'de> {
+    fn new(input: &'de PyAny) -> Depythonizer<'de> {
+        Depythonizer { input }
+    }
+
+    fn type_error(&self) -> Error {
+        let type_name = self.input.get_type().name().into_owned();
+        Error(TypeError::py_err(format!(
+            "unsupported type '{}'",
+            type_name
+        )))
+    }
+
+    /// Returns the items of a list, tuple, set or frozenset.
+    fn sequence(&self) -> Option<PyResult<Vec<&'de PyAny>>> {
+        let obj = self.input;
+        if obj.downcast_ref::<PyList>().is_ok()
+            || obj.downcast_ref::<PyTuple>().is_ok()
+            || obj.downcast_ref::<PySet>().is_ok()
+            || obj.downcast_ref::<PyFrozenSet>().is_ok()
+        {
+            Some(obj.iter().and_then(|iter| iter.collect()))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Depythonizer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let obj = self.input;
+        if obj.is_none() {
+            visitor.visit_unit()
+        } else if let Ok(value) = obj.downcast_ref::<PyBool>() {
+            visitor.visit_bool(value.is_true())
+        } else if obj.downcast_ref::<PyLong>().is_ok() {
+            if let Ok(value) = obj.extract::<i64>() {
+                visitor.visit_i64(value)
+            } else if let Ok(value) = obj.extract::<u64>() {
+                visitor.visit_u64(value)
+            } else {
+                #[cfg(not(Py_LIMITED_API))]
+                {
+                    if let Ok(value) = obj.extract::<i128>() {
+                        return visitor.visit_i128(value);
+                    }
+                    visitor.visit_u128(obj.extract::<u128>()?)
+                }
+                #[cfg(Py_LIMITED_API)]
+                {
+                    visitor.visit_u64(obj.extract::<u64>()?)
+                }
+            }
+        } else if obj.downcast_ref::<PyFloat>().is_ok() {
+            visitor.visit_f64(obj.extract()?)
+        } else if let Ok(value) = obj.downcast_ref::<PyString>() {
+            match value.to_string()? {
+                Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+                Cow::Owned(value) => visitor.visit_string(value),
+            }
+        } else if let Ok(value) = obj.downcast_ref::<PyBytes>() {
+            visitor.visit_borrowed_bytes(value.as_bytes())
+        } else if let Ok(value) = obj.downcast_ref::<PyByteArray>() {
+            visitor.visit_byte_buf(value.to_vec())
+        } else if let Ok(dict) = obj.downcast_ref::<PyDict>() {
+            visitor.visit_map(MapAccess {
+                items: dict.iter().collect::<Vec<_>>().into_iter(),
+                value: None,
+            })
+        } else if let Some(items) = self.sequence() {
+            visitor.visit_seq(SeqAccess {
+                items: items?.into_iter(),
+            })
+        } else {
+            Err(self.type_error())
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.input.is_none() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.input.is_none() {
+            visitor.visit_unit()
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let obj = self.input;
+        if let Ok(variant) = obj.downcast_ref::<PyString>() {
+            let variant: String = variant.to_string()?.into_owned();
+            visitor.visit_enum(variant.into_deserializer())
+        } else if let Ok(dict) = obj.downcast_ref::<PyDict>() {
+            let mut items = dict.iter();
+            match (items.next(), items.next()) {
+                (Some((variant, value)), None) => visitor.visit_enum(EnumAccess { variant, value }),
+                _ => Err(de::Error::invalid_value(
+                    de::Unexpected::Map,
+                    &"a dict with a single key",
+                )),
+            }
+        } else {
+            Err(de::Error::invalid_type(
+                de::Unexpected::Other(&obj.get_type().name()),
+                &"a str or a dict with a single key",
+            ))
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf seq
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct SeqAccess<'de> {
+    items: std::vec::IntoIter<&'de PyAny>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        match self.items.next() {
+            Some(item) => seed.deserialize(Depythonizer::new(item)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct MapAccess<'de> {
+    items: std::vec::IntoIter<(&'de PyAny, &'de PyAny)>,
+    value: Option<&'de PyAny>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.items.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Depythonizer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Depythonizer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// An enum variant given as `{variant: value}`.
+struct EnumAccess<'de> {
+    variant: &'de PyAny,
+    value: &'de PyAny,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = Error;
+    type Variant = Depythonizer<'de>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Depythonizer<'de>)> {
+        let variant = seed.deserialize(Depythonizer::new(self.variant))?;
+        Ok((variant, Depythonizer::new(self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Depythonizer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{depythonize, pythonize};
+    use crate::exceptions;
+    use crate::instance::AsPyRef;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::PyAny;
+    use crate::Python;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect(f64, f64),
+        Polygon { sides: u8 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Drawing<'a> {
+        title: &'a str,
+        shapes: Vec<Shape>,
+        origin: (i32, i32),
+        tags: BTreeMap<String, Option<bool>>,
+        #[serde(with = "serde_bytes_compat")]
+        thumbnail: Vec<u8>,
+    }
+
+    /// Serializes a `Vec<u8>` as bytes rather than as a list of ints
+    mod serde_bytes_compat {
+        use serde::de::{self, Deserializer, Visitor};
+        use serde::Serializer;
+        use std::fmt;
+
+        pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(value)
+        }
+
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(v.to_vec())
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<u8>, D::Error> {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut tags = BTreeMap::new();
+        tags.insert("draft".to_string(), Some(true));
+        tags.insert("shared".to_string(), None);
+        let drawing = Drawing {
+            title: "plan",
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(1.5),
+                Shape::Rect(2.0, 3.0),
+                Shape::Polygon { sides: 6 },
+            ],
+            origin: (-1, 2),
+            tags,
+            thumbnail: vec![0, 255],
+        };
+
+        let obj = pythonize(py, &drawing).unwrap();
+        let expected = py
+            .eval(
+                "{
+                    'title': 'plan',
+                    'shapes': ['Empty', {'Circle': 1.5}, {'Rect': (2.0, 3.0)},
+                               {'Polygon': {'sides': 6}}],
+                    'origin': (-1, 2),
+                    'tags': {'draft': True, 'shared': None},
+                    'thumbnail': b'\\x00\\xff',
+                }",
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(expected.rich_eq(&obj).unwrap());
+
+        // Lists work for tuples, and the title is borrowed from the Python string
+        let input = py
+            .eval(
+                "{'title': 'plan', 'shapes': [{'Rect': [1, 2]}], 'origin': [0, 0], 'tags': {},
+                  'thumbnail': bytearray(b'a')}",
+                None,
+                None,
+            )
+            .unwrap();
+        let parsed: Drawing = depythonize(input).unwrap();
+        assert_eq!(parsed.shapes, vec![Shape::Rect(1.0, 2.0)]);
+        assert_eq!(parsed.thumbnail, b"a");
+        let title = input.get_item("title").unwrap().extract::<&str>().unwrap();
+        assert_eq!(parsed.title.as_ptr(), title.as_ptr());
+
+        let round_trip: Drawing = depythonize(obj.cast_as(py).unwrap()).unwrap();
+        assert_eq!(round_trip, drawing);
+    }
+
+    #[test]
+    fn test_large_integers() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = pythonize(py, &(u64::max_value(), i128::min_value())).unwrap();
+        let value: (u64, i128) = depythonize(&obj.as_ref(py)).unwrap();
+        assert_eq!(value, (u64::max_value(), i128::min_value()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let depythonize_code = |code: &str| {
+            let obj: &PyAny = py.eval(code, None, None).unwrap();
+            depythonize::<BTreeMap<String, u32>>(obj).unwrap_err()
+        };
+        assert!(depythonize_code("{'a': 'b'}").is_instance::<exceptions::TypeError>(py));
+        assert!(depythonize_code("{'a': -1}").is_instance::<exceptions::ValueError>(py));
+        assert!(depythonize_code("object()").is_instance::<exceptions::TypeError>(py));
+
+        let obj = py.eval("{'title': 'x'}", None, None).unwrap();
+        let err = depythonize::<Drawing>(obj).unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+
+        // Keys must be hashable
+        let key = vec![1];
+        let mut map = BTreeMap::new();
+        map.insert(key, 1);
+        let err = pythonize(py, &map).unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+    }
+}
@@ -0,0 +1,557 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Resource limits for embedded scripts.
+//!
+//! [SandboxConfig] bundles the limits an application may want to put on scripts it runs, e.g.
+//! plugins: the recursion depth, the memory allocated through Python (measured with
+//! `tracemalloc`) and the number of executed instructions. The limits are applied around a
+//! closure with [SandboxConfig::run] and lifted again afterwards.
+//!
+//! [ImportPolicy] restricts the modules scripts can import to an allowlist. It can be installed
+//! for the lifetime of the interpreter, or only while a sandbox runs.
+//!
+//! The memory and instruction limits are enforced by a trace function, so they only apply to the
+//! current thread, slow down the traced code and replace debuggers or coverage tools while the
+//! sandbox is active. Like [Python::run_with_timeout](../struct.Python.html#method.run_with_timeout),
+//! they are no security boundary: scripts can catch the exceptions raised when a limit is
+//! exceeded, and code running in C functions is not interrupted.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::sandbox::SandboxConfig;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let sandbox = SandboxConfig::new()
+//!     .recursion_limit(200)
+//!     .memory_limit(10 * 1024 * 1024)
+//!     .instruction_limit(100_000);
+//!
+//! let err = sandbox
+//!     .run(py, || py.run("while True: pass", None, None))
+//!     .unwrap_err();
+//! assert!(err.is_instance::<pyo3::exceptions::RuntimeError>(py));
+//! ```
+
+use crate::err::PyResult;
+use crate::exceptions::{ImportError, MemoryError, RuntimeError};
+use crate::ffi;
+use crate::internal_tricks::bound_function;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict, PyModule, PyTuple};
+use crate::{IntoPyPointer, Python};
+use std::cell::Cell;
+use std::os::raw::c_int;
+use std::ptr;
+
+/// Number of instructions between two checks of the memory usage.
+const MEMORY_CHECK_INTERVAL: u64 = 1000;
+
+/// Returns the maximum depth of the Python interpreter stack.
+pub fn recursion_limit(_py: Python) -> u32 {
+    unsafe { ffi::Py_GetRecursionLimit() as u32 }
+}
+
+/// Sets the maximum depth of the Python interpreter stack, like `sys.setrecursionlimit`.
+/// Deeper recursion raises `RecursionError`.
+pub fn set_recursion_limit(_py: Python, limit: u32) {
+    unsafe { ffi::Py_SetRecursionLimit(limit as c_int) }
+}
+
+/// Limits applied while running embedded scripts. See the [module documentation](index.html).
+#[derive(Default)]
+pub struct SandboxConfig {
+    recursion_limit: Option<u32>,
+    memory_limit: Option<usize>,
+    on_memory_limit: Option<Box<dyn Fn(Python, usize) -> PyResult<()>>>,
+    instruction_limit: Option<u64>,
+    import_policy: Option<ImportPolicy>,
+}
+
+impl SandboxConfig {
+    /// Creates a configuration without any limits.
+    pub fn new() -> SandboxConfig {
+        SandboxConfig::default()
+    }
+
+    /// Sets the maximum depth of the Python interpreter stack, see [set_recursion_limit].
+    pub fn recursion_limit(mut self, limit: u32) -> SandboxConfig {
+        self.recursion_limit = Some(limit);
+        self
+    }
+
+    /// Limits the memory traced by `tracemalloc` to `bytes`. `tracemalloc` is started if it is
+    /// not tracing yet, in which case only memory allocated inside the sandbox is counted.
+    ///
+    /// The memory usage is checked periodically; when it exceeds the limit, `MemoryError` is
+    /// raised, unless a callback was set with [SandboxConfig::on_memory_limit].
+    pub fn memory_limit(mut self, bytes: usize) -> SandboxConfig {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Calls `callback` with the traced memory instead of raising `MemoryError` when the memory
+    /// limit is exceeded. The script is aborted with the error the callback returns, or continues
+    /// if it returns `Ok`, in which case the callback is called again on the next check.
+    pub fn on_memory_limit<F>(mut self, callback: F) -> SandboxConfig
+    where
+        F: Fn(Python, usize) -> PyResult<()> + 'static,
+    {
+        self.on_memory_limit = Some(Box::new(callback));
+        self
+    }
+
+    /// Raises `RuntimeError` once `limit` instructions were executed.
+    ///
+    /// Every executed bytecode instruction is counted; before Python 3.7, which can't report
+    /// single instructions to trace functions, every executed line is counted instead.
+    pub fn instruction_limit(mut self, limit: u64) -> SandboxConfig {
+        self.instruction_limit = Some(limit);
+        self
+    }
+
+    /// Only allows the imports permitted by `policy`, see [ImportPolicy].
+    pub fn import_policy(mut self, policy: ImportPolicy) -> SandboxConfig {
+        self.import_policy = Some(policy);
+        self
+    }
+
+    /// Runs `f` with the limits applied, restoring the previous recursion limit, trace
+    /// function and import system afterwards.
+    pub fn run<R, F>(&self, py: Python, f: F) -> PyResult<R>
+    where
+        F: FnOnce() -> PyResult<R>,
+    {
+        let finder = match self.import_policy {
+            Some(ref policy) => Some(policy.install(py)?),
+            None => None,
+        };
+        let mut guard = RunGuard {
+            py,
+            previous_recursion_limit: recursion_limit(py),
+            finder,
+        };
+        if let Some(limit) = self.recursion_limit {
+            set_recursion_limit(py, limit);
+        }
+
+        let result = if self.memory_limit.is_some() || self.instruction_limit.is_some() {
+            self.run_traced(py, f)
+        } else {
+            f()
+        };
+
+        guard.restore()?;
+        result
+    }
+
+    fn run_traced<R, F>(&self, py: Python, f: F) -> PyResult<R>
+    where
+        F: FnOnce() -> PyResult<R>,
+    {
+        let tracemalloc = py.import("tracemalloc")?;
+        let start_tracemalloc =
+            self.memory_limit.is_some() && !tracemalloc.call0("is_tracing")?.is_true()?;
+        if start_tracemalloc {
+            tracemalloc.call0("start")?;
+        }
+        let sys = py.import("sys")?;
+        let previous_trace = sys.call0("gettrace")?;
+
+        let state = TraceState {
+            config: self,
+            instructions: Cell::new(0),
+            get_traced_memory: tracemalloc.get("get_traced_memory")?.into(),
+        };
+        let outer = ACTIVE.with(|active| active.replace(&state as *const _ as *const ()));
+        let mut guard = TraceGuard {
+            outer,
+            sys,
+            previous_trace: Some(previous_trace),
+            tracemalloc: if start_tracemalloc {
+                Some(tracemalloc)
+            } else {
+                None
+            },
+        };
+        unsafe { ffi::PyEval_SetTrace(Some(trace), ptr::null_mut()) };
+
+        let result = f();
+
+        guard.restore()?;
+        result
+    }
+}
+
+/// Restores the recursion limit and removes the import finder when a sandbox finishes, also if
+/// the sandboxed closure panics.
+struct RunGuard<'p> {
+    py: Python<'p>,
+    previous_recursion_limit: u32,
+    finder: Option<&'p PyAny>,
+}
+
+impl<'p> RunGuard<'p> {
+    fn restore(&mut self) -> PyResult<()> {
+        set_recursion_limit(self.py, self.previous_recursion_limit);
+        if let Some(finder) = self.finder.take() {
+            self.py
+                .import("sys")?
+                .get("meta_path")?
+                .call_method1("remove", (finder,))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'p> Drop for RunGuard<'p> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// Restores the trace function of the enclosing sandbox, or the one set with `sys.settrace`,
+/// when a traced sandbox finishes. It must be dropped before the `TraceState` it replaced in
+/// `ACTIVE`.
+struct TraceGuard<'p> {
+    outer: *const (),
+    sys: &'p PyModule,
+    previous_trace: Option<&'p PyAny>,
+    tracemalloc: Option<&'p PyModule>,
+}
+
+impl<'p> TraceGuard<'p> {
+    fn restore(&mut self) -> PyResult<()> {
+        ACTIVE.with(|active| active.set(self.outer));
+        unsafe {
+            if self.outer.is_null() {
+                ffi::PyEval_SetTrace(None, ptr::null_mut());
+            } else {
+                ffi::PyEval_SetTrace(Some(trace), ptr::null_mut());
+            }
+        }
+        if let Some(previous_trace) = self.previous_trace.take() {
+            if !previous_trace.is_none() {
+                self.sys.call1("settrace", (previous_trace,))?;
+            }
+        }
+        if let Some(tracemalloc) = self.tracemalloc.take() {
+            tracemalloc.call0("stop")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'p> Drop for TraceGuard<'p> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// An allowlist of importable modules.
+///
+/// Once installed, importing a module that is not allowed raises `ImportError`. A prefix allows
+/// a module and all of its submodules, so `"json"` allows `json` and `json.decoder`, but not
+/// `jsonschema`.
+///
+/// The policy is enforced by a finder at the front of `sys.meta_path`, which is consulted for
+/// modules that were not imported yet. Modules already in `sys.modules` remain importable, and
+/// modules imported by allowed modules must be allowed as well unless they were imported before.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::sandbox::ImportPolicy;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let policy = ImportPolicy::new().allow("colorsys");
+/// assert!(policy.is_allowed("colorsys"));
+/// assert!(!policy.is_allowed("ftplib"));
+///
+/// let finder = policy.install(py).unwrap();
+/// py.run("import colorsys", None, None).unwrap();
+/// assert!(py.run("import ftplib", None, None).is_err());
+/// py.import("sys").unwrap().get("meta_path").unwrap().call_method1("remove", (finder,)).unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ImportPolicy {
+    allowed: Vec<String>,
+}
+
+impl ImportPolicy {
+    /// Creates a policy that does not allow any imports.
+    pub fn new() -> ImportPolicy {
+        ImportPolicy::default()
+    }
+
+    /// Allows importing `prefix` and its submodules.
+    pub fn allow(mut self, prefix: &str) -> ImportPolicy {
+        self.allowed.push(prefix.to_string());
+        self
+    }
+
+    /// Returns whether the policy allows importing the module `name`.
+    pub fn is_allowed(&self, name: &str) -> bool {
+        self.allowed
+            .iter()
+            .any(|prefix| matches_prefix(name, prefix))
+    }
+
+    /// Inserts a finder enforcing the policy at the front of `sys.meta_path`. The policy is
+    /// lifted by removing the returned finder from `sys.meta_path` again.
+    pub fn install<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let allowed = PyTuple::new(py, &self.allowed);
+        let def = static_method_def!(
+            "find_spec",
+            PyCFunction(find_spec),
+            ffi::METH_VARARGS,
+            "Raises ImportError for modules that are not allowed."
+        );
+        let find_spec = bound_function(py, def, allowed.as_ref())?;
+
+        let builtins = py.import("builtins")?;
+        let namespace = PyDict::new(py);
+        namespace.set_item("find_spec", builtins.call1("staticmethod", (find_spec,))?)?;
+        namespace.set_item("allowed", allowed)?;
+        let class = builtins.call1("type", ("ImportAllowlist", PyTuple::empty(py), namespace))?;
+        let finder = class.call0()?;
+
+        py.import("sys")?
+            .get("meta_path")?
+            .call_method1("insert", (0, finder))?;
+        Ok(finder)
+    }
+}
+
+fn matches_prefix(name: &str, prefix: &str) -> bool {
+    name.starts_with(prefix)
+        && (name.len() == prefix.len() || name[prefix.len()..].starts_with('.'))
+}
+
+/// `find_spec(fullname, path, target=None)` of the finder installed by `ImportPolicy`, bound to
+/// the tuple of allowed prefixes.
+unsafe extern "C" fn find_spec(
+    allowed: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let result = (|| -> PyResult<PyObject> {
+        let allowed: &PyTuple = py.from_borrowed_ptr(allowed);
+        let args: &PyTuple = py.from_borrowed_ptr(args);
+        let name: &str = args.get_item(0).extract()?;
+        for prefix in allowed.iter() {
+            if matches_prefix(name, prefix.extract()?) {
+                // Let the other finders locate the module
+                return Ok(py.None());
+            }
+        }
+        Err(ImportError::py_err(format!(
+            "Import of module '{}' is not allowed",
+            name
+        )))
+    })();
+    match result {
+        Ok(none) => none.into_ptr(),
+        Err(err) => {
+            err.restore(py);
+            ptr::null_mut()
+        }
+    }
+}
+
+struct TraceState<'a> {
+    config: &'a SandboxConfig,
+    instructions: Cell<u64>,
+    get_traced_memory: PyObject,
+}
+
+impl<'a> TraceState<'a> {
+    fn check(&self, py: Python) -> PyResult<()> {
+        let instructions = self.instructions.get() + 1;
+        self.instructions.set(instructions);
+
+        if let Some(limit) = self.config.instruction_limit {
+            if instructions > limit {
+                return Err(RuntimeError::py_err(format!(
+                    "Instruction limit of {} exceeded",
+                    limit
+                )));
+            }
+        }
+        if let Some(limit) = self.config.memory_limit {
+            if instructions % MEMORY_CHECK_INTERVAL == 0 {
+                let (current, _peak): (usize, usize) =
+                    self.get_traced_memory.call0(py)?.extract(py)?;
+                if current > limit {
+                    return match self.config.on_memory_limit {
+                        Some(ref callback) => callback(py, current),
+                        None => Err(MemoryError::py_err(format!(
+                            "Memory limit of {} bytes exceeded",
+                            limit
+                        ))),
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    /// The `TraceState` of the innermost sandbox running on this thread.
+    static ACTIVE: Cell<*const ()> = Cell::new(ptr::null());
+}
+
+/// The trace event counted as one instruction.
+#[cfg(Py_3_7)]
+const INSTRUCTION_EVENT: c_int = ffi::PyTrace_OPCODE;
+#[cfg(not(Py_3_7))]
+const INSTRUCTION_EVENT: c_int = ffi::PyTrace_LINE;
+
+extern "C" fn trace(
+    _obj: *mut ffi::PyObject,
+    frame: *mut ffi::PyFrameObject,
+    what: c_int,
+    _arg: *mut ffi::PyObject,
+) -> c_int {
+    let state = ACTIVE.with(Cell::get) as *const TraceState;
+    if state.is_null() {
+        return 0;
+    }
+    // Trace functions are called with the GIL held
+    let py = unsafe { Python::assume_gil_acquired() };
+    let result = match what {
+        // Opcode events have to be requested for every frame
+        #[cfg(Py_3_7)]
+        ffi::PyTrace_CALL => unsafe {
+            let frame: &PyAny = py.from_borrowed_ptr(frame as *mut ffi::PyObject);
+            frame.setattr("f_trace_opcodes", true)
+        },
+        INSTRUCTION_EVENT => unsafe { &*state }.check(py),
+        _ => Ok(()),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            err.restore(py);
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{recursion_limit, ImportPolicy, SandboxConfig};
+    use crate::exceptions::{ImportError, MemoryError, RecursionError, RuntimeError};
+    use crate::types::PyDict;
+    use crate::{PyResult, Python};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_recursion_limit() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let before = recursion_limit(py);
+        let sandbox = SandboxConfig::new().recursion_limit(50);
+        let err = sandbox
+            .run(py, || py.run("def f(n): return f(n + 1)\nf(0)", None, None))
+            .unwrap_err();
+        assert!(err.is_instance::<RecursionError>(py));
+        assert_eq!(recursion_limit(py), before);
+    }
+
+    #[test]
+    fn test_instruction_limit() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let sandbox = SandboxConfig::new().instruction_limit(1000);
+        sandbox
+            .run(py, || py.run("for _ in range(10): pass", None, None))
+            .unwrap();
+        let err = sandbox
+            .run(py, || py.run("while True: pass", None, None))
+            .unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+        // The trace function is removed again
+        py.run("for _ in range(10000): pass", None, None).unwrap();
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let code = "data = []\nwhile True: data.append(' ' * 1000)";
+        let locals = PyDict::new(py);
+
+        let sandbox = SandboxConfig::new().memory_limit(1_000_000);
+        let err = sandbox
+            .run(py, || py.run(code, None, Some(locals)))
+            .unwrap_err();
+        assert!(err.is_instance::<MemoryError>(py));
+
+        let reported = Rc::new(Cell::new(0));
+        let sandbox = SandboxConfig::new()
+            .memory_limit(1_000_000)
+            .on_memory_limit({
+                let reported = reported.clone();
+                move |_, current| {
+                    reported.set(current);
+                    Err(RuntimeError::py_err("too much"))
+                }
+            });
+        let err = sandbox
+            .run(py, || py.run(code, None, Some(locals)))
+            .unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+        assert!(reported.get() > 1_000_000);
+    }
+
+    #[test]
+    fn test_import_policy() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let policy = ImportPolicy::new().allow("colorsys").allow("email");
+        assert!(policy.is_allowed("email"));
+        assert!(policy.is_allowed("email.mime.text"));
+        assert!(!policy.is_allowed("emails"));
+        assert!(!policy.is_allowed("mailbox"));
+
+        let sandbox = SandboxConfig::new().import_policy(policy);
+        sandbox
+            .run(py, || py.run("import colorsys", None, None))
+            .unwrap();
+        let err = sandbox
+            .run(py, || py.run("import imaplib", None, None))
+            .unwrap_err();
+        assert!(err.is_instance::<ImportError>(py));
+
+        // The finder is removed again
+        py.run("import imaplib", None, None).unwrap();
+    }
+
+    #[test]
+    fn test_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let before = recursion_limit(py);
+        let sandbox = SandboxConfig::new()
+            .recursion_limit(50)
+            .instruction_limit(1000)
+            .import_policy(ImportPolicy::new());
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            sandbox.run(py, || -> PyResult<()> { panic!("script failed") })
+        }));
+        assert!(result.is_err());
+
+        assert_eq!(recursion_limit(py), before);
+        py.run("for _ in range(10000): pass", None, None).unwrap();
+        py.run("import poplib", None, None).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Control over signal handling when embedding Python.
+//!
+//! By default, [prepare_freethreaded_python](../fn.prepare_freethreaded_python.html) initializes
+//! the interpreter without Python's signal handlers, so Ctrl-C is left to the Rust application
+//! and Python never raises `KeyboardInterrupt`. Applications that want Python to own `SIGINT`
+//! can opt in with [install_python_handlers] before the interpreter is initialized.
+//!
+//! When the Rust application owns the signals, [interrupt] forwards an interrupt to Python, which
+//! raises `KeyboardInterrupt` in the main thread the next time it checks for signals. If Python
+//! owns the signals, [SignalHandlers] can reinstate the application's own handlers for the
+//! duration of a closure.
+//!
+//! # Example
+//! ```
+//! # #[cfg(unix)] {
+//! use pyo3::signal::SignalHandlers;
+//!
+//! // Record the application's handler before Python replaces it ...
+//! let rust_handlers = SignalHandlers::capture(&[pyo3::libc::SIGINT]);
+//! // ... and put it back while a Rust-only section runs.
+//! rust_handlers.with(|| {
+//!     // Ctrl-C is handled by the application here
+//! });
+//! # }
+//! ```
+
+use crate::ffi;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INSTALL_PYTHON_HANDLERS: AtomicBool = AtomicBool::new(false);
+
+/// Chooses whether `prepare_freethreaded_python` installs Python's signal handlers (most
+/// importantly the `SIGINT` handler raising `KeyboardInterrupt`) when it initializes the
+/// interpreter. The default is `false`.
+///
+/// Python's signal handlers only run in the thread that initialized the interpreter, so this
+/// should only be enabled if that thread is the application's main thread.
+///
+/// Returns `false` if the interpreter is already initialized, in which case the setting has no
+/// effect.
+pub fn install_python_handlers(install: bool) -> bool {
+    INSTALL_PYTHON_HANDLERS.store(install, Ordering::SeqCst);
+    unsafe { ffi::Py_IsInitialized() == 0 }
+}
+
+pub(crate) fn python_handlers_enabled() -> bool {
+    INSTALL_PYTHON_HANDLERS.load(Ordering::SeqCst)
+}
+
+/// Simulates the arrival of `SIGINT` in Python: the next time the main thread checks for
+/// signals, the Python `SIGINT` handler runs, which raises `KeyboardInterrupt` by default.
+///
+/// This does not require the GIL and is async-signal-safe, so it can be called from a Rust
+/// signal handler to forward Ctrl-C to Python. If Python's `SIGINT` handler was not installed
+/// (see [install_python_handlers]) and the `signal` module was never imported, the interrupt
+/// is ignored.
+pub fn interrupt() {
+    unsafe { ffi::PyErr_SetInterrupt() }
+}
+
+/// A set of process signal handlers recorded at some point in time, which can be reinstated
+/// later on.
+///
+/// This is meant for applications that share signals with Python: record the application's
+/// handlers with [SignalHandlers::capture] before the interpreter installs its own, and use
+/// [SignalHandlers::with] or [SignalHandlers::install] to use them again temporarily.
+#[cfg(unix)]
+pub struct SignalHandlers {
+    handlers: Vec<(libc::c_int, libc::sigaction)>,
+}
+
+#[cfg(unix)]
+impl SignalHandlers {
+    /// Records the current handlers for `signals`.
+    ///
+    /// # Panics
+    /// Panics if one of `signals` is not a valid signal number.
+    pub fn capture(signals: &[libc::c_int]) -> SignalHandlers {
+        let handlers = signals
+            .iter()
+            .map(|&signal| unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(signal, std::ptr::null(), &mut action) != 0 {
+                    panic!("Invalid signal number {}", signal);
+                }
+                (signal, action)
+            })
+            .collect();
+        SignalHandlers { handlers }
+    }
+
+    /// Installs the recorded handlers. The handlers they replace are reinstated when the
+    /// returned guard is dropped.
+    pub fn install(&self) -> SignalHandlersGuard {
+        let previous = self
+            .handlers
+            .iter()
+            .map(|(signal, action)| unsafe {
+                let mut previous: libc::sigaction = std::mem::zeroed();
+                libc::sigaction(*signal, action, &mut previous);
+                (*signal, previous)
+            })
+            .collect();
+        SignalHandlersGuard {
+            previous: SignalHandlers { handlers: previous },
+        }
+    }
+
+    /// Runs `f` with the recorded handlers installed, reinstating the current handlers
+    /// afterwards, even if `f` panics.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let _guard = self.install();
+        f()
+    }
+}
+
+/// Reinstates the signal handlers replaced by [SignalHandlers::install] when dropped.
+#[cfg(unix)]
+#[must_use]
+pub struct SignalHandlersGuard {
+    previous: SignalHandlers,
+}
+
+#[cfg(unix)]
+impl Drop for SignalHandlersGuard {
+    fn drop(&mut self) {
+        for (signal, action) in &self.previous.handlers {
+            unsafe {
+                libc::sigaction(*signal, action, std::ptr::null_mut());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_handlers() {
+        use super::SignalHandlers;
+
+        extern "C" fn handler(_: libc::c_int) {}
+
+        let current = |signal| unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            libc::sigaction(signal, std::ptr::null(), &mut action);
+            action.sa_sigaction
+        };
+
+        let original = SignalHandlers::capture(&[libc::SIGUSR2]);
+        let before = current(libc::SIGUSR2);
+        unsafe { libc::signal(libc::SIGUSR2, handler as libc::sighandler_t) };
+        assert_eq!(current(libc::SIGUSR2), handler as libc::sighandler_t);
+
+        original.with(|| assert_eq!(current(libc::SIGUSR2), before));
+        assert_eq!(current(libc::SIGUSR2), handler as libc::sighandler_t);
+
+        unsafe { libc::signal(libc::SIGUSR2, before) };
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! The signatures of the functions and methods defined with PyO3's macros.
+//!
+//! `#[pyfunction]`, `#[pyfn]` and `#[pymethods]` record the parameters of every function they
+//! wrap, with their kinds and defaults, in a registry which tools can query at runtime with
+//! [lookup] or [registry]. [inspect_signature] converts a record into an `inspect.Signature`.
+//!
+//! `inspect.signature` reads the text signatures PyO3 puts into docstrings, which can't show
+//! defaults that aren't Python literals and which Python only reads for functions and
+//! classes. Classes with a `#[new]` or `__call__` method therefore also get a `__signature__`
+//! attribute computed from the registry, so `inspect.signature` works on the class and its
+//! instances, including in IPython's `?` and in completion tools.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::signature::{self, ParameterKind};
+//! use pyo3::types::PyAny;
+//! use pyo3::wrap_pyfunction;
+//!
+//! #[pyfunction(scale = "2.0", "*", verbose = "false")]
+//! fn resize(width: u32, scale: f64, verbose: bool) -> f64 {
+//!     f64::from(width) * scale
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let resize = wrap_pyfunction!(resize)(py);
+//! let resize = resize.cast_as::<PyAny>(py).unwrap();
+//! let record = signature::lookup(resize).unwrap();
+//! assert_eq!(record.name, "resize");
+//! assert_eq!(record.params[2].kind, ParameterKind::KeywordOnly);
+//! assert_eq!(record.params[2].default, Some("False"));
+//!
+//! let inspect_signature = signature::inspect_signature(resize).unwrap();
+//! assert_eq!(
+//!     inspect_signature.unwrap().to_string(),
+//!     "(width, scale=2.0, *, verbose=False)"
+//! );
+//! ```
+
+use crate::cache::PyStatic;
+use crate::class::{PyMethodDef, PyMethodType};
+use crate::err::PyResult;
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDict, PyModule};
+use crate::{AsPyPointer, IntoPyPointer, Python, ToPyObject};
+use std::ptr;
+
+/// The kind of a parameter, like the kinds of `inspect.Parameter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterKind {
+    PositionalOnly,
+    PositionalOrKeyword,
+    /// `*args`
+    VarPositional,
+    KeywordOnly,
+    /// `**kwargs`
+    VarKeyword,
+}
+
+impl ParameterKind {
+    /// Returns the name of the kind in `inspect.Parameter`, e.g. `POSITIONAL_ONLY`.
+    pub fn inspect_name(self) -> &'static str {
+        match self {
+            ParameterKind::PositionalOnly => "POSITIONAL_ONLY",
+            ParameterKind::PositionalOrKeyword => "POSITIONAL_OR_KEYWORD",
+            ParameterKind::VarPositional => "VAR_POSITIONAL",
+            ParameterKind::KeywordOnly => "KEYWORD_ONLY",
+            ParameterKind::VarKeyword => "VAR_KEYWORD",
+        }
+    }
+}
+
+/// A parameter of a recorded signature.
+#[derive(Clone, Copy, Debug)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub kind: ParameterKind,
+    /// The default value as a Python expression, which is `...` for defaults that aren't
+    /// Python literals
+    pub default: Option<&'static str>,
+}
+
+/// The signature of a function or method, recorded by the macros.
+#[derive(Debug)]
+pub struct FunctionSignature {
+    /// The C functions implementing the function, which identify it
+    pub meth: PyMethodType,
+    /// The Python name of the function
+    pub name: &'static str,
+    /// The parameters, without `self` or `cls`
+    pub params: &'static [Parameter],
+}
+
+inventory::collect!(FunctionSignature);
+
+/// Returns all recorded signatures.
+pub fn registry() -> impl Iterator<Item = &'static FunctionSignature> {
+    inventory::iter::<FunctionSignature>.into_iter()
+}
+
+/// Returns the recorded signature of `obj`.
+///
+/// `obj` can be a function, a method (bound or not), a class, for which the signature of its
+/// `#[new]` method is returned, or an instance of a class with a `__call__` method.
+pub fn lookup(obj: &PyAny) -> Option<&'static FunctionSignature> {
+    let ptr = obj.as_ptr();
+    let meth = unsafe {
+        if ffi::PyCFunction_Check(ptr) != 0 {
+            ffi::PyCFunction_GetFunction(ptr).map(|meth| meth as usize)
+        } else if ffi::PyType_Check(ptr) != 0 {
+            let ty = ptr as *mut ffi::PyTypeObject;
+            // Python subclasses inherit `tp_new`, but may define `__init__`
+            if (*ty).tp_flags & ffi::Py_TPFLAGS_HEAPTYPE == 0 {
+                (*ty).tp_new.map(|new| new as usize)
+            } else {
+                None
+            }
+        } else if let Some((def, _)) = method_descriptor(obj) {
+            (*def).ml_meth.map(|meth| meth as usize)
+        } else {
+            (*ffi::Py_TYPE(ptr)).tp_call.map(|call| call as usize)
+        }
+    };
+    find(meth?)
+}
+
+/// Returns the signature implemented by the C function at `meth`.
+fn find(meth: usize) -> Option<&'static FunctionSignature> {
+    registry().find(|signature| signature.meth.c_function(0).0 as usize == meth)
+}
+
+/// Returns the `inspect.Signature` of `obj` built from its recorded signature (see [lookup]),
+/// or `None` if there is none.
+///
+/// Unbound methods get a `self` or `cls` parameter, like the functions of Python classes.
+pub fn inspect_signature(obj: &PyAny) -> PyResult<Option<&PyAny>> {
+    let py = obj.py();
+    let signature = match lookup(obj) {
+        Some(signature) => signature,
+        None => return Ok(None),
+    };
+    let inspect = py.import("inspect")?;
+    let parameter = inspect.get("Parameter")?;
+
+    let mut params = Vec::new();
+    if let Some((_, receiver)) = unsafe { method_descriptor(obj) } {
+        let kind = parameter.getattr("POSITIONAL_ONLY")?;
+        params.push(parameter.call1((receiver, kind))?);
+    }
+    for param in signature.params {
+        let kind = parameter.getattr(param.kind.inspect_name())?;
+        let kwargs = PyDict::new(py);
+        if let Some(default) = param.default {
+            kwargs.set_item("default", py.eval(default, None, None)?)?;
+        }
+        params.push(parameter.call((param.name, kind), Some(kwargs))?);
+    }
+    inspect.call1("Signature", (params,)).map(Some)
+}
+
+/// Returns the `PyMethodDef` of a method or class method descriptor, and the name of its
+/// receiver.
+#[cfg(not(any(Py_LIMITED_API, PyPy)))]
+unsafe fn method_descriptor(obj: &PyAny) -> Option<(*mut ffi::PyMethodDef, &'static str)> {
+    let ty = ffi::Py_TYPE(obj.as_ptr());
+    let receiver = if ty == &mut ffi::PyMethodDescr_Type as *mut _ {
+        "self"
+    } else if ty == &mut ffi::PyClassMethodDescr_Type as *mut _ {
+        "cls"
+    } else {
+        return None;
+    };
+    let descr = obj.as_ptr() as *mut ffi::PyMethodDescrObject;
+    Some(((*descr).d_method, receiver))
+}
+
+#[cfg(any(Py_LIMITED_API, PyPy))]
+unsafe fn method_descriptor(_obj: &PyAny) -> Option<(*mut ffi::PyMethodDef, &'static str)> {
+    None
+}
+
+/// Adds the `__signature__` descriptor to a class whose `#[new]` or `__call__` method has a
+/// recorded signature.
+pub(crate) fn add_class_signature(py: Python, type_object: &ffi::PyTypeObject) -> PyResult<()> {
+    let new = type_object.tp_new.map(|new| new as usize);
+    let call = type_object.tp_call.map(|call| call as usize);
+    if new.and_then(find).is_none() && call.and_then(find).is_none() {
+        return Ok(());
+    }
+    let dict: &PyDict = unsafe { py.from_borrowed_ptr(type_object.tp_dict) };
+    dict.set_item("__signature__", signature_descriptor(py))
+}
+
+/// Returns the descriptor computing `__signature__` with [inspect_signature], which is the
+/// same for all classes.
+fn signature_descriptor(py: Python) -> &PyAny {
+    static DESCRIPTOR: PyStatic = PyStatic::new();
+    DESCRIPTOR.get_or_init(py, || {
+        let def = PyMethodDef {
+            ml_name: "inspect_signature",
+            ml_meth: PyMethodType::PyCFunction(compute_signature),
+            ml_flags: ffi::METH_O,
+            ml_doc: "\0",
+        };
+        // Python keeps a pointer to the definition, which is leaked once per interpreter
+        let compute: &PyAny = unsafe {
+            py.from_owned_ptr(ffi::PyCFunction_New(
+                Box::into_raw(Box::new(def.as_method_def())),
+                ptr::null_mut(),
+            ))
+        };
+        PyModule::from_code(py, DESCRIPTOR_CODE, "pyo3_signature.py", "pyo3_signature")
+            .and_then(|module| module.call1("Signature", (compute,)))
+            .expect("failed to define the signature descriptor")
+            .into()
+    })
+}
+
+const DESCRIPTOR_CODE: &str = "
+class Signature:
+    '''The `__signature__` of a PyO3 class, or of its instances if it has a `__call__` method'''
+    __slots__ = ('compute',)
+
+    def __init__(self, compute):
+        self.compute = compute
+
+    def __get__(self, obj, objtype=None):
+        # Instances wrapping a function, see `pyo3::forward::update_wrapper`, have the
+        # signature of that function, which `inspect.signature` finds through `__wrapped__`
+        if '__wrapped__' in getattr(obj, '__dict__', ()):
+            raise AttributeError('__signature__')
+        signature = self.compute(objtype if obj is None else obj)
+        if signature is None:
+            raise AttributeError('__signature__')
+        return signature
+";
+
+unsafe extern "C" fn compute_signature(
+    _slf: *mut ffi::PyObject,
+    obj: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    match inspect_signature(py.from_borrowed_ptr(obj)) {
+        Ok(Some(signature)) => signature.to_object(py).into_ptr(),
+        Ok(None) => py.None().into_ptr(),
+        Err(err) => {
+            err.restore(py);
+            ptr::null_mut()
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Classes with a single instance, such as sentinel values.
+//!
+//! `#[pyclass(singleton)]` implements [PySingleton] for a class implementing `Default`. Calling
+//! the class from Python returns the one instance, and so do `copy.copy`, `copy.deepcopy` and
+//! unpickling, so `value is UNSET` checks stay reliable. Singleton classes can't define a
+//! `#[new]` method, and Rust code should use [PySingleton::instance] instead of `Py::new`. The
+//! instance is created on first use, once per interpreter.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//! use pyo3::singleton::PySingleton;
+//! use pyo3::wrap_pymodule;
+//!
+//! /// Marks arguments that were not passed
+//! #[pyclass(singleton)]
+//! #[derive(Default)]
+//! struct Unset {}
+//!
+//! #[pymodule]
+//! fn sentinels(py: Python, m: &PyModule) -> PyResult<()> {
+//!     m.add("UNSET", Unset::instance(py)?)?;
+//!     m.add_class::<Unset>()
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let sentinels = wrap_pymodule!(sentinels)(py);
+//! pyo3::py_run!(py, sentinels, r#"
+//! import copy
+//! assert sentinels.Unset() is sentinels.UNSET
+//! assert copy.deepcopy(sentinels.UNSET) is sentinels.UNSET
+//! "#);
+//! ```
+
+use crate::cache::PyStatic;
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::TypeError;
+use crate::ffi;
+use crate::instance::Py;
+use crate::type_object::PyTypeCreate;
+use crate::types::{PyDict, PyTuple};
+use crate::{AsPyPointer, IntoPyPointer, PyObject, Python, ToPyObject};
+use std::ptr;
+
+/// A class with a single instance, implemented by `#[pyclass(singleton)]`.
+pub trait PySingleton: PyTypeCreate + Default + 'static {
+    #[doc(hidden)]
+    fn instance_slot() -> &'static PyStatic;
+
+    /// Returns the instance, creating it from `Default::default()` on first use.
+    fn instance(py: Python) -> PyResult<Py<Self>> {
+        let instance = Self::instance_slot()
+            .get_or_try_init(py, || Py::new(py, Self::default()).map(Into::into))?;
+        Ok(unsafe { Py::from_borrowed_ptr(instance.as_ptr()) })
+    }
+}
+
+/// Implemented by `#[new]` methods as well as by `#[pyclass(singleton)]`, so that singleton
+/// classes defining `#[new]` fail to compile with conflicting implementations of this trait.
+#[doc(hidden)]
+pub trait SingletonClassesCannotDefineNew {}
+
+/// The `__new__` of singleton classes, which returns the instance.
+#[doc(hidden)]
+pub unsafe extern "C" fn singleton_new<T: PySingleton>(
+    _subtype: *mut ffi::PyTypeObject,
+    args: *mut ffi::PyObject,
+    kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let args: &PyTuple = py.from_borrowed_ptr(args);
+    let kwargs: Option<&PyDict> = py.from_borrowed_ptr_or_opt(kwargs);
+    let result = if args.is_empty() && kwargs.map_or(true, |kwargs| kwargs.is_empty()) {
+        T::instance(py)
+    } else {
+        Err(PyErr::new::<TypeError, _>(format!(
+            "{}() takes no arguments",
+            T::NAME
+        )))
+    };
+    match result {
+        Ok(instance) => instance.into_ptr(),
+        Err(err) => {
+            err.restore(py);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The `__reduce__` of singleton classes, which makes copying and unpickling return the instance.
+#[doc(hidden)]
+pub unsafe extern "C" fn singleton_reduce(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = crate::GILPool::new(py);
+    let class: PyObject = PyObject::from_borrowed_ptr(py, ffi::Py_TYPE(slf) as *mut _);
+    (class, PyTuple::empty(py)).to_object(py).into_ptr()
+}
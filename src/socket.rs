@@ -0,0 +1,243 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Handing sockets between Python and Rust.
+//!
+//! [PySocket] wraps a Python `socket.socket` and converts it into a `std::net` socket and back,
+//! e.g. for proxies that accept connections in Python and forward the traffic in Rust.
+//!
+//! Converting to Rust duplicates the socket (`socket.dup()`, which uses `WSADuplicateSocket` on
+//! Windows), so the Python and the Rust socket can be closed independently. Both refer to the
+//! same connection though, and on Unix they share the blocking mode: a Python socket with a
+//! timeout is non-blocking at the OS level, so call `set_nonblocking(false)` on the Rust socket
+//! if you need blocking IO.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::socket::PySocket;
+//! use std::io::{Read, Write};
+//! use std::net::{TcpListener, TcpStream};
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+//! let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+//! let (server, _) = listener.accept().unwrap();
+//!
+//! // Python sends on the server side of the connection ...
+//! let socket = PySocket::from_std(py, server).unwrap();
+//! socket.to_object(py).call_method1(py, "sendall", (&b"ping"[..],)).unwrap();
+//!
+//! // ... and a Rust copy of the same socket can be used as well
+//! let mut server: TcpStream = socket.to_std(py).unwrap();
+//! server.write_all(b"pong").unwrap();
+//!
+//! let mut received = [0; 8];
+//! client.read_exact(&mut received).unwrap();
+//! assert_eq!(&received, b"pingpong");
+//! ```
+
+use crate::err::PyResult;
+use crate::exceptions::TypeError;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyAny;
+use crate::{AsPyPointer, FromPyObject, IntoPy, PyObject, Python, ToPyObject};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, IntoRawFd, RawFd as RawSocket};
+#[cfg(windows)]
+use std::os::windows::io::{FromRawSocket, IntoRawSocket, RawSocket};
+
+/// The socket kinds of `std::net` that can be converted from and to a [PySocket].
+pub trait StdSocket: Sized {
+    #[doc(hidden)]
+    const SOCKET_TYPE: &'static str;
+    #[doc(hidden)]
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+    #[doc(hidden)]
+    fn into_raw(self) -> RawSocket;
+    #[doc(hidden)]
+    unsafe fn from_raw(socket: RawSocket) -> Self;
+}
+
+macro_rules! std_socket {
+    ($ty: ty, $socket_type: expr) => {
+        impl StdSocket for $ty {
+            const SOCKET_TYPE: &'static str = $socket_type;
+
+            fn local_addr(&self) -> io::Result<SocketAddr> {
+                <$ty>::local_addr(self)
+            }
+
+            #[cfg(unix)]
+            fn into_raw(self) -> RawSocket {
+                self.into_raw_fd()
+            }
+
+            #[cfg(windows)]
+            fn into_raw(self) -> RawSocket {
+                self.into_raw_socket()
+            }
+
+            #[cfg(unix)]
+            unsafe fn from_raw(socket: RawSocket) -> Self {
+                <$ty>::from_raw_fd(socket)
+            }
+
+            #[cfg(windows)]
+            unsafe fn from_raw(socket: RawSocket) -> Self {
+                <$ty>::from_raw_socket(socket)
+            }
+        }
+    };
+}
+
+std_socket!(TcpStream, "SOCK_STREAM");
+std_socket!(TcpListener, "SOCK_STREAM");
+std_socket!(UdpSocket, "SOCK_DGRAM");
+
+/// A Python `socket.socket` object.
+///
+/// Extracting a `PySocket` raises `TypeError` for objects that are not sockets.
+#[derive(Debug)]
+pub struct PySocket(PyObject);
+
+impl PySocket {
+    /// Creates a Python socket taking ownership of `socket`.
+    pub fn from_std<S: StdSocket>(py: Python, socket: S) -> PyResult<PySocket> {
+        let family = match socket.local_addr()? {
+            SocketAddr::V4(_) => "AF_INET",
+            SocketAddr::V6(_) => "AF_INET6",
+        };
+        let module = py.import("socket")?;
+        let family = module.get(family)?;
+        let socket_type = module.get(S::SOCKET_TYPE)?;
+        let raw = socket.into_raw();
+        match module.call1("socket", (family, socket_type, 0, raw)) {
+            Ok(socket) => Ok(PySocket(socket.into())),
+            Err(err) => {
+                // Python did not take ownership, so close the socket again
+                drop(unsafe { S::from_raw(raw) });
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns a duplicate of the socket as a `std::net` socket.
+    ///
+    /// Raises `TypeError` if the socket family or type does not match, e.g. when converting a
+    /// Unix domain socket or a UDP socket into a `TcpStream`.
+    pub fn to_std<S: StdSocket>(&self, py: Python) -> PyResult<S> {
+        let socket = self.0.as_ref(py);
+        let module = py.import("socket")?;
+        let family = socket.getattr("family")?;
+        if !family.rich_eq(module.get("AF_INET")?)? && !family.rich_eq(module.get("AF_INET6")?)? {
+            return Err(TypeError::py_err(format!(
+                "Expected a socket of family AF_INET or AF_INET6, got {}",
+                family.str()?.to_string_lossy()
+            )));
+        }
+        let socket_type = socket.getattr("type")?;
+        if !socket_type.rich_eq(module.get(S::SOCKET_TYPE)?)? {
+            return Err(TypeError::py_err(format!(
+                "Expected a socket of type {}, got {}",
+                S::SOCKET_TYPE,
+                socket_type.str()?.to_string_lossy()
+            )));
+        }
+        let raw: RawSocket = socket
+            .call_method0("dup")?
+            .call_method0("detach")?
+            .extract()?;
+        Ok(unsafe { S::from_raw(raw) })
+    }
+
+    /// Returns the socket's file descriptor (`SOCKET` handle on Windows), which stays owned by
+    /// the Python socket.
+    pub fn fileno(&self, py: Python) -> PyResult<RawSocket> {
+        self.0.call_method0(py, "fileno")?.extract(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for PySocket {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let socket_class = ob.py().import("socket")?.get("socket")?;
+        match unsafe { crate::ffi::PyObject_IsInstance(ob.as_ptr(), socket_class.as_ptr()) } {
+            1 => Ok(PySocket(ob.into())),
+            0 => Err(TypeError::py_err(format!(
+                "Expected a socket, got {}",
+                ob.get_type().name()
+            ))),
+            _ => Err(crate::PyErr::fetch(ob.py())),
+        }
+    }
+}
+
+impl ToPyObject for PySocket {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.0.clone_ref(py)
+    }
+}
+
+impl IntoPy<PyObject> for PySocket {
+    fn into_py(self, _py: Python) -> PyObject {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PySocket;
+    use crate::exceptions::TypeError;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::{Python, ToPyObject};
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream, UdpSocket};
+
+    #[test]
+    fn test_python_socket_to_rust() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = py.import("socket").unwrap();
+        let listener = module.call0("socket").unwrap();
+        listener.call_method1("bind", (("127.0.0.1", 0),)).unwrap();
+        listener.call_method0("listen").unwrap();
+        let listener: PySocket = listener.extract().unwrap();
+        let listener: TcpListener = listener.to_std(py).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let server = PySocket::from_std(py, server).unwrap();
+        assert!(server.to_std::<UdpSocket>(py).is_err());
+        let mut server_copy: TcpStream = server.to_std(py).unwrap();
+        // Both copies can be closed independently
+        server.to_object(py).call_method0(py, "close").unwrap();
+        server_copy.write_all(b"hello").unwrap();
+        let mut received = [0; 5];
+        client.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"hello");
+
+        let err = 1.to_object(py).extract::<PySocket>(py).err().unwrap();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_to_rust() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = py.import("socket").unwrap();
+        let family = module.get("AF_UNIX").unwrap();
+        let socket_type = module.get("SOCK_STREAM").unwrap();
+        let socket: PySocket = module
+            .call1("socket", (family, socket_type))
+            .unwrap()
+            .extract()
+            .unwrap();
+        let err = socket.to_std::<TcpStream>(py).err().unwrap();
+        assert!(err.is_instance::<TypeError>(py));
+        socket.to_object(py).call_method0(py, "close").unwrap();
+    }
+}
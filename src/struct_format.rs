@@ -0,0 +1,513 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Packing and unpacking binary data described by format strings of Python's `struct` module.
+//!
+//! [StructLayout] parses a format string like `"<HId"` in Rust, without the GIL, and packs or
+//! unpacks data exactly like `struct.pack` and `struct.unpack` do. Values are either handled as
+//! [StructValue]s, or converted from and to Rust tuples through their Python conversions, so
+//! extensions implementing binary protocols can share format strings with Python code.
+//!
+//! All format characters are supported except `e` (half precision floats).
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::struct_format::StructLayout;
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let layout = StructLayout::parse("<H4sd").unwrap();
+//! assert_eq!(layout.size(), 14);
+//!
+//! let data = layout.pack(py, (7, &b"name"[..], 0.5)).unwrap();
+//! let python_data: Vec<u8> = py
+//!     .import("struct").unwrap()
+//!     .call1("pack", ("<H4sd", 7, &b"name"[..], 0.5)).unwrap()
+//!     .extract().unwrap();
+//! assert_eq!(data, python_data);
+//!
+//! let (id, name, value): (u16, Vec<u8>, f64) = layout.unpack(py, &data).unwrap();
+//! assert_eq!((id, &name[..], value), (7, &b"name"[..], 0.5));
+//! ```
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::ValueError;
+use crate::instance::{AsPyRef, Py};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyBytes, PyTuple};
+use crate::{FromPyObject, IntoPy, PyObject, Python, ToPyObject};
+use std::fmt;
+use std::mem;
+use std::os::raw::{c_long, c_ulong};
+
+/// An error in a format string, or data or values that don't match it.
+///
+/// Converts into a `ValueError`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructFormatError(String);
+
+impl fmt::Display for StructFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for StructFormatError {}
+
+impl std::convert::From<StructFormatError> for PyErr {
+    fn from(err: StructFormatError) -> PyErr {
+        ValueError::py_err(err.0)
+    }
+}
+
+fn error<T>(message: impl Into<String>) -> Result<T, StructFormatError> {
+    Err(StructFormatError(message.into()))
+}
+
+/// A single value packed into or unpacked from binary data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StructValue {
+    /// `?`
+    Bool(bool),
+    /// `b`, `h`, `i`, `l`, `q` and `n`
+    Int(i64),
+    /// `B`, `H`, `I`, `L`, `Q`, `N` and `P`
+    UInt(u64),
+    /// `f` and `d`
+    Float(f64),
+    /// `c`, `s` and `p`
+    Bytes(Vec<u8>),
+}
+
+impl ToPyObject for StructValue {
+    fn to_object(&self, py: Python) -> PyObject {
+        match self {
+            StructValue::Bool(value) => value.to_object(py),
+            StructValue::Int(value) => value.to_object(py),
+            StructValue::UInt(value) => value.to_object(py),
+            StructValue::Float(value) => value.to_object(py),
+            StructValue::Bytes(value) => PyBytes::new(py, value).to_object(py),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Pad,
+    Bool,
+    Int,
+    UInt,
+    Float,
+    Char,
+    String,
+    PascalString,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Item {
+    kind: Kind,
+    offset: usize,
+    // The number of bytes, which is the repeat count for `s` and `p`
+    size: usize,
+}
+
+/// A parsed `struct` format string. See the [module documentation](index.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructLayout {
+    order: ByteOrder,
+    items: Vec<Item>,
+    size: usize,
+}
+
+impl StructLayout {
+    /// Parses `format`, following the rules of Python's `struct` module.
+    pub fn parse(format: &str) -> Result<StructLayout, StructFormatError> {
+        let mut chars = format.chars().peekable();
+        let (native, order) = match chars.peek() {
+            Some('<') => (false, ByteOrder::Little),
+            Some('>') | Some('!') => (false, ByteOrder::Big),
+            Some('=') => (false, native_order()),
+            _ => (true, native_order()),
+        };
+        if let Some('<') | Some('>') | Some('!') | Some('=') | Some('@') = chars.peek() {
+            chars.next();
+        }
+
+        let mut items = Vec::new();
+        let mut offset = 0;
+        while let Some(c) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let (count, code) = if let Some(digit) = c.to_digit(10) {
+                let mut count = digit as usize;
+                loop {
+                    match chars.next() {
+                        Some(c) if c.is_ascii_digit() => {
+                            count = count
+                                .checked_mul(10)
+                                .and_then(|n| n.checked_add(c.to_digit(10).unwrap() as usize))
+                                .ok_or_else(|| StructFormatError("Repeat count too large".into()))?
+                        }
+                        Some(c) => break (count, c),
+                        None => return error("Repeat count given without format specifier"),
+                    }
+                }
+            } else {
+                (1, c)
+            };
+
+            let (kind, size) = item_kind(code, native)?;
+            if native {
+                // Native mode aligns each item to its size, like a C compiler
+                offset = (offset + size - 1) / size * size;
+            }
+            match kind {
+                Kind::Pad => offset += count,
+                Kind::String | Kind::PascalString => {
+                    items.push(Item {
+                        kind,
+                        offset,
+                        size: count,
+                    });
+                    offset += count;
+                }
+                _ => {
+                    for _ in 0..count {
+                        items.push(Item { kind, offset, size });
+                        offset += size;
+                    }
+                }
+            }
+        }
+        Ok(StructLayout {
+            order,
+            items,
+            size: offset,
+        })
+    }
+
+    /// Returns the size of the packed data, like `struct.calcsize`.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the format describes no values.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Unpacks `data`, which must have exactly the size of the format.
+    pub fn unpack_values(&self, data: &[u8]) -> Result<Vec<StructValue>, StructFormatError> {
+        if data.len() != self.size {
+            return error(format!(
+                "Unpacking requires a buffer of {} bytes, got {}",
+                self.size,
+                data.len()
+            ));
+        }
+        let values = self
+            .items
+            .iter()
+            .map(|item| {
+                let bytes = &data[item.offset..item.offset + item.size];
+                match item.kind {
+                    Kind::Bool => StructValue::Bool(bytes[0] != 0),
+                    Kind::Int => {
+                        let shift = 64 - 8 * item.size;
+                        StructValue::Int((self.read_uint(bytes) << shift) as i64 >> shift)
+                    }
+                    Kind::UInt => StructValue::UInt(self.read_uint(bytes)),
+                    Kind::Float if item.size == 4 => {
+                        StructValue::Float(f32::from_bits(self.read_uint(bytes) as u32).into())
+                    }
+                    Kind::Float => StructValue::Float(f64::from_bits(self.read_uint(bytes))),
+                    Kind::Char => StructValue::Bytes(bytes[..1].to_vec()),
+                    Kind::String => StructValue::Bytes(bytes.to_vec()),
+                    Kind::PascalString => StructValue::Bytes(match bytes.split_first() {
+                        Some((&len, rest)) => rest[..(len as usize).min(rest.len())].to_vec(),
+                        None => Vec::new(),
+                    }),
+                    Kind::Pad => unreachable!(),
+                }
+            })
+            .collect();
+        Ok(values)
+    }
+
+    /// Packs `values`, which must match the format in number and type.
+    pub fn pack_values(&self, values: &[StructValue]) -> Result<Vec<u8>, StructFormatError> {
+        if values.len() != self.items.len() {
+            return error(format!(
+                "Packing requires exactly {} values, got {}",
+                self.items.len(),
+                values.len()
+            ));
+        }
+        let mut data = vec![0; self.size];
+        for (item, value) in self.items.iter().zip(values) {
+            let bytes = &mut data[item.offset..item.offset + item.size];
+            match (item.kind, value) {
+                (Kind::Bool, StructValue::Bool(value)) => bytes[0] = *value as u8,
+                (Kind::Int, StructValue::Int(value)) => {
+                    let bits = 8 * item.size as u32;
+                    if bits < 64 && (*value < -(1 << (bits - 1)) || *value >= 1 << (bits - 1)) {
+                        return error(format!("Value {} out of range", value));
+                    }
+                    self.write_uint(bytes, *value as u64);
+                }
+                (Kind::UInt, StructValue::UInt(value)) => {
+                    if item.size < 8 && *value >> (8 * item.size) != 0 {
+                        return error(format!("Value {} out of range", value));
+                    }
+                    self.write_uint(bytes, *value);
+                }
+                (Kind::Float, StructValue::Float(value)) if item.size == 4 => {
+                    self.write_uint(bytes, u64::from((*value as f32).to_bits()))
+                }
+                (Kind::Float, StructValue::Float(value)) => self.write_uint(bytes, value.to_bits()),
+                (Kind::Char, StructValue::Bytes(value)) if value.len() == 1 => bytes[0] = value[0],
+                (Kind::String, StructValue::Bytes(value)) => {
+                    let len = value.len().min(item.size);
+                    bytes[..len].copy_from_slice(&value[..len]);
+                }
+                (Kind::PascalString, StructValue::Bytes(value)) if item.size > 0 => {
+                    let len = value.len().min(item.size - 1).min(255);
+                    bytes[0] = len as u8;
+                    bytes[1..1 + len].copy_from_slice(&value[..len]);
+                }
+                (Kind::PascalString, StructValue::Bytes(_)) => {}
+                (_, value) => return error(format!("Can't pack {:?} as {:?}", value, item.kind)),
+            }
+        }
+        Ok(data)
+    }
+
+    /// Unpacks `data` into a Python tuple and extracts it as `T`, usually a Rust tuple.
+    pub fn unpack<'p, T>(&self, py: Python<'p>, data: &[u8]) -> PyResult<T>
+    where
+        T: FromPyObject<'p>,
+    {
+        let values = self.unpack_values(data)?;
+        PyTuple::new(py, values).extract()
+    }
+
+    /// Converts `values`, usually a Rust tuple, into a Python tuple and packs it.
+    pub fn pack<T>(&self, py: Python, values: T) -> PyResult<Vec<u8>>
+    where
+        T: IntoPy<Py<PyTuple>>,
+    {
+        let values = values.into_py(py);
+        let values = values.as_ref(py);
+        if values.len() != self.items.len() {
+            return Err(StructFormatError(format!(
+                "Packing requires exactly {} values, got {}",
+                self.items.len(),
+                values.len()
+            ))
+            .into());
+        }
+        let values = self
+            .items
+            .iter()
+            .zip(values.iter())
+            .map(|(item, value)| extract_value(item.kind, value))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(self.pack_values(&values)?)
+    }
+
+    fn read_uint(&self, bytes: &[u8]) -> u64 {
+        let fold = |value: u64, byte: &u8| value << 8 | u64::from(*byte);
+        match self.order {
+            ByteOrder::Little => bytes.iter().rev().fold(0, fold),
+            ByteOrder::Big => bytes.iter().fold(0, fold),
+        }
+    }
+
+    fn write_uint(&self, bytes: &mut [u8], value: u64) {
+        let size = bytes.len();
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let shift = match self.order {
+                ByteOrder::Little => 8 * i,
+                ByteOrder::Big => 8 * (size - 1 - i),
+            };
+            *byte = (value >> shift) as u8;
+        }
+    }
+}
+
+fn native_order() -> ByteOrder {
+    if cfg!(target_endian = "little") {
+        ByteOrder::Little
+    } else {
+        ByteOrder::Big
+    }
+}
+
+/// Returns the kind and size of a format character.
+fn item_kind(code: char, native: bool) -> Result<(Kind, usize), StructFormatError> {
+    let native_only = |size| {
+        if native {
+            Ok(size)
+        } else {
+            error(format!("Format character '{}' requires native mode", code))
+        }
+    };
+    let choose = |native_size: usize, standard_size: usize| {
+        if native {
+            native_size
+        } else {
+            standard_size
+        }
+    };
+    Ok(match code {
+        'x' => (Kind::Pad, 1),
+        'c' => (Kind::Char, 1),
+        's' => (Kind::String, 1),
+        'p' => (Kind::PascalString, 1),
+        '?' => (Kind::Bool, 1),
+        'b' => (Kind::Int, 1),
+        'B' => (Kind::UInt, 1),
+        'h' => (Kind::Int, 2),
+        'H' => (Kind::UInt, 2),
+        'i' => (Kind::Int, 4),
+        'I' => (Kind::UInt, 4),
+        'l' => (Kind::Int, choose(mem::size_of::<c_long>(), 4)),
+        'L' => (Kind::UInt, choose(mem::size_of::<c_ulong>(), 4)),
+        'q' => (Kind::Int, 8),
+        'Q' => (Kind::UInt, 8),
+        'n' => (Kind::Int, native_only(mem::size_of::<isize>())?),
+        'N' => (Kind::UInt, native_only(mem::size_of::<usize>())?),
+        'P' => (Kind::UInt, native_only(mem::size_of::<*const u8>())?),
+        'f' => (Kind::Float, 4),
+        'd' => (Kind::Float, 8),
+        _ => return error(format!("Bad format character '{}'", code)),
+    })
+}
+
+fn extract_value(kind: Kind, value: &PyAny) -> PyResult<StructValue> {
+    Ok(match kind {
+        Kind::Bool => StructValue::Bool(value.is_true()?),
+        Kind::Int => StructValue::Int(value.extract()?),
+        Kind::UInt => StructValue::UInt(value.extract()?),
+        Kind::Float => StructValue::Float(value.extract()?),
+        Kind::Char | Kind::String | Kind::PascalString => {
+            StructValue::Bytes(value.downcast_ref::<PyBytes>()?.as_bytes().to_vec())
+        }
+        Kind::Pad => unreachable!(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{StructLayout, StructValue};
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::PyTuple;
+    use crate::{Python, ToPyObject};
+
+    #[test]
+    fn test_matches_struct_module() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let struct_module = py.import("struct").unwrap();
+        let formats: &[(&str, &[StructValue])] = &[
+            (
+                "<bHiq",
+                &[
+                    StructValue::Int(-2),
+                    StructValue::UInt(65535),
+                    StructValue::Int(-70000),
+                    StructValue::Int(i64::min_value()),
+                ],
+            ),
+            (
+                ">?3xIf d",
+                &[
+                    StructValue::Bool(true),
+                    StructValue::UInt(0xdead_beef),
+                    StructValue::Float(1.5),
+                    StructValue::Float(-0.25),
+                ],
+            ),
+            (
+                "@cHxlN",
+                &[
+                    StructValue::Bytes(b"a".to_vec()),
+                    StructValue::UInt(3),
+                    StructValue::Int(-4),
+                    StructValue::UInt(5),
+                ],
+            ),
+            (
+                "=5s4p2h",
+                &[
+                    StructValue::Bytes(b"abc".to_vec()),
+                    StructValue::Bytes(b"long".to_vec()),
+                    StructValue::Int(1),
+                    StructValue::Int(2),
+                ],
+            ),
+        ];
+        for (format, values) in formats {
+            let layout = StructLayout::parse(format).unwrap();
+            let size: usize = struct_module
+                .call1("calcsize", (*format,))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(layout.size(), size, "{}", format);
+
+            let packed = layout.pack_values(values).unwrap();
+            let mut args = vec![format.to_object(py)];
+            args.extend(values.iter().map(|value| value.to_object(py)));
+            let expected: Vec<u8> = struct_module
+                .call1("pack", PyTuple::new(py, args))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(packed, expected, "{}", format);
+
+            let python_values: Vec<_> = layout
+                .unpack_values(&packed)
+                .unwrap()
+                .iter()
+                .map(|value| value.to_object(py))
+                .collect();
+            let expected = struct_module
+                .call1("unpack", (*format, &packed[..]))
+                .unwrap();
+            assert!(expected.rich_eq(PyTuple::new(py, python_values)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_tuples_and_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let layout = StructLayout::parse("!HB").unwrap();
+        assert_eq!(layout.len(), 2);
+        assert_eq!(layout.pack(py, (258, 3)).unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            layout.unpack::<(u16, u8)>(py, &[1, 2, 3]).unwrap(),
+            (258, 3)
+        );
+
+        assert!(layout.pack(py, (65536, 0)).is_err());
+        assert!(layout.pack(py, (1,)).is_err());
+        assert!(layout.unpack::<(u16, u8)>(py, &[1, 2]).is_err());
+        assert!(StructLayout::parse("<n").is_err());
+        assert!(StructLayout::parse("e").is_err());
+        assert!(StructLayout::parse("3").is_err());
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Generating `.pyi` stub files for extension modules.
+//!
+//! With the `stubs` feature, `#[pyfunction]`, `#[pyclass]` and `#[pymethods]` record the Python
+//! signatures of the functions, methods and properties they generate, with type hints derived
+//! from the Rust types through [PyStubType]. [module_stub] then renders the stub of a module
+//! from these records and the module's contents, so IDEs and type checkers like mypy can check
+//! code using the extension.
+//!
+//! The stub is generated at runtime from the initialized module, usually by a test or a small
+//! binary of the crate, e.g.
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn write_stub() {
+//!     let gil = Python::acquire_gil();
+//!     let py = gil.python();
+//!     let module = pyo3::wrap_pymodule!(my_module)(py);
+//!     let stub = pyo3::stubs::module_stub(module.cast_as(py).unwrap()).unwrap();
+//!     std::fs::write("my_module.pyi", stub).unwrap();
+//! }
+//! ```
+//!
+//! Rust types without a more specific Python type are hinted as `typing.Any`. Submodules get
+//! their own stub files, generated by calling [module_stub] on them.
+
+use crate::class::PyMethodType;
+use crate::err::PyResult;
+use crate::ffi;
+use crate::instance::{Py, PyNativeType, PyRef, PyRefMut};
+use crate::objectprotocol::ObjectProtocol;
+use crate::type_object::{instance_type, PyTypeInfo};
+use crate::types::{
+    PyAny, PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyFrozenSet, PyList, PyLong, PyModule,
+    PySet, PyString, PyTuple, PyType,
+};
+use crate::{AsPyPointer, PyObject, Python};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+
+/// The Python type hint of a Rust type, used in generated stubs.
+pub trait PyStubType {
+    /// Returns the type hint, e.g. `typing.List[int]`.
+    fn type_hint() -> String;
+}
+
+impl<T: ?Sized> PyStubType for T {
+    default fn type_hint() -> String {
+        "typing.Any".to_string()
+    }
+}
+
+macro_rules! stub_type {
+    ($hint:expr => $($ty:ty),+) => {
+        $(
+            impl PyStubType for $ty {
+                fn type_hint() -> String {
+                    $hint.to_string()
+                }
+            }
+        )+
+    };
+}
+
+stub_type!("bool" => bool, PyBool);
+stub_type!("int" => i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, PyLong);
+stub_type!("float" => f32, f64, PyFloat);
+stub_type!("str" => str, String, char, PyString);
+stub_type!("bytes" => PyBytes);
+stub_type!("bytearray" => PyByteArray);
+stub_type!("None" => ());
+stub_type!("typing.Any" => PyObject, PyAny);
+stub_type!("typing.List[typing.Any]" => PyList);
+stub_type!("typing.Dict[typing.Any, typing.Any]" => PyDict);
+stub_type!("typing.Tuple[typing.Any, ...]" => PyTuple);
+stub_type!("typing.Set[typing.Any]" => PySet);
+stub_type!("typing.FrozenSet[typing.Any]" => PyFrozenSet);
+stub_type!("type" => PyType);
+stub_type!("types.ModuleType" => PyModule);
+
+impl<'a, T: ?Sized + PyStubType> PyStubType for &'a T {
+    fn type_hint() -> String {
+        T::type_hint()
+    }
+}
+
+impl<'a, T: ?Sized + PyStubType> PyStubType for &'a mut T {
+    fn type_hint() -> String {
+        T::type_hint()
+    }
+}
+
+impl<T: PyStubType> PyStubType for Py<T> {
+    fn type_hint() -> String {
+        T::type_hint()
+    }
+}
+
+impl<'a, T: PyTypeInfo + PyStubType> PyStubType for PyRef<'a, T> {
+    fn type_hint() -> String {
+        T::type_hint()
+    }
+}
+
+impl<'a, T: PyTypeInfo + PyStubType> PyStubType for PyRefMut<'a, T> {
+    fn type_hint() -> String {
+        T::type_hint()
+    }
+}
+
+impl<T: PyStubType, E> PyStubType for Result<T, E> {
+    fn type_hint() -> String {
+        T::type_hint()
+    }
+}
+
+impl<T: PyStubType> PyStubType for Option<T> {
+    fn type_hint() -> String {
+        format!("typing.Optional[{}]", T::type_hint())
+    }
+}
+
+impl<T: PyStubType> PyStubType for Vec<T> {
+    fn type_hint() -> String {
+        format!("typing.List[{}]", T::type_hint())
+    }
+}
+
+impl<T: PyStubType> PyStubType for [T] {
+    fn type_hint() -> String {
+        format!("typing.List[{}]", T::type_hint())
+    }
+}
+
+impl<T: PyStubType, S> PyStubType for HashSet<T, S> {
+    fn type_hint() -> String {
+        format!("typing.Set[{}]", T::type_hint())
+    }
+}
+
+impl<T: PyStubType> PyStubType for BTreeSet<T> {
+    fn type_hint() -> String {
+        format!("typing.Set[{}]", T::type_hint())
+    }
+}
+
+impl<K: PyStubType, V: PyStubType, S> PyStubType for HashMap<K, V, S> {
+    fn type_hint() -> String {
+        format!("typing.Dict[{}, {}]", K::type_hint(), V::type_hint())
+    }
+}
+
+impl<K: PyStubType, V: PyStubType> PyStubType for BTreeMap<K, V> {
+    fn type_hint() -> String {
+        format!("typing.Dict[{}, {}]", K::type_hint(), V::type_hint())
+    }
+}
+
+macro_rules! tuple_stub_type {
+    ($($T:ident),+) => {
+        impl<$($T: PyStubType),+> PyStubType for ($($T,)+) {
+            fn type_hint() -> String {
+                let hints: &[String] = &[$($T::type_hint()),+];
+                format!("typing.Tuple[{}]", hints.join(", "))
+            }
+        }
+    };
+}
+
+tuple_stub_type!(A);
+tuple_stub_type!(A, B);
+tuple_stub_type!(A, B, C);
+tuple_stub_type!(A, B, C, D);
+tuple_stub_type!(A, B, C, D, E);
+tuple_stub_type!(A, B, C, D, E, F);
+
+/// The signature of a `#[pyfunction]`, recorded by the macro.
+pub struct FunctionStub {
+    /// The C functions implementing the Python function, which identify it
+    pub meth: PyMethodType,
+    /// Returns the `def` line of the stub
+    pub stub: fn() -> String,
+}
+
+inventory::collect!(FunctionStub);
+
+/// What a member of a class stub is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MemberKind {
+    ClassAttribute,
+    Method,
+    Getter,
+    Setter,
+}
+
+/// A method or property of a `#[pyclass]`, recorded by the macros.
+pub struct MemberStub {
+    pub name: &'static str,
+    pub kind: MemberKind,
+    /// Returns the stub of the member, with decorators on their own lines
+    pub stub: fn() -> String,
+}
+
+/// The members of a `#[pyclass]` declared by one `#[pymethods]` block or its fields.
+pub struct ClassStub {
+    /// Returns the type object of the class, see [type_object_ptr]
+    pub type_object: fn() -> *mut ffi::PyTypeObject,
+    pub members: &'static [MemberStub],
+}
+
+inventory::collect!(ClassStub);
+
+/// Returns the type object Python sees for `T` without initializing it, so that rendering a stub
+/// doesn't initialize the classes of other modules.
+#[doc(hidden)]
+pub fn type_object_ptr<T: PyTypeInfo>() -> *mut ffi::PyTypeObject {
+    unsafe { instance_type::<T>() }
+}
+
+/// Renders the `.pyi` stub of `module`.
+///
+/// Functions and classes defined with PyO3 macros are rendered with their signatures, enums
+/// with their members and other values as variables of their type. Submodules and objects
+/// whose signature isn't known are left out.
+pub fn module_stub(module: &PyModule) -> PyResult<String> {
+    let py = module.py();
+    let mut stub = String::new();
+    writeln!(
+        stub,
+        "# Stub of the `{}` module generated by PyO3",
+        module.name()?
+    )
+    .unwrap();
+    stub.push_str("import enum\nimport types\nimport typing\n");
+
+    for (name, value) in module.dict().iter() {
+        let name: &str = name.extract()?;
+        if name.starts_with("__") {
+            continue;
+        }
+        if let Ok(ty) = value.downcast_ref::<PyType>() {
+            stub.push('\n');
+            stub.push_str(&class_stub(py, name, ty)?);
+        } else if let Some(function) = function_stub(value) {
+            stub.push('\n');
+            stub.push_str(&function);
+            stub.push('\n');
+        } else if value.downcast_ref::<PyModule>().is_err() && !value.is_callable() {
+            let ty = value.get_type();
+            let hint = match ty.getattr("__module__")?.extract::<&str>()? {
+                "builtins" => ty.name().into_owned(),
+                module => format!("{}.{}", module, ty.name()),
+            };
+            writeln!(stub, "\n{}: {}", name, hint).unwrap();
+        }
+    }
+    Ok(stub)
+}
+
+fn function_stub(value: &PyAny) -> Option<String> {
+    if unsafe { ffi::PyCFunction_Check(value.as_ptr()) } == 0 {
+        return None;
+    }
+    let meth = unsafe { ffi::PyCFunction_GetFunction(value.as_ptr()) }? as usize;
+    inventory::iter::<FunctionStub>
+        .into_iter()
+        .find(|function| function.meth.c_function(0).0 as usize == meth)
+        .map(|function| (function.stub)())
+}
+
+fn class_stub(py: Python, name: &str, ty: &PyType) -> PyResult<String> {
+    let enum_type: &PyType = py.import("enum")?.get("Enum")?.downcast_ref()?;
+    let bases: Vec<String> = ty
+        .getattr("__bases__")?
+        .iter()?
+        .map(|base| {
+            let base: &PyType = base?.downcast_ref()?;
+            Ok(match base.getattr("__module__")?.extract::<&str>()? {
+                "builtins" => base.name().into_owned(),
+                module => format!("{}.{}", module, base.name()),
+            })
+        })
+        .collect::<PyResult<_>>()?;
+    let mut stub = if bases == ["object"] {
+        format!("class {}:\n", name)
+    } else {
+        format!("class {}({}):\n", name, bases.join(", "))
+    };
+    let body_start = stub.len();
+
+    if unsafe { ffi::PyObject_IsSubclass(ty.as_ptr(), enum_type.as_ptr()) } == 1 {
+        for member in ty.iter()? {
+            let member = member?;
+            writeln!(
+                stub,
+                "    {} = {}",
+                member.getattr("name")?,
+                member.getattr("value")?.repr()?
+            )
+            .unwrap();
+        }
+    } else {
+        let mut members: Vec<&MemberStub> = inventory::iter::<ClassStub>
+            .into_iter()
+            .filter(|class| (class.type_object)() as *mut ffi::PyObject == ty.as_ptr())
+            .flat_map(|class| class.members.iter())
+            .collect();
+        // A property's setter refers to its getter, which has to come first
+        members.sort_by_key(|member| match member.kind {
+            MemberKind::ClassAttribute => 0,
+            MemberKind::Getter => 1,
+            MemberKind::Setter => 2,
+            MemberKind::Method => 3,
+        });
+        for member in members {
+            for line in (member.stub)().lines() {
+                writeln!(stub, "    {}", line).unwrap();
+            }
+        }
+    }
+
+    if stub.len() == body_start {
+        stub.push_str("    ...\n");
+    }
+    Ok(stub)
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Synchronization helpers that stay correct on free-threaded Python builds.
+//!
+//! On regular CPython builds, holding the GIL guarantees exclusive access to Python objects.
+//! Free-threaded builds (PEP 703, `--disable-gil`) drop that guarantee and instead offer
+//! per-object locks called critical sections. The functions in this module use these locks when
+//! compiled against a free-threaded interpreter, and fall back to relying on the GIL otherwise,
+//! so code written against them is correct for both kinds of interpreters.
+//!
+//! Building against a free-threaded interpreter isn't supported yet and fails with an error, so
+//! for now these helpers always rely on the GIL.
+
+use crate::instance::PyNativeType;
+use crate::types::PyAny;
+use crate::AsPyPointer;
+use crate::Python;
+#[cfg(py_sys_config = "Py_GIL_DISABLED")]
+use crate::{ffi, ObjectProtocol};
+
+/// Whether pyo3 was compiled against a free-threaded (`--disable-gil`) interpreter.
+pub const GIL_DISABLED_BUILD: bool = cfg!(py_sys_config = "Py_GIL_DISABLED");
+
+/// Returns whether the running interpreter uses the GIL.
+///
+/// Free-threaded builds may still re-enable the GIL at runtime, e.g. when an extension module
+/// that does not declare free-threading support is imported.
+pub fn is_gil_enabled(py: Python) -> bool {
+    #[cfg(py_sys_config = "Py_GIL_DISABLED")]
+    {
+        py.import("sys")
+            .and_then(|sys| sys.call0("_is_gil_enabled"))
+            .and_then(|enabled| enabled.is_true())
+            .unwrap_or(true)
+    }
+    #[cfg(not(py_sys_config = "Py_GIL_DISABLED"))]
+    {
+        let _ = py;
+        true
+    }
+}
+
+/// Runs `f` while holding the per-object lock of `object`.
+///
+/// On builds with a GIL, this simply calls `f`, as the GIL already serializes access.
+/// The lock may be temporarily released if `f` blocks, just like the GIL; it only protects
+/// against concurrent modification of `object` by other critical sections.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::sync::with_critical_section;
+/// use pyo3::types::PyList;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let list = PyList::new(py, &[1, 2, 3]);
+/// let first = with_critical_section(list.as_ref(), || list.get_item(0).extract::<i32>());
+/// assert_eq!(first.unwrap(), 1);
+/// ```
+pub fn with_critical_section<F, R>(object: &PyAny, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    #[cfg(py_sys_config = "Py_GIL_DISABLED")]
+    {
+        struct Guard(ffi::PyCriticalSection);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                unsafe { ffi::PyCriticalSection_End(&mut self.0) }
+            }
+        }
+
+        let mut guard: Guard = unsafe { std::mem::zeroed() };
+        unsafe { ffi::PyCriticalSection_Begin(&mut guard.0, object.as_ptr()) };
+        f()
+    }
+    #[cfg(not(py_sys_config = "Py_GIL_DISABLED"))]
+    {
+        let _ = object.py();
+        f()
+    }
+}
+
+/// Runs `f` while holding the per-object locks of both `a` and `b`.
+///
+/// Locking both objects at once avoids the deadlocks that nesting two
+/// [with_critical_section] calls could cause.
+pub fn with_critical_section2<F, R>(a: &PyAny, b: &PyAny, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    #[cfg(py_sys_config = "Py_GIL_DISABLED")]
+    {
+        struct Guard(ffi::PyCriticalSection2);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                unsafe { ffi::PyCriticalSection2_End(&mut self.0) }
+            }
+        }
+
+        let mut guard: Guard = unsafe { std::mem::zeroed() };
+        unsafe { ffi::PyCriticalSection2_Begin(&mut guard.0, a.as_ptr(), b.as_ptr()) };
+        f()
+    }
+    #[cfg(not(py_sys_config = "Py_GIL_DISABLED"))]
+    {
+        let _ = (a.as_ptr(), b.as_ptr());
+        f()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::PyList;
+
+    #[test]
+    fn test_critical_sections() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if !GIL_DISABLED_BUILD {
+            assert!(is_gil_enabled(py));
+        }
+        let a = PyList::new(py, &[1, 2]);
+        let b = PyList::empty(py);
+        with_critical_section(a.as_ref(), || a.append(3).unwrap());
+        with_critical_section2(a.as_ref(), b.as_ref(), || {
+            for item in a.iter() {
+                b.append(item).unwrap();
+            }
+        });
+        assert_eq!(b.len(), 3);
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Storage of Rust values per Python thread.
+//!
+//! A [PyThreadLocal] keeps a separate value for each Python thread state, in the dictionary
+//! returned by `PyThreadState_GetDict`. Unlike `std::thread_local!`, the value is dropped when
+//! the Python thread state is destroyed rather than when the OS thread exits, which makes it
+//! suitable for per-request caches of extensions running in thread pools managed by Python.
+//!
+//! # Example
+//! ```
+//! use pyo3::prelude::*;
+//! use pyo3::thread_local::PyThreadLocal;
+//! use std::cell::Cell;
+//!
+//! static REQUESTS: PyThreadLocal<Cell<u32>> = PyThreadLocal::new();
+//!
+//! fn count_request(py: Python) -> PyResult<u32> {
+//!     let requests = REQUESTS.get_or_init(py, || Cell::new(0))?;
+//!     requests.set(requests.get() + 1);
+//!     Ok(requests.get())
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! assert_eq!(count_request(py).unwrap(), 1);
+//! assert_eq!(count_request(py).unwrap(), 2);
+//! ```
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::RuntimeError;
+use crate::ffi;
+use crate::types::PyAny;
+use crate::{AsPyPointer, Python};
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+const CAPSULE_NAME: &[u8] = b"pyo3.PyThreadLocal\0";
+
+/// A value for each Python thread, which is dropped with the thread state.
+///
+/// `PyThreadLocal` is meant to be stored in a `static`. References returned by [get] and
+/// [get_or_init] stay valid until the current `GILPool` is dropped, even if the value is
+/// removed or replaced in the meantime. Values are only accessed from their own thread, but
+/// Python may destroy a thread state from another thread, so `T` must be `Send`.
+///
+/// [get]: PyThreadLocal::get
+/// [get_or_init]: PyThreadLocal::get_or_init
+pub struct PyThreadLocal<T> {
+    // The key in the thread state dictionary, created on first use
+    key: AtomicPtr<ffi::PyObject>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + 'static> PyThreadLocal<T> {
+    /// Creates a `PyThreadLocal` without values.
+    pub const fn new() -> Self {
+        PyThreadLocal {
+            key: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the value of the current thread, if any.
+    pub fn get<'p>(&'static self, py: Python<'p>) -> Option<&'p T> {
+        unsafe {
+            let dict = ffi::PyThreadState_GetDict();
+            if dict.is_null() {
+                return None;
+            }
+            let capsule = ffi::PyDict_GetItem(dict, self.key());
+            if capsule.is_null() {
+                return None;
+            }
+            // Keep the value alive for the lifetime of the pool
+            ffi::Py_INCREF(capsule);
+            let capsule: &PyAny = py.from_owned_ptr(capsule);
+            let value =
+                ffi::PyCapsule_GetPointer(capsule.as_ptr(), CAPSULE_NAME.as_ptr() as *const _);
+            Some(&*(value as *const T))
+        }
+    }
+
+    /// Returns the value of the current thread, creating it with `init` if there is none.
+    pub fn get_or_init<'p, F>(&'static self, py: Python<'p>, init: F) -> PyResult<&'p T>
+    where
+        F: FnOnce() -> T,
+    {
+        if let Some(value) = self.get(py) {
+            return Ok(value);
+        }
+        self.set(py, init())?;
+        Ok(self.get(py).expect("the value was just set"))
+    }
+
+    /// Sets the value of the current thread, dropping the previous one.
+    pub fn set(&'static self, py: Python, value: T) -> PyResult<()> {
+        unsafe {
+            let dict = thread_state_dict(py)?;
+            let capsule: &PyAny = py.from_owned_ptr_or_err(ffi::PyCapsule_New(
+                Box::into_raw(Box::new(value)) as *mut c_void,
+                CAPSULE_NAME.as_ptr() as *const _,
+                Some(destroy_value::<T>),
+            ))?;
+            if ffi::PyDict_SetItem(dict, self.key(), capsule.as_ptr()) == -1 {
+                return Err(PyErr::fetch(py));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the value of the current thread.
+    pub fn remove(&'static self, py: Python) -> PyResult<()> {
+        unsafe {
+            let dict = thread_state_dict(py)?;
+            if ffi::PyDict_Contains(dict, self.key()) == 1
+                && ffi::PyDict_DelItem(dict, self.key()) == -1
+            {
+                return Err(PyErr::fetch(py));
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn key(&'static self) -> *mut ffi::PyObject {
+        let key = self.key.load(Ordering::Relaxed);
+        if !key.is_null() {
+            return key;
+        }
+        // The address of the static identifies it across all extension modules
+        let name = format!("pyo3.PyThreadLocal.{:p}\0", self as *const Self);
+        let key = ffi::PyUnicode_InternFromString(name.as_ptr() as *const _);
+        self.key.store(key, Ordering::Relaxed);
+        key
+    }
+}
+
+unsafe fn thread_state_dict(_py: Python) -> PyResult<*mut ffi::PyObject> {
+    let dict = ffi::PyThreadState_GetDict();
+    if dict.is_null() {
+        Err(PyErr::new::<RuntimeError, _>(
+            "The current thread has no Python thread state",
+        ))
+    } else {
+        Ok(dict)
+    }
+}
+
+unsafe extern "C" fn destroy_value<T>(capsule: *mut ffi::PyObject) {
+    let value = ffi::PyCapsule_GetPointer(capsule, CAPSULE_NAME.as_ptr() as *const _);
+    drop(Box::from_raw(value as *mut T));
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyThreadLocal;
+    use crate::Python;
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    static COUNTER: PyThreadLocal<Cell<u32>> = PyThreadLocal::new();
+
+    #[test]
+    fn test_values_per_thread() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        COUNTER.remove(py).unwrap();
+        assert!(COUNTER.get(py).is_none());
+        COUNTER.get_or_init(py, || Cell::new(1)).unwrap();
+        assert_eq!(COUNTER.get_or_init(py, || Cell::new(2)).unwrap().get(), 1);
+
+        let other = py.allow_threads(|| {
+            thread::spawn(|| {
+                let gil = Python::acquire_gil();
+                let py = gil.python();
+                assert!(COUNTER.get(py).is_none());
+                COUNTER.get_or_init(py, || Cell::new(10)).unwrap().get()
+            })
+            .join()
+            .unwrap()
+        });
+        assert_eq!(other, 10);
+        assert_eq!(COUNTER.get(py).unwrap().get(), 1);
+
+        // References stay valid after the value was removed
+        let counter = COUNTER.get(py).unwrap();
+        COUNTER.remove(py).unwrap();
+        assert!(COUNTER.get(py).is_none());
+        assert_eq!(counter.get(), 1);
+    }
+
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    static FLAG: PyThreadLocal<DropFlag> = PyThreadLocal::new();
+
+    #[test]
+    fn test_dropped_with_thread_state() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dropped = Arc::new(AtomicBool::new(false));
+        let flag = dropped.clone();
+        py.allow_threads(|| {
+            thread::spawn(move || {
+                let gil = Python::acquire_gil();
+                let py = gil.python();
+                FLAG.set(py, DropFlag(flag)).unwrap();
+            })
+            .join()
+            .unwrap()
+        });
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Reporting the memory of `#[pyclass]` instances to `tracemalloc`.
+//!
+//! `tracemalloc` already traces the memory of the Python object containing a `#[pyclass]`
+//! value, but not the heap memory owned by the value, such as the contents of a `Vec`. Classes
+//! implementing [PyHeapSize] report that memory in the [DOMAIN] trace domain when an instance is
+//! created, attributed to the Python traceback that created it, so that snapshots show which
+//! Python code keeps large Rust values alive. Nothing is reported while `tracemalloc` is not
+//! tracing, and [update_heap_size] reports the new size of a value that grew or shrank.
+//!
+//! Reporting requires Python 3.7 and is not supported by PyPy or the limited API.
+//!
+//! # Example
+//! ```
+//! # #![feature(specialization)]
+//! use pyo3::prelude::*;
+//! use pyo3::tracemalloc::PyHeapSize;
+//!
+//! #[pyclass]
+//! struct Buffer {
+//!     data: Vec<u8>,
+//! }
+//!
+//! impl PyHeapSize for Buffer {
+//!     fn heap_size(&self) -> usize {
+//!         self.data.capacity()
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let tracemalloc = py.import("tracemalloc").unwrap();
+//! tracemalloc.call0("start").unwrap();
+//! let buffer = Py::new(py, Buffer { data: vec![0; 1 << 20] }).unwrap();
+//! pyo3::py_run!(py, buffer, r#"
+//! import tracemalloc
+//! traces = tracemalloc.take_snapshot().filter_traces([tracemalloc.DomainFilter(True, 0x70796f33)])
+//! assert sum(stat.size for stat in traces.statistics("lineno")) == 1 << 20
+//! "#);
+//! tracemalloc.call0("stop").unwrap();
+//! ```
+
+use crate::ffi;
+use crate::type_object::PyTypeInfo;
+use crate::Python;
+use std::os::raw::c_uint;
+
+/// The `tracemalloc` domain of the memory reported for [PyHeapSize] classes, `"pyo3"` in ASCII.
+pub const DOMAIN: c_uint = 0x7079_6f33;
+
+/// A `#[pyclass]` reporting the heap memory it owns to `tracemalloc`.
+pub trait PyHeapSize: PyTypeInfo {
+    /// Returns the size of the heap memory owned by `self`, excluding `size_of::<Self>()`.
+    ///
+    /// This is called when instances are created, so it should be cheap.
+    fn heap_size(&self) -> usize;
+}
+
+/// Reports the current heap size of `value`, after it grew or shrank.
+pub fn update_heap_size<T: PyHeapSize>(_py: Python, value: &T) {
+    unsafe { track(object_ptr(value), value.heap_size()) }
+}
+
+#[doc(hidden)]
+pub trait PyHeapSizeImpl {
+    unsafe fn track_value(&self);
+    unsafe fn untrack_value(&self);
+}
+
+impl<T> PyHeapSizeImpl for T {
+    default unsafe fn track_value(&self) {}
+    default unsafe fn untrack_value(&self) {}
+}
+
+impl<T> PyHeapSizeImpl for T
+where
+    T: PyHeapSize,
+{
+    unsafe fn track_value(&self) {
+        track(object_ptr(self), self.heap_size())
+    }
+
+    unsafe fn untrack_value(&self) {
+        untrack(object_ptr(self))
+    }
+}
+
+fn object_ptr<T: PyTypeInfo>(value: &T) -> *mut ffi::PyObject {
+    unsafe { (value as *const T as *const u8).offset(-T::OFFSET) as *mut ffi::PyObject }
+}
+
+// The return values are ignored, as failing to report memory must not break the class
+#[cfg(all(Py_3_7, not(any(PyPy, Py_LIMITED_API))))]
+unsafe fn track(obj: *mut ffi::PyObject, size: usize) {
+    ffi::PyTraceMalloc_Track(DOMAIN, obj as libc::uintptr_t, size);
+}
+
+#[cfg(all(Py_3_7, not(any(PyPy, Py_LIMITED_API))))]
+unsafe fn untrack(obj: *mut ffi::PyObject) {
+    ffi::PyTraceMalloc_Untrack(DOMAIN, obj as libc::uintptr_t);
+}
+
+#[cfg(not(all(Py_3_7, not(any(PyPy, Py_LIMITED_API)))))]
+unsafe fn track(_obj: *mut ffi::PyObject, _size: usize) {}
+
+#[cfg(not(all(Py_3_7, not(any(PyPy, Py_LIMITED_API)))))]
+unsafe fn untrack(_obj: *mut ffi::PyObject) {}
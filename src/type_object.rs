@@ -2,15 +2,18 @@
 
 //! Python type object information
 
-use crate::class::methods::PyMethodDefType;
+use crate::class::methods::{PyMethodDef, PyMethodDefType, PyMethodType};
 use crate::err::{PyErr, PyResult};
+use crate::exceptions::TypeError;
 use crate::instance::{Py, PyNativeType};
-use crate::types::PyAny;
-use crate::types::PyType;
+use crate::objectprotocol::ObjectProtocol;
+use crate::tracemalloc::PyHeapSizeImpl;
+use crate::types::{PyAny, PyDict, PyFrozenSet, PyTuple, PyType};
 use crate::AsPyPointer;
 use crate::IntoPyPointer;
 use crate::Python;
-use crate::{class, ffi, gil};
+use crate::ToPyObject;
+use crate::{class, ffi, gil, signature};
 use class::methods::PyMethodsProtocol;
 use std::collections::HashMap;
 use std::ffi::CString;
@@ -40,6 +43,24 @@ pub trait PyTypeInfo {
     /// Type flags (ie PY_TYPE_FLAG_GC, PY_TYPE_FLAG_WEAKREF)
     const FLAGS: usize = 0;
 
+    /// Abstract base classes the type is registered with as a virtual subclass once
+    /// it is initialized, see [PyType::register_abc]
+    const ABCS: &'static [&'static str] = &[];
+
+    /// `typing.Protocol`s the type declares to implement, checked when it is added to a
+    /// module, see [PyType::check_protocol]
+    const PROTOCOLS: &'static [&'static str] = &[];
+
+    /// Returns the metaclass of the type if it is not `type`, see `#[pyclass(metaclass = ...)]`
+    const METACLASS: Option<for<'p> fn(Python<'p>) -> PyResult<&'p PyType>> = None;
+
+    /// Returns the Python class the type extends, see `#[pyclass(extends = "module.Name")]`.
+    ///
+    /// Static types can't extend Python classes, so the type extends `object` (`BaseType` is
+    /// `PyAny`) and [python_class](#method.python_class) is the class Python sees, which
+    /// extends both.
+    const PYTHON_BASE: Option<for<'p> fn(Python<'p>) -> PyResult<&'p PyType>> = None;
+
     /// Base class
     type BaseType: PyTypeInfo;
 
@@ -47,6 +68,13 @@ pub trait PyTypeInfo {
     /// be initialized
     unsafe fn type_object() -> &'static mut ffi::PyTypeObject;
 
+    /// For types extending a Python class, the Python class created when the type is
+    /// initialized, which derives from the type and from [PYTHON_BASE](#associatedconstant.PYTHON_BASE).
+    /// Its instances are created for the type, and `Python::get_type` returns it.
+    unsafe fn python_class() -> Option<&'static mut *mut ffi::PyTypeObject> {
+        None
+    }
+
     /// Check if `*mut ffi::PyObject` is instance of this type
     fn is_instance(object: &PyAny) -> bool {
         unsafe { ffi::PyObject_TypeCheck(object.as_ptr(), Self::type_object()) != 0 }
@@ -54,7 +82,16 @@ pub trait PyTypeInfo {
 
     /// Check if `*mut ffi::PyObject` is exact instance of this type
     fn is_exact_instance(object: &PyAny) -> bool {
-        unsafe { (*object.as_ptr()).ob_type == Self::type_object() }
+        unsafe { (*object.as_ptr()).ob_type == instance_type::<Self>() }
+    }
+}
+
+/// Returns the type of the instances created for `T`, which is [PyTypeInfo::python_class] if
+/// the type extends a Python class.
+pub(crate) unsafe fn instance_type<T: PyTypeInfo + ?Sized>() -> *mut ffi::PyTypeObject {
+    match T::python_class() {
+        Some(python_class) if !python_class.is_null() => *python_class,
+        _ => T::type_object(),
     }
 }
 
@@ -70,6 +107,15 @@ pub const PY_TYPE_FLAG_BASETYPE: usize = 1 << 2;
 /// The instances of this type have a dictionary containing instance variables
 pub const PY_TYPE_FLAG_DICT: usize = 1 << 3;
 
+/// Type object is an abstract base class which can't be instantiated from Python
+pub const PY_TYPE_FLAG_ABSTRACT: usize = 1 << 4;
+
+/// Type object is marked as final, i.e. it can't be subclassed
+pub const PY_TYPE_FLAG_FINAL: usize = 1 << 5;
+
+/// Type object has a single instance, see [crate::singleton::PySingleton]
+pub const PY_TYPE_FLAG_SINGLETON: usize = 1 << 6;
+
 /// Special object that is used for python object creation.
 /// `pyo3` library automatically creates this object for class `__new__` method.
 /// Behavior is undefined if constructor of custom class does not initialze
@@ -109,6 +155,9 @@ impl PyRawObject {
         tp_ptr: *mut ffi::PyTypeObject,
         curr_ptr: *mut ffi::PyTypeObject,
     ) -> PyResult<PyRawObject> {
+        if (*curr_ptr).tp_flags & ffi::Py_TPFLAGS_IS_ABSTRACT != 0 {
+            return Err(abstract_class_error(PyType::from_type_ptr(py, curr_ptr)));
+        }
         let alloc = (*curr_ptr).tp_alloc.unwrap_or(ffi::PyType_GenericAlloc);
         let ptr = alloc(curr_ptr, 0);
 
@@ -148,6 +197,7 @@ impl PyRawObject {
             // The `as *mut u8` part is required because the offset is in bytes
             let ptr = (self.ptr as *mut u8).offset(T::OFFSET) as *mut T;
             std::ptr::write(ptr, value);
+            <T as PyHeapSizeImpl>::track_value(&*ptr);
         }
     }
 
@@ -157,6 +207,26 @@ impl PyRawObject {
     }
 }
 
+/// The error Python raises when instantiating a class with abstract methods.
+fn abstract_class_error(ty: &PyType) -> PyErr {
+    let abstract_methods = || -> PyResult<Vec<String>> {
+        let mut names = Vec::new();
+        for name in ty.getattr("__abstractmethods__")?.iter()? {
+            names.push(name?.extract()?);
+        }
+        names.sort();
+        Ok(names)
+    };
+    match abstract_methods() {
+        Ok(ref names) if !names.is_empty() => PyErr::new::<TypeError, _>(format!(
+            "Can't instantiate abstract class {} with abstract methods {}",
+            ty.name(),
+            names.join(", ")
+        )),
+        _ => PyErr::new::<TypeError, _>(format!("Can't instantiate abstract class {}", ty.name())),
+    }
+}
+
 impl<T: PyTypeInfo> AsRef<T> for PyRawObject {
     #[inline]
     fn as_ref(&self) -> &T {
@@ -180,6 +250,7 @@ unsafe impl PyNativeType for PyRawObject {}
 pub(crate) unsafe fn pytype_drop<T: PyTypeInfo>(py: Python, obj: *mut ffi::PyObject) {
     if T::OFFSET != 0 {
         let ptr = (obj as *mut u8).offset(T::OFFSET) as *mut T;
+        <T as PyHeapSizeImpl>::untrack_value(&*ptr);
         std::ptr::drop_in_place(ptr);
         pytype_drop::<T::BaseType>(py, obj);
     }
@@ -191,7 +262,7 @@ pub(crate) unsafe fn pytype_drop<T: PyTypeInfo>(py: Python, obj: *mut ffi::PyObj
 /// [PyObjectWithFreeList](crate::freelist::PyObjectWithFreeList) gets a special version.
 pub trait PyObjectAlloc: PyTypeInfo + Sized {
     unsafe fn alloc(_py: Python) -> *mut ffi::PyObject {
-        let tp_ptr = Self::type_object();
+        let tp_ptr = instance_type::<Self>();
         let alloc = (*tp_ptr).tp_alloc.unwrap_or(ffi::PyType_GenericAlloc);
         alloc(tp_ptr, 0)
     }
@@ -206,7 +277,12 @@ pub trait PyObjectAlloc: PyTypeInfo + Sized {
             return;
         }
 
-        match Self::type_object().tp_free {
+        // Instances of subclasses of the Python class may be allocated differently
+        let free = match Self::python_class() {
+            Some(_) => (*ffi::Py_TYPE(obj)).tp_free,
+            None => Self::type_object().tp_free,
+        };
+        match free {
             Some(free) => free(obj as *mut c_void),
             None => {
                 let ty = ffi::Py_TYPE(obj);
@@ -256,16 +332,15 @@ where
 
         if (type_object.tp_flags & ffi::Py_TPFLAGS_READY) == 0 {
             // automatically initialize the class on-demand
-            let gil = Python::acquire_gil();
-            let py = gil.python();
-
-            initialize_type::<Self>(py, <Self as PyTypeInfo>::MODULE).unwrap_or_else(|e| {
-                e.print(py);
-                panic!("An error occurred while initializing class {}", Self::NAME)
+            Python::with_gil(|py| {
+                initialize_type::<Self>(py, <Self as PyTypeInfo>::MODULE).unwrap_or_else(|e| {
+                    e.print(py);
+                    panic!("An error occurred while initializing class {}", Self::NAME)
+                });
             });
         }
 
-        unsafe { NonNull::new_unchecked(type_object) }
+        unsafe { NonNull::new_unchecked(instance_type::<Self>()) }
     }
 }
 
@@ -285,7 +360,7 @@ pub trait PyTypeCreate: PyObjectAlloc + PyTypeObject + Sized {
                 py,
                 ptr,
                 <Self as PyTypeInfo>::type_object(),
-                <Self as PyTypeInfo>::type_object(),
+                instance_type::<Self>(),
             )
         }
     }
@@ -303,16 +378,16 @@ where
     let base_type_object: &mut ffi::PyTypeObject =
         unsafe { <T::BaseType as PyTypeInfo>::type_object() };
 
-    // PyPy will segfault if passed only a nul terminator as `tp_doc`.
-    // ptr::null() is OK though.
-    if T::DESCRIPTION == "\0" {
-        type_object.tp_doc = ptr::null();
-    } else {
-        type_object.tp_doc = T::DESCRIPTION.as_ptr() as *const _;
-    };
+    type_object.tp_doc = class_doc::<T>();
 
     type_object.tp_base = base_type_object;
 
+    // metaclass, which `PyType_Ready` would otherwise inherit from the base
+    let metaclass = match T::METACLASS {
+        Some(metaclass) => Some(set_metaclass(type_object, metaclass(py)?)?),
+        None => None,
+    };
+
     let name = match module_name {
         Some(module_name) => format!("{}.{}", module_name, T::NAME),
         None => T::NAME.to_string(),
@@ -370,14 +445,28 @@ where
     type_object.tp_as_async = to_ptr(<T as class::pyasync::PyAsyncProtocolImpl>::tp_as_async());
     // buffer protocol
     type_object.tp_as_buffer = to_ptr(<T as class::buffer::PyBufferProtocolImpl>::tp_as_buffer());
+    // operators implemented with a single method
+    <T as class::operators::PyOperatorsImpl>::tp_as_operators(type_object);
+    // operators mapped onto `std::ops` implementations
+    <T as class::ops::PyClassOpsImpl>::tp_as_ops(type_object);
+    // comparison and hashing derived from Rust traits
+    <T as class::compare::PyCompareImpl>::tp_as_compare(type_object);
+    <T as class::compare::PyHashImpl>::tp_as_hash(type_object);
+    // `repr()` and `str()` derived from Rust traits or the fields
+    <T as class::repr::PyReprImpl>::tp_as_repr(type_object);
+    <T as class::repr::PyFieldsReprImpl>::tp_as_fields_repr(type_object);
+    <T as class::repr::PyStrImpl>::tp_as_str(type_object);
 
     // normal methods
-    let (new, call, mut methods) = py_class_method_defs::<T>();
+    let (new, call, mut methods, marked) = py_class_method_defs::<T>();
     if !methods.is_empty() {
         methods.push(ffi::PyMethodDef_INIT);
         type_object.tp_methods = Box::into_raw(methods.into_boxed_slice()) as *mut _;
     }
 
+    // `__getattr__`, `__setattr__` and `__delattr__` of `#[pymethods]`
+    class::basic::tp_as_attribute_methods::<T>(type_object, &marked.attribute_methods);
+
     // __new__ method
     type_object.tp_new = new;
     // __call__ method
@@ -396,15 +485,347 @@ where
 
     // set type flags
     py_class_flags::<T>(type_object);
+    if T::PYTHON_BASE.is_some() {
+        type_object.tp_flags |= ffi::Py_TPFLAGS_BASETYPE;
+    }
 
     // register type object
     unsafe {
-        if ffi::PyType_Ready(type_object) == 0 {
-            Ok(type_object as *mut ffi::PyTypeObject)
-        } else {
-            PyErr::fetch(py).into()
+        if ffi::PyType_Ready(type_object) != 0 {
+            return PyErr::fetch(py).into();
+        }
+    }
+
+    let is_abc = match metaclass {
+        Some(metaclass) => init_abc_metaclass(py, type_object, metaclass)?,
+        None => false,
+    };
+
+    if T::FLAGS & PY_TYPE_FLAG_ABSTRACT != 0 || !marked.abstract_methods.is_empty() {
+        if !is_abc {
+            return Err(PyErr::new::<TypeError, _>(format!(
+                "{} has abstract methods, but its metaclass is not derived from abc.ABCMeta",
+                T::NAME
+            )));
+        }
+        init_abstract_methods(py, type_object, &marked.abstract_methods)?;
+    }
+
+    if !marked.final_methods.is_empty() {
+        init_final_methods(py, type_object, &marked.final_methods)?;
+    }
+    if T::FLAGS & PY_TYPE_FLAG_FINAL != 0 {
+        // Like `typing.final`
+        let dict: &PyDict = unsafe { py.from_borrowed_ptr(type_object.tp_dict) };
+        dict.set_item("__final__", true)?;
+    }
+    signature::add_class_signature(py, type_object)?;
+    init_class_attributes::<T>(py, type_object)?;
+
+    for abc in T::ABCS {
+        unsafe { PyType::from_type_ptr(py, type_object) }.register_abc(abc)?;
+    }
+
+    if let (Some(python_base), Some(python_class)) = (T::PYTHON_BASE, unsafe { T::python_class() })
+    {
+        let class = create_python_class(py, type_object, python_base(py)?, module_name)?;
+        *python_class = class.into_ptr() as *mut ffi::PyTypeObject;
+    }
+
+    Ok(type_object as *mut ffi::PyTypeObject)
+}
+
+/// Returns `super(T, obj)`, which calls the methods `T` inherits, as `super()` does in the
+/// methods of a Python class. For a type extending a Python class, this calls the methods of
+/// that class, e.g. its `__init__`.
+///
+/// `py.get_type::<T>()` can't be used with [PyType::super_of] for such types: it's the Python
+/// class deriving from `T`, so the methods of `T` itself would be called.
+pub fn pyclass_super<'p, T: PyTypeInfo>(
+    py: Python<'p>,
+    obj: &impl AsPyPointer,
+) -> PyResult<&'p PyAny> {
+    unsafe { PyType::from_type_ptr(py, T::type_object()) }.super_of(obj)
+}
+
+/// Creates the Python class extending the Rust type `type_object` and the Python class
+/// `python_base`, like a `class` statement would, so that the metaclass and
+/// `__init_subclass__` of `python_base` are used.
+fn create_python_class<'p>(
+    py: Python<'p>,
+    type_object: &mut ffi::PyTypeObject,
+    python_base: &'p PyType,
+    module_name: Option<&str>,
+) -> PyResult<&'p PyAny> {
+    let rust_type = unsafe { PyType::from_type_ptr(py, type_object) };
+    let name = rust_type.getattr("__name__")?;
+    let namespace = PyDict::new(py);
+    namespace.set_item("__module__", module_name.unwrap_or("builtins"))?;
+    namespace.set_item("__qualname__", name)?;
+    namespace.set_item("__doc__", rust_type.getattr("__doc__")?)?;
+    py.import("builtins")?
+        .call1("type", (name, (rust_type, python_base), namespace))
+}
+
+/// Makes `metaclass` the type of the static `type_object`.
+fn set_metaclass<'p>(
+    type_object: &mut ffi::PyTypeObject,
+    metaclass: &'p PyType,
+) -> PyResult<&'p PyType> {
+    unsafe {
+        let meta = metaclass.as_type_ptr();
+        if ffi::PyType_IsSubtype(meta, &mut ffi::PyType_Type) == 0 {
+            return Err(PyErr::new::<TypeError, _>(format!(
+                "Metaclass {} is not a subclass of type",
+                metaclass.name()
+            )));
+        }
+        // The static type object has no room for data a metaclass adds to its instances
+        if (*meta).tp_basicsize > ffi::PyType_Type.tp_basicsize {
+            return Err(PyErr::new::<TypeError, _>(format!(
+                "Metaclass {} must not add instance attributes (__slots__)",
+                metaclass.name()
+            )));
+        }
+        ffi::Py_INCREF(meta as *mut ffi::PyObject);
+        (*(type_object as *mut ffi::PyTypeObject as *mut ffi::PyObject)).ob_type = meta;
+    }
+    Ok(metaclass)
+}
+
+/// Stores the values of the `#[classattr]` items in the type's dict. The type is ready by then,
+/// so they may be instances of the class itself.
+fn init_class_attributes<T: PyMethodsProtocol>(
+    py: Python,
+    type_object: &mut ffi::PyTypeObject,
+) -> PyResult<()> {
+    let dict: &PyDict = unsafe { py.from_borrowed_ptr(type_object.tp_dict) };
+    for def in T::py_methods() {
+        if let PyMethodDefType::ClassAttribute(def) = def {
+            dict.set_item(def.name, (def.meth)(py)?)?;
         }
     }
+    unsafe { ffi::PyType_Modified(type_object) };
+    Ok(())
+}
+
+/// Sets up the state `abc.ABCMeta.__new__` would have created, since metaclasses are not called
+/// for static types. Returns whether `metaclass` is derived from `abc.ABCMeta`.
+fn init_abc_metaclass(
+    py: Python,
+    type_object: &mut ffi::PyTypeObject,
+    metaclass: &PyType,
+) -> PyResult<bool> {
+    let abc_meta: &PyType = py.import("abc")?.get("ABCMeta")?.downcast_ref()?;
+    if unsafe { ffi::PyType_IsSubtype(metaclass.as_type_ptr(), abc_meta.as_type_ptr()) } == 0 {
+        return Ok(false);
+    }
+    // Static types are immutable, so copy the `_abc_*` attributes of a fresh abstract class
+    // into the type's dict directly
+    let template: &PyType = abc_meta
+        .call1(("_", PyTuple::empty(py), PyDict::new(py)))?
+        .downcast_ref()?;
+    unsafe {
+        let template_dict: &PyDict = py.from_borrowed_ptr((*template.as_type_ptr()).tp_dict);
+        let dict: &PyDict = py.from_borrowed_ptr(type_object.tp_dict);
+        for (key, value) in template_dict.iter() {
+            if key.extract::<&str>()?.starts_with("_abc_") {
+                dict.set_item(key, value)?;
+            }
+        }
+        ffi::PyType_Modified(type_object);
+    }
+    Ok(true)
+}
+
+/// Sets `__abstractmethods__`, which `abc.ABCMeta` checks the overrides of Python subclasses
+/// against, and marks the class as abstract.
+fn init_abstract_methods(
+    py: Python,
+    type_object: &mut ffi::PyTypeObject,
+    abstract_methods: &[&'static str],
+) -> PyResult<()> {
+    unsafe {
+        let dict: &PyDict = py.from_borrowed_ptr(type_object.tp_dict);
+        for name in abstract_methods {
+            if let Some(method) = dict.get_item(name) {
+                dict.set_item(name, AbstractMethod::wrap(py, method)?)?;
+            }
+        }
+        dict.set_item(
+            "__abstractmethods__",
+            PyFrozenSet::new(py, abstract_methods)?,
+        )?;
+        type_object.tp_flags |= ffi::Py_TPFLAGS_IS_ABSTRACT;
+        ffi::PyType_Modified(type_object);
+    }
+    Ok(())
+}
+
+/// Adds an `__init_subclass__` to the class which rejects Python subclasses overriding any of
+/// `final_methods`.
+fn init_final_methods(
+    py: Python,
+    type_object: &mut ffi::PyTypeObject,
+    final_methods: &[&'static str],
+) -> PyResult<()> {
+    unsafe {
+        let ty = PyType::from_type_ptr(py, type_object);
+        let dict: &PyDict = py.from_borrowed_ptr(type_object.tp_dict);
+        if dict.get_item("__init_subclass__").is_some() {
+            return Err(PyErr::new::<TypeError, _>(format!(
+                "{} has final methods, so it can't define __init_subclass__",
+                ty.name()
+            )));
+        }
+        let state = PyTuple::new(
+            py,
+            &[
+                ty.to_object(py),
+                PyFrozenSet::new(py, final_methods)?.to_object(py),
+            ],
+        );
+        let def = PyMethodDef {
+            ml_name: "__init_subclass__",
+            ml_meth: PyMethodType::PyCFunctionWithKeywords(init_subclass),
+            ml_flags: ffi::METH_VARARGS | ffi::METH_KEYWORDS,
+            ml_doc: "Rejects subclasses which override final methods.\0",
+        };
+        let init_subclass: &PyAny = py.from_owned_ptr_or_err(ffi::PyCFunction_New(
+            Box::into_raw(Box::new(def.as_method_def())),
+            state.as_ptr(),
+        ))?;
+        let classmethod = py
+            .import("builtins")?
+            .call1("classmethod", (init_subclass,))?;
+        dict.set_item("__init_subclass__", classmethod)?;
+        ffi::PyType_Modified(type_object);
+    }
+    Ok(())
+}
+
+/// The `__init_subclass__` of classes with final methods, bound to a `(class, final_methods)`
+/// tuple.
+unsafe extern "C" fn init_subclass(
+    state: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+    kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let py = Python::assume_gil_acquired();
+    let _pool = gil::GILPool::new(py);
+    let check = || -> PyResult<()> {
+        let (base, final_methods): (&PyType, &PyFrozenSet) =
+            py.from_borrowed_ptr::<PyAny>(state).extract()?;
+        let args: &PyTuple = py.from_borrowed_ptr(args);
+        let kwargs: Option<&PyDict> = py.from_borrowed_ptr_or_opt(kwargs);
+        let subclass: &PyType = args.get_item(0).downcast_ref()?;
+        let namespace = subclass.getattr("__dict__")?;
+        for name in final_methods {
+            if namespace.call_method1("__contains__", (name,))?.is_true()? {
+                return Err(PyErr::new::<TypeError, _>(format!(
+                    "{} can't override final method {}.{}",
+                    subclass.name(),
+                    base.name(),
+                    name
+                )));
+            }
+        }
+        // Let the other base classes initialize the subclass
+        base.super_of(subclass)?
+            .call_method("__init_subclass__", (), kwargs)?;
+        Ok(())
+    };
+    match check() {
+        Ok(()) => py.None().into_ptr(),
+        Err(err) => {
+            err.restore(py);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Wraps an abstract method, so that `abc.ABCMeta` finds `__isabstractmethod__` on it like on
+/// functions decorated with `abc.abstractmethod`.
+#[repr(C)]
+struct AbstractMethod {
+    ob_base: ffi::PyObject,
+    method: *mut ffi::PyObject,
+}
+
+static mut ABSTRACT_METHOD_TYPE: ffi::PyTypeObject = ffi::PyTypeObject_INIT;
+
+impl AbstractMethod {
+    unsafe fn wrap<'p>(py: Python<'p>, method: &PyAny) -> PyResult<&'p PyAny> {
+        let type_object = &mut ABSTRACT_METHOD_TYPE;
+        if type_object.tp_flags & ffi::Py_TPFLAGS_READY == 0 {
+            type_object.tp_name = "pyo3.AbstractMethod\0".as_ptr() as *const _;
+            type_object.tp_basicsize = std::mem::size_of::<AbstractMethod>() as ffi::Py_ssize_t;
+            type_object.tp_flags = ffi::Py_TPFLAGS_DEFAULT;
+            type_object.tp_dealloc = Some(AbstractMethod::dealloc);
+            type_object.tp_descr_get = Some(AbstractMethod::descr_get);
+            type_object.tp_call = Some(AbstractMethod::call);
+            type_object.tp_getattro = Some(AbstractMethod::getattro);
+            if ffi::PyType_Ready(type_object) != 0 {
+                return Err(PyErr::fetch(py));
+            }
+        }
+        let obj = ffi::PyType_GenericAlloc(type_object, 0);
+        if obj.is_null() {
+            return Err(PyErr::fetch(py));
+        }
+        (*(obj as *mut AbstractMethod)).method = method.into_ptr();
+        Ok(py.from_owned_ptr(obj))
+    }
+
+    unsafe fn method(obj: *mut ffi::PyObject) -> *mut ffi::PyObject {
+        (*(obj as *mut AbstractMethod)).method
+    }
+
+    unsafe extern "C" fn descr_get(
+        obj: *mut ffi::PyObject,
+        instance: *mut ffi::PyObject,
+        owner: *mut ffi::PyObject,
+    ) -> *mut ffi::PyObject {
+        // Like a function, the wrapper itself is returned when looked up on the class, which is
+        // where `abc.ABCMeta` checks for `__isabstractmethod__`
+        if instance.is_null() || instance == ffi::Py_None() {
+            ffi::Py_INCREF(obj);
+            return obj;
+        }
+        let method = AbstractMethod::method(obj);
+        match (*ffi::Py_TYPE(method)).tp_descr_get {
+            Some(descr_get) => descr_get(method, instance, owner),
+            None => {
+                ffi::Py_INCREF(method);
+                method
+            }
+        }
+    }
+
+    unsafe extern "C" fn call(
+        obj: *mut ffi::PyObject,
+        args: *mut ffi::PyObject,
+        kwargs: *mut ffi::PyObject,
+    ) -> *mut ffi::PyObject {
+        ffi::PyObject_Call(AbstractMethod::method(obj), args, kwargs)
+    }
+
+    unsafe extern "C" fn getattro(
+        obj: *mut ffi::PyObject,
+        name: *mut ffi::PyObject,
+    ) -> *mut ffi::PyObject {
+        let is_abstract = "__isabstractmethod__\0".as_ptr() as *const _;
+        if ffi::PyUnicode_CompareWithASCIIString(name, is_abstract) == 0 {
+            ffi::Py_INCREF(ffi::Py_True());
+            return ffi::Py_True();
+        }
+        ffi::PyObject_GetAttr(AbstractMethod::method(obj), name)
+    }
+
+    unsafe extern "C" fn dealloc(obj: *mut ffi::PyObject) {
+        ffi::Py_DECREF(AbstractMethod::method(obj));
+        ffi::PyObject_Free(obj as *mut c_void);
+    }
 }
 
 unsafe extern "C" fn tp_dealloc_callback<T>(obj: *mut ffi::PyObject)
@@ -429,16 +850,66 @@ fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
     }
 }
 
+/// The names of the methods marked with `#[abstractmethod]` or `#[pyo3(final)]`, and of the
+/// attribute access methods which need slots
+#[derive(Default)]
+struct MarkedMethods {
+    abstract_methods: Vec<&'static str>,
+    final_methods: Vec<&'static str>,
+    attribute_methods: Vec<&'static str>,
+}
+
+/// Returns the `tp_doc` of the class, which gets the text signature of `#[new]` unless the class
+/// declares one with `#[text_signature]`.
+fn class_doc<T: PyTypeInfo + PyMethodsProtocol>() -> *const std::os::raw::c_char {
+    const SIGNATURE_END: &str = ")\n--\n\n";
+    let description = T::DESCRIPTION.trim_end_matches('\0');
+    let new_signature = T::py_methods().iter().find_map(|def| match def {
+        PyMethodDefType::New(def) => {
+            let start = def.ml_doc.find('(')?;
+            let end = def.ml_doc.find(SIGNATURE_END)?;
+            Some(&def.ml_doc[start..=end])
+        }
+        _ => None,
+    });
+    match new_signature {
+        Some(signature) if !description.contains(SIGNATURE_END) => {
+            // Types are only initialized once, so leaking the doc is fine
+            let doc = format!("{}{}\n--\n\n{}\0", T::NAME, signature, description);
+            Box::leak(doc.into_boxed_str()).as_ptr() as *const _
+        }
+        // PyPy will segfault if passed only a nul terminator as `tp_doc`.
+        // ptr::null() is OK though.
+        _ if description.is_empty() => ptr::null(),
+        _ => T::DESCRIPTION.as_ptr() as *const _,
+    }
+}
+
 fn py_class_method_defs<T: PyMethodsProtocol>() -> (
     Option<ffi::newfunc>,
     Option<ffi::PyCFunctionWithKeywords>,
     Vec<ffi::PyMethodDef>,
+    MarkedMethods,
 ) {
     let mut defs = Vec::new();
     let mut call = None;
     let mut new = None;
+    let mut marked = MarkedMethods::default();
 
     for def in T::py_methods() {
+        let def = match *def {
+            PyMethodDefType::Abstract(def) => {
+                let def = def();
+                marked.abstract_methods.extend(def.name());
+                def
+            }
+            PyMethodDefType::Final(def) => {
+                let def = def();
+                marked.final_methods.extend(def.name());
+                def
+            }
+            _ => def,
+        };
         match *def {
             PyMethodDefType::New(ref def) => {
                 if let class::methods::PyMethodType::PyNewFunc(meth) = def.ml_meth {
@@ -452,9 +923,16 @@ fn py_class_method_defs<T: PyMethodsProtocol>() -> (
                     panic!("Method type is not supoorted by tp_call slot")
                 }
             }
-            PyMethodDefType::Method(ref def)
-            | PyMethodDefType::Class(ref def)
-            | PyMethodDefType::Static(ref def) => {
+            PyMethodDefType::Method(ref def) => {
+                let mut method_def = def.as_method_def();
+                if let "__getattr__" | "__setattr__" | "__delattr__" = def.ml_name {
+                    marked.attribute_methods.push(def.ml_name);
+                    // Replace the wrapper of the slot, which would call the slot itself
+                    method_def.ml_flags |= ffi::METH_COEXIST;
+                }
+                defs.push(method_def);
+            }
+            PyMethodDefType::Class(ref def) | PyMethodDefType::Static(ref def) => {
                 defs.push(def.as_method_def());
             }
             _ => (),
@@ -473,13 +951,16 @@ fn py_class_method_defs<T: PyMethodsProtocol>() -> (
     for def in <T as class::number::PyNumberProtocolImpl>::methods() {
         defs.push(def.as_method_def());
     }
+    for def in <T as class::iter::PyIterProtocolImpl>::methods() {
+        defs.push(def.as_method_def());
+    }
     for def in <T as class::descr::PyDescrProtocolImpl>::methods() {
         defs.push(def.as_method_def());
     }
 
     py_class_async_methods::<T>(&mut defs);
 
-    (new, call, defs)
+    (new, call, defs, marked)
 }
 
 fn py_class_async_methods<T>(defs: &mut Vec<ffi::PyMethodDef>) {
@@ -1,7 +1,11 @@
-use crate::conversion::AsPyPointer;
-use crate::err::PyDowncastError;
+use crate::class::basic::CompareOp;
+use crate::conversion::{AsPyPointer, ToBorrowedObject, ToPyObject};
+use crate::err::{PyDowncastError, PyErr, PyResult};
+use crate::instance::PyNativeType;
 use crate::internal_tricks::Unsendable;
-use crate::{ffi, PyObject, PyRef, PyRefMut, PyTryFrom, PyTypeInfo};
+use crate::types::PyNumber;
+use crate::{ffi, ObjectProtocol, PyObject, PyRef, PyRefMut, PyTryFrom, PyTypeInfo};
+use std::os::raw::c_int;
 
 /// Represents a python's [Any](https://docs.python.org/3/library/typing.html#typing.Any) type.
 /// We can convert all python objects as `PyAny`.
@@ -21,6 +25,19 @@ use crate::{ffi, PyObject, PyRef, PyRefMut, PyTryFrom, PyTypeInfo};
 /// assert!(any.downcast_ref::<PyDict>().is_ok());
 /// assert!(any.downcast_ref::<PyList>().is_err());
 /// ```
+///
+/// Python objects can be compared with [compare](crate::ObjectProtocol::compare), or with the
+/// boolean helpers such as [lt](PyAny::lt), which makes it easy to sort them from Rust:
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyAny;
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let mut items: Vec<&PyAny> = vec![py.eval("3", None, None).unwrap(), py.eval("1", None, None).unwrap()];
+/// items.sort_by(|a, b| a.compare(*b).unwrap());
+/// assert!(items[0].lt(items[1]).unwrap());
+/// ```
 #[repr(transparent)]
 pub struct PyAny(PyObject, Unsendable);
 pyobject_native_type_named!(PyAny);
@@ -40,6 +57,123 @@ impl PyAny {
     {
         T::try_from_mut(self)
     }
+
+    /// Returns a view of `self` supporting arithmetic through the number protocol.
+    ///
+    /// Fails if `self` does not implement the number protocol.
+    pub fn as_number(&self) -> Result<&PyNumber, PyDowncastError> {
+        PyNumber::try_from(self)
+    }
+
+    /// Compares `self` with `other` using `compare_op`, returning the result as a `bool`.
+    pub fn rich_compare_bool<O>(&self, other: O, compare_op: CompareOp) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        let result = other.with_borrowed_ptr(self.py(), |other| unsafe {
+            ffi::PyObject_RichCompareBool(self.as_ptr(), other, compare_op as c_int)
+        });
+        match result {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PyErr::fetch(self.py())),
+        }
+    }
+
+    /// Equivalent to the Python expression `self < other`.
+    pub fn lt<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Lt)
+    }
+
+    /// Equivalent to the Python expression `self <= other`.
+    pub fn le<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Le)
+    }
+
+    /// Equivalent to the Python expression `self > other`.
+    pub fn gt<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Gt)
+    }
+
+    /// Equivalent to the Python expression `self >= other`.
+    pub fn ge<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Ge)
+    }
+
+    /// Equivalent to the Python expression `self == other`.
+    ///
+    /// Not named `eq`, which would shadow [PartialEq::eq], the identity comparison.
+    pub fn rich_eq<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Eq)
+    }
+
+    /// Equivalent to the Python expression `self != other`.
+    pub fn rich_ne<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Ne)
+    }
+
+    /// [ObjectProtocol::hash], callable without importing the trait.
+    pub fn hash(&self) -> PyResult<isize> {
+        ObjectProtocol::hash(self)
+    }
+
+    /// [ObjectProtocol::len], callable without importing the trait.
+    pub fn len(&self) -> PyResult<usize> {
+        ObjectProtocol::len(self)
+    }
+
+    /// [ObjectProtocol::is_empty], callable without importing the trait.
+    pub fn is_empty(&self) -> PyResult<bool> {
+        ObjectProtocol::is_empty(self)
+    }
+
+    /// [ObjectProtocol::is_true], callable without importing the trait.
+    pub fn is_true(&self) -> PyResult<bool> {
+        ObjectProtocol::is_true(self)
+    }
+
+    /// [ObjectProtocol::is_callable], callable without importing the trait.
+    pub fn is_callable(&self) -> bool {
+        ObjectProtocol::is_callable(self)
+    }
+
+    /// [ObjectProtocol::is_iterable], callable without importing the trait.
+    pub fn is_iterable(&self) -> bool {
+        ObjectProtocol::is_iterable(self)
+    }
+
+    /// Returns an estimate of the number of items in `self`, as given by `len(self)` or
+    /// `self.__length_hint__()`, or `default` if neither is available.
+    ///
+    /// Errors raised while computing the hint are discarded, so this is only suitable
+    /// for preallocation.
+    pub fn length_hint(&self, default: usize) -> usize {
+        let hint = unsafe { ffi::PyObject_LengthHint(self.as_ptr(), default as ffi::Py_ssize_t) };
+        if hint < 0 {
+            PyErr::fetch(self.py());
+            default
+        } else {
+            hint as usize
+        }
+    }
 }
 
 impl<'a, T> From<PyRef<'a, T>> for &'a PyAny
@@ -59,3 +193,33 @@ where
         unsafe { &*(pref.as_ptr() as *const PyAny) }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::types::PyAny;
+    use crate::Python;
+
+    #[test]
+    fn test_protocol_helpers() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let eval = |code| -> &PyAny { py.eval(code, None, None).unwrap() };
+
+        assert_eq!(eval("(1, 2)").len().unwrap(), 2);
+        assert!(eval("[]").is_empty().unwrap());
+        let err = eval("1").len().unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::TypeError>(py));
+
+        assert_eq!(eval("42").hash().unwrap(), 42);
+        let err = eval("[]").hash().unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::TypeError>(py));
+
+        assert!(eval("[0]").is_true().unwrap() && !eval("''").is_true().unwrap());
+        assert!(eval("len").is_callable() && !eval("1").is_callable());
+
+        for iterable in &["[]", "'abc'", "{}", "iter([])", "range(3)"] {
+            assert!(eval(iterable).is_iterable(), "{}", iterable);
+        }
+        assert!(!eval("1").is_iterable() && !eval("None").is_iterable());
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Conversions between [chrono](https://docs.rs/chrono) types and the types of Python's
+//! `datetime` module.
+//!
+//! | Rust                                     | Python                             |
+//! |------------------------------------------|------------------------------------|
+//! | `NaiveDate`                              | `datetime.date`                    |
+//! | `NaiveTime`                              | `datetime.time` without tzinfo     |
+//! | `NaiveDateTime`                          | `datetime.datetime` without tzinfo |
+//! | `DateTime<Utc>`, `DateTime<FixedOffset>` | `datetime.datetime` with tzinfo    |
+//! | `Duration`                               | `datetime.timedelta`               |
+//!
+//! Python only has microsecond precision, so nanoseconds are truncated, and it has no leap
+//! seconds, so a leap second becomes the last microsecond of the second before. Extracting a
+//! `DateTime` accepts any datetime with a tzinfo, using the UTC offset it has at that time, so
+//! e.g. `DateTime<Utc>` can be extracted from a datetime in `zoneinfo.ZoneInfo("Europe/Paris")`.
+//! Naive types can't be extracted from objects with a tzinfo, and vice versa.
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{TypeError, ValueError};
+use crate::instance::PyNativeType;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{
+    PyAny, PyDate, PyDateAccess, PyDateTime, PyDelta, PyDeltaAccess, PyTime, PyTimeAccess,
+};
+use crate::{FromPyObject, IntoPy, PyTryFrom, Python, ToPyObject};
+use chrono::offset::TimeZone;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc,
+};
+use std::convert::TryFrom;
+
+/// Returns the microseconds of `time`, where a leap second becomes the last microsecond of
+/// the second before.
+fn microsecond(time: &impl Timelike) -> u32 {
+    (time.nanosecond() / 1000).min(999_999)
+}
+
+fn date_to_object(py: Python, date: &NaiveDate) -> PyResult<PyObject> {
+    let date = PyDate::new(py, date.year(), date.month() as u8, date.day() as u8)?;
+    Ok(date.into())
+}
+
+fn datetime_to_object(
+    py: Python,
+    datetime: &NaiveDateTime,
+    tzinfo: Option<&PyObject>,
+) -> PyResult<PyObject> {
+    let datetime = PyDateTime::new(
+        py,
+        datetime.year(),
+        datetime.month() as u8,
+        datetime.day() as u8,
+        datetime.hour() as u8,
+        datetime.minute() as u8,
+        datetime.second() as u8,
+        microsecond(datetime),
+        tzinfo,
+    )?;
+    Ok(datetime.into())
+}
+
+/// Returns `datetime.timezone(timedelta(seconds=offset))`, which is `datetime.timezone.utc` for
+/// an offset of 0.
+fn timezone(py: Python, offset: &FixedOffset) -> PyResult<PyObject> {
+    let timezone = py.import("datetime")?.get("timezone")?;
+    let offset = offset.local_minus_utc();
+    if offset == 0 {
+        return Ok(timezone.getattr("utc")?.into());
+    }
+    let delta = PyDelta::new(py, 0, offset, 0, true)?;
+    Ok(timezone.call1((delta,))?.into())
+}
+
+fn duration_to_object(py: Python, duration: &Duration) -> PyResult<PyObject> {
+    let days = duration.num_days();
+    let rest = *duration - Duration::days(days);
+    let seconds = rest.num_seconds();
+    let microseconds = (rest - Duration::seconds(seconds))
+        .num_microseconds()
+        .unwrap_or(0);
+    let days = i32::try_from(days)
+        .map_err(|_| ValueError::py_err("Duration is out of range for timedelta"))?;
+    let delta = PyDelta::new(py, days, seconds as i32, microseconds as i32, true)?;
+    Ok(delta.into())
+}
+
+fn naive_date(date: &impl PyDateAccess) -> PyResult<NaiveDate> {
+    NaiveDate::from_ymd_opt(
+        date.get_year(),
+        u32::from(date.get_month()),
+        u32::from(date.get_day()),
+    )
+    .ok_or_else(|| ValueError::py_err("Date is out of range for chrono"))
+}
+
+fn naive_time(time: &impl PyTimeAccess) -> PyResult<NaiveTime> {
+    NaiveTime::from_hms_micro_opt(
+        u32::from(time.get_hour()),
+        u32::from(time.get_minute()),
+        u32::from(time.get_second()),
+        time.get_microsecond(),
+    )
+    .ok_or_else(|| ValueError::py_err("Invalid time"))
+}
+
+/// Returns the UTC offset of `ob`, a `datetime` or `time`, or `None` if it is naive.
+fn utc_offset(ob: &PyAny) -> PyResult<Option<FixedOffset>> {
+    let delta = ob.call_method0("utcoffset")?;
+    if delta.is_none() {
+        return Ok(None);
+    }
+    let offset: Duration = delta.extract()?;
+    let seconds = i32::try_from(offset.num_seconds()).ok();
+    match seconds.and_then(FixedOffset::east_opt) {
+        // `FixedOffset` has no sub-second precision
+        Some(fixed) if Duration::seconds(offset.num_seconds()) == offset => Ok(Some(fixed)),
+        _ => Err(ValueError::py_err(format!(
+            "UTC offset {} can't be represented by chrono",
+            delta
+        ))),
+    }
+}
+
+fn type_error(ob: &PyAny, expected: &str) -> PyErr {
+    TypeError::py_err(format!(
+        "Expected {}, got {}",
+        expected,
+        ob.get_type().name()
+    ))
+}
+
+impl ToPyObject for NaiveDate {
+    /// Panics if the date is before year 1 or after year 9999, which `datetime` can't represent.
+    fn to_object(&self, py: Python) -> PyObject {
+        date_to_object(py, self).expect("Failed to convert NaiveDate to date")
+    }
+}
+
+impl IntoPy<PyObject> for NaiveDate {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for NaiveDate {
+    /// Accepts `date` objects, including `datetime` objects, whose time is ignored.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let date = <PyDate as PyTryFrom>::try_from(ob).map_err(|_| type_error(ob, "a date"))?;
+        naive_date(date)
+    }
+}
+
+impl ToPyObject for NaiveTime {
+    fn to_object(&self, py: Python) -> PyObject {
+        PyTime::new(
+            py,
+            self.hour() as u8,
+            self.minute() as u8,
+            self.second() as u8,
+            microsecond(self),
+            None,
+        )
+        .expect("Failed to convert NaiveTime to time")
+        .into()
+    }
+}
+
+impl IntoPy<PyObject> for NaiveTime {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for NaiveTime {
+    /// Accepts `time` objects without tzinfo.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let time = <PyTime as PyTryFrom>::try_from(ob).map_err(|_| type_error(ob, "a time"))?;
+        if !ob.getattr("tzinfo")?.is_none() {
+            return Err(type_error(ob, "a time without tzinfo"));
+        }
+        naive_time(time)
+    }
+}
+
+impl ToPyObject for NaiveDateTime {
+    /// Creates a naive `datetime`. Panics if the date is before year 1 or after year 9999.
+    fn to_object(&self, py: Python) -> PyObject {
+        datetime_to_object(py, self, None).expect("Failed to convert NaiveDateTime to datetime")
+    }
+}
+
+impl IntoPy<PyObject> for NaiveDateTime {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for NaiveDateTime {
+    /// Accepts naive `datetime` objects, i.e. without tzinfo.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let datetime =
+            <PyDateTime as PyTryFrom>::try_from(ob).map_err(|_| type_error(ob, "a datetime"))?;
+        if utc_offset(ob)?.is_some() {
+            return Err(type_error(ob, "a naive datetime"));
+        }
+        Ok(NaiveDateTime::new(
+            naive_date(datetime)?,
+            naive_time(datetime)?,
+        ))
+    }
+}
+
+impl ToPyObject for DateTime<FixedOffset> {
+    /// Creates a `datetime` with the local time and a `datetime.timezone` with the offset.
+    /// Panics if the date is before year 1 or after year 9999.
+    fn to_object(&self, py: Python) -> PyObject {
+        timezone(py, self.offset())
+            .and_then(|tzinfo| datetime_to_object(py, &self.naive_local(), Some(&tzinfo)))
+            .expect("Failed to convert DateTime to datetime")
+    }
+}
+
+impl IntoPy<PyObject> for DateTime<FixedOffset> {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for DateTime<FixedOffset> {
+    /// Accepts `datetime` objects with a tzinfo, using the UTC offset they have.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let datetime =
+            <PyDateTime as PyTryFrom>::try_from(ob).map_err(|_| type_error(ob, "a datetime"))?;
+        let offset = utc_offset(ob)?.ok_or_else(|| type_error(ob, "a datetime with tzinfo"))?;
+        let local = NaiveDateTime::new(naive_date(datetime)?, naive_time(datetime)?);
+        offset
+            .from_local_datetime(&local)
+            .single()
+            .ok_or_else(|| ValueError::py_err("Datetime is out of range for chrono"))
+    }
+}
+
+impl ToPyObject for DateTime<Utc> {
+    /// Creates a `datetime` with `datetime.timezone.utc` as tzinfo. Panics if the date is
+    /// before year 1 or after year 9999.
+    fn to_object(&self, py: Python) -> PyObject {
+        self.with_timezone(&FixedOffset::east_opt(0).unwrap())
+            .to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for DateTime<Utc> {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for DateTime<Utc> {
+    /// Accepts `datetime` objects with a tzinfo, which are converted to UTC.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let datetime: DateTime<FixedOffset> = ob.extract()?;
+        Ok(datetime.with_timezone(&Utc))
+    }
+}
+
+impl ToPyObject for Duration {
+    /// Panics if the duration is longer than 999999999 days, the limit of `timedelta`.
+    fn to_object(&self, py: Python) -> PyObject {
+        duration_to_object(py, self).expect("Failed to convert Duration to timedelta")
+    }
+}
+
+impl IntoPy<PyObject> for Duration {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for Duration {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let delta =
+            <PyDelta as PyTryFrom>::try_from(ob).map_err(|_| type_error(ob, "a timedelta"))?;
+        Ok(Duration::days(i64::from(delta.get_days()))
+            + Duration::seconds(i64::from(delta.get_seconds()))
+            + Duration::microseconds(i64::from(delta.get_microseconds())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions::TypeError;
+    use crate::types::PyDict;
+    use crate::{ObjectProtocol, Python, ToPyObject};
+    use chrono::offset::TimeZone;
+    use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+    #[test]
+    fn test_naive() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 29).unwrap();
+        let time = NaiveTime::from_hms_nano_opt(13, 5, 7, 123_456_789).unwrap();
+        let datetime = NaiveDateTime::new(date, time);
+        let locals = PyDict::new(py);
+        locals.set_item("date", date).unwrap();
+        locals.set_item("time", time).unwrap();
+        locals.set_item("datetime", datetime).unwrap();
+        py.run(
+            r#"
+import datetime as dt
+assert date == dt.date(2020, 2, 29)
+# Nanoseconds are truncated
+assert time == dt.time(13, 5, 7, 123456)
+assert datetime == dt.datetime(2020, 2, 29, 13, 5, 7, 123456)
+assert datetime.tzinfo is None
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let truncated = NaiveTime::from_hms_micro_opt(13, 5, 7, 123_456).unwrap();
+        let extracted: NaiveDateTime = locals.get_item("datetime").unwrap().extract().unwrap();
+        assert_eq!(extracted, NaiveDateTime::new(date, truncated));
+        let extracted: NaiveTime = locals.get_item("time").unwrap().extract().unwrap();
+        assert_eq!(extracted, truncated);
+        // A datetime is a date
+        let extracted: NaiveDate = locals.get_item("datetime").unwrap().extract().unwrap();
+        assert_eq!(extracted, date);
+
+        let leap_second = NaiveTime::from_hms_nano_opt(23, 59, 59, 1_500_000_000).unwrap();
+        let extracted: NaiveTime = leap_second.to_object(py).extract(py).unwrap();
+        assert_eq!(
+            extracted,
+            NaiveTime::from_hms_micro_opt(23, 59, 59, 999_999).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_timezones() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let offset = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let local = offset.with_ymd_and_hms(2021, 6, 1, 12, 0, 0).unwrap();
+        let utc = Utc.with_ymd_and_hms(2021, 6, 1, 6, 30, 0).unwrap();
+        let locals = PyDict::new(py);
+        locals.set_item("local", local).unwrap();
+        locals.set_item("utc", utc).unwrap();
+        py.run(
+            r#"
+import datetime as dt
+assert local.utcoffset() == dt.timedelta(hours=5, minutes=30)
+assert (local.hour, local.minute) == (12, 0)
+assert utc.tzinfo is dt.timezone.utc
+assert local == utc
+paris = dt.datetime(2021, 6, 1, 8, 30, tzinfo=dt.timezone(dt.timedelta(hours=2)))
+naive = dt.datetime(2021, 6, 1)
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let extracted: DateTime<FixedOffset> = locals.get_item("local").unwrap().extract().unwrap();
+        assert_eq!(extracted, local);
+        assert_eq!(extracted.offset(), &offset);
+        // Any timezone is converted to UTC
+        let extracted: DateTime<Utc> = locals.get_item("paris").unwrap().extract().unwrap();
+        assert_eq!(extracted, utc);
+
+        let naive = locals.get_item("naive").unwrap();
+        let err = naive.extract::<DateTime<Utc>>().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let err = locals
+            .get_item("utc")
+            .unwrap()
+            .extract::<NaiveDateTime>()
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_duration() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for duration in &[
+            Duration::zero(),
+            Duration::days(3) + Duration::seconds(4) + Duration::microseconds(5),
+            -Duration::microseconds(1),
+            -(Duration::days(1000) + Duration::milliseconds(1500)),
+        ] {
+            let delta = duration.to_object(py);
+            let extracted: Duration = delta.extract(py).unwrap();
+            assert_eq!(extracted, *duration);
+            let seconds: f64 = delta
+                .call_method0(py, "total_seconds")
+                .unwrap()
+                .extract(py)
+                .unwrap();
+            assert_eq!(seconds, duration.num_microseconds().unwrap() as f64 / 1e6);
+        }
+
+        let err = py
+            .eval("1.5", None, None)
+            .unwrap()
+            .extract::<Duration>()
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+}
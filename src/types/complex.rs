@@ -1,5 +1,4 @@
 use crate::ffi;
-#[cfg(not(PyPy))]
 use crate::instance::PyNativeType;
 use crate::internal_tricks::Unsendable;
 use crate::AsPyPointer;
@@ -40,6 +39,19 @@ impl PyComplex {
             ffi::_Py_c_abs(val)
         }
     }
+    /// Returns `|self|`.
+    #[cfg(any(Py_LIMITED_API, PyPy))]
+    pub fn abs(&self) -> c_double {
+        self.real().hypot(self.imag())
+    }
+    /// Returns the phase angle of `self` in radians, like `cmath.phase(self)`.
+    pub fn arg(&self) -> c_double {
+        self.imag().atan2(self.real())
+    }
+    /// Returns the complex conjugate of `self`, like `self.conjugate()`.
+    pub fn conjugate(&self) -> &PyComplex {
+        PyComplex::from_doubles(self.py(), self.real(), -self.imag())
+    }
     /// Returns `self ** other`
     #[cfg(not(Py_LIMITED_API))]
     #[cfg(not(PyPy))]
@@ -145,6 +157,11 @@ mod complex_conversion {
                 py.from_owned_ptr(ptr)
             }
         }
+
+        /// Returns the value as a `num_complex::Complex`, for arithmetic in Rust.
+        pub fn to_complex(&self) -> Complex<f64> {
+            Complex::new(self.real(), self.imag())
+        }
     }
     macro_rules! complex_conversion {
         ($float: ty) => {
@@ -216,6 +233,36 @@ mod complex_conversion {
         assert_eq!(obj.extract::<Complex<f64>>(py).unwrap(), val);
     }
     #[test]
+    fn to_complex() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let py_c = PyComplex::from_doubles(py, 3.0, 1.2);
+        assert_eq!(py_c.to_complex() * 2.0, Complex::new(6.0, 2.4));
+    }
+    #[test]
+    fn from_complex_like() {
+        use crate::ObjectProtocol;
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // Like `complex(obj)`, floats and objects with `__complex__` are accepted
+        let obj = py.eval("1.5", None, None).unwrap();
+        assert_eq!(
+            obj.extract::<Complex<f64>>().unwrap(),
+            Complex::new(1.5, 0.0)
+        );
+        let obj = py
+            .eval(
+                "type('C', (), {'__complex__': lambda self: 2j})()",
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            obj.extract::<Complex<f32>>().unwrap(),
+            Complex::new(0.0, 2.0)
+        );
+    }
+    #[test]
     fn from_complex_err() {
         let gil = Python::acquire_gil();
         let py = gil.python();
@@ -300,7 +347,23 @@ mod test {
         assert_approx_eq!(res.imag(), -1.2);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
+    #[test]
+    fn test_arg() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let val = PyComplex::from_doubles(py, -1.0, 0.0);
+        assert_approx_eq!(val.arg(), std::f64::consts::PI);
+    }
+
+    #[test]
+    fn test_conjugate() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let val = PyComplex::from_doubles(py, 3.0, 1.2).conjugate();
+        assert_approx_eq!(val.real(), 3.0);
+        assert_approx_eq!(val.imag(), -1.2);
+    }
+
     #[test]
     fn test_abs() {
         let gil = Python::acquire_gil();
@@ -4,7 +4,8 @@ use crate::err::{self, PyErr, PyResult};
 use crate::instance::PyNativeType;
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
-use crate::types::{PyAny, PyList};
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyDictItems, PyDictKeys, PyDictValues, PyList};
 use crate::AsPyPointer;
 #[cfg(not(PyPy))]
 use crate::IntoPyPointer;
@@ -27,6 +28,50 @@ impl PyDict {
         unsafe { py.from_owned_ptr::<PyDict>(ffi::PyDict_New()) }
     }
 
+    /// Creates a new empty dictionary with room for at least `capacity` items.
+    ///
+    /// On PyPy, the capacity is ignored.
+    pub fn with_capacity(py: Python, capacity: usize) -> &PyDict {
+        #[cfg(not(PyPy))]
+        unsafe {
+            let capacity = cmp::min(capacity, isize::max_value() as usize) as ffi::Py_ssize_t;
+            py.from_owned_ptr::<PyDict>(ffi::_PyDict_NewPresized(capacity))
+        }
+        #[cfg(PyPy)]
+        {
+            let _ = capacity;
+            PyDict::new(py)
+        }
+    }
+
+    /// Creates a new dictionary from an iterator of key-value pairs.
+    ///
+    /// The dictionary is pre-sized using the lower bound of the iterator's size hint.
+    /// In the case of key collisions, this keeps the last entry seen.
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::PyDict;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let squares = PyDict::from_iter(py, (1..4).map(|i| (i.to_string(), i * i))).unwrap();
+    /// assert_eq!(squares.len(), 3);
+    /// ```
+    pub fn from_iter<K, V, I>(py: Python, iter: I) -> PyResult<&PyDict>
+    where
+        K: IntoPy<PyObject>,
+        V: IntoPy<PyObject>,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let iter = iter.into_iter();
+        let dict = PyDict::with_capacity(py, iter.size_hint().0);
+        for (key, value) in iter {
+            dict.set_item(key.into_py(py), value.into_py(py))?;
+        }
+        Ok(dict)
+    }
+
     /// Creates a new dictionary from the sequence given.
     ///
     /// The sequence must consist of `(PyObject, PyObject)`. This is
@@ -150,6 +195,30 @@ impl PyDict {
         }
     }
 
+    /// Returns a live view of the dictionary's keys.
+    /// This is equivalent to the python expression `dict.keys()`.
+    pub fn keys_view(&self) -> &PyDictKeys {
+        self.call_method0("keys")
+            .and_then(|view| view.downcast_ref().map_err(Into::into))
+            .expect("dict.keys() failed")
+    }
+
+    /// Returns a live view of the dictionary's values.
+    /// This is equivalent to the python expression `dict.values()`.
+    pub fn values_view(&self) -> &PyDictValues {
+        self.call_method0("values")
+            .and_then(|view| view.downcast_ref().map_err(Into::into))
+            .expect("dict.values() failed")
+    }
+
+    /// Returns a live view of the dictionary's items.
+    /// This is equivalent to the python expression `dict.items()`.
+    pub fn items_view(&self) -> &PyDictItems {
+        self.call_method0("items")
+            .and_then(|view| view.downcast_ref().map_err(Into::into))
+            .expect("dict.items() failed")
+    }
+
     /// Returns a iterator of (key, value) pairs in this dictionary
     /// Note that it's unsafe to use when the dictionary might be changed
     /// by other python code.
@@ -224,10 +293,9 @@ where
     H: hash::BuildHasher,
 {
     fn into_py(self, py: Python) -> PyObject {
-        let iter = self
-            .into_iter()
-            .map(|(k, v)| (k.into_py(py), v.into_py(py)));
-        IntoPyDict::into_py_dict(iter, py).into()
+        PyDict::from_iter(py, self)
+            .expect("Failed to set_item on dict")
+            .into()
     }
 }
 
@@ -237,10 +305,9 @@ where
     V: IntoPy<PyObject>,
 {
     fn into_py(self, py: Python) -> PyObject {
-        let iter = self
-            .into_iter()
-            .map(|(k, v)| (k.into_py(py), v.into_py(py)));
-        IntoPyDict::into_py_dict(iter, py).into()
+        PyDict::from_iter(py, self)
+            .expect("Failed to set_item on dict")
+            .into()
     }
 }
 
@@ -258,8 +325,9 @@ where
     I: IntoIterator<Item = T>,
 {
     fn into_py_dict(self, py: Python) -> &PyDict {
-        let dict = PyDict::new(py);
-        for item in self {
+        let iter = self.into_iter();
+        let dict = PyDict::with_capacity(py, iter.size_hint().0);
+        for item in iter {
             dict.set_item(item.key(), item.value())
                 .expect("Failed to set_item on dict");
         }
@@ -360,6 +428,26 @@ mod test {
         assert_eq!(map, dict.extract().unwrap());
     }
 
+    #[test]
+    fn test_from_iter() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = PyDict::with_capacity(py, 100);
+        assert_eq!(dict.len(), 0);
+
+        let pairs = vec![("a".to_string(), vec![1]), ("b".to_string(), vec![2, 3])];
+        let dict = PyDict::from_iter(py, pairs).unwrap();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(
+            dict.get_item("b").unwrap().extract::<Vec<i32>>().unwrap(),
+            vec![2, 3]
+        );
+
+        let dict = (0..10).map(|i| (i, i * 2)).into_py_dict(py);
+        assert_eq!(dict.len(), 10);
+        assert_eq!(dict.get_item(9).unwrap().extract::<i32>().unwrap(), 18);
+    }
+
     #[test]
     fn test_from_sequence() {
         let gil = Python::acquire_gil();
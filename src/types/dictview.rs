@@ -0,0 +1,174 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{PyErr, PyResult};
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::internal_tricks::Unsendable;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyIterator, PySet};
+use crate::AsPyPointer;
+use crate::ToBorrowedObject;
+
+/// Represents the Python `dict_keys` view, as returned by `dict.keys()`.
+#[repr(transparent)]
+pub struct PyDictKeys(PyObject, Unsendable);
+
+/// Represents the Python `dict_values` view, as returned by `dict.values()`.
+#[repr(transparent)]
+pub struct PyDictValues(PyObject, Unsendable);
+
+/// Represents the Python `dict_items` view, as returned by `dict.items()`.
+#[repr(transparent)]
+pub struct PyDictItems(PyObject, Unsendable);
+
+pyobject_native_type!(PyDictKeys, ffi::PyDictKeys_Type, ffi::PyDictKeys_Check);
+pyobject_native_type!(
+    PyDictValues,
+    ffi::PyDictValues_Type,
+    ffi::PyDictValues_Check
+);
+pyobject_native_type!(PyDictItems, ffi::PyDictItems_Type, ffi::PyDictItems_Check);
+
+macro_rules! dict_view_common {
+    ($name: ident) => {
+        impl $name {
+            /// Returns the number of entries in the underlying dictionary.
+            pub fn len(&self) -> usize {
+                unsafe { ffi::PyObject_Size(self.as_ptr()) as usize }
+            }
+
+            /// Checks if the underlying dictionary is empty.
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// Determines if the view contains `value`.
+            /// This is equivalent to the Python expression `value in self`.
+            pub fn contains<V>(&self, value: V) -> PyResult<bool>
+            where
+                V: ToBorrowedObject,
+            {
+                value.with_borrowed_ptr(self.py(), |ptr| unsafe {
+                    match ffi::PySequence_Contains(self.as_ptr(), ptr) {
+                        1 => Ok(true),
+                        0 => Ok(false),
+                        _ => Err(PyErr::fetch(self.py())),
+                    }
+                })
+            }
+
+            /// Returns an iterator over the view, reflecting later changes of the dictionary.
+            pub fn iter(&self) -> PyResult<PyIterator> {
+                ObjectProtocol::iter(self)
+            }
+        }
+    };
+}
+
+macro_rules! dict_view_set_ops {
+    ($name: ident) => {
+        impl $name {
+            /// Equivalent to the Python expression `self | other`.
+            pub fn union(&self, other: &PyAny) -> PyResult<&PySet> {
+                self.set_op(other, ffi::PyNumber_Or)
+            }
+
+            /// Equivalent to the Python expression `self & other`.
+            pub fn intersection(&self, other: &PyAny) -> PyResult<&PySet> {
+                self.set_op(other, ffi::PyNumber_And)
+            }
+
+            /// Equivalent to the Python expression `self - other`.
+            pub fn difference(&self, other: &PyAny) -> PyResult<&PySet> {
+                self.set_op(other, ffi::PyNumber_Subtract)
+            }
+
+            /// Equivalent to the Python expression `self ^ other`.
+            pub fn symmetric_difference(&self, other: &PyAny) -> PyResult<&PySet> {
+                self.set_op(other, ffi::PyNumber_Xor)
+            }
+
+            /// Returns `true` if the view has no elements in common with `other`.
+            pub fn isdisjoint(&self, other: &PyAny) -> PyResult<bool> {
+                self.call_method1("isdisjoint", (other,))?.extract()
+            }
+
+            fn set_op(
+                &self,
+                other: &PyAny,
+                op: unsafe extern "C" fn(
+                    *mut ffi::PyObject,
+                    *mut ffi::PyObject,
+                ) -> *mut ffi::PyObject,
+            ) -> PyResult<&PySet> {
+                unsafe {
+                    self.py()
+                        .from_owned_ptr_or_err(op(self.as_ptr(), other.as_ptr()))
+                }
+            }
+        }
+    };
+}
+
+dict_view_common!(PyDictKeys);
+dict_view_common!(PyDictValues);
+dict_view_common!(PyDictItems);
+dict_view_set_ops!(PyDictKeys);
+dict_view_set_ops!(PyDictItems);
+
+#[cfg(test)]
+mod test {
+    use super::{PyDictItems, PyDictKeys, PyDictValues};
+    use crate::types::{IntoPyDict, PyAny};
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_views() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = vec![("a", 1), ("b", 2)].into_py_dict(py);
+
+        let keys: &PyDictKeys = dict.keys_view();
+        assert_eq!(keys.len(), 2);
+        assert!(keys.contains("a").unwrap());
+        assert!(!keys.contains("c").unwrap());
+        let values: &PyDictValues = dict.values_view();
+        assert!(values.contains(2).unwrap());
+        let items: &PyDictItems = dict.items_view();
+        assert!(items.contains(("b", 2)).unwrap());
+
+        // views are live
+        dict.set_item("c", 3).unwrap();
+        assert_eq!(keys.len(), 3);
+        let collected: Vec<String> = keys.iter_extract().unwrap().map(Result::unwrap).collect();
+        assert_eq!(collected, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_set_ops() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = vec![("a", 1), ("b", 2)].into_py_dict(py);
+        let keys = dict.keys_view();
+        let other: &PyAny = py.eval("{'b', 'c'}", None, None).unwrap();
+
+        assert_eq!(keys.union(other).unwrap().len(), 3);
+        assert!(keys.intersection(other).unwrap().contains("b").unwrap());
+        assert!(keys.difference(other).unwrap().contains("a").unwrap());
+        assert_eq!(keys.symmetric_difference(other).unwrap().len(), 2);
+        assert!(!keys.isdisjoint(other).unwrap());
+
+        let extracted: &PyDictKeys = py
+            .eval("{1: 2}.keys()", None, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(extracted.len(), 1);
+        assert!(py
+            .eval("[1]", None, None)
+            .unwrap()
+            .extract::<&PyDictKeys>()
+            .is_err());
+    }
+}
@@ -4,10 +4,13 @@
 
 use crate::err::{PyDowncastError, PyErr, PyResult};
 use crate::ffi;
-use crate::instance::PyNativeType;
+use crate::gil::GILPool;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::object::PyObject;
 use crate::types::PyAny;
 use crate::AsPyPointer;
-use crate::Python;
+use crate::{FromPyObject, Python};
+use std::marker::PhantomData;
 
 /// A python iterator object.
 ///
@@ -86,6 +89,78 @@ impl<'p> Drop for PyIterator<'p> {
     }
 }
 
+/// An iterator that lazily extracts the items of a Python iterable as `T`.
+///
+/// Created by [ObjectProtocol::iter_extract](crate::ObjectProtocol::iter_extract).
+/// Each item is released as soon as it has been converted, so arbitrarily long
+/// (or infinite) generators can be consumed without growing the GIL pool.
+///
+/// # Example
+///
+/// ```rust
+/// # use pyo3::prelude::*;
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let naturals = py.eval("__import__('itertools').count()", None, None).unwrap();
+/// let evens: Vec<u64> = naturals
+///     .iter_extract::<u64>()
+///     .unwrap()
+///     .map(Result::unwrap)
+///     .filter(|n| n % 2 == 0)
+///     .take(3)
+///     .collect();
+/// assert_eq!(evens, vec![0, 2, 4]);
+/// ```
+pub struct PyExtractIterator<'p, T> {
+    iter: PyIterator<'p>,
+    _marker: PhantomData<T>,
+}
+
+impl<'p, T> PyExtractIterator<'p, T>
+where
+    T: for<'a> FromPyObject<'a>,
+{
+    /// Constructs a `PyExtractIterator` over any iterable object.
+    pub fn from_object<O>(py: Python<'p>, obj: &O) -> Result<Self, PyDowncastError>
+    where
+        O: AsPyPointer,
+    {
+        Ok(PyExtractIterator {
+            iter: PyIterator::from_object(py, obj)?,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'p, T> Iterator for PyExtractIterator<'p, T>
+where
+    T: for<'a> FromPyObject<'a>,
+{
+    type Item = PyResult<T>;
+
+    /// Retrieves and extracts the next item.
+    /// Returns `None` when the iterator is exhausted, and `Some(Err(..))` if
+    /// either the Python iterator or the conversion fails.
+    fn next(&mut self) -> Option<Self::Item> {
+        let py = self.iter.0.py();
+        // Objects created while extracting are only needed until `T` is built
+        let _pool = GILPool::new(py);
+
+        let ptr = unsafe { ffi::PyIter_Next(self.iter.0.as_ptr()) };
+        if ptr.is_null() {
+            if PyErr::occurred(py) {
+                Some(Err(PyErr::fetch(py)))
+            } else {
+                None
+            }
+        } else {
+            let item = unsafe { PyObject::from_owned_ptr(py, ptr) };
+            let item: &PyAny = &item.as_ref(py);
+            Some(T::extract(item))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::gil::GILPool;
@@ -94,7 +169,7 @@ mod tests {
     use crate::types::{PyDict, PyList};
     use crate::GILGuard;
     use crate::Python;
-    use crate::ToPyObject;
+    use crate::{PyResult, ToPyObject};
     use indoc::indoc;
 
     #[test]
@@ -185,4 +260,49 @@ mod tests {
             assert_eq!(actual, *expected)
         }
     }
+
+    #[test]
+    fn iter_extract_is_lazy() {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+        let locals = PyDict::new(py);
+        py.run(
+            "seen = []\ndef gen():\n    for i in range(100):\n        seen.append(i)\n        yield i\n",
+            Some(locals),
+            None,
+        )
+        .unwrap();
+        let gen = py.eval("gen()", Some(locals), None).unwrap();
+        let first: Vec<i32> = gen
+            .iter_extract::<i32>()
+            .unwrap()
+            .take(2)
+            .collect::<PyResult<_>>()
+            .unwrap();
+        assert_eq!(first, vec![0, 1]);
+        let seen = locals.get_item("seen").unwrap();
+        assert_eq!(seen.len().unwrap(), 2);
+
+        let mixed = py.eval("iter([1, 'a'])", None, None).unwrap();
+        let mut it = mixed.iter_extract::<i32>().unwrap();
+        assert_eq!(it.next().unwrap().unwrap(), 1);
+        assert!(it.next().unwrap().is_err());
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn iter_extract_releases_items() {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+        let list = PyList::new(py, &[vec![1, 2], vec![3]]);
+        let first = list.get_item(0);
+        let count = first.get_refcnt();
+        let total: usize = list
+            .iter_extract::<Vec<i32>>()
+            .unwrap()
+            .map(|v| v.unwrap().len())
+            .sum();
+        assert_eq!(total, 3);
+        assert_eq!(first.get_refcnt(), count);
+    }
 }
@@ -2,13 +2,16 @@
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
-use crate::err::{self, PyResult};
+use crate::err::{self, PyErr, PyResult};
+use crate::exceptions;
 use crate::ffi::{self, Py_ssize_t};
 use crate::instance::PyNativeType;
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
+use crate::type_object::PyTypeCreate;
 use crate::types::PyAny;
 use crate::IntoPyPointer;
+use crate::Py;
 use crate::Python;
 use crate::{AsPyPointer, IntoPy};
 use crate::{ToBorrowedObject, ToPyObject};
@@ -38,6 +41,26 @@ impl PyList {
         }
     }
 
+    /// Creates a list of new instances of the pyclass `T`, one for each value.
+    ///
+    /// This uses [Py::new_batch](crate::Py::new_batch), so it's faster than creating the
+    /// instances one by one, e.g. to return a large number of objects to Python.
+    pub fn from_pyclass_iter<T, I>(py: Python<'_>, values: I) -> PyResult<&PyList>
+    where
+        T: PyTypeCreate,
+        I: IntoIterator<Item = T>,
+    {
+        let objects = Py::new_batch(py, values)?;
+        unsafe {
+            let ptr = ffi::PyList_New(objects.len() as Py_ssize_t);
+            let list = py.from_owned_ptr_or_err::<PyList>(ptr)?;
+            for (i, obj) in objects.into_iter().enumerate() {
+                ffi::PyList_SetItem(ptr, i as Py_ssize_t, obj.into_ptr());
+            }
+            Ok(list)
+        }
+    }
+
     /// Construct a new empty list.
     pub fn empty(py: Python) -> &PyList {
         unsafe { py.from_owned_ptr::<PyList>(ffi::PyList_New(0)) }
@@ -113,6 +136,69 @@ impl PyList {
         })
     }
 
+    /// Returns a new list with the items of `other` appended to those of `self`.
+    /// Equivalent to python `self + other`.
+    pub fn concat(&self, other: &PyList) -> PyResult<&PyList> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PySequence_Concat(self.as_ptr(), other.as_ptr()))
+        }
+    }
+
+    /// Returns a new list with the items of `self` repeated `count` times.
+    /// Equivalent to python `self * count`.
+    pub fn repeat(&self, count: usize) -> PyResult<&PyList> {
+        let count = count.min(isize::max_value() as usize) as Py_ssize_t;
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PySequence_Repeat(self.as_ptr(), count))
+        }
+    }
+
+    /// Returns a new list with the items from `low` up to (but not including) `high`.
+    /// Indices past the end are clamped, like python `self[low:high]`.
+    pub fn get_slice(&self, low: usize, high: usize) -> &PyList {
+        let low = low.min(isize::max_value() as usize) as Py_ssize_t;
+        let high = high.min(isize::max_value() as usize) as Py_ssize_t;
+        unsafe {
+            self.py()
+                .from_owned_ptr(ffi::PyList_GetSlice(self.as_ptr(), low, high))
+        }
+    }
+
+    /// Returns the index of the first item equal to `value`.
+    /// Equivalent to python `self.index(value)`.
+    pub fn index<V>(&self, value: V) -> PyResult<usize>
+    where
+        V: ToBorrowedObject,
+    {
+        let r = value.with_borrowed_ptr(self.py(), |ptr| unsafe {
+            ffi::PySequence_Index(self.as_ptr(), ptr)
+        });
+        if r == -1 {
+            Err(PyErr::fetch(self.py()))
+        } else {
+            Ok(r as usize)
+        }
+    }
+
+    /// Returns the number of items equal to `value`.
+    /// Equivalent to python `self.count(value)`.
+    #[cfg(not(PyPy))]
+    pub fn count<V>(&self, value: V) -> PyResult<usize>
+    where
+        V: ToBorrowedObject,
+    {
+        let r = value.with_borrowed_ptr(self.py(), |ptr| unsafe {
+            ffi::PySequence_Count(self.as_ptr(), ptr)
+        });
+        if r == -1 {
+            Err(PyErr::fetch(self.py()))
+        } else {
+            Ok(r as usize)
+        }
+    }
+
     /// Returns an iterator over the tuple items.
     pub fn iter(&self) -> PyListIterator {
         PyListIterator {
@@ -126,6 +212,43 @@ impl PyList {
         unsafe { err::error_on_minusone(self.py(), ffi::PyList_Sort(self.as_ptr())) }
     }
 
+    /// Sorts the list in-place by a key computed in Rust.
+    ///
+    /// The key function is called once per item, and the first error it returns is propagated.
+    /// Like Python's `l.sort(key=...)`, the sort is stable, and fails with `ValueError` if the key
+    /// function modifies the list.
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::PyList;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let list = PyList::new(gil.python(), &["ccc", "a", "bb"]);
+    /// list.sort_by_key(|item| Ok(item.len()?)).unwrap();
+    /// assert_eq!(list.extract::<Vec<String>>().unwrap(), vec!["a", "bb", "ccc"]);
+    /// ```
+    pub fn sort_by_key<K, F>(&self, mut f: F) -> PyResult<()>
+    where
+        K: Ord,
+        F: FnMut(&PyAny) -> PyResult<K>,
+    {
+        // Hold strong references, as the items are released from the list while writing back
+        let items: Vec<PyObject> = self.iter().map(|item| item.into()).collect();
+        let mut keyed = Vec::with_capacity(items.len());
+        for item in items {
+            let key = f(unsafe { self.py().from_borrowed_ptr(item.as_ptr()) })?;
+            keyed.push((key, item));
+        }
+        if keyed.len() != self.len() {
+            return Err(exceptions::ValueError::py_err("list modified during sort"));
+        }
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        for (index, (_, item)) in keyed.into_iter().enumerate() {
+            self.set_item(index as isize, item)?;
+        }
+        Ok(())
+    }
+
     /// Reverses the list in-place. Equivalent to python `l.reverse()`
     pub fn reverse(&self) -> PyResult<()> {
         unsafe { err::error_on_minusone(self.py(), ffi::PyList_Reverse(self.as_ptr())) }
@@ -205,6 +328,7 @@ where
 
 #[cfg(test)]
 mod test {
+    use crate::exceptions;
     use crate::instance::AsPyRef;
     use crate::objectprotocol::ObjectProtocol;
     use crate::types::PyList;
@@ -423,4 +547,55 @@ mod test {
         assert_eq!(3, list.get_item(2).extract::<i32>().unwrap());
         assert_eq!(2, list.get_item(3).extract::<i32>().unwrap());
     }
+
+    #[test]
+    fn test_sequence_ops() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let a = PyList::new(py, &[1, 2, 3]);
+        let b = PyList::new(py, &[3, 4]);
+
+        let joined = a.concat(b).unwrap();
+        assert_eq!(vec![1, 2, 3, 3, 4], joined.extract::<Vec<i32>>().unwrap());
+        let repeated = b.repeat(2).unwrap();
+        assert_eq!(vec![3, 4, 3, 4], repeated.extract::<Vec<i32>>().unwrap());
+        assert_eq!(vec![2, 3], a.get_slice(1, 3).extract::<Vec<i32>>().unwrap());
+        assert_eq!(vec![3], a.get_slice(2, 100).extract::<Vec<i32>>().unwrap());
+
+        assert_eq!(2, joined.index(3).unwrap());
+        assert!(joined.index(42).is_err());
+        #[cfg(not(PyPy))]
+        assert_eq!(2, joined.count(3).unwrap());
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[(2, "b"), (1, "z"), (2, "a"), (0, "c")]);
+        list.sort_by_key(|item| item.get_item(0)?.extract::<i32>())
+            .unwrap();
+        assert_eq!(
+            list.extract::<Vec<(i32, String)>>().unwrap(),
+            vec![
+                (0, "c".to_string()),
+                (1, "z".to_string()),
+                (2, "b".to_string()),
+                (2, "a".to_string())
+            ]
+        );
+
+        let list = PyList::new(py, &[1, 2]);
+        assert!(list.sort_by_key(|item| item.extract::<String>()).is_err());
+        assert_eq!(list.extract::<Vec<i32>>().unwrap(), vec![1, 2]);
+
+        let list = PyList::new(py, &[1, 2]);
+        let err = list
+            .sort_by_key(|item| {
+                list.append(0)?;
+                item.extract::<i32>()
+            })
+            .unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+    }
 }
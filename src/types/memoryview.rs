@@ -0,0 +1,91 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+use crate::buffer::{Element, PyBuffer};
+use crate::err::PyResult;
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::internal_tricks::Unsendable;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::PyBytes;
+use crate::AsPyPointer;
+use crate::Python;
+
+/// Represents a Python `memoryview`.
+///
+/// Use [PyMemoryView::buffer] for access to the data without copying it, and
+/// [BufferExport](crate::buffer::BufferExport) for classes Python can create memoryviews of.
+#[repr(transparent)]
+pub struct PyMemoryView(PyObject, Unsendable);
+
+pyobject_native_type!(
+    PyMemoryView,
+    ffi::PyMemoryView_Type,
+    ffi::PyMemoryView_Check
+);
+
+impl PyMemoryView {
+    /// Creates a memoryview of an object supporting the buffer protocol, like
+    /// `memoryview(src)` in Python.
+    pub fn from<'p, I>(py: Python<'p>, src: &'p I) -> PyResult<&'p PyMemoryView>
+    where
+        I: AsPyPointer,
+    {
+        unsafe { py.from_owned_ptr_or_err(ffi::PyMemoryView_FromObject(src.as_ptr())) }
+    }
+
+    /// Gets the buffer of the memoryview, which gives access to its layout and data.
+    pub fn buffer(&self) -> PyResult<PyBuffer> {
+        PyBuffer::get(self.py(), self.as_ref())
+    }
+
+    /// Copies the items of the memoryview to a vector, in C order.
+    ///
+    /// Fails if the items of the memoryview aren't of type `T`.
+    pub fn to_vec<T: Element + Copy>(&self) -> PyResult<Vec<T>> {
+        self.buffer()?.to_vec(self.py())
+    }
+
+    /// Copies the data of the memoryview to a `bytes` object, like `memoryview.tobytes()`.
+    pub fn to_bytes(&self) -> PyResult<&PyBytes> {
+        Ok(self.call_method0("tobytes")?.downcast_ref()?)
+    }
+
+    /// Releases the buffer of the underlying object, like `memoryview.release()`.
+    ///
+    /// The memoryview can't be used afterwards. Fails if the buffer is still used, e.g. by a
+    /// `PyBuffer` obtained with [PyMemoryView::buffer].
+    pub fn release(&self) -> PyResult<()> {
+        self.call_method0("release")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions;
+    use crate::types::{PyByteArray, PyMemoryView};
+    use crate::Python;
+
+    #[test]
+    fn test_memoryview() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let bytearray = PyByteArray::new(py, b"abc");
+        let view = PyMemoryView::from(py, bytearray).unwrap();
+        assert_eq!(view.to_vec::<u8>().unwrap(), b"abc");
+        assert_eq!(view.to_bytes().unwrap().as_bytes(), b"abc");
+
+        let buffer = view.buffer().unwrap();
+        assert!(!buffer.readonly());
+        assert_eq!(buffer.shape(), [3]);
+        drop(buffer);
+
+        view.release().unwrap();
+        assert!(view.to_bytes().is_err());
+
+        let none = py.None();
+        let err = PyMemoryView::from(py, &none).unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+    }
+}
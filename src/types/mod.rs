@@ -12,17 +12,23 @@ pub use self::datetime::{
     PyDate, PyDateAccess, PyDateTime, PyDelta, PyTime, PyTimeAccess, PyTzInfo,
 };
 pub use self::dict::{IntoPyDict, PyDict};
+pub use self::dictview::{PyDictItems, PyDictKeys, PyDictValues};
 pub use self::floatob::PyFloat;
-pub use self::iterator::PyIterator;
+pub use self::iterator::{PyExtractIterator, PyIterator};
 pub use self::list::PyList;
-pub use self::module::PyModule;
+pub use self::memoryview::PyMemoryView;
+pub use self::module::{ModuleInitFailureHook, PyModule};
 pub use self::num::PyLong;
 pub use self::num::PyLong as PyInt;
+pub use self::number::PyNumber;
 pub use self::sequence::PySequence;
 pub use self::set::{PyFrozenSet, PySet};
+pub use self::singletons::{PyEllipsis, PyNone, PyNotImplemented};
 pub use self::slice::{PySlice, PySliceIndices};
 pub use self::string::{PyString, PyString as PyUnicode};
 pub use self::tuple::PyTuple;
+pub use self::typed_dict::{PyTypedDict, PyTypedDictIterator};
+pub use self::typed_list::{PyTypedList, PyTypedListIterator};
 pub use self::typeobject::PyType;
 
 /// Implements a typesafe conversions throught [FromPyObject], given a typecheck function as second
@@ -168,17 +174,30 @@ mod any;
 mod boolobject;
 mod bytearray;
 mod bytes;
+#[cfg(feature = "chrono")]
+mod chrono_conversion;
 mod complex;
 mod datetime;
 mod dict;
+mod dictview;
 mod floatob;
 mod iterator;
 mod list;
-mod module;
+mod memoryview;
+pub(crate) mod module;
 mod num;
+mod number;
+mod path;
 mod sequence;
 mod set;
+mod singletons;
 mod slice;
 mod string;
+#[cfg(feature = "toml")]
+mod toml_conversion;
 mod tuple;
-mod typeobject;
+mod typed_dict;
+mod typed_list;
+pub(crate) mod typeobject;
+#[cfg(feature = "serde_yaml")]
+mod yaml_conversion;
@@ -2,26 +2,39 @@
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
+use crate::cache::PyStatic;
+use crate::enums::PyEnum;
 use crate::err::{PyErr, PyResult};
 use crate::exceptions;
 use crate::ffi;
-use crate::instance::PyNativeType;
+use crate::instance::{AsPyRef, PyNativeType};
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
 use crate::objectprotocol::ObjectProtocol;
 use crate::type_object::PyTypeCreate;
-use crate::type_object::PyTypeObject;
 use crate::types::PyTuple;
-use crate::types::{PyAny, PyDict, PyList};
+use crate::types::{PyAny, PyDict, PyList, PyType};
 use crate::AsPyPointer;
 use crate::IntoPy;
+use crate::IntoPyPointer;
 use crate::Py;
 use crate::Python;
 use crate::ToPyObject;
+use std::any::TypeId;
 use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
 use std::str;
 
+/// A function called when the initializer of a `#[pymodule]` returns an error.
+///
+/// It receives the name of the module and the original error, before that error is
+/// wrapped in an `ImportError`.
+pub type ModuleInitFailureHook = fn(Python, &str, &PyErr);
+
+// Only accessed while holding the GIL
+static mut INIT_FAILURE_HOOK: Option<ModuleInitFailureHook> = None;
+
 /// Represents a Python `module` object.
 #[repr(transparent)]
 pub struct PyModule(PyObject, Unsendable);
@@ -41,6 +54,45 @@ impl PyModule {
         unsafe { py.from_owned_ptr_or_err(ffi::PyImport_ImportModule(name.as_ptr())) }
     }
 
+    /// Returns a module which imports the module with the specified name when one of its
+    /// attributes is first accessed, like a module loaded with `importlib.util.LazyLoader`.
+    ///
+    /// This defers the cost of slow imports until a module is used, e.g. when embedding
+    /// Python. Finding the module happens immediately, so missing modules raise
+    /// `ModuleNotFoundError` here, and the parent packages of dotted names are imported.
+    /// Errors raised by the module's code are raised by the attribute access triggering the
+    /// import. Modules which have already been imported are returned as they are.
+    ///
+    /// [PyModule::dict] returns the dict of the module without importing it, so use attribute
+    /// access, e.g. [PyModule::get], to force the import.
+    pub fn import_lazy<'p>(py: Python<'p>, name: &str) -> PyResult<&'p PyModule> {
+        let modules = py.import("sys")?.get("modules")?;
+        if let Ok(module) = modules.get_item(name) {
+            return Ok(module.downcast_ref()?);
+        }
+
+        let util = py.import("importlib.util")?;
+        let spec = util.call1("find_spec", (name,))?;
+        if spec.is_none() {
+            return Err(exceptions::ModuleNotFoundError::py_err(format!(
+                "No module named '{}'",
+                name
+            )));
+        }
+        let loader = util.call1("LazyLoader", (spec.getattr("loader")?,))?;
+        spec.setattr("loader", loader)?;
+        let module = util.call1("module_from_spec", (spec,))?;
+        modules.set_item(name, module)?;
+        loader.call_method1("exec_module", (module,))?;
+        // Like the import statement, make submodules attributes of their package
+        if let Some(pos) = name.rfind('.') {
+            modules
+                .get_item(&name[..pos])?
+                .setattr(&name[pos + 1..], module)?;
+        }
+        Ok(module.downcast_ref()?)
+    }
+
     /// Loads the python code specified into a new module
     /// 'code' is the raw Python you want to load into the module
     /// 'file_name' is the file name to associate with the module
@@ -76,7 +128,7 @@ impl PyModule {
     pub fn dict(&self) -> &PyDict {
         unsafe {
             self.py()
-                .from_owned_ptr::<PyDict>(ffi::PyModule_GetDict(self.as_ptr()))
+                .from_borrowed_ptr::<PyDict>(ffi::PyModule_GetDict(self.as_ptr()))
         }
     }
 
@@ -124,6 +176,29 @@ impl PyModule {
         unsafe { self.str_from_ptr(ffi::PyModule_GetFilename(self.as_ptr())) }
     }
 
+    /// Returns the state of a module declared with `#[pyo3(multi_phase, state = "T")]`, which
+    /// every module object, i.e. every interpreter importing the module, has its own copy of.
+    ///
+    /// Fails with a `TypeError` if the module has no state of type `T`.
+    pub fn state<T: 'static>(&self) -> PyResult<&T> {
+        unsafe {
+            let def = ffi::PyModule_GetDef(self.as_ptr());
+            let state = ffi::PyModule_GetState(self.as_ptr()) as *const ModuleState<T>;
+            if !def.is_null()
+                && !state.is_null()
+                && (*def).m_size as usize == std::mem::size_of::<ModuleState<T>>()
+                && (*state).is_valid()
+            {
+                return Ok(&(*state).value);
+            }
+        }
+        Err(exceptions::TypeError::py_err(format!(
+            "Module {} has no state of type {}",
+            self.name()?,
+            std::any::type_name::<T>()
+        )))
+    }
+
     /// Calls a function in the module.
     /// This is equivalent to the Python expression: `getattr(module, name)(*args, **kwargs)`
     pub fn call(
@@ -171,11 +246,62 @@ impl PyModule {
     /// This is a convenience function that initializes the `class`,
     /// sets `new_type.__module__` to this module's name,
     /// and adds the type to this module.
+    ///
+    /// Fails with a `TypeError` if the class does not provide all members of a protocol
+    /// declared with `#[pyclass(implements = "...")]`.
+    ///
+    /// The type objects of `#[pyclass]` types are static, so they would be shared by all
+    /// interpreters; adding a class to a module of a subinterpreter, e.g. one using
+    /// `#[pyo3(multi_phase)]`, fails with an `ImportError`. Enums added with
+    /// [add_enum](PyModule::add_enum) are created per interpreter and work in subinterpreters.
     pub fn add_class<T>(&self) -> PyResult<()>
     where
         T: PyTypeCreate,
     {
-        self.add(T::NAME, <T as PyTypeObject>::type_object())
+        if crate::cache::interpreter_id(self.py()) != 0 {
+            return Err(exceptions::ImportError::py_err(format!(
+                "#[pyclass] {} can not be added to a module of a subinterpreter",
+                T::NAME
+            )));
+        }
+        let ty = self.py().get_type::<T>();
+        for protocol in T::PROTOCOLS {
+            ty.check_protocol(protocol)?;
+        }
+        self.add(T::NAME, ty)
+    }
+
+    /// Adds a fieldless enum marked with `#[pyclass]` to the module, as an `enum.IntEnum`.
+    pub fn add_enum<T>(&self) -> PyResult<()>
+    where
+        T: PyEnum,
+    {
+        self.add(T::NAME, T::type_object(self.py()))
+    }
+
+    /// Adds exception types to the module under their names, e.g. a hierarchy defined with
+    /// [create_exception!](crate::create_exception).
+    ///
+    /// ```rust
+    /// use pyo3::create_exception;
+    /// use pyo3::exceptions::Exception;
+    /// use pyo3::prelude::*;
+    ///
+    /// create_exception!(mymodule, MyError, Exception, "The base of the errors of mymodule.");
+    /// create_exception!(mymodule, MyTimeoutError, MyError);
+    ///
+    /// #[pymodule]
+    /// fn mymodule(py: Python, m: &PyModule) -> PyResult<()> {
+    ///     m.add_exceptions(&[py.get_type::<MyError>(), py.get_type::<MyTimeoutError>()])
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn add_exceptions(&self, types: &[&PyType]) -> PyResult<()> {
+        for ty in types {
+            let name: &str = ty.getattr("__name__")?.extract()?;
+            self.add(name, *ty)?;
+        }
+        Ok(())
     }
 
     /// Adds a function or a (sub)module to a module, using the functions __name__ as name.
@@ -193,11 +319,330 @@ impl PyModule {
     /// ```rust,ignore
     /// m.add("also_double", wrap_pyfunction!(double)(py));
     /// ```
+    ///
+    /// If the wrapper returns `None`, e.g. for a function marked with
+    /// `#[pyo3(min_python = "...")]` on an older interpreter, nothing is added and `Ok(())` is
+    /// returned: the item is silently missing from the module.
     pub fn add_wrapped(&self, wrapper: &impl Fn(Python) -> PyObject) -> PyResult<()> {
         let function = wrapper(self.py());
+        if function.is_none() {
+            return Ok(());
+        }
+        if let Ok(module) = function.as_ref(self.py()).downcast_ref::<PyModule>() {
+            return self.add_submodule(module);
+        }
         let name = function
             .getattr(self.py(), "__name__")
             .expect("A function or module must have a __name__");
         self.add(name.extract(self.py()).unwrap(), function)
     }
+
+    /// Adds `module` to this module as a submodule, so it can be imported from Python.
+    ///
+    /// The submodule is renamed to `<this module>.<submodule>`, its `__package__` is set to this
+    /// module's name and it's registered in `sys.modules`, so `import package.submodule` and
+    /// `from package.submodule import item` work. Submodules of `module` which were added before
+    /// are renamed and registered again.
+    ///
+    /// [add_wrapped](PyModule::add_wrapped) uses this for modules created with
+    /// [wrap_pymodule!].
+    pub fn add_submodule(&self, module: &PyModule) -> PyResult<()> {
+        let name = self.register_submodule(module)?;
+        self.add(&name, module)
+    }
+
+    /// Renames and registers `module` as a submodule of this module, returning its short name.
+    fn register_submodule(&self, module: &PyModule) -> PyResult<String> {
+        let py = self.py();
+        let old_name = module.name()?.to_string();
+        let short_name = old_name.rsplit('.').next().unwrap().to_string();
+        let package = self.name()?;
+        let name = format!("{}.{}", package, short_name);
+
+        module.setattr("__name__", &name)?;
+        module.setattr("__package__", package)?;
+        let modules: &PyDict = py.import("sys")?.getattr("modules")?.downcast_ref()?;
+        if let Some(stale) = modules.get_item(&old_name) {
+            if stale.as_ptr() == module.as_ptr() {
+                modules.del_item(&old_name)?;
+            }
+        }
+        modules.set_item(&name, module)?;
+
+        let children: Vec<&PyModule> = module
+            .dict()
+            .iter()
+            .filter_map(|(attr, value)| {
+                let child = value.downcast_ref::<PyModule>().ok()?;
+                let child_name = format!("{}.{}", old_name, attr);
+                match child.name() {
+                    Ok(n) if n == child_name => Some(child),
+                    _ => None,
+                }
+            })
+            .collect();
+        for child in children {
+            module.register_submodule(child)?;
+        }
+        Ok(short_name)
+    }
+
+    /// Makes this module a package which spans the `<name>` directories of the import path, like
+    /// [`pkgutil.extend_path`](https://docs.python.org/3/library/pkgutil.html#pkgutil.extend_path)
+    /// does in the `__init__.py` of a Python package.
+    ///
+    /// An extension module has no `__path__`, so no submodules can be imported from it. This sets
+    /// `__path__` to every directory named like the module on `sys.path`, or on the `__path__` of
+    /// the parent package for a dotted name, so that modules installed into such a directory by
+    /// other distributions, extension modules of other crates as well as Python files, are found.
+    /// Entries already in `__path__` are kept, so it can be called again after `sys.path` changed.
+    ///
+    /// ```rust
+    /// use pyo3::prelude::*;
+    ///
+    /// // Installed as `company.so`, while other crates install `company/foo.so`
+    /// #[pymodule]
+    /// fn company(_py: Python, m: &PyModule) -> PyResult<()> {
+    ///     m.extend_path()
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn extend_path(&self) -> PyResult<()> {
+        let py = self.py();
+        let name = self.name()?;
+        let (search_path, last) = match name.rfind('.') {
+            Some(dot) => {
+                let modules = py.import("sys")?.get("modules")?;
+                let parent = modules.get_item(&name[..dot])?;
+                (parent.getattr("__path__")?, &name[dot + 1..])
+            }
+            None => (py.import("sys")?.get("path")?, name),
+        };
+
+        let path = match self.getattr("__path__") {
+            Ok(path) => path.downcast_ref::<PyList>()?,
+            Err(_) => {
+                let path = PyList::empty(py);
+                self.setattr("__path__", path)?;
+                path
+            }
+        };
+        let mut dirs: Vec<String> = path.extract()?;
+        let os_path = py.import("os.path")?;
+        for entry in search_path.iter()? {
+            // `sys.path` may contain other objects than strings, which are skipped by imports
+            let entry = match entry?.extract::<&str>() {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let dir: String = os_path.call1("join", (entry, last))?.extract()?;
+            if !dirs.contains(&dir) && os_path.call1("isdir", (&dir,))?.is_true()? {
+                path.append(&dir)?;
+                dirs.push(dir);
+            }
+        }
+
+        // With multi-phase initialization, the spec is already set
+        if let Ok(spec) = self.getattr("__spec__") {
+            if !spec.is_none() {
+                spec.setattr("submodule_search_locations", path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a member to the module if `condition` is true.
+    ///
+    /// This keeps optional functionality (cargo features, runtime detection) out of the
+    /// control flow of the module's initialization function.
+    pub fn add_if<V>(&self, condition: bool, name: &str, value: V) -> PyResult<()>
+    where
+        V: ToPyObject,
+    {
+        if condition {
+            self.add(name, value)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds a new extension type to the module if `condition` is true.
+    ///
+    /// ```rust,ignore
+    /// m.add_class_if::<Gpu>(gpu_available())?;
+    /// ```
+    pub fn add_class_if<T>(&self, condition: bool) -> PyResult<()>
+    where
+        T: PyTypeCreate,
+    {
+        if condition {
+            self.add_class::<T>()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds a function or a (sub)module to the module if `condition` is true.
+    /// See [add_wrapped](PyModule::add_wrapped).
+    pub fn add_wrapped_if(
+        &self,
+        condition: bool,
+        wrapper: &impl Fn(Python) -> PyObject,
+    ) -> PyResult<()> {
+        if condition {
+            self.add_wrapped(wrapper)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds `old_name` as a deprecated alias of the callable `target`.
+    ///
+    /// Calling the alias emits a `DeprecationWarning` with `message`, attributed to the caller,
+    /// and then calls `target` with the same arguments. The alias isn't added to `__all__`, so
+    /// `from module import *` doesn't pick it up. Calling a class through an alias creates
+    /// instances of it, but the alias can't be used with `isinstance` or as a base class.
+    pub fn add_deprecated<V>(&self, old_name: &str, target: V, message: &str) -> PyResult<()>
+    where
+        V: ToPyObject,
+    {
+        let py = self.py();
+        static DEPRECATED: PyStatic = PyStatic::new();
+        let deprecated = DEPRECATED.get_or_try_init(py, || {
+            PyModule::from_code(py, DEPRECATED_CODE, "pyo3_deprecated.py", "pyo3_deprecated")
+                .and_then(|module| module.get("deprecated"))
+                .map(Into::into)
+        })?;
+        let function = deprecated.call1((target.to_object(py), old_name, self.name()?, message))?;
+        self.setattr(old_name, function)
+    }
+
+    /// Adds deprecated aliases for renamed or moved members of the module, given as pairs of
+    /// the old and the new name. See [add_deprecated](PyModule::add_deprecated).
+    ///
+    /// The new names are looked up when the aliases are added, so add the members first. They
+    /// may be dotted paths relative to this module, e.g. `"io.read_file"` for a function moved
+    /// to the submodule `io`. The warning names the version the alias was deprecated in:
+    ///
+    /// ```rust,ignore
+    /// m.add_deprecated_aliases("2.0", &[("loads", "parse"), ("read", "io.read_file")])?;
+    /// ```
+    pub fn add_deprecated_aliases(&self, since: &str, aliases: &[(&str, &str)]) -> PyResult<()> {
+        let module_name = self.name()?;
+        for (old_name, new_name) in aliases {
+            let mut path = new_name.split('.');
+            let mut target = self.getattr(path.next().unwrap())?;
+            for attr in path {
+                target = target.getattr(attr)?;
+            }
+            let message = format!(
+                "{module}.{} is deprecated since {}, use {module}.{} instead",
+                old_name,
+                since,
+                new_name,
+                module = module_name
+            );
+            self.add_deprecated(old_name, target, &message)?;
+        }
+        Ok(())
+    }
+
+    /// Sets a hook that is called whenever a `#[pymodule]` fails to initialize,
+    /// e.g. to log the error before Python turns it into a failed import.
+    ///
+    /// Replaces any previously set hook; pass `None` to remove it.
+    pub fn set_init_failure_hook(_py: Python, hook: Option<ModuleInitFailureHook>) {
+        unsafe { INIT_FAILURE_HOOK = hook }
+    }
+}
+
+/// The state of a module declared with `#[pyo3(multi_phase, state = "T")]`, stored in the memory
+/// Python allocates for each module object (`m_size`).
+#[repr(C)]
+pub(crate) struct ModuleState<T> {
+    /// Distinguishes the states of PyO3 modules from the ones of other extension modules
+    magic: u64,
+    /// Python zeroes the memory, so this is false until the exec slot created the value
+    initialized: bool,
+    type_id: TypeId,
+    value: T,
+}
+
+const MODULE_STATE_MAGIC: u64 = 0x7079_6f33_7374_6174;
+
+impl<T: 'static> ModuleState<T> {
+    fn is_valid(&self) -> bool {
+        self.magic == MODULE_STATE_MAGIC && self.initialized && self.type_id == TypeId::of::<T>()
+    }
+
+    /// Writes a new state into the zeroed memory of `module`.
+    pub(crate) unsafe fn init(module: *mut ffi::PyObject, value: T) {
+        let state = ffi::PyModule_GetState(module) as *mut ModuleState<T>;
+        if !state.is_null() {
+            ptr::write(
+                state,
+                ModuleState {
+                    magic: MODULE_STATE_MAGIC,
+                    initialized: true,
+                    type_id: TypeId::of::<T>(),
+                    value,
+                },
+            );
+        }
+    }
+
+    /// The `m_free` function of the module definition, dropping the state.
+    pub(crate) unsafe extern "C" fn free(module: *mut c_void) {
+        let state = ffi::PyModule_GetState(module as *mut ffi::PyObject) as *mut ModuleState<T>;
+        if !state.is_null() && (*state).initialized {
+            (*state).initialized = false;
+            ptr::drop_in_place(&mut (*state).value);
+        }
+    }
+}
+
+/// Defines the aliases added by [PyModule::add_deprecated] in Python, so that they carry the
+/// name, module and docstring of the alias.
+const DEPRECATED_CODE: &str = r#"
+import warnings
+
+def deprecated(target, name, module, message):
+    def alias(*args, **kwargs):
+        warnings.warn(message, DeprecationWarning, stacklevel=2)
+        return target(*args, **kwargs)
+    alias.__name__ = alias.__qualname__ = name
+    alias.__module__ = module
+    alias.__doc__ = message
+    return alias
+"#;
+
+/// Converts an error returned by the initializer of module `name` into an `ImportError`
+/// naming the module, with the original exception as its `__cause__`.
+pub(crate) fn init_failure_error(py: Python, name: &str, err: PyErr) -> PyErr {
+    if let Some(hook) = unsafe { INIT_FAILURE_HOOK } {
+        hook(py, name, &err);
+    }
+
+    let cause: PyObject = err.into_py(py);
+    let message = format!(
+        "failed to initialize module '{}': {}",
+        name,
+        cause
+            .as_ref(py)
+            .str()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "<unprintable error>".to_string())
+    );
+    let kwargs = PyDict::new(py);
+    let result = kwargs.set_item("name", name).and_then(|_| {
+        py.get_type::<exceptions::ImportError>()
+            .call((message,), Some(kwargs))
+    });
+    match result {
+        Ok(import_error) => {
+            unsafe { ffi::PyException_SetCause(import_error.as_ptr(), cause.into_ptr()) };
+            PyErr::from_instance(import_error)
+        }
+        Err(e) => e,
+    }
 }
@@ -100,6 +100,7 @@ macro_rules! int_convert_128 {
                         IS_LITTLE_ENDIAN,
                         $is_signed,
                     );
+                    ffi::Py_DECREF(num);
                     if ok == -1 {
                         Err(PyErr::fetch(ob.py()))
                     } else {
@@ -243,33 +244,30 @@ mod bigint_conversion {
     use super::*;
     use num_bigint::{BigInt, BigUint};
 
-    unsafe fn extract_small(ob: &PyAny, n: usize, is_signed: c_int) -> PyResult<[c_uchar; 128]> {
-        let buffer = [0; 128];
-        let ok = ffi::_PyLong_AsByteArray(
-            ob.as_ptr() as *mut ffi::PyLongObject,
-            buffer.as_ptr() as *const c_uchar,
-            n,
-            1,
-            is_signed,
-        );
-        if ok == -1 {
-            Err(PyErr::fetch(ob.py()))
+    /// Converts `num`, an `int`, to the minimal number of bytes in little endian order.
+    unsafe fn int_to_bytes(
+        py: Python,
+        num: *mut ffi::PyObject,
+        is_signed: c_int,
+    ) -> PyResult<Vec<c_uchar>> {
+        let n_bits = ffi::_PyLong_NumBits(num);
+        let n_bytes = if n_bits < 0 {
+            return Err(PyErr::fetch(py));
+        } else if n_bits == 0 {
+            0
         } else {
-            Ok(buffer)
-        }
-    }
-
-    unsafe fn extract_large(ob: &PyAny, n: usize, is_signed: c_int) -> PyResult<Vec<c_uchar>> {
-        let buffer = vec![0; n];
+            (n_bits as usize - 1 + is_signed as usize) / 8 + 1
+        };
+        let buffer = vec![0; n_bytes];
         let ok = ffi::_PyLong_AsByteArray(
-            ob.as_ptr() as *mut ffi::PyLongObject,
+            num as *mut ffi::PyLongObject,
             buffer.as_ptr() as *const c_uchar,
-            n,
+            n_bytes,
             1,
             is_signed,
         );
         if ok == -1 {
-            Err(PyErr::fetch(ob.py()))
+            Err(PyErr::fetch(py))
         } else {
             Ok(buffer)
         }
@@ -303,20 +301,10 @@ mod bigint_conversion {
                         if num.is_null() {
                             return Err(PyErr::fetch(ob.py()));
                         }
-                        let n_bits = ffi::_PyLong_NumBits(num);
-                        let n_bytes = if n_bits < 0 {
-                            return Err(PyErr::fetch(ob.py()));
-                        } else if n_bits == 0 {
-                            0
-                        } else {
-                            (n_bits as usize - 1 + $is_signed) / 8 + 1
-                        };
-                        if n_bytes <= 128 {
-                            extract_small(ob, n_bytes, $is_signed)
-                                .map(|b| $from_bytes(&b[..n_bytes]))
-                        } else {
-                            extract_large(ob, n_bytes, $is_signed).map(|b| $from_bytes(&b))
-                        }
+                        // `num` is `ob` if it is an `int`, or the result of `__index__`
+                        let bytes = int_to_bytes(ob.py(), num, $is_signed);
+                        ffi::Py_DECREF(num);
+                        bytes.map(|bytes| $from_bytes(&bytes))
                     }
                 }
             }
@@ -334,6 +322,7 @@ mod bigint_conversion {
     mod test {
         use super::*;
         use crate::types::{PyDict, PyModule};
+        use crate::ObjectProtocol;
         use indoc::indoc;
         use num_traits::{One, Zero};
 
@@ -422,6 +411,31 @@ mod bigint_conversion {
             assert_eq!(zero, BigInt::from(0));
         }
 
+        #[test]
+        fn extract_index() {
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            let fib = python_fib(py);
+            let large = fib.call1("fib_neg", (400,)).unwrap();
+            let locals = PyDict::new(py);
+            locals.set_item("large", large).unwrap();
+            py.run(
+                "class Index:\n    def __index__(self):\n        return large",
+                Some(locals),
+                None,
+            )
+            .unwrap();
+            let index = py.eval("Index()", Some(locals), None).unwrap();
+            let value: BigInt = index.extract().unwrap();
+            assert_eq!(value, rust_fib::<BigInt>(400) * -1);
+
+            // The result of `PyNumber_Index` is released
+            let refcnt = large.get_refcnt();
+            let _: BigInt = large.extract().unwrap();
+            let _: BigInt = index.extract().unwrap();
+            assert_eq!(large.get_refcnt(), refcnt);
+        }
+
         /// `OverflowError` on converting python int to BigInt, see issue #629
         #[test]
         fn check_overflow() {
@@ -538,6 +552,17 @@ mod test {
         assert!(obj.extract::<i128>(py).is_err());
     }
 
+    #[test]
+    #[cfg(not(Py_LIMITED_API))]
+    fn test_i128_refcnt() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = std::i128::MAX.to_object(py);
+        let refcnt = obj.get_refcnt();
+        obj.extract::<i128>(py).unwrap();
+        assert_eq!(obj.get_refcnt(), refcnt);
+    }
+
     #[test]
     #[cfg(not(Py_LIMITED_API))]
     fn test_u128_overflow() {
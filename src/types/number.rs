@@ -0,0 +1,223 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{PyDowncastError, PyResult};
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::internal_tricks::Unsendable;
+use crate::object::PyObject;
+use crate::types::PyAny;
+use crate::AsPyPointer;
+use crate::{Python, ToBorrowedObject, ToPyObject};
+
+/// Represents a reference to a python object supporting the number protocol.
+///
+/// Obtained with [PyAny::as_number](crate::types::PyAny::as_number), this allows arithmetic
+/// on arbitrary Python numbers (`int`, `float`, `fractions.Fraction`, numpy scalars, ...).
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let x = py.eval("3", None, None).unwrap().as_number().unwrap();
+/// let y = x.mul(x).unwrap().as_number().unwrap().add(1).unwrap();
+/// assert_eq!(y.extract::<i32>().unwrap(), 10);
+/// ```
+#[repr(transparent)]
+pub struct PyNumber(PyObject, Unsendable);
+pyobject_native_type_named!(PyNumber);
+
+impl PyNumber {
+    fn binary_op<O>(
+        &self,
+        other: O,
+        op: unsafe extern "C" fn(*mut ffi::PyObject, *mut ffi::PyObject) -> *mut ffi::PyObject,
+    ) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        other.with_borrowed_ptr(self.py(), |other| unsafe {
+            self.py().from_owned_ptr_or_err(op(self.as_ptr(), other))
+        })
+    }
+
+    fn unary_op(
+        &self,
+        op: unsafe extern "C" fn(*mut ffi::PyObject) -> *mut ffi::PyObject,
+    ) -> PyResult<&PyAny> {
+        unsafe { self.py().from_owned_ptr_or_err(op(self.as_ptr())) }
+    }
+
+    /// Equivalent to the Python expression `self + other`.
+    #[inline]
+    pub fn add<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        self.binary_op(other, ffi::PyNumber_Add)
+    }
+
+    /// Equivalent to the Python expression `self - other`.
+    #[inline]
+    pub fn sub<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        self.binary_op(other, ffi::PyNumber_Subtract)
+    }
+
+    /// Equivalent to the Python expression `self * other`.
+    #[inline]
+    pub fn mul<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        self.binary_op(other, ffi::PyNumber_Multiply)
+    }
+
+    /// Equivalent to the Python expression `self / other`.
+    #[inline]
+    pub fn truediv<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        self.binary_op(other, ffi::PyNumber_TrueDivide)
+    }
+
+    /// Equivalent to the Python expression `self // other`.
+    #[inline]
+    pub fn floordiv<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        self.binary_op(other, ffi::PyNumber_FloorDivide)
+    }
+
+    /// Equivalent to the Python expression `self % other`.
+    #[inline]
+    pub fn rem<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        self.binary_op(other, ffi::PyNumber_Remainder)
+    }
+
+    /// Equivalent to the Python expression `divmod(self, other)`.
+    #[inline]
+    pub fn divmod<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        self.binary_op(other, ffi::PyNumber_Divmod)
+    }
+
+    /// Equivalent to the Python expression `self ** exponent`.
+    #[inline]
+    pub fn pow<O>(&self, exponent: O) -> PyResult<&PyAny>
+    where
+        O: ToBorrowedObject,
+    {
+        exponent.with_borrowed_ptr(self.py(), |exponent| unsafe {
+            self.py().from_owned_ptr_or_err(ffi::PyNumber_Power(
+                self.as_ptr(),
+                exponent,
+                ffi::Py_None(),
+            ))
+        })
+    }
+
+    /// Equivalent to the Python expression `-self`.
+    #[inline]
+    pub fn neg(&self) -> PyResult<&PyAny> {
+        self.unary_op(ffi::PyNumber_Negative)
+    }
+
+    /// Equivalent to the Python expression `+self`.
+    #[inline]
+    pub fn pos(&self) -> PyResult<&PyAny> {
+        self.unary_op(ffi::PyNumber_Positive)
+    }
+
+    /// Equivalent to the Python expression `abs(self)`.
+    #[inline]
+    pub fn abs(&self) -> PyResult<&PyAny> {
+        self.unary_op(ffi::PyNumber_Absolute)
+    }
+
+    /// Equivalent to the Python expression `~self`.
+    #[inline]
+    pub fn invert(&self) -> PyResult<&PyAny> {
+        self.unary_op(ffi::PyNumber_Invert)
+    }
+}
+
+impl ToPyObject for PyNumber {
+    #[inline]
+    fn to_object(&self, py: Python) -> PyObject {
+        unsafe { PyObject::from_borrowed_ptr(py, self.as_ptr()) }
+    }
+}
+
+impl PyNumber {
+    /// Casts `value` to a `PyNumber` if it implements the number protocol.
+    ///
+    /// `PyNumber` is a view of any object supporting `PyNumber_Check`, so unlike types
+    /// implementing `PyTryFrom` it can't be borrowed mutably.
+    pub fn try_from<'v, V: Into<&'v PyAny>>(value: V) -> Result<&'v PyNumber, PyDowncastError> {
+        let value = value.into();
+        unsafe {
+            if ffi::PyNumber_Check(value.as_ptr()) != 0 {
+                Ok(&*(value as *const PyAny as *const PyNumber))
+            } else {
+                Err(PyDowncastError)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::instance::AsPyRef;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::PyNumber;
+    use crate::{Python, ToPyObject};
+
+    #[test]
+    fn test_arithmetic() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let seven = 7.to_object(py);
+        let seven = seven.as_ref(py);
+        let seven = seven.as_number().unwrap();
+
+        assert_eq!(seven.add(2).unwrap().extract::<i32>().unwrap(), 9);
+        assert_eq!(seven.sub(2).unwrap().extract::<i32>().unwrap(), 5);
+        assert_eq!(seven.mul(2).unwrap().extract::<i32>().unwrap(), 14);
+        assert_eq!(seven.truediv(2).unwrap().extract::<f64>().unwrap(), 3.5);
+        assert_eq!(seven.floordiv(2).unwrap().extract::<i32>().unwrap(), 3);
+        assert_eq!(seven.rem(2).unwrap().extract::<i32>().unwrap(), 1);
+        assert_eq!(
+            seven.divmod(2).unwrap().extract::<(i32, i32)>().unwrap(),
+            (3, 1)
+        );
+        assert_eq!(seven.pow(2).unwrap().extract::<i32>().unwrap(), 49);
+        assert_eq!(seven.neg().unwrap().extract::<i32>().unwrap(), -7);
+        assert_eq!(seven.pos().unwrap().extract::<i32>().unwrap(), 7);
+        assert_eq!(seven.invert().unwrap().extract::<i32>().unwrap(), -8);
+        let minus_seven = seven.neg().unwrap().as_number().unwrap();
+        assert_eq!(minus_seven.abs().unwrap().extract::<i32>().unwrap(), 7);
+
+        assert!(seven.truediv(0).is_err());
+        assert!(seven.add("a").is_err());
+    }
+
+    #[test]
+    fn test_not_a_number() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let s = "abc".to_object(py);
+        assert!(s.as_ref(py).as_number().is_err());
+        assert!(PyNumber::try_from(s.as_ref(py)).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Conversions of `OsStr` and `Path`, which accept anything `os.fspath` accepts.
+
+use crate::cache::PyStatic;
+use crate::err::PyResult;
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyBytes};
+use crate::{AsPyPointer, FromPyObject, IntoPy, Python, ToPyObject};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Extracts str, bytes and `os.PathLike` objects, like `os.fspath`.
+///
+/// On Unix, strings are encoded like `os.fsencode`, so the bytes Python decoded with
+/// `surrogateescape` are restored. On Windows, bytes are decoded like `os.fsdecode`, and lone
+/// surrogates are kept.
+impl<'source> FromPyObject<'source> for OsString {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let py = ob.py();
+        // Raises `TypeError` for anything that isn't path-like
+        let path: &PyAny = unsafe { py.from_owned_ptr_or_err(ffi::PyOS_FSPath(ob.as_ptr()))? };
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let bytes: &PyBytes = match path.downcast_ref() {
+                Ok(bytes) => bytes,
+                Err(_) => unsafe {
+                    py.from_owned_ptr_or_err(ffi::PyUnicode_EncodeFSDefault(path.as_ptr()))?
+                },
+            };
+            Ok(OsStr::from_bytes(bytes.as_bytes()).to_os_string())
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStringExt;
+            let path = fsdecode(path)?;
+            let utf16 = path.call_method1("encode", ("utf-16-le", "surrogatepass"))?;
+            let utf16: &PyBytes = utf16.downcast_ref()?;
+            let wide: Vec<u16> = utf16
+                .as_bytes()
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .collect();
+            Ok(OsString::from_wide(&wide))
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            Ok(fsdecode(path)?.extract::<String>()?.into())
+        }
+    }
+}
+
+/// Decodes the result of `os.fspath` to a str if it's bytes, like `os.fsdecode`.
+#[cfg(not(unix))]
+fn fsdecode(path: &PyAny) -> PyResult<&PyAny> {
+    match path.downcast_ref::<PyBytes>() {
+        Ok(bytes) => {
+            let bytes = bytes.as_bytes();
+            unsafe {
+                path.py()
+                    .from_owned_ptr_or_err(ffi::PyUnicode_DecodeFSDefaultAndSize(
+                        bytes.as_ptr() as *const std::os::raw::c_char,
+                        bytes.len() as ffi::Py_ssize_t,
+                    ))
+            }
+        }
+        Err(_) => Ok(path),
+    }
+}
+
+/// Converts to a str, like `os.fsdecode`, so paths that aren't valid UTF-8 round-trip.
+impl ToPyObject for OsStr {
+    fn to_object(&self, py: Python) -> PyObject {
+        #[cfg(unix)]
+        unsafe {
+            use std::os::unix::ffi::OsStrExt;
+            let bytes = self.as_bytes();
+            PyObject::from_owned_ptr_or_panic(
+                py,
+                ffi::PyUnicode_DecodeFSDefaultAndSize(
+                    bytes.as_ptr() as *const std::os::raw::c_char,
+                    bytes.len() as ffi::Py_ssize_t,
+                ),
+            )
+        }
+        #[cfg(windows)]
+        unsafe {
+            use std::os::windows::ffi::OsStrExt;
+            let wide: Vec<u16> = self.encode_wide().collect();
+            PyObject::from_owned_ptr_or_panic(
+                py,
+                ffi::PyUnicode_FromWideChar(wide.as_ptr(), wide.len() as ffi::Py_ssize_t),
+            )
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            self.to_string_lossy().to_object(py)
+        }
+    }
+}
+
+impl ToPyObject for OsString {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.as_os_str().to_object(py)
+    }
+}
+
+impl<'a> IntoPy<PyObject> for &'a OsStr {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for OsString {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+/// Extracts str, bytes and `os.PathLike` objects such as `pathlib.Path`, like [OsString].
+impl<'source> FromPyObject<'source> for PathBuf {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(PathBuf::from(OsString::extract(ob)?))
+    }
+}
+
+/// Converts to a `pathlib.Path`.
+impl ToPyObject for Path {
+    fn to_object(&self, py: Python) -> PyObject {
+        static PATH: PyStatic = PyStatic::new();
+        let path_type = PATH.get_or_init(py, || {
+            py.import("pathlib")
+                .and_then(|pathlib| pathlib.get("Path"))
+                .expect("failed to import pathlib.Path")
+                .into()
+        });
+        path_type
+            .call1((self.as_os_str(),))
+            .expect("pathlib.Path() failed")
+            .into()
+    }
+}
+
+impl ToPyObject for PathBuf {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.as_path().to_object(py)
+    }
+}
+
+impl<'a> IntoPy<PyObject> for &'a Path {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl IntoPy<PyObject> for PathBuf {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions;
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::{PyBytes, PyDict};
+    use crate::{PyObject, Python, ToPyObject};
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_extract_path_like() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for code in &[
+            "'/tmp/data'",
+            "b'/tmp/data'",
+            "__import__('pathlib').Path('/tmp/data')",
+        ] {
+            let obj = py.eval(code, None, None).unwrap();
+            assert_eq!(
+                obj.extract::<PathBuf>().unwrap(),
+                Path::new("/tmp/data"),
+                "{}",
+                code
+            );
+        }
+
+        let locals = PyDict::new(py);
+        py.run(
+            "class Custom:\n    def __fspath__(self):\n        return 'custom'",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let obj = py.eval("Custom()", None, Some(locals)).unwrap();
+        assert_eq!(obj.extract::<OsString>().unwrap(), "custom");
+
+        let err = py.eval("1", None, None).unwrap().extract::<PathBuf>();
+        assert!(err.unwrap_err().is_instance::<exceptions::TypeError>(py));
+    }
+
+    #[test]
+    fn test_path_to_object() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = Path::new("dir/file.txt").to_object(py);
+        let locals = PyDict::new(py);
+        locals.set_item("obj", &obj).unwrap();
+        py.run(
+            "import pathlib; assert obj == pathlib.Path('dir/file.txt')",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        assert_eq!(
+            obj.extract::<PathBuf>(py).unwrap(),
+            Path::new("dir/file.txt")
+        );
+
+        let obj = OsString::from("file.txt").to_object(py);
+        assert_eq!(obj.extract::<String>(py).unwrap(), "file.txt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_round_trip() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let name = OsStr::from_bytes(b"caf\xe9.txt");
+        // Decoded with `surrogateescape`, like the names `os.listdir` returns
+        let obj: PyObject = name.to_object(py);
+        let expected = PyBytes::new(py, b"caf\xe9.txt")
+            .call_method1("decode", ("utf-8", "surrogateescape"))
+            .unwrap();
+        assert!(expected.rich_eq(&obj).unwrap());
+        assert_eq!(obj.extract::<OsString>(py).unwrap(), name);
+    }
+}
@@ -270,13 +270,14 @@ where
     }
 }
 
+/// Extracts a `Vec` from any iterable, not only from sequences, so that generators
+/// and views can be passed without wrapping them in `list(...)`.
 fn extract_sequence<'s, T>(obj: &'s PyAny) -> PyResult<Vec<T>>
 where
     T: FromPyObject<'s>,
 {
-    let seq = <PySequence as PyTryFrom>::try_from(obj)?;
-    let mut v = Vec::with_capacity(seq.len().unwrap_or(0) as usize);
-    for item in seq.iter()? {
+    let mut v = Vec::with_capacity(obj.length_hint(0));
+    for item in obj.iter()? {
         v.push(item?.extract::<T>()?);
     }
     Ok(v)
@@ -676,4 +677,35 @@ mod test {
         let empty_seq = empty_list.cast_as::<PySequence>(py).unwrap();
         assert_eq!(empty_seq.is_empty().unwrap(), true);
     }
+
+    #[test]
+    fn test_length_hint() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = vec![1, 2, 3].to_object(py);
+        assert_eq!(list.as_ref(py).length_hint(0), 3);
+        let it = py.eval("iter(range(5))", None, None).unwrap();
+        assert_eq!(it.length_hint(0), 5);
+        let gen = py.eval("(x for x in [])", None, None).unwrap();
+        assert_eq!(gen.length_hint(7), 7);
+        let v: Vec<i32> = list.extract(py).unwrap();
+        assert_eq!(v, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_extract_iterable_to_vec() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let gen = py.eval("(x * 2 for x in range(4))", None, None).unwrap();
+        let v: Vec<i32> = gen.extract().unwrap();
+        assert_eq!(v, vec![0, 2, 4, 6]);
+        let keys = py.eval("{'a': 1, 'b': 2}.keys()", None, None).unwrap();
+        let v: Vec<String> = keys.extract().unwrap();
+        assert_eq!(v, vec!["a", "b"]);
+        assert!(py
+            .eval("42", None, None)
+            .unwrap()
+            .extract::<Vec<i32>>()
+            .is_err());
+    }
 }
@@ -6,12 +6,13 @@ use crate::ffi;
 use crate::instance::PyNativeType;
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
 use crate::types::PyAny;
 use crate::AsPyPointer;
 use crate::Python;
-use crate::{ToBorrowedObject, ToPyObject};
+use crate::{FromPyObject, ToBorrowedObject, ToPyObject};
 use std::ptr;
-use std::{collections, hash};
+use std::{cmp, collections, hash};
 
 /// Represents a Python `set`
 #[repr(transparent)]
@@ -164,6 +165,36 @@ where
     }
 }
 
+impl<'source, K, S> FromPyObject<'source> for collections::HashSet<K, S>
+where
+    K: FromPyObject<'source> + cmp::Eq + hash::Hash,
+    S: hash::BuildHasher + Default,
+{
+    /// Accepts any iterable, e.g. a `set`, `list` or generator.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let mut ret =
+            collections::HashSet::with_capacity_and_hasher(ob.length_hint(0), S::default());
+        for item in ob.iter()? {
+            ret.insert(K::extract(item?)?);
+        }
+        Ok(ret)
+    }
+}
+
+impl<'source, K> FromPyObject<'source> for collections::BTreeSet<K>
+where
+    K: FromPyObject<'source> + cmp::Ord,
+{
+    /// Accepts any iterable, e.g. a `set`, `list` or generator.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let mut ret = collections::BTreeSet::new();
+        for item in ob.iter()? {
+            ret.insert(K::extract(item?)?);
+        }
+        Ok(ret)
+    }
+}
+
 impl PyFrozenSet {
     /// Creates a new frozenset.
     ///
@@ -225,7 +256,7 @@ mod test {
     use crate::objectprotocol::ObjectProtocol;
     use crate::Python;
     use crate::{PyTryFrom, ToPyObject};
-    use std::collections::HashSet;
+    use std::collections::{BTreeSet, HashSet};
 
     #[test]
     fn test_set_new() {
@@ -373,4 +404,21 @@ mod test {
             assert_eq!(1i32, el.extract::<i32>().unwrap());
         }
     }
+
+    #[test]
+    fn test_extract_set_from_iterable() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let set = py.eval("{1, 2, 3}", None, None).unwrap();
+        let v: HashSet<i32> = set.extract().unwrap();
+        assert_eq!(v, [1, 2, 3].iter().cloned().collect());
+        let gen = py.eval("(x % 3 for x in range(10))", None, None).unwrap();
+        let v: BTreeSet<i32> = gen.extract().unwrap();
+        assert_eq!(v, [0, 1, 2].iter().cloned().collect());
+        assert!(py
+            .eval("None", None, None)
+            .unwrap()
+            .extract::<HashSet<i32>>()
+            .is_err());
+    }
 }
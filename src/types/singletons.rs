@@ -0,0 +1,105 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! The types of the Python singletons `None`, `Ellipsis` and `NotImplemented`.
+
+use crate::ffi;
+use crate::internal_tricks::Unsendable;
+use crate::object::PyObject;
+use crate::Python;
+use std::os::raw::c_int;
+
+/// Declares the type `$name` of the singleton returned by `$object`, with `$check` as the name of
+/// its type check.
+macro_rules! pyobject_singleton_type {
+    ($(#[$meta: meta])* $name: ident, $object: path, $check: ident) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        pub struct $name(PyObject, Unsendable);
+
+        pyobject_native_type!($name, *(*$object()).ob_type, $check);
+
+        unsafe fn $check(op: *mut ffi::PyObject) -> c_int {
+            (op == $object()) as c_int
+        }
+
+        impl $name {
+            /// Returns the singleton.
+            #[inline]
+            pub fn get(py: Python) -> &$name {
+                unsafe { py.from_borrowed_ptr($object()) }
+            }
+        }
+    };
+}
+
+pyobject_singleton_type!(
+    /// Represents the Python singleton `None`.
+    PyNone,
+    ffi::Py_None,
+    is_none
+);
+
+pyobject_singleton_type!(
+    /// Represents the Python singleton `Ellipsis` (`...`).
+    PyEllipsis,
+    ffi::Py_Ellipsis,
+    is_ellipsis
+);
+
+pyobject_singleton_type!(
+    /// Represents the Python singleton `NotImplemented`.
+    ///
+    /// Binary operator methods such as `__add__` can return it to let Python try the
+    /// reflected operation (e.g. `__radd__`) of the other operand.
+    PyNotImplemented,
+    ffi::Py_NotImplemented,
+    is_not_implemented
+);
+
+#[cfg(test)]
+mod test {
+    use crate::objectprotocol::ObjectProtocol;
+    use crate::types::{PyAny, PyEllipsis, PyNone, PyNotImplemented};
+    use crate::{AsPyPointer, PyTryFrom, Python};
+
+    #[test]
+    fn test_none() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj: &PyAny = PyNone::get(py).into();
+        assert_eq!(obj.as_ptr(), py.None().as_ptr());
+        assert!(<PyNone as PyTryFrom>::try_from(obj).is_ok());
+        assert!(obj.extract::<&PyNone>().is_ok());
+        assert_eq!(obj.repr().unwrap().to_string_lossy(), "None");
+
+        let other: &PyAny = py.eval("1", None, None).unwrap();
+        assert!(<PyNone as PyTryFrom>::try_from(other).is_err());
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj: &PyAny = PyEllipsis::get(py).into();
+        assert_eq!(obj.as_ptr(), py.Ellipsis().as_ptr());
+        assert!(<PyEllipsis as PyTryFrom>::try_from(obj).is_ok());
+        assert!(obj.extract::<&PyEllipsis>().is_ok());
+        assert_eq!(obj.repr().unwrap().to_string_lossy(), "Ellipsis");
+
+        let other: &PyAny = py.eval("1", None, None).unwrap();
+        assert!(<PyEllipsis as PyTryFrom>::try_from(other).is_err());
+    }
+
+    #[test]
+    fn test_notimplemented() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj: &PyAny = PyNotImplemented::get(py).into();
+        assert_eq!(obj.as_ptr(), py.NotImplemented().as_ptr());
+        assert!(<PyNotImplemented as PyTryFrom>::try_from(obj).is_ok());
+        assert!(obj.extract::<&PyNotImplemented>().is_ok());
+        assert_eq!(obj.repr().unwrap().to_string_lossy(), "NotImplemented");
+
+        let other: &PyAny = py.eval("1", None, None).unwrap();
+        assert!(<PyNotImplemented as PyTryFrom>::try_from(other).is_err());
+    }
+}
@@ -36,6 +36,25 @@ impl PyString {
         unsafe { py.from_owned_ptr(ffi::PyUnicode_FromStringAndSize(ptr, len)) }
     }
 
+    /// Creates an interned Python string, or returns the existing interned string with the same
+    /// contents.
+    ///
+    /// Interned strings are compared by identity, so they make faster dictionary keys and
+    /// attribute names than strings created each time.
+    ///
+    /// Panics if out of memory.
+    pub fn intern<'p>(py: Python<'p>, s: &str) -> &'p PyString {
+        let ptr = s.as_ptr() as *const c_char;
+        let len = s.len() as ffi::Py_ssize_t;
+        unsafe {
+            let mut string = ffi::PyUnicode_FromStringAndSize(ptr, len);
+            if !string.is_null() {
+                ffi::PyUnicode_InternInPlace(&mut string);
+            }
+            py.from_owned_ptr(string)
+        }
+    }
+
     pub fn from_object<'p>(src: &'p PyAny, encoding: &str, errors: &str) -> PyResult<&'p PyString> {
         unsafe {
             src.py()
@@ -188,9 +207,20 @@ mod test {
     use crate::instance::AsPyRef;
     use crate::object::PyObject;
     use crate::Python;
-    use crate::{FromPyObject, PyTryFrom, ToPyObject};
+    use crate::{AsPyPointer, FromPyObject, PyTryFrom, ToPyObject};
     use std::borrow::Cow;
 
+    #[test]
+    fn test_intern() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let first = PyString::intern(py, "interned");
+        let second = PyString::intern(py, &String::from("interned"));
+        assert_eq!(first.as_ptr(), second.as_ptr());
+        assert_eq!(first.to_string().unwrap(), "interned");
+        assert_ne!(first.as_ptr(), PyString::new(py, "not interned").as_ptr());
+    }
+
     #[test]
     fn test_non_bmp() {
         let gil = Python::acquire_gil();
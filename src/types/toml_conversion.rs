@@ -0,0 +1,176 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Conversions between [toml](https://docs.rs/toml) values and Python objects, so that a parsed
+//! configuration can be handed to Python code and read back after it was modified.
+//!
+//! Tables become dicts, arrays become lists and datetimes become `datetime.datetime`,
+//! `datetime.date` or `datetime.time` objects, depending on which parts are present.
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::{TypeError, ValueError};
+use crate::instance::PyNativeType;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyBool, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+use crate::{ffi, AsPyPointer, FromPyObject, IntoPy, PyTryFrom, Python, ToPyObject};
+use toml::value::{Datetime, Table};
+use toml::Value;
+
+fn datetime_to_object(py: Python, datetime: &Datetime) -> PyResult<PyObject> {
+    let text = datetime.to_string();
+    let (has_date, has_time) = (text.contains('-'), text.contains(':'));
+    let class = match (has_date, has_time) {
+        (true, true) => "datetime",
+        (true, false) => "date",
+        _ => "time",
+    };
+    // `datetime.fromisoformat` only accepts the `Z` suffix from Python 3.11 on
+    let text = if text.ends_with('Z') {
+        format!("{}+00:00", &text[..text.len() - 1])
+    } else {
+        text
+    };
+    let value = py
+        .import("datetime")?
+        .get(class)?
+        .call_method1("fromisoformat", (text,))?;
+    Ok(value.into())
+}
+
+impl ToPyObject for Value {
+    /// Panics if a datetime can not be represented by Python's `datetime` module.
+    fn to_object(&self, py: Python) -> PyObject {
+        match self {
+            Value::String(s) => s.to_object(py),
+            Value::Integer(i) => i.to_object(py),
+            Value::Float(f) => f.to_object(py),
+            Value::Boolean(b) => b.to_object(py),
+            Value::Datetime(d) => {
+                datetime_to_object(py, d).expect("Failed to convert TOML datetime")
+            }
+            Value::Array(a) => a.to_object(py),
+            Value::Table(t) => {
+                let dict = PyDict::new(py);
+                for (k, v) in t {
+                    dict.set_item(k, v).expect("Failed to set_item on dict");
+                }
+                dict.into()
+            }
+        }
+    }
+}
+
+impl IntoPy<PyObject> for Value {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for Value {
+    /// Accepts `bool`, `int`, `float`, `str`, `datetime` objects, lists and tuples, and dicts
+    /// with string keys. `None` is rejected, as TOML has no null value.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let py = ob.py();
+        if let Ok(b) = <PyBool as PyTryFrom>::try_from(ob) {
+            return Ok(Value::Boolean(b.is_true()));
+        }
+        if <PyLong as PyTryFrom>::try_from(ob).is_ok() {
+            return Ok(Value::Integer(ob.extract()?));
+        }
+        if <PyFloat as PyTryFrom>::try_from(ob).is_ok() {
+            return Ok(Value::Float(ob.extract()?));
+        }
+        if <PyString as PyTryFrom>::try_from(ob).is_ok() {
+            return Ok(Value::String(ob.extract()?));
+        }
+        if let Ok(dict) = <PyDict as PyTryFrom>::try_from(ob) {
+            let mut table = Table::new();
+            for (k, v) in dict.iter() {
+                table.insert(k.extract()?, v.extract()?);
+            }
+            return Ok(Value::Table(table));
+        }
+        if <PyList as PyTryFrom>::try_from(ob).is_ok()
+            || <PyTuple as PyTryFrom>::try_from(ob).is_ok()
+        {
+            return Ok(Value::Array(ob.extract()?));
+        }
+
+        // `datetime.datetime` is a subclass of `datetime.date`
+        let datetime = py.import("datetime")?;
+        let classes = (datetime.get("date")?, datetime.get("time")?).to_object(py);
+        let is_datetime = match unsafe { ffi::PyObject_IsInstance(ob.as_ptr(), classes.as_ptr()) } {
+            -1 => return Err(PyErr::fetch(py)),
+            result => result == 1,
+        };
+        if is_datetime {
+            let text: String = ob.call_method0("isoformat")?.extract()?;
+            return text
+                .parse()
+                .map(Value::Datetime)
+                .map_err(|e| ValueError::py_err(format!("Invalid TOML datetime {}: {}", text, e)));
+        }
+
+        Err(PyErr::new::<TypeError, _>(format!(
+            "Can not convert {} to a TOML value",
+            ob.get_type().name()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::PyDict;
+    use crate::{ObjectProtocol, Python, ToPyObject};
+    use toml::Value;
+
+    const CONFIG: &str = r#"
+        title = "example"
+        enabled = true
+        ratio = 0.5
+
+        [server]
+        ports = [8000, 8001]
+        started = 1979-05-27T07:32:00Z
+        birthday = 1979-05-27
+    "#;
+
+    #[test]
+    fn test_round_trip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let value: Value = CONFIG.parse().unwrap();
+
+        let obj = value.to_object(py);
+        let locals = PyDict::new(py);
+        locals.set_item("config", &obj).unwrap();
+        py.run(
+            r#"
+import datetime
+assert config["title"] == "example"
+assert config["server"]["ports"] == [8000, 8001]
+assert config["server"]["started"].year == 1979
+assert type(config["server"]["birthday"]) is datetime.date
+config["server"]["ports"].append(8002)
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let back: Value = obj.extract(py).unwrap();
+        assert_eq!(back["server"]["ports"].as_array().unwrap().len(), 3);
+        assert_eq!(back["title"], value["title"]);
+        assert_eq!(back["enabled"], value["enabled"]);
+        assert_eq!(back["server"]["birthday"], value["server"]["birthday"]);
+    }
+
+    #[test]
+    fn test_extract_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert!(py.None().extract::<Value>(py).is_err());
+        let obj = py.eval("{1: 'a'}", None, None).unwrap();
+        assert!(obj.extract::<Value>().is_err());
+    }
+}
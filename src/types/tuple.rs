@@ -11,7 +11,7 @@ use crate::types::PyAny;
 use crate::AsPyPointer;
 use crate::IntoPyPointer;
 use crate::Python;
-use crate::{FromPyObject, IntoPy, PyTryFrom, ToPyObject};
+use crate::{FromPyObject, IntoPy, PyTryFrom, ToBorrowedObject, ToPyObject};
 use std::slice;
 
 /// Represents a Python `tuple` object.
@@ -70,6 +70,58 @@ impl PyTuple {
         }
     }
 
+    /// Returns a new tuple with the items of `other` appended to those of `self`.
+    /// Equivalent to python `self + other`.
+    pub fn concat(&self, other: &PyTuple) -> PyResult<&PyTuple> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PySequence_Concat(self.as_ptr(), other.as_ptr()))
+        }
+    }
+
+    /// Returns a new tuple with the items of `self` repeated `count` times.
+    /// Equivalent to python `self * count`.
+    pub fn repeat(&self, count: usize) -> PyResult<&PyTuple> {
+        let count = count.min(isize::max_value() as usize) as Py_ssize_t;
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PySequence_Repeat(self.as_ptr(), count))
+        }
+    }
+
+    /// Returns the index of the first item equal to `value`.
+    /// Equivalent to python `self.index(value)`.
+    pub fn index<V>(&self, value: V) -> PyResult<usize>
+    where
+        V: ToBorrowedObject,
+    {
+        let r = value.with_borrowed_ptr(self.py(), |ptr| unsafe {
+            ffi::PySequence_Index(self.as_ptr(), ptr)
+        });
+        if r == -1 {
+            Err(PyErr::fetch(self.py()))
+        } else {
+            Ok(r as usize)
+        }
+    }
+
+    /// Returns the number of items equal to `value`.
+    /// Equivalent to python `self.count(value)`.
+    #[cfg(not(PyPy))]
+    pub fn count<V>(&self, value: V) -> PyResult<usize>
+    where
+        V: ToBorrowedObject,
+    {
+        let r = value.with_borrowed_ptr(self.py(), |ptr| unsafe {
+            ffi::PySequence_Count(self.as_ptr(), ptr)
+        });
+        if r == -1 {
+            Err(PyErr::fetch(self.py()))
+        } else {
+            Ok(r as usize)
+        }
+    }
+
     /// Gets the item at the specified index.
     ///
     /// Panics if the index is out of range.
@@ -312,4 +364,22 @@ mod test {
             assert_eq!(i + 1, item.extract().unwrap());
         }
     }
+
+    #[test]
+    fn test_sequence_ops() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let a = PyTuple::new(py, &[1, 2, 3]);
+        let b = PyTuple::new(py, &[3, 4]);
+
+        let joined = a.concat(b).unwrap();
+        assert_eq!(vec![1, 2, 3, 3, 4], joined.extract::<Vec<i32>>().unwrap());
+        let repeated = b.repeat(2).unwrap();
+        assert_eq!(vec![3, 4, 3, 4], repeated.extract::<Vec<i32>>().unwrap());
+
+        assert_eq!(2, joined.index(3).unwrap());
+        assert!(joined.index(42).is_err());
+        #[cfg(not(PyPy))]
+        assert_eq!(2, joined.count(3).unwrap());
+    }
 }
@@ -0,0 +1,259 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{PyErr, PyResult};
+use crate::ffi;
+use crate::instance::PyNativeType;
+use crate::object::PyObject;
+use crate::types::dict::PyDictIterator;
+use crate::types::{PyAny, PyDict};
+use crate::{AsPyPointer, FromPyObject, IntoPy, PyTryFrom, Python, ToBorrowedObject, ToPyObject};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A view of a Python `dict` whose keys have type `K` and whose values have type `V`.
+///
+/// Keys and values are converted when they're accessed, so code treating a dict as a
+/// homogeneous map doesn't have to call `extract` on every item. The dict itself isn't copied,
+/// so changes made through the view are seen by Python and the other way round. Python code can
+/// still add items of other types, which fail to convert when they're accessed.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyTypedDict;
+///
+/// #[pyfunction]
+/// fn add_score(scores: PyTypedDict<String, u32>, name: String, points: u32) -> PyResult<()> {
+///     let score = scores.get(&name)?.unwrap_or(0);
+///     scores.insert(name, score + points)
+/// }
+/// ```
+pub struct PyTypedDict<'p, K, V> {
+    dict: &'p PyDict,
+    _types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'p, K, V> PyTypedDict<'p, K, V>
+where
+    K: ToPyObject + FromPyObject<'p>,
+    V: ToPyObject + FromPyObject<'p>,
+{
+    /// Creates a new empty dictionary.
+    pub fn new(py: Python<'p>) -> Self {
+        PyTypedDict::from_dict(PyDict::new(py))
+    }
+
+    /// Wraps an existing dictionary, without checking the types of its items.
+    pub fn from_dict(dict: &'p PyDict) -> Self {
+        PyTypedDict {
+            dict,
+            _types: PhantomData,
+        }
+    }
+
+    /// Returns the underlying dictionary.
+    pub fn as_dict(&self) -> &'p PyDict {
+        self.dict
+    }
+
+    /// Return the number of items in the dictionary.
+    pub fn len(&self) -> usize {
+        self.dict.len()
+    }
+
+    /// Check if dict is empty.
+    pub fn is_empty(&self) -> bool {
+        self.dict.is_empty()
+    }
+
+    /// Determine if the dictionary contains the specified key.
+    /// This is equivalent to the Python expression `key in self`.
+    pub fn contains_key(&self, key: &K) -> PyResult<bool> {
+        self.dict.contains(key)
+    }
+
+    /// Gets the value of `key`, converted to `V`.
+    ///
+    /// Returns `None` if the key isn't present. Unlike [PyDict::get_item], errors raised while
+    /// looking up the key, e.g. by its `__hash__` method, are returned.
+    pub fn get(&self, key: &K) -> PyResult<Option<V>> {
+        let py = self.dict.py();
+        let value = key.with_borrowed_ptr(py, |key| unsafe {
+            let value = ffi::PyDict_GetItemWithError(self.dict.as_ptr(), key);
+            if value.is_null() && !ffi::PyErr_Occurred().is_null() {
+                return Err(PyErr::fetch(py));
+            }
+            Ok(py.from_borrowed_ptr_or_opt::<PyAny>(value))
+        })?;
+        value.map(V::extract).transpose()
+    }
+
+    /// Sets the value of `key`.
+    /// This is equivalent to the Python expression `self[key] = value`.
+    pub fn insert(&self, key: K, value: V) -> PyResult<()> {
+        self.dict.set_item(key, value)
+    }
+
+    /// Removes `key` and returns its value, or `None` if the key isn't present.
+    pub fn remove(&self, key: &K) -> PyResult<Option<V>> {
+        let value = self.get(key)?;
+        if value.is_some() {
+            self.dict.del_item(key)?;
+        }
+        Ok(value)
+    }
+
+    /// Empty an existing dictionary of all key-value pairs.
+    pub fn clear(&self) {
+        self.dict.clear()
+    }
+
+    /// Returns an iterator of the (key, value) pairs in this dictionary, converted to `K` and `V`.
+    ///
+    /// Like [PyDict::iter], it's unsafe to use when the dictionary might be changed by other
+    /// python code.
+    pub fn iter(&self) -> PyTypedDictIterator<'p, K, V> {
+        PyTypedDictIterator {
+            iter: self.dict.iter(),
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<'p, K, V> Clone for PyTypedDict<'p, K, V> {
+    fn clone(&self) -> Self {
+        PyTypedDict {
+            dict: self.dict,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<'p, K, V> Copy for PyTypedDict<'p, K, V> {}
+
+impl<'p, K, V> fmt::Debug for PyTypedDict<'p, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.dict, f)
+    }
+}
+
+impl<'p, K, V> AsPyPointer for PyTypedDict<'p, K, V> {
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.dict.as_ptr()
+    }
+}
+
+impl<'p, K, V> ToPyObject for PyTypedDict<'p, K, V> {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.dict.to_object(py)
+    }
+}
+
+impl<'p, K, V> IntoPy<PyObject> for PyTypedDict<'p, K, V> {
+    fn into_py(self, py: Python) -> PyObject {
+        self.dict.to_object(py)
+    }
+}
+
+impl<'p, K, V> FromPyObject<'p> for PyTypedDict<'p, K, V>
+where
+    K: ToPyObject + FromPyObject<'p>,
+    V: ToPyObject + FromPyObject<'p>,
+{
+    fn extract(ob: &'p PyAny) -> PyResult<Self> {
+        Ok(PyTypedDict::from_dict(<PyDict as PyTryFrom>::try_from(ob)?))
+    }
+}
+
+impl<'p, K, V> std::iter::IntoIterator for PyTypedDict<'p, K, V>
+where
+    K: ToPyObject + FromPyObject<'p>,
+    V: ToPyObject + FromPyObject<'p>,
+{
+    type Item = PyResult<(K, V)>;
+    type IntoIter = PyTypedDictIterator<'p, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator of the items of a [PyTypedDict], created by [PyTypedDict::iter].
+pub struct PyTypedDictIterator<'p, K, V> {
+    iter: PyDictIterator<'p>,
+    _types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'p, K, V> Iterator for PyTypedDictIterator<'p, K, V>
+where
+    K: FromPyObject<'p>,
+    V: FromPyObject<'p>,
+{
+    type Item = PyResult<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(key, value)| Ok((K::extract(key)?, V::extract(value)?)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions;
+    use crate::types::{IntoPyDict, PyDict, PyTypedDict};
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_typed_dict() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let scores: PyTypedDict<String, u32> = PyTypedDict::new(py);
+        assert!(scores.is_empty());
+
+        scores.insert("a".to_string(), 1).unwrap();
+        scores.insert("b".to_string(), 2).unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores.get(&"a".to_string()).unwrap(), Some(1));
+        assert_eq!(scores.get(&"c".to_string()).unwrap(), None);
+        assert!(scores.contains_key(&"b".to_string()).unwrap());
+
+        assert_eq!(scores.remove(&"a".to_string()).unwrap(), Some(1));
+        assert_eq!(scores.remove(&"a".to_string()).unwrap(), None);
+        let items: Vec<(String, u32)> = scores.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(items, vec![("b".to_string(), 2)]);
+
+        // The view and the dict share their items
+        let dict = scores.as_dict();
+        dict.set_item("c", 3).unwrap();
+        assert_eq!(scores.get(&"c".to_string()).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_typed_dict_extract() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [("a", 1)].into_py_dict(py);
+        let scores: PyTypedDict<String, u32> = dict.as_ref().extract().unwrap();
+        assert_eq!(scores.get(&"a".to_string()).unwrap(), Some(1));
+
+        // Items of other types fail when they're accessed
+        dict.set_item("b", "two").unwrap();
+        let err = scores.get(&"b".to_string()).unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+        assert!(scores.iter().any(|item| item.is_err()));
+
+        let list = py.eval("[]", None, None).unwrap();
+        assert!(list.extract::<PyTypedDict<String, u32>>().is_err());
+    }
+
+    #[test]
+    fn test_typed_dict_unhashable_key() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = PyDict::new(py);
+        let typed: PyTypedDict<Vec<i32>, i32> = PyTypedDict::from_dict(dict);
+        let err = typed.get(&vec![1]).unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+    }
+}
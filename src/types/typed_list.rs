@@ -0,0 +1,265 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{self, PyResult};
+use crate::ffi::{self, Py_ssize_t};
+use crate::instance::PyNativeType;
+use crate::object::PyObject;
+use crate::types::list::PyListIterator;
+use crate::types::{PyAny, PyList};
+use crate::{AsPyPointer, FromPyObject, IntoPy, PyTryFrom, Python, ToPyObject};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A view of a Python `list` whose items have type `T`.
+///
+/// Items are converted when they're accessed, which is a middle ground between a `&PyList`,
+/// where every item has to be extracted by hand, and a `Vec<T>`, which copies the whole list.
+/// Changes made through the view are seen by Python and the other way round. Python code can
+/// still add items of other types, which fail to convert when they're accessed.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyTypedList;
+///
+/// /// Appends the next number of the Fibonacci sequence
+/// #[pyfunction]
+/// fn extend_fibonacci(numbers: PyTypedList<u64>) -> PyResult<()> {
+///     let len = numbers.len();
+///     let next = if len < 2 { 1 } else { numbers.get(len - 1)? + numbers.get(len - 2)? };
+///     numbers.push(next)
+/// }
+/// ```
+pub struct PyTypedList<'p, T> {
+    list: &'p PyList,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<'p, T> PyTypedList<'p, T>
+where
+    T: ToPyObject + FromPyObject<'p>,
+{
+    /// Creates a new empty list.
+    pub fn new(py: Python<'p>) -> Self {
+        PyTypedList::from_list(PyList::empty(py))
+    }
+
+    /// Wraps an existing list, without checking the types of its items.
+    pub fn from_list(list: &'p PyList) -> Self {
+        PyTypedList {
+            list,
+            _type: PhantomData,
+        }
+    }
+
+    /// Returns the underlying list.
+    pub fn as_list(&self) -> &'p PyList {
+        self.list
+    }
+
+    /// Returns the length of the list.
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Checks if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Gets the item at `index`, converted to `T`.
+    ///
+    /// Raises `IndexError` if the index is out of range.
+    pub fn get(&self, index: usize) -> PyResult<T> {
+        let py = self.list.py();
+        let item: &PyAny = unsafe {
+            py.from_borrowed_ptr_or_err(ffi::PyList_GetItem(
+                self.list.as_ptr(),
+                index as Py_ssize_t,
+            ))?
+        };
+        T::extract(item)
+    }
+
+    /// Replaces the item at `index`.
+    ///
+    /// Raises `IndexError` if the index is out of range.
+    pub fn set(&self, index: usize, item: T) -> PyResult<()> {
+        self.list.set_item(index as isize, item)
+    }
+
+    /// Appends an item to the list.
+    pub fn push(&self, item: T) -> PyResult<()> {
+        self.list.append(item)
+    }
+
+    /// Inserts an item at `index`, or at the end of the list if `index` is past it.
+    pub fn insert(&self, index: usize, item: T) -> PyResult<()> {
+        self.list.insert(index as isize, item)
+    }
+
+    /// Removes and returns the last item, or `None` if the list is empty.
+    pub fn pop(&self) -> PyResult<Option<T>> {
+        let len = self.len();
+        if len == 0 {
+            return Ok(None);
+        }
+        let item = self.get(len - 1)?;
+        unsafe {
+            err::error_on_minusone(
+                self.list.py(),
+                ffi::PyList_SetSlice(
+                    self.list.as_ptr(),
+                    (len - 1) as Py_ssize_t,
+                    len as Py_ssize_t,
+                    std::ptr::null_mut(),
+                ),
+            )?;
+        }
+        Ok(Some(item))
+    }
+
+    /// Returns an iterator of the items of the list, converted to `T`.
+    pub fn iter(&self) -> PyTypedListIterator<'p, T> {
+        PyTypedListIterator {
+            iter: self.list.iter(),
+            _type: PhantomData,
+        }
+    }
+
+    /// Copies the items of the list to a vector, failing if any of them isn't a `T`.
+    pub fn to_vec(&self) -> PyResult<Vec<T>> {
+        self.iter().collect()
+    }
+}
+
+impl<'p, T> Clone for PyTypedList<'p, T> {
+    fn clone(&self) -> Self {
+        PyTypedList {
+            list: self.list,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<'p, T> Copy for PyTypedList<'p, T> {}
+
+impl<'p, T> fmt::Debug for PyTypedList<'p, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.list, f)
+    }
+}
+
+impl<'p, T> AsPyPointer for PyTypedList<'p, T> {
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.list.as_ptr()
+    }
+}
+
+impl<'p, T> ToPyObject for PyTypedList<'p, T> {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.list.to_object(py)
+    }
+}
+
+impl<'p, T> IntoPy<PyObject> for PyTypedList<'p, T> {
+    fn into_py(self, py: Python) -> PyObject {
+        self.list.to_object(py)
+    }
+}
+
+impl<'p, T> FromPyObject<'p> for PyTypedList<'p, T>
+where
+    T: ToPyObject + FromPyObject<'p>,
+{
+    fn extract(ob: &'p PyAny) -> PyResult<Self> {
+        Ok(PyTypedList::from_list(<PyList as PyTryFrom>::try_from(ob)?))
+    }
+}
+
+impl<'p, T> std::iter::IntoIterator for PyTypedList<'p, T>
+where
+    T: ToPyObject + FromPyObject<'p>,
+{
+    type Item = PyResult<T>;
+    type IntoIter = PyTypedListIterator<'p, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator of the items of a [PyTypedList], created by [PyTypedList::iter].
+pub struct PyTypedListIterator<'p, T> {
+    iter: PyListIterator<'p>,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<'p, T> Iterator for PyTypedListIterator<'p, T>
+where
+    T: FromPyObject<'p>,
+{
+    type Item = PyResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(T::extract)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions;
+    use crate::types::{PyList, PyTypedList};
+    use crate::{ObjectProtocol, Python};
+
+    #[test]
+    fn test_typed_list() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let numbers: PyTypedList<i32> = PyTypedList::new(py);
+        assert!(numbers.is_empty());
+
+        numbers.push(1).unwrap();
+        numbers.push(3).unwrap();
+        numbers.insert(1, 2).unwrap();
+        assert_eq!(numbers.len(), 3);
+        assert_eq!(numbers.get(1).unwrap(), 2);
+        numbers.set(0, 0).unwrap();
+        assert_eq!(numbers.to_vec().unwrap(), vec![0, 2, 3]);
+
+        assert_eq!(numbers.pop().unwrap(), Some(3));
+        let items: Vec<i32> = numbers.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(items, vec![0, 2]);
+
+        // The view and the list share their items
+        numbers.as_list().append(4).unwrap();
+        assert_eq!(numbers.get(2).unwrap(), 4);
+
+        let err = numbers.get(3).unwrap_err();
+        assert!(err.is_instance::<exceptions::IndexError>(py));
+        let err = numbers.set(3, 0).unwrap_err();
+        assert!(err.is_instance::<exceptions::IndexError>(py));
+
+        while numbers.pop().unwrap().is_some() {}
+        assert!(numbers.is_empty());
+    }
+
+    #[test]
+    fn test_typed_list_extract() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[1, 2]);
+        let numbers: PyTypedList<u8> = list.as_ref().extract().unwrap();
+        assert_eq!(numbers.get(0).unwrap(), 1);
+
+        // Items of other types fail when they're accessed
+        list.append("three").unwrap();
+        assert!(numbers.get(1).is_ok());
+        let err = numbers.get(2).unwrap_err();
+        assert!(err.is_instance::<exceptions::TypeError>(py));
+        assert!(numbers.to_vec().is_err());
+
+        let dict = py.eval("{}", None, None).unwrap();
+        assert!(dict.extract::<PyTypedList<u8>>().is_err());
+    }
+}
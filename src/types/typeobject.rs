@@ -3,11 +3,14 @@
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
 use crate::err::{PyErr, PyResult};
+use crate::exceptions::TypeError;
 use crate::ffi;
 use crate::instance::{Py, PyNativeType};
 use crate::internal_tricks::Unsendable;
 use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
 use crate::type_object::{PyTypeInfo, PyTypeObject};
+use crate::types::PyAny;
 use crate::AsPyPointer;
 use crate::Python;
 use std::borrow::Cow;
@@ -59,6 +62,68 @@ impl PyType {
         }
     }
 
+    /// Registers `self` as a virtual subclass of the abstract base class `abc`, so that
+    /// `isinstance` and `issubclass` checks against it succeed.
+    ///
+    /// `abc` is either the name of a class in `collections.abc`, such as `"Mapping"`, or a
+    /// fully qualified name such as `"numbers.Integral"`.
+    ///
+    /// Classes can also be registered when they are initialized with
+    /// `#[pyclass(abc = "Mapping")]`.
+    pub fn register_abc(&self, abc: &str) -> PyResult<()> {
+        let abc = import_qualified(self.py(), abc, "collections.abc")?;
+        abc.call_method1("register", (self,))?;
+        Ok(())
+    }
+
+    /// Checks that `self` provides every member of the `typing.Protocol` named `protocol`,
+    /// raising a `TypeError` listing the missing members otherwise.
+    ///
+    /// `protocol` is either the name of a class in `typing`, such as `"SupportsInt"`, or a
+    /// fully qualified name such as `"mypackage.protocols.Closeable"`.
+    ///
+    /// Classes declared with `#[pyclass(implements = "...")]` are checked when they are
+    /// added to a module.
+    pub fn check_protocol(&self, protocol: &str) -> PyResult<()> {
+        let py = self.py();
+        let proto = import_qualified(py, protocol, "typing")?;
+        let is_protocol = proto
+            .getattr("_is_protocol")
+            .and_then(ObjectProtocol::is_true)
+            .unwrap_or(false);
+        if !is_protocol {
+            return Err(TypeError::py_err(format!(
+                "{} is not a typing.Protocol",
+                protocol
+            )));
+        }
+
+        let members = match proto.getattr("__protocol_attrs__") {
+            Ok(members) => members,
+            Err(_) => py
+                .import("typing")?
+                .call1("_get_protocol_attrs", (proto,))?,
+        };
+        let mut missing = Vec::new();
+        for member in members.iter()? {
+            let member: String = member?.extract()?;
+            if !self.hasattr(member.as_str())? {
+                missing.push(member);
+            }
+        }
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            missing.sort();
+            Err(TypeError::py_err(format!(
+                "{} does not implement protocol {}: missing {}",
+                self.name(),
+                protocol,
+                missing.join(", ")
+            )))
+        }
+    }
+
     // Check whether `obj` is an instance of `self`
     pub fn is_instance<T: AsPyPointer>(&self, obj: &T) -> PyResult<bool> {
         let result = unsafe { ffi::PyObject_IsInstance(obj.as_ptr(), self.as_ptr()) };
@@ -70,4 +135,26 @@ impl PyType {
             Ok(false)
         }
     }
+
+    /// Returns `super(self, obj)`, which calls the methods of the classes after `self` in the
+    /// method resolution order of `obj`, e.g. `__init__` of the Python class a `#[pyclass]`
+    /// extends.
+    pub fn super_of<T: AsPyPointer>(&self, obj: &T) -> PyResult<&PyAny> {
+        let py = self.py();
+        let obj: &PyAny = unsafe { py.from_borrowed_ptr(obj.as_ptr()) };
+        py.import("builtins")?.call1("super", (self, obj))
+    }
+}
+
+/// Imports `name`, which is either fully qualified or refers to `default_module`.
+pub(crate) fn import_qualified<'p>(
+    py: Python<'p>,
+    name: &str,
+    default_module: &str,
+) -> PyResult<&'p PyAny> {
+    let (module, attr) = match name.rfind('.') {
+        Some(pos) => (&name[..pos], &name[pos + 1..]),
+        None => (default_module, name),
+    };
+    py.import(module)?.get(attr)
 }
@@ -0,0 +1,105 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Conversions between [serde_yaml](https://docs.rs/serde_yaml) values and Python objects, so
+//! that a parsed configuration can be handed to Python code and read back after it was modified.
+//!
+//! Mappings become dicts and sequences become lists. Sequences and mappings used as mapping keys
+//! become tuples, so that they are hashable.
+
+use crate::err::{PyErr, PyResult};
+use crate::exceptions::TypeError;
+use crate::object::PyObject;
+use crate::objectprotocol::ObjectProtocol;
+use crate::types::{PyAny, PyBool, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+use crate::{FromPyObject, IntoPy, PyTryFrom, Python, ToPyObject};
+use serde_yaml::{Mapping, Number, Value};
+
+fn number_to_object(py: Python, number: &Number) -> PyObject {
+    if let Some(i) = number.as_i64() {
+        i.to_object(py)
+    } else if let Some(u) = number.as_u64() {
+        u.to_object(py)
+    } else {
+        number.as_f64().unwrap_or(f64::NAN).to_object(py)
+    }
+}
+
+fn key_to_object(py: Python, key: &Value) -> PyObject {
+    match key {
+        Value::Sequence(s) => PyTuple::new(py, s.iter().map(|v| key_to_object(py, v))).into(),
+        Value::Mapping(m) => PyTuple::new(
+            py,
+            m.iter()
+                .map(|(k, v)| PyTuple::new(py, &[key_to_object(py, k), key_to_object(py, v)])),
+        )
+        .into(),
+        _ => key.to_object(py),
+    }
+}
+
+impl ToPyObject for Value {
+    fn to_object(&self, py: Python) -> PyObject {
+        match self {
+            Value::Null => py.None(),
+            Value::Bool(b) => b.to_object(py),
+            Value::Number(n) => number_to_object(py, n),
+            Value::String(s) => s.to_object(py),
+            Value::Sequence(s) => s.to_object(py),
+            Value::Mapping(m) => {
+                let dict = PyDict::new(py);
+                for (k, v) in m {
+                    dict.set_item(key_to_object(py, k), v)
+                        .expect("Failed to set_item on dict");
+                }
+                dict.into()
+            }
+        }
+    }
+}
+
+impl IntoPy<PyObject> for Value {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for Value {
+    /// Accepts `None`, `bool`, `int`, `float`, `str`, lists, tuples and dicts.
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if ob.is_none() {
+            return Ok(Value::Null);
+        }
+        if let Ok(b) = <PyBool as PyTryFrom>::try_from(ob) {
+            return Ok(Value::Bool(b.is_true()));
+        }
+        if <PyLong as PyTryFrom>::try_from(ob).is_ok() {
+            return match ob.extract::<i64>() {
+                Ok(i) => Ok(Value::Number(i.into())),
+                Err(_) => Ok(Value::Number(ob.extract::<u64>()?.into())),
+            };
+        }
+        if <PyFloat as PyTryFrom>::try_from(ob).is_ok() {
+            return Ok(Value::Number(ob.extract::<f64>()?.into()));
+        }
+        if <PyString as PyTryFrom>::try_from(ob).is_ok() {
+            return Ok(Value::String(ob.extract()?));
+        }
+        if let Ok(dict) = <PyDict as PyTryFrom>::try_from(ob) {
+            let mut mapping = Mapping::new();
+            for (k, v) in dict.iter() {
+                mapping.insert(k.extract()?, v.extract()?);
+            }
+            return Ok(Value::Mapping(mapping));
+        }
+        if <PyList as PyTryFrom>::try_from(ob).is_ok()
+            || <PyTuple as PyTryFrom>::try_from(ob).is_ok()
+        {
+            return Ok(Value::Sequence(ob.extract()?));
+        }
+
+        Err(PyErr::new::<TypeError, _>(format!(
+            "Can not convert {} to a YAML value",
+            ob.get_type().name()
+        )))
+    }
+}
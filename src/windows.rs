@@ -0,0 +1,101 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Choosing the Python DLL at runtime on Windows.
+//!
+//! Normally the Python DLL is linked when the application is built, so Windows looks for it with
+//! the default DLL search order when the application starts, and fails to start it if it isn't
+//! found. With the `delay-load` feature, applications can instead link the DLL with
+//! `/DELAYLOAD`, so it is only loaded when the first Python function is called. This lets an
+//! application discover the user's Python installation (from the registry, `PATH` or its own
+//! configuration) and load its DLL with [load_python_dll] before using PyO3.
+//!
+//! The DLL is still bound to the Python version PyO3 was built for, named by [PYTHON_DLL]. The
+//! application has to pass the matching flag to the linker, e.g. in its build script:
+//!
+//! ```text
+//! println!("cargo:rustc-link-arg=/DELAYLOAD:python38.dll");
+//! ```
+//!
+//! If no DLL was loaded when Python is first used, the delay-load helper falls back to the
+//! default search order. If that fails too, the call raises a structured exception, which
+//! aborts the process.
+//!
+//! # Example
+//! ```no_run
+//! use pyo3::prelude::*;
+//! use std::path::Path;
+//!
+//! let install_dir = Path::new(r"C:\Program Files\Python38");
+//! pyo3::windows::load_python_dll(install_dir.join(pyo3::windows::PYTHON_DLL))
+//!     .expect("failed to load Python");
+//!
+//! let gil = Python::acquire_gil();
+//! ```
+
+use std::ffi::OsStr;
+use std::io;
+use std::iter;
+use std::os::raw::c_void;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+
+/// The file name of the Python DLL which PyO3 was built for, e.g. `python38.dll`.
+pub const PYTHON_DLL: &str = env!("PYO3_PYTHON_DLL");
+
+/// Lets the DLL's own dependencies be found in its directory
+const LOAD_WITH_ALTERED_SEARCH_PATH: u32 = 0x0000_0008;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn LoadLibraryExW(name: *const u16, file: *mut c_void, flags: u32) -> *mut c_void;
+    fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+}
+
+fn to_wide(s: &OsStr) -> Vec<u16> {
+    s.encode_wide().chain(iter::once(0)).collect()
+}
+
+/// Returns true if a DLL named [PYTHON_DLL] is loaded in the process.
+pub fn is_python_dll_loaded() -> bool {
+    let name = to_wide(OsStr::new(PYTHON_DLL));
+    !unsafe { GetModuleHandleW(name.as_ptr()) }.is_null()
+}
+
+/// Loads the Python DLL at `path`, which is used by all Python functions called afterwards.
+///
+/// This must be called before any other PyO3 function (including
+/// [prepare_freethreaded_python](crate::prepare_freethreaded_python)), because the first Python
+/// function call binds all of them to the DLL that is loaded at that point.
+///
+/// Fails with `InvalidInput` if the file name of `path` isn't [PYTHON_DLL], as Windows would
+/// otherwise not use it to resolve the delay-loaded functions, and with `AlreadyExists` if a
+/// Python DLL is already loaded.
+pub fn load_python_dll(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+    if !file_name.eq_ignore_ascii_case(PYTHON_DLL) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("expected a path to {}, got {}", PYTHON_DLL, path.display()),
+        ));
+    }
+    if is_python_dll_loaded() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is already loaded", PYTHON_DLL),
+        ));
+    }
+
+    let path = to_wide(path.as_os_str());
+    let module = unsafe {
+        LoadLibraryExW(
+            path.as_ptr(),
+            std::ptr::null_mut(),
+            LOAD_WITH_ALTERED_SEARCH_PATH,
+        )
+    };
+    if module.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::types::IntoPyDict;
+
+#[pyclass(abc = "Mapping")]
+struct AbcMapping {}
+
+#[pyclass]
+struct AbcSized {}
+
+#[test]
+fn test_abc_registration() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let d = [
+        ("AbcMapping", py.get_type::<AbcMapping>()),
+        ("AbcSized", py.get_type::<AbcSized>()),
+    ]
+    .into_py_dict(py);
+    let run = |code| py.run(code, None, Some(d)).unwrap();
+
+    run("import collections.abc; assert issubclass(AbcMapping, collections.abc.Mapping)");
+    run("import collections.abc; assert not issubclass(AbcSized, collections.abc.Sized)");
+
+    let ty = py.get_type::<AbcSized>();
+    ty.register_abc("collections.abc.Sized").unwrap();
+    run("import collections.abc; assert issubclass(AbcSized, collections.abc.Sized)");
+    assert!(ty.register_abc("NoSuchAbc").is_err());
+}
+
+#[pyclass(implements = "typing.SupportsAbs")]
+struct Absolute {}
+
+#[pymethods]
+impl Absolute {
+    fn __abs__(&self) -> i32 {
+        1
+    }
+}
+
+#[pyclass(implements = "SupportsIndex", implements = "typing.SupportsAbs")]
+struct NotAbsolute {}
+
+#[pymethods]
+impl NotAbsolute {
+    fn __index__(&self) -> i32 {
+        1
+    }
+}
+
+#[test]
+fn test_protocol_conformance() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = PyModule::new(py, "protocols").unwrap();
+
+    module.add_class::<Absolute>().unwrap();
+    let err = module.add_class::<NotAbsolute>().unwrap_err();
+    let d = [("err", err.to_object(py))].into_py_dict(py);
+    py.run(
+        "assert isinstance(err, TypeError); \
+         assert str(err) == 'NotAbsolute does not implement protocol typing.SupportsAbs: missing __abs__'",
+        None,
+        Some(d),
+    )
+    .unwrap();
+    assert!(module.get("NotAbsolute").is_err());
+
+    let ty = py.get_type::<Absolute>();
+    assert!(ty.check_protocol("typing.SupportsInt").is_err());
+    assert!(ty.check_protocol("typing.Any").is_err());
+}
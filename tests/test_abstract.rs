@@ -0,0 +1,142 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::type_object::initialize_type;
+
+#[pyclass(abstract)]
+struct Shape {}
+
+#[pymethods]
+impl Shape {
+    #[new]
+    fn new(obj: &PyRawObject) {
+        obj.init(Shape {})
+    }
+
+    #[abstractmethod]
+    fn area(&self) -> f64 {
+        0.0
+    }
+
+    fn name(&self) -> &'static str {
+        "shape"
+    }
+}
+
+#[pyclass(abstract)]
+struct Marker {}
+
+#[pymethods]
+impl Marker {
+    #[new]
+    fn new(obj: &PyRawObject) {
+        obj.init(Marker {})
+    }
+}
+
+#[test]
+fn test_abstract_class() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let shape = py.get_type::<Shape>();
+    let marker = py.get_type::<Marker>();
+    py_run!(
+        py,
+        shape marker,
+        r#"
+import abc, inspect
+assert isinstance(shape, abc.ABCMeta) and inspect.isabstract(shape)
+assert shape.__abstractmethods__ == frozenset(["area"])
+assert shape.area.__isabstractmethod__
+try:
+    shape()
+except TypeError as e:
+    assert str(e) == "Can't instantiate abstract class Shape with abstract methods area", str(e)
+else:
+    assert False, "instantiated an abstract class"
+try:
+    marker()
+except TypeError as e:
+    assert str(e) == "Can't instantiate abstract class Marker", str(e)
+else:
+    assert False, "instantiated an abstract class"
+"#
+    );
+}
+
+#[test]
+fn test_abstract_class_from_rust() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    // Only Python code is prevented from creating instances
+    let shape = Py::new(py, Shape {}).unwrap();
+    py_run!(
+        py,
+        shape,
+        "assert shape.area() == 0.0 and shape.name() == 'shape'"
+    );
+}
+
+#[pyclass(abstract, metaclass = "type")]
+struct NotAnAbc {}
+
+#[test]
+fn test_abstract_class_needs_abc_metaclass() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let err = initialize_type::<NotAnAbc>(py, None).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
+
+#[cfg(feature = "unsound-subclass")]
+#[pyclass(abstract, subclass)]
+struct Base {}
+
+#[cfg(feature = "unsound-subclass")]
+#[pymethods]
+impl Base {
+    #[new]
+    fn new(obj: &PyRawObject) {
+        obj.init(Base {})
+    }
+
+    #[abstractmethod]
+    fn run(&self) -> &'static str {
+        "base"
+    }
+
+    #[abstractmethod]
+    fn stop(&self) {}
+}
+
+#[cfg(feature = "unsound-subclass")]
+#[test]
+fn test_python_subclass_must_override() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let base = py.get_type::<Base>();
+    py_run!(
+        py,
+        base,
+        r#"
+class Partial(base):
+    def run(self):
+        return "partial " + super().run()
+
+assert Partial.__abstractmethods__ == frozenset(["stop"])
+try:
+    Partial()
+except TypeError:
+    pass
+else:
+    assert False, "instantiated a class with abstract methods"
+
+class Complete(Partial):
+    def stop(self):
+        pass
+
+assert Complete().run() == "partial base"
+"#
+    );
+}
@@ -0,0 +1,106 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use pyo3::wrap_pyfunction;
+
+#[pyfunction(a, "/", b = 2, "*", c, d = 4)]
+fn pos_and_kw_only(a: i32, b: i32, c: i32, d: i32) -> (i32, i32, i32, i32) {
+    (a, b, c, d)
+}
+
+#[pyfunction(a, "/", args = "*", c = 3, kwargs = "**")]
+fn pos_only_kwargs(
+    a: i32,
+    args: &PyTuple,
+    c: i32,
+    kwargs: Option<&PyDict>,
+) -> PyResult<(i32, usize, i32, Vec<String>)> {
+    let keys = match kwargs {
+        Some(kwargs) => kwargs.keys().extract()?,
+        None => Vec::new(),
+    };
+    Ok((a, args.len(), c, keys))
+}
+
+#[pyfunction]
+fn two_args(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[pyfunction(a, b = 1)]
+fn defaults(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn add_functions(py: Python) -> &PyDict {
+    let d = PyDict::new(py);
+    d.set_item("pos_and_kw_only", wrap_pyfunction!(pos_and_kw_only)(py))
+        .unwrap();
+    d.set_item("pos_only_kwargs", wrap_pyfunction!(pos_only_kwargs)(py))
+        .unwrap();
+    d.set_item("two_args", wrap_pyfunction!(two_args)(py))
+        .unwrap();
+    d.set_item("defaults", wrap_pyfunction!(defaults)(py))
+        .unwrap();
+    d
+}
+
+#[test]
+fn test_pos_only_and_kw_only() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let d = add_functions(py);
+    py.run(
+        r#"
+assert pos_and_kw_only(1, c=3) == (1, 2, 3, 4)
+assert pos_and_kw_only(1, b=5, c=3, d=6) == (1, 5, 3, 6)
+assert pos_and_kw_only.__text_signature__ == "(a, /, b=2, *, c, d=4)"
+
+# Positional-only arguments passed by keyword end up in **kwargs
+assert pos_only_kwargs(1, 2, 3, a=4) == (1, 2, 3, ["a"])
+assert pos_only_kwargs.__text_signature__ == "(a, /, *args, c=3, **kwargs)"
+"#,
+        Some(d),
+        None,
+    )
+    .map_err(|e| e.print(py))
+    .unwrap();
+}
+
+#[test]
+fn test_error_messages() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let d = add_functions(py);
+    py.run(
+        r#"
+def error(call):
+    try:
+        call()
+    except TypeError as e:
+        return str(e)
+    raise AssertionError("no TypeError raised")
+
+assert error(lambda: pos_and_kw_only(a=1, c=3)) == \
+    "pos_and_kw_only() got some positional-only arguments passed as keyword arguments: 'a'"
+assert error(lambda: pos_and_kw_only(1)) == \
+    "pos_and_kw_only() missing 1 required keyword-only argument: 'c'"
+assert error(lambda: pos_and_kw_only(1, 2, 3)) == \
+    "pos_and_kw_only() takes from 1 to 2 positional arguments but 3 were given"
+assert error(lambda: two_args()) == \
+    "two_args() missing 2 required positional arguments: 'a' and 'b'"
+assert error(lambda: two_args(1, 2, 3)) == \
+    "two_args() takes 2 positional arguments but 3 were given"
+assert error(lambda: two_args(1, a=2)) == \
+    "two_args() got multiple values for argument 'a'"
+assert error(lambda: two_args(1, 2, c=3)) == \
+    "two_args() got an unexpected keyword argument 'c'"
+assert error(lambda: defaults()) == \
+    "defaults() missing 1 required positional argument: 'a'"
+assert error(lambda: two_args(1, "2")).startswith("argument 'b': ")
+"#,
+        Some(d),
+        None,
+    )
+    .map_err(|e| e.print(py))
+    .unwrap();
+}
@@ -210,6 +210,63 @@ fn rhs_arithmetic() {
     // py_run!(py, c, "assert 1 + c == '1 + RA'");
 }
 
+#[pyclass]
+struct Meters {
+    value: f64,
+}
+
+#[pyproto]
+impl PyNumberProtocol for Meters {
+    fn __add__(lhs: &Meters, rhs: &Meters) -> PyResult<f64> {
+        Ok(lhs.value + rhs.value)
+    }
+
+    fn __sub__(lhs: &PyAny, rhs: &PyAny) -> PyResult<NotImplementedOr<f64>> {
+        let lhs = lhs.extract::<&Meters>().map(|m| m.value);
+        let rhs = rhs.extract::<&Meters>().map(|m| m.value);
+        Ok(match (lhs, rhs) {
+            (Ok(a), Ok(b)) => NotImplementedOr::Value(a - b),
+            _ => NotImplementedOr::NotImplemented,
+        })
+    }
+
+    fn __mul__(lhs: &Meters, rhs: u8) -> PyResult<f64> {
+        Ok(lhs.value * f64::from(rhs))
+    }
+}
+
+#[test]
+fn mixed_type_arithmetic_defers() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let m = Py::new(py, Meters { value: 2.0 }).unwrap();
+    py_run!(
+        py,
+        m,
+        r#"
+class Other:
+    def __radd__(self, other):
+        return "radd"
+    def __rsub__(self, other):
+        return "rsub"
+    def __rmul__(self, other):
+        return "rmul"
+
+assert m + m == 4.0
+assert m - m == 0.0
+assert m + Other() == "radd"
+assert m - Other() == "rsub"
+assert m * 3 == 6.0
+assert m * Other() == "rmul"
+"#
+    );
+    py_expect_exception!(py, m, "m + 1", TypeError);
+    py_expect_exception!(py, m, "m - 1", TypeError);
+    // Only unsupported types are deferred to the other operand
+    py_expect_exception!(py, m, "m * 300", OverflowError);
+}
+
 #[pyclass]
 struct RichComparisons {}
 
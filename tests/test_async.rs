@@ -0,0 +1,208 @@
+#![feature(specialization)]
+
+use pyo3::exceptions::{asyncio::CancelledError, ValueError};
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::types::PyType;
+use pyo3::wrap_pyfunction;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+/// Completes with `value` after `delay`, woken from another thread
+struct Delay {
+    state: Arc<Mutex<(Option<u64>, Option<Waker>)>>,
+}
+
+impl Delay {
+    fn new(value: u64, delay: Duration) -> Delay {
+        let state = Arc::new(Mutex::new((None, None::<Waker>)));
+        let shared = state.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            let mut state = shared.lock().unwrap();
+            state.0 = Some(value);
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        });
+        Delay { state }
+    }
+}
+
+impl Future for Delay {
+    type Output = u64;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<u64> {
+        let mut state = self.state.lock().unwrap();
+        match state.0 {
+            Some(value) => Poll::Ready(value),
+            None => {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[pyfunction]
+async fn sleep_and_double(value: u64, millis: u64) -> u64 {
+    Delay::new(value, Duration::from_millis(millis)).await * 2
+}
+
+#[pyfunction]
+async fn fail(message: String) -> PyResult<()> {
+    Err(ValueError::py_err(message))
+}
+
+#[pyfunction]
+async fn cancel_self() -> PyResult<()> {
+    Err(CancelledError::py_err(()))
+}
+
+#[test]
+fn test_async_function() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let sleep_and_double = wrap_pyfunction!(sleep_and_double)(py);
+    let fail = wrap_pyfunction!(fail)(py);
+    let cancel_self = wrap_pyfunction!(cancel_self)(py);
+    py_run!(
+        py,
+        sleep_and_double fail cancel_self,
+        r#"
+import asyncio
+assert asyncio.iscoroutine(sleep_and_double(1, 0))
+assert asyncio.run(sleep_and_double(21, 10)) == 42
+async def gather(f):
+    tasks = map(f, range(10), [10] * 10)
+    return await __import__("asyncio").wait_for(__import__("asyncio").gather(*tasks), 1)
+results = asyncio.run(gather(sleep_and_double))
+assert results == [2 * i for i in range(10)]
+
+try:
+    asyncio.run(fail("failed"))
+except ValueError as err:
+    assert str(err) == "failed"
+else:
+    assert False
+
+try:
+    asyncio.run(cancel_self())
+except asyncio.CancelledError:
+    pass
+else:
+    assert False
+"#
+    );
+}
+
+static DROPPED: AtomicBool = AtomicBool::new(false);
+
+struct DropFlag;
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[pyfunction]
+async fn forever() {
+    let _flag = DropFlag;
+    std::future::pending::<()>().await
+}
+
+#[test]
+fn test_cancellation() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let forever = wrap_pyfunction!(forever)(py);
+    py_run!(
+        py,
+        forever,
+        r#"
+import asyncio
+try:
+    asyncio.run(asyncio.wait_for(forever(), 0.01))
+except asyncio.TimeoutError:
+    pass
+else:
+    assert False
+"#
+    );
+    assert!(DROPPED.load(Ordering::SeqCst));
+}
+
+#[pyclass]
+struct Counter {
+    count: u64,
+}
+
+#[pymethods]
+impl Counter {
+    #[staticmethod]
+    async fn increment(counter: PyObject, by: u64) -> PyResult<u64> {
+        let by = Delay::new(by, Duration::from_millis(1)).await;
+        let gil = Python::acquire_gil();
+        let counter: &mut Counter = counter.extract(gil.python())?;
+        counter.count += by;
+        Ok(counter.count)
+    }
+}
+
+#[test]
+fn test_async_static_method() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let counter = Py::new(py, Counter { count: 1 }).unwrap();
+    py_run!(
+        py,
+        counter,
+        r#"
+import asyncio
+assert asyncio.run(counter.increment(counter, 2)) == 3
+assert asyncio.run(type(counter).increment(counter, 3)) == 6
+"#
+    );
+}
+
+#[pyclass]
+struct Scaler {
+    factor: u64,
+}
+
+#[pymethods]
+impl Scaler {
+    async fn scale(&self, value: u64) -> u64 {
+        Delay::new(value, Duration::from_millis(1)).await * self.factor
+    }
+
+    #[classmethod]
+    async fn type_name(cls: Py<PyType>, millis: u64) -> String {
+        Delay::new(0, Duration::from_millis(millis)).await;
+        let gil = Python::acquire_gil();
+        cls.as_ref(gil.python()).name().into_owned()
+    }
+}
+
+#[test]
+fn test_async_method_and_class_method() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let scaler = Py::new(py, Scaler { factor: 3 }).unwrap();
+    py_run!(
+        py,
+        scaler,
+        r#"
+import asyncio
+assert asyncio.run(scaler.scale(2)) == 6
+assert asyncio.run(scaler.type_name(1)) == "Scaler"
+assert asyncio.run(type(scaler).type_name(1)) == "Scaler"
+"#
+    );
+}
@@ -1,8 +1,11 @@
+use pyo3::buffer::BufferExport;
 use pyo3::class::PyBufferProtocol;
 use pyo3::exceptions::BufferError;
 use pyo3::ffi;
 use pyo3::prelude::*;
-use pyo3::types::IntoPyDict;
+use pyo3::types::{IntoPyDict, PyMemoryView};
+use pyo3::{py_run, AsPyPointer};
+use std::cell::Cell;
 use std::ffi::CStr;
 use std::os::raw::{c_int, c_void};
 use std::ptr;
@@ -76,3 +79,159 @@ fn test_buffer() {
     let d = [("ob", t)].into_py_dict(py);
     py.run("assert bytes(ob) == b' 23'", None, Some(d)).unwrap();
 }
+
+#[pyclass]
+struct Matrix {
+    values: Vec<i32>,
+    shape: [isize; 2],
+    strides: [isize; 2],
+}
+
+#[pyproto]
+impl PyBufferProtocol for Matrix {
+    fn bf_getbuffer(&self, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        unsafe {
+            BufferExport::new(&self.values)
+                .with_shape(&self.shape, &self.strides)
+                .fill(self, view, flags)
+        }
+    }
+}
+
+#[test]
+fn test_buffer_export_shape() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let matrix = Py::new(
+        py,
+        Matrix {
+            values: vec![1, 2, 3, 4, 5, 6],
+            shape: [2, 3],
+            strides: [12, 4],
+        },
+    )
+    .unwrap();
+    // The transposed matrix
+    let transposed = Py::new(
+        py,
+        Matrix {
+            values: vec![1, 2, 3, 4, 5, 6],
+            shape: [3, 2],
+            strides: [4, 12],
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        matrix transposed,
+        r#"
+        view = memoryview(matrix)
+        assert view.format == "i"
+        assert view.shape == (2, 3)
+        assert view.tolist() == [[1, 2, 3], [4, 5, 6]]
+        assert view.c_contiguous and view.readonly
+        assert memoryview(transposed).tolist() == [[1, 4], [2, 5], [3, 6]]
+        assert memoryview(transposed).f_contiguous
+        # Consumers which can't handle strides get a contiguous copy
+        assert bytes(transposed) == bytes(memoryview(transposed).tobytes())
+        "#
+    );
+
+    let transposed = PyMemoryView::from(py, &transposed).unwrap();
+    assert_eq!(transposed.to_vec::<i32>().unwrap(), [1, 4, 2, 5, 3, 6]);
+}
+
+#[test]
+fn test_buffer_export_invalid_shape() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let matrix = Py::new(
+        py,
+        Matrix {
+            values: vec![1, 2, 3, 4, 5, 6],
+            shape: [3, 3],
+            strides: [12, 4],
+        },
+    )
+    .unwrap();
+    let err = PyMemoryView::from(py, &matrix).unwrap_err();
+    assert!(err.is_instance::<BufferError>(py));
+}
+
+#[pyclass]
+struct Counters {
+    values: Vec<Cell<u64>>,
+    exports: Cell<usize>,
+}
+
+#[pyproto]
+impl PyBufferProtocol for Counters {
+    fn bf_getbuffer(&self, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        unsafe { BufferExport::new_writable(&self.values).fill(self, view, flags)? };
+        self.exports.set(self.exports.get() + 1);
+        Ok(())
+    }
+
+    fn bf_releasebuffer(&self, _view: *mut ffi::Py_buffer) -> PyResult<()> {
+        self.exports.set(self.exports.get() - 1);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_buffer_export_writable() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let counters = Py::new(
+        py,
+        Counters {
+            values: vec![Cell::new(0), Cell::new(1)],
+            exports: Cell::new(0),
+        },
+    )
+    .unwrap();
+    let view = PyMemoryView::from(py, &counters).unwrap();
+    assert_eq!(counters.as_ref(py).exports.get(), 1);
+    py_run!(py, view, "view[1] += 41");
+    assert_eq!(counters.as_ref(py).values[1].get(), 42);
+    py_run!(
+        py,
+        view,
+        r#"
+        assert len(view) == 2 and view.shape == (2,)
+        assert view.tolist() == [0, 42]
+        assert view.nbytes == 16
+        "#
+    );
+
+    view.release().unwrap();
+    assert_eq!(counters.as_ref(py).exports.get(), 0);
+}
+
+#[test]
+fn test_buffer_export_readonly() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let matrix = Py::new(
+        py,
+        Matrix {
+            values: vec![1, 2],
+            shape: [1, 2],
+            strides: [8, 4],
+        },
+    )
+    .unwrap();
+    let err = unsafe {
+        let mut view: ffi::Py_buffer = std::mem::zeroed();
+        assert_eq!(
+            ffi::PyObject_GetBuffer(matrix.as_ptr(), &mut view, ffi::PyBUF_WRITABLE),
+            -1
+        );
+        PyErr::fetch(py)
+    };
+    assert!(err.is_instance::<BufferError>(py));
+}
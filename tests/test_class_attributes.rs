@@ -0,0 +1,89 @@
+#![feature(specialization)]
+
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use pyo3::py_run;
+
+#[pyclass]
+#[derive(Clone)]
+struct Foo {
+    #[pyo3(get)]
+    x: i32,
+}
+
+#[pymethods]
+impl Foo {
+    #[classattr]
+    const MAX_SIZE: usize = 1024;
+
+    #[classattr]
+    const r#NAME: &'static str = "foo";
+
+    #[classattr]
+    fn greeting() -> String {
+        "hello".to_string()
+    }
+
+    #[classattr]
+    #[name = "DEFAULT"]
+    fn default_instance() -> Foo {
+        Foo { x: 42 }
+    }
+
+    #[classattr]
+    fn keys(py: Python) -> PyObject {
+        vec!["a", "b"].to_object(py)
+    }
+}
+
+#[test]
+fn test_class_attributes() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let foo = py.get_type::<Foo>();
+    py_run!(
+        py,
+        foo,
+        r#"
+assert foo.MAX_SIZE == 1024
+assert foo.NAME == "foo"
+assert foo.greeting == "hello"
+assert foo.keys == ["a", "b"]
+"#
+    );
+}
+
+#[test]
+fn test_class_attribute_of_own_type() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let foo = py.get_type::<Foo>();
+    py_run!(
+        py,
+        foo,
+        r#"
+assert isinstance(foo.DEFAULT, foo)
+assert foo.DEFAULT.x == 42
+assert foo.DEFAULT.DEFAULT is foo.DEFAULT
+"#
+    );
+}
+
+#[pyclass]
+struct Broken {}
+
+#[pymethods]
+impl Broken {
+    #[classattr]
+    fn value() -> PyResult<i32> {
+        Err(PyErr::new::<ValueError, _>("can't compute"))
+    }
+}
+
+#[test]
+fn test_class_attribute_error() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let err = pyo3::type_object::initialize_type::<Broken>(py, None).unwrap_err();
+    assert!(err.is_instance::<ValueError>(py));
+}
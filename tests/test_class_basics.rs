@@ -1,6 +1,7 @@
 use pyo3::prelude::*;
 use pyo3::py_run;
 use pyo3::type_object::initialize_type;
+use pyo3::AsPyPointer;
 
 mod common;
 
@@ -113,3 +114,51 @@ fn empty_class_in_module() {
     let module: String = ty.getattr("__module__").unwrap().extract().unwrap();
     assert_eq!(module, "test_module.nested");
 }
+
+#[pyclass]
+struct Point {
+    #[pyo3(get)]
+    x: i32,
+    #[pyo3(get)]
+    y: i32,
+}
+
+#[test]
+fn new_batch() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let points = Py::new_batch(py, (0..3).map(|i| Point { x: i, y: -i })).unwrap();
+    assert_eq!(points.len(), 3);
+    for (i, point) in points.iter().enumerate() {
+        assert_eq!(point.as_ref(py).x, i as i32);
+        assert_eq!(point.as_ref(py).y, -(i as i32));
+    }
+    // One distinct instance per value
+    let ptrs: std::collections::HashSet<_> = points.iter().map(|p| p.as_ptr()).collect();
+    assert_eq!(ptrs.len(), 3);
+
+    let empty = Py::new_batch(py, Vec::<Point>::new()).unwrap();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn list_from_pyclass_iter() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let empty = pyo3::types::PyList::from_pyclass_iter(py, Vec::<Point>::new()).unwrap();
+    assert!(empty.is_empty());
+
+    let objects =
+        pyo3::types::PyList::from_pyclass_iter(py, (0..1000).map(|i| Point { x: i, y: 0 }))
+            .unwrap();
+    let point = py.get_type::<Point>();
+    py_run!(
+        py,
+        objects point,
+        r#"
+        assert len(objects) == 1000
+        assert set(map(type, objects)) == {point}
+        assert [p.x for p in objects] == list(range(1000))
+        "#
+    );
+}
@@ -0,0 +1,128 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+
+#[pyclass(eq, ord, hash)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Version {
+    major: u32,
+    minor: u32,
+}
+
+#[pyclass(eq)]
+#[derive(PartialEq)]
+struct Color {
+    name: String,
+}
+
+#[pyclass(ord)]
+#[derive(PartialEq, PartialOrd)]
+struct Measure {
+    value: f64,
+}
+
+#[test]
+fn test_eq_ord_hash() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let v1 = Py::new(py, Version { major: 1, minor: 2 }).unwrap();
+    let v2 = Py::new(
+        py,
+        Version {
+            major: 1,
+            minor: 10,
+        },
+    )
+    .unwrap();
+    let v2_copy = Py::new(
+        py,
+        Version {
+            major: 1,
+            minor: 10,
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        v1 v2 v2_copy,
+        r#"
+assert v2 == v2_copy and not (v2 != v2_copy) and v2 is not v2_copy
+assert v1 != v2
+assert v1 < v2 and v1 <= v2 and v2 > v1 and v2 >= v1 and v2 >= v2_copy
+assert sorted([v2, v1]) == [v1, v2]
+assert hash(v2) == hash(v2_copy)
+assert len({v1, v2, v2_copy}) == 2
+assert v1 != (1, 2) and not (v1 == (1, 2))
+try:
+    v1 < 1
+except TypeError:
+    pass
+else:
+    assert False
+"#
+    );
+}
+
+#[test]
+fn test_eq_only() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let red = Py::new(
+        py,
+        Color {
+            name: "red".to_string(),
+        },
+    )
+    .unwrap();
+    let other_red = Py::new(
+        py,
+        Color {
+            name: "red".to_string(),
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        red other_red,
+        r#"
+assert red == other_red
+try:
+    red < other_red
+except TypeError:
+    pass
+else:
+    assert False
+# Like Python classes defining __eq__ without __hash__
+try:
+    hash(red)
+except TypeError:
+    pass
+else:
+    assert False
+"#
+    );
+}
+
+#[test]
+fn test_partial_ord() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let one = Py::new(py, Measure { value: 1.0 }).unwrap();
+    let nan = Py::new(
+        py,
+        Measure {
+            value: std::f64::NAN,
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        one nan,
+        r#"
+assert one <= one and one == one
+assert not (nan < one or nan <= one or nan > one or nan >= one or nan == nan)
+assert nan != nan
+"#
+    );
+}
@@ -4,4 +4,7 @@ fn test_compile_errors() {
     t.compile_fail("tests/ui/reject_generics.rs");
     t.compile_fail("tests/ui/too_many_args_to_getter.rs");
     t.compile_fail("tests/ui/invalid_pymethod_names.rs");
+    t.compile_fail("tests/ui/reject_singleton_new.rs");
+    t.compile_fail("tests/ui/reject_gc_field.rs");
+    t.compile_fail("tests/ui/reject_gc_py_field.rs");
 }
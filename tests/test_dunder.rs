@@ -62,6 +62,10 @@ impl<'p> PyIterProtocol for Iterator {
     fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<i32>> {
         Ok(slf.iter.next())
     }
+
+    fn __length_hint__(&self) -> PyResult<usize> {
+        Ok(self.iter.size_hint().0)
+    }
 }
 
 #[test]
@@ -77,7 +81,9 @@ fn iterator() {
     )
     .unwrap();
     py_assert!(py, inst, "iter(inst) is inst");
+    py_assert!(py, inst, "__import__('operator').length_hint(inst) == 3");
     py_assert!(py, inst, "list(inst) == [5, 6, 7]");
+    py_assert!(py, inst, "__import__('operator').length_hint(inst) == 0");
 }
 
 #[pyclass]
@@ -519,3 +525,107 @@ fn getattr_doesnt_override_member() {
     py_assert!(py, inst, "inst.data == 4");
     py_assert!(py, inst, "inst.a == 8");
 }
+
+#[pyclass]
+struct ClassWithFailingGetter {}
+
+#[pymethods]
+impl ClassWithFailingGetter {
+    #[getter]
+    fn broken(&self) -> PyResult<u32> {
+        Err(ValueError::py_err("broken"))
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for ClassWithFailingGetter {
+    fn __getattr__(&self, _name: &str) -> PyResult<u32> {
+        Ok(0)
+    }
+}
+
+#[test]
+fn getattr_only_for_missing_attributes() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = PyRef::new(py, ClassWithFailingGetter {}).unwrap();
+    py_assert!(py, inst, "inst.missing == 0");
+    py_expect_exception!(py, inst, "inst.broken", ValueError);
+}
+
+/// A dynamic configuration store
+#[pyclass]
+struct Config {
+    values: std::collections::HashMap<String, PyObject>,
+    #[pyo3(get, set)]
+    strict: bool,
+}
+
+#[pymethods]
+impl Config {
+    fn __getattr__(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        match self.values.get(name) {
+            Some(value) => Ok(value.clone_ref(py)),
+            None => Err(pyo3::exceptions::AttributeError::py_err(name.to_string())),
+        }
+    }
+
+    fn __setattr__(&mut self, py: Python, name: &str, value: PyObject) -> PyResult<()> {
+        if name == "strict" {
+            // `__setattr__` is called for properties as well
+            self.strict = value.extract(py)?;
+        } else {
+            self.values.insert(name.to_string(), value);
+        }
+        Ok(())
+    }
+
+    fn __delattr__(&mut self, name: &str) -> PyResult<()> {
+        match self.values.remove(name) {
+            Some(_) => Ok(()),
+            None => Err(pyo3::exceptions::AttributeError::py_err(name.to_string())),
+        }
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.values.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+}
+
+#[test]
+fn getattr_setattr_delattr_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let config = PyRef::new(
+        py,
+        Config {
+            values: Default::default(),
+            strict: false,
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        config,
+        r#"
+config.timeout = 30
+config.name = "main"
+config.strict = True
+assert config.timeout == 30 and config.name == "main"
+assert config.strict is True
+assert config.keys() == ["name", "timeout"]
+assert getattr(config, "missing", None) is None
+del config.timeout
+assert not hasattr(config, "timeout")
+try:
+    del config.timeout
+except AttributeError:
+    pass
+else:
+    assert False
+"#
+    );
+    assert!(config.strict);
+}
@@ -0,0 +1,107 @@
+#![feature(specialization)]
+
+use pyo3::enums::PyEnum;
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::wrap_pyfunction;
+
+/// A direction
+#[pyclass(module = "compass")]
+#[derive(Debug, PartialEq)]
+enum Direction {
+    North,
+    East = 90,
+    South = 180,
+    West = 270,
+}
+
+#[pyclass(name = Level)]
+#[derive(Debug, PartialEq)]
+enum LogLevel {
+    Debug = -1,
+    Info,
+}
+
+#[pyfunction]
+fn turn_right(direction: Direction) -> Direction {
+    match direction {
+        Direction::North => Direction::East,
+        Direction::East => Direction::South,
+        Direction::South => Direction::West,
+        Direction::West => Direction::North,
+    }
+}
+
+#[test]
+fn test_enum_class() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let direction = Direction::type_object(py);
+    let level = LogLevel::type_object(py);
+    py_run!(
+        py,
+        direction level,
+        r#"
+import enum
+assert issubclass(direction, enum.IntEnum)
+assert direction.__name__ == "Direction" and direction.__module__ == "compass"
+assert direction.__doc__ == "A direction"
+assert [(d.name, d.value) for d in direction] == [
+    ("North", 0), ("East", 90), ("South", 180), ("West", 270)
+]
+assert direction.South == 180 and int(direction.West) == 270
+assert hash(direction.East) == hash(90)
+assert direction(90) is direction.East and direction["West"] is direction.West
+assert level.__name__ == "Level" and level.Debug == -1 and level.Info == 0
+"#
+    );
+}
+
+#[test]
+fn test_enum_conversion() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let direction = Direction::type_object(py);
+    let west = Direction::West.to_object(py);
+    assert!(direction.is_instance(&west).unwrap());
+    let east: Direction = direction.getattr("East").unwrap().extract().unwrap();
+    assert_eq!(east, Direction::East);
+    assert!(py
+        .eval("90", None, None)
+        .unwrap()
+        .extract::<Direction>()
+        .is_err());
+
+    let turn_right = wrap_pyfunction!(turn_right)(py);
+    py_run!(
+        py,
+        direction turn_right,
+        r#"
+assert turn_right(direction.West) is direction.North
+try:
+    turn_right(90)
+except TypeError:
+    pass
+else:
+    assert False
+"#
+    );
+}
+
+#[pymodule]
+fn compass(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_enum::<Direction>()
+}
+
+#[test]
+fn test_add_enum() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let compass = pyo3::wrap_pymodule!(compass)(py);
+    let direction = Direction::type_object(py);
+    py_run!(
+        py,
+        compass direction,
+        "assert compass.Direction is direction"
+    );
+}
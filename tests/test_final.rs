@@ -0,0 +1,118 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+
+#[pyclass(final)]
+struct Sealed {}
+
+#[test]
+fn test_final_class() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let sealed = py.get_type::<Sealed>();
+    py_run!(
+        py,
+        sealed,
+        r#"
+assert sealed.__final__ is True
+try:
+    class Sub(sealed):
+        pass
+except TypeError:
+    pass
+else:
+    assert False, "subclassed a final class"
+"#
+    );
+}
+
+#[pyclass]
+struct Counter {
+    count: u32,
+}
+
+#[pymethods]
+impl Counter {
+    #[pyo3(final)]
+    fn increment(&mut self) -> u32 {
+        self.count += 1;
+        self.count
+    }
+}
+
+#[test]
+fn test_final_method_is_callable() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let counter = Py::new(py, Counter { count: 0 }).unwrap();
+    py_run!(
+        py,
+        counter,
+        "assert counter.increment() == 1 and counter.increment() == 2"
+    );
+}
+
+#[cfg(feature = "unsound-subclass")]
+#[pyclass(subclass)]
+struct Account {}
+
+#[cfg(feature = "unsound-subclass")]
+#[pymethods]
+impl Account {
+    #[new]
+    fn new(obj: &PyRawObject) {
+        obj.init(Account {})
+    }
+
+    #[pyo3(final)]
+    fn balance(&self) -> u32 {
+        100
+    }
+
+    #[classmethod]
+    #[pyo3(final)]
+    fn currency(_cls: &pyo3::types::PyType) -> &'static str {
+        "EUR"
+    }
+
+    fn describe(&self) -> &'static str {
+        "account"
+    }
+}
+
+#[cfg(feature = "unsound-subclass")]
+#[test]
+fn test_final_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let account = py.get_type::<Account>();
+    py_run!(
+        py,
+        account,
+        r#"
+class Savings(account):
+    def describe(self):
+        return "savings " + super().describe()
+
+assert Savings().describe() == "savings account" and Savings().balance() == 100
+
+for name in ["balance", "currency"]:
+    try:
+        type("Cheat", (Savings,), {name: lambda self: 0})
+    except TypeError as e:
+        assert str(e) == "Cheat can't override final method Account." + name, str(e)
+    else:
+        assert False, "overrode a final method"
+
+# Keyword arguments are passed on to the other base classes
+try:
+    class WithArgument(account, argument=1):
+        pass
+except TypeError:
+    pass
+else:
+    assert False
+"#
+    );
+}
@@ -0,0 +1,220 @@
+use pyo3::forward::{partial, update_wrapper, CallArgs};
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyAny, PyDict, PyTuple};
+use pyo3::{py_run, wrap_pyfunction};
+
+mod common;
+
+/// Calls `func` with an extra first argument, a `timeout` of 10 unless one is given and
+/// without the `verbose` argument.
+#[pyfunction(func, args = "*", kwargs = "**")]
+fn forward(func: &PyAny, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<PyObject> {
+    let mut call = CallArgs::new(args, kwargs);
+    call.insert_arg(0, "first");
+    call.set_default_kwarg("timeout", 10)?;
+    call.remove_kwarg("verbose")?;
+    call.call(func).map(PyObject::from)
+}
+
+#[pyfunction(args = "*", kwargs = "**")]
+fn describe(args: &PyTuple, kwargs: Option<&PyDict>) -> String {
+    let mut keys: Vec<String> = kwargs
+        .map(|kwargs| kwargs.keys().iter().map(|key| key.to_string()).collect())
+        .unwrap_or_default();
+    keys.sort();
+    format!("{} {:?}", args.len(), keys)
+}
+
+#[test]
+fn test_forward_to_python() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let forward = wrap_pyfunction!(forward)(py);
+    py_run!(
+        py,
+        forward,
+        r#"
+        def target(*args, **kwargs):
+            return args, kwargs
+
+        assert forward(target) == (("first",), {"timeout": 10})
+        assert forward(target, 1, timeout=2) == (("first", 1), {"timeout": 2})
+
+        kwargs = {"verbose": True, "x": 1}
+        assert forward(target, **kwargs) == (("first",), {"x": 1, "timeout": 10})
+        assert kwargs == {"verbose": True, "x": 1}
+        "#
+    );
+}
+
+#[test]
+fn test_forward_to_wrapped_function() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let describe = wrap_pyfunction!(describe)(py);
+
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("a", 1).unwrap();
+    let mut call = CallArgs::new(PyTuple::new(py, &[1, 2]), Some(kwargs));
+    call.push_arg(3);
+    assert_eq!(call.remove_arg(0).extract::<i32>().unwrap(), 1);
+    call.set_kwarg("a", 2).unwrap();
+    call.set_kwarg("b", 3).unwrap();
+    assert_eq!(call.get_kwarg("a").unwrap().extract::<i32>().unwrap(), 2);
+
+    let result = call.call(&describe).unwrap();
+    assert_eq!(result.extract::<String>().unwrap(), r#"2 ["a", "b"]"#);
+    // The caller's dict isn't modified
+    assert_eq!(kwargs.len(), 1);
+    assert_eq!(kwargs.get_item("a").unwrap().extract::<i32>().unwrap(), 1);
+
+    let (args, kwargs) = call.into_parts();
+    assert_eq!(args.extract::<(i32, i32)>().unwrap(), (2, 3));
+    assert_eq!(kwargs.unwrap().len(), 2);
+}
+
+#[test]
+fn test_empty() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let describe = wrap_pyfunction!(describe)(py);
+
+    let mut call = CallArgs::empty(py);
+    assert!(call.kwargs().is_none());
+    assert!(call.remove_kwarg("missing").unwrap().is_none());
+    assert!(call.kwargs().is_none());
+    let result = call.call(&describe).unwrap();
+    assert_eq!(result.extract::<String>().unwrap(), "0 []");
+}
+
+/// Calls a function and counts the calls.
+#[pyclass(dict)]
+struct Counted {
+    func: PyObject,
+    #[pyo3(get)]
+    calls: u64,
+}
+
+#[pymethods]
+impl Counted {
+    #[call]
+    #[args(args = "*", kwargs = "**")]
+    fn __call__(&mut self, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<PyObject> {
+        self.calls += 1;
+        CallArgs::new(args, kwargs)
+            .call(&self.func)
+            .map(PyObject::from)
+    }
+}
+
+#[pyfunction]
+fn counted(py: Python, func: &PyAny) -> PyResult<PyObject> {
+    let wrapper: PyObject = Py::new(
+        py,
+        Counted {
+            func: func.into(),
+            calls: 0,
+        },
+    )?
+    .into();
+    update_wrapper(wrapper.cast_as(py)?, func)?;
+    Ok(wrapper)
+}
+
+#[test]
+fn test_update_wrapper() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let counted = wrap_pyfunction!(counted)(py);
+    py_run!(
+        py,
+        counted,
+        r#"
+        import functools, inspect
+
+        def plain(name, punctuation="!"):
+            "Says hello"
+        plain.extra = 1
+        greet = counted(plain)
+
+        assert greet("world") is None and greet.calls == 1
+        for name in functools.WRAPPER_ASSIGNMENTS:
+            if name != "__annotations__":
+                assert getattr(greet, name) == getattr(plain, name), name
+        assert greet.__wrapped__ is plain
+        assert greet.extra == 1
+        assert str(inspect.signature(greet)) == "(name, punctuation='!')"
+        "#
+    );
+}
+
+#[test]
+fn test_update_wrapper_missing_attributes() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let counted = wrap_pyfunction!(counted)(py);
+    py_run!(
+        py,
+        counted,
+        r#"
+        import functools
+
+        partial = functools.partial(print, end="")
+        wrapper = counted(partial)
+        assert wrapper.__wrapped__ is partial
+        assert wrapper.__doc__ == partial.__doc__
+        assert not hasattr(wrapper, "__qualname__")
+        "#
+    );
+}
+
+#[test]
+fn test_update_wrapper_read_only() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let describe = wrap_pyfunction!(describe)(py);
+    let wrapper = wrap_pyfunction!(forward)(py);
+    let err =
+        update_wrapper(wrapper.cast_as(py).unwrap(), describe.cast_as(py).unwrap()).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::AttributeError>(py));
+}
+
+#[test]
+fn test_partial() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let describe = wrap_pyfunction!(describe)(py);
+    let describe = describe.cast_as::<PyAny>(py).unwrap();
+
+    let kwargs = [("a", 1)].into_py_dict(py);
+    let bound = partial(describe, (1, vec![2, 3]), Some(kwargs)).unwrap();
+    py_run!(
+        py,
+        bound,
+        r#"
+        import functools
+        assert isinstance(bound, functools.partial)
+        assert bound.args == (1, [2, 3])
+        assert bound() == '2 ["a"]'
+        assert bound(4, b=5, a=6) == '3 ["a", "b"]'
+        "#
+    );
+
+    let greet = py
+        .eval(
+            "lambda greeting, name, end='.': greeting + ' ' + name + end",
+            None,
+            None,
+        )
+        .unwrap();
+    let hello = partial(greet, ("Hello",), None).unwrap();
+    py_run!(
+        py,
+        hello,
+        r#"
+        from inspect import signature
+        assert hello("world") == "Hello world."
+        assert str(signature(hello)) == "(name, end='.')"
+        "#
+    );
+}
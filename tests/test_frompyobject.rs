@@ -0,0 +1,115 @@
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict};
+
+#[derive(Debug, PartialEq, FromPyObject)]
+#[pyo3(dataclass = "types.SimpleNamespace")]
+struct Config {
+    host: String,
+    #[pyo3(name = "port_number")]
+    port: u16,
+    #[pyo3(default)]
+    tags: Vec<String>,
+}
+
+fn expected() -> Config {
+    Config {
+        host: "localhost".to_string(),
+        port: 8080,
+        tags: Vec::new(),
+    }
+}
+
+#[test]
+fn test_extract_dataclass() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let locals = PyDict::new(py);
+    py.run(
+        r#"
+import dataclasses
+
+@dataclasses.dataclass
+class Config:
+    host: str
+    port_number: int
+
+@dataclasses.dataclass
+class Incomplete:
+    host: str
+
+    @property
+    def port_number(self):
+        return 1
+"#,
+        None,
+        Some(locals),
+    )
+    .unwrap();
+
+    let obj = py
+        .eval("Config('localhost', 8080)", None, Some(locals))
+        .unwrap();
+    assert_eq!(obj.extract::<Config>().unwrap(), expected());
+
+    // Only declared fields are read from dataclasses
+    let obj = py
+        .eval("Incomplete('localhost')", None, Some(locals))
+        .unwrap();
+    assert!(obj.extract::<Config>().is_err());
+    // A dataclass type is not an instance
+    let obj = py.eval("Config", None, Some(locals)).unwrap();
+    assert!(obj.extract::<Config>().is_err());
+}
+
+#[test]
+fn test_extract_dict_and_object() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let dict = [
+        ("host", "localhost".to_object(py)),
+        ("port_number", 8080.to_object(py)),
+    ]
+    .into_py_dict(py);
+    assert_eq!(dict.extract::<Config>().unwrap(), expected());
+
+    let obj = py
+        .eval(
+            "__import__('types').SimpleNamespace(host='localhost', port_number=8080, tags=['a'])",
+            None,
+            None,
+        )
+        .unwrap();
+    let config: Config = obj.extract().unwrap();
+    assert_eq!(config.tags, vec!["a".to_string()]);
+
+    let obj = py
+        .eval(
+            "__import__('types').SimpleNamespace(host='localhost')",
+            None,
+            None,
+        )
+        .unwrap();
+    let err = obj.extract::<Config>().unwrap_err();
+    let d = [("err", err.to_object(py))].into_py_dict(py);
+    py.run(
+        "assert str(err) == \"Failed to extract Config: missing field 'port_number'\"",
+        None,
+        Some(d),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_into_dataclass() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj: PyObject = expected().into_py(py);
+    let d = [("obj", obj)].into_py_dict(py);
+    py.run(
+        "assert obj.host == 'localhost' and obj.port_number == 8080 and obj.tags == []",
+        None,
+        Some(d),
+    )
+    .unwrap();
+}
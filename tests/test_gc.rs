@@ -10,7 +10,7 @@ use pyo3::AsPyPointer;
 use pyo3::PyRawObject;
 use std::cell::RefCell;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 mod common;
 
@@ -42,6 +42,58 @@ fn class_with_freelist() {
     }
 }
 
+#[pyclass(freelist = 2, gc = "manual")]
+struct GCWithFreelist {
+    traversed: Arc<AtomicBool>,
+}
+
+#[pyproto]
+impl PyGCProtocol for GCWithFreelist {
+    fn __traverse__(&self, _visit: PyVisit) -> Result<(), PyTraverseError> {
+        self.traversed.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn __clear__(&mut self) {}
+}
+
+#[test]
+fn gc_class_with_freelist() {
+    let traversed = Arc::new(AtomicBool::new(false));
+    let ptr;
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let inst = Py::new(
+            py,
+            GCWithFreelist {
+                traversed: Arc::clone(&traversed),
+            },
+        )
+        .unwrap();
+        ptr = inst.as_ptr();
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    // The dropped value in the free list must not be traversed
+    traversed.store(false, Ordering::Relaxed);
+    py.run("import gc; gc.collect()", None, None).unwrap();
+    assert!(!traversed.load(Ordering::Relaxed));
+
+    let inst = Py::new(
+        py,
+        GCWithFreelist {
+            traversed: Arc::clone(&traversed),
+        },
+    )
+    .unwrap();
+    assert_eq!(ptr, inst.as_ptr());
+    py_run!(py, inst, "import gc; assert gc.is_tracked(inst)");
+    py.run("import gc; gc.collect()", None, None).unwrap();
+    assert!(traversed.load(Ordering::Relaxed));
+}
+
 struct TestDropCall {
     drop_called: Arc<AtomicBool>,
 }
@@ -177,7 +229,7 @@ fn gc_integration() {
     assert!(drop_called.load(Ordering::Relaxed));
 }
 
-#[pyclass(gc)]
+#[pyclass(gc = "manual")]
 struct GCIntegration2 {}
 
 #[pyproto]
@@ -196,6 +248,16 @@ fn gc_integration2() {
     py_run!(py, inst, "import gc; assert inst in gc.get_objects()");
 }
 
+#[test]
+fn traverse_with_pending_release() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = Py::new(py, GCIntegration2 {}).unwrap();
+    // The reference is released later, which must not happen while the collector runs
+    drop(inst);
+    py.run("import gc; gc.collect()", None, None).unwrap();
+}
+
 #[pyclass(weakref)]
 struct WeakRefSupport {}
 
@@ -277,3 +339,48 @@ fn inheritance_with_new_methods_with_drop() {
     assert!(drop_called1.load(Ordering::Relaxed));
     assert!(drop_called2.load(Ordering::Relaxed));
 }
+
+#[pyclass(gc)]
+struct TraverseIntegration {
+    self_ref: PyObject,
+    items: RefCell<Vec<PyObject>>,
+    maybe: Option<PyObject>,
+    locked: Mutex<(i32, Option<PyObject>)>,
+    #[pyo3(gc = "skip")]
+    dropped: TestDropCall,
+}
+
+#[test]
+fn traverse_integration() {
+    let drop_called = Arc::new(AtomicBool::new(false));
+
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut inst = PyRefMut::new(
+            py,
+            TraverseIntegration {
+                self_ref: py.None(),
+                items: RefCell::new(Vec::new()),
+                maybe: None,
+                locked: Mutex::new((0, None)),
+                dropped: TestDropCall {
+                    drop_called: Arc::clone(&drop_called),
+                },
+            },
+        )
+        .unwrap();
+
+        let obj = inst.to_object(py);
+        py_run!(py, obj, "import gc; assert gc.is_tracked(obj)");
+        inst.self_ref = obj.clone_ref(py);
+        inst.items.borrow_mut().push(obj.clone_ref(py));
+        inst.locked.lock().unwrap().1 = Some(obj.clone_ref(py));
+        inst.maybe = Some(obj);
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    py.run("import gc; gc.collect()", None, None).unwrap();
+    assert!(drop_called.load(Ordering::Relaxed));
+}
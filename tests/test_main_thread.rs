@@ -0,0 +1,52 @@
+use pyo3::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+fn thread_ident(py: Python) -> u64 {
+    py.import("threading")
+        .unwrap()
+        .call0("get_ident")
+        .unwrap()
+        .extract()
+        .unwrap()
+}
+
+// Python is initialized by the thread of the only test, which becomes the main thread
+#[test]
+fn test_run_on_main_thread() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let main_thread = thread_ident(py);
+    assert_eq!(py.run_on_main_thread(thread_ident).unwrap(), main_thread);
+
+    let done = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let done = done.clone();
+        thread::spawn(move || {
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            let local = vec![1, 2, 3];
+            let result = py
+                .run_on_main_thread(|py| (thread_ident(py), local.iter().sum::<i32>()))
+                .unwrap();
+            let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+                py.run_on_main_thread(|_| -> () { panic!("on the main thread") })
+            }))
+            .is_err();
+            done.store(true, Ordering::SeqCst);
+            (result, thread_ident(py), panicked)
+        })
+    };
+    // The pending calls run while the main thread executes Python code
+    while !done.load(Ordering::SeqCst) {
+        py.run("import time; time.sleep(0.001)", None, None)
+            .unwrap();
+    }
+    let ((ran_on, sum), worker_thread, panicked) = py.allow_threads(|| worker.join().unwrap());
+    assert_eq!(ran_on, main_thread);
+    assert_ne!(worker_thread, main_thread);
+    assert_eq!(sum, 6);
+    assert!(panicked);
+}
@@ -0,0 +1,94 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyTuple, PyType};
+
+#[pyclass(metaclass = "pyo3_test_metaclasses.Registry")]
+struct Registered {}
+
+#[pyclass(metaclass = "int")]
+struct NotAMetaclass {}
+
+#[pyclass(metaclass = "abc.ABCMeta")]
+struct AbcBase {}
+
+fn define_metaclasses(py: Python) {
+    py.run(
+        r#"
+import sys, types
+module = types.ModuleType("pyo3_test_metaclasses")
+exec('''
+class Registry(type):
+    classes = []
+    def __init__(cls, name, bases, namespace):
+        super().__init__(name, bases, namespace)
+        Registry.classes.append(name)
+    def describe(cls):
+        return "registered " + cls.__name__
+''', module.__dict__)
+sys.modules["pyo3_test_metaclasses"] = module
+"#,
+        None,
+        None,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_python_metaclass() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    define_metaclasses(py);
+
+    let d = [
+        ("Registered", py.get_type::<Registered>()),
+        ("AbcBase", py.get_type::<AbcBase>()),
+    ]
+    .into_py_dict(py);
+    let run = |code| py.run(code, None, Some(d)).unwrap();
+    run("import pyo3_test_metaclasses as m; assert type(Registered) is m.Registry");
+    run("assert Registered.describe() == 'registered Registered'");
+    // The metaclass is not called to create the class itself
+    run("import pyo3_test_metaclasses as m; assert m.Registry.classes == []");
+
+    run("import abc; assert isinstance(AbcBase, abc.ABCMeta)");
+    run("class Virtual: pass");
+    run("AbcBase.register(Virtual); assert isinstance(Virtual(), AbcBase)");
+}
+
+#[test]
+fn test_invalid_metaclass() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let err = pyo3::type_object::initialize_type::<NotAMetaclass>(py, None).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
+
+#[pyclass(extends = PyType)]
+struct Factory {}
+
+#[pymethods]
+impl Factory {
+    #[call]
+    #[args(args = "*")]
+    fn __call__(&self, args: &PyTuple) -> String {
+        format!("called with {} arguments", args.len())
+    }
+}
+
+#[pyclass(metaclass = Factory)]
+struct Product {}
+
+#[test]
+fn test_rust_metaclass() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let d = [
+        ("Product", py.get_type::<Product>()),
+        ("Factory", py.get_type::<Factory>()),
+    ]
+    .into_py_dict(py);
+    let run = |code| py.run(code, None, Some(d)).unwrap();
+    run("assert type(Product) is Factory");
+    run("assert Product(1, 2) == 'called with 2 arguments'");
+}
@@ -205,6 +205,39 @@ fn static_method_with_args() {
         .unwrap();
 }
 
+#[test]
+#[cfg(all(Py_3_8, not(PyPy)))]
+fn methods_use_fastcall() {
+    use pyo3::{ffi, AsPyPointer};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let flags = |method: &PyObject| unsafe { ffi::PyCFunction_GetFlags(method.as_ptr()) };
+
+    let obj = PyRef::new(py, InstanceMethodWithArgs { member: 7 }).unwrap();
+    let methods = [
+        obj.to_object(py).getattr(py, "method").unwrap(),
+        py.get_type::<ClassMethodWithArgs>()
+            .getattr("method")
+            .unwrap()
+            .to_object(py),
+        py.get_type::<StaticMethodWithArgs>()
+            .getattr("method")
+            .unwrap()
+            .to_object(py),
+    ];
+    for method in methods.iter() {
+        assert_eq!(
+            flags(method) & (ffi::METH_FASTCALL | ffi::METH_VARARGS),
+            ffi::METH_FASTCALL
+        );
+    }
+    // Methods without arguments don't need an argument array
+    let obj = PyRef::new(py, InstanceMethod { member: 7 }).unwrap();
+    let method = obj.to_object(py).getattr(py, "method").unwrap();
+    assert_eq!(flags(&method) & ffi::METH_FASTCALL, 0);
+}
+
 #[pyclass]
 struct MethArgs {}
 
@@ -292,7 +325,7 @@ fn meth_args() {
     py_run!(py, inst, "assert inst.get_default() == 10");
     py_run!(py, inst, "assert inst.get_default(100) == 100");
     py_run!(py, inst, "assert inst.get_kwarg() == 10");
-    py_run!(py, inst, "assert inst.get_kwarg(100) == 10");
+    py_expect_exception!(py, inst, "inst.get_kwarg(100)", TypeError);
     py_run!(py, inst, "assert inst.get_kwarg(test=100) == 100");
     py_run!(py, inst, "assert inst.get_kwargs() == [(), None]");
     py_run!(py, inst, "assert inst.get_kwargs(1,2,3) == [(1,2,3), None]");
@@ -402,3 +435,42 @@ fn method_with_lifetime() {
         "assert obj.set_to_list(set((1, 2, 3))) == [1, 2, 3]"
     );
 }
+
+#[pyclass]
+struct VersionedMethods {}
+
+#[pymethods]
+impl VersionedMethods {
+    #[pyo3(min_python = "3.0")]
+    fn available(&self) -> PyResult<i32> {
+        Ok(1)
+    }
+
+    #[pyo3(min_python = "9.99")]
+    fn unavailable(&self) -> PyResult<i32> {
+        Ok(2)
+    }
+
+    #[getter]
+    #[pyo3(min_python = "9.99")]
+    fn unavailable_getter(&self) -> PyResult<i32> {
+        Ok(3)
+    }
+
+    #[pyo3(min_python = "3.0", final)]
+    fn available_final(&self) -> PyResult<i32> {
+        Ok(4)
+    }
+}
+
+#[test]
+fn min_python_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let obj = PyRef::new(py, VersionedMethods {}).unwrap();
+    py_assert!(py, obj, "obj.available() == 1");
+    py_assert!(py, obj, "not hasattr(obj, 'unavailable')");
+    py_assert!(py, obj, "not hasattr(obj, 'unavailable_getter')");
+    py_assert!(py, obj, "obj.available_final() == 4");
+}
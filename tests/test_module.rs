@@ -1,6 +1,7 @@
 use pyo3::prelude::*;
 
 use pyo3::types::{IntoPyDict, PyTuple};
+use pyo3::AsPyPointer;
 
 mod common;
 
@@ -221,6 +222,52 @@ fn test_module_nesting() {
     );
 }
 
+#[pymodule]
+fn leaf(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add("value", 42)
+}
+
+#[pymodule]
+fn branch(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_wrapped(pyo3::wrap_pymodule!(leaf))
+}
+
+#[pymodule]
+fn tree(_py: Python, module: &PyModule) -> PyResult<()> {
+    use pyo3::wrap_pymodule;
+
+    module.add_wrapped(wrap_pymodule!(submodule))?;
+    module.add_wrapped(wrap_pymodule!(branch))
+}
+
+#[test]
+fn test_submodule_import() {
+    use pyo3::wrap_pymodule;
+
+    let gil = GILGuard::acquire();
+    let py = gil.python();
+    let tree = wrap_pymodule!(tree)(py);
+
+    pyo3::py_run!(
+        py,
+        tree,
+        r#"
+import sys
+assert tree.submodule.__name__ == "tree.submodule"
+assert tree.submodule.__package__ == "tree"
+assert sys.modules["tree.submodule"] is tree.submodule
+assert tree.branch.leaf.__name__ == "tree.branch.leaf"
+assert tree.branch.leaf.__package__ == "tree.branch"
+assert "branch.leaf" not in sys.modules
+sys.modules["tree"] = tree
+from tree.submodule import subfunction
+assert subfunction() == "Subfunction"
+import tree.branch.leaf
+assert tree.branch.leaf.value == 42
+"#
+    );
+}
+
 // Test that argument parsing specification works for pyfunctions
 
 #[pyfunction(a = 5, vararg = "*")]
@@ -252,3 +299,370 @@ fn test_vararg_module() {
     py_assert!(py, m, "m.int_vararg_fn() == [5, ()]");
     py_assert!(py, m, "m.int_vararg_fn(1, 2) == [1, (2,)]");
 }
+
+#[pymodule]
+fn failing_module(_py: Python, _m: &PyModule) -> PyResult<()> {
+    Err(pyo3::exceptions::ValueError::py_err("bad config"))
+}
+
+static mut HOOK_CALLED_FOR: Option<String> = None;
+
+fn record_init_failure(_py: Python, name: &str, _err: &PyErr) {
+    unsafe { HOOK_CALLED_FOR = Some(name.to_string()) };
+}
+
+#[test]
+fn test_module_init_failure() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    PyModule::set_init_failure_hook(py, Some(record_init_failure));
+    let module = unsafe { PyInit_failing_module() };
+    PyModule::set_init_failure_hook(py, None);
+    assert!(module.is_null());
+    assert_eq!(
+        unsafe { HOOK_CALLED_FOR.as_ref().map(|s| s.as_str()) },
+        Some("failing_module")
+    );
+
+    let err = PyErr::fetch(py);
+    assert!(err.is_instance::<pyo3::exceptions::ImportError>(py));
+    let err: PyObject = err.into_py(py);
+    py_assert!(py, err, "err.name == 'failing_module'");
+    py_assert!(py, err, "'bad config' in str(err)");
+    py_assert!(py, err, "isinstance(err.__cause__, ValueError)");
+}
+
+#[pymodule]
+fn conditional_module(_py: Python, m: &PyModule) -> PyResult<()> {
+    use pyo3::wrap_pyfunction;
+
+    #[cfg(any())]
+    #[pyfn(m, "disabled")]
+    fn disabled() -> PyResult<usize> {
+        Ok(0)
+    }
+
+    #[cfg(all())]
+    #[pyfn(m, "enabled")]
+    fn enabled() -> PyResult<usize> {
+        Ok(1)
+    }
+
+    m.add_if(true, "yes", 1)?;
+    m.add_if(false, "no", 0)?;
+    m.add_class_if::<AnonClass>(false)?;
+    m.add_class_if::<LocatedClass>(true)?;
+    m.add_wrapped_if(false, wrap_pyfunction!(double))?;
+    Ok(())
+}
+
+#[test]
+fn test_conditional_registration() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let m = wrap_pymodule!(conditional_module)(py);
+
+    py_assert!(py, m, "m.enabled() == 1");
+    py_assert!(py, m, "not hasattr(m, 'disabled')");
+    py_assert!(py, m, "m.yes == 1");
+    py_assert!(py, m, "not hasattr(m, 'no')");
+    py_assert!(py, m, "not hasattr(m, 'AnonClass')");
+    py_assert!(py, m, "hasattr(m, 'LocatedClass')");
+    py_assert!(py, m, "not hasattr(m, 'double')");
+}
+
+#[pymodule]
+fn versioned_module(_py: Python, m: &PyModule) -> PyResult<()> {
+    #[pyfn(m, "old")]
+    #[pyo3(min_python = "3.0")]
+    fn old() -> PyResult<usize> {
+        Ok(3)
+    }
+
+    #[pyfn(m, "future")]
+    #[pyo3(min_python = "9.99")]
+    fn future() -> PyResult<usize> {
+        Ok(9)
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_min_python_functions() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let m = wrap_pymodule!(versioned_module)(py);
+
+    py_assert!(py, m, "m.old() == 3");
+    py_assert!(py, m, "not hasattr(m, 'future')");
+}
+
+/// A point
+#[pyclass]
+struct IndexedPoint {}
+
+#[pymethods]
+impl IndexedPoint {
+    #[new]
+    fn new(obj: &PyRawObject, x: f64, y: f64) {
+        let _ = (x, y);
+        obj.init(IndexedPoint {})
+    }
+}
+
+#[pyclass]
+struct UndocumentedClass {}
+
+/// Geometry helpers.
+///
+/// Everything is in metres.
+#[pymodule]
+#[pyo3(doc_index)]
+fn indexed_module(_py: Python, m: &PyModule) -> PyResult<()> {
+    /// Computes the distance
+    /// between two points
+    #[pyfn(m, "distance", p, q, "*", squared = "false")]
+    fn distance(p: f64, q: f64, squared: bool) -> f64 {
+        let d = (p - q).abs();
+        if squared {
+            d * d
+        } else {
+            d
+        }
+    }
+
+    m.add_class::<IndexedPoint>()?;
+    m.add_class::<UndocumentedClass>()?;
+    m.add_wrapped(pyo3::wrap_pyfunction!(double))?;
+    m.add("ORIGIN", 0)
+}
+
+#[test]
+fn test_module_doc_index() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let m = wrap_pymodule!(indexed_module)(py);
+
+    let doc: String = m.getattr(py, "__doc__").unwrap().extract(py).unwrap();
+    assert_eq!(
+        doc,
+        "Geometry helpers.
+
+Everything is in metres.
+
+Classes
+-------
+IndexedPoint(x, y)
+    A point
+UndocumentedClass
+
+Functions
+---------
+distance(p, q, *, squared=False)
+    Computes the distance
+double(x)
+    Doubles the given value"
+    );
+    let all: Vec<String> = m.getattr(py, "__all__").unwrap().extract(py).unwrap();
+    assert!(!all.contains(&"__doc__".to_string()));
+}
+
+#[test]
+fn test_module_build_info() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let m = wrap_pymodule!(module_with_functions)(py);
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    let git_hash = option_env!("GIT_HASH");
+
+    pyo3::py_run!(
+        py,
+        m profile git_hash,
+        r#"
+import platform, sys
+build = m.__build__
+assert build['crate'] == 'pyo3'
+assert build['version'] == build['pyo3'] != ''
+assert build['git_hash'] == git_hash
+assert build['profile'] == profile
+assert build['rustc'].startswith('rustc ')
+assert build['python'] == '%s %d.%d' % ((platform.python_implementation(),) + sys.version_info[:2])
+assert isinstance(build['pyo3_features'], list)
+assert '__build__' not in m.__all__
+"#
+    );
+}
+
+#[test]
+fn test_import_lazy() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    // A package whose modules record when they are executed
+    py.run(
+        r#"
+import os, sys, tempfile
+path = tempfile.mkdtemp()
+os.mkdir(os.path.join(path, "pyo3_lazy_package"))
+for name in ["__init__", "heavy"]:
+    with open(os.path.join(path, "pyo3_lazy_package", name + ".py"), "w") as f:
+        f.write("import builtins\nbuiltins.pyo3_lazy_loaded.append(__name__)\nVALUE = 42\n")
+sys.path.insert(0, path)
+import builtins
+builtins.pyo3_lazy_loaded = []
+"#,
+        None,
+        None,
+    )
+    .unwrap();
+    let loaded = || -> Vec<String> {
+        py.import("builtins")
+            .unwrap()
+            .get("pyo3_lazy_loaded")
+            .unwrap()
+            .extract()
+            .unwrap()
+    };
+
+    let heavy = py.import_lazy("pyo3_lazy_package.heavy").unwrap();
+    // Finding the module imports its package
+    assert_eq!(loaded(), ["pyo3_lazy_package"]);
+    assert_eq!(heavy.get("VALUE").unwrap().extract::<i32>().unwrap(), 42);
+    assert_eq!(loaded(), ["pyo3_lazy_package", "pyo3_lazy_package.heavy"]);
+
+    let again = py.import_lazy("pyo3_lazy_package.heavy").unwrap();
+    assert_eq!(again.as_ptr(), heavy.as_ptr());
+    py_assert!(py, heavy, "__import__('pyo3_lazy_package').heavy is heavy");
+
+    let err = py.import_lazy("pyo3_lazy_missing").unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ModuleNotFoundError>(py));
+}
+
+#[pyfunction]
+fn parse(text: &str, strict: bool) -> String {
+    format!("{}:{}", text, strict)
+}
+
+#[pymodule]
+fn deprecating_module(_py: Python, m: &PyModule) -> PyResult<()> {
+    use pyo3::wrap_pyfunction;
+    use pyo3::wrap_pymodule;
+
+    m.add_wrapped(wrap_pyfunction!(parse))?;
+    m.add_wrapped(wrap_pymodule!(submodule))?;
+    m.add_deprecated_aliases(
+        "2.0",
+        &[
+            ("loads", "parse"),
+            ("old_subfunction", "submodule.subfunction"),
+        ],
+    )?;
+    m.add_deprecated("legacy_double", m.get("parse")?, "use parse")?;
+    Ok(())
+}
+
+#[test]
+fn test_deprecated_aliases() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let m = wrap_pymodule!(deprecating_module)(py);
+
+    pyo3::py_run!(
+        py,
+        m,
+        r#"
+import warnings
+with warnings.catch_warnings(record=True) as caught:
+    warnings.simplefilter("always")
+    assert m.loads("x", strict=True) == "x:true"
+    assert m.old_subfunction() == "Subfunction"
+[loads, moved] = caught
+assert loads.category is DeprecationWarning
+assert str(loads.message) == "deprecating_module.loads is deprecated since 2.0, use deprecating_module.parse instead"
+# Attributed to the caller
+assert loads.filename == "<string>"
+assert "submodule.subfunction" in str(moved.message)
+assert m.loads.__name__ == "loads" and m.loads.__module__ == "deprecating_module"
+assert "loads" not in m.__all__ and "parse" in m.__all__
+
+with warnings.catch_warnings():
+    warnings.simplefilter("error")
+    try:
+        m.legacy_double("x", False)
+    except DeprecationWarning as e:
+        assert str(e) == "use parse"
+    else:
+        assert False
+"#
+    );
+}
+
+#[pymodule]
+fn pyo3_company(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("NATIVE", true)?;
+    m.extend_path()
+}
+
+#[test]
+fn test_namespace_package() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    // Two distributions installing modules into the `pyo3_company` package
+    py.run(
+        r#"
+import os, sys, tempfile
+for files in [["bar.py"], ["native/helpers.py"]]:
+    path = tempfile.mkdtemp()
+    for file in files:
+        file = os.path.join(path, "pyo3_company", file)
+        os.makedirs(os.path.dirname(file))
+        with open(file, "w") as f:
+            f.write("NAME = __name__\n")
+    sys.path.append(path)
+"#,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let company = wrap_pymodule!(pyo3_company)(py);
+    let modules = py.import("sys").unwrap().get("modules").unwrap();
+    modules.set_item("pyo3_company", &company).unwrap();
+    let company: &PyModule = company.cast_as(py).unwrap();
+    let native = PyModule::new(py, "native").unwrap();
+    company.add_submodule(native).unwrap();
+    native.extend_path().unwrap();
+    // Calling it again doesn't add the directories twice
+    native.extend_path().unwrap();
+
+    py_assert!(py, company, "len(company.__path__) == 2");
+    py_assert!(py, native, "len(native.__path__) == 1");
+    pyo3::py_run!(
+        py,
+        company,
+        r#"
+import pyo3_company.bar
+import pyo3_company.native.helpers
+assert pyo3_company is company and company.NATIVE
+assert pyo3_company.bar.NAME == "pyo3_company.bar"
+assert pyo3_company.native.helpers.NAME == "pyo3_company.native.helpers"
+"#
+    );
+}
@@ -0,0 +1,172 @@
+use pyo3::interpreters::PyInterpreterPool;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::{py_run, wrap_pyfunction, wrap_pymodule};
+
+#[pyfunction]
+fn interpreter_id(py: Python) -> PyResult<i64> {
+    py.eval(
+        "int(__import__('_xxsubinterpreters').get_current())",
+        None,
+        None,
+    )?
+    .extract()
+}
+
+/// Whether the interpreter has `_xxsubinterpreters`, which `interpreter_id` uses. It's private
+/// and missing from some versions, e.g. Python 3.13 renamed it.
+fn has_subinterpreters_module(py: Python) -> bool {
+    py.import("_xxsubinterpreters").is_ok()
+}
+
+/// A module using multi-phase initialization
+#[pymodule]
+#[pyo3(multi_phase)]
+fn phased(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("state", PyDict::new(py))?;
+    m.add_wrapped(wrap_pyfunction!(interpreter_id))
+}
+
+#[pymodule]
+#[pyo3(multi_phase)]
+fn failing(_py: Python, _m: &PyModule) -> PyResult<()> {
+    Err(pyo3::exceptions::ValueError::py_err("failed"))
+}
+
+#[test]
+fn test_multi_phase_module() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let first = wrap_pymodule!(phased)(py);
+    let second = wrap_pymodule!(phased)(py);
+    py_run!(
+        py,
+        first second,
+        r#"
+import types
+assert isinstance(first, types.ModuleType)
+assert first.__name__ == "phased"
+assert first.__doc__ == "A module using multi-phase initialization"
+assert first is not second and first.state is not second.state
+"#
+    );
+    if has_subinterpreters_module(py) {
+        py_run!(py, first, "assert first.interpreter_id() == 0");
+    }
+}
+
+#[test]
+fn test_multi_phase_module_error() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let result = std::panic::catch_unwind(|| wrap_pymodule!(failing)(py));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_multi_phase_module_in_subinterpreters() {
+    if !has_subinterpreters_module(Python::acquire_gil().python()) {
+        return;
+    }
+    let pool = PyInterpreterPool::new(2, &[]).unwrap();
+    let ids: Vec<_> = (0..2)
+        .map(|_| {
+            pool.submit(|py| {
+                let phased = wrap_pymodule!(phased)(py);
+                phased
+                    .call_method0(py, "interpreter_id")
+                    .and_then(|id| id.extract::<i64>(py))
+                    .unwrap()
+            })
+        })
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert!(ids.iter().all(|&id| id != 0));
+}
+
+#[pyclass]
+struct Shared {}
+
+#[test]
+fn test_add_class_in_subinterpreter() {
+    let pool = PyInterpreterPool::new(1, &[]).unwrap();
+    let error = pool
+        .submit(|py| {
+            let module = PyModule::new(py, "shared").unwrap();
+            let err = module.add_class::<Shared>().unwrap_err();
+            assert!(err.is_instance::<pyo3::exceptions::ImportError>(py));
+            let message: String = err
+                .to_object(py)
+                .as_ref(py)
+                .str()
+                .unwrap()
+                .extract()
+                .unwrap();
+            message
+        })
+        .join()
+        .unwrap();
+    assert_eq!(
+        error,
+        "#[pyclass] Shared can not be added to a module of a subinterpreter"
+    );
+}
+
+static STATES_DROPPED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[derive(Default)]
+struct Counter {
+    imports: std::cell::Cell<u32>,
+}
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        STATES_DROPPED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[pymodule]
+#[pyo3(multi_phase, state = "Counter")]
+fn counted(_py: Python, m: &PyModule) -> PyResult<()> {
+    let counter = m.state::<Counter>()?;
+    counter.imports.set(counter.imports.get() + 1);
+    Ok(())
+}
+
+#[test]
+fn test_multi_phase_module_state() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let first = wrap_pymodule!(counted)(py);
+    let second = wrap_pymodule!(counted)(py);
+    {
+        let (first, second) = (first.as_ref(py), second.as_ref(py));
+        let first: &PyModule = first.downcast_ref().unwrap();
+        let second: &PyModule = second.downcast_ref().unwrap();
+        let state = first.state::<Counter>().unwrap();
+        assert_eq!(state.imports.get(), 1);
+        state.imports.set(5);
+        assert_eq!(second.state::<Counter>().unwrap().imports.get(), 1);
+
+        let err = first.state::<u32>().unwrap_err();
+        assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+    }
+
+    let phased = wrap_pymodule!(phased)(py);
+    let phased = phased.as_ref(py);
+    let phased: &PyModule = phased.downcast_ref().unwrap();
+    assert!(phased.state::<Counter>().is_err());
+
+    // The references are released with the GIL
+    let dropped = STATES_DROPPED.load(std::sync::atomic::Ordering::SeqCst);
+    drop((first, second));
+    drop(gil);
+    let gil = Python::acquire_gil();
+    gil.python()
+        .run("import gc; gc.collect()", None, None)
+        .unwrap();
+    assert_eq!(
+        STATES_DROPPED.load(std::sync::atomic::Ordering::SeqCst),
+        dropped + 2
+    );
+}
@@ -0,0 +1,101 @@
+#![feature(specialization)]
+
+use pyo3::class::operators::{Operator, PyOperators};
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::types::{PyAny, PyString};
+
+/// Records operations as `(operator, operands...)` tuples
+#[pyclass]
+struct Column {
+    name: String,
+}
+
+impl PyOperators for Column {
+    fn apply(
+        &self,
+        py: Python,
+        op: Operator,
+        other: Option<&PyAny>,
+        reflected: bool,
+    ) -> PyResult<PyObject> {
+        // Strings are not valid operands
+        if other.map_or(false, |other| other.downcast_ref::<PyString>().is_ok()) {
+            return Ok(py.NotImplemented());
+        }
+        let name = self.name.as_str();
+        Ok(match other {
+            None => (op.symbol(), name).to_object(py),
+            Some(other) if reflected => (op.symbol(), other, name).to_object(py),
+            Some(other) => (op.symbol(), name, other).to_object(py),
+        })
+    }
+}
+
+#[test]
+fn test_operators() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let c = Py::new(py, Column { name: "c".into() }).unwrap();
+    py_run!(
+        py,
+        c,
+        r#"
+binary = ["+", "-", "*", "@", "/", "//", "%", "**", "<<", ">>", "&", "|", "^"]
+for op in binary:
+    assert eval("c {} 1".format(op)) == (op, "c", 1)
+    assert eval("1 {} c".format(op)) == (op, 1, "c")
+assert (-c, +c, abs(c), ~c) == (("-", "c"), ("+", "c"), ("abs", "c"), ("~", "c"))
+
+x = c
+x += 2
+assert x == ("+", "c", 2)
+"#
+    );
+}
+
+#[test]
+fn test_comparisons() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let c = Py::new(py, Column { name: "c".into() }).unwrap();
+    py_run!(
+        py,
+        c,
+        r#"
+assert (c < 1, c <= 1, c == 1, c != 1, c > 1, c >= 1) == (
+    ("<", "c", 1), ("<=", "c", 1), ("==", "c", 1), ("!=", "c", 1), (">", "c", 1), (">=", "c", 1)
+)
+# Python swaps the operator for comparisons
+assert (1 < c) == (">", "c", 1)
+try:
+    hash(c)
+except TypeError:
+    pass
+else:
+    assert False, "operator classes are hashable"
+"#
+    );
+}
+
+#[test]
+fn test_not_implemented() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let c = Py::new(py, Column { name: "c".into() }).unwrap();
+    py_run!(
+        py,
+        c,
+        r#"
+for expr in ["c + 'a'", "'a' - c", "pow(c, 2, 3)"]:
+    try:
+        eval(expr)
+    except TypeError:
+        pass
+    else:
+        assert False, expr
+# Falls back to the identity comparison
+assert (c == "a") is False
+"#
+    );
+}
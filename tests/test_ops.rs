@@ -0,0 +1,183 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+use std::ops::{Add, AddAssign, BitAnd, Div, Mul, MulAssign, Neg, Not, Sub};
+
+#[pyclass(ops(Add, &Sub, Mul::<i64>, &Div::<i64>, Neg, Not, AddAssign, MulAssign::<i64>))]
+#[derive(Clone, Debug, PartialEq)]
+struct Money {
+    #[pyo3(get)]
+    cents: i64,
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money {
+            cents: self.cents + other.cents,
+        }
+    }
+}
+
+impl Sub for &Money {
+    type Output = Money;
+    fn sub(self, other: &Money) -> Money {
+        Money {
+            cents: self.cents - other.cents,
+        }
+    }
+}
+
+impl Mul<i64> for Money {
+    type Output = Money;
+    fn mul(self, factor: i64) -> Money {
+        Money {
+            cents: self.cents * factor,
+        }
+    }
+}
+
+impl Mul<Money> for i64 {
+    type Output = Money;
+    fn mul(self, money: Money) -> Money {
+        money * self
+    }
+}
+
+impl Div<i64> for &Money {
+    type Output = Money;
+    fn div(self, divisor: i64) -> Money {
+        Money {
+            cents: self.cents / divisor,
+        }
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money { cents: -self.cents }
+    }
+}
+
+impl Not for Money {
+    type Output = bool;
+    fn not(self) -> bool {
+        self.cents == 0
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.cents += other.cents;
+    }
+}
+
+impl MulAssign<i64> for Money {
+    fn mul_assign(&mut self, factor: i64) {
+        self.cents *= factor;
+    }
+}
+
+#[test]
+fn test_binary_ops() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let a = Py::new(py, Money { cents: 150 }).unwrap();
+    let b = Py::new(py, Money { cents: 50 }).unwrap();
+    py_run!(
+        py,
+        a b,
+        r#"
+assert (a + b).cents == 200
+assert (a - b).cents == 100
+assert (a * 3).cents == 450
+assert (a / 4).cents == 37
+# Reflected through `impl Mul<Money> for i64`
+assert (3 * a).cents == 450
+# `i64: Div<Money>` isn't implemented
+try:
+    4 / a
+except TypeError:
+    pass
+else:
+    assert False
+try:
+    a + 1
+except TypeError:
+    pass
+else:
+    assert False
+"#
+    );
+}
+
+#[test]
+fn test_unary_ops() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let a = Py::new(py, Money { cents: 150 }).unwrap();
+    let zero = Py::new(py, Money { cents: 0 }).unwrap();
+    py_run!(
+        py,
+        a zero,
+        r#"
+assert (-a).cents == -150
+assert ~zero is True and ~a is False
+# Operators that aren't listed keep their default behavior
+try:
+    +a
+except TypeError:
+    pass
+else:
+    assert False
+"#
+    );
+}
+
+#[test]
+fn test_inplace_ops() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let a = Py::new(py, Money { cents: 150 }).unwrap();
+    let b = Py::new(py, Money { cents: 50 }).unwrap();
+    py_run!(
+        py,
+        a b,
+        r#"
+c = a
+c += b
+assert c is a and a.cents == 200
+c *= 2
+assert c is a and a.cents == 400
+a += a
+assert a.cents == 800
+# Falls back to `__sub__`, creating a new object
+c -= b
+assert c is not a and c.cents == 750 and a.cents == 800
+"#
+    );
+    assert_eq!(a.as_ref(py).cents, 800);
+}
+
+#[pyclass(ops(&BitAnd))]
+struct Flags {
+    bits: u8,
+}
+
+impl BitAnd for &Flags {
+    type Output = u8;
+    fn bitand(self, other: &Flags) -> u8 {
+        self.bits & other.bits
+    }
+}
+
+#[test]
+fn test_by_ref_without_clone() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let a = Py::new(py, Flags { bits: 0b1100 }).unwrap();
+    let b = Py::new(py, Flags { bits: 0b1010 }).unwrap();
+    py_run!(py, a b, "assert a & b == 0b1000");
+}
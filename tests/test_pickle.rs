@@ -0,0 +1,135 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+
+#[pyclass(pickle, module = "test_pickle")]
+struct Point {
+    #[pyo3(get)]
+    x: i32,
+    #[pyo3(get)]
+    y: i32,
+    tags: Vec<String>,
+    parent: Option<PyObject>,
+}
+
+#[pymethods]
+impl Point {
+    #[new]
+    fn new(obj: &PyRawObject, x: i32, y: i32) {
+        obj.init(Point {
+            x,
+            y,
+            tags: Vec::new(),
+            parent: None,
+        });
+    }
+
+    fn tag(&mut self, tag: String) {
+        self.tags.push(tag);
+    }
+
+    #[getter]
+    fn tags(&self) -> Vec<String> {
+        self.tags.clone()
+    }
+}
+
+#[test]
+fn test_pickle_fields() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let point = py.get_type::<Point>();
+    py_run!(
+        py,
+        point,
+        r#"
+import copy, pickle, sys, types
+
+module = types.ModuleType("test_pickle")
+module.Point = point
+sys.modules["test_pickle"] = module
+
+p = point(1, 2)
+p.tag("origin")
+assert p.__getstate__() == {"x": 1, "y": 2, "tags": ["origin"], "parent": None}
+
+q = pickle.loads(pickle.dumps(p))
+assert type(q) is point and q is not p
+assert (q.x, q.y, q.tags) == (1, 2, ["origin"])
+
+r = copy.deepcopy(p)
+r.tag("copy")
+assert (r.tags, p.tags) == (["origin", "copy"], ["origin"])
+
+p.__setstate__({"x": 5, "y": 6, "tags": [], "parent": None})
+assert (p.x, p.y, p.tags) == (5, 6, [])
+"#
+    );
+}
+
+#[test]
+fn test_setstate_errors() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let point = py.get_type::<Point>();
+    py_run!(
+        py,
+        point,
+        r#"
+p = point(1, 2)
+for state, error in [({"x": 1}, ValueError), ({"x": "1", "y": 2, "tags": [], "parent": None}, TypeError), ([], TypeError)]:
+    try:
+        p.__setstate__(state)
+    except error:
+        pass
+    else:
+        assert False, state
+assert (p.x, p.y) == (1, 2)
+"#
+    );
+}
+
+#[cfg(feature = "serde")]
+mod serde_state {
+    use pyo3::prelude::*;
+    use pyo3::py_run;
+    use serde::{Deserialize, Serialize};
+
+    #[pyclass(pickle = "serde", module = "test_pickle_serde")]
+    #[derive(Serialize, Deserialize)]
+    struct Settings {
+        #[pyo3(get)]
+        name: String,
+        retries: Option<u8>,
+    }
+
+    #[test]
+    fn test_pickle_serde() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let settings = PyRef::new(
+            py,
+            Settings {
+                name: "worker".to_string(),
+                retries: Some(3),
+            },
+        )
+        .unwrap();
+        let cls = py.get_type::<Settings>();
+        py_run!(
+            py,
+            settings cls,
+            r#"
+import pickle, sys, types
+
+module = types.ModuleType("test_pickle_serde")
+module.Settings = cls
+sys.modules["test_pickle_serde"] = module
+
+assert settings.__getstate__() == {"name": "worker", "retries": 3}
+assert pickle.loads(pickle.dumps(settings)).name == "worker"
+"#
+        );
+    }
+}
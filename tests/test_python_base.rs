@@ -0,0 +1,236 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::type_object::{initialize_type, pyclass_super};
+
+#[pyclass(extends = "pyo3_test_handlers.Handler")]
+struct Echo {
+    prefix: String,
+}
+
+#[pymethods]
+impl Echo {
+    #[new]
+    fn new(obj: &PyRawObject, name: String) {
+        let _ = name;
+        obj.init(Echo {
+            prefix: ">".to_string(),
+        })
+    }
+
+    fn respond(&self, request: String) -> String {
+        format!("{} {}", self.prefix, request)
+    }
+}
+
+#[pyclass(extends = "pyo3_test_handlers.Handler")]
+struct Shouting {}
+
+#[pymethods]
+impl Shouting {
+    #[new]
+    fn new(obj: &PyRawObject, name: String) {
+        let _ = name;
+        obj.init(Shouting {})
+    }
+
+    fn __init__(slf: PyRef<Self>, py: Python, name: String) -> PyResult<()> {
+        pyclass_super::<Shouting>(py, &slf)?.call_method1("__init__", (name.to_uppercase(),))?;
+        Ok(())
+    }
+
+    fn respond(&self, request: String) -> String {
+        request.to_uppercase()
+    }
+}
+
+#[pyclass(extends = "pyo3_test_handlers.Handler")]
+struct NoConstructor {}
+
+#[pyclass(extends = "pyo3_test_handlers.Registered")]
+struct Plugin {}
+
+// `int` stores its digits where the Rust value would be
+#[pyclass(extends = "int")]
+struct LayoutConflict {
+    _value: u64,
+}
+
+/// Defines the base classes, once since the classes extending them are only created once
+fn define_handlers(py: Python) {
+    py.run(
+        r#"
+import sys, types
+if "pyo3_test_handlers" not in sys.modules:
+    module = types.ModuleType("pyo3_test_handlers")
+    exec('''
+class Handler:
+    def __init__(self, name):
+        self.name = name
+        self.handled = []
+
+    def handle(self, request):
+        self.handled.append(request)
+        return self.respond(request)
+
+    def respond(self, request):
+        raise NotImplementedError
+
+class Registered(Handler):
+    subclasses = []
+
+    def __init_subclass__(cls):
+        Registered.subclasses.append(cls)
+''', module.__dict__)
+    sys.modules["pyo3_test_handlers"] = module
+"#,
+        None,
+        None,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_python_base() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    define_handlers(py);
+    let echo = py.get_type::<Echo>();
+    py_run!(
+        py,
+        echo,
+        r#"
+        from pyo3_test_handlers import Handler
+
+        # The Rust type is the first base of the class
+        assert echo.__mro__[2:] == (Handler, object)
+        handler = echo("echo")
+        assert isinstance(handler, Handler)
+        # The constructor arguments are passed on to `Handler.__init__`
+        assert handler.name == "echo"
+        # `Handler.handle` calls back into `respond`
+        assert handler.handle("hello") == "> hello"
+        assert handler.handled == ["hello"]
+
+        handler.extra = 1
+        assert handler.__dict__ == {"name": "echo", "handled": ["hello"], "extra": 1}
+
+        class Polite(echo):
+            def respond(self, request):
+                return super().respond(request) + ", please"
+
+        assert Polite("polite").handle("hello") == "> hello, please"
+        "#
+    );
+}
+
+#[test]
+fn test_python_base_init() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    define_handlers(py);
+    let shouting = py.get_type::<Shouting>();
+    py_run!(
+        py,
+        shouting,
+        r#"
+        handler = shouting("quiet")
+        assert handler.name == "QUIET"
+        assert handler.handle("hello") == "HELLO"
+        "#
+    );
+}
+
+#[test]
+fn test_python_base_dealloc() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    define_handlers(py);
+    let echo = py.get_type::<Echo>();
+    py_run!(
+        py,
+        echo,
+        r#"
+        import weakref
+
+        class Value:
+            pass
+
+        handler = echo("echo")
+        handler.value = Value()
+        handler_ref = weakref.ref(handler)
+        value_ref = weakref.ref(handler.value)
+        del handler
+        assert handler_ref() is None
+        assert value_ref() is None
+        "#
+    );
+}
+
+#[test]
+fn test_python_base_no_constructor() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    define_handlers(py);
+    let no_constructor = py.get_type::<NoConstructor>();
+    py_run!(
+        py,
+        no_constructor,
+        r#"
+        try:
+            no_constructor("name")
+        except TypeError as e:
+            pass
+        else:
+            assert False
+        "#
+    );
+}
+
+#[test]
+fn test_python_base_init_subclass() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    define_handlers(py);
+    let plugin = py.get_type::<Plugin>();
+    py_run!(
+        py,
+        plugin,
+        r#"
+        from pyo3_test_handlers import Registered
+        assert Registered.subclasses == [plugin]
+        "#
+    );
+}
+
+#[test]
+fn test_python_base_rust_instance() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    define_handlers(py);
+    let handler = Py::new(
+        py,
+        Echo {
+            prefix: "<".to_string(),
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        handler,
+        r#"
+        from pyo3_test_handlers import Handler
+        assert isinstance(handler, Handler)
+        assert handler.respond("hi") == "< hi"
+        "#
+    );
+}
+
+#[test]
+fn test_invalid_python_base() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let err = initialize_type::<LayoutConflict>(py, None).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
@@ -0,0 +1,114 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+use std::fmt;
+
+#[pyclass(repr)]
+#[derive(Debug)]
+struct Config {
+    name: String,
+    retries: u32,
+}
+
+#[pyclass(repr = "fields", str)]
+struct Point {
+    x: i32,
+    y: i32,
+    label: Option<String>,
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[pyclass(repr = "fields")]
+struct Node {
+    value: i32,
+    next: Option<PyObject>,
+}
+
+#[test]
+fn test_debug_repr() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let config = Py::new(
+        py,
+        Config {
+            name: "main".to_string(),
+            retries: 3,
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        config,
+        r#"
+assert repr(config) == 'Config { name: "main", retries: 3 }'
+assert str(config) == repr(config)
+"#
+    );
+}
+
+#[test]
+fn test_fields_repr_and_display_str() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let point = Py::new(
+        py,
+        Point {
+            x: 1,
+            y: -2,
+            label: Some("it's".to_string()),
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        point,
+        r#"
+assert repr(point) == "Point(x=1, y=-2, label=\"it's\")", repr(point)
+assert str(point) == "(1, -2)"
+assert repr([point]) == "[Point(x=1, y=-2, label=\"it's\")]"
+"#
+    );
+}
+
+#[test]
+fn test_recursive_fields_repr() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let mut first = Py::new(
+        py,
+        Node {
+            value: 1,
+            next: None,
+        },
+    )
+    .unwrap();
+    let node = Py::new(
+        py,
+        Node {
+            value: 2,
+            next: Some(first.clone_ref(py).into()),
+        },
+    )
+    .unwrap();
+    py_run!(
+        py,
+        node,
+        r#"assert repr(node) == "Node(value=2, next=Node(value=1, next=None))""#
+    );
+
+    // A node referring to itself
+    let itself = first.clone_ref(py).into();
+    first.as_mut(py).next = Some(itself);
+    py_run!(
+        py,
+        first,
+        r#"assert repr(first) == "Node(value=1, next=...)""#
+    );
+    first.as_mut(py).next = None;
+}
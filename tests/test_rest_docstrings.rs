@@ -0,0 +1,39 @@
+#![cfg(feature = "rest-docstrings")]
+
+use pyo3::prelude::*;
+use pyo3::wrap_pyfunction;
+
+mod common;
+
+/// Adds `a` and `b`, see [`Add`](std::ops::Add).
+///
+/// # Example
+/// ```
+/// # use adder::add;
+/// assert_eq!(add(1, 2), 3);
+/// ```
+#[pyfunction]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[test]
+fn test_rest_docstring() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let add = wrap_pyfunction!(add)(py);
+
+    py_assert!(
+        py,
+        add,
+        r#"add.__doc__ == """Adds ``a`` and ``b``, see ``Add``.
+
+Example
+=======
+
+.. code-block:: rust
+
+    assert_eq!(add(1, 2), 3);""""#
+    );
+    py_assert!(py, add, "add.__text_signature__ == '(a, b)'");
+}
@@ -0,0 +1,182 @@
+use pyo3::prelude::*;
+use pyo3::signature::{self, ParameterKind};
+use pyo3::types::{PyAny, PyDict, PyTuple};
+use pyo3::{py_run, wrap_pyfunction};
+
+mod common;
+
+#[pyfunction(a, "/", b = "Vec::new()", args = "*", c, d = "4", kwargs = "**")]
+fn all_kinds(
+    a: i32,
+    b: Vec<i32>,
+    args: &PyTuple,
+    c: i32,
+    d: i32,
+    kwargs: Option<&PyDict>,
+) -> usize {
+    let _ = (a, c, d, kwargs);
+    b.len() + args.len()
+}
+
+#[pyclass]
+struct Scaler {
+    factor: f64,
+}
+
+#[pymethods]
+impl Scaler {
+    #[new]
+    #[args(factor = "1.0")]
+    fn new(obj: &PyRawObject, factor: f64) {
+        obj.init(Scaler { factor })
+    }
+
+    fn scale(&self, value: f64) -> f64 {
+        value * self.factor
+    }
+
+    #[classmethod]
+    #[args(percent, "*", verbose = "false")]
+    fn from_percent(_cls: &pyo3::types::PyType, percent: f64, verbose: bool) -> Scaler {
+        let _ = verbose;
+        Scaler {
+            factor: percent / 100.0,
+        }
+    }
+
+    #[staticmethod]
+    fn identity() -> Scaler {
+        Scaler { factor: 1.0 }
+    }
+
+    #[call]
+    fn __call__(&self, value: f64) -> f64 {
+        value * self.factor
+    }
+}
+
+#[test]
+fn test_lookup() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let function = wrap_pyfunction!(all_kinds)(py);
+    let function = function.cast_as::<PyAny>(py).unwrap();
+    let record = signature::lookup(function).unwrap();
+
+    assert_eq!(record.name, "all_kinds");
+    let params: Vec<_> = record
+        .params
+        .iter()
+        .map(|param| (param.name, param.kind, param.default))
+        .collect();
+    assert_eq!(
+        params,
+        [
+            ("a", ParameterKind::PositionalOnly, None),
+            ("b", ParameterKind::PositionalOrKeyword, Some("...")),
+            ("args", ParameterKind::VarPositional, None),
+            ("c", ParameterKind::KeywordOnly, None),
+            ("d", ParameterKind::KeywordOnly, Some("4")),
+            ("kwargs", ParameterKind::VarKeyword, None),
+        ]
+    );
+    assert!(signature::registry().any(|record| record.name == "from_percent"));
+
+    let not_recorded = py.eval("len", None, None).unwrap();
+    assert!(signature::lookup(not_recorded).is_none());
+}
+
+#[test]
+fn test_inspect_signature() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let function = wrap_pyfunction!(all_kinds)(py);
+    let function = function.cast_as::<PyAny>(py).unwrap();
+    let signature = signature::inspect_signature(function).unwrap().unwrap();
+    assert_eq!(
+        signature.to_string(),
+        "(a, /, b=Ellipsis, *args, c, d=4, **kwargs)"
+    );
+
+    let scaler = py.get_type::<Scaler>();
+    let scaler_dict = scaler.getattr("__dict__").unwrap();
+    for (name, expected) in &[
+        ("scale", "(self, /, value)"),
+        ("from_percent", "(cls, /, percent, *, verbose=False)"),
+    ] {
+        let method = scaler_dict.get_item(*name).unwrap();
+        let signature = signature::inspect_signature(method).unwrap().unwrap();
+        assert_eq!(signature.to_string(), *expected);
+    }
+
+    let instance = Py::new(py, Scaler { factor: 2.0 }).unwrap();
+    py_run!(
+        py,
+        scaler instance,
+        r#"
+        from inspect import signature
+        assert str(signature(scaler)) == "(factor=1.0)"
+        assert str(signature(instance)) == "(value)"
+        assert str(signature(scaler.scale)) == "(self, /, value)"
+        assert str(signature(instance.scale)) == "(value)"
+        assert str(signature(scaler.from_percent)) == "(percent, *, verbose=False)"
+        assert str(signature(scaler.identity)) == "()"
+        "#
+    );
+}
+
+#[test]
+fn test_signature_descriptor() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let scaler = py.get_type::<Scaler>();
+    let instance = Py::new(py, Scaler { factor: 2.0 }).unwrap();
+    py_run!(
+        py,
+        scaler instance,
+        r#"
+        from inspect import Signature, signature
+        assert isinstance(scaler.__signature__, Signature)
+        assert str(scaler.__signature__) == "(factor=1.0)"
+        assert str(instance.__signature__) == "(value)"
+        "#
+    );
+}
+
+#[cfg(feature = "unsound-subclass")]
+#[pyclass(subclass)]
+struct Base {}
+
+#[cfg(feature = "unsound-subclass")]
+#[pymethods]
+impl Base {
+    #[new]
+    fn new(obj: &PyRawObject, value: i32) {
+        let _ = value;
+        obj.init(Base {})
+    }
+}
+
+#[cfg(feature = "unsound-subclass")]
+#[test]
+fn test_python_subclass_signature() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let base = py.get_type::<Base>();
+    py_run!(
+        py,
+        base,
+        r#"
+        from inspect import signature
+
+        class Sub(base):
+            def __init__(self, value, label):
+                pass
+
+        # The signature of `#[new]` doesn't describe Python subclasses
+        assert str(signature(base)) == "(value)"
+        assert not hasattr(Sub, "__signature__")
+        assert str(signature(Sub)) == "(value, label)"
+        "#
+    );
+}
@@ -0,0 +1,42 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::singleton::PySingleton;
+use pyo3::AsPyPointer;
+
+#[pyclass(singleton, module = "test_singleton")]
+#[derive(Default)]
+struct Missing {}
+
+#[test]
+fn test_singleton_identity() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let missing = Missing::instance(py).unwrap();
+    assert_eq!(missing.as_ptr(), Missing::instance(py).unwrap().as_ptr());
+
+    let cls = py.get_type::<Missing>();
+    py_run!(
+        py,
+        missing cls,
+        r#"
+import copy, pickle, sys, types
+assert cls() is missing and type(missing)() is missing
+assert copy.copy(missing) is missing and copy.deepcopy([missing])[0] is missing
+assert not hasattr(missing, "__dict__")
+
+module = types.ModuleType("test_singleton")
+module.Missing = cls
+sys.modules["test_singleton"] = module
+assert pickle.loads(pickle.dumps(missing)) is missing
+
+try:
+    cls(1)
+except TypeError as e:
+    assert str(e) == "Missing() takes no arguments", str(e)
+else:
+    assert False
+"#
+    );
+}
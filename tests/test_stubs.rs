@@ -0,0 +1,170 @@
+#![cfg(feature = "stubs")]
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::stubs::module_stub;
+use pyo3::types::{IntoPyDict, PyDict, PyTuple, PyType};
+use pyo3::wrap_pymodule;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A point
+#[pyclass]
+struct Point {
+    #[pyo3(get, set)]
+    x: f64,
+    #[pyo3(get)]
+    label: Option<String>,
+}
+
+#[pymethods]
+impl Point {
+    #[classattr]
+    const DIMENSIONS: usize = 1;
+
+    #[new]
+    fn new(obj: &PyRawObject, x: f64, label: Option<String>) {
+        obj.init(Point { x, label })
+    }
+
+    fn moved(&self, dx: f64) -> Point {
+        Point {
+            x: self.x + dx,
+            label: self.label.clone(),
+        }
+    }
+
+    #[staticmethod]
+    fn origin() -> Point {
+        Point {
+            x: 0.0,
+            label: None,
+        }
+    }
+
+    #[classmethod]
+    fn named(_cls: &PyType, label: String) -> PyResult<Point> {
+        Ok(Point {
+            x: 0.0,
+            label: Some(label),
+        })
+    }
+
+    #[getter]
+    fn get_norm(&self) -> f64 {
+        self.x.abs()
+    }
+}
+
+#[pyclass]
+enum Color {
+    Red,
+    Green = 5,
+}
+
+#[pyclass]
+struct Opaque {}
+
+static UNUSED_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// A class of another module, which rendering the stub must not initialize
+#[pyclass]
+struct Unused {}
+
+#[pymethods]
+impl Unused {
+    #[classattr]
+    fn initialized() -> bool {
+        UNUSED_INITIALIZED.store(true, Ordering::SeqCst);
+        true
+    }
+}
+
+#[pyfunction(scale = "1.0", args = "*", verbose = "false", kwargs = "**")]
+fn scale_all(
+    points: Vec<&Point>,
+    scale: f64,
+    args: &PyTuple,
+    verbose: bool,
+    kwargs: Option<&PyDict>,
+) -> Vec<f64> {
+    let _ = (args, verbose, kwargs);
+    points.iter().map(|p| p.x * scale).collect()
+}
+
+#[pyfunction]
+fn histogram(py: Python, values: Vec<u32>) -> PyResult<HashMap<u32, usize>> {
+    let _ = py;
+    let mut counts = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+    Ok(counts)
+}
+
+#[pyfunction]
+fn touch(_color: Color) {}
+
+#[pymodule]
+fn geometry(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("VERSION", "1.0")?;
+    m.add_class::<Point>()?;
+    m.add_class::<Opaque>()?;
+    m.add_enum::<Color>()?;
+    m.add_wrapped(pyo3::wrap_pyfunction!(scale_all))?;
+    m.add_wrapped(pyo3::wrap_pyfunction!(histogram))?;
+    m.add_wrapped(pyo3::wrap_pyfunction!(touch))
+}
+
+#[test]
+fn test_module_stub() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let geometry = wrap_pymodule!(geometry)(py);
+    let stub = module_stub(geometry.cast_as(py).unwrap()).unwrap();
+
+    let expected = r#"# Stub of the `geometry` module generated by PyO3
+import enum
+import types
+import typing
+
+VERSION: str
+
+class Point:
+    DIMENSIONS: typing.ClassVar[int]
+    @property
+    def x(self) -> float: ...
+    @property
+    def label(self) -> typing.Optional[str]: ...
+    @property
+    def norm(self) -> float: ...
+    @x.setter
+    def x(self, value: float) -> None: ...
+    def __new__(cls, x: float, label: typing.Optional[str] = ...) -> Point: ...
+    def moved(self, dx: float) -> Point: ...
+    @staticmethod
+    def origin() -> Point: ...
+    @classmethod
+    def named(cls, label: str) -> Point: ...
+
+class Opaque:
+    ...
+
+class Color(enum.IntEnum):
+    Red = 0
+    Green = 5
+
+def scale_all(points: typing.List[Point], scale: float = ..., *args, verbose: bool = ..., **kwargs) -> typing.List[float]: ...
+
+def histogram(values: typing.List[int]) -> typing.Dict[int, int]: ...
+
+def touch(_color: Color) -> None: ...
+"#;
+    assert_eq!(stub, expected);
+    assert!(!UNUSED_INITIALIZED.load(Ordering::SeqCst));
+
+    // The stub is valid Python
+    let ctx = [("stub", &stub)].into_py_dict(py);
+    py.run("compile(stub, 'geometry.pyi', 'exec')", None, Some(ctx))
+        .unwrap();
+}
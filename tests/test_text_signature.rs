@@ -1,5 +1,5 @@
 use pyo3::prelude::*;
-use pyo3::{types::PyType, wrap_pyfunction, wrap_pymodule};
+use pyo3::{py_run, types::PyType, wrap_pyfunction, wrap_pymodule};
 
 mod common;
 
@@ -184,3 +184,161 @@ fn test_methods() {
         "typeobj.static_method.__text_signature__ == '(d)'"
     );
 }
+
+#[test]
+fn test_auto_function() {
+    #[pyfunction(
+        a,
+        b = "None",
+        args = "*",
+        c = 42,
+        d = "\"x\"",
+        e = "-1.5",
+        f = "vec![]"
+    )]
+    fn my_function(
+        py: Python,
+        a: i32,
+        b: Option<i32>,
+        args: &pyo3::types::PyTuple,
+        c: i32,
+        d: &str,
+        e: f64,
+        f: Vec<i32>,
+    ) {
+        let _ = (py, a, b, args, c, d, e, f);
+    }
+
+    #[pyfunction]
+    fn no_attrs(r#type: i32, flag: Option<bool>) {
+        let _ = (r#type, flag);
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let f = wrap_pyfunction!(my_function)(py);
+    let g = wrap_pyfunction!(no_attrs)(py);
+
+    py_assert!(
+        py,
+        f,
+        "f.__text_signature__ == '(a, b=None, *args, c=42, d=\"x\", e=-1.5, f=...)'"
+    );
+    py_assert!(py, g, "g.__text_signature__ == '(type, flag=None)'");
+    py_assert!(
+        py,
+        g,
+        "str(__import__('inspect').signature(g)) == '(type, flag=None)'"
+    );
+}
+
+#[test]
+fn test_auto_kw_only() {
+    #[pyfunction(a, "*", b = 1, kwargs = "**")]
+    fn kw_only(a: i32, b: i32, kwargs: Option<&pyo3::types::PyDict>) {
+        let _ = (a, b, kwargs);
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let f = wrap_pyfunction!(kw_only)(py);
+
+    py_assert!(py, f, "f.__text_signature__ == '(a, *, b=1, **kwargs)'");
+}
+
+#[test]
+fn test_auto_methods() {
+    /// A class
+    #[pyclass]
+    struct MyClass {}
+
+    #[pymethods]
+    impl MyClass {
+        #[new]
+        #[args(a, b = "None")]
+        fn new(obj: &PyRawObject, a: i32, b: Option<i32>) {
+            let _ = (a, b);
+            obj.init(Self {});
+        }
+        fn method(&self, a: i32) {
+            let _ = a;
+        }
+        #[name = "renamed"]
+        #[text_signature = "($self, x)"]
+        fn original(&self, x: i32) {
+            let _ = x;
+        }
+        fn pyself_method(_this: PyRef<Self>, b: i32) {
+            let _ = b;
+        }
+        #[classmethod]
+        fn class_method(_cls: &PyType, c: i32) {
+            let _ = c;
+        }
+        #[staticmethod]
+        fn static_method(d: i32) {
+            let _ = d;
+        }
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let typeobj = py.get_type::<MyClass>();
+
+    py_assert!(py, typeobj, "typeobj.__text_signature__ == '(a, b=None)'");
+    py_assert!(py, typeobj, "typeobj.__doc__ == 'A class'");
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.method.__text_signature__ == '($self, a)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.renamed.__text_signature__ == '($self, x)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.pyself_method.__text_signature__ == '($self, b)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.class_method.__text_signature__ == '($cls, c)'"
+    );
+    py_assert!(
+        py,
+        typeobj,
+        "typeobj.static_method.__text_signature__ == '(d)'"
+    );
+    py_run!(
+        py,
+        typeobj,
+        r#"
+import inspect
+assert str(inspect.signature(typeobj)) == "(a, b=None)"
+assert str(inspect.signature(typeobj(1).method)) == "(a)"
+assert str(inspect.signature(typeobj.class_method)) == "(c)"
+"#
+    );
+}
+
+#[test]
+fn test_doc_signature_not_overridden() {
+    /// add(a, b, /)
+    /// --
+    ///
+    /// Adds two numbers.
+    #[pyfunction]
+    fn add(a: u64, b: u64) -> u64 {
+        a + b
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let f = wrap_pyfunction!(add)(py);
+
+    py_assert!(py, f, "f.__text_signature__ == '(a, b, /)'");
+    py_assert!(py, f, "f.__doc__ == 'Adds two numbers.'");
+}
@@ -0,0 +1,72 @@
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::py_run;
+use pyo3::tracemalloc::{update_heap_size, PyHeapSize};
+use pyo3::wrap_pyfunction;
+
+#[pyclass]
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl PyHeapSize for Buffer {
+    fn heap_size(&self) -> usize {
+        self.data.capacity()
+    }
+}
+
+#[pymethods]
+impl Buffer {
+    fn grow(&mut self, py: Python, additional: usize) {
+        self.data.reserve_exact(additional);
+        update_heap_size(py, self);
+    }
+}
+
+#[pyfunction]
+fn make_buffer(py: Python, size: usize) -> PyResult<Py<Buffer>> {
+    Py::new(
+        py,
+        Buffer {
+            data: vec![0; size],
+        },
+    )
+}
+
+#[test]
+fn test_heap_size_is_traced() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let make_buffer = wrap_pyfunction!(make_buffer)(py);
+    py_run!(
+        py,
+        make_buffer,
+        r#"
+import tracemalloc
+domain = [tracemalloc.DomainFilter(True, 0x70796f33)]
+
+tracemalloc.start()
+try:
+    buffer = make_buffer(1000)
+    [stat] = tracemalloc.take_snapshot().filter_traces(domain).statistics("lineno")
+    # Attributed to the line creating the instance
+    assert stat.size == 1000 and stat.traceback[0].lineno == 6, stat
+    buffer.grow(4000)
+    [stat] = tracemalloc.take_snapshot().filter_traces(domain).statistics("lineno")
+    assert stat.size >= 5000
+    del buffer
+    assert tracemalloc.take_snapshot().filter_traces(domain).statistics("lineno") == []
+finally:
+    tracemalloc.stop()
+"#
+    );
+}
+
+#[test]
+fn test_not_traced_without_tracemalloc() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let buffer = Py::new(py, Buffer { data: vec![0; 10] }).unwrap();
+    py_run!(py, buffer, "buffer.grow(10)");
+}
@@ -36,6 +36,94 @@ fn mut_ref_arg() {
     assert_eq!(inst2.as_ref(py).n, 100);
 }
 
+#[pyclass]
+struct Account {
+    #[pyo3(get)]
+    balance: i32,
+}
+
+#[pymethods]
+impl Account {
+    fn absorb(&mut self, other: &mut Account) {
+        self.balance += other.balance;
+        other.balance = 0;
+    }
+
+    fn copy_from(&mut self, other: &Account) {
+        self.balance = other.balance;
+    }
+
+    fn same_balance(&self, other: &Account) -> bool {
+        self.balance == other.balance
+    }
+
+    #[staticmethod]
+    fn transfer(from: &mut Account, to: Option<&mut Account>, amount: i32) {
+        from.balance -= amount;
+        if let Some(to) = to {
+            to.balance += amount;
+        }
+    }
+}
+
+#[pyfunction]
+fn swap_balances(a: &mut Account, b: &mut Account) {
+    std::mem::swap(&mut a.balance, &mut b.balance);
+}
+
+#[test]
+fn mut_ref_arg_aliasing() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let a = Py::new(py, Account { balance: 10 }).unwrap();
+    let b = Py::new(py, Account { balance: 5 }).unwrap();
+    let account = py.get_type::<Account>();
+    let swap_balances = wrap_pyfunction!(swap_balances)(py);
+
+    py_run!(
+        py,
+        a b account swap_balances,
+        r#"
+        def assert_aliasing(message, f, *args):
+            try:
+                f(*args)
+            except RuntimeError as e:
+                assert str(e) == message, str(e)
+            else:
+                assert False
+
+        swap_balances(a, b)
+        assert (a.balance, b.balance) == (5, 10)
+        assert_aliasing(
+            "argument 'b' is the same object as argument 'a', which is borrowed mutably",
+            swap_balances, a, a,
+        )
+
+        a.absorb(b)
+        assert (a.balance, b.balance) == (15, 0)
+        assert_aliasing(
+            "argument 'other' is the same object as self, which is borrowed mutably",
+            a.absorb, a,
+        )
+        assert_aliasing(
+            "argument 'other' is the same object as self, which is borrowed mutably",
+            a.copy_from, a,
+        )
+        # Shared borrows of the same object are fine
+        assert a.same_balance(a)
+
+        account.transfer(a, b, 5)
+        account.transfer(a, None, 5)
+        assert (a.balance, b.balance) == (5, 5)
+        assert_aliasing(
+            "argument 'to' is the same object as argument 'from', which is borrowed mutably",
+            account.transfer, a, a, 5,
+        )
+        assert a.balance == 5
+        "#
+    );
+}
+
 #[pyclass]
 struct PyUsize {
     #[pyo3(get)]
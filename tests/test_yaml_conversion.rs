@@ -0,0 +1,61 @@
+// The conversions are tested here rather than in the library, as serde_yaml implements
+// `PartialEq<Value>` for the integer types, which breaks type inference in the library's tests.
+#![cfg(feature = "serde_yaml")]
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::ToPyObject;
+use serde_yaml::Value;
+
+const CONFIG: &str = "
+title: example
+enabled: true
+ratio: 0.5
+missing: ~
+big: 18446744073709551615
+servers:
+  - host: a
+    ports: [8000, 8001]
+? [1, 2]
+: pair
+";
+
+#[test]
+fn test_round_trip() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let value: Value = serde_yaml::from_str(CONFIG).unwrap();
+
+    let obj = value.to_object(py);
+    let locals = PyDict::new(py);
+    locals.set_item("config", &obj).unwrap();
+    py.run(
+        r#"
+assert config["title"] == "example"
+assert config["missing"] is None
+assert config["big"] == 2 ** 64 - 1
+assert config["servers"][0]["ports"] == [8000, 8001]
+assert config[(1, 2)] == "pair"
+del config[(1, 2)]
+config["servers"][0]["ports"].append(8002)
+"#,
+        None,
+        Some(locals),
+    )
+    .unwrap();
+
+    let back: Value = obj.extract(py).unwrap();
+    assert_eq!(back["servers"][0]["ports"].as_sequence().unwrap().len(), 3);
+    assert_eq!(back["title"], value["title"]);
+    assert_eq!(back["missing"], Value::Null);
+    assert_eq!(back["big"], value["big"]);
+    assert_eq!(back["ratio"], value["ratio"]);
+}
+
+#[test]
+fn test_extract_errors() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = py.eval("object()", None, None).unwrap();
+    assert!(obj.extract::<Value>().is_err());
+}
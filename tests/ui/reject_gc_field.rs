@@ -0,0 +1,11 @@
+#![feature(specialization)]
+use pyo3::prelude::*;
+use std::sync::Arc;
+
+#[pyclass(gc)]
+struct Shared {
+    object: PyObject,
+    shared: Arc<PyObject>,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+#![feature(specialization)]
+use pyo3::prelude::*;
+use std::cell::RefCell;
+
+#[pyclass(gc)]
+struct Node {
+    parent: Option<Py<Node>>,
+    children: Vec<Py<Node>>,
+    next: RefCell<Py<Node>>,
+}
+
+fn main() {}
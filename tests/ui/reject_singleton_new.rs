@@ -0,0 +1,16 @@
+#![feature(specialization)]
+use pyo3::prelude::*;
+
+#[pyclass(singleton)]
+#[derive(Default)]
+struct Unset {}
+
+#[pymethods]
+impl Unset {
+    #[new]
+    fn new(obj: &PyRawObject) {
+        obj.init(Unset {});
+    }
+}
+
+fn main() {}